@@ -0,0 +1,70 @@
+//! Recording cost of iterated dispatches
+//!
+//! Compares a batch of iterations submitted one builder each with the same
+//! iterations chained into one command buffer through
+//! `CommandBuilder::then`, where the repeated pipeline and descriptor set
+//! binds are skipped. Runs against the in-process mock ICD, so the numbers
+//! reflect the CPU side of recording and submission only.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use kronos_compute::api::{Buffer, ComputeContext, Pipeline};
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+
+/// Iterations per batch
+const ITERATIONS: &[u32] = &[4, 16, 64];
+
+fn separate(ctx: &ComputeContext, pipeline: &Pipeline, buffers: &[Buffer; 3], iterations: u32) {
+    for iteration in 0..iterations {
+        ctx.dispatch(pipeline)
+            .bind_buffer(2, &buffers[2])
+            .bind_buffer(1, &buffers[1])
+            .bind_buffer(0, &buffers[0])
+            .push_constants(&(iteration as f32))
+            .workgroups(4, 1, 1)
+            .execute()
+            .expect("execute");
+    }
+}
+
+fn chained(ctx: &ComputeContext, pipeline: &Pipeline, buffers: &[Buffer; 3], iterations: u32) {
+    let mut builder = ctx
+        .dispatch(pipeline)
+        .bind_buffer(2, &buffers[2])
+        .bind_buffer(1, &buffers[1])
+        .bind_buffer(0, &buffers[0]);
+    for iteration in 0..iterations {
+        if iteration > 0 {
+            builder = builder.then(pipeline);
+        }
+        builder = builder.push_constants(&(iteration as f32)).workgroups(4, 1, 1);
+    }
+    builder.execute().expect("execute");
+}
+
+fn benchmark_iterations(c: &mut Criterion) {
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let ctx = ComputeContext::new().expect("context on mock ICD");
+    let shader = ctx
+        .create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))
+        .expect("shader");
+    let pipeline = ctx.create_pipeline(&shader).expect("pipeline");
+    let buffers = [
+        ctx.create_buffer(&[1.0f32; 256]).expect("buffer"),
+        ctx.create_buffer(&[1.0f32; 256]).expect("buffer"),
+        ctx.create_buffer(&[0.0f32; 256]).expect("buffer"),
+    ];
+
+    let mut group = c.benchmark_group("iterated_dispatch");
+    for &iterations in ITERATIONS {
+        group.bench_with_input(BenchmarkId::new("separate", iterations), &iterations, |b, &n| {
+            b.iter(|| separate(&ctx, &pipeline, &buffers, n));
+        });
+        group.bench_with_input(BenchmarkId::new("chained", iterations), &iterations, |b, &n| {
+            b.iter(|| chained(&ctx, &pipeline, &buffers, n));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_iterations);
+criterion_main!(benches);
@@ -779,7 +779,7 @@ unsafe fn create_optimized_context() -> Option<OptimizedContext> {
         driverVersion: 0,
         vendorID: 0,
         deviceID: 0,
-        deviceType: VkPhysicalDeviceType::Other,
+        deviceType: VkPhysicalDeviceType::Other as i32,
         deviceName: [0; VK_MAX_PHYSICAL_DEVICE_NAME_SIZE],
         pipelineCacheUUID: [0; VK_UUID_SIZE],
         limits: unsafe { std::mem::zeroed() },
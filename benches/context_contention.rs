@@ -0,0 +1,78 @@
+//! Contention on a shared ComputeContext
+//!
+//! Several threads hammer the same context with accessor calls and buffer
+//! creation. Runs against the in-process mock ICD, so the numbers reflect
+//! the safe API's locking rather than any driver.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use kronos_compute::api::ComputeContext;
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use std::sync::Barrier;
+use std::time::{Duration, Instant};
+
+/// Thread counts to compare
+const THREAD_COUNTS: &[usize] = &[1, 2, 4, 8];
+
+/// Run `op` `iters` times on each of `threads` threads and return the wall time
+fn contend<F>(threads: usize, iters: u64, op: F) -> Duration
+where
+    F: Fn() + Sync,
+{
+    let barrier = Barrier::new(threads + 1);
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                barrier.wait();
+                for _ in 0..iters {
+                    op();
+                }
+                barrier.wait();
+            });
+        }
+        barrier.wait();
+        let start = Instant::now();
+        barrier.wait();
+        start.elapsed()
+    })
+}
+
+fn benchmark_accessors(c: &mut Criterion) {
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let ctx = ComputeContext::new().expect("context on mock ICD");
+    let mut group = c.benchmark_group("context_accessors");
+
+    for &threads in THREAD_COUNTS {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                contend(threads, iters, || {
+                    black_box(ctx.device());
+                    black_box(ctx.queue());
+                    black_box(ctx.device_properties());
+                })
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_buffer_creation(c: &mut Criterion) {
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let ctx = ComputeContext::new().expect("context on mock ICD");
+    let mut group = c.benchmark_group("context_buffer_creation");
+
+    for &threads in THREAD_COUNTS {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                contend(threads, iters, || {
+                    black_box(ctx.create_buffer_uninit(4096).expect("create buffer"));
+                })
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_accessors, benchmark_buffer_creation);
+criterion_main!(benches);
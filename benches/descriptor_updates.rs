@@ -0,0 +1,333 @@
+//! CPU cost of the descriptor update paths
+//!
+//! Every dispatch of a batch binds its own slice of three storage buffers,
+//! and the four ways of getting those bindings to the driver are compared:
+//!
+//! - `update_per_dispatch`: a `vkUpdateDescriptorSets` with three writes
+//!   before each dispatch
+//! - `persistent`: sets written once up front, only bound per dispatch
+//! - `update_template`: a `vkUpdateDescriptorSetWithTemplate` from packed
+//!   buffer infos before each dispatch
+//! - `dynamic_offsets`: one set written once, bound per dispatch with the
+//!   slice passed as dynamic offsets
+//!
+//! The `descriptor_paths` group records through Kronos's exported entry
+//! points; `safe_api_bindings` runs the equivalent paths of the safe API
+//! end to end. Runs against the in-process mock ICD, so the numbers reflect
+//! the CPU side of recording and submission only.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use kronos_compute::api::{Buffer, BufferBinding, ComputeContext, Pipeline, PipelineConfig};
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use kronos_compute::*;
+use std::ptr;
+
+/// Dispatches per batch
+const BATCH_SIZES: &[u32] = &[1, 16, 64];
+
+/// Bytes of each buffer a dispatch binds
+const SLICE: VkDeviceSize = 1024;
+
+/// Descriptor objects for the raw paths, sized for the largest batch
+struct RawDescriptors {
+    device: VkDevice,
+    pool: VkDescriptorPool,
+    /// One set per dispatch, in the pipeline's own layout
+    sets: Vec<VkDescriptorSet>,
+    template: VkDescriptorUpdateTemplate,
+    dynamic_set_layout: VkDescriptorSetLayout,
+    dynamic_layout: VkPipelineLayout,
+    dynamic_set: VkDescriptorSet,
+    command_pool: VkCommandPool,
+    command_buffer: VkCommandBuffer,
+}
+
+/// The three buffer infos of dispatch `index`
+fn slice_infos(buffers: &[Buffer; 3], index: u32) -> [VkDescriptorBufferInfo; 3] {
+    [0, 1, 2].map(|i| VkDescriptorBufferInfo {
+        buffer: buffers[i].raw(),
+        offset: index as VkDeviceSize * SLICE,
+        range: SLICE,
+    })
+}
+
+fn storage_bindings(descriptor_type: VkDescriptorType) -> [VkDescriptorSetLayoutBinding; 3] {
+    [0, 1, 2].map(|binding| VkDescriptorSetLayoutBinding {
+        binding,
+        descriptorType: descriptor_type,
+        descriptorCount: 1,
+        stageFlags: VkShaderStageFlags::COMPUTE,
+        pImmutableSamplers: ptr::null(),
+    })
+}
+
+/// Write the slice of dispatch `index` into `set`
+unsafe fn write_set(device: VkDevice, set: VkDescriptorSet, infos: &[VkDescriptorBufferInfo; 3], descriptor_type: VkDescriptorType) {
+    let writes = [0, 1, 2].map(|i| VkWriteDescriptorSet {
+        dstSet: set,
+        dstBinding: i as u32,
+        descriptorCount: 1,
+        descriptorType: descriptor_type,
+        pBufferInfo: &infos[i],
+        ..Default::default()
+    });
+    vkUpdateDescriptorSets(device, writes.len() as u32, writes.as_ptr(), 0, ptr::null());
+}
+
+impl RawDescriptors {
+    unsafe fn new(ctx: &ComputeContext, pipeline: &Pipeline, buffers: &[Buffer; 3]) -> Self {
+        let device = ctx.device();
+        let max_sets = *BATCH_SIZES.iter().max().unwrap();
+
+        let pool_sizes = [
+            VkDescriptorPoolSize { type_: VkDescriptorType::StorageBuffer, descriptorCount: 3 * max_sets },
+            VkDescriptorPoolSize { type_: VkDescriptorType::StorageBufferDynamic, descriptorCount: 3 },
+        ];
+        let pool_info = VkDescriptorPoolCreateInfo {
+            maxSets: max_sets + 1,
+            poolSizeCount: pool_sizes.len() as u32,
+            pPoolSizes: pool_sizes.as_ptr(),
+            ..Default::default()
+        };
+        let mut pool = VkDescriptorPool::NULL;
+        assert_eq!(vkCreateDescriptorPool(device, &pool_info, ptr::null(), &mut pool), VkResult::Success);
+
+        let layouts = vec![pipeline.descriptor_set_layout(); max_sets as usize];
+        let mut sets = vec![VkDescriptorSet::NULL; max_sets as usize];
+        let alloc_info = VkDescriptorSetAllocateInfo {
+            descriptorPool: pool,
+            descriptorSetCount: max_sets,
+            pSetLayouts: layouts.as_ptr(),
+            ..Default::default()
+        };
+        assert_eq!(vkAllocateDescriptorSets(device, &alloc_info, sets.as_mut_ptr()), VkResult::Success);
+
+        let entries = [0usize, 1, 2].map(|i| VkDescriptorUpdateTemplateEntry {
+            dstBinding: i as u32,
+            dstArrayElement: 0,
+            descriptorCount: 1,
+            descriptorType: VkDescriptorType::StorageBuffer,
+            offset: i * std::mem::size_of::<VkDescriptorBufferInfo>(),
+            stride: std::mem::size_of::<VkDescriptorBufferInfo>(),
+        });
+        let template_info = VkDescriptorUpdateTemplateCreateInfo {
+            descriptorUpdateEntryCount: entries.len() as u32,
+            pDescriptorUpdateEntries: entries.as_ptr(),
+            descriptorSetLayout: pipeline.descriptor_set_layout(),
+            pipelineLayout: pipeline.layout(),
+            ..Default::default()
+        };
+        let mut template = VkDescriptorUpdateTemplate::NULL;
+        assert_eq!(vkCreateDescriptorUpdateTemplate(device, &template_info, ptr::null(), &mut template), VkResult::Success);
+
+        let dynamic_bindings = storage_bindings(VkDescriptorType::StorageBufferDynamic);
+        let set_layout_info = VkDescriptorSetLayoutCreateInfo {
+            bindingCount: dynamic_bindings.len() as u32,
+            pBindings: dynamic_bindings.as_ptr(),
+            ..Default::default()
+        };
+        let mut dynamic_set_layout = VkDescriptorSetLayout::NULL;
+        assert_eq!(vkCreateDescriptorSetLayout(device, &set_layout_info, ptr::null(), &mut dynamic_set_layout), VkResult::Success);
+        let layout_info = VkPipelineLayoutCreateInfo {
+            setLayoutCount: 1,
+            pSetLayouts: &dynamic_set_layout,
+            ..Default::default()
+        };
+        let mut dynamic_layout = VkPipelineLayout::NULL;
+        assert_eq!(vkCreatePipelineLayout(device, &layout_info, ptr::null(), &mut dynamic_layout), VkResult::Success);
+        let mut dynamic_set = VkDescriptorSet::NULL;
+        let alloc_info = VkDescriptorSetAllocateInfo {
+            descriptorPool: pool,
+            descriptorSetCount: 1,
+            pSetLayouts: &dynamic_set_layout,
+            ..Default::default()
+        };
+        assert_eq!(vkAllocateDescriptorSets(device, &alloc_info, &mut dynamic_set), VkResult::Success);
+        write_set(device, dynamic_set, &slice_infos(buffers, 0), VkDescriptorType::StorageBufferDynamic);
+
+        let command_pool_info = VkCommandPoolCreateInfo {
+            flags: VkCommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+            ..Default::default()
+        };
+        let mut command_pool = VkCommandPool::NULL;
+        assert_eq!(vkCreateCommandPool(device, &command_pool_info, ptr::null(), &mut command_pool), VkResult::Success);
+        let command_buffer_info = VkCommandBufferAllocateInfo {
+            commandPool: command_pool,
+            commandBufferCount: 1,
+            ..Default::default()
+        };
+        let mut command_buffer = VkCommandBuffer::NULL;
+        assert_eq!(vkAllocateCommandBuffers(device, &command_buffer_info, &mut command_buffer), VkResult::Success);
+
+        Self {
+            device,
+            pool,
+            sets,
+            template,
+            dynamic_set_layout,
+            dynamic_layout,
+            dynamic_set,
+            command_pool,
+            command_buffer,
+        }
+    }
+
+    /// Record `dispatches` dispatches, each preceded by `bind`
+    unsafe fn record(&self, pipeline: &Pipeline, dispatches: u32, mut bind: impl FnMut(VkCommandBuffer, u32)) {
+        let begin_info = VkCommandBufferBeginInfo {
+            flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+            ..Default::default()
+        };
+        vkBeginCommandBuffer(self.command_buffer, &begin_info);
+        vkCmdBindPipeline(self.command_buffer, VkPipelineBindPoint::Compute, pipeline.raw());
+        for index in 0..dispatches {
+            bind(self.command_buffer, index);
+            vkCmdDispatch(self.command_buffer, 4, 1, 1);
+        }
+        vkEndCommandBuffer(self.command_buffer);
+    }
+
+    unsafe fn update_per_dispatch(&self, pipeline: &Pipeline, buffers: &[Buffer; 3], dispatches: u32) {
+        self.record(pipeline, dispatches, |cb, index| {
+            let set = self.sets[index as usize];
+            write_set(self.device, set, &slice_infos(buffers, index), VkDescriptorType::StorageBuffer);
+            vkCmdBindDescriptorSets(cb, VkPipelineBindPoint::Compute, pipeline.layout(), 0, 1, &set, 0, ptr::null());
+        });
+    }
+
+    unsafe fn persistent(&self, pipeline: &Pipeline, dispatches: u32) {
+        self.record(pipeline, dispatches, |cb, index| {
+            let set = self.sets[index as usize];
+            vkCmdBindDescriptorSets(cb, VkPipelineBindPoint::Compute, pipeline.layout(), 0, 1, &set, 0, ptr::null());
+        });
+    }
+
+    unsafe fn update_template(&self, pipeline: &Pipeline, buffers: &[Buffer; 3], dispatches: u32) {
+        self.record(pipeline, dispatches, |cb, index| {
+            let set = self.sets[index as usize];
+            let infos = slice_infos(buffers, index);
+            vkUpdateDescriptorSetWithTemplate(self.device, set, self.template, infos.as_ptr().cast());
+            vkCmdBindDescriptorSets(cb, VkPipelineBindPoint::Compute, pipeline.layout(), 0, 1, &set, 0, ptr::null());
+        });
+    }
+
+    unsafe fn dynamic_offsets(&self, pipeline: &Pipeline, dispatches: u32) {
+        self.record(pipeline, dispatches, |cb, index| {
+            let offset = index * SLICE as u32;
+            let offsets = [offset; 3];
+            vkCmdBindDescriptorSets(
+                cb,
+                VkPipelineBindPoint::Compute,
+                self.dynamic_layout,
+                0,
+                1,
+                &self.dynamic_set,
+                offsets.len() as u32,
+                offsets.as_ptr(),
+            );
+        });
+    }
+}
+
+impl Drop for RawDescriptors {
+    fn drop(&mut self) {
+        unsafe {
+            vkDestroyCommandPool(self.device, self.command_pool, ptr::null());
+            vkDestroyDescriptorUpdateTemplate(self.device, self.template, ptr::null());
+            vkDestroyDescriptorPool(self.device, self.pool, ptr::null());
+            vkDestroyPipelineLayout(self.device, self.dynamic_layout, ptr::null());
+            vkDestroyDescriptorSetLayout(self.device, self.dynamic_set_layout, ptr::null());
+        }
+    }
+}
+
+fn benchmark_descriptor_paths(c: &mut Criterion) {
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let ctx = ComputeContext::new().expect("context on mock ICD");
+    let shader = ctx
+        .create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))
+        .expect("shader");
+    let config = PipelineConfig {
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        ..Default::default()
+    };
+    let pipeline = ctx.create_pipeline_with_config(&shader, config).expect("pipeline");
+    let floats = (SLICE as usize / 4) * *BATCH_SIZES.iter().max().unwrap() as usize;
+    let buffers = [
+        ctx.create_buffer(&vec![1.0f32; floats]).expect("buffer"),
+        ctx.create_buffer(&vec![1.0f32; floats]).expect("buffer"),
+        ctx.create_buffer(&vec![0.0f32; floats]).expect("buffer"),
+    ];
+    let raw = unsafe { RawDescriptors::new(&ctx, &pipeline, &buffers) };
+    for index in 0..raw.sets.len() as u32 {
+        unsafe { write_set(raw.device, raw.sets[index as usize], &slice_infos(&buffers, index), VkDescriptorType::StorageBuffer) };
+    }
+
+    let mut group = c.benchmark_group("descriptor_paths");
+    for &dispatches in BATCH_SIZES {
+        group.bench_with_input(BenchmarkId::new("update_per_dispatch", dispatches), &dispatches, |b, &n| {
+            b.iter(|| unsafe { raw.update_per_dispatch(&pipeline, &buffers, n) });
+        });
+        group.bench_with_input(BenchmarkId::new("persistent", dispatches), &dispatches, |b, &n| {
+            b.iter(|| unsafe { raw.persistent(&pipeline, n) });
+        });
+        group.bench_with_input(BenchmarkId::new("update_template", dispatches), &dispatches, |b, &n| {
+            b.iter(|| unsafe { raw.update_template(&pipeline, &buffers, n) });
+        });
+        group.bench_with_input(BenchmarkId::new("dynamic_offsets", dispatches), &dispatches, |b, &n| {
+            b.iter(|| unsafe { raw.dynamic_offsets(&pipeline, n) });
+        });
+    }
+    group.finish();
+    drop(raw);
+
+    // Bindings out of order miss the persistent path and are written into a
+    // fresh set on every dispatch; in order, the cached set is reused
+    let prebuilt = pipeline
+        .bind_all(&[&buffers[0], &buffers[1], &buffers[2]])
+        .expect("bind_all");
+    let mut group = c.benchmark_group("safe_api_bindings");
+    for &dispatches in BATCH_SIZES {
+        group.bench_with_input(BenchmarkId::new("update_per_dispatch", dispatches), &dispatches, |b, &n| {
+            b.iter(|| {
+                for _ in 0..n {
+                    ctx.dispatch(&pipeline)
+                        .bind_buffer(2, &buffers[2])
+                        .bind_buffer(1, &buffers[1])
+                        .bind_buffer(0, &buffers[0])
+                        .workgroups(4, 1, 1)
+                        .execute()
+                        .expect("execute");
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("persistent", dispatches), &dispatches, |b, &n| {
+            b.iter(|| {
+                for _ in 0..n {
+                    ctx.dispatch(&pipeline)
+                        .bind_buffer(0, &buffers[0])
+                        .bind_buffer(1, &buffers[1])
+                        .bind_buffer(2, &buffers[2])
+                        .workgroups(4, 1, 1)
+                        .execute()
+                        .expect("execute");
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("prebuilt_set", dispatches), &dispatches, |b, &n| {
+            b.iter(|| {
+                for _ in 0..n {
+                    ctx.dispatch(&pipeline)
+                        .descriptor_set(&prebuilt)
+                        .workgroups(4, 1, 1)
+                        .execute()
+                        .expect("execute");
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_descriptor_paths);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+//! Copy strategies for writes into mapped memory
+//!
+//! Times `memcpy` against streaming stores for writes of increasing size,
+//! and a run of small adjacent writes against the same bytes gathered into
+//! a shadow and streamed at once. On a device with host-mappable
+//! device-local memory the writes land in a direct buffer, which drivers
+//! map write-combined; elsewhere they land in host memory and only show
+//! what streaming costs on cached memory. The crossover of the first group
+//! backs `host_copy::STREAMING_THRESHOLD`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use kronos_compute::api::{ComputeContext, CopyStrategy};
+
+/// Largest write timed
+const TARGET_SIZE: usize = 1 << 20;
+
+/// Write sizes timed with each strategy
+const SIZES: &[usize] = &[64, 128, 256, 512, 4 << 10, 64 << 10, TARGET_SIZE];
+
+/// Size of each of the small adjacent writes
+const REGION: usize = 16;
+
+fn copy_strategies(c: &mut Criterion, name: &str, target: *mut u8) {
+    let src: Vec<u8> = (0..TARGET_SIZE).map(|i| i as u8).collect();
+
+    let mut group = c.benchmark_group(format!("host_copy/{}", name));
+    for &size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        for strategy in [CopyStrategy::Memcpy, CopyStrategy::Streaming] {
+            let id = BenchmarkId::new(format!("{:?}", strategy), size);
+            group.bench_with_input(id, &size, |b, &size| {
+                b.iter(|| unsafe { strategy.copy(black_box(&src[..size]), target) });
+            });
+        }
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("host_copy_scattered/{}", name));
+    for &size in &SIZES[2..5] {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("direct", size), &size, |b, &size| {
+            b.iter(|| {
+                // Every other region first, so no two writes in a row are adjacent
+                for start in (0..size).step_by(2 * REGION).chain((REGION..size).step_by(2 * REGION)) {
+                    unsafe { CopyStrategy::Memcpy.copy(black_box(&src[start..start + REGION]), target.add(start)) };
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("shadow", size), &size, |b, &size| {
+            let mut shadow = vec![0u8; size];
+            b.iter(|| {
+                for start in (0..size).step_by(2 * REGION).chain((REGION..size).step_by(2 * REGION)) {
+                    shadow[start..start + REGION].copy_from_slice(black_box(&src[start..start + REGION]));
+                }
+                unsafe { CopyStrategy::Streaming.copy(&shadow, target) };
+            });
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_host_copy(c: &mut Criterion) {
+    let ctx = ComputeContext::new().ok();
+    let mut buffer = ctx.as_ref().and_then(|ctx| ctx.create_buffer_direct(TARGET_SIZE).ok()).filter(|buffer| buffer.is_direct());
+    match buffer.as_mut() {
+        Some(buffer) => {
+            let write_combined = buffer.is_write_combined();
+            let mut mapping = buffer.try_map_direct().expect("map direct buffer").expect("direct buffer is host-visible");
+            let name = if write_combined { "write_combined" } else { "direct_cached" };
+            copy_strategies(c, name, mapping.as_mut_ptr());
+        }
+        None => {
+            println!("No host-mappable device-local memory; timing host memory instead");
+            let mut host = vec![0u8; TARGET_SIZE];
+            copy_strategies(c, "host", host.as_mut_ptr());
+        }
+    }
+}
+
+criterion_group!(benches, benchmark_host_copy);
+criterion_main!(benches);
@@ -0,0 +1,107 @@
+//! Fragmentation of the pool allocator under mixed allocation sizes
+//!
+//! Replays the same churn of allocations and frees against each slab and
+//! fit configuration on the in-process mock ICD. Besides the time per churn,
+//! the slab memory reserved and the fragmentation of the free space at the
+//! end of a churn are printed once per configuration; these back the
+//! `PoolConfig` defaults.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use kronos_compute::api::{ComputeContext, FitStrategy, MemoryConfig, PoolConfig, SlabGrowth};
+use kronos_compute::core::*;
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use kronos_compute::implementation::pool_allocator::{
+    allocate_from_pool, free_allocation, get_pool_stats, PoolStats, PoolType,
+};
+use kronos_compute::sys::*;
+
+/// Allocation sizes drawn by the churn, small requests being the most common
+const SIZES: &[VkDeviceSize] = &[
+    4 << 10, 4 << 10, 4 << 10, 16 << 10, 16 << 10, 48 << 10, 96 << 10, 200 << 10, 1 << 20,
+];
+
+/// Allocations and frees per churn
+const STEPS: usize = 4096;
+
+/// Live allocations the churn keeps at most
+const MAX_LIVE: usize = 256;
+
+fn configurations() -> Vec<(&'static str, PoolConfig)> {
+    let fixed = PoolConfig { slab_size: 256 << 10, growth: SlabGrowth::Fixed, fit: FitStrategy::FirstFit };
+    let exponential = SlabGrowth::Exponential { max_slab_size: 32 << 20 };
+    vec![
+        ("first_fit_fixed", fixed),
+        ("best_fit_fixed", PoolConfig { fit: FitStrategy::BestFit, ..fixed }),
+        ("first_fit_exponential", PoolConfig { growth: exponential, ..fixed }),
+        ("best_fit_exponential", PoolConfig::default()),
+        ("buddy_exponential", PoolConfig { growth: exponential, fit: FitStrategy::Buddy, ..fixed }),
+    ]
+}
+
+/// Run one deterministic churn and return the pool statistics before the
+/// remaining allocations are freed
+fn churn(device: VkDevice) -> PoolStats {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut live = Vec::with_capacity(MAX_LIVE);
+    for _ in 0..STEPS {
+        let roll = next();
+        if live.len() < MAX_LIVE && (live.is_empty() || roll % 3 != 0) {
+            let requirements = VkMemoryRequirements {
+                size: SIZES[(roll >> 8) as usize % SIZES.len()],
+                alignment: 256,
+                memoryTypeBits: 1,
+            };
+            let id = unsafe { allocate_from_pool(device, &requirements, PoolType::DeviceLocal) }.expect("allocate");
+            live.push(id);
+        } else {
+            let id = live.swap_remove((roll >> 8) as usize % live.len());
+            unsafe { free_allocation(device, id) }.expect("free");
+        }
+    }
+
+    let stats = get_pool_stats(device, PoolType::DeviceLocal).expect("pool stats");
+    for id in live {
+        unsafe { free_allocation(device, id) }.expect("free");
+    }
+    stats
+}
+
+fn benchmark_fragmentation(c: &mut Criterion) {
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let mut group = c.benchmark_group("pool_fragmentation");
+    group.sample_size(20);
+
+    for (name, config) in configurations() {
+        let ctx = ComputeContext::builder()
+            .memory_config(MemoryConfig::uniform(config))
+            .build()
+            .expect("context on mock ICD");
+
+        let stats = churn(ctx.device());
+        println!(
+            "{}: {} slabs, {} KiB reserved, {} KiB in use, fragmentation {:.2}",
+            name,
+            stats.total_slabs,
+            stats.total_allocated >> 10,
+            stats.bytes_in_use >> 10,
+            stats.fragmentation(),
+        );
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &ctx, |b, ctx| {
+            b.iter(|| black_box(churn(ctx.device())));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_fragmentation);
+criterion_main!(benches);
@@ -26,7 +26,48 @@ fn main() {
         }
     }
     
+    // The vk* entry points are exported unmangled with
+    // `export-vulkan-symbols`, unless `no-export` is also enabled
+    println!("cargo:rustc-check-cfg=cfg(export_vulkan_symbols)");
+    if env::var_os("CARGO_FEATURE_EXPORT_VULKAN_SYMBOLS").is_some() && env::var_os("CARGO_FEATURE_NO_EXPORT").is_none() {
+        println!("cargo:rustc-cfg=export_vulkan_symbols");
+    }
+    
     // Re-run build if the Kronos headers change
     println!("cargo:rerun-if-changed=../Kronos/core/vulkan_compute_optimized.h");
     println!("cargo:rerun-if-changed=../Kronos/core/vulkan_compute_complete.h");
+
+    #[cfg(feature = "capi")]
+    generate_capi_header();
+}
+
+/// Generate `kronos_capi.h` for the flat C API into `$OUT_DIR/include`,
+/// and to `$KRONOS_CAPI_HEADER` if set
+#[cfg(feature = "capi")]
+fn generate_capi_header() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let include_dir = PathBuf::from(env::var("OUT_DIR").unwrap()).join("include");
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-env-changed=KRONOS_CAPI_HEADER");
+
+    let mut config = cbindgen::Config::default();
+    config.language = cbindgen::Language::C;
+    config.header = Some("/* Kronos Compute - flat C API over the safe layer */".into());
+    config.include_guard = Some("KRONOS_CAPI_H".into());
+    config.autogen_warning = Some("/* Warning: this file is autogenerated by cbindgen. Don't modify manually. */".into());
+    config.sys_includes = vec!["stdint.h".into(), "stddef.h".into()];
+    config.no_includes = true;
+    config.usize_is_size_t = true;
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src").join("capi.rs"))
+        .generate()
+        .expect("Failed to generate kronos_capi.h");
+
+    std::fs::create_dir_all(&include_dir).unwrap();
+    bindings.write_to_file(include_dir.join("kronos_capi.h"));
+    if let Some(path) = env::var_os("KRONOS_CAPI_HEADER") {
+        bindings.write_to_file(path);
+    }
+    println!("cargo:include={}", include_dir.display());
 }
@@ -430,8 +430,8 @@ fn main() {
                 
                 vkCmdPipelineBarrier(
                     cmd_buffer,
-                    VkPipelineStageFlags::from_bits(0x00001000).unwrap(), // TRANSFER
-                    VkPipelineStageFlags::from_bits(0x00000020).unwrap(), // COMPUTE_SHADER
+                    VkPipelineStageFlags::TRANSFER,
+                    VkPipelineStageFlags::COMPUTE_SHADER,
                     VkDependencyFlags::empty(),
                     0, ptr::null(),
                     1, &barrier,
@@ -135,7 +135,7 @@ fn main() {
         let buffer_info = VkBufferCreateInfo {
             sType: VkStructureType::BufferCreateInfo,
             pNext: ptr::null(),
-            flags: VkBufferCreateFlags::from_bits(0).unwrap(),
+            flags: VkBufferCreateFlags::empty(),
             size: buffer_size,
             usage: VkBufferUsageFlags::STORAGE_BUFFER,
             sharingMode: VkSharingMode::Exclusive,
@@ -277,7 +277,7 @@ fn main() {
         let stage_info = VkPipelineShaderStageCreateInfo {
             sType: VkStructureType::PipelineShaderStageCreateInfo,
             pNext: ptr::null(),
-            flags: VkPipelineShaderStageCreateFlags::from_bits(0).unwrap(),
+            flags: VkPipelineShaderStageCreateFlags::empty(),
             stage: VkShaderStageFlagBits::Compute,
             module: shader_module,
             pName: entry_point.as_ptr(),
@@ -287,7 +287,7 @@ fn main() {
         let pipeline_info = VkComputePipelineCreateInfo {
             sType: VkStructureType::ComputePipelineCreateInfo,
             pNext: ptr::null(),
-            flags: VkPipelineCreateFlags::from_bits(0).unwrap(),
+            flags: VkPipelineCreateFlags::empty(),
             stage: stage_info,
             layout: pipeline_layout,
             basePipelineHandle: VkPipeline::NULL,
@@ -307,7 +307,7 @@ fn main() {
         let pool_info = VkDescriptorPoolCreateInfo {
             sType: VkStructureType::DescriptorPoolCreateInfo,
             pNext: ptr::null(),
-            flags: VkDescriptorPoolCreateFlags::from_bits(0).unwrap(),
+            flags: VkDescriptorPoolCreateFlags::empty(),
             maxSets: 1,
             poolSizeCount: 1,
             pPoolSizes: &pool_size,
@@ -393,7 +393,7 @@ fn main() {
         let pool_create_info = VkCommandPoolCreateInfo {
             sType: VkStructureType::CommandPoolCreateInfo,
             pNext: ptr::null(),
-            flags: VkCommandPoolCreateFlags::from_bits(0).unwrap(),
+            flags: VkCommandPoolCreateFlags::empty(),
             queueFamilyIndex: compute_queue_family,
         };
         
@@ -75,7 +75,7 @@ fn main() {
                 .trim_end_matches('\0');
             
             println!("Device {}: {}", idx, device_name);
-            println!("  Type: {:?}", props.deviceType);
+            println!("  Type: {:?}", props.device_type());
             println!("  Vendor ID: 0x{:04X} ({})", props.vendorID, vendor_name(props.vendorID));
             println!("  Device ID: 0x{:04X}", props.deviceID);
             println!("  API Version: {}.{}.{}", 
@@ -134,8 +134,9 @@ fn main() {
             }
             
             // Check features relevant to compute
-            let mut features: VkPhysicalDeviceFeatures = std::mem::zeroed();
-            kronos_compute::vkGetPhysicalDeviceFeatures(*device, &mut features);
+            let mut full = [VK_FALSE; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT];
+            kronos_compute::vkGetPhysicalDeviceFeatures(*device, full.as_mut_ptr() as *mut VkPhysicalDeviceFeatures);
+            let features = VkPhysicalDeviceFeatures::from_full(&full);
             
             println!("  Compute Features:");
             println!("    Robust buffer access: {}", features.robustBufferAccess != 0);
@@ -0,0 +1,298 @@
+//! Compute benchmark with machine-readable results
+//!
+//! Runs SAXPY and the kernel library ([`ops`]) over a range of sizes and
+//! prints one JSON document to stdout, so results from different machines,
+//! drivers or native Vulkan/CUDA implementations can be compared with a
+//! script. Progress goes to stderr.
+//!
+//! ```text
+//! cargo run --release --example kronos-bench -- --sizes 65536,1048576 --batch 8 > results.json
+//! ```
+//!
+//! Options:
+//!
+//! ```text
+//! --device <vendor>      prefer a device vendor (AMD, NVIDIA, Apple)
+//! --icd <index|path>     bind an ICD by index (see `icd_select list`) or library path
+//! --batch <n>            SAXPY dispatches recorded per submission (default 1)
+//! --sizes <n,n,...>      element counts (default 65536,1048576,16777216)
+//! --iterations <n>       timed runs per op and size, after one warmup (default 10)
+//! --ops <op,op,...>      run a subset of saxpy, fill_random, sort_u32, exclusive_scan, histogram, compact
+//! ```
+//!
+//! Times are wall-clock, from submission to completion. Bandwidth counts
+//! the bytes an op has to read and write once, whatever its kernels
+//! actually move; GFLOPs are only reported for SAXPY. `cpu_submit_us` is
+//! the time spent in [`CommandBuilder::submit`] per submission; the library
+//! ops submit and wait internally, so theirs is `null`.
+//!
+//! [`ops`]: kronos_compute::api::ops
+//! [`CommandBuilder::submit`]: kronos_compute::api::CommandBuilder::submit
+
+use kronos_compute::api::ops::{self, Distribution};
+use kronos_compute::api::{Buffer, BufferBinding, ComputeContext, Pipeline, PipelineConfig};
+use serde::Serialize;
+use std::env;
+use std::ffi::CStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const OPS: &[&str] = &["saxpy", "fill_random", "sort_u32", "exclusive_scan", "histogram", "compact"];
+const DEFAULT_SIZES: &[u32] = &[1 << 16, 1 << 20, 1 << 24];
+const SAXPY_LOCAL_SIZE: u32 = 256;
+const HISTOGRAM_BINS: u32 = 256;
+
+type BoxError = Box<dyn std::error::Error>;
+
+struct Options {
+    device: Option<String>,
+    icd: Option<String>,
+    batch: u32,
+    sizes: Vec<u32>,
+    iterations: u32,
+    ops: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    kronos_version: &'static str,
+    device: String,
+    driver: Option<String>,
+    icd: Option<String>,
+    batch: u32,
+    iterations: u32,
+    results: Vec<OpResult>,
+}
+
+#[derive(Serialize)]
+struct OpResult {
+    op: String,
+    elements: u32,
+    /// Average wall time of one iteration
+    time_us: f64,
+    gb_per_s: f64,
+    gflops: Option<f64>,
+    cpu_submit_us: Option<f64>,
+}
+
+/// Push constants of `shaders/saxpy.comp`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SaxpyParams {
+    alpha: f32,
+    count: u32,
+}
+
+fn print_usage() {
+    eprintln!("Usage: kronos-bench [--device <vendor>] [--icd <index|path>] [--batch <n>]");
+    eprintln!("                    [--sizes <n,n,...>] [--iterations <n>] [--ops <op,op,...>]");
+    eprintln!("Ops: {}", OPS.join(", "));
+}
+
+fn parse_options() -> Result<Options, BoxError> {
+    let mut options = Options {
+        device: None,
+        icd: None,
+        batch: 1,
+        sizes: DEFAULT_SIZES.to_vec(),
+        iterations: 10,
+        ops: OPS.iter().map(|op| op.to_string()).collect(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            print_usage();
+            std::process::exit(0);
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--device" => options.device = Some(value),
+            "--icd" => options.icd = Some(value),
+            "--batch" => options.batch = value.parse::<u32>()?.max(1),
+            "--sizes" => options.sizes = value.split(',').map(str::parse).collect::<Result<_, _>>()?,
+            "--iterations" => options.iterations = value.parse::<u32>()?.max(1),
+            "--ops" => {
+                options.ops = value.split(',').map(str::to_string).collect();
+                if let Some(unknown) = options.ops.iter().find(|op| !OPS.contains(&op.as_str())) {
+                    return Err(format!("unknown op '{}'", unknown).into());
+                }
+            }
+            _ => return Err(format!("unknown option '{}'", flag).into()),
+        }
+    }
+    Ok(options)
+}
+
+fn create_context(options: &Options) -> Result<ComputeContext, BoxError> {
+    let mut builder = ComputeContext::builder().app_name("kronos-bench");
+    if let Some(vendor) = &options.device {
+        builder = builder.prefer_vendor(vendor.as_str());
+    }
+    if let Some(icd) = &options.icd {
+        builder = match icd.parse::<usize>() {
+            Ok(index) => builder.prefer_icd_index(index),
+            Err(_) => builder.prefer_icd_path(icd.as_str()),
+        };
+    }
+    Ok(builder.build()?)
+}
+
+fn saxpy_pipeline(ctx: &ComputeContext) -> Result<Pipeline, BoxError> {
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))?;
+    let pipeline = ctx.create_pipeline_with_config(&shader, PipelineConfig {
+        local_size: (SAXPY_LOCAL_SIZE, 1, 1),
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        push_constant_size: std::mem::size_of::<SaxpyParams>() as u32,
+        ..Default::default()
+    })?;
+    Ok(pipeline)
+}
+
+/// A buffer of `count` random `u32`
+fn random_buffer(ctx: &ComputeContext, count: u32, seed: u64) -> Result<Buffer, BoxError> {
+    let buffer = ctx.create_buffer_uninit(count as usize * std::mem::size_of::<u32>())?;
+    ops::fill_random(ctx, &buffer, Distribution::Bits, seed)?;
+    Ok(buffer)
+}
+
+/// Time `batch` chained SAXPY dispatches per submission; returns the
+/// average iteration and submit times
+fn bench_saxpy(ctx: &ComputeContext, pipeline: &Pipeline, count: u32, options: &Options) -> Result<(Duration, Duration), BoxError> {
+    let bytes = count as usize * std::mem::size_of::<f32>();
+    let x = ctx.create_buffer_uninit(bytes)?;
+    let y = ctx.create_buffer_uninit(bytes)?;
+    let out = ctx.create_buffer_uninit(bytes)?;
+    ops::fill_random(ctx, &x, Distribution::Uniform { low: -1.0, high: 1.0 }, 1)?;
+    ops::fill_random(ctx, &y, Distribution::Uniform { low: -1.0, high: 1.0 }, 2)?;
+    let params = SaxpyParams { alpha: 2.0, count };
+    let workgroups = count / SAXPY_LOCAL_SIZE + (count % SAXPY_LOCAL_SIZE != 0) as u32;
+
+    let (mut total, mut submit) = (Duration::ZERO, Duration::ZERO);
+    for iteration in 0..=options.iterations {
+        let mut builder = ctx.dispatch(pipeline).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out);
+        for _ in 1..options.batch {
+            builder = builder.push_constants(&params).workgroups(workgroups, 1, 1).then(pipeline);
+        }
+        let (done, completed) = mpsc::channel();
+        let builder = builder
+            .push_constants(&params)
+            .workgroups(workgroups, 1, 1)
+            .on_complete(move || {
+                let _ = done.send(());
+            });
+        let start = Instant::now();
+        builder.submit()?;
+        let submitted = start.elapsed();
+        completed.recv()?;
+        // The first iteration warms up pipelines and pools
+        if iteration > 0 {
+            total += start.elapsed();
+            submit += submitted;
+        }
+    }
+    Ok((total / options.iterations, submit / options.iterations))
+}
+
+/// Average time of `op` over the timed iterations, after one warmup
+fn bench_op<F>(options: &Options, mut op: F) -> Result<Duration, BoxError>
+where
+    F: FnMut() -> Result<(), BoxError>,
+{
+    op()?;
+    let start = Instant::now();
+    for _ in 0..options.iterations {
+        op()?;
+    }
+    Ok(start.elapsed() / options.iterations)
+}
+
+fn run_op(ctx: &ComputeContext, saxpy: &Pipeline, op: &str, count: u32, options: &Options) -> Result<OpResult, BoxError> {
+    let words = count as f64 * std::mem::size_of::<u32>() as f64;
+    let (time, bytes, flops, submit) = match op {
+        "saxpy" => {
+            let (time, submit) = bench_saxpy(ctx, saxpy, count, options)?;
+            let batch = options.batch as f64;
+            (time, 3.0 * words * batch, Some(2.0 * count as f64 * batch), Some(submit / options.batch))
+        }
+        "fill_random" => {
+            let buffer = ctx.create_buffer_uninit(count as usize * std::mem::size_of::<u32>())?;
+            let mut seed = 0;
+            let time = bench_op(options, || {
+                seed += 1;
+                Ok(ops::fill_random(ctx, &buffer, Distribution::Bits, seed)?)
+            })?;
+            (time, words, None, None)
+        }
+        "sort_u32" => {
+            let keys = random_buffer(ctx, count, 3)?;
+            // Re-sorting sorted keys still runs every radix pass
+            let time = bench_op(options, || Ok(ops::sort_u32(ctx, &keys)?))?;
+            (time, 2.0 * words, None, None)
+        }
+        "exclusive_scan" => {
+            let data = ctx.create_buffer_uninit(count as usize * std::mem::size_of::<u32>())?;
+            let time = bench_op(options, || Ok(ops::exclusive_scan(ctx, &data).map(drop)?))?;
+            (time, 2.0 * words, None, None)
+        }
+        "histogram" => {
+            let data = random_buffer(ctx, count, 4)?;
+            let time = bench_op(options, || Ok(ops::histogram(ctx, &data, HISTOGRAM_BINS).map(drop)?))?;
+            (time, words, None, None)
+        }
+        "compact" => {
+            let data = random_buffer(ctx, count, 5)?;
+            let mask = random_buffer(ctx, count, 6)?;
+            let time = bench_op(options, || Ok(ops::compact(ctx, &data, &mask).map(drop)?))?;
+            // Data and mask in, about half of the data out
+            (time, 2.5 * words, None, None)
+        }
+        _ => unreachable!("options only hold known ops"),
+    };
+    let seconds = time.as_secs_f64().max(f64::MIN_POSITIVE);
+    Ok(OpResult {
+        op: op.to_string(),
+        elements: count,
+        time_us: time.as_secs_f64() * 1e6,
+        gb_per_s: bytes / seconds / 1e9,
+        gflops: flops.map(|flops| flops / seconds / 1e9),
+        cpu_submit_us: submit.map(|submit: Duration| submit.as_secs_f64() * 1e6),
+    })
+}
+
+fn main() -> Result<(), BoxError> {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("kronos-bench: {}", error);
+            print_usage();
+            std::process::exit(2);
+        }
+    };
+    let ctx = create_context(&options)?;
+    let properties = ctx.device_properties();
+    // SAFETY: deviceName is NUL-terminated within its fixed-size array
+    let device = unsafe { CStr::from_ptr(properties.deviceName.as_ptr()) }.to_string_lossy().into_owned();
+    eprintln!("kronos-bench on {}", device);
+
+    let saxpy = saxpy_pipeline(&ctx)?;
+    let mut results = Vec::new();
+    for &count in &options.sizes {
+        for op in &options.ops {
+            let result = run_op(&ctx, &saxpy, op, count, &options)?;
+            eprintln!("{:>16} {:>10} elements {:>10.1} us {:>8.2} GB/s", op, count, result.time_us, result.gb_per_s);
+            results.push(result);
+        }
+    }
+
+    let report = Report {
+        kronos_version: env!("CARGO_PKG_VERSION"),
+        device,
+        driver: ctx.driver_info().map(|driver| driver.to_string()),
+        icd: ctx.icd_info().map(|icd| icd.library_path.display().to_string()),
+        batch: options.batch,
+        iterations: options.iterations,
+        results,
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -72,7 +72,7 @@ fn main() {
         let device_name = std::ffi::CStr::from_ptr(properties.deviceName.as_ptr())
             .to_string_lossy();
         println!("✓ Device: {}", device_name);
-        println!("  Type: {:?}", properties.deviceType);
+        println!("  Type: {:?}", properties.device_type());
         println!("  Vendor ID: 0x{:04X}", properties.vendorID);
         
         // 4. Create logical device
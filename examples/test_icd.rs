@@ -68,7 +68,7 @@ fn main() {
                 let name = std::ffi::CStr::from_ptr(props.deviceName.as_ptr())
                     .to_string_lossy();
                 println!("   Device {}: {}", i, name);
-                println!("      Type: {:?}", props.deviceType);
+                println!("      Type: {:?}", props.device_type());
                 println!("      API Version: {}.{}.{}", 
                     (props.apiVersion >> 22) & 0x3FF,
                     (props.apiVersion >> 12) & 0x3FF,
@@ -34,7 +34,7 @@ fn main() {
                 .map(|&c| c as u8)
                 .collect();
             let device_name = std::str::from_utf8(&device_name_u8).unwrap_or("Unknown Device");
-            println!("  Device: {} ({:?})", device_name, props.deviceType);
+            println!("  Device: {} ({:?})", device_name, props.device_type());
         }
         Err(e) => {
             println!("✗ Failed to create ComputeContext: {:?}", e);
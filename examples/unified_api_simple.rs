@@ -30,6 +30,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             BufferBinding { binding: 2, ..Default::default() }, // Output C
         ],
         push_constant_size: std::mem::size_of::<f32>() as u32, // For scalar parameter
+        ..Default::default()
     };
     
     let pipeline = ctx.create_pipeline_with_config(&shader, pipeline_config)?;
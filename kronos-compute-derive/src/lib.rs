@@ -0,0 +1,70 @@
+//! Derive macros for kronos-compute
+//!
+//! `#[derive(Std430)]` describes the memory layout of a `#[repr(C)]` struct
+//! so `kronos_compute::api::layout` can compare it with the std430 layout a
+//! kernel declares for the same data.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Implement `kronos_compute::api::Std430` for a `#[repr(C)]` struct with
+/// named fields
+#[proc_macro_derive(Std430)]
+pub fn derive_std430(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "Std430 needs a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "Std430 can only be derived for structs")),
+    };
+
+    // Without repr(C) the compiler may reorder fields, so offsets mean nothing
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            }
+            Ok(())
+        })?;
+    }
+    if !repr_c {
+        return Err(syn::Error::new_spanned(&input.ident, "Std430 needs #[repr(C)]"));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let host_fields = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        quote! {
+            ::kronos_compute::api::HostField {
+                name: stringify!(#ident),
+                offset: unsafe {
+                    (::core::ptr::addr_of!((*base).#ident) as *const u8).offset_from(base as *const u8) as usize
+                },
+                size: ::core::mem::size_of::<#ty>(),
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::kronos_compute::api::Std430 for #name #ty_generics #where_clause {
+            fn host_fields() -> ::std::vec::Vec<::kronos_compute::api::HostField> {
+                let uninit = ::core::mem::MaybeUninit::<Self>::uninit();
+                let base = uninit.as_ptr();
+                ::std::vec![#(#host_fields),*]
+            }
+        }
+    })
+}
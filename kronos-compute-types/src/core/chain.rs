@@ -0,0 +1,266 @@
+//! Type-checked pNext chains
+//!
+//! Vulkan extends a create or query structure by linking extension
+//! structures through `pNext`. [`Chain`] builds those links from typed
+//! references: an extension can only be pushed onto a base it is declared
+//! to extend, every structure's `sType` is checked against its type, and
+//! the borrow keeps each linked structure alive and in place while the
+//! chain is used.
+//!
+//! ```ignore
+//! let mut timeline = VkSemaphoreTypeCreateInfo { semaphoreType: VkSemaphoreType::Timeline, ..Default::default() };
+//! let mut create_info = VkSemaphoreCreateInfo::default();
+//! let chain = Chain::new(&mut create_info).push(&mut timeline);
+//! vkCreateSemaphore(device, chain.as_ptr(), ptr::null(), &mut semaphore);
+//! ```
+
+use ::core::marker::PhantomData;
+use ::core::ptr;
+use crate::core::compute::*;
+use crate::core::enums::VkStructureType;
+use crate::core::structs::*;
+use crate::core::timeline::*;
+
+/// Common header of every structure that has `sType` and `pNext`
+#[repr(C)]
+#[derive(Debug)]
+pub struct VkBaseOutStructure {
+    pub sType: VkStructureType,
+    pub pNext: *mut VkBaseOutStructure,
+}
+
+/// A structure that starts with `sType` and `pNext`
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]` with `sType: VkStructureType` as its first
+/// field and a `pNext` pointer as its second, and `STRUCTURE_TYPE` must be
+/// the `sType` Vulkan defines for it.
+pub unsafe trait VkStruct {
+    const STRUCTURE_TYPE: VkStructureType;
+}
+
+/// An extension structure Vulkan allows in the pNext chain of `Base`
+///
+/// # Safety
+///
+/// The Vulkan specification must list `Self` among the valid pNext
+/// structures of `Base`.
+pub unsafe trait Extends<Base: VkStruct>: VkStruct {}
+
+/// A base structure and the extension structures linked into its pNext chain
+///
+/// The links are written into the structures themselves, so they remain
+/// after the chain is dropped; the structures must then stay in place until
+/// Vulkan has read them.
+pub struct Chain<'a, B: VkStruct> {
+    base: &'a mut B,
+    tail: *mut VkBaseOutStructure,
+    _links: PhantomData<&'a mut VkBaseOutStructure>,
+}
+
+impl<'a, B: VkStruct> Chain<'a, B> {
+    /// Start a chain at `base`, keeping any links it already has
+    ///
+    /// # Panics
+    ///
+    /// If the `sType` of `base` is not `B::STRUCTURE_TYPE`.
+    pub fn new(base: &'a mut B) -> Self {
+        let header = base as *mut B as *mut VkBaseOutStructure;
+        unsafe {
+            check_structure_type::<B>(header);
+        }
+        let mut chain = Self { base, tail: header, _links: PhantomData };
+        chain.tail = unsafe { chain.last_link(header) };
+        chain
+    }
+
+    /// Link `ext`, and any structures already chained to it, at the end
+    ///
+    /// # Panics
+    ///
+    /// If the `sType` of `ext` is not `E::STRUCTURE_TYPE`, or a structure of
+    /// the same type is already in the chain.
+    pub fn push<E: Extends<B>>(mut self, ext: &'a mut E) -> Self {
+        let header = ext as *mut E as *mut VkBaseOutStructure;
+        unsafe {
+            check_structure_type::<E>(header);
+            assert!(
+                self.find_header(E::STRUCTURE_TYPE).is_null(),
+                "{:?} is already in the pNext chain of {:?}",
+                E::STRUCTURE_TYPE,
+                B::STRUCTURE_TYPE
+            );
+            (*self.tail).pNext = header;
+            self.tail = self.last_link(header);
+        }
+        self
+    }
+
+    /// The linked structure of type `E`, if any
+    pub fn find<E: Extends<B>>(&self) -> Option<&E> {
+        unsafe { (self.find_header(E::STRUCTURE_TYPE) as *const E).as_ref() }
+    }
+
+    /// The base structure
+    pub fn base(&self) -> &B {
+        self.base
+    }
+
+    /// Pointer to the base structure, for passing to Vulkan
+    pub fn as_ptr(&self) -> *const B {
+        &*self.base
+    }
+
+    /// Mutable pointer to the base structure, for queries Vulkan fills in
+    pub fn as_mut_ptr(&mut self) -> *mut B {
+        &mut *self.base
+    }
+
+    /// The first linked structure with sType `s_type`, or null
+    unsafe fn find_header(&self, s_type: VkStructureType) -> *mut VkBaseOutStructure {
+        let mut link = (*(&*self.base as *const B as *const VkBaseOutStructure)).pNext;
+        while !link.is_null() && (*link).sType != s_type {
+            link = (*link).pNext;
+        }
+        link
+    }
+
+    /// The last structure of the chain starting at `header`
+    unsafe fn last_link(&self, mut header: *mut VkBaseOutStructure) -> *mut VkBaseOutStructure {
+        while !(*header).pNext.is_null() {
+            header = (*header).pNext;
+        }
+        header
+    }
+}
+
+unsafe fn check_structure_type<T: VkStruct>(header: *const VkBaseOutStructure) {
+    let s_type = ptr::addr_of!((*header).sType).read();
+    assert_eq!(s_type, T::STRUCTURE_TYPE, "structure has the wrong sType for its type");
+}
+
+macro_rules! vk_struct {
+    ($($ty:ty => $s_type:ident),* $(,)?) => {
+        $(unsafe impl VkStruct for $ty {
+            const STRUCTURE_TYPE: VkStructureType = VkStructureType::$s_type;
+        })*
+    };
+}
+
+macro_rules! extends {
+    ($($ext:ty: $($base:ty),+;)*) => {
+        $($(unsafe impl Extends<$base> for $ext {})+)*
+    };
+}
+
+vk_struct! {
+    VkInstanceCreateInfo => InstanceCreateInfo,
+    VkDeviceCreateInfo => DeviceCreateInfo,
+    VkSubmitInfo => SubmitInfo,
+    VkMemoryAllocateInfo => MemoryAllocateInfo,
+    VkSemaphoreCreateInfo => SemaphoreCreateInfo,
+    VkQueryPoolCreateInfo => QueryPoolCreateInfo,
+    VkQueueFamilyProperties2 => QueueFamilyProperties2,
+    VkPhysicalDeviceProperties2 => PhysicalDeviceProperties2,
+    VkPhysicalDeviceDriverProperties => PhysicalDeviceDriverProperties,
+    VkPhysicalDeviceIDProperties => PhysicalDeviceIdProperties,
+    VkPhysicalDevicePCIBusInfoPropertiesEXT => PhysicalDevicePciBusInfoPropertiesEXT,
+    VkMemoryAllocateFlagsInfo => MemoryAllocateFlagsInfo,
+    VkSemaphoreTypeCreateInfo => SemaphoreTypeCreateInfo,
+    VkTimelineSemaphoreSubmitInfo => TimelineSemaphoreSubmitInfo,
+    VkPhysicalDevicePerformanceQueryFeaturesKHR => PhysicalDevicePerformanceQueryFeaturesKHR,
+    VkPhysicalDeviceTimelineSemaphoreFeatures => PhysicalDeviceTimelineSemaphoreFeatures,
+    VkQueryPoolPerformanceCreateInfoKHR => QueryPoolPerformanceCreateInfoKHR,
+    VkPerformanceQuerySubmitInfoKHR => PerformanceQuerySubmitInfoKHR,
+    VkQueueFamilyGlobalPriorityPropertiesKHR => QueueFamilyGlobalPriorityPropertiesKHR,
+    VkQueueFamilyVideoPropertiesKHR => QueueFamilyVideoPropertiesKHR,
+    VkBufferCreateInfo => BufferCreateInfo,
+    VkExternalMemoryBufferCreateInfo => ExternalMemoryBufferCreateInfo,
+    VkImportMemoryFdInfoKHR => ImportMemoryFdInfoKHR,
+    VkMemoryDedicatedAllocateInfo => MemoryDedicatedAllocateInfo,
+    VkExportMemoryAllocateInfo => ExportMemoryAllocateInfo,
+    VkExportSemaphoreCreateInfo => ExportSemaphoreCreateInfo,
+    VkComputePipelineCreateInfo => ComputePipelineCreateInfo,
+    VkPipelineCreationFeedbackCreateInfo => PipelineCreationFeedbackCreateInfo,
+    VkDebugUtilsMessengerCreateInfoEXT => DebugUtilsMessengerCreateInfoEXT,
+    VkValidationFeaturesEXT => ValidationFeaturesEXT,
+    VkPhysicalDeviceMemoryPriorityFeaturesEXT => PhysicalDeviceMemoryPriorityFeaturesEXT,
+    VkPhysicalDevicePageableDeviceLocalMemoryFeaturesEXT => PhysicalDevicePageableDeviceLocalMemoryFeaturesEXT,
+    VkMemoryPriorityAllocateInfoEXT => MemoryPriorityAllocateInfoEXT,
+    VkPhysicalDeviceConditionalRenderingFeaturesEXT => PhysicalDeviceConditionalRenderingFeaturesEXT,
+    VkConditionalRenderingBeginInfoEXT => ConditionalRenderingBeginInfoEXT,
+}
+
+extends! {
+    VkMemoryAllocateFlagsInfo: VkMemoryAllocateInfo;
+    VkSemaphoreTypeCreateInfo: VkSemaphoreCreateInfo;
+    VkTimelineSemaphoreSubmitInfo: VkSubmitInfo;
+    VkPhysicalDevicePerformanceQueryFeaturesKHR: VkDeviceCreateInfo;
+    VkPhysicalDeviceTimelineSemaphoreFeatures: VkDeviceCreateInfo;
+    VkQueryPoolPerformanceCreateInfoKHR: VkQueryPoolCreateInfo;
+    VkPerformanceQuerySubmitInfoKHR: VkSubmitInfo;
+    VkQueueFamilyGlobalPriorityPropertiesKHR: VkQueueFamilyProperties2;
+    VkQueueFamilyVideoPropertiesKHR: VkQueueFamilyProperties2;
+    VkPhysicalDeviceDriverProperties: VkPhysicalDeviceProperties2;
+    VkPhysicalDeviceIDProperties: VkPhysicalDeviceProperties2;
+    VkPhysicalDevicePCIBusInfoPropertiesEXT: VkPhysicalDeviceProperties2;
+    VkExternalMemoryBufferCreateInfo: VkBufferCreateInfo;
+    VkImportMemoryFdInfoKHR: VkMemoryAllocateInfo;
+    VkMemoryDedicatedAllocateInfo: VkMemoryAllocateInfo;
+    VkExportMemoryAllocateInfo: VkMemoryAllocateInfo;
+    VkExportSemaphoreCreateInfo: VkSemaphoreCreateInfo;
+    VkPipelineCreationFeedbackCreateInfo: VkComputePipelineCreateInfo;
+    VkDebugUtilsMessengerCreateInfoEXT: VkInstanceCreateInfo;
+    VkValidationFeaturesEXT: VkInstanceCreateInfo;
+    VkPhysicalDeviceMemoryPriorityFeaturesEXT: VkDeviceCreateInfo;
+    VkPhysicalDevicePageableDeviceLocalMemoryFeaturesEXT: VkDeviceCreateInfo;
+    VkMemoryPriorityAllocateInfoEXT: VkMemoryAllocateInfo;
+    VkPhysicalDeviceConditionalRenderingFeaturesEXT: VkDeviceCreateInfo;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::enums::VkSemaphoreType;
+
+    #[test]
+    fn test_chain_links_in_order() {
+        let mut timeline = VkTimelineSemaphoreSubmitInfo::default();
+        let mut perf = VkPerformanceQuerySubmitInfoKHR { counterPassIndex: 3, ..Default::default() };
+        let perf_ptr: *const VkPerformanceQuerySubmitInfoKHR = &perf;
+        let mut submit = VkSubmitInfo::default();
+        let chain = Chain::new(&mut submit).push(&mut timeline).push(&mut perf);
+
+        assert_eq!(chain.find::<VkPerformanceQuerySubmitInfoKHR>().unwrap().counterPassIndex, 3);
+        let first = chain.base().pNext as *const VkTimelineSemaphoreSubmitInfo;
+        assert!(ptr::eq(first, chain.find::<VkTimelineSemaphoreSubmitInfo>().unwrap()));
+        assert!(ptr::eq(unsafe { (*first).pNext } as *const VkPerformanceQuerySubmitInfoKHR, perf_ptr));
+        assert!(perf.pNext.is_null());
+    }
+
+    #[test]
+    fn test_chain_keeps_existing_links() {
+        let mut timeline = VkSemaphoreTypeCreateInfo { semaphoreType: VkSemaphoreType::Timeline, ..Default::default() };
+        let mut create_info = VkSemaphoreCreateInfo { pNext: &mut timeline as *mut _ as *const _, ..Default::default() };
+        let chain = Chain::new(&mut create_info);
+        assert_eq!(chain.find::<VkSemaphoreTypeCreateInfo>().unwrap().semaphoreType, VkSemaphoreType::Timeline);
+    }
+
+    #[test]
+    #[should_panic(expected = "already in the pNext chain")]
+    fn test_chain_rejects_duplicate_types() {
+        let mut first = VkTimelineSemaphoreSubmitInfo::default();
+        let mut second = VkTimelineSemaphoreSubmitInfo::default();
+        let mut submit = VkSubmitInfo::default();
+        let _ = Chain::new(&mut submit).push(&mut first).push(&mut second);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong sType")]
+    fn test_chain_checks_structure_type() {
+        let mut timeline = VkTimelineSemaphoreSubmitInfo { sType: VkStructureType::SubmitInfo, ..Default::default() };
+        let mut submit = VkSubmitInfo::default();
+        let _ = Chain::new(&mut submit).push(&mut timeline);
+    }
+}
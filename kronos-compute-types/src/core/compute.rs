@@ -0,0 +1,1392 @@
+//! Compute-specific structures for Kronos
+
+use ::core::ffi::{c_char, c_int, c_void};
+use ::core::ptr;
+use crate::sys::*;
+use crate::core::enums::*;
+use crate::core::flags::*;
+use crate::core::structs::{VkExtent3D, VkQueueFamilyProperties, VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE};
+
+/// Shader module creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkShaderModuleCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkFlags,
+    pub codeSize: usize,
+    pub pCode: *const u32,
+}
+
+impl Default for VkShaderModuleCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ShaderModuleCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            codeSize: 0,
+            pCode: ptr::null(),
+        }
+    }
+}
+
+/// Specialization map entry
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkSpecializationMapEntry {
+    pub constantID: u32,
+    pub offset: u32,
+    pub size: usize,
+}
+
+/// Specialization info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkSpecializationInfo {
+    pub mapEntryCount: u32,
+    pub pMapEntries: *const VkSpecializationMapEntry,
+    pub dataSize: usize,
+    pub pData: *const c_void,
+}
+
+/// Pipeline shader stage creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPipelineShaderStageCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkPipelineShaderStageCreateFlags,
+    pub stage: VkShaderStageFlagBits,
+    pub module: VkShaderModule,
+    pub pName: *const i8,
+    pub pSpecializationInfo: *const VkSpecializationInfo,
+}
+
+impl Default for VkPipelineShaderStageCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PipelineShaderStageCreateInfo,
+            pNext: ptr::null(),
+            flags: VkPipelineShaderStageCreateFlags::empty(),
+            stage: VkShaderStageFlagBits::Compute,
+            module: VkShaderModule::NULL,
+            pName: b"main\0".as_ptr() as *const i8,
+            pSpecializationInfo: ptr::null(),
+        }
+    }
+}
+
+/// Compute pipeline creation info (optimized)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkComputePipelineCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkPipelineCreateFlags,
+    pub stage: VkPipelineShaderStageCreateInfo,
+    pub layout: VkPipelineLayout,
+    pub basePipelineHandle: VkPipeline,
+    pub basePipelineIndex: i32,
+}
+
+impl Default for VkComputePipelineCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ComputePipelineCreateInfo,
+            pNext: ptr::null(),
+            flags: VkPipelineCreateFlags::empty(),
+            stage: VkPipelineShaderStageCreateInfo::default(),
+            layout: VkPipelineLayout::NULL,
+            basePipelineHandle: VkPipeline::NULL,
+            basePipelineIndex: -1,
+        }
+    }
+}
+
+/// Push constant range
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPushConstantRange {
+    pub stageFlags: VkShaderStageFlags,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// Pipeline layout creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPipelineLayoutCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkPipelineLayoutCreateFlags,
+    pub setLayoutCount: u32,
+    pub pSetLayouts: *const VkDescriptorSetLayout,
+    pub pushConstantRangeCount: u32,
+    pub pPushConstantRanges: *const VkPushConstantRange,
+}
+
+impl Default for VkPipelineLayoutCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PipelineLayoutCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            setLayoutCount: 0,
+            pSetLayouts: ptr::null(),
+            pushConstantRangeCount: 0,
+            pPushConstantRanges: ptr::null(),
+        }
+    }
+}
+
+/// Descriptor set layout binding
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorSetLayoutBinding {
+    pub binding: u32,
+    pub descriptorType: VkDescriptorType,
+    pub descriptorCount: u32,
+    pub stageFlags: VkShaderStageFlags,
+    pub pImmutableSamplers: *const VkSampler,
+}
+
+/// Descriptor set layout creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorSetLayoutCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkDescriptorSetLayoutCreateFlags,
+    pub bindingCount: u32,
+    pub pBindings: *const VkDescriptorSetLayoutBinding,
+}
+
+impl Default for VkDescriptorSetLayoutCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DescriptorSetLayoutCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            bindingCount: 0,
+            pBindings: ptr::null(),
+        }
+    }
+}
+
+/// Descriptor pool size
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorPoolSize {
+    pub type_: VkDescriptorType,
+    pub descriptorCount: u32,
+}
+
+/// Descriptor pool creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorPoolCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkDescriptorPoolCreateFlags,
+    pub maxSets: u32,
+    pub poolSizeCount: u32,
+    pub pPoolSizes: *const VkDescriptorPoolSize,
+}
+
+impl Default for VkDescriptorPoolCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DescriptorPoolCreateInfo,
+            pNext: ptr::null(),
+            flags: VkDescriptorPoolCreateFlags::empty(),
+            maxSets: 0,
+            poolSizeCount: 0,
+            pPoolSizes: ptr::null(),
+        }
+    }
+}
+
+/// Descriptor set allocate info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorSetAllocateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub descriptorPool: VkDescriptorPool,
+    pub descriptorSetCount: u32,
+    pub pSetLayouts: *const VkDescriptorSetLayout,
+}
+
+impl Default for VkDescriptorSetAllocateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DescriptorSetAllocateInfo,
+            pNext: ptr::null(),
+            descriptorPool: VkDescriptorPool::NULL,
+            descriptorSetCount: 0,
+            pSetLayouts: ptr::null(),
+        }
+    }
+}
+
+/// Descriptor buffer info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorBufferInfo {
+    pub buffer: VkBuffer,
+    pub offset: VkDeviceSize,
+    pub range: VkDeviceSize,
+}
+
+/// Descriptor image info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorImageInfo {
+    pub sampler: VkSampler,
+    pub imageView: VkImageView,
+    pub imageLayout: VkImageLayout,
+}
+
+/// Write descriptor set
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkWriteDescriptorSet {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub dstSet: VkDescriptorSet,
+    pub dstBinding: u32,
+    pub dstArrayElement: u32,
+    pub descriptorCount: u32,
+    pub descriptorType: VkDescriptorType,
+    pub pImageInfo: *const VkDescriptorImageInfo,
+    pub pBufferInfo: *const VkDescriptorBufferInfo,
+    pub pTexelBufferView: *const VkBufferView,
+}
+
+impl Default for VkWriteDescriptorSet {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::WriteDescriptorSet,
+            pNext: ptr::null(),
+            dstSet: VkDescriptorSet::NULL,
+            dstBinding: 0,
+            dstArrayElement: 0,
+            descriptorCount: 0,
+            descriptorType: VkDescriptorType::StorageBuffer,
+            pImageInfo: ptr::null(),
+            pBufferInfo: ptr::null(),
+            pTexelBufferView: ptr::null(),
+        }
+    }
+}
+
+/// Copy descriptor set
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkCopyDescriptorSet {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub srcSet: VkDescriptorSet,
+    pub srcBinding: u32,
+    pub srcArrayElement: u32,
+    pub dstSet: VkDescriptorSet,
+    pub dstBinding: u32,
+    pub dstArrayElement: u32,
+    pub descriptorCount: u32,
+}
+
+/// Where one binding's descriptors sit in the data passed to
+/// vkUpdateDescriptorSetWithTemplate
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorUpdateTemplateEntry {
+    pub dstBinding: u32,
+    pub dstArrayElement: u32,
+    pub descriptorCount: u32,
+    pub descriptorType: VkDescriptorType,
+    pub offset: usize,
+    pub stride: usize,
+}
+
+/// Descriptor update template creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDescriptorUpdateTemplateCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkDescriptorUpdateTemplateCreateFlags,
+    pub descriptorUpdateEntryCount: u32,
+    pub pDescriptorUpdateEntries: *const VkDescriptorUpdateTemplateEntry,
+    pub templateType: VkDescriptorUpdateTemplateType,
+    pub descriptorSetLayout: VkDescriptorSetLayout,
+    pub pipelineBindPoint: VkPipelineBindPoint,
+    pub pipelineLayout: VkPipelineLayout,
+    pub set: u32,
+}
+
+impl Default for VkDescriptorUpdateTemplateCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DescriptorUpdateTemplateCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            descriptorUpdateEntryCount: 0,
+            pDescriptorUpdateEntries: ptr::null(),
+            templateType: VkDescriptorUpdateTemplateType::DescriptorSet,
+            descriptorSetLayout: VkDescriptorSetLayout::NULL,
+            pipelineBindPoint: VkPipelineBindPoint::Compute,
+            pipelineLayout: VkPipelineLayout::NULL,
+            set: 0,
+        }
+    }
+}
+
+// BufferView handle type
+pub type VkBufferView = Handle<BufferViewT>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum BufferViewT {}
+
+// Add missing enums
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VkImageLayout {
+    Undefined = 0,
+    General = 1,
+    TransferSrcOptimal = 6,
+    TransferDstOptimal = 7,
+}
+
+/// Three-dimensional offset
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VkOffset3D {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Image creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImageCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkImageCreateFlags,
+    pub imageType: VkImageType,
+    pub format: VkFormat,
+    pub extent: VkExtent3D,
+    pub mipLevels: u32,
+    pub arrayLayers: u32,
+    pub samples: VkSampleCountFlags,
+    pub tiling: VkImageTiling,
+    pub usage: VkImageUsageFlags,
+    pub sharingMode: VkSharingMode,
+    pub queueFamilyIndexCount: u32,
+    pub pQueueFamilyIndices: *const u32,
+    pub initialLayout: VkImageLayout,
+}
+
+impl Default for VkImageCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ImageCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            imageType: VkImageType::Type2D,
+            format: VkFormat::Undefined,
+            extent: VkExtent3D::default(),
+            mipLevels: 1,
+            arrayLayers: 1,
+            samples: VkSampleCountFlags::TYPE_1,
+            tiling: VkImageTiling::Optimal,
+            usage: VkImageUsageFlags::empty(),
+            sharingMode: VkSharingMode::Exclusive,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: ptr::null(),
+            initialLayout: VkImageLayout::Undefined,
+        }
+    }
+}
+
+/// Component mapping for image views
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkComponentMapping {
+    pub r: VkComponentSwizzle,
+    pub g: VkComponentSwizzle,
+    pub b: VkComponentSwizzle,
+    pub a: VkComponentSwizzle,
+}
+
+impl Default for VkComponentMapping {
+    fn default() -> Self {
+        Self {
+            r: VkComponentSwizzle::Identity,
+            g: VkComponentSwizzle::Identity,
+            b: VkComponentSwizzle::Identity,
+            a: VkComponentSwizzle::Identity,
+        }
+    }
+}
+
+/// Image subresource range
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImageSubresourceRange {
+    pub aspectMask: VkImageAspectFlags,
+    pub baseMipLevel: u32,
+    pub levelCount: u32,
+    pub baseArrayLayer: u32,
+    pub layerCount: u32,
+}
+
+impl Default for VkImageSubresourceRange {
+    fn default() -> Self {
+        Self {
+            aspectMask: VkImageAspectFlags::COLOR,
+            baseMipLevel: 0,
+            levelCount: 1,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        }
+    }
+}
+
+/// Image subresource layers (for copies)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImageSubresourceLayers {
+    pub aspectMask: VkImageAspectFlags,
+    pub mipLevel: u32,
+    pub baseArrayLayer: u32,
+    pub layerCount: u32,
+}
+
+impl Default for VkImageSubresourceLayers {
+    fn default() -> Self {
+        Self {
+            aspectMask: VkImageAspectFlags::COLOR,
+            mipLevel: 0,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        }
+    }
+}
+
+/// Image view creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImageViewCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkImageViewCreateFlags,
+    pub image: VkImage,
+    pub viewType: VkImageViewType,
+    pub format: VkFormat,
+    pub components: VkComponentMapping,
+    pub subresourceRange: VkImageSubresourceRange,
+}
+
+impl Default for VkImageViewCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ImageViewCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            image: VkImage::NULL,
+            viewType: VkImageViewType::Type2D,
+            format: VkFormat::Undefined,
+            components: VkComponentMapping::default(),
+            subresourceRange: VkImageSubresourceRange::default(),
+        }
+    }
+}
+
+/// Image memory barrier
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImageMemoryBarrier {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub srcAccessMask: VkAccessFlags,
+    pub dstAccessMask: VkAccessFlags,
+    pub oldLayout: VkImageLayout,
+    pub newLayout: VkImageLayout,
+    pub srcQueueFamilyIndex: u32,
+    pub dstQueueFamilyIndex: u32,
+    pub image: VkImage,
+    pub subresourceRange: VkImageSubresourceRange,
+}
+
+impl Default for VkImageMemoryBarrier {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ImageMemoryBarrier,
+            pNext: ptr::null(),
+            srcAccessMask: VkAccessFlags::empty(),
+            dstAccessMask: VkAccessFlags::empty(),
+            oldLayout: VkImageLayout::Undefined,
+            newLayout: VkImageLayout::General,
+            srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            image: VkImage::NULL,
+            subresourceRange: VkImageSubresourceRange::default(),
+        }
+    }
+}
+
+/// Buffer/image copy region
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VkBufferImageCopy {
+    pub bufferOffset: VkDeviceSize,
+    pub bufferRowLength: u32,
+    pub bufferImageHeight: u32,
+    pub imageSubresource: VkImageSubresourceLayers,
+    pub imageOffset: VkOffset3D,
+    pub imageExtent: VkExtent3D,
+}
+
+/// Sampler creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkSamplerCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkSamplerCreateFlags,
+    pub magFilter: VkFilter,
+    pub minFilter: VkFilter,
+    pub mipmapMode: VkSamplerMipmapMode,
+    pub addressModeU: VkSamplerAddressMode,
+    pub addressModeV: VkSamplerAddressMode,
+    pub addressModeW: VkSamplerAddressMode,
+    pub mipLodBias: f32,
+    pub anisotropyEnable: VkBool32,
+    pub maxAnisotropy: f32,
+    pub compareEnable: VkBool32,
+    pub compareOp: VkCompareOp,
+    pub minLod: f32,
+    pub maxLod: f32,
+    pub borderColor: VkBorderColor,
+    pub unnormalizedCoordinates: VkBool32,
+}
+
+/// Query pool creation info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkQueryPoolCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkQueryPoolCreateFlags,
+    pub queryType: VkQueryType,
+    pub queryCount: u32,
+    pub pipelineStatistics: VkQueryPipelineStatisticFlags,
+}
+
+/// Properties of an instance or device extension
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkExtensionProperties {
+    pub extensionName: [c_char; VK_MAX_EXTENSION_NAME_SIZE],
+    pub specVersion: u32,
+}
+
+impl Default for VkExtensionProperties {
+    fn default() -> Self {
+        unsafe { ::core::mem::zeroed() }
+    }
+}
+
+/// Name of the VK_KHR_global_priority device extension
+pub const VK_KHR_GLOBAL_PRIORITY_EXTENSION_NAME: &str = "VK_KHR_global_priority";
+
+/// Name of the VK_EXT_global_priority_query device extension, the
+/// predecessor of the queue family query in VK_KHR_global_priority
+pub const VK_EXT_GLOBAL_PRIORITY_QUERY_EXTENSION_NAME: &str = "VK_EXT_global_priority_query";
+
+/// Maximum number of priorities in VkQueueFamilyGlobalPriorityPropertiesKHR
+pub const VK_MAX_GLOBAL_PRIORITY_SIZE_KHR: usize = 16;
+
+/// Queue family properties with an extension chain
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkQueueFamilyProperties2 {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub queueFamilyProperties: VkQueueFamilyProperties,
+}
+
+impl Default for VkQueueFamilyProperties2 {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::QueueFamilyProperties2,
+            pNext: ptr::null_mut(),
+            queueFamilyProperties: VkQueueFamilyProperties::default(),
+        }
+    }
+}
+
+/// Global priorities a queue family supports, chained into VkQueueFamilyProperties2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkQueueFamilyGlobalPriorityPropertiesKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub priorityCount: u32,
+    pub priorities: [VkQueueGlobalPriorityKHR; VK_MAX_GLOBAL_PRIORITY_SIZE_KHR],
+}
+
+impl VkQueueFamilyGlobalPriorityPropertiesKHR {
+    /// The reported priorities, lowest first
+    pub fn priorities(&self) -> &[VkQueueGlobalPriorityKHR] {
+        let count = (self.priorityCount as usize).min(VK_MAX_GLOBAL_PRIORITY_SIZE_KHR);
+        &self.priorities[..count]
+    }
+}
+
+impl Default for VkQueueFamilyGlobalPriorityPropertiesKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::QueueFamilyGlobalPriorityPropertiesKHR,
+            pNext: ptr::null_mut(),
+            priorityCount: 0,
+            priorities: [VkQueueGlobalPriorityKHR::Medium; VK_MAX_GLOBAL_PRIORITY_SIZE_KHR],
+        }
+    }
+}
+
+/// Video codec operations a queue family supports, chained into VkQueueFamilyProperties2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkQueueFamilyVideoPropertiesKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub videoCodecOperations: VkFlags,
+}
+
+impl Default for VkQueueFamilyVideoPropertiesKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::QueueFamilyVideoPropertiesKHR,
+            pNext: ptr::null_mut(),
+            videoCodecOperations: 0,
+        }
+    }
+}
+
+/// Device properties with an extension chain
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceProperties2 {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    /// The full Vulkan 1.0 VkPhysicalDeviceProperties, whose leading part
+    /// is the simplified VkPhysicalDeviceProperties
+    pub properties: [u64; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8],
+}
+
+impl Default for VkPhysicalDeviceProperties2 {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceProperties2,
+            pNext: ptr::null_mut(),
+            properties: [0; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8],
+        }
+    }
+}
+
+/// Name of the VK_KHR_driver_properties device extension, core in Vulkan 1.2
+pub const VK_KHR_DRIVER_PROPERTIES_EXTENSION_NAME: &str = "VK_KHR_driver_properties";
+
+/// Khronos-registered identifier of a driver implementation
+///
+/// A newtype rather than an enum, since drivers may report IDs registered
+/// after this list.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VkDriverId(pub u32);
+
+impl VkDriverId {
+    pub const AMD_PROPRIETARY: Self = Self(1);
+    pub const AMD_OPEN_SOURCE: Self = Self(2);
+    pub const MESA_RADV: Self = Self(3);
+    pub const NVIDIA_PROPRIETARY: Self = Self(4);
+    pub const INTEL_PROPRIETARY_WINDOWS: Self = Self(5);
+    pub const INTEL_OPEN_SOURCE_MESA: Self = Self(6);
+    pub const IMAGINATION_PROPRIETARY: Self = Self(7);
+    pub const QUALCOMM_PROPRIETARY: Self = Self(8);
+    pub const ARM_PROPRIETARY: Self = Self(9);
+    pub const GOOGLE_SWIFTSHADER: Self = Self(10);
+    pub const GGP_PROPRIETARY: Self = Self(11);
+    pub const BROADCOM_PROPRIETARY: Self = Self(12);
+    pub const MESA_LLVMPIPE: Self = Self(13);
+    pub const MOLTENVK: Self = Self(14);
+    pub const COREAVI_PROPRIETARY: Self = Self(15);
+    pub const JUICE_PROPRIETARY: Self = Self(16);
+    pub const VERISILICON_PROPRIETARY: Self = Self(17);
+    pub const MESA_TURNIP: Self = Self(18);
+    pub const MESA_V3DV: Self = Self(19);
+    pub const MESA_PANVK: Self = Self(20);
+    pub const SAMSUNG_PROPRIETARY: Self = Self(21);
+    pub const MESA_VENUS: Self = Self(22);
+    pub const MESA_DOZEN: Self = Self(23);
+    pub const MESA_NVK: Self = Self(24);
+    pub const IMAGINATION_OPEN_SOURCE_MESA: Self = Self(25);
+    pub const MESA_HONEYKRISP: Self = Self(26);
+
+    /// Short name of a registered driver, e.g. "RADV" or "AMDVLK"
+    pub fn name(self) -> Option<&'static str> {
+        let name = match self {
+            Self::AMD_PROPRIETARY => "AMD proprietary",
+            Self::AMD_OPEN_SOURCE => "AMDVLK",
+            Self::MESA_RADV => "RADV",
+            Self::NVIDIA_PROPRIETARY => "NVIDIA proprietary",
+            Self::INTEL_PROPRIETARY_WINDOWS => "Intel proprietary (Windows)",
+            Self::INTEL_OPEN_SOURCE_MESA => "ANV",
+            Self::IMAGINATION_PROPRIETARY => "Imagination proprietary",
+            Self::QUALCOMM_PROPRIETARY => "Qualcomm proprietary",
+            Self::ARM_PROPRIETARY => "Arm proprietary",
+            Self::GOOGLE_SWIFTSHADER => "SwiftShader",
+            Self::GGP_PROPRIETARY => "GGP proprietary",
+            Self::BROADCOM_PROPRIETARY => "Broadcom proprietary",
+            Self::MESA_LLVMPIPE => "llvmpipe",
+            Self::MOLTENVK => "MoltenVK",
+            Self::COREAVI_PROPRIETARY => "CoreAVI proprietary",
+            Self::JUICE_PROPRIETARY => "Juice proprietary",
+            Self::VERISILICON_PROPRIETARY => "VeriSilicon proprietary",
+            Self::MESA_TURNIP => "Turnip",
+            Self::MESA_V3DV => "V3DV",
+            Self::MESA_PANVK => "PanVK",
+            Self::SAMSUNG_PROPRIETARY => "Samsung proprietary",
+            Self::MESA_VENUS => "Venus",
+            Self::MESA_DOZEN => "Dozen",
+            Self::MESA_NVK => "NVK",
+            Self::IMAGINATION_OPEN_SOURCE_MESA => "Imagination open source (Mesa)",
+            Self::MESA_HONEYKRISP => "Honeykrisp",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// Version of the Vulkan conformance test suite a driver passed
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VkConformanceVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub subminor: u8,
+    pub patch: u8,
+}
+
+/// Driver identification, chained into VkPhysicalDeviceProperties2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceDriverProperties {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub driverID: VkDriverId,
+    pub driverName: [c_char; VK_MAX_DRIVER_NAME_SIZE],
+    pub driverInfo: [c_char; VK_MAX_DRIVER_INFO_SIZE],
+    pub conformanceVersion: VkConformanceVersion,
+}
+
+impl Default for VkPhysicalDeviceDriverProperties {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceDriverProperties,
+            pNext: ptr::null_mut(),
+            driverID: VkDriverId::default(),
+            driverName: [0; VK_MAX_DRIVER_NAME_SIZE],
+            driverInfo: [0; VK_MAX_DRIVER_INFO_SIZE],
+            conformanceVersion: VkConformanceVersion::default(),
+        }
+    }
+}
+
+/// Identifiers shared with other APIs driving the same device, chained
+/// into VkPhysicalDeviceProperties2; core in Vulkan 1.1
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceIDProperties {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub deviceUUID: [u8; VK_UUID_SIZE],
+    pub driverUUID: [u8; VK_UUID_SIZE],
+    pub deviceLUID: [u8; VK_LUID_SIZE],
+    pub deviceNodeMask: u32,
+    pub deviceLUIDValid: VkBool32,
+}
+
+impl Default for VkPhysicalDeviceIDProperties {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceIdProperties,
+            pNext: ptr::null_mut(),
+            deviceUUID: [0; VK_UUID_SIZE],
+            driverUUID: [0; VK_UUID_SIZE],
+            deviceLUID: [0; VK_LUID_SIZE],
+            deviceNodeMask: 0,
+            deviceLUIDValid: VK_FALSE,
+        }
+    }
+}
+
+/// Name of the VK_EXT_pci_bus_info device extension
+pub const VK_EXT_PCI_BUS_INFO_EXTENSION_NAME: &str = "VK_EXT_pci_bus_info";
+
+/// PCI address of a device, chained into VkPhysicalDeviceProperties2
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDevicePCIBusInfoPropertiesEXT {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub pciDomain: u32,
+    pub pciBus: u32,
+    pub pciDevice: u32,
+    pub pciFunction: u32,
+}
+
+impl Default for VkPhysicalDevicePCIBusInfoPropertiesEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDevicePciBusInfoPropertiesEXT,
+            pNext: ptr::null_mut(),
+            pciDomain: 0,
+            pciBus: 0,
+            pciDevice: 0,
+            pciFunction: 0,
+        }
+    }
+}
+
+/// Name of the VK_KHR_performance_query device extension
+pub const VK_KHR_PERFORMANCE_QUERY_EXTENSION_NAME: &str = "VK_KHR_performance_query";
+
+/// Performance query features, chained into VkDeviceCreateInfo to enable them
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDevicePerformanceQueryFeaturesKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub performanceCounterQueryPools: VkBool32,
+    pub performanceCounterMultipleQueryPools: VkBool32,
+}
+
+/// Performance counter properties
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPerformanceCounterKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub unit: VkPerformanceCounterUnitKHR,
+    pub scope: VkPerformanceCounterScopeKHR,
+    pub storage: VkPerformanceCounterStorageKHR,
+    pub uuid: [u8; VK_UUID_SIZE],
+}
+
+impl Default for VkPerformanceCounterKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PerformanceCounterKHR,
+            pNext: ptr::null_mut(),
+            unit: VkPerformanceCounterUnitKHR::Generic,
+            scope: VkPerformanceCounterScopeKHR::CommandBuffer,
+            storage: VkPerformanceCounterStorageKHR::Uint64,
+            uuid: [0; VK_UUID_SIZE],
+        }
+    }
+}
+
+/// Human-readable performance counter description
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPerformanceCounterDescriptionKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub flags: VkPerformanceCounterDescriptionFlagsKHR,
+    pub name: [c_char; VK_MAX_DESCRIPTION_SIZE],
+    pub category: [c_char; VK_MAX_DESCRIPTION_SIZE],
+    pub description: [c_char; VK_MAX_DESCRIPTION_SIZE],
+}
+
+impl Default for VkPerformanceCounterDescriptionKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PerformanceCounterDescriptionKHR,
+            pNext: ptr::null_mut(),
+            flags: VkPerformanceCounterDescriptionFlagsKHR::empty(),
+            name: [0; VK_MAX_DESCRIPTION_SIZE],
+            category: [0; VK_MAX_DESCRIPTION_SIZE],
+            description: [0; VK_MAX_DESCRIPTION_SIZE],
+        }
+    }
+}
+
+/// Counters collected by a performance query pool, chained into VkQueryPoolCreateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkQueryPoolPerformanceCreateInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub queueFamilyIndex: u32,
+    pub counterIndexCount: u32,
+    pub pCounterIndices: *const u32,
+}
+
+/// Profiling lock acquisition info
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkAcquireProfilingLockInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkAcquireProfilingLockFlagsKHR,
+    pub timeout: u64,
+}
+
+/// Counter pass of a submission, chained into VkSubmitInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPerformanceQuerySubmitInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub counterPassIndex: u32,
+}
+
+impl Default for VkPerformanceQuerySubmitInfoKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PerformanceQuerySubmitInfoKHR,
+            pNext: ptr::null(),
+            counterPassIndex: 0,
+        }
+    }
+}
+
+/// Performance counter result; the active member follows the counter's storage
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union VkPerformanceCounterResultKHR {
+    pub int32: i32,
+    pub int64: i64,
+    pub uint32: u32,
+    pub uint64: u64,
+    pub float32: f32,
+    pub float64: f64,
+}
+
+impl Default for VkPerformanceCounterResultKHR {
+    fn default() -> Self {
+        Self { uint64: 0 }
+    }
+}
+
+impl Default for VkSamplerCreateInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::SamplerCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            magFilter: VkFilter::Nearest,
+            minFilter: VkFilter::Nearest,
+            mipmapMode: VkSamplerMipmapMode::Nearest,
+            addressModeU: VkSamplerAddressMode::ClampToEdge,
+            addressModeV: VkSamplerAddressMode::ClampToEdge,
+            addressModeW: VkSamplerAddressMode::ClampToEdge,
+            mipLodBias: 0.0,
+            anisotropyEnable: VK_FALSE,
+            maxAnisotropy: 1.0,
+            compareEnable: VK_FALSE,
+            compareOp: VkCompareOp::Never,
+            minLod: 0.0,
+            maxLod: 0.0,
+            borderColor: VkBorderColor::FloatTransparentBlack,
+            unnormalizedCoordinates: VK_FALSE,
+        }
+    }
+}
+
+/// Name of the VK_KHR_external_memory_fd device extension
+pub const VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME: &str = "VK_KHR_external_memory_fd";
+
+/// Name of the VK_EXT_external_memory_dma_buf device extension
+pub const VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME: &str = "VK_EXT_external_memory_dma_buf";
+
+/// Name of the VK_KHR_external_semaphore_fd device extension
+pub const VK_KHR_EXTERNAL_SEMAPHORE_FD_EXTENSION_NAME: &str = "VK_KHR_external_semaphore_fd";
+
+/// Name of the VK_KHR_portability_enumeration instance extension
+pub const VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME: &str = "VK_KHR_portability_enumeration";
+
+/// Name of the VK_KHR_portability_subset device extension, which must be
+/// enabled on devices that expose it
+pub const VK_KHR_PORTABILITY_SUBSET_EXTENSION_NAME: &str = "VK_KHR_portability_subset";
+
+/// Name of the VK_EXT_pipeline_creation_feedback device extension, core in Vulkan 1.3
+pub const VK_EXT_PIPELINE_CREATION_FEEDBACK_EXTENSION_NAME: &str = "VK_EXT_pipeline_creation_feedback";
+
+/// How long creating a pipeline or one of its stages took
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VkPipelineCreationFeedback {
+    pub flags: VkPipelineCreationFeedbackFlags,
+    /// Nanoseconds
+    pub duration: u64,
+}
+
+impl Default for VkPipelineCreationFeedback {
+    fn default() -> Self {
+        Self {
+            flags: VkPipelineCreationFeedbackFlags::empty(),
+            duration: 0,
+        }
+    }
+}
+
+/// Where the driver writes creation feedback, chained into
+/// VkComputePipelineCreateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPipelineCreationFeedbackCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub pPipelineCreationFeedback: *mut VkPipelineCreationFeedback,
+    pub pipelineStageCreationFeedbackCount: u32,
+    pub pPipelineStageCreationFeedbacks: *mut VkPipelineCreationFeedback,
+}
+
+/// Handle types a buffer's memory may be imported from, chained into VkBufferCreateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkExternalMemoryBufferCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub handleTypes: VkExternalMemoryHandleTypeFlags,
+}
+
+/// File descriptor to import, chained into VkMemoryAllocateInfo
+///
+/// A successful import transfers ownership of `fd` to the driver.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkImportMemoryFdInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub handleType: VkExternalMemoryHandleTypeFlags,
+    pub fd: c_int,
+}
+
+/// Memory types a file descriptor can be imported into
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMemoryFdPropertiesKHR {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub memoryTypeBits: u32,
+}
+
+impl Default for VkMemoryFdPropertiesKHR {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::MemoryFdPropertiesKHR,
+            pNext: ptr::null_mut(),
+            memoryTypeBits: 0,
+        }
+    }
+}
+
+/// Resource an allocation is dedicated to, chained into VkMemoryAllocateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMemoryDedicatedAllocateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub image: VkImage,
+    pub buffer: VkBuffer,
+}
+
+/// Handle types an allocation may be exported as, chained into VkMemoryAllocateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkExportMemoryAllocateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub handleTypes: VkExternalMemoryHandleTypeFlags,
+}
+
+/// Allocation to export as a file descriptor
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMemoryGetFdInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub memory: VkDeviceMemory,
+    pub handleType: VkExternalMemoryHandleTypeFlags,
+}
+
+/// Handle types a semaphore may be exported as, chained into VkSemaphoreCreateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkExportSemaphoreCreateInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub handleTypes: VkExternalSemaphoreHandleTypeFlags,
+}
+
+/// Semaphore to export as a file descriptor
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkSemaphoreGetFdInfoKHR {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub semaphore: VkSemaphore,
+    pub handleType: VkExternalSemaphoreHandleTypeFlags,
+}
+
+/// Properties of an instance layer
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkLayerProperties {
+    pub layerName: [c_char; VK_MAX_EXTENSION_NAME_SIZE],
+    pub specVersion: u32,
+    pub implementationVersion: u32,
+    pub description: [c_char; VK_MAX_DESCRIPTION_SIZE],
+}
+
+impl Default for VkLayerProperties {
+    fn default() -> Self {
+        unsafe { ::core::mem::zeroed() }
+    }
+}
+
+/// Name of the Khronos validation layer
+pub const VK_LAYER_KHRONOS_VALIDATION_NAME: &str = "VK_LAYER_KHRONOS_validation";
+
+/// Name of the VK_EXT_debug_utils instance extension
+pub const VK_EXT_DEBUG_UTILS_EXTENSION_NAME: &str = "VK_EXT_debug_utils";
+
+/// Name of the VK_EXT_validation_features instance extension, provided by
+/// the validation layer
+pub const VK_EXT_VALIDATION_FEATURES_EXTENSION_NAME: &str = "VK_EXT_validation_features";
+
+/// Name of the VK_KHR_shader_non_semantic_info device extension, core in
+/// Vulkan 1.3; lets shaders carry debugPrintfEXT calls
+pub const VK_KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION_NAME: &str = "VK_KHR_shader_non_semantic_info";
+
+/// Validation layer features to turn on or off, chained into VkInstanceCreateInfo
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkValidationFeaturesEXT {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub enabledValidationFeatureCount: u32,
+    pub pEnabledValidationFeatures: *const VkValidationFeatureEnableEXT,
+    pub disabledValidationFeatureCount: u32,
+    pub pDisabledValidationFeatures: *const VkValidationFeatureDisableEXT,
+}
+
+impl Default for VkValidationFeaturesEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ValidationFeaturesEXT,
+            pNext: ptr::null(),
+            enabledValidationFeatureCount: 0,
+            pEnabledValidationFeatures: ptr::null(),
+            disabledValidationFeatureCount: 0,
+            pDisabledValidationFeatures: ptr::null(),
+        }
+    }
+}
+
+/// Message passed to a debug messenger callback
+///
+/// Kronos reads only the message and its ID; the labels and objects are
+/// left untyped.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDebugUtilsMessengerCallbackDataEXT {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkFlags,
+    pub pMessageIdName: *const c_char,
+    pub messageIdNumber: i32,
+    pub pMessage: *const c_char,
+    pub queueLabelCount: u32,
+    pub pQueueLabels: *const c_void,
+    pub cmdBufLabelCount: u32,
+    pub pCmdBufLabels: *const c_void,
+    pub objectCount: u32,
+    pub pObjects: *const c_void,
+}
+
+impl Default for VkDebugUtilsMessengerCallbackDataEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DebugUtilsMessengerCallbackDataEXT,
+            pNext: ptr::null(),
+            flags: 0,
+            pMessageIdName: ptr::null(),
+            messageIdNumber: 0,
+            pMessage: ptr::null(),
+            queueLabelCount: 0,
+            pQueueLabels: ptr::null(),
+            cmdBufLabelCount: 0,
+            pCmdBufLabels: ptr::null(),
+            objectCount: 0,
+            pObjects: ptr::null(),
+        }
+    }
+}
+
+/// Receives validation layer and driver messages
+pub type PFN_vkDebugUtilsMessengerCallbackEXT = Option<unsafe extern "C" fn(
+    messageSeverity: VkDebugUtilsMessageSeverityFlagsEXT,
+    messageTypes: VkDebugUtilsMessageTypeFlagsEXT,
+    pCallbackData: *const VkDebugUtilsMessengerCallbackDataEXT,
+    pUserData: *mut c_void,
+) -> VkBool32>;
+
+/// Debug messenger creation info; also chained into VkInstanceCreateInfo
+/// to receive messages from instance creation and destruction
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDebugUtilsMessengerCreateInfoEXT {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkFlags,
+    pub messageSeverity: VkDebugUtilsMessageSeverityFlagsEXT,
+    pub messageType: VkDebugUtilsMessageTypeFlagsEXT,
+    pub pfnUserCallback: PFN_vkDebugUtilsMessengerCallbackEXT,
+    pub pUserData: *mut c_void,
+}
+
+impl Default for VkDebugUtilsMessengerCreateInfoEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::DebugUtilsMessengerCreateInfoEXT,
+            pNext: ptr::null(),
+            flags: 0,
+            messageSeverity: VkDebugUtilsMessageSeverityFlagsEXT::empty(),
+            messageType: VkDebugUtilsMessageTypeFlagsEXT::empty(),
+            pfnUserCallback: None,
+            pUserData: ptr::null_mut(),
+        }
+    }
+}
+
+/// VK_EXT_memory_priority extension name
+pub const VK_EXT_MEMORY_PRIORITY_EXTENSION_NAME: &str = "VK_EXT_memory_priority";
+
+/// VK_EXT_pageable_device_local_memory extension name; lets the OS page
+/// device-local memory out by priority rather than failing allocations
+pub const VK_EXT_PAGEABLE_DEVICE_LOCAL_MEMORY_EXTENSION_NAME: &str = "VK_EXT_pageable_device_local_memory";
+
+/// Memory priority feature, chained into VkDeviceCreateInfo to enable it
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceMemoryPriorityFeaturesEXT {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub memoryPriority: VkBool32,
+}
+
+impl Default for VkPhysicalDeviceMemoryPriorityFeaturesEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceMemoryPriorityFeaturesEXT,
+            pNext: ptr::null_mut(),
+            memoryPriority: VK_FALSE,
+        }
+    }
+}
+
+/// Pageable device-local memory feature, chained into VkDeviceCreateInfo
+/// to enable it
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDevicePageableDeviceLocalMemoryFeaturesEXT {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub pageableDeviceLocalMemory: VkBool32,
+}
+
+impl Default for VkPhysicalDevicePageableDeviceLocalMemoryFeaturesEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDevicePageableDeviceLocalMemoryFeaturesEXT,
+            pNext: ptr::null_mut(),
+            pageableDeviceLocalMemory: VK_FALSE,
+        }
+    }
+}
+
+/// VK_EXT_conditional_rendering extension name; predicates draws and
+/// dispatches on a value in a buffer
+pub const VK_EXT_CONDITIONAL_RENDERING_EXTENSION_NAME: &str = "VK_EXT_conditional_rendering";
+
+/// Conditional rendering features, chained into VkDeviceCreateInfo to
+/// enable them
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceConditionalRenderingFeaturesEXT {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub conditionalRendering: VkBool32,
+    pub inheritedConditionalRendering: VkBool32,
+}
+
+impl Default for VkPhysicalDeviceConditionalRenderingFeaturesEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceConditionalRenderingFeaturesEXT,
+            pNext: ptr::null_mut(),
+            conditionalRendering: VK_FALSE,
+            inheritedConditionalRendering: VK_FALSE,
+        }
+    }
+}
+
+/// Predicate of a conditional rendering block: the 32-bit value at
+/// `offset` in `buffer`, which needs CONDITIONAL_RENDERING_EXT usage
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkConditionalRenderingBeginInfoEXT {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub buffer: VkBuffer,
+    pub offset: VkDeviceSize,
+    pub flags: VkConditionalRenderingFlagsEXT,
+}
+
+impl Default for VkConditionalRenderingBeginInfoEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::ConditionalRenderingBeginInfoEXT,
+            pNext: ptr::null(),
+            buffer: VkBuffer::NULL,
+            offset: 0,
+            flags: VkConditionalRenderingFlagsEXT::empty(),
+        }
+    }
+}
+
+/// Residency priority of an allocation, from 0.0 to 1.0, chained into
+/// VkMemoryAllocateInfo; allocations without one get 0.5
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMemoryPriorityAllocateInfoEXT {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub priority: f32,
+}
+
+impl Default for VkMemoryPriorityAllocateInfoEXT {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::MemoryPriorityAllocateInfoEXT,
+            pNext: ptr::null(),
+            priority: 0.5,
+        }
+    }
+}
@@ -0,0 +1,564 @@
+//! Enumerations for Kronos API
+//!
+//! Every enum converts from its raw value with `TryFrom`, which names the
+//! enum and the value when the value is none of its variants. Values a
+//! driver writes into a struct or returns may be newer than these
+//! definitions; convert them from the raw integer rather than reading the
+//! field as the enum.
+
+use core::fmt;
+
+/// A raw value that is none of an enum's variants, from its `TryFrom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownValue {
+    /// Name of the enum
+    pub type_name: &'static str,
+    pub value: i64,
+}
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a known {}", self.value, self.type_name)
+    }
+}
+
+/// Define an enum with a fixed representation and `TryFrom` its raw value
+#[doc(hidden)]
+#[macro_export]
+macro_rules! vk_enum {
+    (
+        $(#[doc = $doc:literal])*
+        #[repr($repr:ident)]
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:expr,)*
+        }
+    ) => {
+        $(#[doc = $doc])*
+        #[repr($repr)]
+        $(#[$meta])*
+        pub enum $name {
+            $($(#[$variant_meta])* $variant = $value,)*
+        }
+
+        impl TryFrom<$repr> for $name {
+            type Error = $crate::core::UnknownValue;
+
+            fn try_from(raw: $repr) -> Result<Self, Self::Error> {
+                $(if raw == $name::$variant as $repr {
+                    return Ok($name::$variant);
+                })*
+                Err($crate::core::UnknownValue { type_name: stringify!($name), value: raw as i64 })
+            }
+        }
+    };
+}
+
+vk_enum! {
+    /// Structure type identifiers
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkStructureType {
+        ApplicationInfo = 0,
+        InstanceCreateInfo = 1,
+        DeviceQueueCreateInfo = 2,
+        DeviceCreateInfo = 3,
+        SubmitInfo = 4,
+        MemoryAllocateInfo = 5,
+        MappedMemoryRange = 6,
+        FenceCreateInfo = 8,
+        SemaphoreCreateInfo = 9,
+        EventCreateInfo = 10,
+        QueryPoolCreateInfo = 11,
+        BufferCreateInfo = 12,
+        ImageCreateInfo = 14,
+        ImageViewCreateInfo = 15,
+        PipelineShaderStageCreateInfo = 18,
+        ComputePipelineCreateInfo = 29,
+        PipelineLayoutCreateInfo = 30,
+        SamplerCreateInfo = 31,
+        DescriptorSetLayoutCreateInfo = 32,
+        DescriptorPoolCreateInfo = 33,
+        DescriptorSetAllocateInfo = 34,
+        WriteDescriptorSet = 35,
+        CopyDescriptorSet = 36,
+        ShaderModuleCreateInfo = 16,
+        CommandPoolCreateInfo = 39,
+        CommandBufferAllocateInfo = 40,
+        CommandBufferBeginInfo = 42,
+        BufferMemoryBarrier = 44,
+        ImageMemoryBarrier = 45,
+        MemoryBarrier = 46,
+        // Per Vulkan spec: PipelineCacheCreateInfo = 17
+        PipelineCacheCreateInfo = 17,
+        // Vulkan 1.1 (VK_KHR_device_group)
+        MemoryAllocateFlagsInfo = 1000060000,
+        // Vulkan 1.1 (VK_KHR_external_memory)
+        ExternalMemoryBufferCreateInfo = 1000072000,
+        ExportMemoryAllocateInfo = 1000072002,
+        // VK_KHR_external_memory_fd
+        ImportMemoryFdInfoKHR = 1000074000,
+        MemoryFdPropertiesKHR = 1000074001,
+        MemoryGetFdInfoKHR = 1000074002,
+        // Vulkan 1.1 (VK_KHR_external_semaphore)
+        ExportSemaphoreCreateInfo = 1000077000,
+        // VK_KHR_external_semaphore_fd
+        SemaphoreGetFdInfoKHR = 1000079001,
+        // VK_EXT_debug_utils
+        DebugUtilsMessengerCallbackDataEXT = 1000128003,
+        DebugUtilsMessengerCreateInfoEXT = 1000128004,
+        // VK_EXT_validation_features
+        ValidationFeaturesEXT = 1000247000,
+        // VK_EXT_memory_priority
+        PhysicalDeviceMemoryPriorityFeaturesEXT = 1000238000,
+        MemoryPriorityAllocateInfoEXT = 1000238001,
+        // VK_EXT_conditional_rendering
+        PhysicalDeviceConditionalRenderingFeaturesEXT = 1000081001,
+        ConditionalRenderingBeginInfoEXT = 1000081002,
+        // VK_EXT_pageable_device_local_memory
+        PhysicalDevicePageableDeviceLocalMemoryFeaturesEXT = 1000412000,
+        // Vulkan 1.1 (VK_KHR_dedicated_allocation)
+        MemoryDedicatedAllocateInfo = 1000127001,
+        // Vulkan 1.1 (VK_KHR_descriptor_update_template)
+        DescriptorUpdateTemplateCreateInfo = 1000085000,
+        // Timeline semaphore extensions
+        PhysicalDeviceTimelineSemaphoreFeatures = 1000207000,
+        SemaphoreTypeCreateInfo = 1000207002,
+        TimelineSemaphoreSubmitInfo = 1000207003,
+        SemaphoreWaitInfo = 1000207004,
+        // VK_KHR_performance_query
+        PhysicalDevicePerformanceQueryFeaturesKHR = 1000116000,
+        PhysicalDevicePerformanceQueryPropertiesKHR = 1000116001,
+        QueryPoolPerformanceCreateInfoKHR = 1000116002,
+        PerformanceQuerySubmitInfoKHR = 1000116003,
+        AcquireProfilingLockInfoKHR = 1000116004,
+        PerformanceCounterKHR = 1000116005,
+        PerformanceCounterDescriptionKHR = 1000116006,
+        // Vulkan 1.1 (VK_KHR_get_physical_device_properties2)
+        PhysicalDeviceProperties2 = 1000059001,
+        QueueFamilyProperties2 = 1000059005,
+        // Vulkan 1.1 (protected memory)
+        DeviceQueueInfo2 = 1000145003,
+        // Vulkan 1.1 (VK_KHR_external_memory_capabilities)
+        PhysicalDeviceIdProperties = 1000071004,
+        // VK_EXT_pci_bus_info
+        PhysicalDevicePciBusInfoPropertiesEXT = 1000212000,
+        // Vulkan 1.2 (VK_KHR_driver_properties)
+        PhysicalDeviceDriverProperties = 1000196000,
+        // Vulkan 1.3 (VK_EXT_pipeline_creation_feedback)
+        PipelineCreationFeedbackCreateInfo = 1000192000,
+        // VK_KHR_global_priority
+        QueueFamilyGlobalPriorityPropertiesKHR = 1000388001,
+        // VK_KHR_video_queue
+        QueueFamilyVideoPropertiesKHR = 1000023012,
+    }
+}
+
+vk_enum! {
+    /// System-wide priority of a queue relative to other processes' queues
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum VkQueueGlobalPriorityKHR {
+        Low = 128,
+        Medium = 256,
+        High = 512,
+        Realtime = 1024,
+    }
+}
+
+vk_enum! {
+    /// Queue capability flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkQueueFlagBits {
+        Compute = 0x00000002,
+        Transfer = 0x00000004,
+        SparseBinding = 0x00000008,
+    }
+}
+
+vk_enum! {
+    /// Memory property flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkMemoryPropertyFlagBits {
+        DeviceLocal = 0x00000001,
+        HostVisible = 0x00000002,
+        HostCoherent = 0x00000004,
+        HostCached = 0x00000008,
+        LazilyAllocated = 0x00000010,
+    }
+}
+
+vk_enum! {
+    /// Buffer usage flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkBufferUsageFlagBits {
+        TransferSrc = 0x00000001,
+        TransferDst = 0x00000002,
+        UniformBuffer = 0x00000010,
+        StorageBuffer = 0x00000020,
+        IndexBuffer = 0x00000040,
+        VertexBuffer = 0x00000080,
+        IndirectBuffer = 0x00000100,
+    }
+}
+
+vk_enum! {
+    /// Sharing mode
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkSharingMode {
+        Exclusive = 0,
+        Concurrent = 1,
+    }
+}
+
+vk_enum! {
+    /// Pipeline bind point
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPipelineBindPoint {
+        Compute = 1,
+    }
+}
+
+vk_enum! {
+    /// Command buffer level
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkCommandBufferLevel {
+        Primary = 0,
+        Secondary = 1,
+    }
+}
+
+vk_enum! {
+    /// Command buffer usage flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkCommandBufferUsageFlagBits {
+        OneTimeSubmit = 0x00000001,
+        SimultaneousUse = 0x00000004,
+    }
+}
+
+vk_enum! {
+    /// Shader stage flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkShaderStageFlagBits {
+        Compute = 0x00000020,
+    }
+}
+
+vk_enum! {
+    /// Descriptor type (compute-relevant only)
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkDescriptorType {
+        Sampler = 0,
+        CombinedImageSampler = 1,
+        SampledImage = 2,
+        StorageImage = 3,
+        UniformBuffer = 6,
+        StorageBuffer = 7,
+        UniformBufferDynamic = 8,
+        StorageBufferDynamic = 9,
+    }
+}
+
+vk_enum! {
+    /// What a descriptor update template writes
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkDescriptorUpdateTemplateType {
+        DescriptorSet = 0,
+        PushDescriptorsKHR = 1,
+    }
+}
+
+vk_enum! {
+    /// Pipeline stage flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPipelineStageFlagBits {
+        TopOfPipe = 0x00000001,
+        ComputeShader = 0x00000800,
+        BottomOfPipe = 0x00002000,
+        Host = 0x00004000,
+        AllCommands = 0x00010000,
+    }
+}
+
+vk_enum! {
+    /// Access flags
+    #[repr(u32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkAccessFlagBits {
+        IndirectCommandRead = 0x00000001,
+        IndexRead = 0x00000002,
+        VertexAttributeRead = 0x00000004,
+        UniformRead = 0x00000008,
+        InputAttachmentRead = 0x00000010,
+        ShaderRead = 0x00000020,
+        ShaderWrite = 0x00000040,
+        TransferRead = 0x00000800,
+        TransferWrite = 0x00001000,
+        HostRead = 0x00002000,
+        HostWrite = 0x00004000,
+        MemoryRead = 0x00008000,
+        MemoryWrite = 0x00010000,
+    }
+}
+
+vk_enum! {
+    /// Validation layer feature enabled through VkValidationFeaturesEXT
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkValidationFeatureEnableEXT {
+        GpuAssisted = 0,
+        GpuAssistedReserveBindingSlot = 1,
+        BestPractices = 2,
+        DebugPrintf = 3,
+        SynchronizationValidation = 4,
+    }
+}
+
+vk_enum! {
+    /// Validation layer feature disabled through VkValidationFeaturesEXT
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkValidationFeatureDisableEXT {
+        All = 0,
+        Shaders = 1,
+        ThreadSafety = 2,
+        ApiParameters = 3,
+        ObjectLifetimes = 4,
+        CoreChecks = 5,
+        UniqueHandles = 6,
+        ShaderValidationCache = 7,
+    }
+}
+
+vk_enum! {
+    /// Semaphore type
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkSemaphoreType {
+        Binary = 0,
+        Timeline = 1,
+    }
+}
+
+vk_enum! {
+    /// Physical device type
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPhysicalDeviceType {
+        Other = 0,
+        IntegratedGpu = 1,
+        DiscreteGpu = 2,
+        VirtualGpu = 3,
+        Cpu = 4,
+    }
+}
+
+vk_enum! {
+    /// Image formats (storage-image capable subset)
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkFormat {
+        Undefined = 0,
+        R8Unorm = 9,
+        R8G8B8A8Unorm = 37,
+        R16Sfloat = 76,
+        R16G16B16A16Sfloat = 97,
+        R32Uint = 98,
+        R32Sint = 99,
+        R32Sfloat = 100,
+        R32G32Sfloat = 103,
+        R32G32B32A32Uint = 107,
+        R32G32B32A32Sfloat = 109,
+    }
+}
+
+impl VkFormat {
+    /// Size of one texel in bytes (0 for Undefined)
+    pub const fn texel_size(self) -> u32 {
+        match self {
+            VkFormat::Undefined => 0,
+            VkFormat::R8Unorm => 1,
+            VkFormat::R16Sfloat => 2,
+            VkFormat::R8G8B8A8Unorm | VkFormat::R32Uint | VkFormat::R32Sint | VkFormat::R32Sfloat => 4,
+            VkFormat::R16G16B16A16Sfloat | VkFormat::R32G32Sfloat => 8,
+            VkFormat::R32G32B32A32Uint | VkFormat::R32G32B32A32Sfloat => 16,
+        }
+    }
+}
+
+vk_enum! {
+    /// Image dimensionality
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkImageType {
+        Type1D = 0,
+        Type2D = 1,
+        Type3D = 2,
+    }
+}
+
+vk_enum! {
+    /// Image view dimensionality
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkImageViewType {
+        Type1D = 0,
+        Type2D = 1,
+        Type3D = 2,
+    }
+}
+
+vk_enum! {
+    /// Image tiling
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkImageTiling {
+        Optimal = 0,
+        Linear = 1,
+    }
+}
+
+vk_enum! {
+    /// Component swizzle for image views
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkComponentSwizzle {
+        Identity = 0,
+        Zero = 1,
+        One = 2,
+        R = 3,
+        G = 4,
+        B = 5,
+        A = 6,
+    }
+}
+
+vk_enum! {
+    /// Sampler filtering mode
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkFilter {
+        Nearest = 0,
+        Linear = 1,
+    }
+}
+
+vk_enum! {
+    /// Sampler mipmap mode
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkSamplerMipmapMode {
+        Nearest = 0,
+        Linear = 1,
+    }
+}
+
+vk_enum! {
+    /// Sampler addressing mode for out-of-range coordinates
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkSamplerAddressMode {
+        Repeat = 0,
+        MirroredRepeat = 1,
+        ClampToEdge = 2,
+        ClampToBorder = 3,
+    }
+}
+
+vk_enum! {
+    /// Border color used with ClampToBorder addressing
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkBorderColor {
+        FloatTransparentBlack = 0,
+        IntTransparentBlack = 1,
+        FloatOpaqueBlack = 2,
+        IntOpaqueBlack = 3,
+        FloatOpaqueWhite = 4,
+        IntOpaqueWhite = 5,
+    }
+}
+
+vk_enum! {
+    /// Comparison operator for depth-compare samplers
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkCompareOp {
+        Never = 0,
+        Less = 1,
+        Equal = 2,
+        LessOrEqual = 3,
+        Greater = 4,
+        NotEqual = 5,
+        GreaterOrEqual = 6,
+        Always = 7,
+    }
+}
+
+vk_enum! {
+    /// Query pool type
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkQueryType {
+        Occlusion = 0,
+        PipelineStatistics = 1,
+        Timestamp = 2,
+        PerformanceQueryKHR = 1000116000,
+    }
+}
+
+vk_enum! {
+    /// Unit of a performance counter value
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPerformanceCounterUnitKHR {
+        Generic = 0,
+        Percentage = 1,
+        Nanoseconds = 2,
+        Bytes = 3,
+        BytesPerSecond = 4,
+        Kelvin = 5,
+        Watts = 6,
+        Volts = 7,
+        Amps = 8,
+        Hertz = 9,
+        Cycles = 10,
+    }
+}
+
+vk_enum! {
+    /// Granularity a performance counter is collected at
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPerformanceCounterScopeKHR {
+        CommandBuffer = 0,
+        RenderPass = 1,
+        Command = 2,
+    }
+}
+
+vk_enum! {
+    /// Storage type of a performance counter result
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkPerformanceCounterStorageKHR {
+        Int32 = 0,
+        Int64 = 1,
+        Uint32 = 2,
+        Uint64 = 3,
+        Float32 = 4,
+        Float64 = 5,
+    }
+}
@@ -6,9 +6,15 @@ use crate::sys::VkFlags;
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct VkQueueFlags: VkFlags {
+        /// Not used by Kronos; tells dedicated compute families apart
+        const GRAPHICS = 0x00000001;
         const COMPUTE = 0x00000002;
         const TRANSFER = 0x00000004;
         const SPARSE_BINDING = 0x00000008;
+        /// Family can create protected queues, which need protected memory
+        const PROTECTED = 0x00000010;
+        const VIDEO_DECODE_KHR = 0x00000020;
+        const VIDEO_ENCODE_KHR = 0x00000040;
     }
 }
 
@@ -23,6 +29,68 @@ bitflags! {
     }
 }
 
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkMemoryAllocateFlags: VkFlags {
+        const DEVICE_MASK = 0x00000001;
+        const DEVICE_ADDRESS = 0x00000002;
+        const DEVICE_ADDRESS_CAPTURE_REPLAY = 0x00000004;
+    }
+}
+
+bitflags! {
+    /// Kinds of OS handle memory can be exported to or imported from
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkExternalMemoryHandleTypeFlags: VkFlags {
+        const OPAQUE_FD = 0x00000001;
+        const DMA_BUF_EXT = 0x00000200;
+    }
+}
+
+bitflags! {
+    /// Kinds of OS handle a semaphore can be exported to or imported from
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkExternalSemaphoreHandleTypeFlags: VkFlags {
+        const OPAQUE_FD = 0x00000001;
+        const SYNC_FD = 0x00000010;
+    }
+}
+
+bitflags! {
+    /// Severities a debug messenger receives
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkDebugUtilsMessageSeverityFlagsEXT: VkFlags {
+        const VERBOSE = 0x00000001;
+        const INFO = 0x00000010;
+        const WARNING = 0x00000100;
+        const ERROR = 0x00001000;
+    }
+}
+
+bitflags! {
+    /// Kinds of message a debug messenger receives
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkDebugUtilsMessageTypeFlagsEXT: VkFlags {
+        const GENERAL = 0x00000001;
+        const VALIDATION = 0x00000002;
+        const PERFORMANCE = 0x00000004;
+    }
+}
+
+bitflags! {
+    /// How a conditional rendering block reads its predicate
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkConditionalRenderingFlagsEXT: VkFlags {
+        /// Discard the commands when the predicate is non-zero instead
+        const INVERTED = 0x00000001;
+    }
+}
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct VkBufferUsageFlags: VkFlags {
@@ -31,6 +99,7 @@ bitflags! {
         const UNIFORM_BUFFER = 0x00000010;
         const STORAGE_BUFFER = 0x00000020;
         const INDIRECT_BUFFER = 0x00000100;
+        const CONDITIONAL_RENDERING_EXT = 0x00000200;
     }
 }
 
@@ -61,6 +130,7 @@ bitflags! {
 }
 
 bitflags! {
+    #[repr(transparent)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct VkShaderStageFlags: VkFlags {
         const COMPUTE = 0x00000020;
@@ -73,10 +143,13 @@ bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct VkPipelineStageFlags: VkFlags {
         const TOP_OF_PIPE = 0x00000001;
+        const DRAW_INDIRECT = 0x00000002;
         const COMPUTE_SHADER = 0x00000800;
+        const TRANSFER = 0x00001000;
         const BOTTOM_OF_PIPE = 0x00002000;
         const HOST = 0x00004000;
         const ALL_COMMANDS = 0x00010000;
+        const CONDITIONAL_RENDERING_EXT = 0x00040000;
     }
 }
 
@@ -94,6 +167,7 @@ bitflags! {
         const HOST_WRITE = 0x00004000;
         const MEMORY_READ = 0x00008000;
         const MEMORY_WRITE = 0x00010000;
+        const CONDITIONAL_RENDERING_READ_EXT = 0x00100000;
     }
 }
 
@@ -103,6 +177,7 @@ bitflags! {
         const DISABLE_OPTIMIZATION = 0x00000001;
         const ALLOW_DERIVATIVES = 0x00000002;
         const DERIVATIVE = 0x00000004;
+        const DISPATCH_BASE = 0x00000010;
     }
 }
 
@@ -115,6 +190,7 @@ bitflags! {
 }
 
 bitflags! {
+    #[repr(transparent)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct VkDescriptorPoolResetFlags: VkFlags {
         // Reserved for future use
@@ -152,6 +228,61 @@ bitflags! {
     }
 }
 
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkImageUsageFlags: VkFlags {
+        const TRANSFER_SRC = 0x00000001;
+        const TRANSFER_DST = 0x00000002;
+        const SAMPLED = 0x00000004;
+        const STORAGE = 0x00000008;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkImageAspectFlags: VkFlags {
+        const COLOR = 0x00000001;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkSampleCountFlags: VkFlags {
+        const TYPE_1 = 0x00000001;
+    }
+}
+
+bitflags! {
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkQueryResultFlags: VkFlags {
+        const TYPE_64 = 0x00000001;
+        const WAIT = 0x00000002;
+        const WITH_AVAILABILITY = 0x00000004;
+        const PARTIAL = 0x00000008;
+    }
+}
+
+bitflags! {
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkPerformanceCounterDescriptionFlagsKHR: VkFlags {
+        const PERFORMANCE_IMPACTING = 0x00000001;
+        const CONCURRENTLY_IMPACTED = 0x00000002;
+    }
+}
+
+bitflags! {
+    /// What VkPipelineCreationFeedback reports about a pipeline or stage
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VkPipelineCreationFeedbackFlags: VkFlags {
+        const VALID = 0x00000001;
+        const APPLICATION_PIPELINE_CACHE_HIT = 0x00000002;
+        const BASE_PIPELINE_ACCELERATION = 0x00000004;
+    }
+}
+
 // Type aliases for flags that don't have specific bits
 pub type VkInstanceCreateFlags = VkFlags;
 pub type VkDeviceCreateFlags = VkFlags;
@@ -160,8 +291,19 @@ pub type VkMemoryMapFlags = VkFlags;
 pub type VkSemaphoreCreateFlags = VkFlags;
 pub type VkEventCreateFlags = VkFlags;
 pub type VkQueryPoolCreateFlags = VkFlags;
+pub type VkQueryPipelineStatisticFlags = VkFlags;
+pub type VkQueryControlFlags = VkFlags;
+pub type VkAcquireProfilingLockFlagsKHR = VkFlags;
 pub type VkPipelineLayoutCreateFlags = VkFlags;
 pub type VkDescriptorSetLayoutCreateFlags = VkFlags;
+pub type VkDescriptorUpdateTemplateCreateFlags = VkFlags;
+pub type VkImageCreateFlags = VkFlags;
+pub type VkImageViewCreateFlags = VkFlags;
+pub type VkSamplerCreateFlags = VkFlags;
+
+/// VkInstanceCreateFlags bit of VK_KHR_portability_enumeration: the
+/// instance also enumerates portability drivers such as MoltenVK
+pub const VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR: VkInstanceCreateFlags = 0x00000001;
 
 #[cfg(test)]
 mod tests {
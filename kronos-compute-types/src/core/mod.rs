@@ -6,9 +6,11 @@ pub mod flags;
 pub mod compute;
 pub mod thread_safety;
 pub mod timeline;
+pub mod chain;
 
 pub use enums::*;
 pub use structs::*;
 pub use flags::*;
 pub use compute::*;
-pub use timeline::*;
\ No newline at end of file
+pub use timeline::*;
+pub use chain::*;
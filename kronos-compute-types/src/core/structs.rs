@@ -1,7 +1,7 @@
 //! Core structures for Kronos API
 
-use std::ffi::{c_char, c_void};
-use std::ptr;
+use ::core::ffi::{c_char, c_void};
+use ::core::ptr;
 use crate::sys::*;
 use crate::core::enums::*;
 use crate::core::flags::*;
@@ -94,6 +94,17 @@ pub struct VkQueueFamilyProperties {
     pub minImageTransferGranularity: VkExtent3D,
 }
 
+impl Default for VkQueueFamilyProperties {
+    fn default() -> Self {
+        Self {
+            queueFlags: VkQueueFlags::empty(),
+            queueCount: 0,
+            timestampValidBits: 0,
+            minImageTransferGranularity: VkExtent3D::default(),
+        }
+    }
+}
+
 /// Physical device features (compute-relevant only)
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -123,6 +134,52 @@ impl Default for VkPhysicalDeviceFeatures {
     }
 }
 
+/// Number of VkBool32 members in the full Vulkan 1.0 VkPhysicalDeviceFeatures
+///
+/// Drivers always read and write the complete structure, so queries and
+/// device creation go through a buffer of this size.
+pub const VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT: usize = 55;
+
+impl VkPhysicalDeviceFeatures {
+    // Member offsets within the full Vulkan 1.0 layout
+    const ROBUST_BUFFER_ACCESS: usize = 0;
+    const SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT: usize = 31;
+    const SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT: usize = 32;
+    const SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING: usize = 35;
+    const SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING: usize = 36;
+    const SHADER_FLOAT64: usize = 39;
+    const SHADER_INT64: usize = 40;
+    const SHADER_INT16: usize = 41;
+
+    /// Extract the compute subset from the full driver layout
+    pub fn from_full(full: &[VkBool32; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT]) -> Self {
+        Self {
+            robustBufferAccess: full[Self::ROBUST_BUFFER_ACCESS],
+            shaderFloat64: full[Self::SHADER_FLOAT64],
+            shaderInt64: full[Self::SHADER_INT64],
+            shaderInt16: full[Self::SHADER_INT16],
+            shaderStorageBufferArrayDynamicIndexing: full[Self::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING],
+            shaderStorageImageArrayDynamicIndexing: full[Self::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING],
+            shaderStorageImageReadWithoutFormat: full[Self::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT],
+            shaderStorageImageWriteWithoutFormat: full[Self::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT],
+        }
+    }
+
+    /// Expand into the full driver layout, leaving non-compute features disabled
+    pub fn to_full(&self) -> [VkBool32; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT] {
+        let mut full = [VK_FALSE; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT];
+        full[Self::ROBUST_BUFFER_ACCESS] = self.robustBufferAccess;
+        full[Self::SHADER_FLOAT64] = self.shaderFloat64;
+        full[Self::SHADER_INT64] = self.shaderInt64;
+        full[Self::SHADER_INT16] = self.shaderInt16;
+        full[Self::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING] = self.shaderStorageBufferArrayDynamicIndexing;
+        full[Self::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING] = self.shaderStorageImageArrayDynamicIndexing;
+        full[Self::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT] = self.shaderStorageImageReadWithoutFormat;
+        full[Self::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT] = self.shaderStorageImageWriteWithoutFormat;
+        full
+    }
+}
+
 /// Device queue creation info
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -148,6 +205,18 @@ impl Default for VkDeviceQueueCreateInfo {
     }
 }
 
+/// Which queue vkGetDeviceQueue2 returns; `flags` must match the flags
+/// the queue was created with
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkDeviceQueueInfo2 {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkDeviceQueueCreateFlags,
+    pub queueFamilyIndex: u32,
+    pub queueIndex: u32,
+}
+
 /// Device creation info
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -209,7 +278,7 @@ pub struct VkPhysicalDeviceMemoryProperties {
 
 impl Default for VkPhysicalDeviceMemoryProperties {
     fn default() -> Self {
-        unsafe { std::mem::zeroed() }
+        unsafe { ::core::mem::zeroed() }
     }
 }
 
@@ -244,6 +313,30 @@ impl Default for VkMemoryAllocateInfo {
     }
 }
 
+/// Allocation flags, chained into VkMemoryAllocateInfo
+///
+/// `DEVICE_ADDRESS` is required for memory bound to buffers whose device
+/// address is queried.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMemoryAllocateFlagsInfo {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub flags: VkMemoryAllocateFlags,
+    pub deviceMask: u32,
+}
+
+impl Default for VkMemoryAllocateFlagsInfo {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::MemoryAllocateFlagsInfo,
+            pNext: ptr::null(),
+            flags: VkMemoryAllocateFlags::empty(),
+            deviceMask: 0,
+        }
+    }
+}
+
 /// Memory requirements
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -481,6 +574,17 @@ pub struct VkSubmitInfo {
     pub pSignalSemaphores: *const VkSemaphore,
 }
 
+/// Range of mapped memory to flush or invalidate
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkMappedMemoryRange {
+    pub sType: VkStructureType,
+    pub pNext: *const c_void,
+    pub memory: VkDeviceMemory,
+    pub offset: VkDeviceSize,
+    pub size: VkDeviceSize,
+}
+
 /// Buffer copy region
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -517,9 +621,36 @@ pub struct VkPhysicalDeviceLimits {
     // Add more fields as needed...
 }
 
+/// Size in bytes of the full Vulkan 1.0 VkPhysicalDeviceProperties
+///
+/// Limits outside the simplified structure are read from a buffer of this
+/// size, like the features query.
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE: usize = 824;
+
+/// Byte offset of `limits.timestampPeriod` (f32) in the full properties layout
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_TIMESTAMP_PERIOD_OFFSET: usize = 720;
+
+/// Byte offset of `limits.minStorageBufferOffsetAlignment` (VkDeviceSize)
+/// in the full properties layout
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT_OFFSET: usize = 624;
+
+/// Byte offset of `limits.minUniformBufferOffsetAlignment` (VkDeviceSize)
+/// in the full properties layout
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT_OFFSET: usize = 616;
+
+/// Byte offset of `limits.maxPushConstantsSize` (u32) in the full
+/// properties layout
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_MAX_PUSH_CONSTANTS_SIZE_OFFSET: usize = 328;
+
+/// Byte offset of `limits.maxComputeSharedMemorySize` (u32) in the full
+/// properties layout
+///
+/// The simplified limits structure puts the field elsewhere.
+pub const VK_PHYSICAL_DEVICE_PROPERTIES_MAX_COMPUTE_SHARED_MEMORY_SIZE_OFFSET: usize = 512;
+
 impl Default for VkPhysicalDeviceLimits {
     fn default() -> Self {
-        unsafe { std::mem::zeroed() }
+        unsafe { ::core::mem::zeroed() }
     }
 }
 
@@ -536,7 +667,7 @@ pub struct VkPhysicalDeviceSparseProperties {
 
 impl Default for VkPhysicalDeviceSparseProperties {
     fn default() -> Self {
-        unsafe { std::mem::zeroed() }
+        unsafe { ::core::mem::zeroed() }
     }
 }
 
@@ -548,7 +679,9 @@ pub struct VkPhysicalDeviceProperties {
     pub driverVersion: u32,
     pub vendorID: u32,
     pub deviceID: u32,
-    pub deviceType: VkPhysicalDeviceType,
+    /// Raw [`VkPhysicalDeviceType`], as drivers may report types this crate
+    /// does not know; read it with [`device_type`](Self::device_type)
+    pub deviceType: i32,
     pub deviceName: [c_char; VK_MAX_PHYSICAL_DEVICE_NAME_SIZE],
     pub pipelineCacheUUID: [u8; VK_UUID_SIZE],
     pub limits: VkPhysicalDeviceLimits,
@@ -557,7 +690,14 @@ pub struct VkPhysicalDeviceProperties {
 
 impl Default for VkPhysicalDeviceProperties {
     fn default() -> Self {
-        unsafe { std::mem::zeroed() }
+        unsafe { ::core::mem::zeroed() }
+    }
+}
+
+impl VkPhysicalDeviceProperties {
+    /// Type of the device, with types this crate does not know as `Other`
+    pub fn device_type(&self) -> VkPhysicalDeviceType {
+        VkPhysicalDeviceType::try_from(self.deviceType).unwrap_or(VkPhysicalDeviceType::Other)
     }
 }
 
@@ -658,4 +798,22 @@ mod tests {
         assert_eq!(barrier.dstQueueFamilyIndex, VK_QUEUE_FAMILY_IGNORED);
         assert_eq!(barrier.size, VK_WHOLE_SIZE);
     }
+    
+    #[test]
+    fn test_physical_device_features_full_layout_roundtrip() {
+        let features = VkPhysicalDeviceFeatures {
+            shaderInt64: VK_TRUE,
+            shaderStorageImageWriteWithoutFormat: VK_TRUE,
+            ..Default::default()
+        };
+        let full = features.to_full();
+        assert_eq!(full.iter().filter(|&&b| b == VK_TRUE).count(), 2);
+        assert_eq!(full[40], VK_TRUE);
+        assert_eq!(full[32], VK_TRUE);
+        
+        let back = VkPhysicalDeviceFeatures::from_full(&full);
+        assert_eq!(back.shaderInt64, VK_TRUE);
+        assert_eq!(back.shaderStorageImageWriteWithoutFormat, VK_TRUE);
+        assert_eq!(back.shaderFloat64, VK_FALSE);
+    }
 }
\ No newline at end of file
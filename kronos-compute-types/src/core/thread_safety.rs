@@ -138,3 +138,36 @@ unsafe impl Sync for VkWriteDescriptorSet {}
 unsafe impl Send for VkCopyDescriptorSet {}
 unsafe impl Sync for VkCopyDescriptorSet {}
 
+// Image structures
+unsafe impl Send for VkImageCreateInfo {}
+unsafe impl Sync for VkImageCreateInfo {}
+
+unsafe impl Send for VkImageViewCreateInfo {}
+unsafe impl Sync for VkImageViewCreateInfo {}
+
+unsafe impl Send for VkImageMemoryBarrier {}
+unsafe impl Sync for VkImageMemoryBarrier {}
+
+unsafe impl Send for VkSamplerCreateInfo {}
+unsafe impl Sync for VkSamplerCreateInfo {}
+
+unsafe impl Send for VkQueryPoolCreateInfo {}
+unsafe impl Sync for VkQueryPoolCreateInfo {}
+
+unsafe impl Send for VkPhysicalDevicePerformanceQueryFeaturesKHR {}
+unsafe impl Sync for VkPhysicalDevicePerformanceQueryFeaturesKHR {}
+
+unsafe impl Send for VkPerformanceCounterKHR {}
+unsafe impl Sync for VkPerformanceCounterKHR {}
+
+unsafe impl Send for VkPerformanceCounterDescriptionKHR {}
+unsafe impl Sync for VkPerformanceCounterDescriptionKHR {}
+
+unsafe impl Send for VkQueryPoolPerformanceCreateInfoKHR {}
+unsafe impl Sync for VkQueryPoolPerformanceCreateInfoKHR {}
+
+unsafe impl Send for VkAcquireProfilingLockInfoKHR {}
+unsafe impl Sync for VkAcquireProfilingLockInfoKHR {}
+
+unsafe impl Send for VkPerformanceQuerySubmitInfoKHR {}
+unsafe impl Sync for VkPerformanceQuerySubmitInfoKHR {}
@@ -1,11 +1,33 @@
 //! Timeline semaphore structures for Kronos
 
-use std::ffi::c_void;
-use std::ptr;
+use ::core::ffi::c_void;
+use ::core::ptr;
 use crate::sys::*;
 use crate::core::enums::*;
 use crate::core::flags::*;
 
+/// Name of the VK_KHR_timeline_semaphore device extension, core in Vulkan 1.2
+pub const VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME: &str = "VK_KHR_timeline_semaphore";
+
+/// Timeline semaphore feature, chained into VkDeviceCreateInfo to enable it
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VkPhysicalDeviceTimelineSemaphoreFeatures {
+    pub sType: VkStructureType,
+    pub pNext: *mut c_void,
+    pub timelineSemaphore: VkBool32,
+}
+
+impl Default for VkPhysicalDeviceTimelineSemaphoreFeatures {
+    fn default() -> Self {
+        Self {
+            sType: VkStructureType::PhysicalDeviceTimelineSemaphoreFeatures,
+            pNext: ptr::null_mut(),
+            timelineSemaphore: VK_FALSE,
+        }
+    }
+}
+
 /// Semaphore type create info (for timeline semaphores)
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,14 @@
+//! Vulkan compute structures, flags and handles used by kronos-compute
+//!
+//! The `#[repr(C)]` layouts kronos-compute passes to drivers, without the
+//! loader, the safe API or any dependency on `std` or libc. Firmware-side
+//! tooling and code generators can share the exact layouts by depending on
+//! this crate alone; kronos-compute re-exports both modules unchanged as
+//! `kronos_compute::core` and `kronos_compute::sys`.
+
+#![cfg_attr(not(test), no_std)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+pub mod core;
+pub mod sys;
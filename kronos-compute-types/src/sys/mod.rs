@@ -2,8 +2,9 @@
 //! 
 //! Type-safe handle system with zero overhead
 
-use std::marker::PhantomData;
-use std::fmt;
+use ::core::marker::PhantomData;
+use ::core::fmt;
+use ::core::sync::atomic::{AtomicPtr, Ordering};
 
 /// Opaque handle type with phantom data for type safety
 #[repr(transparent)]
@@ -38,8 +39,45 @@ impl<T> Handle<T> {
     }
 }
 
-impl<T> fmt::Debug for Handle<T> {
+/// Marker type of a handle, naming the Vulkan object it refers to
+pub trait HandleType {
+    /// Vulkan type name, e.g. `VkBuffer`
+    const NAME: &'static str;
+}
+
+impl<T: HandleType> Handle<T> {
+    /// Vulkan type name of the handle
+    pub const fn type_name(&self) -> &'static str {
+        T::NAME
+    }
+}
+
+/// Writes a richer `Debug` description of the handle of type `name` with
+/// value `raw`, or returns `None` to fall back to the raw value
+pub type HandleFormatter = fn(name: &'static str, raw: u64, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result>;
+
+static HANDLE_FORMATTER: AtomicPtr<()> = AtomicPtr::new(::core::ptr::null_mut());
+
+/// Install the formatter used by the `Debug` output of every handle
+///
+/// kronos-compute installs one with its `object-registry` feature, so that
+/// handles owned by a safe-API object print as `VkBuffer(#42, device #1)`.
+pub fn set_handle_formatter(formatter: HandleFormatter) {
+    HANDLE_FORMATTER.store(formatter as *mut (), Ordering::Release);
+}
+
+/// Handles print through the installed [`HandleFormatter`], if any, and as
+/// their raw value otherwise.
+impl<T: HandleType> fmt::Debug for Handle<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatter = HANDLE_FORMATTER.load(Ordering::Acquire);
+        if !formatter.is_null() {
+            // Only ever stored from a `HandleFormatter` above
+            let formatter = unsafe { ::core::mem::transmute::<*mut (), HandleFormatter>(formatter) };
+            if let Some(result) = formatter(T::NAME, self.raw, f) {
+                return result;
+            }
+        }
         f.debug_struct("Handle")
             .field("raw", &self.raw)
             .field("_marker", &self._marker)
@@ -94,7 +132,50 @@ pub enum PipelineCacheT {}
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SamplerT {}
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageT {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ImageViewT {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryPoolT {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DescriptorUpdateTemplateT {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugUtilsMessengerEXTT {}
+
+macro_rules! handle_types {
+    ($($ty:ident => $name:literal),* $(,)?) => {
+        $(impl HandleType for $ty {
+            const NAME: &'static str = $name;
+        })*
+    };
+}
+
+handle_types! {
+    InstanceT => "VkInstance",
+    PhysicalDeviceT => "VkPhysicalDevice",
+    DeviceT => "VkDevice",
+    QueueT => "VkQueue",
+    CommandBufferT => "VkCommandBuffer",
+    BufferT => "VkBuffer",
+    DeviceMemoryT => "VkDeviceMemory",
+    PipelineT => "VkPipeline",
+    PipelineLayoutT => "VkPipelineLayout",
+    ShaderModuleT => "VkShaderModule",
+    DescriptorSetT => "VkDescriptorSet",
+    DescriptorSetLayoutT => "VkDescriptorSetLayout",
+    DescriptorPoolT => "VkDescriptorPool",
+    CommandPoolT => "VkCommandPool",
+    FenceT => "VkFence",
+    SemaphoreT => "VkSemaphore",
+    EventT => "VkEvent",
+    PipelineCacheT => "VkPipelineCache",
+    SamplerT => "VkSampler",
+    ImageT => "VkImage",
+    ImageViewT => "VkImageView",
+    QueryPoolT => "VkQueryPool",
+    DescriptorUpdateTemplateT => "VkDescriptorUpdateTemplate",
+    DebugUtilsMessengerEXTT => "VkDebugUtilsMessengerEXT",
+}
 
 // Type aliases for handles
 pub type VkInstance = Handle<InstanceT>;
@@ -116,7 +197,11 @@ pub type VkSemaphore = Handle<SemaphoreT>;
 pub type VkEvent = Handle<EventT>;
 pub type VkPipelineCache = Handle<PipelineCacheT>;
 pub type VkSampler = Handle<SamplerT>;
+pub type VkImage = Handle<ImageT>;
 pub type VkImageView = Handle<ImageViewT>;
+pub type VkQueryPool = Handle<QueryPoolT>;
+pub type VkDescriptorUpdateTemplate = Handle<DescriptorUpdateTemplateT>;
+pub type VkDebugUtilsMessengerEXT = Handle<DebugUtilsMessengerEXTT>;
 
 // Basic types
 pub type VkBool32 = u32;
@@ -128,12 +213,18 @@ pub const VK_TRUE: VkBool32 = 1;
 pub const VK_FALSE: VkBool32 = 0;
 pub const VK_WHOLE_SIZE: VkDeviceSize = !0;
 pub const VK_QUEUE_FAMILY_IGNORED: u32 = !0;
+pub const VK_QUEUE_FAMILY_EXTERNAL: u32 = !0 - 1;
 
 // Size limits  
 pub const VK_MAX_PHYSICAL_DEVICE_NAME_SIZE: usize = 256;
 pub const VK_UUID_SIZE: usize = 16;
+pub const VK_LUID_SIZE: usize = 8;
 pub const VK_MAX_MEMORY_HEAPS: usize = 16;
 pub const VK_MAX_MEMORY_TYPES: usize = 32;
+pub const VK_MAX_EXTENSION_NAME_SIZE: usize = 256;
+pub const VK_MAX_DESCRIPTION_SIZE: usize = 256;
+pub const VK_MAX_DRIVER_NAME_SIZE: usize = 256;
+pub const VK_MAX_DRIVER_INFO_SIZE: usize = 256;
 
 // API version
 pub const VK_API_VERSION_1_0: u32 = (1 << 22) | (0 << 12) | 0;
@@ -197,4 +288,10 @@ mod tests {
         let debug_str = format!("{:?}", handle);
         assert!(debug_str.contains("raw: 999"));
     }
+
+    #[test]
+    fn test_handle_type_name() {
+        assert_eq!(VkBuffer::NULL.type_name(), "VkBuffer");
+        assert_eq!(VkDescriptorSetLayout::NULL.type_name(), "VkDescriptorSetLayout");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+/* Kronos Compute - flat C API over the safe layer */
+
+#ifndef KRONOS_CAPI_H
+#define KRONOS_CAPI_H
+
+/* Warning: this file is autogenerated by cbindgen. Don't modify manually. */
+
+#include <stdint.h>
+#include <stddef.h>
+
+/**
+ * Result of a `kronos_*` call
+ */
+typedef enum KronosStatus {
+  KRONOS_SUCCESS = 0,
+  /**
+   * A handle or pointer was null, or a size did not fit
+   */
+  KRONOS_ERROR_INVALID_ARGUMENT = 1,
+  KRONOS_ERROR_INITIALIZATION = 2,
+  KRONOS_ERROR_DEVICE_NOT_FOUND = 3,
+  KRONOS_ERROR_SHADER = 4,
+  KRONOS_ERROR_BUFFER = 5,
+  KRONOS_ERROR_EXECUTION = 6,
+  KRONOS_ERROR_SYNCHRONIZATION = 7,
+  KRONOS_ERROR_UNSUPPORTED = 8,
+  KRONOS_ERROR_VULKAN = 9,
+  /**
+   * Kronos panicked; the context may be unusable
+   */
+  KRONOS_ERROR_INTERNAL = 10,
+} KronosStatus;
+
+/**
+ * Opaque handle to a [`Buffer`]
+ */
+typedef struct KronosBuffer KronosBuffer;
+
+/**
+ * Opaque handle to a [`ComputeContext`]
+ */
+typedef struct KronosContext KronosContext;
+
+/**
+ * Opaque handle to a [`Pipeline`]
+ */
+typedef struct KronosPipeline KronosPipeline;
+
+/**
+ * Create a context on the best available device
+ *
+ * # Safety
+ *
+ * `out` must be null or valid for writes.
+ */
+enum KronosStatus kronos_ctx_create(struct KronosContext **out);
+
+/**
+ * Destroy a context; buffers and pipelines created from it stay usable
+ * until destroyed themselves
+ *
+ * # Safety
+ *
+ * `ctx` must be null or a handle from [`kronos_ctx_create`] not yet
+ * destroyed.
+ */
+void kronos_ctx_destroy(struct KronosContext *ctx);
+
+/**
+ * Create a device-local storage buffer of `size` bytes, filled from
+ * `data` unless it is null
+ *
+ * # Safety
+ *
+ * `ctx` must be null or a live context, `data` null or valid for `size`
+ * bytes of reads, and `out` null or valid for writes.
+ */
+enum KronosStatus kronos_buffer_create(const struct KronosContext *ctx,
+                                       const void *data,
+                                       size_t size,
+                                       struct KronosBuffer **out);
+
+/**
+ * Size of a buffer in bytes; 0 for a null handle
+ *
+ * # Safety
+ *
+ * `buffer` must be null or a live buffer.
+ */
+size_t kronos_buffer_size(const struct KronosBuffer *buffer);
+
+/**
+ * Write `size` bytes from `data` to the start of a buffer
+ *
+ * # Safety
+ *
+ * `buffer` must be null or a live buffer not in use by another thread,
+ * and `data` null or valid for `size` bytes of reads.
+ */
+enum KronosStatus kronos_buffer_write(struct KronosBuffer *buffer, const void *data, size_t size);
+
+/**
+ * Read the first `size` bytes of a buffer into `out`
+ *
+ * # Safety
+ *
+ * `buffer` must be null or a live buffer, and `out` null or valid for
+ * `size` bytes of writes.
+ */
+enum KronosStatus kronos_buffer_read(const struct KronosBuffer *buffer, void *out, size_t size);
+
+/**
+ * Destroy a buffer
+ *
+ * # Safety
+ *
+ * `buffer` must be null or a handle from [`kronos_buffer_create`] not yet
+ * destroyed.
+ */
+void kronos_buffer_destroy(struct KronosBuffer *buffer);
+
+/**
+ * Create a compute pipeline from `code_size` bytes of SPIR-V
+ *
+ * Bindings and push constants are taken from the shader's reflection.
+ *
+ * # Safety
+ *
+ * `ctx` must be null or a live context, `code` null or valid for
+ * `code_size` bytes of reads, and `out` null or valid for writes.
+ */
+enum KronosStatus kronos_pipeline_create(const struct KronosContext *ctx,
+                                         const uint32_t *code,
+                                         size_t code_size,
+                                         struct KronosPipeline **out);
+
+/**
+ * Destroy a pipeline
+ *
+ * # Safety
+ *
+ * `pipeline` must be null or a handle from [`kronos_pipeline_create`] not
+ * yet destroyed.
+ */
+void kronos_pipeline_destroy(struct KronosPipeline *pipeline);
+
+/**
+ * Dispatch `x * y * z` workgroups of a pipeline and wait for them
+ *
+ * `buffers[i]` is bound to binding `i`; `push_constants` may be null when
+ * `push_constant_size` is 0.
+ *
+ * # Safety
+ *
+ * `ctx` and `pipeline` must be null or live handles, `buffers` null or
+ * valid for `buffer_count` reads of live buffers, and `push_constants`
+ * null or valid for `push_constant_size` bytes of reads.
+ */
+enum KronosStatus kronos_dispatch(const struct KronosContext *ctx,
+                                  const struct KronosPipeline *pipeline,
+                                  const struct KronosBuffer *const *buffers,
+                                  uint32_t buffer_count,
+                                  const void *push_constants,
+                                  size_t push_constant_size,
+                                  uint32_t x,
+                                  uint32_t y,
+                                  uint32_t z);
+
+/**
+ * Message of the last failed call on the calling thread, or null if none
+ * has failed
+ *
+ * The string stays valid until the next failing call on the thread.
+ */
+const char *kronos_last_error(void);
+
+#endif /* KRONOS_CAPI_H */
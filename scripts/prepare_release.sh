@@ -39,6 +39,7 @@ if command -v cbindgen &> /dev/null; then
 else
     echo "Warning: cbindgen not installed, skipping header generation"
 fi
+KRONOS_CAPI_HEADER="$PWD/kronos_capi.h" cargo build --features capi --quiet || { echo "C API header generation failed!"; exit 1; }
 
 # Update version in files
 echo "Updating version to $VERSION..."
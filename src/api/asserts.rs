@@ -0,0 +1,184 @@
+//! Device-side assertions reported through a flags buffer
+//!
+//! Kernels have no way to fail loudly: an out-of-range index or a NaN just
+//! produces wrong results. With [`DeviceAsserts`], a kernel binds a small
+//! buffer and records the first failed check in it:
+//!
+//! ```glsl
+//! layout(set = 0, binding = 3) buffer KronosAsserts { uint code; uint value; } kronos_assert;
+//!
+//! #define KRONOS_ASSERT(cond, c, v) \
+//!     if (!(cond) && atomicCompSwap(kronos_assert.code, 0u, (c)) == 0u) { kronos_assert.value = (v); }
+//! ```
+//!
+//! Codes are nonzero and mapped to messages with [`DeviceAsserts::register`];
+//! `value` carries whatever helps diagnose the failure, such as the
+//! offending index. A dispatch bound with [`CommandBuilder::check_asserts`]
+//! reads and clears the buffer once it completes: `execute` returns the
+//! failure as an error, and failures of submitted dispatches are returned by
+//! the next [`DeviceAsserts::check`].
+//!
+//! Only the first failure of a dispatch is kept. Dispatches sharing a
+//! `DeviceAsserts` should not run concurrently, or a failure may be
+//! attributed to the wrong one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use crate::*;
+use crate::implementation::{vkMapMemory, vkUnmapMemory};
+use super::*;
+
+/// Size of the flags buffer: the code and the value
+const FLAGS_SIZE: usize = 8;
+
+/// A failed device-side assertion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertFailure {
+    /// Code the kernel wrote
+    pub code: u32,
+    /// Value the kernel wrote alongside it
+    pub value: u32,
+    /// Message registered for the code
+    pub message: Option<String>,
+}
+
+impl fmt::Display for AssertFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{} (code {}, value {})", message, self.code, self.value),
+            None => write!(f, "unregistered code {} (value {})", self.code, self.value),
+        }
+    }
+}
+
+impl From<AssertFailure> for KronosError {
+    fn from(failure: AssertFailure) -> Self {
+        KronosError::CommandExecutionFailed(format!("Kernel assertion failed: {}", failure))
+    }
+}
+
+pub(super) struct AssertShared {
+    buffer: Buffer,
+    flags: *mut u32,
+    messages: Mutex<HashMap<u32, String>>,
+    /// Failures of submitted dispatches not yet returned by `check`
+    failures: Mutex<Vec<AssertFailure>>,
+}
+
+// The flags are only touched by `collect`, under the failures lock
+unsafe impl Send for AssertShared {}
+unsafe impl Sync for AssertShared {}
+
+impl AssertShared {
+    /// Read and clear the flags, returning the failure they record
+    pub(super) fn take(&self) -> Option<AssertFailure> {
+        let _failures = self.failures.lock().unwrap();
+        let (code, value) = unsafe {
+            let code = ptr::read_volatile(self.flags);
+            let value = ptr::read_volatile(self.flags.add(1));
+            ptr::write_volatile(self.flags, 0);
+            ptr::write_volatile(self.flags.add(1), 0);
+            (code, value)
+        };
+        (code != 0).then(|| AssertFailure {
+            code,
+            value,
+            message: self.messages.lock().unwrap().get(&code).cloned(),
+        })
+    }
+
+    /// Keep the failure of a submitted dispatch for the next `check`
+    pub(super) fn collect(&self) {
+        if let Some(failure) = self.take() {
+            self.failures.lock().unwrap().push(failure);
+        }
+    }
+}
+
+impl Drop for AssertShared {
+    fn drop(&mut self) {
+        let device = self.buffer.context.with_inner(|inner| inner.device);
+        unsafe { vkUnmapMemory(device, self.buffer.memory) };
+    }
+}
+
+/// Flags buffer that kernels report failed assertions in
+///
+/// Created by [`ComputeContext::device_asserts`]. Clones share the buffer
+/// and the registered messages.
+#[derive(Clone)]
+pub struct DeviceAsserts {
+    pub(super) shared: Arc<AssertShared>,
+}
+
+impl DeviceAsserts {
+    /// Describe the failure reported with `code`
+    pub fn register(&self, code: u32, message: impl Into<String>) -> &Self {
+        self.shared.messages.lock().unwrap().insert(code, message.into());
+        self
+    }
+
+    /// The flags buffer, for binding by hand
+    pub fn buffer(&self) -> &Buffer {
+        &self.shared.buffer
+    }
+
+    /// Return the oldest failure of a submitted dispatch as an error
+    ///
+    /// Also picks up a failure left in the buffer by a dispatch not bound
+    /// with [`CommandBuilder::check_asserts`].
+    pub fn check(&self) -> Result<()> {
+        self.shared.collect();
+        let mut failures = self.shared.failures.lock().unwrap();
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(failures.remove(0).into())
+    }
+}
+
+impl ComputeContext {
+    /// Create a flags buffer for device-side assertions
+    pub fn device_asserts(&self) -> Result<DeviceAsserts> {
+        let buffer = unsafe {
+            self.create_buffer_with_memory(
+                FLAGS_SIZE,
+                BufferUsage::STORAGE,
+                &[VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT],
+                "asserts",
+            )?
+        };
+        let device = self.with_inner(|inner| inner.device);
+        let mut mapped = ptr::null_mut();
+        let result = unsafe { vkMapMemory(device, buffer.memory, 0, FLAGS_SIZE as VkDeviceSize, 0, &mut mapped) };
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        let flags = mapped as *mut u32;
+        unsafe { ptr::write_bytes(flags, 0, 2) };
+        Ok(DeviceAsserts {
+            shared: Arc::new(AssertShared {
+                buffer,
+                flags,
+                messages: Mutex::default(),
+                failures: Mutex::default(),
+            }),
+        })
+    }
+}
+
+impl CommandBuilder {
+    /// Bind the flags of `asserts` to `binding` and check them once the
+    /// dispatch completes
+    ///
+    /// [`execute`](Self::execute) returns a failed assertion as an error;
+    /// after [`submit`](Self::submit), it is returned by
+    /// [`DeviceAsserts::check`].
+    pub fn check_asserts(mut self, binding: u32, asserts: &DeviceAsserts) -> Self {
+        self = self.bind_buffer(binding, &asserts.shared.buffer);
+        self.asserts = Some(asserts.shared.clone());
+        self
+    }
+}
@@ -11,17 +11,22 @@ use crate::implementation::{
     vkBindBufferMemory, vkAllocateMemory, vkFreeMemory,
     vkMapMemory, vkUnmapMemory, vkCmdCopyBuffer,
 };
+use crate::implementation::pool_allocator::{self, TagUsage, UNTAGGED};
 
 // If implementation feature is not enabled, these functions must come from
 // linking to an external Vulkan library
 use std::marker::PhantomData;
 use std::ptr;
 use std::slice;
+use std::sync::{Arc, Mutex};
+use super::deferred::LastUse;
+use super::host_copy;
+use super::plugin::Plugin;
 
 /// Usage flags for buffers
 #[derive(Debug, Clone, Copy)]
 pub struct BufferUsage {
-    flags: VkBufferUsageFlags,
+    pub(super) flags: VkBufferUsageFlags,
 }
 
 impl BufferUsage {
@@ -47,7 +52,7 @@ impl std::ops::BitOr for BufferUsage {
     
     fn bitor(self, rhs: Self) -> Self::Output {
         Self {
-            flags: VkBufferUsageFlags::from_bits_truncate(self.flags.bits() | rhs.flags.bits())
+            flags: self.flags | rhs.flags
         }
     }
 }
@@ -55,21 +60,194 @@ impl std::ops::BitOr for BufferUsage {
 /// A GPU buffer with automatic memory management
 /// 
 /// Buffers are automatically freed when dropped and use the
-/// pool allocator for efficient memory management.
+/// pool allocator for efficient memory management. A buffer may be dropped
+/// while a submitted dispatch still uses it; it is then destroyed once that
+/// dispatch completes.
 pub struct Buffer {
     pub(super) context: ComputeContext,
-    pub(super) buffer: VkBuffer,
+    pub(super) buffer: Owned<VkBuffer>,
     pub(super) memory: VkDeviceMemory,
     pub(super) size: usize,
     pub(super) usage: BufferUsage,
+    pub(super) memory_flags: VkMemoryPropertyFlags,
+    /// Accounting tag and the bytes accounted under it
+    pub(super) tag: String,
+    pub(super) allocation_size: VkDeviceSize,
+    /// Handle types the memory was allocated exportable as
+    pub(super) export_handle_types: VkExternalMemoryHandleTypeFlags,
+    /// Plugin that allocated the memory, and frees it
+    pub(super) allocator: Option<Arc<Plugin>>,
+    /// Last submission that used the buffer
+    pub(super) last_use: Arc<LastUse>,
+    /// Accesses outside Kronos the next dispatch must wait for
+    pub(super) external: Arc<ExternalAccess>,
     pub(super) _marker: PhantomData<*const u8>,
 }
 
+/// Stages and writes of accesses made outside Kronos, declared with
+/// [`Buffer::declare_external_write`] and [`Buffer::declare_external_read`]
+#[derive(Debug, Default)]
+pub(super) struct ExternalAccess {
+    pending: Mutex<Option<(VkPipelineStageFlags, VkAccessFlags)>>,
+}
+
+impl ExternalAccess {
+    fn declare(&self, stage: VkPipelineStageFlags, access: VkAccessFlags) {
+        let mut pending = self.pending.lock().unwrap();
+        let (stages, accesses) = pending.unwrap_or((VkPipelineStageFlags::empty(), VkAccessFlags::empty()));
+        *pending = Some((stages | stage, accesses | access));
+    }
+
+    /// The declared accesses, left in place
+    pub(super) fn pending(&self) -> Option<(VkPipelineStageFlags, VkAccessFlags)> {
+        *self.pending.lock().unwrap()
+    }
+
+    /// The declared accesses, cleared once a dispatch waits for them
+    pub(super) fn take(&self) -> Option<(VkPipelineStageFlags, VkAccessFlags)> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
 // Send + Sync for thread safety
 unsafe impl Send for Buffer {}
 unsafe impl Sync for Buffer {}
 
+/// `VK_MEMORY_HEAP_DEVICE_LOCAL_BIT`
+const MEMORY_HEAP_DEVICE_LOCAL: VkFlags = 0x1;
+
+/// Memory types a CPU can write into directly while the GPU reads at full speed
+const DIRECT_MEMORY: VkMemoryPropertyFlags = VkMemoryPropertyFlags::from_bits_truncate(
+    VkMemoryPropertyFlags::DEVICE_LOCAL.bits()
+        | VkMemoryPropertyFlags::HOST_VISIBLE.bits()
+        | VkMemoryPropertyFlags::HOST_COHERENT.bits(),
+);
+
+/// Properties of one memory heap on the selected device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryHeapInfo {
+    /// Heap index
+    pub index: u32,
+    /// Heap size in bytes
+    pub size: u64,
+    /// Heap lives in device (VRAM) memory
+    pub device_local: bool,
+    /// Heap has a memory type the host can map
+    pub host_visible: bool,
+}
+
+impl MemoryHeapInfo {
+    /// Device-local and host-visible, i.e. a resizable BAR window into VRAM
+    pub fn is_direct(&self) -> bool {
+        self.device_local && self.host_visible
+    }
+}
+
+/// Live device memory by allocation tag
+///
+/// Obtained from [`ComputeContext::memory_report`]. Buffers count under the
+/// tag given to [`Buffer::new_tagged`], other buffers and pool allocations
+/// without a tag under `"untagged"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Usage per tag on the context's device, largest first
+    pub tags: Vec<(String, TagUsage)>,
+    /// Peak of live accounted memory across all devices of the process
+    pub high_water_mark: u64,
+    /// Cap set with [`ContextBuilder::memory_limit_bytes`]
+    pub limit: Option<u64>,
+}
+
+impl MemoryReport {
+    /// Usage under one tag
+    pub fn tag(&self, tag: &str) -> Option<TagUsage> {
+        self.tags.iter().find(|(name, _)| name == tag).map(|(_, usage)| *usage)
+    }
+    
+    /// Usage summed over all tags
+    pub fn total(&self) -> TagUsage {
+        self.tags.iter().fold(TagUsage::default(), |total, (_, usage)| TagUsage {
+            bytes: total.bytes + usage.bytes,
+            count: total.count + usage.count,
+        })
+    }
+}
+
+/// A CPU mapping of a buffer's memory, unmapped on drop
+///
+/// Obtained from [`Buffer::try_map_direct`]. The memory is host-coherent, so
+/// writes become visible to later submissions without explicit flushes.
+pub struct DirectMapping<'a> {
+    buffer: &'a mut Buffer,
+    ptr: *mut u8,
+}
+
+/// A byte range of a buffer, from [`Buffer::slice`]
+///
+/// Lets one buffer back several logical arrays: slices bind with
+/// [`CommandBuilder::bind_slice`] and copy with
+/// [`ComputeContext::copy_slice`].
+#[derive(Clone, Copy)]
+pub struct BufferSlice<'a> {
+    pub(super) buffer: &'a Buffer,
+    pub(super) offset: usize,
+    pub(super) len: usize,
+}
+
+impl<'a> BufferSlice<'a> {
+    /// The buffer the slice is part of
+    pub fn buffer(&self) -> &'a Buffer {
+        self.buffer
+    }
+
+    /// Offset of the slice in the buffer, in bytes
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Length of the slice in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the slice is empty; [`Buffer::slice`] never returns one
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the slice covers the whole buffer
+    pub fn is_whole(&self) -> bool {
+        self.offset == 0 && self.len == self.buffer.size
+    }
+
+    fn overlaps(&self, other: &BufferSlice<'_>) -> bool {
+        ptr::eq(self.buffer, other.buffer)
+            && self.offset < other.offset + other.len
+            && other.offset < self.offset + self.len
+    }
+}
+
+impl<'a> From<&'a Buffer> for BufferSlice<'a> {
+    fn from(buffer: &'a Buffer) -> Self {
+        Self { buffer, offset: 0, len: buffer.size }
+    }
+}
+
 impl Buffer {
+    /// Create an uninitialized buffer accounted under `tag`
+    ///
+    /// Tags group buffers in [`ComputeContext::memory_report`], e.g. by
+    /// model layer or role.
+    pub fn new_tagged(ctx: &ComputeContext, size: usize, tag: &str) -> Result<Buffer> {
+        let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST | BufferUsage::TRANSFER_SRC;
+        unsafe { ctx.create_buffer_raw_tagged(size, usage, tag, MemoryPriority::Normal) }
+    }
+    
+    /// Accounting tag of the buffer
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    
     /// Get the size of the buffer in bytes
     pub fn size(&self) -> usize {
         self.size
@@ -80,9 +258,197 @@ impl Buffer {
         self.usage
     }
     
+    /// The `len` bytes at `offset`, for binding or copying on their own
+    ///
+    /// `offset` must be a multiple of the device's
+    /// [`min_storage_buffer_offset_alignment`](ComputeContext::min_storage_buffer_offset_alignment),
+    /// and the range non-empty and inside the buffer.
+    pub fn slice(&self, offset: usize, len: usize) -> Result<BufferSlice<'_>> {
+        let alignment = self.context.min_storage_buffer_offset_alignment();
+        if offset as VkDeviceSize % alignment != 0 {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Slice offset {} is not a multiple of the storage buffer offset alignment {}",
+                offset, alignment
+            )));
+        }
+        if len == 0 || offset.checked_add(len).map_or(true, |end| end > self.size) {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Slice of {} bytes at offset {} does not fit buffer size {}",
+                len, offset, self.size
+            )));
+        }
+        Ok(BufferSlice { buffer: self, offset, len })
+    }
+    
     /// Get the raw Vulkan buffer handle (for advanced usage)
     pub fn raw(&self) -> VkBuffer {
-        self.buffer
+        self.buffer.raw()
+    }
+    
+    /// Take ownership of a buffer created outside Kronos
+    ///
+    /// The buffer and its memory are destroyed when the returned buffer is
+    /// dropped. The memory is accounted as `size` untagged bytes.
+    ///
+    /// # Safety
+    ///
+    /// - `buffer` must have been created on `context`'s device with at least
+    ///   `size` bytes and the flags of `usage`, and be bound at offset 0 to
+    ///   `memory`, which nothing else is bound to
+    /// - `memory_flags` must be the property flags of `memory`'s type
+    /// - Neither handle may be destroyed or freed elsewhere, and work
+    ///   submitted outside Kronos must complete before the buffer is dropped
+    pub unsafe fn from_raw_parts(
+        context: &ComputeContext,
+        buffer: VkBuffer,
+        memory: VkDeviceMemory,
+        size: usize,
+        usage: BufferUsage,
+        memory_flags: VkMemoryPropertyFlags,
+    ) -> Buffer {
+        context.with_inner(|inner| {
+            pool_allocator::record_allocation(inner.device, UNTAGGED, size as VkDeviceSize);
+            Buffer {
+                context: context.clone(),
+                buffer: Owned::new(buffer, inner.id),
+                memory,
+                size,
+                usage,
+                memory_flags,
+                tag: UNTAGGED.to_owned(),
+                allocation_size: size as VkDeviceSize,
+                export_handle_types: VkExternalMemoryHandleTypeFlags::empty(),
+                allocator: None,
+                last_use: Arc::default(),
+                external: Arc::default(),
+                _marker: PhantomData,
+            }
+        })
+    }
+    
+    /// Declare that something outside Kronos wrote the buffer
+    ///
+    /// For writes Kronos cannot see, such as a copy recorded by hand into
+    /// [`raw`](Self::raw) or by an API the memory is shared with. The next
+    /// dispatch the buffer is bound to waits for `stage` and makes `access`
+    /// visible to its shaders. Declarations add up until that dispatch.
+    pub fn declare_external_write(&self, stage: VkPipelineStageFlags, access: VkAccessFlags) {
+        self.external.declare(stage, access);
+    }
+    
+    /// Declare that something outside Kronos read the buffer
+    ///
+    /// The next dispatch the buffer is bound to waits for `stage` before
+    /// its shaders may overwrite what was read.
+    pub fn declare_external_read(&self, stage: VkPipelineStageFlags) {
+        self.external.declare(stage, VkAccessFlags::empty());
+    }
+    
+    /// Whether the buffer's memory can be mapped with [`try_map_direct`](Self::try_map_direct)
+    pub fn is_host_visible(&self) -> bool {
+        self.memory_flags.contains(VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT)
+    }
+    
+    /// Whether the buffer lives in device-local memory the host can map (ReBAR)
+    pub fn is_direct(&self) -> bool {
+        self.memory_flags.contains(DIRECT_MEMORY)
+    }
+    
+    /// Map the buffer for direct CPU access
+    ///
+    /// Returns `Ok(None)` when the buffer's memory is not host-visible; use
+    /// [`write`](Self::write) to fall back to a staging upload automatically.
+    /// The GPU must not be using the buffer while the mapping is written.
+    pub fn try_map_direct(&mut self) -> Result<Option<DirectMapping<'_>>> {
+        if !self.is_host_visible() {
+            return Ok(None);
+        }
+        
+        let mut mapped_ptr = ptr::null_mut();
+        let result = self.context.with_inner(|inner| unsafe {
+            vkMapMemory(inner.device, self.memory, 0, self.size as VkDeviceSize, 0, &mut mapped_ptr)
+        });
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        
+        Ok(Some(DirectMapping {
+            buffer: self,
+            ptr: mapped_ptr as *mut u8,
+        }))
+    }
+    
+    /// Write data to the start of the buffer
+    ///
+    /// Host-visible buffers are written through a direct mapping; others go
+    /// through the context's upload ring, or a staging buffer for large
+    /// writes, and a GPU copy.
+    pub fn write<T>(&mut self, data: &[T]) -> Result<()>
+    where
+        T: Copy + 'static,
+    {
+        if let Some(mut mapping) = self.try_map_direct()? {
+            return mapping.write(0, data);
+        }
+        
+        let size = std::mem::size_of_val(data);
+        if size > self.size {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Write of {} bytes exceeds buffer size {}",
+                size, self.size
+            )));
+        }
+        
+        unsafe {
+            let bytes = slice::from_raw_parts(data.as_ptr() as *const u8, size);
+            self.context.upload(bytes, self)
+        }
+    }
+}
+
+impl DirectMapping<'_> {
+    /// Size of the mapped range in bytes
+    pub fn len(&self) -> usize {
+        self.buffer.size
+    }
+    
+    /// Whether the mapped range is empty
+    pub fn is_empty(&self) -> bool {
+        self.buffer.size == 0
+    }
+    
+    /// Raw pointer to the start of the mapping (for advanced usage)
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr
+    }
+    
+    /// Copy `data` into the mapping starting at byte `offset`
+    pub fn write<T>(&mut self, offset: usize, data: &[T]) -> Result<()>
+    where
+        T: Copy + 'static,
+    {
+        let size = std::mem::size_of_val(data);
+        if offset.checked_add(size).map_or(true, |end| end > self.buffer.size) {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Write of {} bytes at offset {} exceeds buffer size {}",
+                size, offset, self.buffer.size
+            )));
+        }
+        unsafe {
+            let bytes = slice::from_raw_parts(data.as_ptr() as *const u8, size);
+            host_copy::write_mapped(self.buffer.memory_flags, bytes, self.ptr.add(offset));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DirectMapping<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.buffer.context.with_inner(|inner| {
+                vkUnmapMemory(inner.device, self.buffer.memory);
+            });
+        }
     }
 }
 
@@ -96,45 +462,84 @@ impl ComputeContext {
         let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST;
         
         unsafe {
-            // Create buffer
             let buffer = self.create_buffer_raw(size, usage)?;
+            let bytes = slice::from_raw_parts(data.as_ptr() as *const u8, size);
+            self.upload(bytes, &buffer)?;
+            Ok(buffer)
+        }
+    }
+    
+    /// Internal: Copy `bytes` to the start of `dst` on the GPU
+    ///
+    /// Small uploads are staged in the upload ring; larger ones, or any that
+    /// find the ring full, in a dedicated staging buffer.
+    ///
+    /// # Safety
+    ///
+    /// `dst` must have TRANSFER_DST usage and hold at least `bytes.len()` bytes.
+    pub(super) unsafe fn upload(&self, bytes: &[u8], dst: &Buffer) -> Result<()> {
+        let region = VkBufferCopy { srcOffset: 0, dstOffset: 0, size: bytes.len() as VkDeviceSize };
+        self.upload_regions(bytes, dst, &[region])
+    }
+
+    /// Internal: Stage `bytes` and copy `regions` of them into `dst` in one
+    /// submission
+    ///
+    /// Source offsets are relative to the start of `bytes`, which is staged
+    /// 16-byte aligned.
+    ///
+    /// # Safety
+    ///
+    /// `dst` must have TRANSFER_DST usage and every region must lie within
+    /// both `bytes` and `dst`.
+    pub(super) unsafe fn upload_regions(&self, bytes: &[u8], dst: &Buffer, regions: &[VkBufferCopy]) -> Result<()> {
+        let size = bytes.len();
+        if size == 0 || regions.is_empty() {
+            return Ok(());
+        }
+        let staged = self.with_inner(|inner| {
+            if size > inner.upload_limit() {
+                return None;
+            }
+            let ring = inner.upload_ring()?;
+            let mut region = ring.allocate(size)?;
+            region.write(bytes);
+            let regions: Vec<VkBufferCopy> = regions
+                .iter()
+                .map(|copy| VkBufferCopy { srcOffset: copy.srcOffset + region.offset(), ..*copy })
+                .collect();
+            // The copy waits for the queue, after which the region is free again
+            Some(self.copy_raw(ring.buffer(), dst.buffer.on(inner.id), &regions))
+        });
+        if let Some(result) = staged {
+            return result;
+        }
+        
+        let staging = self.create_buffer_raw(size, BufferUsage::TRANSFER_SRC)?;
+        self.with_inner(|inner| {
+            let mut mapped_ptr = ptr::null_mut();
+            let result = vkMapMemory(
+                inner.device,
+                staging.memory,
+                0,
+                size as VkDeviceSize,
+                0,
+                &mut mapped_ptr,
+            );
             
-            // Create staging buffer
-            let staging_usage = BufferUsage::TRANSFER_SRC;
-            let staging = self.create_buffer_raw(size, staging_usage)?;
-            
-            // Map and copy data
-            self.with_inner(|inner| {
-                let mut mapped_ptr = ptr::null_mut();
-                let result = vkMapMemory(
-                    inner.device,
-                    staging.memory,
-                    0,
-                    size as VkDeviceSize,
-                    0,
-                    &mut mapped_ptr,
-                );
-                
-                if result != VkResult::Success {
-                    return Err(KronosError::from(result));
-                }
-                
-                ptr::copy_nonoverlapping(
-                    data.as_ptr() as *const u8,
-                    mapped_ptr as *mut u8,
-                    size,
-                );
-                
-                vkUnmapMemory(inner.device, staging.memory);
-                Ok(())
-            })?;
+            if result != VkResult::Success {
+                return Err(KronosError::from(result));
+            }
             
-            // Copy staging to device buffer
-            self.copy_buffer(&staging, &buffer, size)?;
+            host_copy::write_mapped(staging.memory_flags, bytes, mapped_ptr as *mut u8);
             
-            // Staging buffer will be dropped automatically
-            Ok(buffer)
-        }
+            vkUnmapMemory(inner.device, staging.memory);
+            Ok(())
+        })?;
+        self.with_inner(|inner| {
+            inner.check_copy_usage(staging.usage, dst.usage);
+            self.copy_raw(staging.buffer.on(inner.id), dst.buffer.on(inner.id), regions)
+        })
     }
     
     /// Create an uninitialized buffer
@@ -143,6 +548,61 @@ impl ComputeContext {
         unsafe { self.create_buffer_raw(size, usage) }
     }
     
+    /// Create an uninitialized device-local buffer, host-mappable when possible
+    ///
+    /// Uses device-local, host-visible memory (resizable BAR) when the device
+    /// has it and the allocation fits; otherwise falls back to plain
+    /// device-local memory. Check [`Buffer::is_direct`] to see which was used.
+    pub fn create_buffer_direct(&self, size: usize) -> Result<Buffer> {
+        let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST | BufferUsage::TRANSFER_SRC;
+        unsafe {
+            self.create_buffer_with_memory(size, usage, &[DIRECT_MEMORY, VkMemoryPropertyFlags::DEVICE_LOCAL], UNTAGGED)
+        }
+    }
+    
+    /// Live device memory per allocation tag, with the process high-water mark
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport {
+            tags: pool_allocator::tag_usage(self.device()),
+            high_water_mark: pool_allocator::high_water_mark(),
+            limit: pool_allocator::memory_limit(self.device()),
+        }
+    }
+    
+    /// List the memory heaps of the selected device
+    pub fn memory_heaps(&self) -> Vec<MemoryHeapInfo> {
+        self.with_inner(|inner| Self::heap_infos(&inner.memory_properties))
+    }
+    
+    /// Size of the largest device-local heap the host can write directly
+    ///
+    /// `None` means direct writes are unavailable and uploads always stage.
+    /// Without resizable BAR this window is typically only 256 MiB.
+    pub fn direct_heap_size(&self) -> Option<u64> {
+        self.memory_heaps()
+            .into_iter()
+            .filter(MemoryHeapInfo::is_direct)
+            .map(|heap| heap.size)
+            .max()
+    }
+    
+    pub(super) fn heap_infos(memory_properties: &VkPhysicalDeviceMemoryProperties) -> Vec<MemoryHeapInfo> {
+        let types = &memory_properties.memoryTypes[..memory_properties.memoryTypeCount as usize];
+        memory_properties.memoryHeaps[..memory_properties.memoryHeapCount as usize]
+            .iter()
+            .enumerate()
+            .map(|(index, heap)| MemoryHeapInfo {
+                index: index as u32,
+                size: heap.size,
+                device_local: heap.flags & MEMORY_HEAP_DEVICE_LOCAL != 0,
+                host_visible: types.iter().any(|memory_type| {
+                    memory_type.heapIndex == index as u32
+                        && memory_type.propertyFlags.contains(VkMemoryPropertyFlags::HOST_VISIBLE)
+                }),
+            })
+            .collect()
+    }
+    
     /// Internal: Create a raw buffer
     ///
     /// # Safety
@@ -153,8 +613,67 @@ impl ComputeContext {
     /// - The created buffer must be properly destroyed to avoid memory leaks
     /// - Memory allocation may fail and must be handled appropriately
     /// - The returned Buffer takes ownership of the Vulkan resources
-    unsafe fn create_buffer_raw(&self, size: usize, usage: BufferUsage) -> Result<Buffer> {
+    pub(super) unsafe fn create_buffer_raw(&self, size: usize, usage: BufferUsage) -> Result<Buffer> {
+        self.create_buffer_raw_tagged(size, usage, UNTAGGED, MemoryPriority::Normal)
+    }
+    
+    /// Internal: Create a raw buffer accounted under `tag`, its memory
+    /// allocated with residency `priority`
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`create_buffer_raw`](Self::create_buffer_raw).
+    pub(super) unsafe fn create_buffer_raw_tagged(
+        &self,
+        size: usize,
+        usage: BufferUsage,
+        tag: &str,
+        priority: MemoryPriority,
+    ) -> Result<Buffer> {
+        let properties = if usage.flags.contains(VkBufferUsageFlags::TRANSFER_SRC) {
+            VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT
+        } else {
+            VkMemoryPropertyFlags::DEVICE_LOCAL
+        };
+        self.create_buffer_with_priority(size, usage, &[properties], tag, priority)
+    }
+    
+    /// Internal: Create a raw buffer in the first memory kind of `candidates` that allocates
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`create_buffer_raw`](Self::create_buffer_raw).
+    pub(super) unsafe fn create_buffer_with_memory(
+        &self,
+        size: usize,
+        usage: BufferUsage,
+        candidates: &[VkMemoryPropertyFlags],
+        tag: &str,
+    ) -> Result<Buffer> {
+        self.create_buffer_with_priority(size, usage, candidates, tag, MemoryPriority::Normal)
+    }
+    
+    /// Internal: [`create_buffer_with_memory`](Self::create_buffer_with_memory)
+    /// with residency `priority`, see [`residency`](super::residency)
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`create_buffer_raw`](Self::create_buffer_raw).
+    pub(super) unsafe fn create_buffer_with_priority(
+        &self,
+        size: usize,
+        usage: BufferUsage,
+        candidates: &[VkMemoryPropertyFlags],
+        tag: &str,
+        priority: MemoryPriority,
+    ) -> Result<Buffer> {
         self.with_inner(|inner| {
+            // Any storage buffer may hold the predicate of a dispatch, see
+            // `CommandBuilder::predicated_on`
+            let mut usage = usage;
+            if inner.conditional_rendering && usage.flags.contains(VkBufferUsageFlags::STORAGE_BUFFER) {
+                usage.flags |= VkBufferUsageFlags::CONDITIONAL_RENDERING_EXT;
+            }
             // Create buffer
             let buffer_info = VkBufferCreateInfo {
                 sType: VkStructureType::BufferCreateInfo,
@@ -168,7 +687,7 @@ impl ComputeContext {
             };
             
             let mut buffer = VkBuffer::NULL;
-            log::debug!("API layer calling vkCreateBuffer for device {:?}", inner.device);
+            kronos_log!(Debug, "API layer calling vkCreateBuffer for device {:?}", inner.device);
             let result = vkCreateBuffer(inner.device, &buffer_info, ptr::null(), &mut buffer);
             
             if result != VkResult::Success {
@@ -178,56 +697,99 @@ impl ComputeContext {
             // Get memory requirements
             let mut mem_requirements = VkMemoryRequirements::default();
             vkGetBufferMemoryRequirements(inner.device, buffer, &mut mem_requirements);
+            if let Err(e) = pool_allocator::reserve_allocation(inner.device, tag, mem_requirements.size) {
+                vkDestroyBuffer(inner.device, buffer, ptr::null());
+                return Err(e.into());
+            }
             
-            // Find suitable memory type
-            let memory_type_index = Self::find_memory_type(
-                &inner.memory_properties,
-                mem_requirements.memoryTypeBits,
-                if usage.flags.contains(VkBufferUsageFlags::TRANSFER_SRC) {
-                    VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT
-                } else {
-                    VkMemoryPropertyFlags::DEVICE_LOCAL
-                },
-            )?;
-            
-            // Allocate memory (this would use the pool allocator in the real implementation)
-            let alloc_info = VkMemoryAllocateInfo {
-                sType: VkStructureType::MemoryAllocateInfo,
-                pNext: ptr::null(),
-                allocationSize: mem_requirements.size,
-                memoryTypeIndex: memory_type_index,
-            };
-            
+            let allocator = inner.plugin.clone().filter(|plugin| plugin.allocator().is_some());
+            let plugin_allocator = allocator.as_deref().and_then(Plugin::allocator);
+            // Try each memory kind in order; a full heap falls through to the next
             let mut memory = VkDeviceMemory::NULL;
-            let result = vkAllocateMemory(inner.device, &alloc_info, ptr::null(), &mut memory);
+            let mut memory_flags = VkMemoryPropertyFlags::empty();
+            let mut last_error = KronosError::BufferCreationFailed("No suitable memory type found".into());
+            for &properties in candidates {
+                let memory_type_index = match Self::find_memory_type(
+                    &inner.memory_properties,
+                    mem_requirements.memoryTypeBits,
+                    properties,
+                ) {
+                    Ok(index) => index,
+                    Err(e) => {
+                        last_error = e;
+                        continue;
+                    }
+                };
+                
+                // Allocate memory (this would use the pool allocator in the real implementation)
+                let mut alloc_info = VkMemoryAllocateInfo {
+                    sType: VkStructureType::MemoryAllocateInfo,
+                    pNext: ptr::null(),
+                    allocationSize: mem_requirements.size,
+                    memoryTypeIndex: memory_type_index,
+                };
+                let mut priority_info = VkMemoryPriorityAllocateInfoEXT {
+                    priority: priority.value(),
+                    ..Default::default()
+                };
+                let mut alloc_info = Chain::new(&mut alloc_info);
+                // Normal is the driver's default, so those allocations go unchanged
+                if inner.memory_priority && priority != MemoryPriority::Normal {
+                    alloc_info = alloc_info.push(&mut priority_info);
+                }
+                
+                let result = match plugin_allocator {
+                    Some(plugin) => plugin.allocate(inner.device, &*alloc_info.as_ptr(), &mut memory),
+                    None => vkAllocateMemory(inner.device, alloc_info.as_ptr(), ptr::null(), &mut memory),
+                };
+                if result == VkResult::Success {
+                    inner.counters.allocation(mem_requirements.size);
+                    memory_flags = inner.memory_properties.memoryTypes[memory_type_index as usize].propertyFlags;
+                    break;
+                }
+                memory = VkDeviceMemory::NULL;
+                last_error = KronosError::BufferCreationFailed(format!("vkAllocateMemory failed: {:?}", result));
+            }
             
-            if result != VkResult::Success {
+            if memory == VkDeviceMemory::NULL {
                 vkDestroyBuffer(inner.device, buffer, ptr::null());
-                return Err(KronosError::BufferCreationFailed(format!("vkAllocateMemory failed: {:?}", result)));
+                pool_allocator::record_free(inner.device, tag, mem_requirements.size);
+                return Err(last_error);
             }
             
             // Bind memory to buffer
             let result = vkBindBufferMemory(inner.device, buffer, memory, 0);
             
             if result != VkResult::Success {
-                vkFreeMemory(inner.device, memory, ptr::null());
+                match plugin_allocator {
+                    Some(plugin) => plugin.free(inner.device, memory),
+                    None => vkFreeMemory(inner.device, memory, ptr::null()),
+                }
                 vkDestroyBuffer(inner.device, buffer, ptr::null());
+                pool_allocator::record_free(inner.device, tag, mem_requirements.size);
                 return Err(KronosError::BufferCreationFailed(format!("vkBindBufferMemory failed: {:?}", result)));
             }
             
             Ok(Buffer {
                 context: self.clone(),
-                buffer,
+                buffer: Owned::new(buffer, inner.id),
                 memory,
                 size,
                 usage,
+                memory_flags,
+                tag: tag.to_owned(),
+                allocation_size: mem_requirements.size,
+                export_handle_types: VkExternalMemoryHandleTypeFlags::empty(),
+                allocator,
+                last_use: Arc::default(),
+                external: Arc::default(),
                 _marker: std::marker::PhantomData,
             })
         })
     }
     
     /// Find a suitable memory type
-    fn find_memory_type(
+    pub(super) fn find_memory_type(
         memory_properties: &VkPhysicalDeviceMemoryProperties,
         type_filter: u32,
         properties: VkMemoryPropertyFlags,
@@ -242,6 +804,38 @@ impl ComputeContext {
         Err(KronosError::BufferCreationFailed("No suitable memory type found".into()))
     }
     
+    /// Copy `src` to the start of `dst` and wait for the copy
+    ///
+    /// `dst` must be at least as long as `src`, and the two must not
+    /// overlap. `src`'s buffer needs TRANSFER_SRC usage and `dst`'s
+    /// TRANSFER_DST usage.
+    pub fn copy_slice(&self, src: BufferSlice<'_>, dst: BufferSlice<'_>) -> Result<()> {
+        if src.len > dst.len {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Copy of {} bytes exceeds destination slice of {} bytes",
+                src.len, dst.len
+            )));
+        }
+        if src.overlaps(&dst) {
+            return Err(KronosError::CommandExecutionFailed(
+                "Copy source and destination slices overlap".into(),
+            ));
+        }
+        self.with_inner(|inner| inner.check_copy_usage(src.buffer.usage, dst.buffer.usage));
+        let device_id = self.device_id();
+        let (src_buffer, dst_buffer) = (src.buffer.buffer.on(device_id), dst.buffer.buffer.on(device_id));
+        unsafe {
+            self.submit_one_shot(|command_buffer| {
+                let region = VkBufferCopy {
+                    srcOffset: src.offset as VkDeviceSize,
+                    dstOffset: dst.offset as VkDeviceSize,
+                    size: src.len as VkDeviceSize,
+                };
+                vkCmdCopyBuffer(command_buffer, src_buffer, dst_buffer, 1, &region);
+            })
+        }
+    }
+    
     /// Copy data between buffers
     ///
     /// # Safety
@@ -255,12 +849,40 @@ impl ComputeContext {
     /// - Concurrent access to the buffers during copy is undefined behavior
     unsafe fn copy_buffer(&self, src: &Buffer, dst: &Buffer, size: usize) -> Result<()> {
         self.with_inner(|inner| {
+            inner.check_copy_usage(src.usage, dst.usage);
+            let region = VkBufferCopy { srcOffset: 0, dstOffset: 0, size: size as VkDeviceSize };
+            self.copy_raw(src.buffer.on(inner.id), dst.buffer.on(inner.id), &[region])
+        })
+    }
+    
+    /// Internal: Copy `regions` from `src` to `dst` in one submission
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`copy_buffer`](Self::copy_buffer), for raw
+    /// handles and every region.
+    pub(super) unsafe fn copy_raw(&self, src: VkBuffer, dst: VkBuffer, regions: &[VkBufferCopy]) -> Result<()> {
+        self.submit_one_shot(|command_buffer| {
+            vkCmdCopyBuffer(command_buffer, src, dst, regions.len() as u32, regions.as_ptr());
+        })
+    }
+    
+    /// Internal: Record commands with `record` into a one-time command
+    /// buffer, submit it and wait for the queue to go idle
+    ///
+    /// # Safety
+    ///
+    /// The commands `record` records must only reference valid handles of
+    /// this context's device.
+    pub(super) unsafe fn submit_one_shot(&self, record: impl FnOnce(VkCommandBuffer)) -> Result<()> {
+        self.with_inner(|inner| {
+            let pools = inner.pools.lock().unwrap();
             if inner.device == VkDevice::NULL {
                 return Err(KronosError::CommandExecutionFailed(
                     "Compute context has no valid Vulkan device".into(),
                 ));
             }
-            if inner.command_pool == VkCommandPool::NULL {
+            if pools.command_pool == VkCommandPool::NULL {
                 return Err(KronosError::CommandExecutionFailed(
                     "Compute context has no valid command pool".into(),
                 ));
@@ -275,7 +897,7 @@ impl ComputeContext {
             let alloc_info = VkCommandBufferAllocateInfo {
                 sType: VkStructureType::CommandBufferAllocateInfo,
                 pNext: ptr::null(),
-                commandPool: inner.command_pool,
+                commandPool: pools.command_pool,
                 level: VkCommandBufferLevel::Primary,
                 commandBufferCount: 1,
             };
@@ -301,23 +923,16 @@ impl ComputeContext {
             
             let result = vkBeginCommandBuffer(command_buffer, &begin_info);
             if result != VkResult::Success {
-                vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &command_buffer);
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
                 return Err(KronosError::from(result));
             }
             
-            // Record copy command
-            let region = VkBufferCopy {
-                srcOffset: 0,
-                dstOffset: 0,
-                size: size as VkDeviceSize,
-            };
-            
-            vkCmdCopyBuffer(command_buffer, src.buffer, dst.buffer, 1, &region);
+            record(command_buffer);
             
             // End recording
             let result = vkEndCommandBuffer(command_buffer);
             if result != VkResult::Success {
-                vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &command_buffer);
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
                 return Err(KronosError::from(result));
             }
             
@@ -334,16 +949,17 @@ impl ComputeContext {
                 pSignalSemaphores: ptr::null(),
             };
             
-            let result = vkQueueSubmit(inner.queue, 1, &submit_info, VkFence::NULL);
+            let _queue = inner.queue_lock.lock().unwrap();
+            let result = inner.device_events.check(inner.queue_submit(inner.queue, 1, &submit_info, VkFence::NULL));
             if result != VkResult::Success {
-                vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &command_buffer);
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
                 return Err(KronosError::from(result));
             }
             
             // Wait for completion
-            let result = vkQueueWaitIdle(inner.queue);
+            let result = inner.device_events.check(vkQueueWaitIdle(inner.queue));
             if result != VkResult::Success {
-                vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &command_buffer);
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
                 return Err(KronosError::SynchronizationError(format!(
                     "vkQueueWaitIdle failed: {:?}",
                     result
@@ -351,7 +967,7 @@ impl ComputeContext {
             }
             
             // Free command buffer
-            vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &command_buffer);
+            vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
             
             Ok(())
         })
@@ -409,11 +1025,19 @@ impl Buffer {
 
 impl Drop for Buffer {
     fn drop(&mut self) {
-        unsafe {
-            self.context.with_inner(|inner| {
-                vkFreeMemory(inner.device, self.memory, ptr::null());
-                vkDestroyBuffer(inner.device, self.buffer, ptr::null());
-            });
-        }
+        let (buffer, memory, allocation_size) = (self.buffer.raw(), self.memory, self.allocation_size);
+        let tag = std::mem::take(&mut self.tag);
+        let allocator = self.allocator.take();
+        self.context.with_inner(|inner| {
+            let device = inner.device;
+            inner.deferred.destroy(std::mem::take(&mut self.last_use), Box::new(move || unsafe {
+                match allocator.as_deref().and_then(Plugin::allocator) {
+                    Some(plugin) => plugin.free(device, memory),
+                    None => vkFreeMemory(device, memory, ptr::null()),
+                }
+                vkDestroyBuffer(device, buffer, ptr::null());
+                pool_allocator::record_free(device, &tag, allocation_size);
+            }));
+        });
     }
 }
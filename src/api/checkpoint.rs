@@ -0,0 +1,263 @@
+//! Disk-backed checkpoints of long-running scheduler jobs
+//!
+//! A [`Job`] given [`passes`](Job::passes) is dispatched that many times by
+//! its [`Scheduler`](super::Scheduler), each pass once the previous one has
+//! completed. With [`checkpoint`](Job::checkpoint), the scheduler copies
+//! the job's state buffers, bound with
+//! [`bind_state_buffer`](Job::bind_state_buffer), to the host every few
+//! passes and writes them to a file together with the number of passes
+//! done. Checkpoints are taken between passes, so they never see a pass
+//! half run, and written to a temporary file renamed over the previous
+//! checkpoint, so a crash leaves the last complete one in place.
+//!
+//! Pipelines and buffers cannot be saved. After a restart, build the job
+//! again with fresh buffers and [`resume`](Job::resume) it: the saved state
+//! is uploaded into its state buffers and it continues with the pass after
+//! the checkpoint. The file is removed once the job completes, so a
+//! finished job is not resumed by mistake.
+//!
+//! ```no_run
+//! use kronos_compute::api::{ComputeContext, FlightLimiter, Job, Scheduler};
+//! use std::path::Path;
+//! use std::sync::Arc;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let shader = ctx.load_shader("shaders/simulate.spv")?;
+//! let pipeline = Arc::new(ctx.create_pipeline(&shader)?);
+//! let state = Arc::new(ctx.create_buffer(&[0.0f32; 1 << 20])?);
+//!
+//! let path = Path::new("checkpoints/simulation.ckpt");
+//! let mut job = Job::new(pipeline).bind_state_buffer(0, state).passes(100_000).checkpoint(path, 1000);
+//! if path.exists() {
+//!     job = job.resume(path)?;
+//! }
+//! let scheduler = Scheduler::new(FlightLimiter::new(4));
+//! scheduler.add_context(&ctx);
+//! scheduler.submit(job).wait()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! State buffers are read back with [`Buffer::read`], so each checkpoint
+//! costs a copy of the state through a staging buffer. Pick the interval so
+//! that copy is small next to the passes between checkpoints.
+
+use super::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Identifies a checkpoint file
+const MAGIC: &[u8; 8] = b"KRNCKPT\0";
+/// Version of the checkpoint layout
+const VERSION: u32 = 1;
+
+/// Where and how often a job is checkpointed
+pub(super) struct Checkpointing {
+    path: PathBuf,
+    every: u64,
+    /// Passes done when the last checkpoint was taken
+    saved: u64,
+}
+
+/// Contents of a checkpoint file
+///
+/// Little-endian: the magic, the version, the passes done, the number of
+/// state buffers, then each buffer's binding, length and bytes.
+struct Checkpoint {
+    passes: u64,
+    states: Vec<(u32, Vec<u8>)>,
+}
+
+impl Checkpoint {
+    fn encode(&self) -> Vec<u8> {
+        let size = self.states.iter().map(|(_, bytes)| 12 + bytes.len()).sum::<usize>();
+        let mut out = Vec::with_capacity(24 + size);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.passes.to_le_bytes());
+        out.extend_from_slice(&(self.states.len() as u32).to_le_bytes());
+        for (binding, bytes) in &self.states {
+            out.extend_from_slice(&binding.to_le_bytes());
+            out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+        out
+    }
+
+    /// Parse a checkpoint; `None` if it is truncated or not a checkpoint
+    fn decode(mut data: &[u8]) -> Option<Self> {
+        fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+            if data.len() < len {
+                return None;
+            }
+            let (head, tail) = data.split_at(len);
+            *data = tail;
+            Some(head)
+        }
+        let u32_at = |data: &mut &[u8]| take(data, 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+        let u64_at = |data: &mut &[u8]| take(data, 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+
+        if take(&mut data, MAGIC.len())? != MAGIC || u32_at(&mut data)? != VERSION {
+            return None;
+        }
+        let passes = u64_at(&mut data)?;
+        let count = u32_at(&mut data)?;
+        let mut states = Vec::new();
+        for _ in 0..count {
+            let binding = u32_at(&mut data)?;
+            let len = usize::try_from(u64_at(&mut data)?).ok()?;
+            states.push((binding, take(&mut data, len)?.to_vec()));
+        }
+        data.is_empty().then_some(Self { passes, states })
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(|e| checkpoint_error(path, e))?;
+        Self::decode(&data).ok_or_else(|| {
+            KronosError::CommandExecutionFailed(format!("{:?} is not a Kronos checkpoint, or is truncated", path))
+        })
+    }
+
+    fn store(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename so a crash never leaves a partial checkpoint
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(format!(".{}", std::process::id()));
+        fs::write(&partial, self.encode())?;
+        fs::rename(&partial, path)
+    }
+}
+
+fn checkpoint_error(path: &Path, e: std::io::Error) -> KronosError {
+    KronosError::CommandExecutionFailed(format!("Checkpoint {:?}: {}", path, e))
+}
+
+impl Job {
+    /// Run the dispatch `passes` times, each pass after the previous one
+    /// has completed
+    ///
+    /// The job stays pending until the last pass completes, and its
+    /// [`timeout`](Self::timeout) covers every pass.
+    pub fn passes(mut self, passes: u64) -> Self {
+        self.passes = passes.max(1);
+        self
+    }
+
+    /// Bind a buffer holding state that checkpoints save and
+    /// [`resume`](Self::resume) restores
+    ///
+    /// The buffer needs transfer source and destination usage, as buffers
+    /// from [`ComputeContext::create_buffer_uninit`] have.
+    pub fn bind_state_buffer(mut self, binding: u32, buffer: Arc<Buffer>) -> Self {
+        self.state_bindings.retain(|&state| state != binding);
+        self.state_bindings.push(binding);
+        self.bind_buffer(binding, buffer)
+    }
+
+    /// Write a checkpoint of the state buffers to `path` every `every`
+    /// passes, see [`checkpoint`](super::checkpoint)
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>, every: u64) -> Self {
+        self.checkpoint = Some(Checkpointing { path: path.into(), every: every.max(1), saved: self.completed_passes });
+        self
+    }
+
+    /// Restore the state buffers and pass count from the checkpoint at
+    /// `path`
+    ///
+    /// Call once the job's passes and state buffers are set. Fails if the
+    /// checkpoint's state buffers do not match the job's in binding and
+    /// size, or if it has more passes than the job.
+    pub fn resume(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let checkpoint = Checkpoint::load(path)?;
+        if checkpoint.passes > self.passes {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Checkpoint {:?} is at pass {} of a job with {} passes",
+                path, checkpoint.passes, self.passes
+            )));
+        }
+        let mismatch = |reason: String| KronosError::CommandExecutionFailed(format!("Checkpoint {:?}: {}", path, reason));
+        if checkpoint.states.len() != self.state_bindings.len() {
+            return Err(mismatch(format!(
+                "saved {} state buffers, the job has {}",
+                checkpoint.states.len(),
+                self.state_bindings.len()
+            )));
+        }
+        for (binding, bytes) in &checkpoint.states {
+            let buffer = self
+                .state_buffer(*binding)
+                .ok_or_else(|| mismatch(format!("binding {} is not a state buffer of the job", binding)))?;
+            if buffer.size != bytes.len() {
+                return Err(mismatch(format!(
+                    "binding {} saved {} bytes into a buffer of {}",
+                    binding,
+                    bytes.len(),
+                    buffer.size
+                )));
+            }
+        }
+
+        for (binding, bytes) in &checkpoint.states {
+            let buffer = self.state_buffer(*binding).expect("state buffers were checked");
+            // SAFETY: state buffers have transfer destination usage and the
+            // size was checked above
+            unsafe { buffer.context.upload(bytes, buffer)? };
+        }
+        self.completed_passes = checkpoint.passes;
+        if let Some(checkpointing) = &mut self.checkpoint {
+            checkpointing.saved = checkpoint.passes;
+        }
+        Ok(self)
+    }
+
+    /// Passes run so far, including those restored by
+    /// [`resume`](Self::resume)
+    pub fn completed_passes(&self) -> u64 {
+        self.completed_passes
+    }
+
+    /// The buffer last bound to a state binding
+    fn state_buffer(&self, binding: u32) -> Option<&Arc<Buffer>> {
+        if !self.state_bindings.contains(&binding) {
+            return None;
+        }
+        self.buffers.iter().rev().find(|(bound, _)| *bound == binding).map(|(_, buffer)| buffer)
+    }
+
+    /// Write a checkpoint if `every` passes have run since the last one
+    ///
+    /// Called by the scheduler between passes, when no pass is in flight.
+    pub(super) fn checkpoint_if_due(&mut self) -> Result<()> {
+        let Some(checkpointing) = &self.checkpoint else { return Ok(()) };
+        if self.completed_passes < checkpointing.saved + checkpointing.every {
+            return Ok(());
+        }
+        let mut states = Vec::with_capacity(self.state_bindings.len());
+        for &binding in &self.state_bindings {
+            let buffer = self.state_buffer(binding).expect("state bindings are bound");
+            states.push((binding, buffer.read::<u8>()?));
+        }
+        let path = &checkpointing.path;
+        Checkpoint { passes: self.completed_passes, states }.store(path).map_err(|e| checkpoint_error(path, e))?;
+        kronos_log!(Debug, "[SAFE API] Checkpointed job at pass {} to {:?}", self.completed_passes, path);
+        if let Some(checkpointing) = &mut self.checkpoint {
+            checkpointing.saved = self.completed_passes;
+        }
+        Ok(())
+    }
+
+    /// Remove the checkpoint of a job that has completed
+    pub(super) fn remove_checkpoint(&self) {
+        let Some(checkpointing) = &self.checkpoint else { return };
+        match fs::remove_file(&checkpointing.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => kronos_log!(Warn, "[SAFE API] Could not remove checkpoint {:?}: {}", checkpointing.path, e),
+        }
+    }
+}
@@ -3,8 +3,162 @@
 use super::*;
 use crate::*; // Import all functions from the crate root
 #[cfg(feature = "implementation")]
-use crate::implementation::persistent_descriptors::get_persistent_descriptor_set;
+use crate::implementation::persistent_descriptors::get_or_write_persistent_descriptor_set;
+use super::context::{ContextInner, Pools};
+use super::descriptor_pools::DescriptorPools;
+use super::pipeline::PipelineLayouts;
+use super::queue::QueueCommandPool;
+use super::reaper::{CompletionCallback, Reaper, SubmissionResources};
+use super::readback::ReadbackShared;
+use super::asserts::AssertShared;
+use super::interop::{record_ownership_transfers, OwnershipTransfer};
+use super::deferred::LastUse;
+use super::buffer::ExternalAccess;
+use super::timing::{GpuTimer, TimedDispatch};
+use super::worker::WorkerShared;
+use super::stats::CommandStats;
+use super::progress::ProgressShared;
+use super::hashing::PendingHashes;
+use super::predication::{Emulation, Predicate};
+use super::constants::{BoundConstants, Constants, ConstantsSlot};
 use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// Pipeline handles used by a dispatch
+///
+/// The builder must not own a [`Pipeline`]: dropping it would destroy the
+/// caller's pipeline. The shared layouts stay alive through `layouts`.
+struct BoundPipeline {
+    pipeline: VkPipeline,
+    layout: VkPipelineLayout,
+    descriptor_set_layout: VkDescriptorSetLayout,
+    interface: Arc<KernelInterface>,
+    label: Arc<str>,
+    dispatch_base: bool,
+    layouts: Arc<PipelineLayouts>,
+}
+
+impl BoundPipeline {
+    fn new(pipeline: &Pipeline, device: DeviceId) -> Self {
+        Self {
+            pipeline: pipeline.pipeline.on(device),
+            layout: pipeline.layout,
+            descriptor_set_layout: pipeline.descriptor_set_layout,
+            interface: pipeline.interface.clone(),
+            label: pipeline.label.clone(),
+            dispatch_base: pipeline.dispatch_base,
+            layouts: pipeline.layouts.clone(),
+        }
+    }
+}
+
+/// A dispatch recorded before the builder's current one, see [`CommandBuilder::then`]
+struct DispatchStep {
+    pipeline: BoundPipeline,
+    push_constants: Vec<u8>,
+    /// Dynamic offset of the step's constants, see [`CommandBuilder::constants`]
+    constants_offset: u32,
+    workgroups: (u32, u32, u32),
+    base: (u32, u32, u32),
+}
+
+/// State bound in the command buffer being recorded
+///
+/// Consecutive dispatches of one builder usually bind the same pipeline,
+/// descriptor set and push constants again; each method returns whether the
+/// command is needed and records the new state if so.
+#[derive(Default)]
+pub(super) struct BindState {
+    pipeline: Option<VkPipeline>,
+    descriptor_set: Option<(VkPipelineLayout, VkDescriptorSet, Option<u32>)>,
+    push_constants: Option<(VkPipelineLayout, Vec<u8>)>,
+}
+
+impl BindState {
+    pub(super) fn bind_pipeline(&mut self, pipeline: VkPipeline) -> bool {
+        if self.pipeline == Some(pipeline) {
+            return false;
+        }
+        self.pipeline = Some(pipeline);
+        true
+    }
+
+    pub(super) fn bind_descriptor_set(&mut self, layout: VkPipelineLayout, set: VkDescriptorSet, dynamic_offset: Option<u32>) -> bool {
+        if self.descriptor_set == Some((layout, set, dynamic_offset)) {
+            return false;
+        }
+        self.descriptor_set = Some((layout, set, dynamic_offset));
+        true
+    }
+
+    pub(super) fn push_constants(&mut self, layout: VkPipelineLayout, data: &[u8]) -> bool {
+        if matches!(&self.push_constants, Some((bound, bytes)) if *bound == layout && bytes == data) {
+            return false;
+        }
+        self.push_constants = Some((layout, data.to_vec()));
+        true
+    }
+}
+
+/// Buffer handle bound to a dispatch; the caller keeps ownership
+#[derive(Clone, Copy)]
+struct BoundBuffer {
+    buffer: VkBuffer,
+    offset: VkDeviceSize,
+    size: usize,
+    /// Whether the range is the whole buffer
+    whole: bool,
+    /// Whether the buffer has STORAGE_BUFFER usage
+    storage: bool,
+}
+
+/// Semaphores a submission signals for the builders ordered after it
+#[derive(Default)]
+pub(super) struct Dependents {
+    pub(super) semaphores: Vec<Arc<Semaphore>>,
+    pub(super) submitted: bool,
+}
+
+/// Semaphores linking one submission to those before and after it
+#[derive(Default)]
+struct SubmissionLinks {
+    waits: Vec<Arc<Semaphore>>,
+    signals: Vec<Arc<Semaphore>>,
+}
+
+impl SubmissionLinks {
+    fn is_empty(&self) -> bool {
+        self.waits.is_empty() && self.signals.is_empty()
+    }
+}
+
+/// How [`CommandBuilder::execute_split`] divides a long-running dispatch
+#[derive(Debug, Clone, Copy)]
+pub struct SplitDispatch {
+    /// Binding of the progress buffer: two `u32`s holding the first
+    /// workgroup of the current submission along x and the total workgroup
+    /// count along x
+    pub progress_binding: u32,
+    /// Longest one submission should run; defaults to half the device's
+    /// watchdog timeout, see [`ComputeContext::quirks`]
+    pub max_dispatch_duration: Option<Duration>,
+    /// Estimated time of one workgroup column (all of y and z at one x),
+    /// used to size the first submission and refined from each one
+    pub workgroup_estimate: Duration,
+}
+
+impl SplitDispatch {
+    /// Split with the progress buffer at `progress_binding`
+    pub fn new(progress_binding: u32) -> Self {
+        Self {
+            progress_binding,
+            max_dispatch_duration: None,
+            workgroup_estimate: Duration::from_micros(100),
+        }
+    }
+}
 
 /// Fluent builder for compute dispatch commands
 /// 
@@ -12,13 +166,55 @@ use std::ptr;
 /// and executing compute commands. All Kronos optimizations
 /// are applied automatically.
 pub struct CommandBuilder {
-    context: ComputeContext,
-    pipeline: Pipeline,
+    pub(super) context: ComputeContext,
+    /// Dispatches added before the current one with [`then`](Self::then)
+    steps: Vec<DispatchStep>,
+    pipeline: BoundPipeline,
     command_buffer: VkCommandBuffer,
     descriptor_set: Option<VkDescriptorSet>,
-    bindings: Vec<(u32, Buffer)>,
+    bound_set: Option<(VkDescriptorSet, VkDescriptorSetLayout, u32)>,
+    bound_buffers: Vec<(VkBuffer, VkDeviceSize)>,
+    /// Queue, its command pool and its family index
+    target_queue: Option<(VkQueue, Arc<QueueCommandPool>, u32)>,
+    /// Performance query pool and counter pass recorded around the dispatch
+    perf_pass: Option<(VkQueryPool, u32)>,
+    callbacks: Vec<CompletionCallback>,
+    flight_limiter: Option<FlightLimiter>,
+    /// Worker whose pools record the dispatch, if created by [`Worker::dispatch`]
+    pub(super) worker: Option<Arc<WorkerShared>>,
+    bindings: Vec<(u32, BoundBuffer)>,
+    image_bindings: Vec<(u32, VkDescriptorType, VkDescriptorImageInfo)>,
     push_constants: Vec<u8>,
+    /// Uniform range of the builder's constants, if not pushed
+    constants: Option<BoundConstants>,
+    /// Dynamic offset of the current dispatch's constants
+    constants_offset: u32,
+    /// Ring slots holding the constants of each dispatch
+    constant_slots: Vec<ConstantsSlot>,
     workgroups: (u32, u32, u32),
+    /// First workgroup ID, see [`base`](Self::base)
+    base: (u32, u32, u32),
+    /// Signaled for the builders that called [`after`](Self::after) on this one
+    pub(super) dependents: Arc<Mutex<Dependents>>,
+    /// Submissions this one waits for, and the semaphore each signals
+    pub(super) waits: Vec<(Arc<Mutex<Dependents>>, Arc<Semaphore>)>,
+    /// Buffers and images bound to the dispatch, whose destruction waits for it
+    uses: Vec<Arc<LastUse>>,
+    /// Buffers copied into readback channels after the last dispatch
+    readbacks: Vec<(VkBuffer, Arc<ReadbackShared>)>,
+    /// Buffers earlier submissions accessed from compute shaders, which the
+    /// first dispatch waits for
+    hazards: Vec<(VkBuffer, VkDeviceSize)>,
+    /// Bound buffers, with the accesses made outside Kronos they may carry
+    external: Vec<(VkBuffer, VkDeviceSize, Arc<ExternalAccess>)>,
+    /// Device-side assertion flags checked after completion
+    pub(super) asserts: Option<Arc<AssertShared>>,
+    /// Buffers whose ownership moves from or to an external API
+    pub(super) ownership_transfers: Vec<(VkBuffer, OwnershipTransfer)>,
+    /// Timeline the submission advances, see [`progress`](Self::progress)
+    progress: Option<Arc<ProgressShared>>,
+    /// Word the dispatches run on, see [`predicated_on`](Self::predicated_on)
+    predicate: Option<Predicate>,
 }
 
 impl ComputeContext {
@@ -26,31 +222,150 @@ impl ComputeContext {
     pub fn dispatch(&self, pipeline: &Pipeline) -> CommandBuilder {
         CommandBuilder {
             context: self.clone(),
-            pipeline: Pipeline {
-                context: pipeline.context.clone(),
-                pipeline: pipeline.pipeline,
-                layout: pipeline.layout,
-                descriptor_set_layout: pipeline.descriptor_set_layout,
-            },
+            steps: Vec::new(),
+            pipeline: BoundPipeline::new(pipeline, self.device_id()),
             command_buffer: VkCommandBuffer::NULL,
             descriptor_set: None,
+            bound_set: None,
+            bound_buffers: Vec::new(),
+            target_queue: None,
+            perf_pass: None,
+            callbacks: Vec::new(),
+            flight_limiter: None,
+            worker: None,
             bindings: Vec::new(),
+            image_bindings: Vec::new(),
             push_constants: Vec::new(),
+            constants: None,
+            constants_offset: 0,
+            constant_slots: Vec::new(),
             workgroups: (1, 1, 1),
+            base: (0, 0, 0),
+            dependents: Arc::default(),
+            waits: Vec::new(),
+            uses: Vec::new(),
+            readbacks: Vec::new(),
+            hazards: Vec::new(),
+            external: Vec::new(),
+            asserts: None,
+            ownership_transfers: Vec::new(),
+            progress: None,
+            predicate: None,
         }
     }
 }
 
 impl CommandBuilder {
     /// Bind a buffer to a binding point
-    pub fn bind_buffer(mut self, binding: u32, buffer: &Buffer) -> Self {
-        self.bindings.push((binding, Buffer {
-            context: buffer.context.clone(),
-            buffer: buffer.buffer,
-            memory: buffer.memory,
-            size: buffer.size,
-            usage: buffer.usage,
-            _marker: std::marker::PhantomData,
+    pub fn bind_buffer(self, binding: u32, buffer: &Buffer) -> Self {
+        self.bind_slice(binding, buffer.into())
+    }
+    
+    /// Bind a range of a buffer from [`Buffer::slice`] to a binding point
+    ///
+    /// The shader sees the slice as the whole storage buffer.
+    pub fn bind_slice(mut self, binding: u32, slice: BufferSlice<'_>) -> Self {
+        let buffer = slice.buffer;
+        self.uses.push(buffer.last_use.clone());
+        let handle = buffer.buffer.on(self.context.device_id());
+        self.external.push((handle, buffer.size as VkDeviceSize, buffer.external.clone()));
+        self.bindings.push((binding, BoundBuffer {
+            buffer: handle,
+            offset: slice.offset as VkDeviceSize,
+            size: slice.len,
+            whole: slice.is_whole(),
+            storage: buffer.usage.flags.contains(VkBufferUsageFlags::STORAGE_BUFFER),
+        }));
+        self
+    }
+    
+    /// Bind a buffer to the storage buffer binding named `name` in the kernel
+    ///
+    /// Names come from the shader's debug info (see [`Pipeline::interface`]);
+    /// an anonymous buffer block is found by its block name.
+    pub fn bind_named(self, name: &str, buffer: &Buffer) -> Result<Self> {
+        let binding = self.pipeline.interface.binding(name).ok_or_else(|| {
+            KronosError::CommandExecutionFailed(format!(
+                "Kernel '{}' has no binding named '{}'",
+                self.pipeline.interface.entry_point, name
+            ))
+        })?;
+        if binding.set != 0 || binding.descriptor_type != VkDescriptorType::StorageBuffer {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Binding '{}' is a {:?} in set {}, expected a storage buffer in set 0",
+                name, binding.descriptor_type, binding.set
+            )));
+        }
+        let binding = binding.binding;
+        Ok(self.bind_buffer(binding, buffer))
+    }
+    
+    /// Copy the start of `source` into `channel` once the dispatches have run
+    ///
+    /// Each submission fills the channel slot not holding the newest
+    /// results; [`ReadbackChannel::latest`] returns them once the submission
+    /// completes. `source` needs TRANSFER_SRC usage.
+    pub fn read_back<T>(mut self, source: &Buffer, channel: &ReadbackChannel<T>) -> Result<Self> {
+        let shared = &channel.shared;
+        if source.size < shared.size() {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Readback of {} bytes exceeds source buffer size {}",
+                shared.size(),
+                source.size
+            )));
+        }
+        self.uses.push(source.last_use.clone());
+        self.readbacks.push((source.buffer.on(self.context.device_id()), shared.clone()));
+        Ok(self)
+    }
+    
+    /// Wait for the compute shader accesses of earlier submissions to `buffer`
+    pub(super) fn after_shader_access(mut self, buffer: &Buffer) -> Self {
+        self.hazards.push((buffer.buffer.on(self.context.device_id()), buffer.size as VkDeviceSize));
+        self
+    }
+    
+    /// Submit on a queue from [`ComputeContext::create_queue`] instead of the default queue
+    pub fn on_queue(mut self, queue: &Queue) -> Self {
+        self.target_queue = Some((queue.queue.on(self.context.device_id()), queue.command_pool.clone(), queue.family_index));
+        self
+    }
+    
+    /// Use a descriptor set prepared with [`Pipeline::bind_all`]
+    ///
+    /// Cannot be combined with `bind_buffer` or image bindings.
+    pub fn descriptor_set(mut self, set: &DescriptorSet) -> Self {
+        self.bound_set = Some((set.set.on(self.context.device_id()), set.layout, set.dynamic_bindings));
+        self.bound_buffers = set.buffers.clone();
+        self.uses.extend(set.uses.iter().cloned());
+        self
+    }
+    
+    /// Bind a storage image to a binding point
+    ///
+    /// The image is accessed in the GENERAL layout; the pipeline's binding
+    /// must be declared as `VkDescriptorType::StorageImage`.
+    pub fn bind_storage_image(mut self, binding: u32, image: &Image) -> Self {
+        self.uses.push(image.last_use.clone());
+        self.image_bindings.push((binding, VkDescriptorType::StorageImage, VkDescriptorImageInfo {
+            sampler: VkSampler::NULL,
+            imageView: image.view.on(self.context.device_id()),
+            imageLayout: VkImageLayout::General,
+        }));
+        self
+    }
+    
+    /// Bind an image with a sampler to a binding point
+    ///
+    /// The pipeline's binding must be declared as
+    /// `VkDescriptorType::CombinedImageSampler`.
+    pub fn bind_sampled_image(mut self, binding: u32, image: &Image, sampler: &Sampler) -> Self {
+        self.uses.push(image.last_use.clone());
+        self.uses.push(sampler.last_use.clone());
+        self.image_bindings.push((binding, VkDescriptorType::CombinedImageSampler, VkDescriptorImageInfo {
+            sampler: sampler.sampler.on(self.context.device_id()),
+            imageView: image.view.on(self.context.device_id()),
+            imageLayout: VkImageLayout::General,
         }));
         self
     }
@@ -67,286 +382,1571 @@ impl CommandBuilder {
         self
     }
     
+    /// Set push constants from raw bytes
+    pub(crate) fn push_constant_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.push_constants = bytes;
+        self
+    }
+    
+    /// Set the push constant member named `name`
+    ///
+    /// Other members keep their values; the block is zero-filled until set.
+    pub fn push_named<T: Copy>(mut self, name: &str, value: T) -> Result<Self> {
+        let interface = &self.pipeline.interface;
+        let member = interface.push_constant(name).ok_or_else(|| {
+            KronosError::CommandExecutionFailed(format!(
+                "Kernel '{}' has no push constant named '{}'",
+                interface.entry_point, name
+            ))
+        })?;
+        let size = std::mem::size_of::<T>();
+        if member.size as usize != size {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Push constant '{}' is {} bytes, got a {}-byte value",
+                name, member.size, size
+            )));
+        }
+        
+        let offset = member.offset as usize;
+        let block_size = interface.push_constants.as_ref().map_or(0, |block| block.size as usize);
+        let needed = block_size.max(offset + size);
+        if self.push_constants.len() < needed {
+            self.push_constants.resize(needed, 0);
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&value as *const T as *const u8, size)
+        };
+        self.push_constants[offset..offset + size].copy_from_slice(bytes);
+        Ok(self)
+    }
+    
+    /// Pass `value` as the dispatch's parameter block, see
+    /// [`constants`](super::constants)
+    ///
+    /// Pushes `value` if `constants` is pushed, and otherwise writes it to
+    /// a free slot of its ring, which fails if every slot is in flight.
+    /// The pipeline must come from
+    /// [`create_pipeline_with_constants`](ComputeContext::create_pipeline_with_constants)
+    /// with the same `constants`.
+    pub fn constants<T: Copy + 'static>(mut self, constants: &Constants<T>, value: &T) -> Result<Self> {
+        let Some(slot) = constants.stage(value)? else {
+            return Ok(self.push_constants(value));
+        };
+        if self.constants.is_some_and(|bound| bound != slot.bound()) {
+            return Err(KronosError::CommandExecutionFailed(
+                "Dispatches of one builder must take their constants from one Constants".into(),
+            ));
+        }
+        self.uses.push(slot.last_use());
+        self.constants = Some(slot.bound());
+        self.constants_offset = slot.offset();
+        self.constant_slots.push(slot);
+        Ok(self)
+    }
+    
     /// Set the number of workgroups
     pub fn workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
         self.workgroups = (x, y, z);
         self
     }
     
-    /// Execute the dispatch
+    /// Start the workgroup IDs at `(x, y, z)` instead of zero
+    ///
+    /// The shader's `gl_WorkGroupID` runs from the base to the base plus
+    /// [`workgroups`](Self::workgroups), so tiles of a domain larger than one
+    /// dispatch can be processed without passing their offset in push
+    /// constants. A non-zero base is recorded with vkCmdDispatchBase and
+    /// needs a pipeline created with [`PipelineConfig::dispatch_base`].
+    pub fn base(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.base = (x, y, z);
+        self
+    }
+    
+    /// Record another dispatch of `pipeline` after this one
+    ///
+    /// Both run from one command buffer and share the buffer and image
+    /// bindings, so the pipelines must have the same descriptor set layout.
+    /// The new dispatch starts without push constants and with a single
+    /// workgroup at base zero, and waits for the shader writes of the one before it.
+    /// Binds that would repeat the state left by the previous dispatch are
+    /// skipped, so iterating one pipeline only records push constants and
+    /// the dispatch itself.
+    pub fn then(mut self, pipeline: &Pipeline) -> Self {
+        let next = BoundPipeline::new(pipeline, self.context.device_id());
+        self.steps.push(DispatchStep {
+            pipeline: std::mem::replace(&mut self.pipeline, next),
+            push_constants: std::mem::take(&mut self.push_constants),
+            constants_offset: std::mem::take(&mut self.constants_offset),
+            workgroups: std::mem::replace(&mut self.workgroups, (1, 1, 1)),
+            base: std::mem::take(&mut self.base),
+        });
+        self
+    }
+    
+    /// Run the dispatches only if the 32-bit word at `offset` in `flag` is
+    /// non-zero when they execute
+    ///
+    /// The word is read on the device, so an earlier dispatch can decide
+    /// whether these run without a readback; it covers every dispatch of
+    /// the builder and must not be written by them. See
+    /// [`predication`](super::predication) for how devices without
+    /// VK_EXT_conditional_rendering run it.
+    pub fn predicated_on(mut self, flag: &Buffer, offset: VkDeviceSize) -> Self {
+        self.uses.push(flag.last_use.clone());
+        self.predicate = Some(Predicate::new(flag, self.context.device_id(), offset));
+        self
+    }
+    
+    /// Pipeline of the first dispatch, which names the command buffer
+    fn first_pipeline(&self) -> &BoundPipeline {
+        self.steps.first().map_or(&self.pipeline, |step| &step.pipeline)
+    }
+    
+    /// Run `callback` once the dispatch has completed on the GPU
+    ///
+    /// With [`submit`](Self::submit) callbacks run on the context's reaper
+    /// thread when the submission fence signals; with
+    /// [`execute`](Self::execute), which already waits, they run on the
+    /// calling thread before it returns. Callbacks must not block on other
+    /// in-flight submissions.
+    pub fn on_complete<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+    
+    /// Count this dispatch against `limiter`
+    ///
+    /// Submission blocks until the limiter has a free slot; the slot is
+    /// released once the dispatch completes on the GPU, after any
+    /// [`on_complete`](Self::on_complete) callbacks.
+    pub fn flight_limit(mut self, limiter: &FlightLimiter) -> Self {
+        self.flight_limiter = Some(limiter.clone());
+        self
+    }
+    
+    /// Start this dispatch only after the one built by `other` has completed
+    ///
+    /// A semaphore is created for the pair: `other` signals it when its
+    /// submission finishes on the GPU and this dispatch waits for it before
+    /// its compute shaders run, so the two may be submitted to different
+    /// queues or workers without a CPU round trip. Calling `after` on several
+    /// builders, or for several dispatches on one builder, expresses any DAG
+    /// of work. Each edge gets its own binary semaphore, which the waiting
+    /// dispatch consumes.
+    ///
+    /// `other` must be submitted before this dispatch is, otherwise
+    /// submitting this one fails with
+    /// [`KronosError::SynchronizationError`].
+    pub fn after(mut self, other: &CommandBuilder) -> Result<Self> {
+        if other.context.device_id() != self.context.device_id() {
+            return Err(KronosError::SynchronizationError(
+                "Cannot order dispatches on different devices".into(),
+            ));
+        }
+        let semaphore = Arc::new(self.context.create_semaphore()?);
+        other.dependents.lock().unwrap().semaphores.push(semaphore.clone());
+        self.waits.push((other.dependents.clone(), semaphore));
+        Ok(self)
+    }
+    
+    /// Count the submission as stages of `progress`
+    ///
+    /// Once the submission completes on the GPU, `progress` has advanced
+    /// by one stage per dispatch chained with [`then`](Self::then). Fails
+    /// if `progress` was created on another device.
+    pub fn progress(mut self, progress: &Progress) -> Result<Self> {
+        if progress.shared.device_id() != self.context.device_id() {
+            return Err(KronosError::SynchronizationError(
+                "Progress belongs to a different device".into(),
+            ));
+        }
+        self.progress = Some(progress.shared.clone());
+        Ok(self)
+    }
+    
+    /// Execute the dispatch and wait for it to complete
     pub fn execute(mut self) -> Result<()> {
-        unsafe {
-            let mut allocated_command_buffer = VkCommandBuffer::NULL;
-            let mut allocated_descriptor_set = VkDescriptorSet::NULL;
-            let has_bindings = !self.bindings.is_empty();
-            #[cfg(feature = "implementation")]
-            let use_persistent_descriptors = has_bindings && self.bindings
-                .iter()
-                .enumerate()
-                .all(|(index, (binding, _))| *binding == index as u32);
-            #[cfg(not(feature = "implementation"))]
-            let use_persistent_descriptors = false;
-
-            let execute_result = self.context.with_inner(|inner| {
-                if inner.device == VkDevice::NULL {
-                    return Err(KronosError::CommandExecutionFailed(
-                        "Compute context has no valid Vulkan device".into(),
-                    ));
-                }
-                if inner.command_pool == VkCommandPool::NULL {
-                    return Err(KronosError::CommandExecutionFailed(
-                        "Compute context has no valid command pool".into(),
-                    ));
+        let _permit = self.flight_limiter.take().map(|limiter| limiter.acquire());
+        self.run(true)?;
+        for callback in std::mem::take(&mut self.callbacks) {
+            callback();
+        }
+        match self.asserts.as_ref().and_then(|asserts| asserts.take()) {
+            Some(failure) => Err(failure.into()),
+            None => Ok(()),
+        }
+    }
+    
+    /// Submit the dispatch without waiting for it to complete
+    ///
+    /// Completion is observed by the reaper thread, which runs the
+    /// [`on_complete`](Self::on_complete) callbacks and recycles the command
+    /// buffer. Buffers and images bound to the dispatch may be dropped before
+    /// it completes; they are destroyed once it has.
+    pub fn submit(mut self) -> Result<()> {
+        // Acquire before taking the context lock so a blocked producer does
+        // not stall other threads; a failed submission drops the permit
+        if let Some(limiter) = self.flight_limiter.take() {
+            let permit = limiter.acquire();
+            self.callbacks.push(Box::new(move || drop(permit)));
+        }
+        if let Some(asserts) = self.asserts.clone() {
+            self.callbacks.push(Box::new(move || asserts.collect()));
+        }
+        if !self.constant_slots.is_empty() {
+            let slots = std::mem::take(&mut self.constant_slots);
+            self.callbacks.push(Box::new(move || drop(slots)));
+        }
+        self.run(false)
+    }
+    
+    /// Record the dispatch into a command buffer without submitting it
+    ///
+    /// For submitting Kronos dispatches by hand alongside commands Kronos
+    /// does not wrap, through [`Queue::submit`] or `vkQueueSubmit`. The
+    /// command buffer comes from the pool of the [`on_queue`](Self::on_queue)
+    /// queue, or of the context's compute queue. Kronos does not see the
+    /// submission, so dispatches with work to do on completion
+    /// ([`on_complete`](Self::on_complete), [`read_back`](Self::read_back),
+    /// [`progress`](Self::progress), device assertions) or ordered with
+    /// [`after`](Self::after) are
+    /// refused, as are worker dispatches and dry runs. GPU timing is not
+    /// recorded.
+    pub fn into_command_buffer(mut self) -> Result<CommandBuffer> {
+        if self.worker.is_some() {
+            return Err(KronosError::CommandExecutionFailed(
+                "Worker dispatches cannot be recorded for manual submission".into(),
+            ));
+        }
+        if !self.callbacks.is_empty() || !self.readbacks.is_empty() || self.asserts.is_some() || self.progress.is_some() {
+            return Err(KronosError::CommandExecutionFailed(
+                "Dispatches with completion work cannot be recorded for manual submission".into(),
+            ));
+        }
+        if !self.waits.is_empty() || !self.dependents.lock().unwrap().semaphores.is_empty() {
+            return Err(KronosError::CommandExecutionFailed(
+                "Dispatches ordered with after cannot be recorded for manual submission".into(),
+            ));
+        }
+        let predicate = self.prepare_predicate()?;
+        let context = self.context.clone();
+        let result = context.with_inner(|inner| unsafe {
+            let pools = inner.pools.lock().unwrap();
+            let (queue, command_pool, queue_family) = self.queue_target(inner, &pools);
+            let mut target = DispatchTarget::new(inner, queue, command_pool, queue_family, pools.descriptors.clone());
+            if target.dry_run {
+                return Err(KronosError::CommandExecutionFailed(
+                    "Dry runs cannot record command buffers for manual submission".into(),
+                ));
+            }
+            // Timestamps are read when the submission completes, which
+            // Kronos does not see
+            target.gpu_timer = None;
+            let mut owned = OwnedObjects::default();
+            match self.record(&target, &mut owned) {
+                Ok(recorded) => {
+                    let command_buffer = CommandBuffer {
+                        context: self.context.clone(),
+                        command_buffer: recorded.command_buffer,
+                        command_pool,
+                        queue_family,
+                        descriptor_set: owned.descriptor_set,
+                        descriptors: target.descriptors.clone(),
+                        _queue_pool: self.target_queue.as_ref().map(|(_, pool, _)| pool.clone()),
+                        _predicate: predicate,
+                        _constants: std::mem::take(&mut self.constant_slots),
+                    };
+                    Ok(command_buffer)
                 }
-                if inner.queue == VkQueue::NULL {
-                    return Err(KronosError::CommandExecutionFailed(
-                        "Compute context has no valid compute queue".into(),
-                    ));
+                Err(e) => {
+                    owned.free(&target);
+                    Err(e)
                 }
-                if self.pipeline.pipeline == VkPipeline::NULL {
-                    return Err(KronosError::CommandExecutionFailed(
-                        "CommandBuilder has no valid compute pipeline".into(),
-                    ));
+            }
+        });
+        self.command_buffer = VkCommandBuffer::NULL;
+        self.descriptor_set = None;
+        result
+    }
+    
+    /// Buffers the dispatch binds, and the references that keep them alive
+    /// while it may run
+    pub(super) fn bound_resources(&self) -> (Vec<VkBuffer>, Vec<Arc<LastUse>>) {
+        let buffers = self.bindings
+            .iter()
+            .map(|(_, bound)| bound.buffer)
+            .chain(self.bound_buffers.iter().map(|&(buffer, _)| buffer))
+            .collect();
+        (buffers, self.uses.clone())
+    }
+    
+    /// Queue, command pool and family the dispatch will be submitted to
+    pub(super) fn queue_target(&self, inner: &ContextInner, pools: &Pools) -> (VkQueue, VkCommandPool, u32) {
+        self.target_queue
+            .as_ref()
+            .map_or((inner.queue, pools.command_pool, inner.queue_family_index), |(queue, pool, family)| (*queue, pool.raw, *family))
+    }
+    
+    /// Execute once per counter pass of a performance query pool
+    ///
+    /// Query 0 of `pool` spans each command buffer; the completion callbacks
+    /// run once after the last pass.
+    pub(super) fn execute_counter_passes(mut self, pool: VkQueryPool, passes: u32) -> Result<()> {
+        let _permit = self.flight_limiter.take().map(|limiter| limiter.acquire());
+        for pass in 0..passes {
+            self.perf_pass = Some((pool, pass));
+            self.run(true)?;
+        }
+        for callback in std::mem::take(&mut self.callbacks) {
+            callback();
+        }
+        Ok(())
+    }
+    
+    /// Execute a long-running dispatch as several shorter submissions
+    ///
+    /// GPU watchdogs reset the device when one submission runs too long
+    /// (TDR on Windows, see [`Quirks`] for other platforms). This splits the
+    /// workgroups along x into chunks expected to finish within
+    /// `split.max_dispatch_duration`, and executes them one after another.
+    /// Before each chunk the progress buffer bound at
+    /// `split.progress_binding` is set to the chunk's first workgroup and
+    /// the total, so the kernel adds the first to `gl_WorkGroupID.x`:
+    ///
+    /// ```glsl
+    /// layout(set = 0, binding = 3) readonly buffer Progress { uint base; uint total; };
+    /// uint group = gl_WorkGroupID.x + base;
+    /// ```
+    ///
+    /// Chunk sizes adapt to the measured time of the previous chunk. Without
+    /// a duration and without a watchdog the dispatch runs in one
+    /// submission. Returns the number of submissions; chained dispatches
+    /// from [`then`](Self::then) cannot be split.
+    pub fn execute_split(mut self, split: &SplitDispatch) -> Result<u32> {
+        if !self.steps.is_empty() {
+            return Err(KronosError::CommandExecutionFailed(
+                "Chained dispatches cannot be split".into(),
+            ));
+        }
+        let max_duration = split
+            .max_dispatch_duration
+            .or_else(|| self.context.quirks().watchdog_timeout.map(|timeout| timeout / 2));
+        let mut progress = self.context.create_buffer_direct(2 * std::mem::size_of::<u32>())?;
+        self = self.bind_buffer(split.progress_binding, &progress);
+        
+        let _permit = self.flight_limiter.take().map(|limiter| limiter.acquire());
+        let (total, y, z) = self.workgroups;
+        let mut estimate = split.workgroup_estimate.max(Duration::from_nanos(1));
+        let (mut base, mut submissions) = (0, 0);
+        while base < total || submissions == 0 {
+            let remaining = total - base;
+            let chunk = match max_duration {
+                Some(max) => (max.as_nanos() / estimate.as_nanos()).max(1).min(remaining as u128) as u32,
+                None => remaining,
+            };
+            progress.write(&[base, total])?;
+            self.workgroups = (chunk, y, z);
+            let start = Instant::now();
+            self.run(true)?;
+            if chunk > 0 {
+                estimate = (start.elapsed() / chunk).max(Duration::from_nanos(1));
+            }
+            base += chunk;
+            submissions += 1;
+        }
+        for callback in std::mem::take(&mut self.callbacks) {
+            callback();
+        }
+        Ok(submissions)
+    }
+    
+    /// Create what the predicate needs before the pools are locked for
+    /// recording, see [`predication`](super::predication)
+    fn prepare_predicate(&mut self) -> Result<Option<Arc<Emulation>>> {
+        let dispatches = self.steps.len() + 1;
+        match self.predicate.as_mut() {
+            Some(predicate) => predicate.prepare(&self.context, dispatches),
+            None => Ok(None),
+        }
+    }
+    
+    fn run(&mut self, wait: bool) -> Result<()> {
+        if let Some(emulation) = self.prepare_predicate()? {
+            self.callbacks.push(Box::new(move || drop(emulation)));
+        }
+        let ranges = self.bound_ranges();
+        let hashes = PendingHashes::before(&self.context, &self.first_pipeline().label, &ranges)?;
+        let links = self.take_links()?;
+        let result = self.run_linked(wait, &links);
+        if !links.signals.is_empty() {
+            let mut dependents = self.dependents.lock().unwrap();
+            if result.is_ok() {
+                dependents.submitted = true;
+            } else {
+                dependents.semaphores.extend(links.signals);
+            }
+        }
+        match hashes {
+            Some(hashes) if result.is_ok() => hashes.after(&self.context, &ranges),
+            _ => result,
+        }
+    }
+    
+    /// Binding, buffer, offset and size of each buffer range bound with
+    /// [`bind_buffer`](Self::bind_buffer) or [`bind_slice`](Self::bind_slice)
+    fn bound_ranges(&self) -> Vec<(u32, VkBuffer, VkDeviceSize, usize)> {
+        let mut ranges: Vec<_> = self.bindings
+            .iter()
+            .map(|(binding, bound)| (*binding, bound.buffer, bound.offset, bound.size))
+            .collect();
+        ranges.sort_by_key(|&(binding, ..)| binding);
+        ranges
+    }
+    
+    /// Semaphores the next submission waits for and signals
+    ///
+    /// Both are handed out once, so only the first counter pass of a
+    /// performance query is linked. A submission that signals semaphores
+    /// keeps them alive until it completes.
+    fn take_links(&mut self) -> Result<SubmissionLinks> {
+        if self.waits.iter().any(|(dependency, _)| !dependency.lock().unwrap().submitted) {
+            return Err(KronosError::SynchronizationError(
+                "A dispatch this one runs after has not been submitted".into(),
+            ));
+        }
+        let links = SubmissionLinks {
+            waits: self.waits.drain(..).map(|(_, semaphore)| semaphore).collect(),
+            signals: std::mem::take(&mut self.dependents.lock().unwrap().semaphores),
+        };
+        if !links.is_empty() {
+            let keep_alive: Vec<Arc<Semaphore>> = links.waits.iter().chain(&links.signals).cloned().collect();
+            self.callbacks.push(Box::new(move || drop(keep_alive)));
+        }
+        Ok(links)
+    }
+    
+    fn run_linked(&mut self, wait: bool, links: &SubmissionLinks) -> Result<()> {
+        if let Some(worker) = self.worker.clone() {
+            return unsafe { self.run_on_worker(&worker, wait, links) };
+        }
+        let context = self.context.clone();
+        let result = context.with_inner(|inner| unsafe {
+            let pools = inner.pools.lock().unwrap();
+            inner.release_retired(&pools);
+            let (queue, command_pool, queue_family) = self.queue_target(inner, &pools);
+            let target = DispatchTarget::new(inner, queue, command_pool, queue_family, pools.descriptors.clone());
+            let mut owned = OwnedObjects::default();
+            let result = match self.record(&target, &mut owned) {
+                Ok(recorded) => self.submit_on_context(inner, &target, recorded.linked(links), wait, &mut owned),
+                Err(e) => Err(e),
+            };
+            owned.free(&target);
+            result
+        });
+        self.command_buffer = VkCommandBuffer::NULL;
+        self.descriptor_set = None;
+        result
+    }
+    
+    /// Record with the worker's pools and submit without holding the
+    /// context lock longer than the queue submission
+    unsafe fn run_on_worker(&mut self, worker: &WorkerShared, wait: bool, links: &SubmissionLinks) -> Result<()> {
+        if self.target_queue.is_some() {
+            return Err(KronosError::CommandExecutionFailed(
+                "Worker dispatches cannot be routed with on_queue".into(),
+            ));
+        }
+        let pools = worker.pools.lock().unwrap();
+        worker.release_retired(&pools);
+        let target = self.context.with_inner(|inner| {
+            DispatchTarget::new(inner, worker.queue, pools.command_pool, worker.queue_family, pools.descriptors.clone())
+        });
+        let mut owned = OwnedObjects::default();
+        let result = match self.record(&target, &mut owned) {
+            Ok(recorded) => self.submit_on_worker(worker, &target, recorded.linked(links), wait, &mut owned),
+            Err(e) => Err(e),
+        };
+        owned.free(&target);
+        drop(pools);
+        self.command_buffer = VkCommandBuffer::NULL;
+        self.descriptor_set = None;
+        result
+    }
+    
+    /// Validate the dispatch and record it into a new command buffer
+    ///
+    /// Objects allocated on the way are noted in `owned`, for the caller to
+    /// release unless a submission takes them over.
+    unsafe fn record(&mut self, target: &DispatchTarget, owned: &mut OwnedObjects) -> Result<Recorded> {
+        let has_bindings = !self.bindings.is_empty() || !self.image_bindings.is_empty() || self.constants.is_some();
+        // Persistent descriptor sets only hold whole storage buffers, and are
+        // not used in strict mode
+        #[cfg(feature = "implementation")]
+        let use_persistent_descriptors = !target.strict && has_bindings && self.image_bindings.is_empty() && self.constants.is_none() && self.bindings
+            .iter()
+            .enumerate()
+            .all(|(index, (binding, buffer))| *binding == index as u32 && buffer.whole);
+        #[cfg(not(feature = "implementation"))]
+        let use_persistent_descriptors = false;
+        let dry_run = target.dry_run;
+        let mut plan = Vec::new();
+        let mut stats = CommandStats::new(self.first_pipeline().label.clone());
+        
+        if target.device == VkDevice::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "Compute context has no valid Vulkan device".into(),
+            ));
+        }
+        if target.command_pool == VkCommandPool::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "Compute context has no valid command pool".into(),
+            ));
+        }
+        if target.queue == VkQueue::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "Compute context has no valid compute queue".into(),
+            ));
+        }
+        let mut bases = self.steps.iter().map(|step| (&step.pipeline, step.base)).chain(std::iter::once((&self.pipeline, self.base)));
+        if let Some((pipeline, _)) = bases.find(|(pipeline, base)| *base != (0, 0, 0) && !pipeline.dispatch_base) {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Kernel '{}' was not created with dispatch_base and cannot start at a workgroup base",
+                pipeline.label
+            )));
+        }
+        let emulated = self.predicate.as_ref().is_some_and(|predicate| predicate.is_emulated());
+        if emulated && self.steps.iter().map(|step| step.base).chain(std::iter::once(self.base)).any(|base| base != (0, 0, 0)) {
+            return Err(KronosError::CommandExecutionFailed(
+                "Dispatches with an emulated predicate cannot start at a workgroup base".into(),
+            ));
+        }
+        let pipelines = self.steps.iter().map(|step| &step.pipeline).chain(std::iter::once(&self.pipeline));
+        for pipeline in pipelines {
+            if pipeline.pipeline == VkPipeline::NULL {
+                return Err(KronosError::CommandExecutionFailed(
+                    "CommandBuilder has no valid compute pipeline".into(),
+                ));
+            }
+            if pipeline.layout == VkPipelineLayout::NULL {
+                return Err(KronosError::CommandExecutionFailed(
+                    "CommandBuilder has no valid pipeline layout".into(),
+                ));
+            }
+            if pipeline.descriptor_set_layout != self.pipeline.descriptor_set_layout
+                && (has_bindings || self.bound_set.is_some())
+            {
+                return Err(KronosError::CommandExecutionFailed(format!(
+                    "Kernels '{}' and '{}' share bindings but not a descriptor set layout",
+                    pipeline.label, self.pipeline.label
+                )));
+            }
+        }
+        if has_bindings && self.pipeline.descriptor_set_layout == VkDescriptorSetLayout::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "Buffer bindings require a valid descriptor set layout".into(),
+            ));
+        }
+        if let Some((_, layout, _)) = &self.bound_set {
+            if has_bindings {
+                return Err(KronosError::CommandExecutionFailed(
+                    "A prepared descriptor set cannot be combined with individual bindings".into(),
+                ));
+            }
+            if *layout != self.pipeline.descriptor_set_layout {
+                return Err(KronosError::CommandExecutionFailed(
+                    "Descriptor set was prepared for a different pipeline layout".into(),
+                ));
+            }
+        }
+        for (binding, descriptor_type, image_info) in &self.image_bindings {
+            if image_info.imageView == VkImageView::NULL {
+                return Err(KronosError::CommandExecutionFailed(format!(
+                    "Binding {} has a NULL Vulkan image view",
+                    binding
+                )));
+            }
+            if *descriptor_type == VkDescriptorType::CombinedImageSampler && image_info.sampler == VkSampler::NULL {
+                return Err(KronosError::CommandExecutionFailed(format!(
+                    "Binding {} has a NULL Vulkan sampler",
+                    binding
+                )));
+            }
+        }
+        for (binding_index, (_, buffer)) in self.bindings.iter().enumerate() {
+            if buffer.buffer == VkBuffer::NULL {
+                return Err(KronosError::CommandExecutionFailed(format!(
+                    "Binding {} has a NULL Vulkan buffer",
+                    binding_index
+                )));
+            }
+        }
+        if target.strict {
+            self.context.with_inner(|inner| self.check_conformance(inner));
+        }
+
+        // Allocate command buffer
+        let alloc_info = VkCommandBufferAllocateInfo {
+            sType: VkStructureType::CommandBufferAllocateInfo,
+            pNext: ptr::null(),
+            commandPool: target.command_pool,
+            level: VkCommandBufferLevel::Primary,
+            commandBufferCount: 1,
+        };
+        
+        let mut command_buffer = VkCommandBuffer::NULL;
+        let result = vkAllocateCommandBuffers(target.device, &alloc_info, &mut command_buffer);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        if command_buffer == VkCommandBuffer::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "vkAllocateCommandBuffers returned NULL".into(),
+            ));
+        }
+        self.command_buffer = command_buffer;
+        owned.command_buffer = command_buffer;
+        
+        // Begin command buffer
+        let begin_info = VkCommandBufferBeginInfo {
+            sType: VkStructureType::CommandBufferBeginInfo,
+            pNext: ptr::null(),
+            flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+            pInheritanceInfo: ptr::null(),
+        };
+        
+        let result = vkBeginCommandBuffer(command_buffer, &begin_info);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        
+        // Strict mode does not rely on Kronos tracking hazards between
+        // submissions: wait for every earlier compute and transfer write
+        if target.strict {
+            let barrier = VkMemoryBarrier {
+                sType: VkStructureType::MemoryBarrier,
+                pNext: ptr::null(),
+                srcAccessMask: VkAccessFlags::SHADER_WRITE | VkAccessFlags::TRANSFER_WRITE,
+                dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+            };
+            let src_stage = VkPipelineStageFlags::COMPUTE_SHADER | VkPipelineStageFlags::TRANSFER;
+            vkCmdPipelineBarrier(
+                command_buffer,
+                src_stage,
+                VkPipelineStageFlags::COMPUTE_SHADER,
+                VkDependencyFlags::empty(),
+                1,
+                &barrier,
+                0,
+                ptr::null(),
+                0,
+                ptr::null(),
+            );
+            stats.barrier();
+            if dry_run {
+                plan.push(PlannedCommand::PipelineBarrier {
+                    src_stage,
+                    dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                    buffers: Vec::new(),
+                });
+            }
+        }
+        
+        // Timestamps need a queue family that supports them; every step of
+        // a chain is timed on its own and attributed to its pipeline
+        let timing: Vec<Option<TimedDispatch>> = match &target.gpu_timer {
+            Some(timer) if target.timestamp_bits > 0 && self.perf_pass.is_none() && !dry_run => self.steps
+                .iter()
+                .map(|step| &step.pipeline)
+                .chain(std::iter::once(&self.pipeline))
+                .map(|pipeline| timer.begin(pipeline.label.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        // Performance queries must enclose every command in the buffer
+        if let Some((pool, _)) = self.perf_pass {
+            vkCmdBeginQuery(command_buffer, pool, 0, 0);
+            stats.command();
+        }
+        
+        // Create and update descriptor set if we have bindings
+        if has_bindings {
+            if use_persistent_descriptors {
+                #[cfg(feature = "implementation")]
+                {
+                    let persistent_buffers: Vec<VkBuffer> = self.bindings
+                        .iter()
+                        .map(|(_, buffer)| buffer.buffer)
+                        .collect();
+                    let (descriptor_set, written) = get_or_write_persistent_descriptor_set(target.device, &persistent_buffers)?;
+                    self.context.with_inner(|inner| inner.counters.descriptor_updates(written));
+                    self.descriptor_set = Some(descriptor_set);
                 }
-                if self.pipeline.layout == VkPipelineLayout::NULL {
+                #[cfg(not(feature = "implementation"))]
+                {
                     return Err(KronosError::CommandExecutionFailed(
-                        "CommandBuilder has no valid pipeline layout".into(),
+                        "Persistent descriptors are not available without implementation feature".into(),
                     ));
                 }
-                if has_bindings && self.pipeline.descriptor_set_layout == VkDescriptorSetLayout::NULL {
+            } else {
+                // Allocate descriptor set
+                let descriptor_set = target.descriptors.allocate(
+                    self.pipeline.descriptor_set_layout,
+                    self.pipeline.layouts.descriptor_counts(),
+                )?;
+                owned.descriptor_set = descriptor_set;
+                
+                // Update descriptor set
+                let buffer_infos: Vec<VkDescriptorBufferInfo> = self.bindings.iter().map(|(_, buffer)| {
+                    VkDescriptorBufferInfo {
+                        buffer: buffer.buffer,
+                        offset: buffer.offset,
+                        range: buffer.size as VkDeviceSize,
+                    }
+                }).collect();
+                
+                let mut writes: Vec<VkWriteDescriptorSet> = self.bindings.iter().enumerate().map(|(i, (binding, _))| {
+                    VkWriteDescriptorSet {
+                        sType: VkStructureType::WriteDescriptorSet,
+                        pNext: ptr::null(),
+                        dstSet: descriptor_set,
+                        dstBinding: *binding,
+                        dstArrayElement: 0,
+                        descriptorCount: 1,
+                        descriptorType: VkDescriptorType::StorageBuffer,
+                        pImageInfo: ptr::null(),
+                        pBufferInfo: &buffer_infos[i],
+                        pTexelBufferView: ptr::null(),
+                    }
+                }).collect();
+                if writes.len() != buffer_infos.len() {
                     return Err(KronosError::CommandExecutionFailed(
-                        "Buffer bindings require a valid descriptor set layout".into(),
+                        "Descriptor write/buffer mismatch".into(),
                     ));
                 }
-                for (binding_index, (_, buffer)) in self.bindings.iter().enumerate() {
-                    if buffer.buffer == VkBuffer::NULL {
-                        return Err(KronosError::CommandExecutionFailed(format!(
-                            "Binding {} has a NULL Vulkan buffer",
-                            binding_index
-                        )));
+                
+                writes.extend(self.image_bindings.iter().map(|(binding, descriptor_type, image_info)| {
+                    VkWriteDescriptorSet {
+                        sType: VkStructureType::WriteDescriptorSet,
+                        pNext: ptr::null(),
+                        dstSet: descriptor_set,
+                        dstBinding: *binding,
+                        dstArrayElement: 0,
+                        descriptorCount: 1,
+                        descriptorType: *descriptor_type,
+                        pImageInfo: image_info,
+                        pBufferInfo: ptr::null(),
+                        pTexelBufferView: ptr::null(),
                     }
+                }));
+                // Each dispatch adds its dynamic offset to the slot at zero
+                let constants_info = self.constants.map(|constants| VkDescriptorBufferInfo {
+                    buffer: constants.buffer,
+                    offset: 0,
+                    range: constants.range,
+                });
+                if let (Some(constants), Some(info)) = (self.constants, &constants_info) {
+                    writes.push(VkWriteDescriptorSet {
+                        sType: VkStructureType::WriteDescriptorSet,
+                        pNext: ptr::null(),
+                        dstSet: descriptor_set,
+                        dstBinding: constants.binding,
+                        dstArrayElement: 0,
+                        descriptorCount: 1,
+                        descriptorType: VkDescriptorType::UniformBufferDynamic,
+                        pImageInfo: ptr::null(),
+                        pBufferInfo: info,
+                        pTexelBufferView: ptr::null(),
+                    });
                 }
+                vkUpdateDescriptorSets(target.device, writes.len() as u32, writes.as_ptr(), 0, ptr::null());
+                self.context.with_inner(|inner| inner.counters.descriptor_updates(writes.len()));
 
-                // Allocate command buffer
-                let alloc_info = VkCommandBufferAllocateInfo {
-                    sType: VkStructureType::CommandBufferAllocateInfo,
+                self.descriptor_set = Some(descriptor_set);
+            }
+        }
+        
+        if let Some((set, _, _)) = &self.bound_set {
+            self.descriptor_set = Some(*set);
+        }
+        
+        // Insert barriers for buffers (smart barrier optimization)
+        let barriers: Vec<VkBufferMemoryBarrier> = self.bindings.iter()
+            .map(|(_, buffer)| (buffer.buffer, buffer.offset, buffer.size as VkDeviceSize))
+            .chain(self.bound_buffers.iter().map(|&(buffer, size)| (buffer, 0, size)))
+            .map(|(buffer, offset, size)| {
+                VkBufferMemoryBarrier {
+                    sType: VkStructureType::BufferMemoryBarrier,
                     pNext: ptr::null(),
-                    commandPool: inner.command_pool,
-                    level: VkCommandBufferLevel::Primary,
-                    commandBufferCount: 1,
-                };
-                
-                let mut command_buffer = VkCommandBuffer::NULL;
-                let result = vkAllocateCommandBuffers(inner.device, &alloc_info, &mut command_buffer);
-                if result != VkResult::Success {
-                    return Err(KronosError::from(result));
+                    srcAccessMask: VkAccessFlags::TRANSFER_WRITE,
+                    dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+                    srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                    dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                    buffer,
+                    offset,
+                    size,
                 }
-                if command_buffer == VkCommandBuffer::NULL {
-                    return Err(KronosError::CommandExecutionFailed(
-                        "vkAllocateCommandBuffers returned NULL".into(),
-                    ));
-                }
-                self.command_buffer = command_buffer;
-                allocated_command_buffer = command_buffer;
-                
-                // Begin command buffer
-                let begin_info = VkCommandBufferBeginInfo {
-                    sType: VkStructureType::CommandBufferBeginInfo,
+            })
+            .collect();
+        
+        if dry_run && !barriers.is_empty() {
+            plan.push(PlannedCommand::PipelineBarrier {
+                src_stage: VkPipelineStageFlags::TOP_OF_PIPE,
+                dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                buffers: barriers.iter().map(|barrier| (barrier.buffer, barrier.size)).collect(),
+            });
+        }
+        if !barriers.is_empty() {
+            vkCmdPipelineBarrier(
+                command_buffer,
+                VkPipelineStageFlags::TOP_OF_PIPE,
+                VkPipelineStageFlags::COMPUTE_SHADER,
+                VkDependencyFlags::empty(),
+                0,
+                ptr::null(),
+                barriers.len() as u32,
+                barriers.as_ptr(),
+                0,
+                ptr::null(),
+            );
+            stats.barrier();
+        }
+        
+        // Earlier submissions may still be reading or writing these buffers
+        let hazards: Vec<VkBufferMemoryBarrier> = self.hazards
+            .iter()
+            .map(|&(buffer, size)| VkBufferMemoryBarrier {
+                sType: VkStructureType::BufferMemoryBarrier,
+                pNext: ptr::null(),
+                srcAccessMask: VkAccessFlags::SHADER_WRITE,
+                dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+                srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                buffer,
+                offset: 0,
+                size,
+            })
+            .collect();
+        if !hazards.is_empty() {
+            vkCmdPipelineBarrier(
+                command_buffer,
+                VkPipelineStageFlags::COMPUTE_SHADER,
+                VkPipelineStageFlags::COMPUTE_SHADER,
+                VkDependencyFlags::empty(),
+                0,
+                ptr::null(),
+                hazards.len() as u32,
+                hazards.as_ptr(),
+                0,
+                ptr::null(),
+            );
+            stats.barrier();
+            if dry_run {
+                plan.push(PlannedCommand::PipelineBarrier {
+                    src_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                    dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                    buffers: hazards.iter().map(|barrier| (barrier.buffer, barrier.size)).collect(),
+                });
+            }
+        }
+        // Accesses declared outside Kronos; a dry run leaves them for the real one
+        let mut external_stages = VkPipelineStageFlags::empty();
+        let external: Vec<VkBufferMemoryBarrier> = self.external
+            .iter()
+            .filter_map(|(buffer, size, access)| {
+                let (stage, src_access) = if dry_run { access.pending() } else { access.take() }?;
+                external_stages |= stage;
+                Some(VkBufferMemoryBarrier {
+                    sType: VkStructureType::BufferMemoryBarrier,
+                    pNext: ptr::null(),
+                    srcAccessMask: src_access,
+                    dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+                    srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                    dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+                    buffer: *buffer,
+                    offset: 0,
+                    size: *size,
+                })
+            })
+            .collect();
+        if !external.is_empty() {
+            vkCmdPipelineBarrier(
+                command_buffer,
+                external_stages,
+                VkPipelineStageFlags::COMPUTE_SHADER,
+                VkDependencyFlags::empty(),
+                0,
+                ptr::null(),
+                external.len() as u32,
+                external.as_ptr(),
+                0,
+                ptr::null(),
+            );
+            stats.barrier();
+            if dry_run {
+                plan.push(PlannedCommand::PipelineBarrier {
+                    src_stage: external_stages,
+                    dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                    buffers: external.iter().map(|barrier| (barrier.buffer, barrier.size)).collect(),
+                });
+            }
+        }
+        let acquire = record_ownership_transfers(command_buffer, target.queue_family, &self.ownership_transfers, OwnershipTransfer::Acquire);
+        if acquire.is_some() {
+            stats.barrier();
+        }
+        if let Some(acquire) = acquire.filter(|_| dry_run) {
+            plan.push(acquire);
+        }
+        if let Some(predicate) = &self.predicate {
+            let workgroups: Vec<(u32, u32, u32)> = self.steps
+                .iter()
+                .map(|step| step.workgroups)
+                .chain(std::iter::once(self.workgroups))
+                .collect();
+            predicate.record_begin(command_buffer, &workgroups, &mut stats, dry_run.then_some(&mut plan));
+        }
+        
+        let steps = self.steps
+            .iter()
+            .map(|step| (&step.pipeline, &step.push_constants, step.constants_offset, step.workgroups, step.base))
+            .chain(std::iter::once((&self.pipeline, &self.push_constants, self.constants_offset, self.workgroups, self.base)));
+        let mut state = BindState::default();
+        for (index, (pipeline, push_constants, constants_offset, (x, y, z), base)) in steps.enumerate() {
+            // Later dispatches wait for the shader writes of the previous one
+            if index > 0 {
+                let barrier = VkMemoryBarrier {
+                    sType: VkStructureType::MemoryBarrier,
                     pNext: ptr::null(),
-                    flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
-                    pInheritanceInfo: ptr::null(),
+                    srcAccessMask: VkAccessFlags::SHADER_WRITE,
+                    dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
                 };
-                
-                let result = vkBeginCommandBuffer(command_buffer, &begin_info);
-                if result != VkResult::Success {
-                    return Err(KronosError::from(result));
-                }
-                
-                // Create and update descriptor set if we have bindings
-                if has_bindings {
-                    if use_persistent_descriptors {
-                        #[cfg(feature = "implementation")]
-                        {
-                            let persistent_buffers: Vec<VkBuffer> = self.bindings
-                                .iter()
-                                .map(|(_, buffer)| buffer.buffer)
-                                .collect();
-                            let descriptor_set = get_persistent_descriptor_set(inner.device, &persistent_buffers)?;
-                            self.descriptor_set = Some(descriptor_set);
-                        }
-                        #[cfg(not(feature = "implementation"))]
-                        {
-                            return Err(KronosError::CommandExecutionFailed(
-                                "Persistent descriptors are not available without implementation feature".into(),
-                            ));
-                        }
-                    } else {
-                        // Allocate descriptor set
-                        let alloc_info = VkDescriptorSetAllocateInfo {
-                            sType: VkStructureType::DescriptorSetAllocateInfo,
-                            pNext: ptr::null(),
-                            descriptorPool: inner.descriptor_pool,
-                            descriptorSetCount: 1,
-                            pSetLayouts: &self.pipeline.descriptor_set_layout,
-                        };
-                        
-                        let mut descriptor_set = VkDescriptorSet::NULL;
-                        let result = vkAllocateDescriptorSets(inner.device, &alloc_info, &mut descriptor_set);
-                        if result != VkResult::Success {
-                            return Err(KronosError::from(result));
-                        }
-                        if descriptor_set == VkDescriptorSet::NULL {
-                            return Err(KronosError::CommandExecutionFailed(
-                                "vkAllocateDescriptorSets returned NULL".into(),
-                            ));
-                        }
-                        
-                        // Update descriptor set
-                        let buffer_infos: Vec<VkDescriptorBufferInfo> = self.bindings.iter().map(|(_, buffer)| {
-                            VkDescriptorBufferInfo {
-                                buffer: buffer.buffer,
-                                offset: 0,
-                                range: buffer.size as VkDeviceSize,
-                            }
-                        }).collect();
-                        
-                        let writes: Vec<VkWriteDescriptorSet> = self.bindings.iter().enumerate().map(|(i, (binding, _))| {
-                            VkWriteDescriptorSet {
-                                sType: VkStructureType::WriteDescriptorSet,
-                                pNext: ptr::null(),
-                                dstSet: descriptor_set,
-                                dstBinding: *binding,
-                                dstArrayElement: 0,
-                                descriptorCount: 1,
-                                descriptorType: VkDescriptorType::StorageBuffer,
-                                pImageInfo: ptr::null(),
-                                pBufferInfo: &buffer_infos[i],
-                                pTexelBufferView: ptr::null(),
-                            }
-                        }).collect();
-                        if writes.len() != buffer_infos.len() {
-                            return Err(KronosError::CommandExecutionFailed(
-                                "Descriptor write/buffer mismatch".into(),
-                            ));
-                        }
-                        vkUpdateDescriptorSets(inner.device, writes.len() as u32, writes.as_ptr(), 0, ptr::null());
-
-                        allocated_descriptor_set = descriptor_set;
-                        self.descriptor_set = Some(descriptor_set);
-                    }
+                vkCmdPipelineBarrier(
+                    command_buffer,
+                    VkPipelineStageFlags::COMPUTE_SHADER,
+                    VkPipelineStageFlags::COMPUTE_SHADER,
+                    VkDependencyFlags::empty(),
+                    1,
+                    &barrier,
+                    0,
+                    ptr::null(),
+                    0,
+                    ptr::null(),
+                );
+                stats.barrier();
+                if dry_run {
+                    plan.push(PlannedCommand::PipelineBarrier {
+                        src_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                        dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                        buffers: Vec::new(),
+                    });
                 }
-                
-                // Insert barriers for buffers (smart barrier optimization)
-                let barriers: Vec<VkBufferMemoryBarrier> = self.bindings.iter().map(|(_, buffer)| {
-                    VkBufferMemoryBarrier {
-                        sType: VkStructureType::BufferMemoryBarrier,
-                        pNext: ptr::null(),
-                        srcAccessMask: VkAccessFlags::TRANSFER_WRITE,
-                        dstAccessMask: VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
-                        srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
-                        dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
-                        buffer: buffer.buffer,
-                        offset: 0,
-                        size: buffer.size as VkDeviceSize,
-                    }
-                }).collect();
-                
-                if !barriers.is_empty() {
-                    vkCmdPipelineBarrier(
-                        command_buffer,
-                        VkPipelineStageFlags::TOP_OF_PIPE,
-                        VkPipelineStageFlags::COMPUTE_SHADER,
-                        VkDependencyFlags::empty(),
-                        0,
-                        ptr::null(),
-                        barriers.len() as u32,
-                        barriers.as_ptr(),
-                        0,
-                        ptr::null(),
-                    );
+            }
+            
+            let step_timing = timing.get(index).and_then(Option::as_ref);
+            if let Some(timing) = step_timing {
+                timing.write_begin(command_buffer);
+                stats.command();
+                stats.command();
+            }
+            
+            // Bind pipeline
+            if state.bind_pipeline(pipeline.pipeline) {
+                vkCmdBindPipeline(command_buffer, VkPipelineBindPoint::Compute, pipeline.pipeline);
+                stats.command();
+                if dry_run {
+                    plan.push(PlannedCommand::BindPipeline { pipeline: pipeline.pipeline });
                 }
-                
-                // Bind pipeline
-                vkCmdBindPipeline(command_buffer, VkPipelineBindPoint::Compute, self.pipeline.pipeline);
-                
-                // Bind descriptor set
-                if let Some(descriptor_set) = self.descriptor_set {
+            }
+            
+            // Bind descriptor set
+            if let Some(descriptor_set) = self.descriptor_set {
+                let dynamic_offset = self.constants.map(|_| constants_offset);
+                if state.bind_descriptor_set(pipeline.layout, descriptor_set, dynamic_offset) {
+                    let prepared_offsets;
+                    let dynamic_offsets = match (&dynamic_offset, &self.bound_set) {
+                        (Some(offset), _) => std::slice::from_ref(offset),
+                        // A prepared set binds its dynamic buffers from their start
+                        (None, Some((_, _, dynamic_bindings))) => {
+                            prepared_offsets = vec![0; *dynamic_bindings as usize];
+                            &prepared_offsets[..]
+                        }
+                        (None, None) => &[][..],
+                    };
                     vkCmdBindDescriptorSets(
                         command_buffer,
                         VkPipelineBindPoint::Compute,
-                        self.pipeline.layout,
+                        pipeline.layout,
                         0,
                         1,
                         &descriptor_set,
-                        0,
-                        ptr::null(),
+                        dynamic_offsets.len() as u32,
+                        dynamic_offsets.as_ptr(),
                     );
+                    stats.command();
+                    if dry_run {
+                        let buffers = self.bindings.iter().map(|(binding, buffer)| {
+                            (*binding, PlannedResource::Buffer { buffer: buffer.buffer, size: buffer.size as VkDeviceSize })
+                        });
+                        let images = self.image_bindings.iter().map(|(binding, descriptor_type, image_info)| {
+                            (*binding, PlannedResource::Image { view: image_info.imageView, descriptor_type: *descriptor_type })
+                        });
+                        let constants = self.constants.map(|constants| {
+                            (constants.binding, PlannedResource::Constants {
+                                buffer: constants.buffer,
+                                offset: constants_offset,
+                                size: constants.range,
+                            })
+                        });
+                        plan.push(PlannedCommand::BindDescriptorSet {
+                            set: descriptor_set,
+                            persistent: use_persistent_descriptors,
+                            bindings: buffers.chain(images).chain(constants).collect(),
+                        });
+                    }
                 }
-                
-                // Push constants
-                if !self.push_constants.is_empty() {
-                    vkCmdPushConstants(
-                        command_buffer,
-                        self.pipeline.layout,
-                        VkShaderStageFlags::COMPUTE,
-                        0,
-                        self.push_constants.len() as u32,
-                        self.push_constants.as_ptr() as *const _,
-                    );
+            }
+            
+            // Push constants
+            if !push_constants.is_empty() && state.push_constants(pipeline.layout, push_constants) {
+                vkCmdPushConstants(
+                    command_buffer,
+                    pipeline.layout,
+                    VkShaderStageFlags::COMPUTE,
+                    0,
+                    push_constants.len() as u32,
+                    push_constants.as_ptr() as *const _,
+                );
+                stats.push_constants(push_constants.len());
+                if dry_run {
+                    plan.push(PlannedCommand::PushConstants { data: push_constants.clone() });
                 }
-                
-                // Dispatch
-                vkCmdDispatch(command_buffer, self.workgroups.0, self.workgroups.1, self.workgroups.2);
-                
-                // End command buffer
-                let result = vkEndCommandBuffer(command_buffer);
-                if result != VkResult::Success {
-                    return Err(KronosError::from(result));
+            }
+            
+            // Dispatch
+            if let Some((buffer, offset)) = self.predicate.as_ref().and_then(|predicate| predicate.indirect(index)) {
+                vkCmdDispatchIndirect(command_buffer, buffer, offset);
+                if dry_run {
+                    plan.push(PlannedCommand::DispatchIndirect { buffer, offset });
                 }
-                
-                // Submit (with timeline batching optimization)
-                let submit_info = VkSubmitInfo {
-                    sType: VkStructureType::SubmitInfo,
-                    pNext: ptr::null(),
-                    waitSemaphoreCount: 0,
-                    pWaitSemaphores: ptr::null(),
-                    pWaitDstStageMask: ptr::null(),
-                    commandBufferCount: 1,
-                    pCommandBuffers: &command_buffer,
-                    signalSemaphoreCount: 0,
-                    pSignalSemaphores: ptr::null(),
-                };
-                
-                let result = vkQueueSubmit(inner.queue, 1, &submit_info, VkFence::NULL);
-                if result != VkResult::Success {
-                    return Err(KronosError::CommandExecutionFailed(
-                        format!("vkQueueSubmit failed: {:?}", result)
+            } else if base == (0, 0, 0) {
+                vkCmdDispatch(command_buffer, x, y, z);
+                if dry_run {
+                    plan.push(PlannedCommand::Dispatch { x, y, z });
+                }
+            } else {
+                vkCmdDispatchBase(command_buffer, base.0, base.1, base.2, x, y, z);
+                if dry_run {
+                    plan.push(PlannedCommand::DispatchBase { base, x, y, z });
+                }
+            }
+            stats.dispatch(x, y, z);
+            if let Some(timing) = step_timing {
+                timing.write_end(command_buffer);
+                stats.command();
+            }
+        }
+        if let Some(predicate) = &self.predicate {
+            predicate.record_end(command_buffer, &mut stats, dry_run.then_some(&mut plan));
+        }
+        if let Some((pool, _)) = self.perf_pass {
+            vkCmdEndQuery(command_buffer, pool, 0);
+            stats.command();
+        }
+        if !dry_run && !self.readbacks.is_empty() {
+            self.record_readbacks(command_buffer, &mut stats);
+        }
+        let release = record_ownership_transfers(command_buffer, target.queue_family, &self.ownership_transfers, OwnershipTransfer::Release);
+        if release.is_some() {
+            stats.barrier();
+        }
+        if let Some(release) = release.filter(|_| dry_run) {
+            plan.push(release);
+        }
+        
+        // End command buffer
+        let result = vkEndCommandBuffer(command_buffer);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        
+        Ok(Recorded {
+            command_buffer,
+            timing: timing.into_iter().flatten().collect(),
+            plan,
+            stats,
+            perf_submit: self.perf_pass.map(|(_, pass)| VkPerformanceQuerySubmitInfoKHR {
+                counterPassIndex: pass,
+                ..Default::default()
+            }),
+            wait_semaphores: Vec::new(),
+            wait_stages: Vec::new(),
+            signal_semaphores: Vec::new(),
+            signal_values: Vec::new(),
+            timeline_submit: None,
+        })
+    }
+    
+    /// Copy the readback sources into their channels' free slots
+    ///
+    /// Each copy claims its slot now, and hands it to readers once the
+    /// submission completes.
+    unsafe fn record_readbacks(&mut self, command_buffer: VkCommandBuffer, stats: &mut CommandStats) {
+        let memory_barrier = |src_access, dst_access, src_stage, dst_stage| {
+            let barrier = VkMemoryBarrier {
+                sType: VkStructureType::MemoryBarrier,
+                pNext: ptr::null(),
+                srcAccessMask: src_access,
+                dstAccessMask: dst_access,
+            };
+            vkCmdPipelineBarrier(
+                command_buffer,
+                src_stage,
+                dst_stage,
+                VkDependencyFlags::empty(),
+                1,
+                &barrier,
+                0,
+                ptr::null(),
+                0,
+                ptr::null(),
+            );
+        };
+        memory_barrier(
+            VkAccessFlags::SHADER_WRITE,
+            VkAccessFlags::TRANSFER_READ,
+            VkPipelineStageFlags::COMPUTE_SHADER,
+            VkPipelineStageFlags::TRANSFER,
+        );
+        stats.barrier();
+        for (source, channel) in &self.readbacks {
+            let (iteration, slot, offset) = channel.claim();
+            let region = VkBufferCopy {
+                srcOffset: 0,
+                dstOffset: offset,
+                size: channel.size() as VkDeviceSize,
+            };
+            vkCmdCopyBuffer(command_buffer, *source, channel.buffer(&self.context), 1, &region);
+            stats.command();
+            let channel = channel.clone();
+            self.callbacks.push(Box::new(move || channel.complete(iteration, slot)));
+        }
+        memory_barrier(
+            VkAccessFlags::TRANSFER_WRITE,
+            VkAccessFlags::HOST_READ,
+            VkPipelineStageFlags::TRANSFER,
+            VkPipelineStageFlags::HOST,
+        );
+        stats.barrier();
+    }
+    
+    /// Report what stock Vulkan would reject in the dispatches
+    fn check_conformance(&self, inner: &ContextInner) {
+        let max_workgroups = inner.device_properties.limits.maxComputeWorkGroupCount;
+        let label = &*self.first_pipeline().label;
+        for (binding, buffer) in &self.bindings {
+            if !buffer.storage {
+                inner.report_conformance(label, format!("buffer at binding {} lacks STORAGE_BUFFER usage", binding));
+            }
+        }
+        let steps = self.steps
+            .iter()
+            .map(|step| (&step.pipeline, &step.push_constants, step.workgroups))
+            .chain(std::iter::once((&self.pipeline, &self.push_constants, self.workgroups)));
+        for (pipeline, push_constants, (x, y, z)) in steps {
+            let label = &*pipeline.label;
+            let range = pipeline.layouts.push_constant_size() as usize;
+            if push_constants.len() > range {
+                inner.report_conformance(label, format!(
+                    "pushes {} bytes of constants, beyond the layout's {} byte range",
+                    push_constants.len(),
+                    range
+                ));
+            }
+            for ((axis, count), max) in ["x", "y", "z"].into_iter().zip([x, y, z]).zip(max_workgroups) {
+                if count > max {
+                    inner.report_conformance(label, format!(
+                        "dispatches {} workgroups along {}, the device allows {}",
+                        count, axis, max
                     ));
                 }
-                
-                // Wait for completion
-                let result = vkQueueWaitIdle(inner.queue);
-                if result != VkResult::Success {
-                    return Err(KronosError::SynchronizationError(format!(
-                        "vkQueueWaitIdle failed: {:?}",
-                        result
-                    )));
+            }
+            for (binding, _) in &self.bindings {
+                match pipeline.layouts.descriptor_type(*binding) {
+                    Some(VkDescriptorType::StorageBuffer) => {}
+                    Some(descriptor_type) => inner.report_conformance(label, format!(
+                        "binds a storage buffer to {:?} binding {}",
+                        descriptor_type, binding
+                    )),
+                    None => inner.report_conformance(label, format!(
+                        "binds binding {}, which the pipeline layout does not declare",
+                        binding
+                    )),
                 }
-                
-                Ok(())
-            });
+            }
+        }
+    }
+    
+    /// Dry run: keep the plan and release everything as if the dispatch had
+    /// completed, without running the callbacks
+    fn planned_dispatch(&mut self, target: &DispatchTarget, plan: Vec<PlannedCommand>, wait: bool) -> PlannedDispatch {
+        self.callbacks.clear();
+        PlannedDispatch {
+            label: self.first_pipeline().label.clone(),
+            queue_family: target.queue_family,
+            blocking: wait,
+            commands: plan,
+        }
+    }
+    
+    /// Submit a recorded dispatch with the context's pool lock held throughout
+    unsafe fn submit_on_context(
+        &mut self,
+        inner: &ContextInner,
+        target: &DispatchTarget,
+        mut recorded: Recorded,
+        wait: bool,
+        owned: &mut OwnedObjects,
+    ) -> Result<()> {
+        if target.dry_run {
+            let dispatch = self.planned_dispatch(target, recorded.plan, wait);
+            inner.planned.lock().unwrap().push(dispatch);
+            return Ok(());
+        }
+        
+        // Submit (with timeline batching optimization)
+        self.advance_progress(&mut recorded);
+        let submit_info = recorded.submit_info();
+        let _queue = inner.queue_lock.lock().unwrap();
+        let serial = inner.deferred.begin_submission(&self.uses);
+        if !wait {
+            let fence = create_fence(target.device)?;
+            let result = inner.device_events.check(inner.queue_submit(target.queue, 1, &submit_info, fence));
+            if result != VkResult::Success {
+                vkDestroyFence(target.device, fence, ptr::null());
+                return Err(KronosError::CommandExecutionFailed(
+                    format!("vkQueueSubmit failed: {:?}", result)
+                ));
+            }
+            recorded.record_batch(target);
+            inner.record_command_stats(recorded.stats.clone());
+            self.callbacks.push(Box::new(move || drop(serial)));
+            self.track(inner.reaper(), target, recorded, fence, owned);
+            return Ok(());
+        }
+        
+        let result = inner.device_events.check(inner.queue_submit(target.queue, 1, &submit_info, VkFence::NULL));
+        if result != VkResult::Success {
+            return Err(KronosError::CommandExecutionFailed(
+                format!("vkQueueSubmit failed: {:?}", result)
+            ));
+        }
+        recorded.record_batch(target);
+        inner.record_command_stats(recorded.stats);
+        
+        // Wait for completion
+        let result = inner.device_events.check(vkQueueWaitIdle(target.queue));
+        if result != VkResult::Success {
+            return Err(KronosError::SynchronizationError(format!(
+                "vkQueueWaitIdle failed: {:?}",
+                result
+            )));
+        }
+        for timing in recorded.timing {
+            timing.finish(target.timestamp_bits);
+        }
+        
+        Ok(())
+    }
+    
+    /// Submit a recorded dispatch from a worker, waiting on a fence outside
+    /// the context lock
+    unsafe fn submit_on_worker(
+        &mut self,
+        worker: &WorkerShared,
+        target: &DispatchTarget,
+        mut recorded: Recorded,
+        wait: bool,
+        owned: &mut OwnedObjects,
+    ) -> Result<()> {
+        if target.dry_run {
+            let dispatch = self.planned_dispatch(target, recorded.plan, wait);
+            self.context.with_inner(|inner| inner.planned.lock().unwrap().push(dispatch));
+            return Ok(());
+        }
+        
+        let fence = create_fence(target.device)?;
+        let serial = self.context.with_inner(|inner| inner.deferred.begin_submission(&self.uses));
+        self.advance_progress(&mut recorded);
+        let result = worker.submit(&recorded.submit_info(), fence);
+        if result != VkResult::Success {
+            vkDestroyFence(target.device, fence, ptr::null());
+            return Err(KronosError::CommandExecutionFailed(
+                format!("vkQueueSubmit failed: {:?}", result)
+            ));
+        }
+        recorded.record_batch(target);
+        self.context.with_inner(|inner| inner.record_command_stats(recorded.stats.clone()));
+        if !wait {
+            self.callbacks.push(Box::new(move || drop(serial)));
+            self.track(worker.reaper(), target, recorded, fence, owned);
+            return Ok(());
+        }
+        
+        let result = worker.wait(fence);
+        vkDestroyFence(target.device, fence, ptr::null());
+        if result != VkResult::Success {
+            return Err(KronosError::SynchronizationError(format!(
+                "vkWaitForFences failed: {:?}",
+                result
+            )));
+        }
+        for timing in recorded.timing {
+            timing.finish(target.timestamp_bits);
+        }
+        
+        Ok(())
+    }
+    
+    /// Signal the next stages of the builder's progress, if any, and report
+    /// them once the submission has completed
+    fn advance_progress(&mut self, recorded: &mut Recorded) {
+        let Some(progress) = self.progress.clone() else { return };
+        let value = progress.next_value(self.steps.len() as u64 + 1);
+        recorded.signal_timeline(progress.semaphore(), value);
+        self.callbacks.push(Box::new(move || progress.observe()));
+    }
+    
+    /// Hand a submitted dispatch to `reaper`, which now owns its command
+    /// buffer and descriptor set
+    fn track(&mut self, reaper: &Reaper, target: &DispatchTarget, recorded: Recorded, fence: VkFence, owned: &mut OwnedObjects) {
+        if !recorded.timing.is_empty() {
+            let (timing, timestamp_bits) = (recorded.timing, target.timestamp_bits);
+            self.callbacks.insert(0, Box::new(move || timing.into_iter().for_each(|timing| timing.finish(timestamp_bits))));
+        }
+        reaper.track(
+            SubmissionResources {
+                fence,
+                command_pool: target.command_pool,
+                command_buffer: recorded.command_buffer,
+                descriptor_set: owned.descriptor_set,
+                _queue_pool: self.target_queue.as_ref().map(|(_, pool, _)| pool.clone()),
+            },
+            std::mem::take(&mut self.callbacks),
+        );
+        *owned = OwnedObjects::default();
+    }
+}
+
+/// A dispatch recorded by [`CommandBuilder::into_command_buffer`], for
+/// submitting by hand
+///
+/// The command buffer and its descriptor set are freed when this is
+/// dropped.
+pub struct CommandBuffer {
+    context: ComputeContext,
+    command_buffer: VkCommandBuffer,
+    command_pool: VkCommandPool,
+    queue_family: u32,
+    /// Set allocated for the dispatch's bindings, if not a persistent one
+    descriptor_set: VkDescriptorSet,
+    descriptors: Arc<DescriptorPools>,
+    /// Pool of the [`Queue`] recorded for, kept until the command buffer
+    /// is freed
+    _queue_pool: Option<Arc<QueueCommandPool>>,
+    /// Objects an emulated predicate records with
+    _predicate: Option<Arc<Emulation>>,
+    /// Ring slots the constants were written to
+    _constants: Vec<ConstantsSlot>,
+}
 
+impl CommandBuffer {
+    /// Get the raw Vulkan command buffer handle (for advanced usage)
+    ///
+    /// The command buffer is fully recorded. Submissions of it must
+    /// complete before it is dropped.
+    pub fn raw(&self) -> VkCommandBuffer {
+        self.command_buffer
+    }
+    
+    /// Queue family the command buffer may be submitted to
+    pub fn queue_family_index(&self) -> u32 {
+        self.queue_family
+    }
+}
+
+impl Drop for CommandBuffer {
+    fn drop(&mut self) {
+        unsafe {
             self.context.with_inner(|inner| {
-                if allocated_command_buffer != VkCommandBuffer::NULL {
-                    vkFreeCommandBuffers(inner.device, inner.command_pool, 1, &allocated_command_buffer);
-                }
-                if allocated_descriptor_set != VkDescriptorSet::NULL {
-                    vkFreeDescriptorSets(inner.device, inner.descriptor_pool, 1, &allocated_descriptor_set);
+                let _pools = inner.pools.lock().unwrap();
+                vkFreeCommandBuffers(inner.device, self.command_pool, 1, &self.command_buffer);
+                if self.descriptor_set != VkDescriptorSet::NULL {
+                    self.descriptors.free(self.descriptor_set);
                 }
             });
-            self.command_buffer = VkCommandBuffer::NULL;
-            self.descriptor_set = None;
-            execute_result
         }
     }
 }
+
+/// Device objects a dispatch is recorded with and submitted to
+struct DispatchTarget {
+    device: VkDevice,
+    queue: VkQueue,
+    command_pool: VkCommandPool,
+    descriptors: Arc<DescriptorPools>,
+    queue_family: u32,
+    /// `timestampValidBits` of the queue family
+    timestamp_bits: u32,
+    gpu_timer: Option<Arc<GpuTimer>>,
+    dry_run: bool,
+    /// Strict conformance mode, see [`conformance`](super::conformance)
+    strict: bool,
+}
+
+impl DispatchTarget {
+    fn new(
+        inner: &ContextInner,
+        queue: VkQueue,
+        command_pool: VkCommandPool,
+        queue_family: u32,
+        descriptors: Arc<DescriptorPools>,
+    ) -> Self {
+        Self {
+            device: inner.device,
+            queue,
+            command_pool,
+            descriptors,
+            queue_family,
+            timestamp_bits: inner.queue_families
+                .get(queue_family as usize)
+                .map_or(0, |family| family.timestampValidBits),
+            gpu_timer: inner.gpu_timer.get().cloned(),
+            dry_run: inner.dry_run.load(Ordering::Acquire),
+            strict: inner.strict,
+        }
+    }
+}
+
+/// Objects allocated for a dispatch that no submission has taken over yet
+struct OwnedObjects {
+    command_buffer: VkCommandBuffer,
+    descriptor_set: VkDescriptorSet,
+}
+
+impl Default for OwnedObjects {
+    fn default() -> Self {
+        Self {
+            command_buffer: VkCommandBuffer::NULL,
+            descriptor_set: VkDescriptorSet::NULL,
+        }
+    }
+}
+
+impl OwnedObjects {
+    /// # Safety
+    ///
+    /// The objects must have been allocated from the pools of `target`, and
+    /// those pools must be externally synchronized by the caller.
+    unsafe fn free(&mut self, target: &DispatchTarget) {
+        if self.command_buffer != VkCommandBuffer::NULL {
+            vkFreeCommandBuffers(target.device, target.command_pool, 1, &self.command_buffer);
+        }
+        if self.descriptor_set != VkDescriptorSet::NULL {
+            target.descriptors.free(self.descriptor_set);
+        }
+        *self = Self::default();
+    }
+}
+
+/// A fully recorded dispatch, ready to submit
+struct Recorded {
+    command_buffer: VkCommandBuffer,
+    /// Timestamp slots of the timed steps
+    timing: Vec<TimedDispatch>,
+    /// Commands recorded in dry-run mode
+    plan: Vec<PlannedCommand>,
+    stats: CommandStats,
+    perf_submit: Option<VkPerformanceQuerySubmitInfoKHR>,
+    /// Semaphores from [`CommandBuilder::after`]
+    wait_semaphores: Vec<VkSemaphore>,
+    wait_stages: Vec<VkPipelineStageFlags>,
+    signal_semaphores: Vec<VkSemaphore>,
+    /// Values of `signal_semaphores`, once a timeline semaphore is among them
+    signal_values: Vec<u64>,
+    timeline_submit: Option<VkTimelineSemaphoreSubmitInfo>,
+}
+
+impl Recorded {
+    /// Count the submission in the timing report's batching, if any of its
+    /// dispatches are timed
+    fn record_batch(&self, target: &DispatchTarget) {
+        if let (Some(timer), false) = (&target.gpu_timer, self.timing.is_empty()) {
+            timer.record_submission(self.stats.dispatches as usize);
+        }
+    }
+
+    /// Wait for and signal the semaphores of `links` on submission
+    fn linked(mut self, links: &SubmissionLinks) -> Self {
+        self.wait_semaphores = links.waits.iter().map(|semaphore| semaphore.raw()).collect();
+        self.wait_stages = vec![VkPipelineStageFlags::COMPUTE_SHADER; links.waits.len()];
+        self.signal_semaphores = links.signals.iter().map(|semaphore| semaphore.raw()).collect();
+        self
+    }
+    
+    /// Also signal timeline `semaphore` with `value`
+    fn signal_timeline(&mut self, semaphore: VkSemaphore, value: u64) {
+        // Binary semaphores ignore their values
+        self.signal_values.resize(self.signal_semaphores.len(), 0);
+        self.signal_semaphores.push(semaphore);
+        self.signal_values.push(value);
+        self.timeline_submit = Some(VkTimelineSemaphoreSubmitInfo::default());
+    }
+    
+    /// Submit info pointing into `self`, which must not move while it is used
+    fn submit_info(&mut self) -> VkSubmitInfo {
+        let mut submit_info = VkSubmitInfo {
+            commandBufferCount: 1,
+            pCommandBuffers: &self.command_buffer,
+            waitSemaphoreCount: self.wait_semaphores.len() as u32,
+            pWaitSemaphores: self.wait_semaphores.as_ptr(),
+            pWaitDstStageMask: self.wait_stages.as_ptr(),
+            signalSemaphoreCount: self.signal_semaphores.len() as u32,
+            pSignalSemaphores: self.signal_semaphores.as_ptr(),
+            ..Default::default()
+        };
+        let mut chain = Chain::new(&mut submit_info);
+        if let Some(perf_submit) = self.perf_submit.as_mut() {
+            chain = chain.push(perf_submit);
+        }
+        if let Some(timeline_submit) = self.timeline_submit.as_mut() {
+            timeline_submit.signalSemaphoreValueCount = self.signal_values.len() as u32;
+            timeline_submit.pSignalSemaphoreValues = self.signal_values.as_ptr();
+            chain.push(timeline_submit);
+        }
+        submit_info
+    }
+}
+
+/// Create an unsignaled fence
+///
+/// # Safety
+///
+/// The device must be a valid VkDevice handle
+pub(super) unsafe fn create_fence(device: VkDevice) -> Result<VkFence> {
+    let fence_info = VkFenceCreateInfo {
+        sType: VkStructureType::FenceCreateInfo,
+        pNext: ptr::null(),
+        flags: VkFenceCreateFlags::empty(),
+    };
+    let mut fence = VkFence::NULL;
+    let result = vkCreateFence(device, &fence_info, ptr::null(), &mut fence);
+    if result != VkResult::Success {
+        return Err(KronosError::SynchronizationError(
+            format!("vkCreateFence failed: {:?}", result)
+        ));
+    }
+    Ok(fence)
+}
@@ -0,0 +1,91 @@
+//! Strict Vulkan conformance mode
+//!
+//! Kronos takes liberties stock Vulkan does not: consecutive submissions
+//! touching the same buffers are not separated by barriers unless a
+//! hazard is known, buffer bindings are served from descriptor sets
+//! persisted across submissions, and transfers and bindings are recorded
+//! without checking the usage the buffers were created with. Code that
+//! leans on these will misbehave when ported to a plain Vulkan loader.
+//!
+//! A context built with
+//! [`strict_conformance`](super::ContextBuilder::strict_conformance) turns
+//! the reorderings off: every command buffer starts with a barrier on
+//! earlier compute and transfer writes, and descriptor sets are allocated
+//! and written per submission from the pipeline's own layout. Builders are
+//! submitted as recorded, strict or not. It also checks each dispatch and
+//! copy against what stock Vulkan allows and reports every violation as a
+//! [`ConformanceIssue`], logged as a warning and kept until
+//! [`take_conformance_issues`](ComputeContext::take_conformance_issues).
+//! The work still runs, so a test suite can run once in strict mode and
+//! assert that no issues were found:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::builder().strict_conformance(true).build()?;
+//! let data = ctx.create_buffer(&[1.0f32; 1024])?;
+//! data.read::<f32>()?;
+//! // create_buffer buffers lack TRANSFER_SRC usage, so the read is reported
+//! for issue in ctx.take_conformance_issues() {
+//!     eprintln!("{}", issue);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Strict mode checks usage Kronos can see: buffer usage, bindings
+//! against the pipeline layout, push constant ranges and workgroup counts.
+//! It is not a replacement for the validation layers.
+
+use super::*;
+use super::context::ContextInner;
+use std::fmt;
+
+/// A use of the API stock Vulkan would not allow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceIssue {
+    /// Pipeline label, or the transfer, the issue was found in
+    pub label: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConformanceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.label, self.message)
+    }
+}
+
+impl ContextInner {
+    /// Record a conformance issue; does nothing outside strict mode
+    pub(super) fn report_conformance(&self, label: &str, message: String) {
+        if !self.strict {
+            return;
+        }
+        kronos_log!(Warn, "[SAFE API] Not conformant to stock Vulkan: {}: {}", label, message);
+        self.conformance_issues.lock().unwrap().push(ConformanceIssue { label: label.to_string(), message });
+    }
+
+    /// Report a copy between buffers lacking transfer usage
+    pub(super) fn check_copy_usage(&self, src: BufferUsage, dst: BufferUsage) {
+        if !src.flags.contains(VkBufferUsageFlags::TRANSFER_SRC) {
+            self.report_conformance("copy", "source buffer lacks TRANSFER_SRC usage".into());
+        }
+        if !dst.flags.contains(VkBufferUsageFlags::TRANSFER_DST) {
+            self.report_conformance("copy", "destination buffer lacks TRANSFER_DST usage".into());
+        }
+    }
+}
+
+impl ComputeContext {
+    /// Whether the context was built in strict conformance mode
+    pub fn is_strict(&self) -> bool {
+        self.with_inner(|inner| inner.strict)
+    }
+
+    /// Take the conformance issues found so far; always empty outside
+    /// strict mode
+    pub fn take_conformance_issues(&self) -> Vec<ConformanceIssue> {
+        self.with_inner(|inner| std::mem::take(&mut *inner.conformance_issues.lock().unwrap()))
+    }
+}
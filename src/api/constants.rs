@@ -0,0 +1,258 @@
+//! Parameter blocks of any size, pushed when they fit
+//!
+//! Push constants are the cheapest way to hand a kernel its parameters,
+//! but a device only has [`max_push_constants_size`] bytes of them, as
+//! little as 128. A [`Constants<T>`] passes a block `T` of any size with
+//! the same host code:
+//!
+//! ```no_run
+//! use kronos_compute::api::{ComputeContext, PipelineConfig};
+//!
+//! #[repr(C)]
+//! #[derive(Clone, Copy)]
+//! struct Filter {
+//!     taps: [[f32; 4]; 16],
+//!     count: u32,
+//! }
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let shader = ctx.load_shader("filter.spv")?;
+//! let constants = ctx.create_constants::<Filter>(2)?;
+//! let pipeline = ctx.create_pipeline_with_constants(&shader, PipelineConfig::default(), &constants)?;
+//! let (input, output) = (ctx.create_buffer(&[0.0f32; 1024])?, ctx.create_buffer_uninit(4096)?);
+//! let filter = Filter { taps: [[0.25; 4]; 16], count: 1024 };
+//! ctx.dispatch(&pipeline)
+//!     .bind_buffer(0, &input)
+//!     .bind_buffer(1, &output)
+//!     .constants(&constants, &filter)?
+//!     .workgroups(16, 1, 1)
+//!     .execute()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The kernel declares `T` as its push constant block. When `T` fits the
+//! limit it stays one, and [`CommandBuilder::constants`] pushes it. When it
+//! does not, [`create_pipeline_with_constants`] creates the pipeline from a
+//! copy of the kernel whose block is a dynamic uniform buffer at the
+//! binding the `Constants` was created with. Each dispatch then writes its
+//! value into a free slot of a ring of host-visible uniform memory and
+//! binds that slot by its dynamic offset.
+//!
+//! A slot is reused once the submission reading it has completed, or once
+//! a builder that never ran is dropped. Setting constants fails while every
+//! slot is in flight, so give the ring at least as many slots as dispatches
+//! in flight with [`create_constants_with_slots`].
+//!
+//! Uniform blocks follow std140 rules, which push constants do not: arrays,
+//! matrices and nested structs in a block that may not fit must be 16-byte
+//! aligned with 16-byte strides, or [`create_pipeline_with_constants`]
+//! fails. All dispatches of one builder take their constants from one
+//! `Constants`.
+//!
+//! [`max_push_constants_size`]: ComputeContext::max_push_constants_size
+//! [`create_pipeline_with_constants`]: ComputeContext::create_pipeline_with_constants
+//! [`create_constants_with_slots`]: ComputeContext::create_constants_with_slots
+
+use super::*;
+use super::deferred::LastUse;
+use crate::implementation::pool_allocator::UNTAGGED;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Ring slots of a [`Constants`] from [`ComputeContext::create_constants`]
+pub const DEFAULT_CONSTANTS_SLOTS: u32 = 64;
+
+/// Least `maxUniformBufferRange` Vulkan guarantees
+const MIN_UNIFORM_BUFFER_RANGE: usize = 16384;
+
+/// A kernel parameter block of type `T`, see [`constants`](self)
+pub struct Constants<T> {
+    binding: u32,
+    /// Uniform slots, if `T` is too large to push
+    ring: Option<Arc<ConstantsRing>>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+/// Host-visible uniform buffer split into slots of one block each
+pub(super) struct ConstantsRing {
+    /// Mapped for each write
+    buffer: Mutex<Buffer>,
+    bound: BoundConstants,
+    last_use: Arc<LastUse>,
+    /// Distance between slots, a multiple of `minUniformBufferOffsetAlignment`
+    stride: usize,
+    busy: Vec<AtomicBool>,
+    /// Slot the next search starts at
+    next: AtomicUsize,
+}
+
+/// Uniform buffer range bound to a builder's constants binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BoundConstants {
+    pub(super) binding: u32,
+    pub(super) buffer: VkBuffer,
+    /// Size of one block
+    pub(super) range: VkDeviceSize,
+}
+
+/// A ring slot holding a dispatch's constants, freed on drop
+pub(super) struct ConstantsSlot {
+    ring: Arc<ConstantsRing>,
+    index: usize,
+}
+
+impl ConstantsSlot {
+    /// Dynamic offset of the slot
+    pub(super) fn offset(&self) -> u32 {
+        (self.index * self.ring.stride) as u32
+    }
+
+    pub(super) fn bound(&self) -> BoundConstants {
+        self.ring.bound
+    }
+
+    pub(super) fn last_use(&self) -> Arc<LastUse> {
+        self.ring.last_use.clone()
+    }
+}
+
+impl Drop for ConstantsSlot {
+    fn drop(&mut self) {
+        self.ring.busy[self.index].store(false, Ordering::Release);
+    }
+}
+
+impl<T: Copy + 'static> Constants<T> {
+    /// Whether blocks are passed as push constants rather than through the
+    /// uniform ring
+    pub fn is_pushed(&self) -> bool {
+        self.ring.is_none()
+    }
+
+    /// Binding the block moves to when it is not pushed
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    /// Slots of the uniform ring, 0 when blocks are pushed
+    pub fn slots(&self) -> u32 {
+        self.ring.as_ref().map_or(0, |ring| ring.busy.len() as u32)
+    }
+
+    /// Write `value` into a free slot, or return `None` if blocks are pushed
+    pub(super) fn stage(&self, value: &T) -> Result<Option<ConstantsSlot>> {
+        let Some(ring) = &self.ring else {
+            return Ok(None);
+        };
+        let slots = ring.busy.len();
+        let start = ring.next.fetch_add(1, Ordering::Relaxed);
+        let index = (start..start + slots)
+            .map(|index| index % slots)
+            .find(|&index| !ring.busy[index].swap(true, Ordering::Acquire))
+            .ok_or_else(|| {
+                KronosError::CommandExecutionFailed(format!("All {} constants slots are in flight", slots))
+            })?;
+        let slot = ConstantsSlot { ring: ring.clone(), index };
+        let mut buffer = ring.buffer.lock().unwrap();
+        match buffer.try_map_direct()? {
+            Some(mut mapping) => mapping.write(slot.offset() as usize, std::slice::from_ref(value))?,
+            None => {
+                return Err(KronosError::BufferCreationFailed(
+                    "Constants ring is not host-visible".into(),
+                ))
+            }
+        }
+        Ok(Some(slot))
+    }
+}
+
+impl ComputeContext {
+    /// Create constants of type `T` with [`DEFAULT_CONSTANTS_SLOTS`] ring
+    /// slots, moved to `binding` if `T` cannot be pushed
+    pub fn create_constants<T: Copy + 'static>(&self, binding: u32) -> Result<Constants<T>> {
+        self.create_constants_with_slots(binding, DEFAULT_CONSTANTS_SLOTS)
+    }
+
+    /// Create constants of type `T`, moved to `binding` of a ring with
+    /// `slots` slots if `T` cannot be pushed
+    pub fn create_constants_with_slots<T: Copy + 'static>(&self, binding: u32, slots: u32) -> Result<Constants<T>> {
+        let size = std::mem::size_of::<T>();
+        if size <= self.max_push_constants_size() as usize {
+            return Ok(Constants { binding, ring: None, _marker: PhantomData });
+        }
+        if size > MIN_UNIFORM_BUFFER_RANGE {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Constants of {} bytes exceed the {} byte uniform buffer range every device supports",
+                size, MIN_UNIFORM_BUFFER_RANGE
+            )));
+        }
+        if slots == 0 {
+            return Err(KronosError::BufferCreationFailed("Constants need at least one ring slot".into()));
+        }
+
+        // Vulkan requires the alignment to be a power of two
+        let alignment = self.min_uniform_buffer_offset_alignment() as usize;
+        let stride = (size + alignment - 1) & !(alignment - 1);
+        let usage = BufferUsage { flags: VkBufferUsageFlags::UNIFORM_BUFFER };
+        let coherent = VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT;
+        let buffer = unsafe { self.create_buffer_with_memory(stride * slots as usize, usage, &[coherent], UNTAGGED)? };
+        let bound = BoundConstants {
+            binding,
+            buffer: buffer.buffer.on(self.device_id()),
+            range: size as VkDeviceSize,
+        };
+        Ok(Constants {
+            binding,
+            ring: Some(Arc::new(ConstantsRing {
+                last_use: buffer.last_use.clone(),
+                buffer: Mutex::new(buffer),
+                bound,
+                stride,
+                busy: (0..slots).map(|_| AtomicBool::new(false)).collect(),
+                next: AtomicUsize::new(0),
+            })),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Create a pipeline whose kernel takes its push constant block through
+    /// `constants`
+    ///
+    /// Sets `config`'s push constant size, or, if `T` is not pushed, adds
+    /// the dynamic uniform buffer binding and creates the pipeline from a
+    /// copy of the kernel reading the block from it.
+    pub fn create_pipeline_with_constants<T: Copy + 'static>(
+        &self,
+        shader: &Shader,
+        mut config: PipelineConfig,
+        constants: &Constants<T>,
+    ) -> Result<Pipeline> {
+        let size = std::mem::size_of::<T>();
+        let block = shader.interface(&config.entry_point)?.push_constants.ok_or_else(|| {
+            KronosError::ShaderCompilationFailed(format!("Kernel '{}' has no push constant block", config.entry_point))
+        })?;
+        if block.size as usize > size {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "Kernel's push constant block is {} bytes, larger than the {} byte constants",
+                block.size, size
+            )));
+        }
+        if constants.is_pushed() {
+            config.push_constant_size = size as u32;
+            return self.create_pipeline_with_config(shader, config);
+        }
+
+        let words = reflect::push_constants_to_uniform(shader.spirv(), constants.binding)?;
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        let shader = self.create_shader_from_spirv(&bytes)?;
+        config.push_constant_size = 0;
+        config.bindings.push(BufferBinding {
+            binding: constants.binding,
+            descriptor_type: VkDescriptorType::UniformBufferDynamic,
+        });
+        self.create_pipeline_with_config(&shader, config)
+    }
+}
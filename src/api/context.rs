@@ -7,18 +7,39 @@ use crate::implementation::initialize_kronos;
 // Explicitly import Vulkan functions from implementation when available
 #[cfg(feature = "implementation")]
 use crate::implementation::{
-    vkCreateInstance, vkDestroyInstance, vkEnumeratePhysicalDevices,
-    vkGetPhysicalDeviceProperties, vkGetPhysicalDeviceMemoryProperties,
-    vkGetPhysicalDeviceQueueFamilyProperties,
-    vkCreateDevice, vkDestroyDevice, vkGetDeviceQueue,
-    vkCreateDescriptorPool, vkDestroyDescriptorPool,
+    vkEnumerateInstanceVersion, vkEnumerateInstanceExtensionProperties, vkCreateInstance, vkDestroyInstance, vkEnumeratePhysicalDevices,
+    vkCreateDevice, vkDestroyDevice, vkGetDeviceQueue, vkGetDeviceQueue2,
     vkCreateCommandPool, vkDestroyCommandPool,
 };
-use std::ffi::CString;
+use std::ffi::{c_char, CStr, CString};
 use std::ptr;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, OnceLock};
+use super::devices::{self, DeviceInfo, DriverInfo, PciBusAddress};
+use super::events::DeviceEvents;
+use super::owned::DeviceId;
+use super::reaper::Reaper;
+use super::deferred::DeferredDestruction;
+use super::upload::{UploadRing, DEFAULT_UPLOAD_RING_SIZE};
+use super::hashing::HashTrace;
+use super::descriptor_pools::{DescriptorPools, DEFAULT_DESCRIPTOR_POOL_SETS};
+use super::plan::PlannedDispatch;
+use super::stats::{CommandStatsLog, ContextCounters};
+use super::pipeline::{LayoutCache, ShaderModuleCache};
+use super::timing::GpuTimer;
+use super::plugin::Plugin;
+use super::validation::Validation;
 #[cfg(feature = "implementation")]
 use crate::implementation::persistent_descriptors::cleanup_persistent_descriptors;
+use crate::implementation::{icd_loader, pool_allocator};
+
+/// Instance API versions Kronos can drive, highest first
+const SUPPORTED_API_VERSIONS: &[u32] = &[
+    VK_API_VERSION_1_3,
+    VK_API_VERSION_1_2,
+    VK_API_VERSION_1_1,
+    VK_API_VERSION_1_0,
+];
 
 const SUPPORTED_VULKAN_VENDORS: &[(u32, &str)] = &[
     (0x10DE, "NVIDIA"),
@@ -31,16 +52,183 @@ pub(super) struct ContextInner {
     pub(super) instance: VkInstance,
     pub(super) physical_device: VkPhysicalDevice,
     pub(super) device: VkDevice,
+    /// Identity tagged onto every object created on `device`
+    pub(super) id: DeviceId,
     pub(super) queue: VkQueue,
     pub(super) queue_family_index: u32,
+    /// Queue family properties of the physical device, indexed by family
+    pub(super) queue_families: Vec<VkQueueFamilyProperties>,
+    /// Global priorities of each queue family, empty where unknown
+    pub(super) global_priorities: Vec<Vec<VkQueueGlobalPriorityKHR>>,
+    /// Negotiated instance API version passed in VkApplicationInfo
+    pub(super) api_version: u32,
     
     // Optimization managers
-    pub(super) descriptor_pool: VkDescriptorPool,
-    pub(super) command_pool: VkCommandPool,
+    /// Default command and descriptor pools; the lock also guards the
+    /// command pools of queues from `create_queue`
+    pub(super) pools: Mutex<Pools>,
+    /// Serializes submissions and waits on the device's queues, which
+    /// Vulkan requires to be externally synchronized
+    pub(super) queue_lock: Mutex<()>,
     
     // Device properties
     pub(super) device_properties: VkPhysicalDeviceProperties,
     pub(super) memory_properties: VkPhysicalDeviceMemoryProperties,
+    /// Alignment of storage buffer offsets bound to a descriptor
+    pub(super) min_storage_buffer_offset_alignment: VkDeviceSize,
+    /// Workgroup memory one workgroup may use, in bytes
+    pub(super) max_compute_shared_memory_size: u32,
+    /// Alignment of dynamic uniform buffer offsets
+    pub(super) min_uniform_buffer_offset_alignment: VkDeviceSize,
+    /// Largest push constant range a pipeline layout may declare, in bytes
+    pub(super) max_push_constants_size: u32,
+    /// Driver identification, if the device reports it
+    pub(super) driver: Option<DriverInfo>,
+    /// `deviceUUID`, if the device reports it
+    pub(super) device_uuid: Option<[u8; VK_UUID_SIZE]>,
+    /// PCI address, if the device reports it
+    pub(super) pci_bus: Option<PciBusAddress>,
+    pub(super) enabled_features: Features,
+    /// Completion reaper, spawned on first non-blocking submission
+    pub(super) reaper: OnceLock<Reaper>,
+    /// Placement of the reaper threads of the context and its workers
+    pub(super) threads: ThreadConfig,
+    /// Buffers and images dropped while submissions may still use them
+    pub(super) deferred: Arc<DeferredDestruction>,
+    /// Mapped staging ring for small uploads, created on first upload or
+    /// by `prewarm`; `None` if the device has no host-coherent pool to put
+    /// it in
+    pub(super) upload_ring: OnceLock<Option<UploadRing>>,
+    /// Size of the upload ring in bytes
+    pub(super) staging_capacity: u64,
+    /// Sets a new descriptor pool has room for at least
+    pub(super) descriptor_pool_sets: u32,
+    /// Timestamp queries, created by the first `enable_gpu_timing`
+    pub(super) gpu_timer: OnceLock<Arc<GpuTimer>>,
+    /// Whether VK_KHR_performance_query was enabled on the device
+    pub(super) performance_query: bool,
+    /// Handle types memory can be imported from, per the enabled extensions
+    pub(super) external_memory: VkExternalMemoryHandleTypeFlags,
+    /// Whether VK_KHR_external_semaphore_fd was enabled on the device
+    pub(super) external_semaphores: bool,
+    /// Whether VK_EXT_pipeline_creation_feedback was enabled on the device
+    pub(super) pipeline_creation_feedback: bool,
+    /// Whether VK_EXT_memory_priority was enabled, see `residency`
+    pub(super) memory_priority: bool,
+    /// Whether VK_EXT_conditional_rendering was enabled, see
+    /// `CommandBuilder::predicated_on`
+    pub(super) conditional_rendering: bool,
+    /// Whether vkCmdDispatchBase is available (Vulkan 1.1 instance and device)
+    pub(super) dispatch_base: bool,
+    /// Whether queues are fetched with vkGetDeviceQueue2 (Vulkan 1.1
+    /// instance and device)
+    pub(super) device_queue2: bool,
+    /// Whether the timelineSemaphore feature was enabled (Vulkan 1.2
+    /// instance and device)
+    pub(super) timeline_semaphores: bool,
+    /// Routes validation layer messages into the log, if validation is on
+    pub(super) debug_messenger: VkDebugUtilsMessengerEXT,
+    /// Whether the validation layer's debug printf is on, see `validation`
+    pub(super) debug_printf: bool,
+    /// Strict conformance mode, see `conformance`
+    pub(super) strict: bool,
+    /// Reported by `take_conformance_issues`
+    pub(super) conformance_issues: Mutex<Vec<ConformanceIssue>>,
+    /// Record dispatches without submitting them
+    pub(super) dry_run: AtomicBool,
+    /// Dispatches recorded in dry-run mode, drained by `take_command_listing`
+    pub(super) planned: Mutex<Vec<PlannedDispatch>>,
+    /// Shader modules shared by shaders created from the same SPIR-V
+    pub(super) shader_modules: Mutex<ShaderModuleCache>,
+    /// Descriptor set and pipeline layouts shared by pipelines with the
+    /// same bindings
+    pub(super) layouts: Mutex<LayoutCache>,
+    /// Buffer hashes around dispatches, see `hashing`
+    pub(super) hashes: Mutex<HashTrace>,
+    /// Size of submitted command buffers, reported by `command_stats`
+    pub(super) command_stats: Mutex<CommandStatsLog>,
+    /// Running totals reported by `stats_snapshot`
+    pub(super) counters: ContextCounters,
+    /// Device loss detection and `on_device_event` callbacks
+    pub(super) device_events: Arc<DeviceEvents>,
+    /// Plugin replacing buffer allocation or queue submission
+    pub(super) plugin: Option<Arc<Plugin>>,
+}
+
+impl ContextInner {
+    /// Get the completion reaper, spawning its thread on first use
+    pub(super) fn reaper(&self) -> &Reaper {
+        self.reaper.get_or_init(|| Reaper::spawn(self.device, self.device_events.clone(), &self.threads))
+    }
+    
+    /// Get the upload ring, creating it on first use
+    pub(super) fn upload_ring(&self) -> Option<&UploadRing> {
+        self.upload_ring.get_or_init(|| unsafe { UploadRing::new(self.device, self.staging_capacity) }).as_ref()
+    }
+    
+    /// Largest upload staged through the upload ring, a quarter of its
+    /// capacity
+    pub(super) fn upload_limit(&self) -> usize {
+        (self.staging_capacity / 4) as usize
+    }
+    
+    /// Release GPU objects of submissions the reaper has seen complete
+    ///
+    /// Takes the guard of `self.pools`, since the command and descriptor
+    /// pools require external synchronization.
+    pub(super) fn release_retired(&self, pools: &Pools) {
+        let Some(reaper) = self.reaper.get() else { return };
+        for resources in reaper.take_retired() {
+            unsafe { resources.release(self.device, &pools.descriptors) };
+        }
+    }
+}
+
+/// A command pool and the descriptor pools, destroyed on drop
+pub(super) struct Pools {
+    pub(super) device: VkDevice,
+    pub(super) command_pool: VkCommandPool,
+    /// Shared with recorded command buffers that free their set on drop
+    pub(super) descriptors: Arc<DescriptorPools>,
+}
+
+impl Pools {
+    /// Create the command pool for `queue_family_index`; descriptor pools
+    /// of at least `descriptor_sets` sets are created as sets need them
+    ///
+    /// # Safety
+    ///
+    /// The device must be a valid VkDevice handle and the queue family must
+    /// exist on it
+    pub(super) unsafe fn new(device: VkDevice, queue_family_index: u32, descriptor_sets: u32) -> Result<Self> {
+        let mut pools = Self {
+            device,
+            command_pool: VkCommandPool::NULL,
+            descriptors: Arc::new(DescriptorPools::new(device, descriptor_sets)),
+        };
+        pools.command_pool = ComputeContext::create_command_pool(device, queue_family_index)?;
+        kronos_log!(Info, "[SAFE API] Command pool created: {:?}", pools.command_pool);
+        Ok(pools)
+    }
+
+    /// Destroy all pools; safe to call more than once
+    ///
+    /// # Safety
+    ///
+    /// No command buffer or descriptor set from the pools may be in use
+    pub(super) unsafe fn destroy(&mut self) {
+        if self.command_pool != VkCommandPool::NULL {
+            vkDestroyCommandPool(self.device, self.command_pool, ptr::null());
+            self.command_pool = VkCommandPool::NULL;
+        }
+        self.descriptors.destroy();
+    }
+}
+
+impl Drop for Pools {
+    fn drop(&mut self) {
+        unsafe { self.destroy() }
+    }
 }
 
 /// Main context for compute operations
@@ -50,7 +238,7 @@ pub(super) struct ContextInner {
 /// methods to create buffers, pipelines, and execute commands.
 #[derive(Clone)]
 pub struct ComputeContext {
-    pub(super) inner: Arc<Mutex<ContextInner>>,
+    pub(super) inner: Arc<ContextInner>,
 }
 
 // Send + Sync for thread safety
@@ -59,28 +247,35 @@ unsafe impl Sync for ComputeContext {}
 
 impl ComputeContext {
     pub(super) fn new_with_config(config: ContextConfig) -> Result<Self> {
-        log::info!("[SAFE API] ComputeContext::new_with_config() called");
+        if let Some(verbosity) = config.log_verbosity {
+            crate::implementation::logging::set_log_verbosity(verbosity);
+        }
+        kronos_log!(Info, "[SAFE API] ComputeContext::new_with_config() called");
         unsafe {
             // Apply preferred ICD selection BEFORE initialization
             // This is crucial - preferences must be set before initialize_kronos()
-            log::info!("[SAFE API] Applying ICD preferences");
+            kronos_log!(Info, "[SAFE API] Applying ICD preferences");
+            for dir in &config.library_search_dirs {
+                crate::implementation::icd_loader::add_library_search_dir(dir.clone());
+            }
             if let Some(ref p) = config.preferred_icd_path {
-                log::info!("[SAFE API] Setting preferred ICD path: {:?}", p);
+                kronos_log!(Info, "[SAFE API] Setting preferred ICD path: {:?}", crate::implementation::logging::redact(p));
                 crate::implementation::icd_loader::set_preferred_icd_path(p.clone());
             } else if let Some(i) = config.preferred_icd_index {
-                log::info!("[SAFE API] Setting preferred ICD index: {}", i);
+                kronos_log!(Info, "[SAFE API] Setting preferred ICD index: {}", i);
                 crate::implementation::icd_loader::set_preferred_icd_index(i);
             }
 
             // Initialize Kronos ICD loader
-            log::info!("[SAFE API] Initializing Kronos ICD loader");
-            log::info!("[SAFE API] KRONOS_AGGREGATE_ICD = {:?}", std::env::var("KRONOS_AGGREGATE_ICD").ok());
+            kronos_log!(Info, "[SAFE API] Initializing Kronos ICD loader");
+            kronos_log!(Info, "[SAFE API] KRONOS_AGGREGATE_ICD = {:?}", std::env::var("KRONOS_AGGREGATE_ICD").ok());
             initialize_kronos()
                 .map_err(|e| {
-                    log::error!("[SAFE API] Failed to initialize Kronos: {:?}", e);
+                    kronos_log!(Error, "[SAFE API] Failed to initialize Kronos: {:?}", e);
                     KronosError::InitializationFailed(e.to_string())
                 })?;
-            log::info!("[SAFE API] Kronos initialized successfully");
+            kronos_log!(Info, "[SAFE API] Kronos initialized successfully");
+            let plugin = config.plugin.as_ref().map(Plugin::load).transpose()?.map(Arc::new);
 
             let preferred_vendor_id = match config.preferred_vendor.as_deref() {
                 Some(vendor) if !vendor.trim().is_empty() => {
@@ -89,79 +284,201 @@ impl ComputeContext {
                 _ => None,
             };
             
+            // Negotiate instance API version
+            let mut instance_version = VK_API_VERSION_1_0;
+            let result = vkEnumerateInstanceVersion(&mut instance_version);
+            if result != VkResult::Success {
+                kronos_log!(Warn, "[SAFE API] vkEnumerateInstanceVersion failed ({:?}), assuming Vulkan 1.0", result);
+                instance_version = VK_API_VERSION_1_0;
+            }
+            let api_version = match config.api_version {
+                Some(requested) => Self::requested_api_version(instance_version, requested)?,
+                None => Self::negotiate_api_version(instance_version),
+            };
+            kronos_log!(Info, 
+                "[SAFE API] Instance version 0x{:x}, negotiated API version 0x{:x}",
+                instance_version, api_version
+            );
+            
             // Create instance
-            log::info!("[SAFE API] Creating Vulkan instance");
-            let instance = Self::create_instance(&config)?;
-            log::info!("[SAFE API] Instance created: {:?}", instance);
+            kronos_log!(Info, "[SAFE API] Creating Vulkan instance");
+            let validation = Validation::configure(&config, &Self::instance_extensions());
+            let instance = Self::create_instance(&config, api_version, &validation)?;
+            kronos_log!(Info, "[SAFE API] Instance created: {:?}", instance);
+            let debug_messenger = validation.create_messenger(instance);
             
             // Find compute-capable device
-            log::info!("[SAFE API] Finding compute-capable device");
-            let (physical_device, queue_family_index) = Self::find_compute_device(instance, preferred_vendor_id)?;
-            log::info!("[SAFE API] Found device: {:?}, queue family: {}", physical_device, queue_family_index);
-            
-            log::info!("[SAFE API] find_compute_device returned successfully");
+            kronos_log!(Info, "[SAFE API] Finding compute-capable device");
+            let (physical_device, queue_family_index, device_info) = Self::find_compute_device(instance, preferred_vendor_id, &config)?;
+            kronos_log!(Info, "[SAFE API] Found device: {:?}, queue family: {}", physical_device, queue_family_index);
             
-            // Get device properties
-            log::info!("[SAFE API] Getting device properties");
-            let mut device_properties = VkPhysicalDeviceProperties::default();
-            vkGetPhysicalDeviceProperties(physical_device, &mut device_properties);
-            log::info!("[SAFE API] Got device properties successfully");
+            kronos_log!(Info, "[SAFE API] find_compute_device returned successfully");
             
-            let mut memory_properties = VkPhysicalDeviceMemoryProperties::default();
-            log::info!("[SAFE API] Getting memory properties");
-            vkGetPhysicalDeviceMemoryProperties(physical_device, &mut memory_properties);
-            log::info!("[SAFE API] Got memory properties successfully");
+            let device_properties = device_info.properties;
+            let memory_properties = device_info.memory_properties;
             
             // Log selected device info
             // deviceName is a fixed-size array, ensure it's null-terminated
             let device_name = Self::describe_device_name(&device_properties);
-            let device_type_str = Self::describe_device_type(device_properties.deviceType);
+            let device_type_str = Self::describe_device_type(device_properties.device_type());
             let vendor_name = Self::vendor_name(device_properties.vendorID).unwrap_or("Unknown Vendor");
-            log::info!("Selected Vulkan device: {} ({})", device_name, device_type_str);
+            kronos_log!(Info, "Selected Vulkan device: {} ({})", device_name, device_type_str);
             if Self::is_supported_vendor(device_properties.vendorID) {
-                log::info!("Selected vendor: {} (0x{:04x})", vendor_name, device_properties.vendorID);
+                kronos_log!(Info, "Selected vendor: {} (0x{:04x})", vendor_name, device_properties.vendorID);
             }
+            let queue_families = device_info.queue_families.clone();
+            let global_priorities = device_info.global_priorities.clone();
             
-            // Create logical device
-            log::info!("[SAFE API] Creating logical device");
-            let (device, queue) = Self::create_device(physical_device, queue_family_index)?;
-            log::info!("[SAFE API] Device created: {:?}, queue: {:?}", device, queue);
+            let performance_query = config.performance_counters
+                && device_info.supports_extension(VK_KHR_PERFORMANCE_QUERY_EXTENSION_NAME);
+            if config.performance_counters && !performance_query {
+                kronos_log!(Warn, "[SAFE API] Performance counters requested but {} is not supported", VK_KHR_PERFORMANCE_QUERY_EXTENSION_NAME);
+            }
+            let mut extensions = Vec::new();
+            if performance_query {
+                extensions.push(VK_KHR_PERFORMANCE_QUERY_EXTENSION_NAME);
+            }
+            let external_memory = Self::external_memory_handle_types(&device_info, config.external_memory);
+            if external_memory.contains(VkExternalMemoryHandleTypeFlags::OPAQUE_FD) {
+                extensions.push(VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME);
+            }
+            if external_memory.contains(VkExternalMemoryHandleTypeFlags::DMA_BUF_EXT) {
+                extensions.push(VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME);
+            }
+            let external_semaphores = config.external_memory
+                && device_info.supports_extension(VK_KHR_EXTERNAL_SEMAPHORE_FD_EXTENSION_NAME);
+            if external_semaphores {
+                extensions.push(VK_KHR_EXTERNAL_SEMAPHORE_FD_EXTENSION_NAME);
+            }
+            let pipeline_creation_feedback = device_info.supports_extension(VK_EXT_PIPELINE_CREATION_FEEDBACK_EXTENSION_NAME);
+            if pipeline_creation_feedback {
+                extensions.push(VK_EXT_PIPELINE_CREATION_FEEDBACK_EXTENSION_NAME);
+            }
+            let memory_priority = device_info.supports_extension(VK_EXT_MEMORY_PRIORITY_EXTENSION_NAME);
+            if memory_priority {
+                extensions.push(VK_EXT_MEMORY_PRIORITY_EXTENSION_NAME);
+                // Builds on memory priorities, so only enabled alongside them
+                if device_info.supports_extension(VK_EXT_PAGEABLE_DEVICE_LOCAL_MEMORY_EXTENSION_NAME) {
+                    extensions.push(VK_EXT_PAGEABLE_DEVICE_LOCAL_MEMORY_EXTENSION_NAME);
+                }
+            }
+            let conditional_rendering = device_info.supports_extension(VK_EXT_CONDITIONAL_RENDERING_EXTENSION_NAME);
+            if conditional_rendering {
+                extensions.push(VK_EXT_CONDITIONAL_RENDERING_EXTENSION_NAME);
+            }
+            // Core features are usable up to the lower of the two versions
+            let device_api_version = api_version.min(device_properties.apiVersion & !0xFFF);
+            // VK_KHR_device_group would also need VK_KHR_device_group_creation
+            // on the instance, so 1.0 devices go without
+            let dispatch_base = device_api_version >= VK_API_VERSION_1_1;
+            let device_queue2 = device_api_version >= VK_API_VERSION_1_1;
+            // Every Vulkan 1.2 device supports timeline semaphores, as do
+            // 1.1 devices exposing VK_KHR_timeline_semaphore
+            let timeline_semaphore_khr = device_api_version == VK_API_VERSION_1_1
+                && device_info.supports_extension(VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME);
+            if timeline_semaphore_khr {
+                extensions.push(VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME);
+            }
+            let timeline_semaphores = device_api_version >= VK_API_VERSION_1_2 || timeline_semaphore_khr;
+            // Shaders with debugPrintfEXT calls need it before Vulkan 1.3
+            if validation.debug_printf() && device_info.supports_extension(VK_KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION_NAME) {
+                extensions.push(VK_KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION_NAME);
+            }
+            // Required wherever the device exposes it
+            if device_info.supports_extension(VK_KHR_PORTABILITY_SUBSET_EXTENSION_NAME) {
+                extensions.push(VK_KHR_PORTABILITY_SUBSET_EXTENSION_NAME);
+            }
             
-            // Create descriptor pool for persistent descriptors
-            let descriptor_pool = Self::create_descriptor_pool(device)?;
-            log::info!("[SAFE API] Descriptor pool created: {:?}", descriptor_pool);
+            // Create logical device
+            kronos_log!(Info, "[SAFE API] Creating logical device");
+            let (device, queue) = Self::create_device(
+                physical_device,
+                queue_family_index,
+                &queue_families,
+                &config.required_features,
+                &extensions,
+                performance_query,
+                device_api_version,
+            )?;
+            kronos_log!(Info, "[SAFE API] Device created: {:?}, queue: {:?}", device, queue);
             
-            // Create command pool
-            log::info!("[SAFE API] Creating command pool");
-            let command_pool = Self::create_command_pool(device, queue_family_index)?;
-            log::info!("[SAFE API] Command pool created: {:?}", command_pool);
+            // Create descriptor and command pools
+            kronos_log!(Info, "[SAFE API] Creating descriptor and command pools");
+            let descriptor_pool_sets = config.descriptor_pool_sets.unwrap_or(DEFAULT_DESCRIPTOR_POOL_SETS);
+            let pools = Pools::new(device, queue_family_index, descriptor_pool_sets)?;
+            pool_allocator::initialize_pools_with_config(device, physical_device, &config.memory)?;
+            pool_allocator::set_memory_limit(device, config.memory_limit_bytes);
             
             let inner = ContextInner {
                 instance,
                 physical_device,
                 device,
+                id: DeviceId::next(),
                 queue,
                 queue_family_index,
-                descriptor_pool,
-                command_pool,
+                queue_families,
+                global_priorities,
+                api_version,
+                pools: Mutex::new(pools),
+                queue_lock: Mutex::new(()),
                 device_properties,
                 memory_properties,
+                min_storage_buffer_offset_alignment: device_info.min_storage_buffer_offset_alignment,
+                max_compute_shared_memory_size: device_info.max_compute_shared_memory_size,
+                min_uniform_buffer_offset_alignment: device_info.min_uniform_buffer_offset_alignment,
+                max_push_constants_size: device_info.max_push_constants_size,
+                driver: device_info.driver.clone(),
+                device_uuid: device_info.device_uuid,
+                pci_bus: device_info.pci_bus,
+                enabled_features: config.required_features,
+                reaper: OnceLock::new(),
+                threads: config.threads.clone(),
+                deferred: Arc::default(),
+                upload_ring: OnceLock::new(),
+                staging_capacity: config.staging_capacity.unwrap_or(DEFAULT_UPLOAD_RING_SIZE),
+                descriptor_pool_sets,
+                gpu_timer: OnceLock::new(),
+                performance_query,
+                external_memory,
+                external_semaphores,
+                pipeline_creation_feedback,
+                memory_priority,
+                conditional_rendering,
+                dispatch_base,
+                device_queue2,
+                timeline_semaphores,
+                debug_messenger,
+                debug_printf: validation.debug_printf(),
+                strict: config.strict_conformance,
+                conformance_issues: Mutex::default(),
+                dry_run: AtomicBool::new(false),
+                planned: Mutex::new(Vec::new()),
+                shader_modules: Mutex::default(),
+                layouts: Mutex::default(),
+                hashes: Mutex::default(),
+                command_stats: Mutex::default(),
+                counters: ContextCounters::default(),
+                device_events: Arc::new(DeviceEvents::new(instance, &device_properties)),
+                plugin,
             };
             
             // Log selected ICD info
             if let Some(info) = crate::implementation::icd_loader::selected_icd_info() {
-                log::info!(
+                kronos_log!(Info, 
                     "ComputeContext bound to ICD: {} ({}), api=0x{:x}",
                     info.library_path.display(),
                     if info.is_software { "software" } else { "hardware" },
                     info.api_version
                 );
             }
+            if let Some(driver) = &inner.driver {
+                kronos_log!(Info, "ComputeContext driver: {}", driver);
+            }
 
             let result = Self {
-                inner: Arc::new(Mutex::new(inner)),
+                inner: Arc::new(inner),
             };
-            log::info!("[SAFE API] ComputeContext created successfully");
+            kronos_log!(Info, "[SAFE API] ComputeContext created successfully");
             Ok(result)
         }
     }
@@ -175,50 +492,120 @@ impl ComputeContext {
     /// - The returned instance must be destroyed with vkDestroyInstance to avoid leaks
     /// - The config strings must remain valid for the lifetime of the instance creation
     /// - Null or invalid pointers in the create info will cause undefined behavior
-    unsafe fn create_instance(config: &ContextConfig) -> Result<VkInstance> {
-        log::info!("[SAFE API] create_instance called with app_name: {}", config.app_name);
+    unsafe fn create_instance(config: &ContextConfig, api_version: u32, validation: &Validation) -> Result<VkInstance> {
+        kronos_log!(Info, "[SAFE API] create_instance called with app_name: {}", config.app_name);
         let app_name = CString::new(config.app_name.clone())
             .unwrap_or_else(|_| CString::new("Kronos App").unwrap());
-        let engine_name = CString::new("Kronos Compute").unwrap();
-        log::info!("[SAFE API] CStrings created successfully");
+        let engine_name = CString::new(config.engine_name.as_deref().unwrap_or("Kronos Compute"))
+            .unwrap_or_else(|_| CString::new("Kronos Compute").unwrap());
+        kronos_log!(Info, "[SAFE API] CStrings created successfully");
         
         let app_info = VkApplicationInfo {
             sType: VkStructureType::ApplicationInfo,
             pNext: ptr::null(),
             pApplicationName: app_name.as_ptr(),
-            applicationVersion: VK_MAKE_VERSION(1, 0, 0),
+            applicationVersion: config.application_version.unwrap_or(Version::V1_0).to_raw(),
             pEngineName: engine_name.as_ptr(),
             engineVersion: VK_MAKE_VERSION(1, 0, 0),
-            apiVersion: VK_API_VERSION_1_0,
+            apiVersion: api_version,
         };
         
-        let create_info = VkInstanceCreateInfo {
+        let mut flags = 0;
+        let mut extensions = Vec::new();
+        if Self::portability_enumeration() {
+            kronos_log!(Info, "[SAFE API] Enabling {} for a portability driver", VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME);
+            flags |= VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR;
+            extensions.push(VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME);
+        }
+        extensions.extend(validation.extensions());
+        let layer_cstrings: Vec<CString> = validation.layers().iter().map(|name| CString::new(*name).unwrap()).collect();
+        let layer_names: Vec<*const c_char> = layer_cstrings.iter().map(|name| name.as_ptr()).collect();
+        let extension_cstrings: Vec<CString> = extensions.iter().map(|name| CString::new(*name).unwrap()).collect();
+        let extension_names: Vec<*const c_char> = extension_cstrings.iter().map(|name| name.as_ptr()).collect();
+        
+        let mut create_info = VkInstanceCreateInfo {
             sType: VkStructureType::InstanceCreateInfo,
             pNext: ptr::null(),
-            flags: 0,
+            flags,
             pApplicationInfo: &app_info,
-            enabledLayerCount: 0,
-            ppEnabledLayerNames: ptr::null(),
-            enabledExtensionCount: 0,
-            ppEnabledExtensionNames: ptr::null(),
+            enabledLayerCount: layer_names.len() as u32,
+            ppEnabledLayerNames: if layer_names.is_empty() { ptr::null() } else { layer_names.as_ptr() },
+            enabledExtensionCount: extension_names.len() as u32,
+            ppEnabledExtensionNames: if extension_names.is_empty() { ptr::null() } else { extension_names.as_ptr() },
         };
         
         let mut instance = VkInstance::NULL;
         // IMPORTANT: CStrings must remain alive during vkCreateInstance call
         // They are dropped at the end of this function, which is safe
-        log::info!("[SAFE API] Calling vkCreateInstance");
-        let result = vkCreateInstance(&create_info, ptr::null(), &mut instance);
-        log::info!("[SAFE API] vkCreateInstance returned: {:?}", result);
+        kronos_log!(Info, "[SAFE API] Calling vkCreateInstance");
+        let result = validation.chain_instance_create_info(&mut create_info, |create_info| {
+            vkCreateInstance(create_info, ptr::null(), &mut instance)
+        });
+        kronos_log!(Info, "[SAFE API] vkCreateInstance returned: {:?}", result);
         
         if result != VkResult::Success {
-            log::error!("[SAFE API] vkCreateInstance failed with: {:?}", result);
+            kronos_log!(Error, "[SAFE API] vkCreateInstance failed with: {:?}", result);
             return Err(KronosError::from(result));
         }
         
-        log::info!("[SAFE API] Instance created successfully: {:?}", instance);
+        kronos_log!(Info, "[SAFE API] Instance created successfully: {:?}", instance);
         Ok(instance)
     }
     
+    /// Whether the instance needs VK_KHR_portability_enumeration
+    ///
+    /// Portability drivers such as MoltenVK only report their devices to
+    /// instances created with it. It is enabled when one of the loaded
+    /// ICDs is a portability driver and the extension is supported.
+    ///
+    /// # Safety
+    ///
+    /// The ICDs must be loaded.
+    unsafe fn portability_enumeration() -> bool {
+        let icds = if icd_loader::aggregated_mode_enabled() {
+            icd_loader::get_all_icds()
+        } else {
+            icd_loader::get_icd().into_iter().collect()
+        };
+        if !icds.iter().any(|icd| icd.is_portability_driver) {
+            return false;
+        }
+        let supported = Self::instance_extensions()
+            .iter()
+            .any(|extension| extension == VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME);
+        if !supported {
+            kronos_log!(
+                Warn,
+                "[SAFE API] A portability driver is loaded but {} is not supported; its devices may not be enumerated",
+                VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME
+            );
+        }
+        supported
+    }
+    
+    /// Names of the instance extensions the loaded ICDs support
+    ///
+    /// # Safety
+    ///
+    /// The ICDs must be loaded.
+    unsafe fn instance_extensions() -> Vec<String> {
+        let mut count = 0u32;
+        let result = vkEnumerateInstanceExtensionProperties(ptr::null(), &mut count, ptr::null_mut());
+        if result != VkResult::Success || count == 0 {
+            return Vec::new();
+        }
+        let mut extensions = vec![VkExtensionProperties::default(); count as usize];
+        let result = vkEnumerateInstanceExtensionProperties(ptr::null(), &mut count, extensions.as_mut_ptr());
+        if !matches!(result, VkResult::Success | VkResult::Incomplete) {
+            return Vec::new();
+        }
+        extensions.truncate(count as usize);
+        extensions
+            .iter()
+            .map(|extension| CStr::from_ptr(extension.extensionName.as_ptr()).to_string_lossy().into_owned())
+            .collect()
+    }
+    
     /// Find a physical device with compute capabilities
     ///
     /// # Safety
@@ -228,17 +615,22 @@ impl ComputeContext {
     /// - Calls vkEnumeratePhysicalDevices which may fail with invalid instance
     /// - The returned physical device is tied to the instance lifetime
     /// - Accessing the device after instance destruction is undefined behavior
-    unsafe fn find_compute_device(instance: VkInstance, preferred_vendor: Option<u32>) -> Result<(VkPhysicalDevice, u32)> {
+    unsafe fn find_compute_device(
+        instance: VkInstance,
+        preferred_vendor: Option<u32>,
+        config: &ContextConfig,
+    ) -> Result<(VkPhysicalDevice, u32, Arc<DeviceInfo>)> {
+        let required_features = &config.required_features;
         let mut device_count = 0;
-        log::info!("[SAFE API] Enumerating physical devices...");
+        kronos_log!(Info, "[SAFE API] Enumerating physical devices...");
         
         // First call to get count
         let result = vkEnumeratePhysicalDevices(instance, &mut device_count, ptr::null_mut());
         if result != VkResult::Success {
-            log::error!("[SAFE API] Failed to get device count: {:?}", result);
+            kronos_log!(Error, "[SAFE API] Failed to get device count: {:?}", result);
             return Err(KronosError::from(result));
         }
-        log::info!("[SAFE API] Found {} physical devices", device_count);
+        kronos_log!(Info, "[SAFE API] Found {} physical devices", device_count);
         
         if device_count == 0 {
             return Err(KronosError::DeviceNotFound);
@@ -247,28 +639,42 @@ impl ComputeContext {
         let mut devices = vec![VkPhysicalDevice::NULL; device_count as usize];
         let result = vkEnumeratePhysicalDevices(instance, &mut device_count, devices.as_mut_ptr());
         if result != VkResult::Success {
-            log::error!("[SAFE API] Failed to enumerate devices: {:?}", result);
+            kronos_log!(Error, "[SAFE API] Failed to enumerate devices: {:?}", result);
             return Err(KronosError::from(result));
         }
-        log::info!("[SAFE API] Successfully enumerated {} devices", device_count);
+        kronos_log!(Info, "[SAFE API] Successfully enumerated {} devices", device_count);
         
         // Collect all devices with compute support and their properties
         let mut candidates = Vec::<(VkPhysicalDevice, u32, VkPhysicalDeviceType, u32, String)>::new();
         
-        for (dev_idx, device) in devices.iter().enumerate() {
-            log::info!("[SAFE API] Checking device {} for compute support", dev_idx);
-            let queue_family = Self::find_compute_queue_family(*device)?;
-            if let Some(index) = queue_family {
-                // Get device properties to determine device type
-                let mut properties = VkPhysicalDeviceProperties::default();
-                vkGetPhysicalDeviceProperties(*device, &mut properties);
-                
-                let device_name = Self::describe_device_name(&properties);
-                candidates.push((*device, index, properties.deviceType, properties.vendorID, device_name));
+        let infos = devices::device_infos(&devices);
+        let info_for = |device: VkPhysicalDevice| {
+            let index = devices.iter().position(|d| *d == device).unwrap();
+            infos[index].clone()
+        };
+        
+        let mut rejected = Vec::new();
+        for (dev_idx, (device, info)) in devices.iter().zip(&infos).enumerate() {
+            kronos_log!(Info, "[SAFE API] Checking device {} for compute support", dev_idx);
+            let properties = info.properties;
+            let device_name = Self::describe_device_name(&properties);
+            match info.compute_queue_family(config.queue_family, config.prefer_dedicated_compute_family) {
+                Ok(index) => candidates.push((*device, index, properties.device_type(), properties.vendorID, device_name)),
+                Err(e) => {
+                    kronos_log!(Info, "[SAFE API] Device {} skipped: {}", device_name, e);
+                    rejected.push(format!("{}: {}", device_name, e));
+                }
             }
         }
         
         if candidates.is_empty() {
+            if let Some(index) = config.queue_family {
+                return Err(KronosError::UnsupportedHardware(format!(
+                    "No device can use queue family {}. {}",
+                    index,
+                    rejected.join("; ")
+                )));
+            }
             return Err(KronosError::DeviceNotFound);
         }
 
@@ -291,18 +697,7 @@ impl ComputeContext {
                 supported_candidates = preferred_devices;
             } else {
                 let preferred_name = Self::vendor_name(preferred_vendor_id).unwrap_or("Unknown Vendor");
-                let all_device_report = candidates
-                    .iter()
-                    .map(|(_, _, _, vendor_id, name)| {
-                        format!(
-                            "{}:{} [0x{:04x}]",
-                            name,
-                            Self::vendor_name(*vendor_id).unwrap_or("Unknown Vendor"),
-                            vendor_id
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ");
+                let all_device_report = Self::discovery_report(&candidates);
                 return Err(KronosError::UnsupportedHardware(format!(
                     "Preferred vendor {} not available. Discovered Vulkan devices: {}",
                     preferred_name, all_device_report
@@ -311,18 +706,7 @@ impl ComputeContext {
         }
 
         if supported_candidates.is_empty() {
-            let discovered_devices = candidates
-                .iter()
-                .map(|(_, _, _, vendor_id, name)| {
-                    format!(
-                        "{}:{} [0x{:04x}]",
-                        name,
-                        Self::vendor_name(*vendor_id).unwrap_or("Unknown Vendor"),
-                        vendor_id
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
+            let discovered_devices = Self::discovery_report(&candidates);
             let supported_vendors = SUPPORTED_VULKAN_VENDORS
                 .iter()
                 .map(|(_, name)| *name)
@@ -335,6 +719,32 @@ impl ComputeContext {
             )));
         }
         
+        if !required_features.is_empty() {
+            let mut missing_report = Vec::new();
+            supported_candidates.retain(|(device, _, _, vendor_id, name)| {
+                let missing = required_features.missing_from(&Features::from(info_for(*device).features));
+                if missing.is_empty() {
+                    return true;
+                }
+                kronos_log!(Info, "[SAFE API] Device {} lacks required features: {:?}", name, missing);
+                missing_report.push(format!(
+                    "{}:{} [0x{:04x}] missing {}",
+                    name,
+                    Self::vendor_name(*vendor_id).unwrap_or("Unknown Vendor"),
+                    vendor_id,
+                    missing.join(", ")
+                ));
+                false
+            });
+
+            if supported_candidates.is_empty() {
+                return Err(KronosError::UnsupportedHardware(format!(
+                    "No device supports the required features. {}",
+                    missing_report.join("; ")
+                )));
+            }
+        }
+
         // Prefer supported discrete GPUs first, then integrated, then virtual, then fallback.
         supported_candidates.sort_by_key(|(_, _, device_type, _, _)| {
             match *device_type {
@@ -348,57 +758,82 @@ impl ComputeContext {
         });
         
         let &(device, queue_index, device_type, _, _) = supported_candidates.first().unwrap();
-        log::info!(
+        kronos_log!(Info, 
             "[SAFE API] Selected device with queue index {}, type {:?} and supported vendor",
             queue_index,
             device_type
         );
-        Ok((device, queue_index))
+        Ok((device, queue_index, info_for(device)))
     }
     
-    /// Find a queue family with compute support
+    /// Whether a queue family is created on the logical device and usable via `create_queue`
+    pub(super) fn is_exposed_queue_family(family: &VkQueueFamilyProperties) -> bool {
+        family.queueCount > 0
+            && family.queueFlags.intersects(VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER)
+    }
+    
+    /// Fetch queue `index` of `family` from `device`
+    ///
+    /// Vulkan 1.1 devices go through vkGetDeviceQueue2, the only way to
+    /// reach queues created with flags; Kronos creates its queues without
+    /// any, so the flags it asks for are empty as well. Older devices use
+    /// vkGetDeviceQueue. Returns NULL if the device has no such queue.
     ///
     /// # Safety
     ///
-    /// This function is unsafe because:
-    /// - The device must be a valid VkPhysicalDevice handle
-    /// - Calls vkGetPhysicalDeviceQueueFamilyProperties with the device
-    /// - Invalid device handle will cause undefined behavior
-    /// - The device must remain valid during the function execution
-    unsafe fn find_compute_queue_family(device: VkPhysicalDevice) -> Result<Option<u32>> {
-        log::info!("[SAFE API] Finding queue families for device {:?}", device);
-        let mut queue_family_count = 0;
-        log::info!("[SAFE API] Calling vkGetPhysicalDeviceQueueFamilyProperties (count query)...");
-        vkGetPhysicalDeviceQueueFamilyProperties(device, &mut queue_family_count, ptr::null_mut());
-        log::info!("[SAFE API] Device has {} queue families", queue_family_count);
-        
-        let mut queue_families = vec![
-            VkQueueFamilyProperties {
-                queueFlags: VkQueueFlags::empty(),
-                queueCount: 0,
-                timestampValidBits: 0,
-                minImageTransferGranularity: VkExtent3D { width: 0, height: 0, depth: 0 },
+    /// `device` must be a valid VkDevice, and `device_queue2` only set if
+    /// it supports Vulkan 1.1
+    pub(super) unsafe fn get_device_queue(device: VkDevice, family: u32, index: u32, device_queue2: bool) -> VkQueue {
+        let mut queue = VkQueue::NULL;
+        if device_queue2 {
+            let info = VkDeviceQueueInfo2 {
+                sType: VkStructureType::DeviceQueueInfo2,
+                pNext: ptr::null(),
+                flags: 0,
+                queueFamilyIndex: family,
+                queueIndex: index,
             };
-            queue_family_count as usize
-        ];
-        log::info!("[SAFE API] Getting queue family properties...");
-        vkGetPhysicalDeviceQueueFamilyProperties(device, &mut queue_family_count, queue_families.as_mut_ptr());
-        log::info!("[SAFE API] Got queue family properties, checking for compute support");
-        
-        for (index, family) in queue_families.iter().enumerate() {
-            log::info!("[SAFE API] Queue family {}: flags={:?}", index, family.queueFlags);
-            if family.queueFlags.contains(VkQueueFlags::COMPUTE) && family.queueCount > 0 {
-                log::info!("[SAFE API] Found compute queue at index {}", index);
-                return Ok(Some(index as u32));
+            vkGetDeviceQueue2(device, &info, &mut queue);
+        } else {
+            vkGetDeviceQueue(device, family, index, &mut queue);
+        }
+        queue
+    }
+    
+    /// Handle types external memory can be imported from, if `requested`
+    ///
+    /// Opaque fds need VK_KHR_external_memory_fd; dma-bufs need
+    /// VK_EXT_external_memory_dma_buf on top of it.
+    fn external_memory_handle_types(device_info: &DeviceInfo, requested: bool) -> VkExternalMemoryHandleTypeFlags {
+        let mut handle_types = VkExternalMemoryHandleTypeFlags::empty();
+        if !requested {
+            return handle_types;
+        }
+        if device_info.supports_extension(VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME) {
+            handle_types |= VkExternalMemoryHandleTypeFlags::OPAQUE_FD;
+            if device_info.supports_extension(VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME) {
+                handle_types |= VkExternalMemoryHandleTypeFlags::DMA_BUF_EXT;
             }
+        } else {
+            kronos_log!(Warn, "[SAFE API] External memory requested but {} is not supported", VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME);
         }
-        
-        log::info!("[SAFE API] No compute queue family found");
-        Ok(None)
+        handle_types
     }
     
     /// Create a logical device and get its compute queue
     ///
+    /// Every queue of every compute- or transfer-capable family is created so
+    /// that `create_queue` can later hand out any of them. `extensions` are
+    /// enabled on the device; with `performance_query` the counter query
+    /// pool feature of VK_KHR_performance_query is enabled as well, and
+    /// the features of VK_EXT_memory_priority,
+    /// VK_EXT_pageable_device_local_memory and VK_EXT_conditional_rendering
+    /// if they are in `extensions`. For a
+    /// Vulkan 1.2 `api_version`, the lower of the instance and device
+    /// versions, or with VK_KHR_timeline_semaphore in `extensions`, the
+    /// timelineSemaphore feature is enabled too. The queue is
+    /// fetched as described in `get_device_queue`.
+    ///
     /// # Safety
     ///
     /// This function is unsafe because:
@@ -407,48 +842,122 @@ impl ComputeContext {
     /// - Calls vkCreateDevice and vkGetDeviceQueue which require valid handles
     /// - The returned device and queue must be properly destroyed
     /// - Queue family index out of bounds will cause undefined behavior
-    unsafe fn create_device(physical_device: VkPhysicalDevice, queue_family_index: u32) -> Result<(VkDevice, VkQueue)> {
-        let queue_priority = 1.0f32;
+    /// - Requested features must have been verified as supported by the device
+    unsafe fn create_device(
+        physical_device: VkPhysicalDevice,
+        queue_family_index: u32,
+        queue_families: &[VkQueueFamilyProperties],
+        features: &Features,
+        extensions: &[&str],
+        performance_query: bool,
+        api_version: u32,
+    ) -> Result<(VkDevice, VkQueue)> {
+        let max_queue_count = queue_families.iter().map(|f| f.queueCount).max().unwrap_or(1).max(1);
+        let queue_priorities = vec![1.0f32; max_queue_count as usize];
         
-        let queue_create_info = VkDeviceQueueCreateInfo {
-            sType: VkStructureType::DeviceQueueCreateInfo,
-            pNext: ptr::null(),
-            flags: 0,
-            queueFamilyIndex: queue_family_index,
-            queueCount: 1,
-            pQueuePriorities: &queue_priority,
+        let mut queue_create_infos: Vec<VkDeviceQueueCreateInfo> = queue_families
+            .iter()
+            .enumerate()
+            .filter(|(_, family)| Self::is_exposed_queue_family(family))
+            .map(|(index, family)| VkDeviceQueueCreateInfo {
+                sType: VkStructureType::DeviceQueueCreateInfo,
+                pNext: ptr::null(),
+                flags: 0,
+                queueFamilyIndex: index as u32,
+                queueCount: family.queueCount,
+                pQueuePriorities: queue_priorities.as_ptr(),
+            })
+            .collect();
+        if !queue_create_infos.iter().any(|info| info.queueFamilyIndex == queue_family_index) {
+            queue_create_infos.push(VkDeviceQueueCreateInfo {
+                sType: VkStructureType::DeviceQueueCreateInfo,
+                pNext: ptr::null(),
+                flags: 0,
+                queueFamilyIndex: queue_family_index,
+                queueCount: 1,
+                pQueuePriorities: queue_priorities.as_ptr(),
+            });
+        }
+        
+        // Without requirements pass NULL like the working example; otherwise the
+        // driver reads the full Vulkan 1.0 layout, not our compute subset
+        let enabled_features = VkPhysicalDeviceFeatures::from(*features).to_full();
+        let p_enabled_features = if features.is_empty() {
+            kronos_log!(Info, "[SAFE API] Creating device with NULL features pointer (no features requested)");
+            ptr::null()
+        } else {
+            kronos_log!(Info, "[SAFE API] Creating device with features {:?}", features);
+            enabled_features.as_ptr() as *const VkPhysicalDeviceFeatures
         };
         
-        // Don't request any features - use NULL pointer like the working example
-        log::info!("[SAFE API] Creating device with NULL features pointer (no features requested)");
+        let extension_cstrings: Vec<CString> = extensions.iter().map(|name| CString::new(*name).unwrap()).collect();
+        let extension_names: Vec<*const c_char> = extension_cstrings.iter().map(|name| name.as_ptr()).collect();
+        let mut performance_query_features = VkPhysicalDevicePerformanceQueryFeaturesKHR {
+            sType: VkStructureType::PhysicalDevicePerformanceQueryFeaturesKHR,
+            pNext: ptr::null_mut(),
+            performanceCounterQueryPools: VK_TRUE,
+            performanceCounterMultipleQueryPools: VK_FALSE,
+        };
         
-        let device_create_info = VkDeviceCreateInfo {
+        let mut device_create_info = VkDeviceCreateInfo {
             sType: VkStructureType::DeviceCreateInfo,
             pNext: ptr::null(),
             flags: 0,
-            queueCreateInfoCount: 1,
-            pQueueCreateInfos: &queue_create_info,
+            queueCreateInfoCount: queue_create_infos.len() as u32,
+            pQueueCreateInfos: queue_create_infos.as_ptr(),
             enabledLayerCount: 0,
             ppEnabledLayerNames: ptr::null(),
-            enabledExtensionCount: 0,
-            ppEnabledExtensionNames: ptr::null(),
-            pEnabledFeatures: ptr::null(),  // Use NULL like the working example
+            enabledExtensionCount: extension_names.len() as u32,
+            ppEnabledExtensionNames: extension_names.as_ptr(),
+            pEnabledFeatures: p_enabled_features,
+        };
+        let mut device_create_info = Chain::new(&mut device_create_info);
+        if performance_query {
+            device_create_info = device_create_info.push(&mut performance_query_features);
+        }
+        let mut timeline_semaphore_features = VkPhysicalDeviceTimelineSemaphoreFeatures {
+            timelineSemaphore: VK_TRUE,
+            ..Default::default()
         };
+        if api_version >= VK_API_VERSION_1_2 || extensions.contains(&VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME) {
+            device_create_info = device_create_info.push(&mut timeline_semaphore_features);
+        }
+        // Devices exposing these extensions must support their features
+        let mut memory_priority_features = VkPhysicalDeviceMemoryPriorityFeaturesEXT {
+            memoryPriority: VK_TRUE,
+            ..Default::default()
+        };
+        if extensions.contains(&VK_EXT_MEMORY_PRIORITY_EXTENSION_NAME) {
+            device_create_info = device_create_info.push(&mut memory_priority_features);
+        }
+        let mut pageable_memory_features = VkPhysicalDevicePageableDeviceLocalMemoryFeaturesEXT {
+            pageableDeviceLocalMemory: VK_TRUE,
+            ..Default::default()
+        };
+        if extensions.contains(&VK_EXT_PAGEABLE_DEVICE_LOCAL_MEMORY_EXTENSION_NAME) {
+            device_create_info = device_create_info.push(&mut pageable_memory_features);
+        }
+        let mut conditional_rendering_features = VkPhysicalDeviceConditionalRenderingFeaturesEXT {
+            conditionalRendering: VK_TRUE,
+            ..Default::default()
+        };
+        if extensions.contains(&VK_EXT_CONDITIONAL_RENDERING_EXTENSION_NAME) {
+            device_create_info = device_create_info.push(&mut conditional_rendering_features);
+        }
         
         let mut device = VkDevice::NULL;
-        log::info!("[SAFE API] Calling vkCreateDevice with queue family index {}", queue_family_index);
-        let result = vkCreateDevice(physical_device, &device_create_info, ptr::null(), &mut device);
-        log::info!("[SAFE API] vkCreateDevice returned: {:?}", result);
+        kronos_log!(Info, "[SAFE API] Calling vkCreateDevice with queue family index {}", queue_family_index);
+        let result = vkCreateDevice(physical_device, device_create_info.as_ptr(), ptr::null(), &mut device);
+        kronos_log!(Info, "[SAFE API] vkCreateDevice returned: {:?}", result);
         
         if result != VkResult::Success {
-            log::error!("[SAFE API] Failed to create device: {:?}", result);
+            kronos_log!(Error, "[SAFE API] Failed to create device: {:?}", result);
             return Err(KronosError::from(result));
         }
         
-        let mut queue = VkQueue::NULL;
-        vkGetDeviceQueue(device, queue_family_index, 0, &mut queue);
+        let queue = Self::get_device_queue(device, queue_family_index, 0, api_version >= VK_API_VERSION_1_1);
         if queue == VkQueue::NULL {
-            log::error!("[SAFE API] vkGetDeviceQueue returned NULL queue");
+            kronos_log!(Error, "[SAFE API] Device returned NULL queue");
             return Err(KronosError::UnsupportedHardware(
                 "Compute queue was not created by Vulkan device".into(),
             ));
@@ -457,51 +966,6 @@ impl ComputeContext {
         Ok((device, queue))
     }
     
-    /// Create a descriptor pool for persistent descriptors
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because:
-    /// - The device must be a valid VkDevice handle
-    /// - Calls vkCreateDescriptorPool which requires valid device
-    /// - The returned pool must be destroyed with vkDestroyDescriptorPool
-    /// - Invalid device handle will cause undefined behavior
-    /// - Pool creation may fail if device limits are exceeded
-    unsafe fn create_descriptor_pool(device: VkDevice) -> Result<VkDescriptorPool> {
-        log::info!("[SAFE API] Creating descriptor pool with device: {:?}", device);
-        // Create a large pool for persistent descriptors
-        let pool_size = VkDescriptorPoolSize {
-            type_: VkDescriptorType::StorageBuffer,
-            descriptorCount: 10000, // Should be enough for most use cases
-        };
-        
-        let pool_info = VkDescriptorPoolCreateInfo {
-            sType: VkStructureType::DescriptorPoolCreateInfo,
-            pNext: ptr::null(),
-            flags: VkDescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET,
-            maxSets: 1000,
-            poolSizeCount: 1,
-            pPoolSizes: &pool_size,
-        };
-        
-        let mut pool = VkDescriptorPool::NULL;
-        log::info!("[SAFE API] Calling vkCreateDescriptorPool");
-        let result = vkCreateDescriptorPool(device, &pool_info, ptr::null(), &mut pool);
-        log::info!("[SAFE API] vkCreateDescriptorPool returned: {:?}", result);
-        
-        if result != VkResult::Success {
-            log::error!("[SAFE API] Failed to create descriptor pool: {:?}", result);
-            return Err(KronosError::from(result));
-        }
-        if pool == VkDescriptorPool::NULL {
-            return Err(KronosError::UnsupportedHardware(
-                "Descriptor pool creation returned NULL handle".into(),
-            ));
-        }
-        
-        Ok(pool)
-    }
-    
     /// Create a command pool for allocating command buffers
     ///
     /// # Safety
@@ -512,7 +976,7 @@ impl ComputeContext {
     /// - Calls vkCreateCommandPool which requires valid parameters
     /// - The returned pool must be destroyed with vkDestroyCommandPool
     /// - Invalid queue family index will cause undefined behavior
-    unsafe fn create_command_pool(device: VkDevice, queue_family_index: u32) -> Result<VkCommandPool> {
+    pub(super) unsafe fn create_command_pool(device: VkDevice, queue_family_index: u32) -> Result<VkCommandPool> {
         let pool_info = VkCommandPoolCreateInfo {
             sType: VkStructureType::CommandPoolCreateInfo,
             pNext: ptr::null(),
@@ -521,12 +985,12 @@ impl ComputeContext {
         };
         
         let mut pool = VkCommandPool::NULL;
-        log::info!("[SAFE API] Calling vkCreateCommandPool with device {:?}, queue family {}", device, queue_family_index);
+        kronos_log!(Info, "[SAFE API] Calling vkCreateCommandPool with device {:?}, queue family {}", device, queue_family_index);
         let result = vkCreateCommandPool(device, &pool_info, ptr::null(), &mut pool);
-        log::info!("[SAFE API] vkCreateCommandPool returned: {:?}", result);
+        kronos_log!(Info, "[SAFE API] vkCreateCommandPool returned: {:?}", result);
         
         if result != VkResult::Success {
-            log::error!("[SAFE API] Failed to create command pool: {:?}", result);
+            kronos_log!(Error, "[SAFE API] Failed to create command pool: {:?}", result);
             return Err(KronosError::from(result));
         }
         if pool == VkCommandPool::NULL {
@@ -538,6 +1002,32 @@ impl ComputeContext {
         Ok(pool)
     }
 
+    /// Pick the highest API version supported by both Kronos and the ICD
+    ///
+    /// Patch levels are ignored; anything below 1.0 falls back to 1.0.
+    pub(super) fn negotiate_api_version(instance_version: u32) -> u32 {
+        let major_minor = instance_version & !0xFFF;
+        SUPPORTED_API_VERSIONS
+            .iter()
+            .copied()
+            .find(|&version| version <= major_minor)
+            .unwrap_or(VK_API_VERSION_1_0)
+    }
+
+    /// The API version to create the instance with for an explicit request
+    ///
+    /// Fails if the loader's instance version is older than `requested`.
+    pub(super) fn requested_api_version(instance_version: u32, requested: Version) -> Result<u32> {
+        let available = Version::from_raw(instance_version & !0xFFF);
+        if Version::new(requested.major, requested.minor, 0) > available {
+            return Err(KronosError::UnsupportedHardware(format!(
+                "Vulkan {} requested but the loader only supports {}",
+                requested, available
+            )));
+        }
+        Ok(Self::negotiate_api_version(requested.to_raw()))
+    }
+
     fn parse_vendor_id(vendor: &str) -> Result<u32> {
         let vendor_normalized = vendor.trim().to_ascii_lowercase();
         match vendor_normalized.as_str() {
@@ -561,6 +1051,26 @@ impl ComputeContext {
             .find_map(|(id, name)| (*id == vendor_id).then_some(*name))
     }
 
+    /// Devices listed in selection errors; just a count with the `minimal`
+    /// feature
+    fn discovery_report(candidates: &[(VkPhysicalDevice, u32, VkPhysicalDeviceType, u32, String)]) -> String {
+        if cfg!(feature = "minimal") {
+            return format!("{} (details omitted in minimal builds)", candidates.len());
+        }
+        candidates
+            .iter()
+            .map(|(_, _, _, vendor_id, name)| {
+                format!(
+                    "{}:{} [0x{:04x}]",
+                    name,
+                    Self::vendor_name(*vendor_id).unwrap_or("Unknown Vendor"),
+                    vendor_id
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     fn describe_device_type(device_type: VkPhysicalDeviceType) -> &'static str {
         match device_type {
             VkPhysicalDeviceType::DiscreteGpu => "Discrete GPU",
@@ -587,62 +1097,243 @@ impl ComputeContext {
     
     /// Get the underlying Vulkan device (for advanced usage)
     pub fn device(&self) -> VkDevice {
-        self.inner.lock().unwrap().device
+        self.inner.device
+    }
+    
+    /// Identity of this context's device, shared by all objects created on it
+    pub fn device_id(&self) -> DeviceId {
+        self.inner.id
+    }
+    
+    /// Describe a raw handle value from a log, e.g. `VkBuffer(#42, device #1)`
+    ///
+    /// Only live handles of this context's safe-API objects are known. A
+    /// value shared by handles of several types yields one description
+    /// for each, joined with " or ".
+    #[cfg(feature = "object-registry")]
+    pub fn describe_handle(&self, raw: u64) -> Option<String> {
+        let descriptions = super::registry::describe(raw, self.inner.id);
+        (!descriptions.is_empty()).then(|| descriptions.join(" or "))
     }
     
     /// Get the compute queue
     pub fn queue(&self) -> VkQueue {
-        self.inner.lock().unwrap().queue
+        self.inner.queue
     }
     
     /// Get device properties
     pub fn device_properties(&self) -> VkPhysicalDeviceProperties {
-        self.inner.lock().unwrap().device_properties
+        self.inner.device_properties
+    }
+    
+    /// Alignment [`Buffer::slice`] offsets must have
+    ///
+    /// The device's `minStorageBufferOffsetAlignment` limit.
+    pub fn min_storage_buffer_offset_alignment(&self) -> VkDeviceSize {
+        self.inner.min_storage_buffer_offset_alignment
+    }
+    
+    /// Alignment of the uniform ring slots of [`Constants`](super::Constants)
+    ///
+    /// The device's `minUniformBufferOffsetAlignment` limit.
+    pub fn min_uniform_buffer_offset_alignment(&self) -> VkDeviceSize {
+        self.inner.min_uniform_buffer_offset_alignment
+    }
+    
+    /// Bytes of shared (workgroup) memory one workgroup may use
+    ///
+    /// The device's `maxComputeSharedMemorySize` limit. Pipelines whose
+    /// kernels declare more are refused when they are created.
+    pub fn max_shared_memory_size(&self) -> u32 {
+        self.inner.max_compute_shared_memory_size
+    }
+    
+    /// Bytes of push constants a pipeline may take
+    ///
+    /// The device's `maxPushConstantsSize` limit; larger blocks go through
+    /// [`Constants`](super::Constants).
+    pub fn max_push_constants_size(&self) -> u32 {
+        self.inner.max_push_constants_size
+    }
+    
+    /// Known driver behavior of the device, such as its watchdog timeout
+    pub fn quirks(&self) -> Quirks {
+        Quirks::for_driver(&self.inner.device_properties, self.inner.driver.as_ref().map(|driver| driver.id))
+    }
+    
+    /// Which driver runs the device, if it reports VK_KHR_driver_properties
+    pub fn driver_info(&self) -> Option<DriverInfo> {
+        self.inner.driver.clone()
+    }
+    
+    /// Universally unique ID of the device, as Vulkan 1.1 reports it
+    ///
+    /// Other APIs that interoperate with Vulkan, such as OpenCL with
+    /// cl_khr_device_uuid, report the same ID for the same device.
+    pub fn device_uuid(&self) -> Option<[u8; VK_UUID_SIZE]> {
+        self.inner.device_uuid
+    }
+    
+    /// Where the device sits on the PCI bus, if it exposes VK_EXT_pci_bus_info
+    pub fn pci_bus_address(&self) -> Option<PciBusAddress> {
+        self.inner.pci_bus
+    }
+    
+    /// Get the negotiated instance API version
+    pub fn api_version(&self) -> u32 {
+        self.inner.api_version
+    }
+    
+    /// Whether pipelines can be created with [`PipelineConfig::dispatch_base`]
+    ///
+    /// Needs Vulkan 1.1 on both the instance and the device.
+    pub fn supports_dispatch_base(&self) -> bool {
+        self.inner.dispatch_base
+    }
+    
+    /// Whether predicated dispatches are skipped by the device itself
+    ///
+    /// Needs VK_EXT_conditional_rendering; without it
+    /// [`CommandBuilder::predicated_on`] emulates the predicate with
+    /// indirect dispatches.
+    pub fn supports_conditional_rendering(&self) -> bool {
+        self.inner.conditional_rendering
+    }
+    
+    /// Whether [`create_progress`](Self::create_progress) is available
+    ///
+    /// Needs Vulkan 1.2 on both the instance and the device.
+    pub fn supports_timeline_semaphores(&self) -> bool {
+        self.inner.timeline_semaphores
+    }
+    
+    /// Get the features enabled on the logical device
+    pub fn enabled_features(&self) -> Features {
+        self.inner.enabled_features
+    }
+    
+    /// Number of non-blocking submissions not yet observed complete
+    pub fn in_flight_submissions(&self) -> usize {
+        self.with_inner(|inner| inner.reaper.get().map_or(0, |reaper| reaper.in_flight()))
     }
     
     /// Get information about the ICD bound to this context (process-wide)
     pub fn icd_info(&self) -> Option<crate::implementation::icd_loader::IcdInfo> {
         crate::implementation::icd_loader::selected_icd_info()
     }
+    
+    /// Get the physical device the context's device was created from
+    pub fn physical_device(&self) -> VkPhysicalDevice {
+        self.inner.physical_device
+    }
+    
+    /// Get the instance the context's ICD created, for instance-level
+    /// entry points from [`icd_entry`](Self::icd_entry)
+    ///
+    /// In aggregated mode Kronos hands out a meta instance spanning every
+    /// ICD; this is the driver's own instance behind it.
+    pub fn icd_instance(&self) -> VkInstance {
+        match icd_loader::icd_for_device(self.inner.device) {
+            Some(icd) => icd_loader::icd_instance(self.inner.instance, &icd),
+            None => self.inner.instance,
+        }
+    }
+    
+    /// Resolve an entry point Kronos does not wrap from the context's ICD
+    ///
+    /// Device-level entry points come from the driver's vkGetDeviceProcAddr
+    /// for the context's device, so calls go straight to the driver's
+    /// dispatch for it; others come from its vkGetInstanceProcAddr for
+    /// [`icd_instance`](Self::icd_instance). `F` names the entry point's
+    /// type:
+    ///
+    /// ```no_run
+    /// use kronos_compute::api::ComputeContext;
+    /// use kronos_compute::ffi::PFN_vkDeviceWaitIdle;
+    ///
+    /// # fn main() -> kronos_compute::api::Result<()> {
+    /// let ctx = ComputeContext::new()?;
+    /// let wait_idle = unsafe { ctx.icd_entry::<PFN_vkDeviceWaitIdle>("vkDeviceWaitIdle")? };
+    /// unsafe { wait_idle.unwrap()(ctx.device()) };
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Calls through the pointer bypass Kronos: objects they create are not
+    /// tracked, and the safe API does not see what they change. Extension
+    /// entry points need their extension enabled on the device or instance.
+    /// Fails with [`KronosError::UnsupportedHardware`] if the driver does
+    /// not expose `name`.
+    ///
+    /// # Safety
+    ///
+    /// `F` must be the PFN type of `name`, or the function pointer type it
+    /// wraps.
+    pub unsafe fn icd_entry<F: Copy>(&self, name: &str) -> Result<F> {
+        assert_eq!(
+            std::mem::size_of::<F>(),
+            std::mem::size_of::<unsafe extern "C" fn()>(),
+            "icd_entry needs a function pointer type"
+        );
+        let entry = CString::new(name)
+            .ok()
+            .and_then(|c_name| icd_loader::resolve_entry_point(self.inner.instance, self.inner.device, &c_name));
+        match entry {
+            Some(entry) => Ok(std::mem::transmute_copy(&entry)),
+            None => Err(KronosError::UnsupportedHardware(format!("The ICD does not expose {}", name))),
+        }
+    }
 
     // Internal helper for other modules
+    //
+    // The state is shared without a lock: fields are either immutable or
+    // carry their own synchronization (`pools`, `queue_lock`, ...).
     pub(super) fn with_inner<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&ContextInner) -> R,
     {
-        let inner = self.inner.lock().unwrap();
-        f(&*inner)
+        f(&self.inner)
     }
 }
 
-impl Drop for ComputeContext {
+impl Drop for ContextInner {
+    // Runs once the last ComputeContext clone is gone
     fn drop(&mut self) {
-        // Only the last Clone should perform destruction to avoid double-free.
-        if std::sync::Arc::strong_count(&self.inner) != 1 {
-            return;
+        let pools = self.pools.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(reaper) = self.reaper.get() {
+            reaper.shutdown();
+            for resources in reaper.take_retired() {
+                unsafe { resources.release(self.device, &pools.descriptors) };
+            }
         }
-        let inner = self.inner.lock().unwrap();
+        self.deferred.flush();
         unsafe {
-            if inner.device != VkDevice::NULL {
-                if let Err(err) = cleanup_persistent_descriptors(inner.device) {
-                    log::warn!(
+            if let Some(timer) = self.gpu_timer.get() {
+                timer.destroy();
+            }
+            if let Some(ring) = self.upload_ring.get().and_then(Option::as_ref) {
+                ring.destroy(self.device);
+            }
+            if self.device != VkDevice::NULL {
+                if let Err(err) = cleanup_persistent_descriptors(self.device) {
+                    kronos_log!(
+                        Warn,
                         "Failed to cleanup persistent descriptor cache for device {:?}: {:?}",
-                        inner.device,
+                        self.device,
                         err
                     );
                 }
+                if let Err(err) = pool_allocator::destroy_pools(self.device) {
+                    kronos_log!(Warn, "Failed to release memory pools of device {:?}: {:?}", self.device, err);
+                }
             }
-            if inner.command_pool != VkCommandPool::NULL {
-                vkDestroyCommandPool(inner.device, inner.command_pool, ptr::null());
-            }
-            if inner.descriptor_pool != VkDescriptorPool::NULL {
-                vkDestroyDescriptorPool(inner.device, inner.descriptor_pool, ptr::null());
-            }
-            if inner.device != VkDevice::NULL {
-                vkDestroyDevice(inner.device, ptr::null());
+            pools.destroy();
+            if self.device != VkDevice::NULL {
+                vkDestroyDevice(self.device, ptr::null());
             }
-            if inner.instance != VkInstance::NULL {
-                vkDestroyInstance(inner.instance, ptr::null());
+            if self.instance != VkInstance::NULL {
+                vkDestroyDebugUtilsMessengerEXT(self.instance, self.debug_messenger, ptr::null());
+                vkDestroyInstance(self.instance, ptr::null());
             }
         }
     }
@@ -0,0 +1,132 @@
+//! Deferred destruction of resources the GPU may still use
+//!
+//! Every submission that can outlive the call making it gets a serial, and
+//! the resources it references are stamped with that serial. Dropping a
+//! [`Buffer`], [`Image`] or [`Sampler`] hands its handles to the context's
+//! [`DeferredDestruction`] queue, which destroys them once every submission
+//! up to their last serial has completed and no unsubmitted
+//! [`CommandBuilder`] refers to them any more. Resources no submission is
+//! using are destroyed immediately, as before.
+//!
+//! The queue is collected whenever a submission completes and when a
+//! resource is dropped; the context destroys whatever is left when it is
+//! dropped.
+//!
+//! [`Buffer`]: super::Buffer
+//! [`Image`]: super::Image
+//! [`Sampler`]: super::Sampler
+//! [`CommandBuilder`]: super::CommandBuilder
+
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Destroys the handles of a dropped resource
+pub(super) type Destructor = Box<dyn FnOnce() + Send + 'static>;
+
+/// Serial of the last submission that referenced a resource, 0 if none
+///
+/// Shared with every builder the resource is bound to; a resource is only
+/// destroyed once it holds the last reference.
+#[derive(Debug, Default)]
+pub(super) struct LastUse(AtomicU64);
+
+struct Pending {
+    last_use: Arc<LastUse>,
+    destroy: Destructor,
+}
+
+#[derive(Default)]
+struct State {
+    next_serial: u64,
+    in_flight: BTreeSet<u64>,
+    pending: Vec<Pending>,
+}
+
+impl State {
+    /// Whether the GPU and all builders are done with `last_use`
+    fn is_idle(&self, last_use: &Arc<LastUse>) -> bool {
+        let serial = last_use.0.load(Ordering::Relaxed);
+        Arc::strong_count(last_use) == 1 && self.in_flight.first().map_or(true, |&oldest| serial < oldest)
+    }
+
+    fn take_idle(&mut self) -> Vec<Destructor> {
+        let (idle, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|entry| self.is_idle(&entry.last_use));
+        self.pending = pending;
+        idle.into_iter().map(|entry| entry.destroy).collect()
+    }
+}
+
+/// Resources waiting for the submissions that use them, per context
+#[derive(Default)]
+pub(super) struct DeferredDestruction {
+    state: Mutex<State>,
+}
+
+impl DeferredDestruction {
+    /// Start a submission, stamping `uses` with its serial
+    ///
+    /// The serial stays in flight until the returned guard is dropped.
+    pub(super) fn begin_submission<'a>(
+        self: &Arc<Self>,
+        uses: impl IntoIterator<Item = &'a Arc<LastUse>>,
+    ) -> SubmissionSerial {
+        let mut state = self.state.lock().unwrap();
+        state.next_serial += 1;
+        let serial = state.next_serial;
+        state.in_flight.insert(serial);
+        for last_use in uses {
+            last_use.0.store(serial, Ordering::Relaxed);
+        }
+        SubmissionSerial { queue: self.clone(), serial }
+    }
+
+    /// Run `destroy` now if nothing uses the resource, otherwise once its
+    /// last submission has completed
+    pub(super) fn destroy(&self, last_use: Arc<LastUse>, destroy: Destructor) {
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            let mut ready = state.take_idle();
+            if state.is_idle(&last_use) {
+                ready.push(destroy);
+            } else {
+                state.pending.push(Pending { last_use, destroy });
+            }
+            ready
+        };
+        ready.into_iter().for_each(|destroy| destroy());
+    }
+
+    /// Destroy the resources that have become idle
+    pub(super) fn collect(&self) {
+        let ready = self.state.lock().unwrap().take_idle();
+        ready.into_iter().for_each(|destroy| destroy());
+    }
+
+    /// Destroy everything still queued; the device must be idle
+    pub(super) fn flush(&self) {
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending);
+        pending.into_iter().for_each(|entry| (entry.destroy)());
+    }
+
+    /// Number of resources waiting to be destroyed
+    #[cfg(test)]
+    pub(super) fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+}
+
+/// A submission in flight; dropping it retires its serial
+pub(super) struct SubmissionSerial {
+    queue: Arc<DeferredDestruction>,
+    serial: u64,
+}
+
+impl Drop for SubmissionSerial {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().in_flight.remove(&self.serial);
+        self.queue.collect();
+    }
+}
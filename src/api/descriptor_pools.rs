@@ -0,0 +1,381 @@
+//! Descriptor pools sized from the pipelines that allocate from them
+//!
+//! A context's descriptor sets come from a chain of pools instead of one
+//! pool sized for every descriptor type up front. Each pool holds only the
+//! descriptor types the context's pipelines have bound, as many of each as
+//! the largest set seen needs, so a context whose kernels bind storage
+//! buffers alone reserves no image or uniform descriptors. When no pool has
+//! room for a set, Kronos creates one with room for as many sets as all
+//! earlier pools together, and at least
+//! [`ContextBuilder::descriptor_pool_sets`].
+//!
+//! A workload that knows its pipelines up front can size a pool for them
+//! once instead:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! # let spirv: &[u8] = &[];
+//! let shader = ctx.create_shader_from_spirv(spirv)?;
+//! let pipeline = ctx.create_pipeline(&shader)?;
+//! ctx.reserve_descriptor_sets(&[(&pipeline, 256)])?;
+//!
+//! // ... run a representative workload, then check the reservation
+//! for usage in ctx.descriptor_pool_usage().types {
+//!     println!("{:?}: {} of {}", usage.descriptor_type, usage.high_water, usage.capacity);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ComputeContext::descriptor_pool_usage`] reports the capacity, current
+//! use and high-water mark of each descriptor type. Workers allocate from
+//! pools of their own, which grow the same way and are not in the report.
+
+use super::*;
+use crate::*; // Need all the type definitions
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::Mutex;
+
+/// Sets a new descriptor pool has room for at least, unless
+/// [`ContextBuilder::descriptor_pool_sets`] says otherwise
+pub const DEFAULT_DESCRIPTOR_POOL_SETS: u32 = 64;
+
+/// Number of descriptors of each type, in type order and without zeros
+pub(super) type DescriptorCounts = Vec<(VkDescriptorType, u32)>;
+
+/// Descriptors of each type in a set layout with `bindings`
+pub(super) fn layout_counts(bindings: &[(u32, VkDescriptorType)]) -> DescriptorCounts {
+    let mut counts = DescriptorCounts::new();
+    for &(_, descriptor_type) in bindings {
+        add(&mut counts, descriptor_type, 1);
+    }
+    counts
+}
+
+fn count(counts: &DescriptorCounts, descriptor_type: VkDescriptorType) -> u32 {
+    counts.iter().find(|(t, _)| *t == descriptor_type).map_or(0, |&(_, n)| n)
+}
+
+fn add(counts: &mut DescriptorCounts, descriptor_type: VkDescriptorType, n: u32) {
+    match counts.binary_search_by_key(&(descriptor_type as i32), |&(t, _)| t as i32) {
+        Ok(at) => counts[at].1 += n,
+        Err(at) => counts.insert(at, (descriptor_type, n)),
+    }
+}
+
+fn sub(counts: &mut DescriptorCounts, descriptor_type: VkDescriptorType, n: u32) {
+    if let Some(entry) = counts.iter_mut().find(|(t, _)| *t == descriptor_type) {
+        entry.1 -= n;
+    }
+    counts.retain(|&(_, n)| n > 0);
+}
+
+/// Descriptors of one type in a context's descriptor pools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorTypeUsage {
+    pub descriptor_type: VkDescriptorType,
+    /// Descriptors the pools were created with
+    pub capacity: u32,
+    /// Descriptors in allocated sets
+    pub in_use: u32,
+    /// Most descriptors of the type in allocated sets at once
+    pub high_water: u32,
+}
+
+/// Capacity and use of a context's descriptor pools, see
+/// [`descriptor_pools`](super::descriptor_pools)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DescriptorPoolUsage {
+    /// Pools created so far
+    pub pools: usize,
+    /// Sets the pools were created for
+    pub max_sets: u32,
+    /// Sets allocated now
+    pub sets_in_use: u32,
+    /// Most sets allocated at once
+    pub sets_high_water: u32,
+    /// Each descriptor type the pools hold, in type order
+    pub types: Vec<DescriptorTypeUsage>,
+}
+
+struct Pool {
+    pool: VkDescriptorPool,
+    max_sets: u32,
+    capacity: DescriptorCounts,
+    sets: u32,
+    used: DescriptorCounts,
+    /// The driver failed an allocation the counts had room for; cleared
+    /// when a set is freed
+    exhausted: bool,
+}
+
+impl Pool {
+    fn fits(&self, counts: &DescriptorCounts) -> bool {
+        !self.exhausted
+            && self.sets < self.max_sets
+            && counts.iter().all(|&(t, n)| count(&self.used, t) + n <= count(&self.capacity, t))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pools: Vec<Pool>,
+    /// Pool index and descriptor counts of each allocated set
+    sets: HashMap<u64, (usize, DescriptorCounts)>,
+    /// Most descriptors of each type one set has needed
+    per_set: DescriptorCounts,
+    in_use: DescriptorCounts,
+    high_water: DescriptorCounts,
+    sets_high_water: u32,
+}
+
+impl State {
+    fn note_layout(&mut self, counts: &DescriptorCounts) {
+        for &(t, n) in counts {
+            let most = count(&self.per_set, t);
+            if n > most {
+                add(&mut self.per_set, t, n - most);
+            }
+        }
+    }
+}
+
+/// The descriptor pools of a context or worker
+///
+/// Sets are allocated and freed under an internal lock and remember the
+/// pool they came from.
+pub(super) struct DescriptorPools {
+    device: VkDevice,
+    /// Sets a new pool has room for at least
+    min_sets: u32,
+    state: Mutex<State>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for DescriptorPools {}
+unsafe impl Sync for DescriptorPools {}
+
+impl DescriptorPools {
+    /// No pools yet; the first is created by the first allocation
+    pub(super) fn new(device: VkDevice, min_sets: u32) -> Self {
+        Self { device, min_sets: min_sets.max(1), state: Mutex::default() }
+    }
+
+    /// Allocate a set of `layout`, which holds `counts` descriptors
+    ///
+    /// # Safety
+    ///
+    /// `layout` must be a valid descriptor set layout of the device with
+    /// the bindings `counts` was taken from.
+    pub(super) unsafe fn allocate(&self, layout: VkDescriptorSetLayout, counts: &DescriptorCounts) -> Result<VkDescriptorSet> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.note_layout(counts);
+        loop {
+            let (index, fresh) = match state.pools.iter().position(|pool| pool.fits(counts)) {
+                Some(index) => (index, false),
+                None => {
+                    let pool = self.grow(state)?;
+                    state.pools.push(pool);
+                    (state.pools.len() - 1, true)
+                }
+            };
+            let pool = &mut state.pools[index];
+            let alloc_info = VkDescriptorSetAllocateInfo {
+                sType: VkStructureType::DescriptorSetAllocateInfo,
+                pNext: ptr::null(),
+                descriptorPool: pool.pool,
+                descriptorSetCount: 1,
+                pSetLayouts: &layout,
+            };
+            let mut set = VkDescriptorSet::NULL;
+            match vkAllocateDescriptorSets(self.device, &alloc_info, &mut set) {
+                VkResult::Success if set == VkDescriptorSet::NULL => {
+                    return Err(KronosError::CommandExecutionFailed("vkAllocateDescriptorSets returned NULL".into()));
+                }
+                VkResult::Success => {
+                    pool.sets += 1;
+                    for &(t, n) in counts {
+                        add(&mut pool.used, t, n);
+                        add(&mut state.in_use, t, n);
+                        let high_water = count(&state.high_water, t);
+                        if count(&state.in_use, t) > high_water {
+                            add(&mut state.high_water, t, count(&state.in_use, t) - high_water);
+                        }
+                    }
+                    state.sets.insert(set.as_raw(), (index, counts.clone()));
+                    state.sets_high_water = state.sets_high_water.max(state.sets.len() as u32);
+                    return Ok(set);
+                }
+                // Drivers may fragment a pool below its counts; a pool made
+                // for this set failing it is not fragmentation
+                VkResult::ErrorOutOfPoolMemory | VkResult::ErrorFragmentedPool if !fresh => pool.exhausted = true,
+                result => return Err(KronosError::from(result)),
+            }
+        }
+    }
+
+    /// Free `set`, which must no longer be in use by any submission
+    ///
+    /// # Safety
+    ///
+    /// `set` must have been allocated by [`allocate`](Self::allocate).
+    pub(super) unsafe fn free(&self, set: VkDescriptorSet) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let Some((index, counts)) = state.sets.remove(&set.as_raw()) else { return };
+        let pool = &mut state.pools[index];
+        vkFreeDescriptorSets(self.device, pool.pool, 1, &set);
+        pool.sets -= 1;
+        pool.exhausted = false;
+        for (t, n) in counts {
+            sub(&mut pool.used, t, n);
+            sub(&mut state.in_use, t, n);
+        }
+    }
+
+    /// Create a pool with room for `sets` sets of each layout with the
+    /// given counts, in addition to whatever the pools have free
+    ///
+    /// # Safety
+    ///
+    /// The device must be valid.
+    pub(super) unsafe fn reserve(&self, layouts: &[(&DescriptorCounts, u32)]) -> Result<()> {
+        let mut capacity = DescriptorCounts::new();
+        let mut max_sets = 0u32;
+        for &(counts, sets) in layouts {
+            max_sets = max_sets.saturating_add(sets);
+            for &(t, n) in counts {
+                add(&mut capacity, t, n.saturating_mul(sets));
+            }
+        }
+        if max_sets == 0 {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        for &(counts, _) in layouts {
+            state.note_layout(counts);
+        }
+        let pool = self.create_pool(max_sets, capacity)?;
+        state.pools.push(pool);
+        Ok(())
+    }
+
+    /// Create a pool with room for as many sets as all pools so far, and at
+    /// least `min_sets`, of the largest set seen
+    unsafe fn grow(&self, state: &State) -> Result<Pool> {
+        let existing: u32 = state.pools.iter().map(|pool| pool.max_sets).sum();
+        let max_sets = existing.max(self.min_sets);
+        let capacity = state.per_set.iter().map(|&(t, n)| (t, n.saturating_mul(max_sets))).collect();
+        self.create_pool(max_sets, capacity)
+    }
+
+    unsafe fn create_pool(&self, max_sets: u32, mut capacity: DescriptorCounts) -> Result<Pool> {
+        // Vulkan wants at least one pool size, even for sets without bindings
+        if capacity.is_empty() {
+            capacity.push((VkDescriptorType::StorageBuffer, 1));
+        }
+        let pool_sizes: Vec<VkDescriptorPoolSize> = capacity
+            .iter()
+            .map(|&(type_, descriptorCount)| VkDescriptorPoolSize { type_, descriptorCount })
+            .collect();
+        let pool_info = VkDescriptorPoolCreateInfo {
+            sType: VkStructureType::DescriptorPoolCreateInfo,
+            pNext: ptr::null(),
+            flags: VkDescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET,
+            maxSets: max_sets,
+            poolSizeCount: pool_sizes.len() as u32,
+            pPoolSizes: pool_sizes.as_ptr(),
+        };
+
+        let mut pool = VkDescriptorPool::NULL;
+        let result = vkCreateDescriptorPool(self.device, &pool_info, ptr::null(), &mut pool);
+        if result != VkResult::Success {
+            kronos_log!(Error, "[SAFE API] Failed to create descriptor pool: {:?}", result);
+            return Err(KronosError::from(result));
+        }
+        if pool == VkDescriptorPool::NULL {
+            return Err(KronosError::UnsupportedHardware(
+                "Descriptor pool creation returned NULL handle".into(),
+            ));
+        }
+        kronos_log!(Info, "[SAFE API] Descriptor pool created for {} sets of {:?}: {:?}", max_sets, capacity, pool);
+        Ok(Pool { pool, max_sets, capacity, sets: 0, used: DescriptorCounts::new(), exhausted: false })
+    }
+
+    pub(super) fn usage(&self) -> DescriptorPoolUsage {
+        let state = self.state.lock().unwrap();
+        let mut capacity = DescriptorCounts::new();
+        for pool in &state.pools {
+            for &(t, n) in &pool.capacity {
+                add(&mut capacity, t, n);
+            }
+        }
+        DescriptorPoolUsage {
+            pools: state.pools.len(),
+            max_sets: state.pools.iter().map(|pool| pool.max_sets).sum(),
+            sets_in_use: state.sets.len() as u32,
+            sets_high_water: state.sets_high_water,
+            // Sets only take descriptors of types their pool holds
+            types: capacity
+                .iter()
+                .map(|&(t, _)| DescriptorTypeUsage {
+                    descriptor_type: t,
+                    capacity: count(&capacity, t),
+                    in_use: count(&state.in_use, t),
+                    high_water: count(&state.high_water, t),
+                })
+                .collect(),
+        }
+    }
+
+    /// Destroy every pool, freeing their sets; safe to call more than once
+    ///
+    /// # Safety
+    ///
+    /// No set from the pools may be in use.
+    pub(super) unsafe fn destroy(&self) {
+        let mut state = self.state.lock().unwrap();
+        for pool in state.pools.drain(..) {
+            vkDestroyDescriptorPool(self.device, pool.pool, ptr::null());
+        }
+        *state = State::default();
+    }
+}
+
+impl ComputeContext {
+    /// Create a descriptor pool with room for the given number of sets of
+    /// each pipeline's bindings, see [`descriptor_pools`](super::descriptor_pools)
+    pub fn reserve_descriptor_sets(&self, pipelines: &[(&Pipeline, u32)]) -> Result<()> {
+        let layouts: Vec<_> = pipelines.iter().map(|&(pipeline, sets)| (pipeline.layouts.descriptor_counts(), sets)).collect();
+        self.with_inner(|inner| unsafe { inner.pools.lock().unwrap().descriptors.reserve(&layouts) })
+    }
+
+    /// Capacity, use and high-water mark of the context's descriptor pools
+    pub fn descriptor_pool_usage(&self) -> DescriptorPoolUsage {
+        self.with_inner(|inner| inner.pools.lock().unwrap().descriptors.usage())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_counts_are_per_type_in_type_order() {
+        let counts = layout_counts(&[
+            (0, VkDescriptorType::StorageBuffer),
+            (1, VkDescriptorType::StorageImage),
+            (2, VkDescriptorType::StorageBuffer),
+        ]);
+        assert_eq!(counts, vec![(VkDescriptorType::StorageImage, 1), (VkDescriptorType::StorageBuffer, 2)]);
+
+        let mut counts = counts;
+        sub(&mut counts, VkDescriptorType::StorageImage, 1);
+        assert_eq!(counts, vec![(VkDescriptorType::StorageBuffer, 2)]);
+    }
+}
@@ -0,0 +1,478 @@
+//! Process-level cache of physical device information
+//!
+//! Context creation needs the properties, features, queue families and
+//! extensions of every physical device. Some drivers are slow to answer
+//! these queries, so the answers are kept for the life of the process and
+//! reused by later contexts. The cache belongs to the set of loaded ICDs:
+//! switching ICDs discards it, and a device whose vendor, device ID or
+//! driver version changed is queried again.
+//!
+//! Call [`refresh_devices`] to force a full re-scan, for example after an
+//! external GPU has been plugged in.
+
+use super::*;
+use crate::implementation::icd_loader;
+#[cfg(feature = "implementation")]
+use crate::implementation::{
+    vkEnumerateDeviceExtensionProperties, vkGetPhysicalDeviceFeatures, vkGetPhysicalDeviceMemoryProperties,
+    vkGetPhysicalDeviceProperties, vkGetPhysicalDeviceProperties2, vkGetPhysicalDeviceQueueFamilyProperties2,
+};
+use std::ffi::CStr;
+use std::fmt;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+/// Least `maxComputeSharedMemorySize` Vulkan guarantees
+const MIN_COMPUTE_SHARED_MEMORY_SIZE: u32 = 16384;
+
+/// Least `maxPushConstantsSize` Vulkan guarantees
+const MIN_PUSH_CONSTANTS_SIZE: u32 = 128;
+
+/// Everything context creation needs to know about a physical device
+#[derive(Debug, Clone)]
+pub(super) struct DeviceInfo {
+    pub(super) properties: VkPhysicalDeviceProperties,
+    /// `limits.minStorageBufferOffsetAlignment`, from the full properties
+    pub(super) min_storage_buffer_offset_alignment: VkDeviceSize,
+    /// `limits.maxComputeSharedMemorySize`, from the full properties
+    pub(super) max_compute_shared_memory_size: u32,
+    /// `limits.minUniformBufferOffsetAlignment`, from the full properties
+    pub(super) min_uniform_buffer_offset_alignment: VkDeviceSize,
+    /// `limits.maxPushConstantsSize`, from the full properties
+    pub(super) max_push_constants_size: u32,
+    pub(super) memory_properties: VkPhysicalDeviceMemoryProperties,
+    pub(super) features: VkPhysicalDeviceFeatures,
+    pub(super) queue_families: Vec<VkQueueFamilyProperties>,
+    /// Global priorities each queue family supports, empty without
+    /// VK_KHR_global_priority
+    pub(super) global_priorities: Vec<Vec<VkQueueGlobalPriorityKHR>>,
+    pub(super) extensions: Vec<String>,
+    /// Driver identification, if the device reports it
+    pub(super) driver: Option<DriverInfo>,
+    /// `deviceUUID`, shared with other APIs driving the device; `None`
+    /// before Vulkan 1.1
+    pub(super) device_uuid: Option<[u8; VK_UUID_SIZE]>,
+    /// Where the device sits on the PCI bus, from VK_EXT_pci_bus_info
+    pub(super) pci_bus: Option<PciBusAddress>,
+}
+
+/// Which driver runs a device, from VK_KHR_driver_properties
+///
+/// Tells apart drivers for the same hardware, such as RADV, AMDVLK and
+/// AMD's proprietary driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverInfo {
+    pub id: VkDriverId,
+    /// Name the driver gives itself, e.g. "radv"
+    pub name: String,
+    /// Free-form version details, e.g. "Mesa 24.0.5"
+    pub info: String,
+    /// Conformance test suite version the driver passed
+    pub conformance_version: VkConformanceVersion,
+}
+
+impl fmt::Display for DriverInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id.name() {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "driver {}", self.id.0)?,
+        }
+        write!(f, " ({} {})", self.name, self.info)?;
+        let version = self.conformance_version;
+        write!(f, ", conformance {}.{}.{}.{}", version.major, version.minor, version.subminor, version.patch)
+    }
+}
+
+/// PCI address of a device, e.g. `0000:03:00.0`
+///
+/// Identifies one physical GPU even when several share a vendor and device
+/// ID, which is how the kernel and vendor tools name it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PciBusAddress {
+    pub domain: u32,
+    pub bus: u32,
+    pub device: u32,
+    pub function: u32,
+}
+
+impl PciBusAddress {
+    /// Parse the `domain:bus:device.function` form, all hexadecimal
+    #[cfg(feature = "telemetry")]
+    pub(super) fn parse(text: &str) -> Option<Self> {
+        let (domain, rest) = text.split_once(':')?;
+        let (bus, rest) = rest.split_once(':')?;
+        let (device, function) = rest.split_once('.')?;
+        let hex = |part: &str| u32::from_str_radix(part, 16).ok();
+        Some(Self {
+            domain: hex(domain)?,
+            bus: hex(bus)?,
+            device: hex(device)?,
+            function: hex(function)?,
+        })
+    }
+}
+
+impl fmt::Display for PciBusAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:02x}:{:02x}.{:x}", self.domain, self.bus, self.device, self.function)
+    }
+}
+
+impl DeviceInfo {
+    /// Query a physical device
+    ///
+    /// # Safety
+    ///
+    /// The device must be a valid VkPhysicalDevice handle
+    unsafe fn query(device: VkPhysicalDevice, properties: VkPhysicalDeviceProperties) -> Self {
+        let mut memory_properties = VkPhysicalDeviceMemoryProperties::default();
+        vkGetPhysicalDeviceMemoryProperties(device, &mut memory_properties);
+        let mut full = [VK_FALSE; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT];
+        vkGetPhysicalDeviceFeatures(device, full.as_mut_ptr() as *mut VkPhysicalDeviceFeatures);
+        let features = VkPhysicalDeviceFeatures::from_full(&full);
+        let extensions = query_extensions(device);
+        let global_priority = extensions.iter().any(|extension| {
+            extension == VK_KHR_GLOBAL_PRIORITY_EXTENSION_NAME || extension == VK_EXT_GLOBAL_PRIORITY_QUERY_EXTENSION_NAME
+        });
+        let (queue_families, global_priorities) = query_queue_families(device, global_priority);
+        let driver_properties = properties.apiVersion >= VK_API_VERSION_1_2
+            || extensions.iter().any(|extension| extension == VK_KHR_DRIVER_PROPERTIES_EXTENSION_NAME);
+        let pci_bus_info = extensions.iter().any(|extension| extension == VK_EXT_PCI_BUS_INFO_EXTENSION_NAME);
+        let full_properties = query_full_properties(device);
+        Self {
+            properties,
+            min_storage_buffer_offset_alignment: min_storage_buffer_offset_alignment(&full_properties),
+            max_compute_shared_memory_size: max_compute_shared_memory_size(&full_properties),
+            min_uniform_buffer_offset_alignment: min_uniform_buffer_offset_alignment(&full_properties),
+            max_push_constants_size: max_push_constants_size(&full_properties),
+            memory_properties,
+            features,
+            queue_families,
+            global_priorities,
+            extensions,
+            driver: if driver_properties { query_driver(device) } else { None },
+            device_uuid: if properties.apiVersion >= VK_API_VERSION_1_1 { query_device_uuid(device) } else { None },
+            pci_bus: if pci_bus_info { query_pci_bus(device) } else { None },
+        }
+    }
+
+    /// Queue family for the context's compute queue
+    ///
+    /// A `requested` family must support compute and have a queue.
+    /// Otherwise the first compute family is chosen or, with
+    /// `prefer_dedicated`, the first one without graphics support if the
+    /// device has one. Families that can create protected queues are only
+    /// chosen if nothing else computes: some drivers fail device creation
+    /// for them without protected memory, which Kronos never enables.
+    /// Errors say why the device cannot be used.
+    pub(super) fn compute_queue_family(&self, requested: Option<u32>, prefer_dedicated: bool) -> std::result::Result<u32, String> {
+        let usable = |family: &VkQueueFamilyProperties| {
+            family.queueFlags.contains(VkQueueFlags::COMPUTE) && family.queueCount > 0
+        };
+        if let Some(index) = requested {
+            let family = self.queue_families.get(index as usize).ok_or_else(|| {
+                format!("queue family {} does not exist (device has {})", index, self.queue_families.len())
+            })?;
+            if !usable(family) {
+                return Err(format!("queue family {} has no compute queue", index));
+            }
+            return Ok(index);
+        }
+        self.queue_families
+            .iter()
+            .enumerate()
+            .filter(|(_, family)| usable(family))
+            .min_by_key(|(index, family)| {
+                (
+                    family.queueFlags.contains(VkQueueFlags::PROTECTED),
+                    prefer_dedicated && family.queueFlags.contains(VkQueueFlags::GRAPHICS),
+                    *index,
+                )
+            })
+            .map(|(index, _)| index as u32)
+            .ok_or_else(|| "no queue family supports compute".to_string())
+    }
+
+    /// Whether the device exposes a device extension
+    pub(super) fn supports_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|extension| extension == name)
+    }
+
+    /// Whether `properties` describe the same device and driver build
+    fn matches(&self, properties: &VkPhysicalDeviceProperties) -> bool {
+        self.properties.vendorID == properties.vendorID
+            && self.properties.deviceID == properties.deviceID
+            && self.properties.driverVersion == properties.driverVersion
+    }
+}
+
+struct DeviceCache {
+    /// Library paths of the ICDs the devices were enumerated from
+    icds: Vec<PathBuf>,
+    /// Device information in enumeration order
+    devices: Vec<Arc<DeviceInfo>>,
+}
+
+static DEVICE_CACHE: Mutex<Option<DeviceCache>> = Mutex::new(None);
+
+/// Discard cached device information
+///
+/// The next context creation enumerates and queries every device again.
+pub fn refresh_devices() {
+    *DEVICE_CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// One line per cached device naming the hardware and driver
+///
+/// Empty until a context has enumerated the devices.
+pub(crate) fn describe_cached_devices() -> Vec<String> {
+    let cache = DEVICE_CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(cache) = cache.as_ref() else {
+        return Vec::new();
+    };
+    cache
+        .devices
+        .iter()
+        .map(|info| {
+            let properties = &info.properties;
+            // SAFETY: deviceName is a NUL-terminated string filled in by the driver
+            let name = unsafe { CStr::from_ptr(properties.deviceName.as_ptr()) }.to_string_lossy();
+            let mut line = format!(
+                "{} ({:?}, vendor 0x{:04x}, device 0x{:04x}, Vulkan {}, driver version 0x{:x})",
+                name,
+                properties.device_type(),
+                properties.vendorID,
+                properties.deviceID,
+                Version::from_raw(properties.apiVersion),
+                properties.driverVersion
+            );
+            if let Some(driver) = &info.driver {
+                line.push_str(&format!(", {}", driver));
+            }
+            line
+        })
+        .collect()
+}
+
+fn loaded_icds() -> Vec<PathBuf> {
+    if icd_loader::aggregated_mode_enabled() {
+        icd_loader::get_all_icds().iter().map(|icd| icd.library_path.clone()).collect()
+    } else {
+        icd_loader::selected_icd_info().map(|info| info.library_path).into_iter().collect()
+    }
+}
+
+/// Information for each of `devices`, from the cache where still valid
+///
+/// Only the (cheap) properties query is repeated for cached devices.
+///
+/// # Safety
+///
+/// Every handle in `devices` must be a valid VkPhysicalDevice
+pub(super) unsafe fn device_infos(devices: &[VkPhysicalDevice]) -> Vec<Arc<DeviceInfo>> {
+    let icds = loaded_icds();
+    let mut cache = DEVICE_CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let cached = match cache.take() {
+        Some(cache) if cache.icds == icds && cache.devices.len() == devices.len() => cache.devices,
+        _ => Vec::new(),
+    };
+
+    let infos: Vec<Arc<DeviceInfo>> = devices
+        .iter()
+        .enumerate()
+        .map(|(index, device)| {
+            let mut properties = VkPhysicalDeviceProperties::default();
+            vkGetPhysicalDeviceProperties(*device, &mut properties);
+            match cached.get(index) {
+                Some(info) if info.matches(&properties) => info.clone(),
+                _ => {
+                    kronos_log!(Debug, "[SAFE API] Querying physical device {} ({:?})", index, device);
+                    Arc::new(DeviceInfo::query(*device, properties))
+                }
+            }
+        })
+        .collect();
+
+    *cache = Some(DeviceCache { icds, devices: infos.clone() });
+    infos
+}
+
+/// Query the properties in their full Vulkan 1.0 layout, for the limits
+/// outside the simplified structure
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle
+unsafe fn query_full_properties(device: VkPhysicalDevice) -> [u64; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8] {
+    let mut properties = [0u64; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8];
+    vkGetPhysicalDeviceProperties(device, properties.as_mut_ptr() as *mut VkPhysicalDeviceProperties);
+    properties
+}
+
+/// `limits.minStorageBufferOffsetAlignment` of full properties
+///
+/// Drivers that leave it at 0 get the largest alignment Vulkan allows, 256.
+fn min_storage_buffer_offset_alignment(properties: &[u64]) -> VkDeviceSize {
+    match properties[VK_PHYSICAL_DEVICE_PROPERTIES_MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT_OFFSET / 8] {
+        0 => 256,
+        alignment => alignment,
+    }
+}
+
+/// `limits.minUniformBufferOffsetAlignment` of full properties
+///
+/// Drivers that leave it at 0 get the largest alignment Vulkan allows, 256.
+fn min_uniform_buffer_offset_alignment(properties: &[u64]) -> VkDeviceSize {
+    match properties[VK_PHYSICAL_DEVICE_PROPERTIES_MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT_OFFSET / 8] {
+        0 => 256,
+        alignment => alignment,
+    }
+}
+
+/// 32-bit limit at byte `offset` of full properties
+fn limit_u32(properties: &[u64], offset: usize) -> u32 {
+    let word = properties[offset / 8].to_ne_bytes();
+    let start = offset % 8;
+    u32::from_ne_bytes([word[start], word[start + 1], word[start + 2], word[start + 3]])
+}
+
+/// `limits.maxComputeSharedMemorySize` of full properties
+///
+/// Drivers that leave it at 0 get the least Vulkan guarantees, 16 KiB.
+fn max_compute_shared_memory_size(properties: &[u64]) -> u32 {
+    match limit_u32(properties, VK_PHYSICAL_DEVICE_PROPERTIES_MAX_COMPUTE_SHARED_MEMORY_SIZE_OFFSET) {
+        0 => MIN_COMPUTE_SHARED_MEMORY_SIZE,
+        size => size,
+    }
+}
+
+/// `limits.maxPushConstantsSize` of full properties
+///
+/// Drivers that leave it at 0 get the least Vulkan guarantees, 128 bytes.
+fn max_push_constants_size(properties: &[u64]) -> u32 {
+    match limit_u32(properties, VK_PHYSICAL_DEVICE_PROPERTIES_MAX_PUSH_CONSTANTS_SIZE_OFFSET) {
+        0 => MIN_PUSH_CONSTANTS_SIZE,
+        size => size,
+    }
+}
+
+/// Query the driver identification of a physical device
+///
+/// `None` if the driver leaves the chained structure unfilled, as drivers
+/// without the Vulkan 1.1 properties query do.
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle that supports
+/// Vulkan 1.2 or exposes VK_KHR_driver_properties
+unsafe fn query_driver(device: VkPhysicalDevice) -> Option<DriverInfo> {
+    let mut driver = VkPhysicalDeviceDriverProperties::default();
+    let mut properties = VkPhysicalDeviceProperties2::default();
+    let mut chain = Chain::new(&mut properties).push(&mut driver);
+    vkGetPhysicalDeviceProperties2(device, chain.as_mut_ptr());
+    if driver.driverID == VkDriverId::default() {
+        return None;
+    }
+    Some(DriverInfo {
+        id: driver.driverID,
+        name: CStr::from_ptr(driver.driverName.as_ptr()).to_string_lossy().into_owned(),
+        info: CStr::from_ptr(driver.driverInfo.as_ptr()).to_string_lossy().into_owned(),
+        conformance_version: driver.conformanceVersion,
+    })
+}
+
+/// Query the `deviceUUID` of a physical device
+///
+/// `None` if the driver leaves it all zeros, as drivers without the
+/// Vulkan 1.1 properties query do.
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle that supports
+/// Vulkan 1.1
+unsafe fn query_device_uuid(device: VkPhysicalDevice) -> Option<[u8; VK_UUID_SIZE]> {
+    let mut id = VkPhysicalDeviceIDProperties::default();
+    let mut properties = VkPhysicalDeviceProperties2::default();
+    let mut chain = Chain::new(&mut properties).push(&mut id);
+    vkGetPhysicalDeviceProperties2(device, chain.as_mut_ptr());
+    (id.deviceUUID != [0; VK_UUID_SIZE]).then_some(id.deviceUUID)
+}
+
+/// Query the PCI address of a physical device
+///
+/// `None` if the driver leaves the chained structure unfilled; no GPU sits
+/// at address `0000:00:00.0`, the host bridge.
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle that exposes
+/// VK_EXT_pci_bus_info
+unsafe fn query_pci_bus(device: VkPhysicalDevice) -> Option<PciBusAddress> {
+    let mut pci = VkPhysicalDevicePCIBusInfoPropertiesEXT::default();
+    let mut properties = VkPhysicalDeviceProperties2::default();
+    let mut chain = Chain::new(&mut properties).push(&mut pci);
+    vkGetPhysicalDeviceProperties2(device, chain.as_mut_ptr());
+    let address = PciBusAddress {
+        domain: pci.pciDomain,
+        bus: pci.pciBus,
+        device: pci.pciDevice,
+        function: pci.pciFunction,
+    };
+    (address != PciBusAddress { domain: 0, bus: 0, device: 0, function: 0 }).then_some(address)
+}
+
+/// Query all queue family properties of a physical device
+///
+/// With `global_priority` the global priorities of each family are chained
+/// into the query; otherwise the returned priority lists are empty.
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle, and `global_priority`
+/// only set if it exposes VK_KHR_global_priority or
+/// VK_EXT_global_priority_query
+unsafe fn query_queue_families(
+    device: VkPhysicalDevice,
+    global_priority: bool,
+) -> (Vec<VkQueueFamilyProperties>, Vec<Vec<VkQueueGlobalPriorityKHR>>) {
+    let mut queue_family_count = 0;
+    vkGetPhysicalDeviceQueueFamilyProperties2(device, &mut queue_family_count, ptr::null_mut());
+    kronos_log!(Info, "[SAFE API] Device has {} queue families", queue_family_count);
+
+    let count = queue_family_count as usize;
+    let mut priorities = vec![VkQueueFamilyGlobalPriorityPropertiesKHR::default(); if global_priority { count } else { 0 }];
+    let mut queue_families = vec![VkQueueFamilyProperties2::default(); count];
+    for (family, priorities) in queue_families.iter_mut().zip(priorities.iter_mut()) {
+        Chain::new(family).push(priorities);
+    }
+    vkGetPhysicalDeviceQueueFamilyProperties2(device, &mut queue_family_count, queue_families.as_mut_ptr());
+
+    let count = (queue_family_count as usize).min(count);
+    let global_priorities = priorities.iter().take(count).map(|p| p.priorities().to_vec()).collect();
+    let queue_families = queue_families.iter().take(count).map(|family| family.queueFamilyProperties).collect();
+    (queue_families, global_priorities)
+}
+
+/// Names of the device extensions a physical device exposes
+///
+/// # Safety
+///
+/// The device must be a valid VkPhysicalDevice handle
+unsafe fn query_extensions(device: VkPhysicalDevice) -> Vec<String> {
+    let mut count = 0u32;
+    let result = vkEnumerateDeviceExtensionProperties(device, ptr::null(), &mut count, ptr::null_mut());
+    if result != VkResult::Success || count == 0 {
+        return Vec::new();
+    }
+    let mut extensions = vec![VkExtensionProperties::default(); count as usize];
+    let result = vkEnumerateDeviceExtensionProperties(device, ptr::null(), &mut count, extensions.as_mut_ptr());
+    if !matches!(result, VkResult::Success | VkResult::Incomplete) {
+        return Vec::new();
+    }
+    extensions.truncate(count as usize);
+    extensions
+        .iter()
+        .map(|extension| CStr::from_ptr(extension.extensionName.as_ptr()).to_string_lossy().into_owned())
+        .collect()
+}
@@ -0,0 +1,148 @@
+//! Device loss and removal notifications
+//!
+//! When a submission or wait reports `VK_ERROR_DEVICE_LOST`, the context
+//! re-enumerates the physical devices of its instance to tell a driver reset
+//! or hang apart from a device that is gone (an unplugged eGPU, for example)
+//! and notifies callbacks registered with
+//! [`ComputeContext::on_device_event`]. A lost context cannot be recovered;
+//! services should move their work to a new context.
+
+use super::*;
+use super::devices;
+#[cfg(feature = "implementation")]
+use crate::implementation::{vkEnumeratePhysicalDevices, vkGetPhysicalDeviceProperties};
+use std::ffi::CStr;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A change in the state of a context's device
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeviceEvent {
+    /// The device was lost but is still enumerated, e.g. after a driver
+    /// reset or GPU hang
+    Lost { device_name: String },
+    /// The device was lost and is no longer enumerated
+    Removed { device_name: String },
+}
+
+type DeviceEventCallback = Arc<dyn Fn(&DeviceEvent) + Send + Sync>;
+
+/// Loss detection and event callbacks of a context
+pub(super) struct DeviceEvents {
+    instance: VkInstance,
+    /// Identity of the context's device, for re-enumeration
+    vendor_id: u32,
+    device_id: u32,
+    device_name: String,
+    lost: AtomicBool,
+    event: Mutex<Option<DeviceEvent>>,
+    callbacks: Mutex<Vec<DeviceEventCallback>>,
+}
+
+impl DeviceEvents {
+    pub(super) fn new(instance: VkInstance, properties: &VkPhysicalDeviceProperties) -> Self {
+        // SAFETY: deviceName is a NUL-terminated string filled in by the driver
+        let device_name = unsafe { CStr::from_ptr(properties.deviceName.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        Self {
+            instance,
+            vendor_id: properties.vendorID,
+            device_id: properties.deviceID,
+            device_name,
+            lost: AtomicBool::new(false),
+            event: Mutex::new(None),
+            callbacks: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    /// Pass `result` through, raising a device event the first time it
+    /// reports the device lost
+    pub(super) fn check(&self, result: VkResult) -> VkResult {
+        if result == VkResult::ErrorDeviceLost && !self.lost.swap(true, Ordering::AcqRel) {
+            let event = if self.still_enumerated() {
+                DeviceEvent::Lost { device_name: self.device_name.clone() }
+            } else {
+                DeviceEvent::Removed { device_name: self.device_name.clone() }
+            };
+            kronos_log!(Error, "[SAFE API] {:?}", event);
+            let callbacks = self.callbacks.lock().unwrap();
+            *self.event.lock().unwrap() = Some(event.clone());
+            Self::deliver(callbacks.clone(), event);
+        }
+        result
+    }
+
+    /// Whether the instance still enumerates a device with our identity
+    fn still_enumerated(&self) -> bool {
+        // Device information cached before the loss may describe hardware
+        // that no longer exists
+        devices::refresh_devices();
+        unsafe {
+            let mut count = 0;
+            if vkEnumeratePhysicalDevices(self.instance, &mut count, ptr::null_mut()) != VkResult::Success {
+                return false;
+            }
+            let mut physical_devices = vec![VkPhysicalDevice::NULL; count as usize];
+            if vkEnumeratePhysicalDevices(self.instance, &mut count, physical_devices.as_mut_ptr()) != VkResult::Success {
+                return false;
+            }
+            physical_devices.truncate(count as usize);
+            physical_devices.iter().any(|device| {
+                let mut properties = VkPhysicalDeviceProperties::default();
+                vkGetPhysicalDeviceProperties(*device, &mut properties);
+                properties.vendorID == self.vendor_id && properties.deviceID == self.device_id
+            })
+        }
+    }
+
+    /// Run callbacks on their own thread, so they may take the context lock
+    fn deliver(callbacks: Vec<DeviceEventCallback>, event: DeviceEvent) {
+        if callbacks.is_empty() {
+            return;
+        }
+        let spawned = std::thread::Builder::new()
+            .name("kronos-device-events".into())
+            .spawn(move || {
+                for callback in callbacks {
+                    callback(&event);
+                }
+            });
+        if let Err(err) = spawned {
+            kronos_log!(Error, "[SAFE API] Failed to spawn device event thread: {}", err);
+        }
+    }
+
+    fn subscribe(&self, callback: DeviceEventCallback) {
+        let mut callbacks = self.callbacks.lock().unwrap();
+        callbacks.push(callback.clone());
+        // A callback registered after the loss still hears about it
+        if let Some(event) = self.event.lock().unwrap().clone() {
+            Self::deliver(vec![callback], event);
+        }
+    }
+}
+
+impl ComputeContext {
+    /// Register a callback for device loss and removal
+    ///
+    /// Callbacks run on a separate thread, once per event. Registering after
+    /// the device was lost delivers that event immediately.
+    pub fn on_device_event<F>(&self, callback: F)
+    where
+        F: Fn(&DeviceEvent) + Send + Sync + 'static,
+    {
+        self.with_inner(|inner| inner.device_events.subscribe(Arc::new(callback)))
+    }
+
+    /// Whether the device has reported `VK_ERROR_DEVICE_LOST`
+    pub fn is_device_lost(&self) -> bool {
+        self.with_inner(|inner| inner.device_events.is_lost())
+    }
+}
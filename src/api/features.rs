@@ -0,0 +1,85 @@
+//! Device feature requirements for context creation
+
+use crate::core::*;
+use crate::sys::*;
+
+/// Compute-relevant physical device features
+///
+/// Used with [`ContextBuilder::require_features`](super::ContextBuilder::require_features)
+/// to restrict device selection to GPUs that support every requested feature.
+/// Requested features are also enabled on the created logical device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Features {
+    pub robust_buffer_access: bool,
+    pub shader_float64: bool,
+    pub shader_int64: bool,
+    pub shader_int16: bool,
+    pub shader_storage_buffer_array_dynamic_indexing: bool,
+    pub shader_storage_image_array_dynamic_indexing: bool,
+    pub shader_storage_image_read_without_format: bool,
+    pub shader_storage_image_write_without_format: bool,
+}
+
+impl Features {
+    /// Returns true if no feature is requested
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Names of the features requested by `self` that `available` lacks
+    ///
+    /// Names follow the Vulkan spelling so they can be matched against
+    /// `vulkaninfo` output.
+    pub fn missing_from(&self, available: &Features) -> Vec<&'static str> {
+        self.entries()
+            .iter()
+            .zip(available.entries().iter())
+            .filter(|((_, wanted), (_, have))| *wanted && !*have)
+            .map(|((name, _), _)| *name)
+            .collect()
+    }
+
+    fn entries(&self) -> [(&'static str, bool); 8] {
+        [
+            ("robustBufferAccess", self.robust_buffer_access),
+            ("shaderFloat64", self.shader_float64),
+            ("shaderInt64", self.shader_int64),
+            ("shaderInt16", self.shader_int16),
+            ("shaderStorageBufferArrayDynamicIndexing", self.shader_storage_buffer_array_dynamic_indexing),
+            ("shaderStorageImageArrayDynamicIndexing", self.shader_storage_image_array_dynamic_indexing),
+            ("shaderStorageImageReadWithoutFormat", self.shader_storage_image_read_without_format),
+            ("shaderStorageImageWriteWithoutFormat", self.shader_storage_image_write_without_format),
+        ]
+    }
+}
+
+impl From<VkPhysicalDeviceFeatures> for Features {
+    fn from(f: VkPhysicalDeviceFeatures) -> Self {
+        Self {
+            robust_buffer_access: f.robustBufferAccess == VK_TRUE,
+            shader_float64: f.shaderFloat64 == VK_TRUE,
+            shader_int64: f.shaderInt64 == VK_TRUE,
+            shader_int16: f.shaderInt16 == VK_TRUE,
+            shader_storage_buffer_array_dynamic_indexing: f.shaderStorageBufferArrayDynamicIndexing == VK_TRUE,
+            shader_storage_image_array_dynamic_indexing: f.shaderStorageImageArrayDynamicIndexing == VK_TRUE,
+            shader_storage_image_read_without_format: f.shaderStorageImageReadWithoutFormat == VK_TRUE,
+            shader_storage_image_write_without_format: f.shaderStorageImageWriteWithoutFormat == VK_TRUE,
+        }
+    }
+}
+
+impl From<Features> for VkPhysicalDeviceFeatures {
+    fn from(f: Features) -> Self {
+        let b = |v: bool| if v { VK_TRUE } else { VK_FALSE };
+        Self {
+            robustBufferAccess: b(f.robust_buffer_access),
+            shaderFloat64: b(f.shader_float64),
+            shaderInt64: b(f.shader_int64),
+            shaderInt16: b(f.shader_int16),
+            shaderStorageBufferArrayDynamicIndexing: b(f.shader_storage_buffer_array_dynamic_indexing),
+            shaderStorageImageArrayDynamicIndexing: b(f.shader_storage_image_array_dynamic_indexing),
+            shaderStorageImageReadWithoutFormat: b(f.shader_storage_image_read_without_format),
+            shaderStorageImageWriteWithoutFormat: b(f.shader_storage_image_write_without_format),
+        }
+    }
+}
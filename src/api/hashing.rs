@@ -0,0 +1,245 @@
+//! Buffer content hashes around dispatches, for bisecting nondeterminism
+//!
+//! With [`enable_dispatch_hashing`](ComputeContext::enable_dispatch_hashing)
+//! every dispatch reads back the buffer ranges bound to it before and after
+//! it runs and records a hash of each in the context's hash trace. Traces
+//! of two runs, or of one workload on two devices, are compared with
+//! [`first_divergence`], which names the first dispatch whose outputs
+//! differ although its inputs matched:
+//!
+//! ```no_run
+//! use kronos_compute::api::{first_divergence, ComputeContext, DispatchHashes};
+//!
+//! # fn run(ctx: &ComputeContext) -> kronos_compute::api::Result<()> { Ok(()) }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let ctx = ComputeContext::new()?;
+//! ctx.enable_dispatch_hashing();
+//! run(&ctx)?;
+//! let hashes = ctx.dispatch_hashes();
+//!
+//! let reference: Vec<DispatchHashes> = serde_json::from_str(&std::fs::read_to_string("reference.json")?)?;
+//! match first_divergence(&reference, &hashes) {
+//!     Some(divergence) => eprintln!("{}", divergence),
+//!     None => std::fs::write("reference.json", serde_json::to_string(&hashes)?)?,
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Hashes are 64-bit FNV-1a over the bytes of each range, the same on
+//! every platform, and [`hash_bytes`] computes them for host data. Every
+//! range is copied into a staging buffer on the host, and the device is
+//! idle before the outputs are read, so this is for debug runs only.
+//! Submitted dispatches are waited for like executed ones. Dry runs,
+//! dispatches in a [`SubmitPlan`], command buffers from
+//! [`into_command_buffer`](CommandBuilder::into_command_buffer) and buffers
+//! bound through a [`DescriptorSet`] are not hashed.
+
+use super::*;
+use crate::*; // Need all the type definitions
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Hashes of the buffers bound to one dispatch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DispatchHashes {
+    /// Label of the dispatch's (first) pipeline
+    pub label: String,
+    /// Binding and content hash of each bound range before the dispatch
+    pub before: Vec<(u32, u64)>,
+    /// Binding and content hash of each bound range after the dispatch
+    pub after: Vec<(u32, u64)>,
+}
+
+/// Where two hash traces first differ, see [`first_divergence`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashDivergence {
+    /// The runs dispatched different kernels or bindings at `index`
+    Sequence { index: usize },
+    /// `binding` already differed before dispatch `index`, so it was
+    /// changed outside the hashed dispatches
+    Input { index: usize, label: String, binding: u32 },
+    /// Dispatch `index` read the same inputs and wrote different contents
+    /// to `binding`
+    Output { index: usize, label: String, binding: u32 },
+    /// One trace ends after `index` dispatches and the other does not
+    Length { index: usize },
+}
+
+impl fmt::Display for HashDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashDivergence::Sequence { index } => {
+                write!(f, "dispatch {} runs a different kernel or binds different buffers", index)
+            }
+            HashDivergence::Input { index, label, binding } => write!(
+                f,
+                "binding {} of dispatch {} ('{}') differs before the dispatch; it was changed outside hashed dispatches",
+                binding, index, label
+            ),
+            HashDivergence::Output { index, label, binding } => write!(
+                f,
+                "dispatch {} ('{}') wrote different contents to binding {} from the same inputs",
+                index, label, binding
+            ),
+            HashDivergence::Length { index } => write!(f, "one trace ends after {} dispatches", index),
+        }
+    }
+}
+
+/// First point where trace `actual` differs from `expected`, or `None` if
+/// they match
+pub fn first_divergence(expected: &[DispatchHashes], actual: &[DispatchHashes]) -> Option<HashDivergence> {
+    let differing = |a: &[(u32, u64)], b: &[(u32, u64)]| a.iter().zip(b).find(|(a, b)| a != b).map(|(a, _)| a.0);
+    for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        let bindings = |hashes: &DispatchHashes| hashes.before.iter().map(|&(binding, _)| binding).collect::<Vec<_>>();
+        if expected.label != actual.label || bindings(expected) != bindings(actual) {
+            return Some(HashDivergence::Sequence { index });
+        }
+        let label = expected.label.clone();
+        if let Some(binding) = differing(&expected.before, &actual.before) {
+            return Some(HashDivergence::Input { index, label, binding });
+        }
+        if let Some(binding) = differing(&expected.after, &actual.after) {
+            return Some(HashDivergence::Output { index, label, binding });
+        }
+    }
+    (expected.len() != actual.len()).then(|| HashDivergence::Length { index: expected.len().min(actual.len()) })
+}
+
+/// 64-bit FNV-1a hash of `bytes`, as recorded in the hash trace
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Hash trace of a context
+#[derive(Default)]
+pub(super) struct HashTrace {
+    enabled: bool,
+    entries: Vec<DispatchHashes>,
+}
+
+/// Input hashes of a dispatch waiting for its outputs
+pub(super) struct PendingHashes {
+    label: String,
+    before: Vec<(u32, u64)>,
+}
+
+impl PendingHashes {
+    /// Hash the `ranges` bound to a dispatch of `label` about to run, or
+    /// `None` if hashing is off
+    pub(super) fn before(context: &ComputeContext, label: &str, ranges: &[(u32, VkBuffer, VkDeviceSize, usize)]) -> Result<Option<Self>> {
+        let enabled = context.with_inner(|inner| {
+            inner.hashes.lock().unwrap().enabled && !inner.dry_run.load(std::sync::atomic::Ordering::Acquire)
+        });
+        if !enabled {
+            return Ok(None);
+        }
+        Ok(Some(Self { label: label.to_owned(), before: hash_ranges(context, ranges)? }))
+    }
+
+    /// Hash the same `ranges` once the dispatch has completed and record
+    /// the entry
+    pub(super) fn after(self, context: &ComputeContext, ranges: &[(u32, VkBuffer, VkDeviceSize, usize)]) -> Result<()> {
+        // Submitted dispatches may still run, on any queue
+        context.with_inner(|inner| {
+            let _queue = inner.queue_lock.lock().unwrap();
+            match inner.device_events.check(unsafe { vkDeviceWaitIdle(inner.device) }) {
+                VkResult::Success => Ok(()),
+                result => Err(KronosError::from(result)),
+            }
+        })?;
+        let after = hash_ranges(context, ranges)?;
+        context.with_inner(|inner| {
+            inner.hashes.lock().unwrap().entries.push(DispatchHashes { label: self.label, before: self.before, after });
+        });
+        Ok(())
+    }
+}
+
+fn hash_ranges(context: &ComputeContext, ranges: &[(u32, VkBuffer, VkDeviceSize, usize)]) -> Result<Vec<(u32, u64)>> {
+    ranges
+        .iter()
+        .map(|&(binding, buffer, offset, size)| Ok((binding, hash_bytes(&read_range(context, buffer, offset, size)?))))
+        .collect()
+}
+
+/// Copy `size` bytes at `offset` in `buffer` back to the host
+fn read_range(context: &ComputeContext, buffer: VkBuffer, offset: VkDeviceSize, size: usize) -> Result<Vec<u8>> {
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+    let staging = context.create_buffer_uninit(size)?;
+    let region = VkBufferCopy { srcOffset: offset, dstOffset: 0, size: size as VkDeviceSize };
+    // SAFETY: the range lies within the bound buffer, kept alive by the builder
+    context.with_inner(|inner| unsafe { context.copy_raw(buffer, staging.buffer.on(inner.id), &[region]) })?;
+    staging.read::<u8>()
+}
+
+impl Buffer {
+    /// Hash of the buffer's contents, as recorded in the hash trace, see
+    /// [`hashing`](super::hashing)
+    pub fn content_hash(&self) -> Result<u64> {
+        Ok(hash_bytes(&self.read::<u8>()?))
+    }
+}
+
+impl ComputeContext {
+    /// Start hashing the buffers of every dispatch into a fresh trace, see
+    /// [`hashing`](super::hashing)
+    pub fn enable_dispatch_hashing(&self) {
+        self.with_inner(|inner| *inner.hashes.lock().unwrap() = HashTrace { enabled: true, entries: Vec::new() });
+    }
+
+    /// Stop hashing; the trace is kept
+    pub fn disable_dispatch_hashing(&self) {
+        self.with_inner(|inner| inner.hashes.lock().unwrap().enabled = false);
+    }
+
+    /// Hashes of each dispatch since hashing was last enabled, in
+    /// submission order
+    pub fn dispatch_hashes(&self) -> Vec<DispatchHashes> {
+        self.with_inner(|inner| inner.hashes.lock().unwrap().entries.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(label: &str, before: &[(u32, u64)], after: &[(u32, u64)]) -> DispatchHashes {
+        DispatchHashes { label: label.into(), before: before.to_vec(), after: after.to_vec() }
+    }
+
+    #[test]
+    fn test_hash_bytes_is_fnv1a() {
+        assert_eq!(hash_bytes(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_bytes(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_first_divergence_blames_the_first_differing_dispatch() {
+        let run = vec![hashes("fill", &[(0, 1)], &[(0, 2)]), hashes("scale", &[(0, 2), (1, 3)], &[(0, 2), (1, 4)])];
+        assert_eq!(first_divergence(&run, &run), None);
+
+        let mut nondeterministic = run.clone();
+        nondeterministic[1].after[1].1 = 5;
+        assert_eq!(
+            first_divergence(&run, &nondeterministic),
+            Some(HashDivergence::Output { index: 1, label: "scale".into(), binding: 1 })
+        );
+
+        let mut host_write = run.clone();
+        host_write[1].before[1].1 = 9;
+        host_write[1].after[1].1 = 5;
+        assert_eq!(
+            first_divergence(&run, &host_write),
+            Some(HashDivergence::Input { index: 1, label: "scale".into(), binding: 1 })
+        );
+
+        let mut reordered = run.clone();
+        reordered.swap(0, 1);
+        assert_eq!(first_divergence(&run, &reordered), Some(HashDivergence::Sequence { index: 0 }));
+        assert_eq!(first_divergence(&run, &run[..1]), Some(HashDivergence::Length { index: 1 }));
+    }
+}
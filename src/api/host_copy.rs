@@ -0,0 +1,147 @@
+//! Copies from host memory into mapped device memory
+//!
+//! Host-visible memory that is not HOST_CACHED is mapped write-combined on
+//! most drivers: the CPU gathers stores in a handful of line-sized buffers
+//! and flushes them over the bus, uncached. A full, in-order line goes out
+//! as one burst, but small writes scattered across the mapping evict those
+//! buffers half-filled and each flush becomes a partial bus transaction,
+//! which is several times slower than the bulk copy it could have been.
+//!
+//! Kronos picks a [`CopyStrategy`] for each write from the memory type it
+//! lands in. Writes of at least [`STREAMING_THRESHOLD`] bytes into
+//! write-combined memory use nontemporal streaming stores, which fill whole
+//! lines in order and bypass the cache; everything else is a plain
+//! `memcpy`. Scattered writes through [`Buffer::write_regions`] go through a
+//! shadow copy first, so adjacent regions reach the mapping as one stream.
+//!
+//! Streaming stores need x86_64; elsewhere every strategy is a `memcpy`.
+//! The `host_copy` benchmark compares both strategies across sizes and
+//! backs the threshold:
+//!
+//! ```text
+//! cargo bench --bench host_copy
+//! ```
+
+use super::*;
+use std::ptr;
+
+/// Smallest write into write-combined memory that is streamed
+///
+/// Below it, aligning the destination and fencing the stores costs more
+/// than the partial line flushes they avoid.
+pub const STREAMING_THRESHOLD: usize = 256;
+
+/// How bytes are copied into mapped memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStrategy {
+    /// `ptr::copy_nonoverlapping`
+    Memcpy,
+    /// Aligned nontemporal stores followed by a store fence
+    Streaming,
+}
+
+/// Whether memory of a type with `flags` is mapped write-combined
+pub fn is_write_combined(flags: VkMemoryPropertyFlags) -> bool {
+    flags.contains(VkMemoryPropertyFlags::HOST_VISIBLE) && !flags.contains(VkMemoryPropertyFlags::HOST_CACHED)
+}
+
+impl CopyStrategy {
+    /// Strategy for writing `len` bytes into memory of a type with `flags`
+    pub fn for_memory(flags: VkMemoryPropertyFlags, len: usize) -> Self {
+        if cfg!(target_arch = "x86_64") && is_write_combined(flags) && len >= STREAMING_THRESHOLD {
+            CopyStrategy::Streaming
+        } else {
+            CopyStrategy::Memcpy
+        }
+    }
+
+    /// Copy `src` to `dst`
+    ///
+    /// # Safety
+    ///
+    /// `dst` must be valid for writes of `src.len()` bytes and must not
+    /// overlap `src`.
+    pub unsafe fn copy(self, src: &[u8], dst: *mut u8) {
+        match self {
+            CopyStrategy::Memcpy => ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()),
+            CopyStrategy::Streaming => stream(src, dst),
+        }
+    }
+}
+
+/// Copy `src` into memory of a type with `flags` mapped at `dst`
+///
+/// # Safety
+///
+/// As for [`CopyStrategy::copy`].
+pub(super) unsafe fn write_mapped(flags: VkMemoryPropertyFlags, src: &[u8], dst: *mut u8) {
+    CopyStrategy::for_memory(flags, src.len()).copy(src, dst)
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn stream(src: &[u8], dst: *mut u8) {
+    use std::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_sfence, _mm_stream_si128};
+
+    let len = src.len();
+    let src = src.as_ptr();
+    let head = dst.align_offset(16).min(len);
+    ptr::copy_nonoverlapping(src, dst, head);
+    let mut at = head;
+    // SSE2 is part of x86_64, so these need no feature detection
+    while at + 64 <= len {
+        for lane in (0..64).step_by(16) {
+            let value = _mm_loadu_si128(src.add(at + lane) as *const __m128i);
+            _mm_stream_si128(dst.add(at + lane) as *mut __m128i, value);
+        }
+        at += 64;
+    }
+    while at + 16 <= len {
+        _mm_stream_si128(dst.add(at) as *mut __m128i, _mm_loadu_si128(src.add(at) as *const __m128i));
+        at += 16;
+    }
+    ptr::copy_nonoverlapping(src.add(at), dst.add(at), len - at);
+    // Streaming stores are weakly ordered; fence them before a submission
+    // can tell the device to read what they wrote
+    _mm_sfence();
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn stream(src: &[u8], dst: *mut u8) {
+    ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len())
+}
+
+impl Buffer {
+    /// Whether the buffer's memory is mapped write-combined, see
+    /// [`host_copy`](super::host_copy)
+    pub fn is_write_combined(&self) -> bool {
+        self.is_host_visible() && is_write_combined(self.memory_flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COHERENT: VkMemoryPropertyFlags = VkMemoryPropertyFlags::HOST_VISIBLE.union(VkMemoryPropertyFlags::HOST_COHERENT);
+
+    #[test]
+    fn test_strategy_follows_memory_type_and_size() {
+        let streaming = if cfg!(target_arch = "x86_64") { CopyStrategy::Streaming } else { CopyStrategy::Memcpy };
+        assert_eq!(CopyStrategy::for_memory(COHERENT, STREAMING_THRESHOLD), streaming);
+        assert_eq!(CopyStrategy::for_memory(COHERENT, STREAMING_THRESHOLD - 1), CopyStrategy::Memcpy);
+        let cached = COHERENT | VkMemoryPropertyFlags::HOST_CACHED;
+        assert_eq!(CopyStrategy::for_memory(cached, 1 << 20), CopyStrategy::Memcpy);
+        assert_eq!(CopyStrategy::for_memory(VkMemoryPropertyFlags::DEVICE_LOCAL, 1 << 20), CopyStrategy::Memcpy);
+    }
+
+    #[test]
+    fn test_streaming_copies_unaligned_ranges_exactly() {
+        let src: Vec<u8> = (0..1024u32).map(|i| (i * 7) as u8).collect();
+        for (offset, len) in [(0, 0), (3, 5), (1, 300), (16, 512), (7, 1000)] {
+            let mut dst = vec![0xaau8; 1040];
+            unsafe { CopyStrategy::Streaming.copy(&src[..len], dst.as_mut_ptr().add(offset)) };
+            assert_eq!(&dst[offset..offset + len], &src[..len], "{} bytes at {}", len, offset);
+            assert!(dst[..offset].iter().chain(&dst[offset + len..]).all(|&b| b == 0xaa));
+        }
+    }
+}
@@ -0,0 +1,427 @@
+//! Storage images and samplers for 2D compute kernels
+//!
+//! Images are always kept in the GENERAL layout once created, so they can be
+//! bound as storage images, sampled through a [`Sampler`], and used as copy
+//! source/destination without further transitions.
+
+use super::*;
+use crate::*; // Need all the type definitions
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+use super::deferred::LastUse;
+
+/// A 2D storage image with its own memory and view
+///
+/// Storage images give kernels such as convolutions and image filters
+/// 2D-local cache behavior that linear buffers lack.
+pub struct Image {
+    pub(super) context: ComputeContext,
+    pub(super) image: Owned<VkImage>,
+    pub(super) view: Owned<VkImageView>,
+    pub(super) memory: VkDeviceMemory,
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) format: VkFormat,
+    /// Last submission that used the image
+    pub(super) last_use: Arc<LastUse>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for Image {}
+unsafe impl Sync for Image {}
+
+/// A texture sampler for filtered lookups from compute kernels
+///
+/// Bind together with an [`Image`] through
+/// [`CommandBuilder::bind_sampled_image`](super::CommandBuilder::bind_sampled_image).
+pub struct Sampler {
+    pub(super) context: ComputeContext,
+    pub(super) sampler: Owned<VkSampler>,
+    /// Last submission that used the sampler
+    pub(super) last_use: Arc<LastUse>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for Sampler {}
+unsafe impl Sync for Sampler {}
+
+impl Sampler {
+    /// Get the raw Vulkan sampler handle (for advanced usage)
+    pub fn raw(&self) -> VkSampler {
+        self.sampler.raw()
+    }
+}
+
+impl Image {
+    /// Width in texels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in texels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Texel format
+    pub fn format(&self) -> VkFormat {
+        self.format
+    }
+
+    /// Size of the image contents in bytes when tightly packed
+    pub fn size_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * self.format.texel_size() as usize
+    }
+
+    /// Get the raw Vulkan image handle (for advanced usage)
+    pub fn raw(&self) -> VkImage {
+        self.image.raw()
+    }
+
+    /// Get the raw Vulkan image view handle (for advanced usage)
+    pub fn view(&self) -> VkImageView {
+        self.view.raw()
+    }
+
+    /// Upload tightly packed texel data into the image
+    pub fn write<T>(&self, data: &[T]) -> Result<()>
+    where
+        T: Copy + 'static,
+    {
+        let size = std::mem::size_of_val(data);
+        if size != self.size_bytes() {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Image data is {} bytes, expected {} for {}x{} {:?}",
+                size, self.size_bytes(), self.width, self.height, self.format
+            )));
+        }
+
+        unsafe {
+            let staging = self.context.create_buffer_raw(size, BufferUsage::TRANSFER_SRC)?;
+            self.context.with_inner(|inner| {
+                let mut mapped_ptr = ptr::null_mut();
+                let result = vkMapMemory(inner.device, staging.memory, 0, size as VkDeviceSize, 0, &mut mapped_ptr);
+                if result != VkResult::Success {
+                    return Err(KronosError::from(result));
+                }
+                ptr::copy_nonoverlapping(data.as_ptr() as *const u8, mapped_ptr as *mut u8, size);
+                vkUnmapMemory(inner.device, staging.memory);
+                Ok(())
+            })?;
+
+            let region = self.full_copy_region();
+            self.context.submit_image_commands(|command_buffer| {
+                vkCmdCopyBufferToImage(command_buffer, staging.buffer.raw(), self.image.raw(), VkImageLayout::General, 1, &region);
+            })
+        }
+    }
+
+    /// Read the image contents back as tightly packed texels
+    pub fn read<T>(&self) -> Result<Vec<T>>
+    where
+        T: Copy + 'static,
+    {
+        let size = self.size_bytes();
+        let element_size = std::mem::size_of::<T>();
+        if element_size == 0 || size % element_size != 0 {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Image size {} is not a multiple of element size {}",
+                size, element_size
+            )));
+        }
+
+        unsafe {
+            let staging = self.context.create_buffer_uninit(size)?;
+            let region = self.full_copy_region();
+            self.context.submit_image_commands(|command_buffer| {
+                vkCmdCopyImageToBuffer(command_buffer, self.image.raw(), VkImageLayout::General, staging.buffer.raw(), 1, &region);
+            })?;
+
+            self.context.with_inner(|inner| {
+                let mut mapped_ptr = ptr::null_mut();
+                let result = vkMapMemory(inner.device, staging.memory, 0, size as VkDeviceSize, 0, &mut mapped_ptr);
+                if result != VkResult::Success {
+                    return Err(KronosError::from(result));
+                }
+                let vec = slice::from_raw_parts(mapped_ptr as *const T, size / element_size).to_vec();
+                vkUnmapMemory(inner.device, staging.memory);
+                Ok(vec)
+            })
+        }
+    }
+
+    fn full_copy_region(&self) -> VkBufferImageCopy {
+        VkBufferImageCopy {
+            bufferOffset: 0,
+            bufferRowLength: 0,
+            bufferImageHeight: 0,
+            imageSubresource: VkImageSubresourceLayers::default(),
+            imageOffset: VkOffset3D::default(),
+            imageExtent: VkExtent3D { width: self.width, height: self.height, depth: 1 },
+        }
+    }
+}
+
+impl ComputeContext {
+    /// Create a device-local 2D storage image in the GENERAL layout
+    pub fn create_storage_image(&self, width: u32, height: u32, format: VkFormat) -> Result<Image> {
+        if width == 0 || height == 0 || format == VkFormat::Undefined {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Invalid storage image {}x{} {:?}",
+                width, height, format
+            )));
+        }
+
+        let image = unsafe {
+            self.with_inner(|inner| {
+                let image_info = VkImageCreateInfo {
+                    format,
+                    extent: VkExtent3D { width, height, depth: 1 },
+                    usage: VkImageUsageFlags::STORAGE
+                        | VkImageUsageFlags::SAMPLED
+                        | VkImageUsageFlags::TRANSFER_SRC
+                        | VkImageUsageFlags::TRANSFER_DST,
+                    ..Default::default()
+                };
+
+                let mut image = VkImage::NULL;
+                let result = vkCreateImage(inner.device, &image_info, ptr::null(), &mut image);
+                if result != VkResult::Success {
+                    return Err(KronosError::BufferCreationFailed(format!("vkCreateImage failed: {:?}", result)));
+                }
+
+                let mut mem_requirements = VkMemoryRequirements::default();
+                vkGetImageMemoryRequirements(inner.device, image, &mut mem_requirements);
+
+                let memory_type_index = match Self::find_memory_type(
+                    &inner.memory_properties,
+                    mem_requirements.memoryTypeBits,
+                    VkMemoryPropertyFlags::DEVICE_LOCAL,
+                ) {
+                    Ok(index) => index,
+                    Err(e) => {
+                        vkDestroyImage(inner.device, image, ptr::null());
+                        return Err(e);
+                    }
+                };
+
+                let alloc_info = VkMemoryAllocateInfo {
+                    sType: VkStructureType::MemoryAllocateInfo,
+                    pNext: ptr::null(),
+                    allocationSize: mem_requirements.size,
+                    memoryTypeIndex: memory_type_index,
+                };
+
+                let mut memory = VkDeviceMemory::NULL;
+                let result = vkAllocateMemory(inner.device, &alloc_info, ptr::null(), &mut memory);
+                if result != VkResult::Success {
+                    vkDestroyImage(inner.device, image, ptr::null());
+                    return Err(KronosError::BufferCreationFailed(format!("vkAllocateMemory failed: {:?}", result)));
+                }
+                inner.counters.allocation(mem_requirements.size);
+
+                let result = vkBindImageMemory(inner.device, image, memory, 0);
+                if result != VkResult::Success {
+                    vkFreeMemory(inner.device, memory, ptr::null());
+                    vkDestroyImage(inner.device, image, ptr::null());
+                    return Err(KronosError::BufferCreationFailed(format!("vkBindImageMemory failed: {:?}", result)));
+                }
+
+                let view_info = VkImageViewCreateInfo {
+                    image,
+                    format,
+                    ..Default::default()
+                };
+
+                let mut view = VkImageView::NULL;
+                let result = vkCreateImageView(inner.device, &view_info, ptr::null(), &mut view);
+                if result != VkResult::Success {
+                    vkFreeMemory(inner.device, memory, ptr::null());
+                    vkDestroyImage(inner.device, image, ptr::null());
+                    return Err(KronosError::BufferCreationFailed(format!("vkCreateImageView failed: {:?}", result)));
+                }
+
+                Ok(Image {
+                    context: self.clone(),
+                    image: Owned::new(image, inner.id),
+                    view: Owned::new(view, inner.id),
+                    memory,
+                    width,
+                    height,
+                    format,
+                    last_use: Arc::default(),
+                })
+            })?
+        };
+
+        // Move the image out of UNDEFINED once; it stays GENERAL afterwards
+        let barrier = VkImageMemoryBarrier {
+            srcAccessMask: VkAccessFlags::empty(),
+            dstAccessMask: VkAccessFlags::SHADER_READ
+                | VkAccessFlags::SHADER_WRITE
+                | VkAccessFlags::TRANSFER_READ
+                | VkAccessFlags::TRANSFER_WRITE,
+            oldLayout: VkImageLayout::Undefined,
+            newLayout: VkImageLayout::General,
+            image: image.image.raw(),
+            ..Default::default()
+        };
+        unsafe {
+            self.submit_image_commands(|command_buffer| {
+                vkCmdPipelineBarrier(
+                    command_buffer,
+                    VkPipelineStageFlags::TOP_OF_PIPE,
+                    VkPipelineStageFlags::COMPUTE_SHADER,
+                    VkDependencyFlags::empty(),
+                    0,
+                    ptr::null(),
+                    0,
+                    ptr::null(),
+                    1,
+                    &barrier,
+                );
+            })?;
+        }
+
+        Ok(image)
+    }
+
+    /// Create a sampler with normalized coordinates and no mipmapping
+    ///
+    /// `VkFilter::Linear` gives hardware bilinear interpolation; the image
+    /// format must support linear filtering for it to take effect.
+    pub fn create_sampler(&self, filter: VkFilter, address_mode: VkSamplerAddressMode) -> Result<Sampler> {
+        unsafe {
+            self.with_inner(|inner| {
+                let sampler_info = VkSamplerCreateInfo {
+                    magFilter: filter,
+                    minFilter: filter,
+                    addressModeU: address_mode,
+                    addressModeV: address_mode,
+                    addressModeW: address_mode,
+                    ..Default::default()
+                };
+
+                let mut sampler = VkSampler::NULL;
+                let result = vkCreateSampler(inner.device, &sampler_info, ptr::null(), &mut sampler);
+                if result != VkResult::Success {
+                    return Err(KronosError::from(result));
+                }
+
+                Ok(Sampler {
+                    context: self.clone(),
+                    sampler: Owned::new(sampler, inner.id),
+                    last_use: Arc::default(),
+                })
+            })
+        }
+    }
+
+    /// Record and synchronously execute a one-off command buffer
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because:
+    /// - The recorded commands must only reference live handles owned by this context
+    /// - The closure must only record commands, not begin/end or submit the buffer
+    /// - The function submits to the compute queue and waits for it to become idle
+    unsafe fn submit_image_commands<F>(&self, record: F) -> Result<()>
+    where
+        F: FnOnce(VkCommandBuffer),
+    {
+        self.with_inner(|inner| {
+            let pools = inner.pools.lock().unwrap();
+            let alloc_info = VkCommandBufferAllocateInfo {
+                sType: VkStructureType::CommandBufferAllocateInfo,
+                pNext: ptr::null(),
+                commandPool: pools.command_pool,
+                level: VkCommandBufferLevel::Primary,
+                commandBufferCount: 1,
+            };
+
+            let mut command_buffer = VkCommandBuffer::NULL;
+            let result = vkAllocateCommandBuffers(inner.device, &alloc_info, &mut command_buffer);
+            if result != VkResult::Success {
+                return Err(KronosError::from(result));
+            }
+
+            let begin_info = VkCommandBufferBeginInfo {
+                sType: VkStructureType::CommandBufferBeginInfo,
+                pNext: ptr::null(),
+                flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                pInheritanceInfo: ptr::null(),
+            };
+
+            let result = vkBeginCommandBuffer(command_buffer, &begin_info);
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            record(command_buffer);
+
+            let result = vkEndCommandBuffer(command_buffer);
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            let submit_info = VkSubmitInfo {
+                sType: VkStructureType::SubmitInfo,
+                pNext: ptr::null(),
+                waitSemaphoreCount: 0,
+                pWaitSemaphores: ptr::null(),
+                pWaitDstStageMask: ptr::null(),
+                commandBufferCount: 1,
+                pCommandBuffers: &command_buffer,
+                signalSemaphoreCount: 0,
+                pSignalSemaphores: ptr::null(),
+            };
+
+            let _queue = inner.queue_lock.lock().unwrap();
+            let result = inner.device_events.check(inner.queue_submit(inner.queue, 1, &submit_info, VkFence::NULL));
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            let result = inner.device_events.check(vkQueueWaitIdle(inner.queue));
+            vkFreeCommandBuffers(inner.device, pools.command_pool, 1, &command_buffer);
+            if result != VkResult::Success {
+                return Err(KronosError::SynchronizationError(format!(
+                    "vkQueueWaitIdle failed: {:?}",
+                    result
+                )));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        let (image, view, memory) = (self.image.raw(), self.view.raw(), self.memory);
+        self.context.with_inner(|inner| {
+            let device = inner.device;
+            inner.deferred.destroy(std::mem::take(&mut self.last_use), Box::new(move || unsafe {
+                vkDestroyImageView(device, view, ptr::null());
+                vkDestroyImage(device, image, ptr::null());
+                vkFreeMemory(device, memory, ptr::null());
+            }));
+        });
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        let sampler = self.sampler.raw();
+        self.context.with_inner(|inner| {
+            let device = inner.device;
+            inner.deferred.destroy(std::mem::take(&mut self.last_use), Box::new(move || unsafe {
+                vkDestroySampler(device, sampler, ptr::null());
+            }));
+        });
+    }
+}
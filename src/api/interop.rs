@@ -0,0 +1,570 @@
+//! Sharing memory and semaphores with HIP and CUDA
+//!
+//! Applications mixing HIP libraries such as rocBLAS, or a CUDA inference
+//! engine, with Kronos kernels can share buffers without a round trip
+//! through the host. The context must be built with
+//! [`ContextBuilder::enable_external_memory`](super::ContextBuilder::enable_external_memory).
+//! Sharing goes through file descriptors, so it is only available on Unix.
+//!
+//! # HIP
+//!
+//! Export the allocation on the HIP side, for example with
+//! `hipMemGetHandleForAddressRange` (a dma-buf) or
+//! `hipMemExportToShareableHandle` (an opaque fd), then import it with
+//! [`ComputeContext::import_hip_buffer`].
+//!
+//! # CUDA
+//!
+//! CUDA imports Vulkan memory rather than exporting its own allocations.
+//! Create the shared buffer with [`ComputeContext::create_exportable_buffer`],
+//! export it with [`Buffer::export_fd`] and pass the fd and size to
+//! `cudaImportExternalMemory` as `cudaExternalMemoryHandleTypeOpaqueFd` with
+//! `cudaExternalMemoryDedicated`. Memory CUDA's virtual memory API exported
+//! with `cuMemExportToShareableHandle` can be imported with
+//! [`ComputeContext::import_cuda_buffer`].
+//!
+//! Work on the GPU is ordered with semaphores from
+//! [`ComputeContext::create_exportable_semaphore`], imported into CUDA with
+//! `cudaImportExternalSemaphore` as `cudaExternalSemaphoreHandleTypeOpaqueFd`.
+//! A dispatch built with [`CommandBuilder::signal_external`] signals one for
+//! `cudaWaitExternalSemaphoresAsync`; one built with
+//! [`CommandBuilder::wait_external`] waits for
+//! `cudaSignalExternalSemaphoresAsync`, which must already be enqueued when
+//! the dispatch is submitted.
+//!
+//! # Ownership
+//!
+//! Neither API synchronizes with the other on its own, and Vulkan requires
+//! shared buffers to move between its queue family and the external one:
+//!
+//! - With semaphores, build the first dispatch after the other API's writes
+//!   with [`CommandBuilder::acquire_external`] and the last one before it
+//!   reads again with [`CommandBuilder::release_external`]. The transfers
+//!   are recorded into the dispatch, ordered by its semaphores.
+//! - Without them, finish the other API's work on the host (for example
+//!   `hipStreamSynchronize`) and call [`Buffer::acquire_from_external`]
+//!   before the buffer's first dispatch, then [`Buffer::release_to_external`]
+//!   before the other API touches the memory again. Releasing waits for the
+//!   context's queue to go idle; work submitted to queues from
+//!   `create_queue` must be waited for first.
+//!
+//! Imported memory belongs to the exporting allocation, which must outlive
+//! the buffer.
+
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use crate::*;
+#[cfg(unix)]
+use crate::implementation::{
+    vkAllocateMemory, vkBindBufferMemory, vkCreateBuffer, vkCreateSemaphore, vkDestroyBuffer, vkFreeMemory,
+    vkGetBufferMemoryRequirements, vkGetMemoryFdKHR, vkGetMemoryFdPropertiesKHR, vkGetSemaphoreFdKHR,
+};
+#[cfg(unix)]
+use crate::implementation::pool_allocator;
+use super::*;
+use super::command::Dependents;
+
+/// Accounting tag of imported and exportable buffers
+#[cfg(unix)]
+const EXTERNAL_TAG: &str = "external";
+
+/// Kind of file descriptor an allocation is exported as
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternalHandleType {
+    /// Driver-specific fd, importable only by the same driver on the same device
+    OpaqueFd,
+    /// Linux dma-buf, needs VK_EXT_external_memory_dma_buf
+    DmaBuf,
+}
+
+#[cfg(unix)]
+impl ExternalHandleType {
+    fn flags(self) -> VkExternalMemoryHandleTypeFlags {
+        match self {
+            ExternalHandleType::OpaqueFd => VkExternalMemoryHandleTypeFlags::OPAQUE_FD,
+            ExternalHandleType::DmaBuf => VkExternalMemoryHandleTypeFlags::DMA_BUF_EXT,
+        }
+    }
+}
+
+/// Memory exported for another API to import
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportedMemory {
+    /// Opaque fd the importer takes ownership of
+    pub fd: RawFd,
+    /// Size of the whole allocation, which the importer must be given
+    pub size: u64,
+}
+
+/// Binary semaphore shared with another API
+#[cfg(unix)]
+pub struct ExternalSemaphore {
+    semaphore: Arc<Semaphore>,
+}
+
+#[cfg(unix)]
+impl ExternalSemaphore {
+    /// Export the semaphore as an opaque fd
+    ///
+    /// The caller owns the fd until it is imported. Every fd refers to the
+    /// same semaphore.
+    pub fn export_fd(&self) -> Result<RawFd> {
+        let info = VkSemaphoreGetFdInfoKHR {
+            sType: VkStructureType::SemaphoreGetFdInfoKHR,
+            pNext: ptr::null(),
+            semaphore: self.semaphore.semaphore,
+            handleType: VkExternalSemaphoreHandleTypeFlags::OPAQUE_FD,
+        };
+        let device = self.semaphore.context.with_inner(|inner| inner.device);
+        let mut fd = -1;
+        match unsafe { vkGetSemaphoreFdKHR(device, &info, &mut fd) } {
+            VkResult::Success => Ok(fd),
+            result => Err(KronosError::SynchronizationError(format!("vkGetSemaphoreFdKHR failed: {:?}", result))),
+        }
+    }
+
+    /// Get the raw Vulkan semaphore handle
+    pub fn raw(&self) -> VkSemaphore {
+        self.semaphore.raw()
+    }
+}
+
+#[cfg(unix)]
+impl ComputeContext {
+    /// Whether memory exported as `handle_type` can be imported
+    pub fn supports_external_memory(&self, handle_type: ExternalHandleType) -> bool {
+        self.with_inner(|inner| inner.external_memory.contains(handle_type.flags()))
+    }
+
+    /// Whether semaphores can be shared through fds
+    pub fn supports_external_semaphores(&self) -> bool {
+        self.with_inner(|inner| inner.external_semaphores)
+    }
+
+    /// Wrap `size` bytes of a HIP allocation, exported as `fd`, in a buffer
+    ///
+    /// `allocation_size` is the size of the whole exported allocation,
+    /// which the import must match; `size` may be smaller. On success the driver owns `fd` and closes it when the buffer is
+    /// dropped; on failure the caller still owns it. The buffer starts out
+    /// owned by HIP: acquire it before using it.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be an fd of `handle_type` exported from an allocation of
+    ///   `allocation_size` bytes on this context's device
+    /// - The exporting allocation must stay alive as long as the buffer
+    pub unsafe fn import_hip_buffer(
+        &self,
+        fd: RawFd,
+        size: usize,
+        allocation_size: usize,
+        handle_type: ExternalHandleType,
+    ) -> Result<Buffer> {
+        if size > allocation_size {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "A {} byte buffer does not fit the {} byte allocation",
+                size, allocation_size
+            )));
+        }
+        self.create_external_buffer(size, handle_type, Some(fd), Some(allocation_size as VkDeviceSize))
+    }
+
+    /// Wrap `size` bytes of memory CUDA exported as the opaque fd `fd` in a
+    /// buffer
+    ///
+    /// Ownership of `fd` and of the buffer is as for
+    /// [`import_hip_buffer`](Self::import_hip_buffer).
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`import_hip_buffer`](Self::import_hip_buffer).
+    pub unsafe fn import_cuda_buffer(&self, fd: RawFd, size: usize) -> Result<Buffer> {
+        self.create_external_buffer(size, ExternalHandleType::OpaqueFd, Some(fd), None)
+    }
+
+    /// Create a device-local buffer another API can import through
+    /// [`Buffer::export_fd`]
+    pub fn create_exportable_buffer(&self, size: usize) -> Result<Buffer> {
+        unsafe { self.create_external_buffer(size, ExternalHandleType::OpaqueFd, None, None) }
+    }
+
+    /// Create a binary semaphore another API can import through
+    /// [`ExternalSemaphore::export_fd`]
+    pub fn create_exportable_semaphore(&self) -> Result<ExternalSemaphore> {
+        if !self.supports_external_semaphores() {
+            return Err(KronosError::UnsupportedHardware(format!(
+                "{} is not enabled; build the context with enable_external_memory",
+                VK_KHR_EXTERNAL_SEMAPHORE_FD_EXTENSION_NAME
+            )));
+        }
+        self.with_inner(|inner| unsafe {
+            let mut export_info = VkExportSemaphoreCreateInfo {
+                sType: VkStructureType::ExportSemaphoreCreateInfo,
+                pNext: ptr::null(),
+                handleTypes: VkExternalSemaphoreHandleTypeFlags::OPAQUE_FD,
+            };
+            let mut create_info = VkSemaphoreCreateInfo {
+                sType: VkStructureType::SemaphoreCreateInfo,
+                pNext: ptr::null(),
+                flags: 0,
+            };
+            let create_info = Chain::new(&mut create_info).push(&mut export_info);
+            let mut semaphore = VkSemaphore::NULL;
+            let result = vkCreateSemaphore(inner.device, create_info.as_ptr(), ptr::null(), &mut semaphore);
+            if result != VkResult::Success {
+                return Err(KronosError::SynchronizationError(format!("vkCreateSemaphore failed: {:?}", result)));
+            }
+            Ok(ExternalSemaphore {
+                semaphore: Arc::new(Semaphore { context: self.clone(), semaphore }),
+            })
+        })
+    }
+
+    /// Internal: Create a buffer with dedicated memory that is imported
+    /// from `import`, or exportable as `handle_type` without it
+    ///
+    /// The memory is `allocation_size` bytes, or as large as the buffer
+    /// requires without it.
+    ///
+    /// # Safety
+    ///
+    /// With `import`, the requirements of
+    /// [`import_hip_buffer`](Self::import_hip_buffer).
+    unsafe fn create_external_buffer(
+        &self,
+        size: usize,
+        handle_type: ExternalHandleType,
+        import: Option<RawFd>,
+        allocation_size: Option<VkDeviceSize>,
+    ) -> Result<Buffer> {
+        if !self.supports_external_memory(handle_type) {
+            return Err(KronosError::UnsupportedHardware(format!(
+                "Sharing {:?} memory is not supported; build the context with enable_external_memory",
+                handle_type
+            )));
+        }
+        let handle_flags = handle_type.flags();
+        self.with_inner(|inner| {
+            let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_SRC | BufferUsage::TRANSFER_DST;
+            let mut external_info = VkExternalMemoryBufferCreateInfo {
+                sType: VkStructureType::ExternalMemoryBufferCreateInfo,
+                pNext: ptr::null(),
+                handleTypes: handle_flags,
+            };
+            let mut buffer_info = VkBufferCreateInfo {
+                sType: VkStructureType::BufferCreateInfo,
+                pNext: ptr::null(),
+                flags: VkBufferCreateFlags::empty(),
+                size: size as VkDeviceSize,
+                usage: usage.flags,
+                sharingMode: VkSharingMode::Exclusive,
+                queueFamilyIndexCount: 0,
+                pQueueFamilyIndices: ptr::null(),
+            };
+            let buffer_info = Chain::new(&mut buffer_info).push(&mut external_info);
+            let mut buffer = VkBuffer::NULL;
+            let result = vkCreateBuffer(inner.device, buffer_info.as_ptr(), ptr::null(), &mut buffer);
+            if result != VkResult::Success {
+                return Err(KronosError::BufferCreationFailed(format!("vkCreateBuffer failed: {:?}", result)));
+            }
+
+            let mut requirements = VkMemoryRequirements::default();
+            vkGetBufferMemoryRequirements(inner.device, buffer, &mut requirements);
+            let allocation_size = allocation_size.unwrap_or(requirements.size);
+            if allocation_size < requirements.size {
+                vkDestroyBuffer(inner.device, buffer, ptr::null());
+                return Err(KronosError::BufferCreationFailed(format!(
+                    "The buffer needs {} bytes of memory, more than the {} byte allocation",
+                    requirements.size, allocation_size
+                )));
+            }
+            let mut type_bits = requirements.memoryTypeBits;
+            // Opaque fds must not be queried; they import into the type they were exported from
+            if let (Some(fd), ExternalHandleType::DmaBuf) = (import, handle_type) {
+                let mut fd_properties = VkMemoryFdPropertiesKHR::default();
+                let result = vkGetMemoryFdPropertiesKHR(inner.device, handle_flags, fd, &mut fd_properties);
+                if result != VkResult::Success {
+                    vkDestroyBuffer(inner.device, buffer, ptr::null());
+                    return Err(KronosError::BufferCreationFailed(format!(
+                        "vkGetMemoryFdPropertiesKHR failed: {:?}",
+                        result
+                    )));
+                }
+                type_bits &= fd_properties.memoryTypeBits;
+            }
+            let memory_type_index = match Self::find_memory_type(&inner.memory_properties, type_bits, VkMemoryPropertyFlags::DEVICE_LOCAL)
+                .or_else(|_| Self::find_memory_type(&inner.memory_properties, type_bits, VkMemoryPropertyFlags::empty()))
+            {
+                Ok(index) => index,
+                Err(e) => {
+                    vkDestroyBuffer(inner.device, buffer, ptr::null());
+                    return Err(e);
+                }
+            };
+
+            // HIP and CUDA share whole allocations, which drivers expect to be dedicated
+            let mut dedicated_info = VkMemoryDedicatedAllocateInfo {
+                sType: VkStructureType::MemoryDedicatedAllocateInfo,
+                pNext: ptr::null(),
+                image: VkImage::NULL,
+                buffer,
+            };
+            let mut import_info = VkImportMemoryFdInfoKHR {
+                sType: VkStructureType::ImportMemoryFdInfoKHR,
+                pNext: ptr::null(),
+                handleType: handle_flags,
+                fd: import.unwrap_or(-1),
+            };
+            let mut export_info = VkExportMemoryAllocateInfo {
+                sType: VkStructureType::ExportMemoryAllocateInfo,
+                pNext: ptr::null(),
+                handleTypes: handle_flags,
+            };
+            let mut alloc_info = VkMemoryAllocateInfo {
+                sType: VkStructureType::MemoryAllocateInfo,
+                pNext: ptr::null(),
+                allocationSize: allocation_size,
+                memoryTypeIndex: memory_type_index,
+            };
+            let alloc_info = Chain::new(&mut alloc_info).push(&mut dedicated_info);
+            let alloc_info = match import {
+                Some(_) => alloc_info.push(&mut import_info),
+                None => alloc_info.push(&mut export_info),
+            };
+            if let Err(e) = pool_allocator::reserve_allocation(inner.device, EXTERNAL_TAG, allocation_size) {
+                vkDestroyBuffer(inner.device, buffer, ptr::null());
+                return Err(e.into());
+            }
+            let mut memory = VkDeviceMemory::NULL;
+            let result = vkAllocateMemory(inner.device, alloc_info.as_ptr(), ptr::null(), &mut memory);
+            if result != VkResult::Success {
+                vkDestroyBuffer(inner.device, buffer, ptr::null());
+                pool_allocator::record_free(inner.device, EXTERNAL_TAG, allocation_size);
+                return Err(KronosError::BufferCreationFailed(match import {
+                    Some(fd) => format!("Importing fd {} failed: {:?}", fd, result),
+                    None => format!("vkAllocateMemory failed: {:?}", result),
+                }));
+            }
+            inner.counters.allocation(allocation_size);
+
+            let result = vkBindBufferMemory(inner.device, buffer, memory, 0);
+            if result != VkResult::Success {
+                vkFreeMemory(inner.device, memory, ptr::null());
+                vkDestroyBuffer(inner.device, buffer, ptr::null());
+                pool_allocator::record_free(inner.device, EXTERNAL_TAG, allocation_size);
+                return Err(KronosError::BufferCreationFailed(format!("vkBindBufferMemory failed: {:?}", result)));
+            }
+
+            Ok(Buffer {
+                context: self.clone(),
+                buffer: Owned::new(buffer, inner.id),
+                memory,
+                size,
+                usage,
+                memory_flags: inner.memory_properties.memoryTypes[memory_type_index as usize].propertyFlags,
+                tag: EXTERNAL_TAG.to_owned(),
+                allocation_size,
+                export_handle_types: if import.is_some() { VkExternalMemoryHandleTypeFlags::empty() } else { handle_flags },
+                allocator: None,
+                last_use: Arc::default(),
+                external: Arc::default(),
+                _marker: std::marker::PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Buffer {
+    /// Export the memory of a buffer from
+    /// [`ComputeContext::create_exportable_buffer`] as an opaque fd
+    ///
+    /// The caller owns the fd until it is imported. Each call returns a new
+    /// fd for the same memory.
+    pub fn export_fd(&self) -> Result<ExportedMemory> {
+        let handle_type = VkExternalMemoryHandleTypeFlags::OPAQUE_FD;
+        if !self.export_handle_types.contains(handle_type) {
+            return Err(KronosError::BufferCreationFailed(
+                "Only buffers from create_exportable_buffer can be exported".into(),
+            ));
+        }
+        let info = VkMemoryGetFdInfoKHR {
+            sType: VkStructureType::MemoryGetFdInfoKHR,
+            pNext: ptr::null(),
+            memory: self.memory,
+            handleType: handle_type,
+        };
+        let device = self.context.with_inner(|inner| inner.device);
+        let mut fd = -1;
+        match unsafe { vkGetMemoryFdKHR(device, &info, &mut fd) } {
+            VkResult::Success => Ok(ExportedMemory { fd, size: self.allocation_size }),
+            result => Err(KronosError::BufferCreationFailed(format!("vkGetMemoryFdKHR failed: {:?}", result))),
+        }
+    }
+}
+
+/// Direction of a queue family ownership transfer with an external API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OwnershipTransfer {
+    /// From the external queue family to the context's
+    Acquire,
+    /// From the context's queue family to the external one
+    Release,
+}
+
+impl OwnershipTransfer {
+    /// Barrier moving `buffer` between `queue_family` and the external family
+    ///
+    /// Access masks are ignored on the external side of the transfer.
+    fn barrier(self, buffer: VkBuffer, queue_family: u32) -> VkBufferMemoryBarrier {
+        let (src_family, dst_family, src_access, dst_access) = match self {
+            OwnershipTransfer::Acquire => (
+                VK_QUEUE_FAMILY_EXTERNAL,
+                queue_family,
+                VkAccessFlags::empty(),
+                VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+            ),
+            OwnershipTransfer::Release => (
+                queue_family,
+                VK_QUEUE_FAMILY_EXTERNAL,
+                VkAccessFlags::SHADER_WRITE | VkAccessFlags::TRANSFER_WRITE,
+                VkAccessFlags::empty(),
+            ),
+        };
+        VkBufferMemoryBarrier {
+            sType: VkStructureType::BufferMemoryBarrier,
+            pNext: ptr::null(),
+            srcAccessMask: src_access,
+            dstAccessMask: dst_access,
+            srcQueueFamilyIndex: src_family,
+            dstQueueFamilyIndex: dst_family,
+            buffer,
+            offset: 0,
+            size: VK_WHOLE_SIZE,
+        }
+    }
+}
+
+/// Record the `direction` transfers of `transfers` into a dispatch,
+/// returning the barrier for dry-run listings
+///
+/// Acquires go before the first dispatch and releases after the last one;
+/// the dispatch's semaphores order them with the external API's work.
+pub(super) unsafe fn record_ownership_transfers(
+    command_buffer: VkCommandBuffer,
+    queue_family: u32,
+    transfers: &[(VkBuffer, OwnershipTransfer)],
+    direction: OwnershipTransfer,
+) -> Option<PlannedCommand> {
+    let barriers: Vec<VkBufferMemoryBarrier> = transfers
+        .iter()
+        .filter(|(_, transfer)| *transfer == direction)
+        .map(|&(buffer, transfer)| transfer.barrier(buffer, queue_family))
+        .collect();
+    if barriers.is_empty() {
+        return None;
+    }
+    let (src_stage, dst_stage) = match direction {
+        OwnershipTransfer::Acquire => (VkPipelineStageFlags::COMPUTE_SHADER, VkPipelineStageFlags::COMPUTE_SHADER),
+        OwnershipTransfer::Release => (
+            VkPipelineStageFlags::COMPUTE_SHADER | VkPipelineStageFlags::TRANSFER,
+            VkPipelineStageFlags::BOTTOM_OF_PIPE,
+        ),
+    };
+    vkCmdPipelineBarrier(
+        command_buffer,
+        src_stage,
+        dst_stage,
+        VkDependencyFlags::empty(),
+        0,
+        ptr::null(),
+        barriers.len() as u32,
+        barriers.as_ptr(),
+        0,
+        ptr::null(),
+    );
+    Some(PlannedCommand::PipelineBarrier {
+        src_stage,
+        dst_stage,
+        buffers: barriers.iter().map(|barrier| (barrier.buffer, barrier.size)).collect(),
+    })
+}
+
+impl Buffer {
+    /// Take ownership of shared memory from the external API
+    ///
+    /// Call once the external API's writes to the memory have completed and
+    /// before the buffer is used in a dispatch.
+    pub fn acquire_from_external(&self) -> Result<()> {
+        self.transfer_ownership(OwnershipTransfer::Acquire)
+    }
+
+    /// Hand shared memory back to the external API
+    ///
+    /// Returns once the context's queue is idle, after which the external
+    /// API may read and write the memory again.
+    pub fn release_to_external(&self) -> Result<()> {
+        self.transfer_ownership(OwnershipTransfer::Release)
+    }
+
+    fn transfer_ownership(&self, transfer: OwnershipTransfer) -> Result<()> {
+        let (queue_family, buffer) = self.context.with_inner(|inner| (inner.queue_family_index, self.buffer.on(inner.id)));
+        let barrier = transfer.barrier(buffer, queue_family);
+        unsafe {
+            self.context.submit_one_shot(|command_buffer| {
+                vkCmdPipelineBarrier(
+                    command_buffer,
+                    VkPipelineStageFlags::ALL_COMMANDS,
+                    VkPipelineStageFlags::ALL_COMMANDS,
+                    VkDependencyFlags::empty(),
+                    0,
+                    ptr::null(),
+                    1,
+                    &barrier,
+                    0,
+                    ptr::null(),
+                );
+            })
+        }
+    }
+}
+
+impl CommandBuilder {
+    /// Take ownership of `buffer` from the external API before the first
+    /// dispatch
+    pub fn acquire_external(mut self, buffer: &Buffer) -> Self {
+        let buffer = buffer.buffer.on(self.context.device_id());
+        self.ownership_transfers.push((buffer, OwnershipTransfer::Acquire));
+        self
+    }
+
+    /// Hand `buffer` to the external API after the last dispatch
+    pub fn release_external(mut self, buffer: &Buffer) -> Self {
+        let buffer = buffer.buffer.on(self.context.device_id());
+        self.ownership_transfers.push((buffer, OwnershipTransfer::Release));
+        self
+    }
+
+    /// Wait for the external API to signal `semaphore` before the dispatch
+    /// runs
+    ///
+    /// The signal must already be enqueued when the dispatch is submitted.
+    #[cfg(unix)]
+    pub fn wait_external(mut self, semaphore: &ExternalSemaphore) -> Self {
+        // The signaling side is outside Kronos, so it counts as submitted
+        let signaler = Arc::new(Mutex::new(Dependents { semaphores: Vec::new(), submitted: true }));
+        self.waits.push((signaler, semaphore.semaphore.clone()));
+        self
+    }
+
+    /// Signal `semaphore` for the external API once the dispatch completes
+    #[cfg(unix)]
+    pub fn signal_external(self, semaphore: &ExternalSemaphore) -> Self {
+        self.dependents.lock().unwrap().semaphores.push(semaphore.semaphore.clone());
+        self
+    }
+}
@@ -0,0 +1,59 @@
+//! Batched small-problem kernels
+//!
+//! Many small vectors or matrices are packed into shared buffers, and an
+//! array of descriptors gives each problem's element offsets and shape.
+//! One workgroup solves one problem, so thousands of problems run in a
+//! single dispatch. Problems past the workgroup count limit continue along
+//! the dispatch's y dimension.
+
+/// SPIR-V for `shaders/axpy_batched.comp`
+pub const AXPY_BATCHED_SPIRV: &[u8] = include_bytes!("../../../shaders/axpy_batched.spv");
+
+/// SPIR-V for `shaders/gemv_batched.comp`
+pub const GEMV_BATCHED_SPIRV: &[u8] = include_bytes!("../../../shaders/gemv_batched.spv");
+
+/// Invocations per workgroup, and so per problem, in the batched kernels
+pub const LOCAL_SIZE: u32 = 64;
+
+/// Push constants of both batched kernels
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchedParams {
+    pub count: u32,
+}
+
+/// One `y = alpha * x + y` problem of [`AXPY_BATCHED_SPIRV`]
+///
+/// Offsets count `f32` elements from the start of the `x` and `y` buffers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxpyBatch {
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub len: u32,
+    pub alpha: f32,
+}
+
+/// One `y = alpha * A * x + beta * y` problem of [`GEMV_BATCHED_SPIRV`]
+///
+/// `A` is `rows` x `cols` and row-major. Offsets count `f32` elements from
+/// the start of the `a`, `x` and `y` buffers. With `beta == 0.0`, `y` is
+/// only written.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GemvBatch {
+    pub a_offset: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub rows: u32,
+    pub cols: u32,
+    pub alpha: f32,
+    pub beta: f32,
+}
+
+/// Workgroups covering `count` problems, given at most `max_x` workgroups
+/// along x
+pub fn workgroups(count: u32, max_x: u32) -> (u32, u32, u32) {
+    let x = count.clamp(1, max_x.max(1));
+    (x, count / x + (count % x != 0) as u32, 1)
+}
@@ -0,0 +1,21 @@
+//! Stream compaction kernels
+//!
+//! Compaction normalizes the predicate mask to 0/1 flags
+//! ([`COMPACT_FLAGS_SPIRV`]), scans them with the [`scan`](super::scan)
+//! kernel, and writes kept elements to their slots ([`COMPACT_SCATTER_SPIRV`]).
+
+/// SPIR-V for `shaders/compact_flags.comp`
+pub const COMPACT_FLAGS_SPIRV: &[u8] = include_bytes!("../../../shaders/compact_flags.spv");
+
+/// SPIR-V for `shaders/compact_scatter.comp`
+pub const COMPACT_SCATTER_SPIRV: &[u8] = include_bytes!("../../../shaders/compact_scatter.spv");
+
+/// Invocations per workgroup in the compaction kernels
+pub const LOCAL_SIZE: u32 = 256;
+
+/// Push constants of both compaction kernels
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactParams {
+    pub count: u32,
+}
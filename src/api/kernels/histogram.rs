@@ -0,0 +1,18 @@
+//! Histogram kernel
+
+/// SPIR-V for `shaders/histogram.comp`
+pub const HISTOGRAM_SPIRV: &[u8] = include_bytes!("../../../shaders/histogram.spv");
+
+/// Invocations per workgroup in [`HISTOGRAM_SPIRV`]
+pub const LOCAL_SIZE: u32 = 256;
+
+/// Elements counted by one workgroup
+pub const TILE: u32 = LOCAL_SIZE * 8;
+
+/// Push constants of [`HISTOGRAM_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistogramParams {
+    pub count: u32,
+    pub bins: u32,
+}
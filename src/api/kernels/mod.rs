@@ -0,0 +1,13 @@
+//! Built-in compute kernels
+//!
+//! Each submodule embeds precompiled SPIR-V kernels (sources live next to
+//! the binaries in `shaders/`) together with the push constant layouts they
+//! expect. The [`ops`](super::ops) module wraps them in ready-to-use calls.
+
+pub mod batched;
+pub mod compact;
+pub mod histogram;
+pub mod predicate;
+pub mod rng;
+pub mod scan;
+pub mod sort;
@@ -0,0 +1,26 @@
+//! Predicated dispatch emulation kernel
+//!
+//! Without VK_EXT_conditional_rendering, predicated dispatches run
+//! indirectly: [`PREDICATE_ARGS_SPIRV`] writes the arguments of each one,
+//! with no workgroups if the predicate is zero. See
+//! [`CommandBuilder::predicated_on`](crate::api::CommandBuilder::predicated_on).
+
+/// SPIR-V for `shaders/predicate_args.comp`
+pub const PREDICATE_ARGS_SPIRV: &[u8] = include_bytes!("../../../shaders/predicate_args.spv");
+
+/// Invocations per workgroup in [`PREDICATE_ARGS_SPIRV`]; each dispatch's
+/// arguments are written by one workgroup
+pub const LOCAL_SIZE: u32 = 1;
+
+/// Push constants of [`PREDICATE_ARGS_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PredicateParams {
+    /// Index of the predicate's 32-bit word in the flag buffer
+    pub predicate: u32,
+    /// Dispatch whose arguments are written, at `3 * slot` words
+    pub slot: u32,
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
@@ -0,0 +1,88 @@
+//! Philox4x32-10 random number generation kernel
+//!
+//! Philox is counter-based: output block `i` of stream `s` is a pure function
+//! of `(i, s, seed)`, so every invocation generates independently and results
+//! do not depend on the dispatch shape.
+
+/// SPIR-V for `shaders/rng_philox.comp`
+pub const PHILOX_SPIRV: &[u8] = include_bytes!("../../../shaders/rng_philox.spv");
+
+/// Invocations per workgroup in [`PHILOX_SPIRV`]
+pub const LOCAL_SIZE: u32 = 256;
+
+/// 32-bit outputs produced by each invocation
+pub const OUTPUTS_PER_INVOCATION: u32 = 4;
+
+/// Distribution of generated values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Uniformly distributed raw `u32` bits
+    Bits,
+    /// `f32` uniformly distributed in `[low, high)`
+    Uniform { low: f32, high: f32 },
+    /// `f32` normally distributed (Box-Muller)
+    Normal { mean: f32, std_dev: f32 },
+}
+
+/// Push constants of [`PHILOX_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RngParams {
+    pub seed_lo: u32,
+    pub seed_hi: u32,
+    pub count: u32,
+    pub distribution: u32,
+    pub param_a: f32,
+    pub param_b: f32,
+    pub stream: u32,
+}
+
+impl RngParams {
+    /// Parameters filling `count` 32-bit elements of `stream`
+    pub fn new(distribution: Distribution, seed: u64, count: u32, stream: u32) -> Self {
+        let (distribution, param_a, param_b) = match distribution {
+            Distribution::Bits => (0, 0.0, 0.0),
+            Distribution::Uniform { low, high } => (1, low, high),
+            Distribution::Normal { mean, std_dev } => (2, mean, std_dev),
+        };
+        Self {
+            seed_lo: seed as u32,
+            seed_hi: (seed >> 32) as u32,
+            count,
+            distribution,
+            param_a,
+            param_b,
+            stream,
+        }
+    }
+
+    /// Workgroups needed to cover `count` elements
+    pub fn workgroups(&self) -> u32 {
+        let per_group = LOCAL_SIZE * OUTPUTS_PER_INVOCATION;
+        self.count / per_group + (self.count % per_group != 0) as u32
+    }
+}
+
+const PHILOX_M0: u32 = 0xD251_1F53;
+const PHILOX_M1: u32 = 0xCD9E_8D57;
+const PHILOX_W0: u32 = 0x9E37_79B9;
+const PHILOX_W1: u32 = 0xBB67_AE85;
+
+/// CPU reference of the kernel's raw output for one counter
+pub fn philox4x32_10(mut ctr: [u32; 4], mut key: [u32; 2]) -> [u32; 4] {
+    for round in 0..10 {
+        if round > 0 {
+            key[0] = key[0].wrapping_add(PHILOX_W0);
+            key[1] = key[1].wrapping_add(PHILOX_W1);
+        }
+        let p0 = PHILOX_M0 as u64 * ctr[0] as u64;
+        let p1 = PHILOX_M1 as u64 * ctr[2] as u64;
+        ctr = [
+            (p1 >> 32) as u32 ^ ctr[1] ^ key[0],
+            p1 as u32,
+            (p0 >> 32) as u32 ^ ctr[3] ^ key[1],
+            p0 as u32,
+        ];
+    }
+    ctr
+}
@@ -0,0 +1,14 @@
+//! Exclusive prefix sum kernel
+
+/// SPIR-V for `shaders/scan.comp`
+pub const SCAN_SPIRV: &[u8] = include_bytes!("../../../shaders/scan.spv");
+
+/// Invocations in the single scanning workgroup
+pub const LOCAL_SIZE: u32 = 256;
+
+/// Push constants of [`SCAN_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanParams {
+    pub count: u32,
+}
@@ -0,0 +1,96 @@
+//! Radix and bitonic sort kernels
+//!
+//! The LSD radix sort runs eight 4-bit passes. Each pass counts digits per
+//! 1024-key tile ([`RADIX_COUNT_SPIRV`]), scans the digit-major histogram
+//! with the [`scan`](super::scan) kernel, and stably scatters keys (and
+//! optional values) to their sorted positions ([`RADIX_SCATTER_SPIRV`]).
+
+/// SPIR-V for `shaders/radix_count.comp`
+pub const RADIX_COUNT_SPIRV: &[u8] = include_bytes!("../../../shaders/radix_count.spv");
+
+/// SPIR-V for `shaders/radix_scatter.comp`
+pub const RADIX_SCATTER_SPIRV: &[u8] = include_bytes!("../../../shaders/radix_scatter.spv");
+
+/// SPIR-V for `shaders/float_keys.comp`
+pub const FLOAT_KEYS_SPIRV: &[u8] = include_bytes!("../../../shaders/float_keys.spv");
+
+/// SPIR-V for `shaders/bitonic_sort.comp`
+pub const BITONIC_SPIRV: &[u8] = include_bytes!("../../../shaders/bitonic_sort.spv");
+
+/// Invocations per workgroup in every sort kernel
+pub const LOCAL_SIZE: u32 = 256;
+
+/// Keys handled by one radix workgroup
+pub const RADIX_TILE: u32 = 1024;
+
+/// Bits sorted per radix pass
+pub const RADIX_BITS: u32 = 4;
+
+/// Distinct digit values per radix pass
+pub const RADIX_DIGITS: u32 = 1 << RADIX_BITS;
+
+/// Radix passes needed for 32-bit keys
+pub const RADIX_PASSES: u32 = 32 / RADIX_BITS;
+
+/// Push constants of [`RADIX_COUNT_SPIRV`] and [`RADIX_SCATTER_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadixParams {
+    pub count: u32,
+    pub shift: u32,
+    pub num_groups: u32,
+    pub has_values: u32,
+}
+
+impl RadixParams {
+    /// Tiles (and workgroups) needed for `count` keys
+    pub fn groups_for(count: u32) -> u32 {
+        count / RADIX_TILE + (count % RADIX_TILE != 0) as u32
+    }
+}
+
+/// Push constants of [`FLOAT_KEYS_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatKeyParams {
+    pub count: u32,
+    pub inverse: u32,
+}
+
+/// Push constants of [`BITONIC_SPIRV`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitonicParams {
+    pub count: u32,
+    pub block: u32,
+    pub step: u32,
+    pub flip: u32,
+}
+
+/// The compare-exchange steps of a bitonic sort of `count` keys, in order
+pub fn bitonic_steps(count: u32) -> Vec<BitonicParams> {
+    let padded = count.max(1).next_power_of_two();
+    let mut steps = Vec::new();
+    let mut block = 2;
+    while block <= padded {
+        let mut step = block / 2;
+        let mut flip = 1;
+        while step >= 1 {
+            steps.push(BitonicParams { count, block, step, flip });
+            step /= 2;
+            flip = 0;
+        }
+        block *= 2;
+    }
+    steps
+}
+
+/// Map an `f32` bit pattern to a key that sorts in float order (CPU reference of [`FLOAT_KEYS_SPIRV`])
+pub fn float_to_key(bits: u32) -> u32 {
+    if bits & 0x8000_0000 != 0 { !bits } else { bits | 0x8000_0000 }
+}
+
+/// Inverse of [`float_to_key`]
+pub fn key_to_float(key: u32) -> u32 {
+    if key & 0x8000_0000 != 0 { key & 0x7FFF_FFFF } else { !key }
+}
@@ -0,0 +1,197 @@
+//! Checking host structs against the std430 layouts kernels declare
+//!
+//! A struct shared with a kernel must place every field where the kernel's
+//! std430 block expects it. Rust's `#[repr(C)]` layout and std430 agree for
+//! scalars, but not for `vec3`, which std430 aligns to 16 bytes: a
+//! `[f32; 3]` followed by another `[f32; 3]` sits at offset 12 in Rust and
+//! 16 on the GPU, and the kernel silently reads garbage. Derive [`Std430`]
+//! for the struct and call [`KernelInterface::check_buffer_layout`] or
+//! [`KernelInterface::check_push_constant_layout`] in a test to catch this
+//! before it reaches the GPU:
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Clone, Copy, Std430)]
+//! struct Particle {
+//!     position: [f32; 3],
+//!     _pad: f32,
+//!     velocity: [f32; 3],
+//!     mass: f32,
+//! }
+//!
+//! pipeline.interface().check_buffer_layout::<Particle>("Particles")?;
+//! ```
+//!
+//! Fields are matched with the kernel's members by name when the module has
+//! debug info, otherwise by position. Padding fields whose names start with
+//! `_` are skipped when matching by name.
+
+use std::fmt;
+use super::*;
+use super::reflect::{BlockLayout, BlockMember, PushConstantBlock};
+
+/// Offset and size of one field of a host struct
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostField {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Host layout of a struct shared with kernels
+///
+/// Derive it with `#[derive(Std430)]` on a `#[repr(C)]` struct.
+pub trait Std430: Copy + Sized {
+    /// Fields in declaration order
+    fn host_fields() -> Vec<HostField>;
+}
+
+/// One way a host struct disagrees with a kernel block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutMismatch {
+    /// The field is at a different offset than the kernel's member
+    Offset { field: &'static str, host: usize, kernel: u32 },
+    /// The field has a different size than the kernel's member
+    Size { field: &'static str, host: usize, kernel: u32 },
+    /// The kernel has no member for the field
+    MissingMember { field: &'static str },
+    /// The host struct has no field for the kernel's member
+    MissingField { member: String },
+    /// Array elements are further apart on the GPU than the struct is large
+    Stride { host: usize, kernel: u32 },
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutMismatch::Offset { field, host, kernel } => {
+                write!(f, "field `{}` is at offset {} on the host but {} in the kernel", field, host, kernel)?;
+                if (*kernel as usize) > *host {
+                    write!(f, " (add {} bytes of padding before it)", *kernel as usize - host)?;
+                }
+                Ok(())
+            }
+            LayoutMismatch::Size { field, host, kernel } => {
+                write!(f, "field `{}` is {} bytes on the host but {} in the kernel", field, host, kernel)
+            }
+            LayoutMismatch::MissingMember { field } => write!(f, "field `{}` has no member in the kernel", field),
+            LayoutMismatch::MissingField { member } => write!(f, "kernel member `{}` has no host field", member),
+            LayoutMismatch::Stride { host, kernel } => write!(
+                f,
+                "the struct is {} bytes but array elements are {} bytes apart in the kernel (pad it to {})",
+                host, kernel, kernel
+            ),
+        }
+    }
+}
+
+/// Compare the fields of `T` with the members of `block`
+pub fn check_std430<T: Std430>(block: &BlockLayout) -> Vec<LayoutMismatch> {
+    let by_name = block.members.iter().any(|member| member.name.is_some());
+    let mut mismatches = Vec::new();
+    let mut matched = vec![false; block.members.len()];
+
+    for (index, field) in T::host_fields().into_iter().enumerate() {
+        let member = if by_name {
+            if field.name.starts_with('_') {
+                continue;
+            }
+            block.members.iter().position(|member| member.name.as_deref() == Some(field.name))
+        } else {
+            (index < block.members.len()).then_some(index)
+        };
+        let Some(member_index) = member else {
+            mismatches.push(LayoutMismatch::MissingMember { field: field.name });
+            continue;
+        };
+        matched[member_index] = true;
+        let member = &block.members[member_index];
+        if member.offset as usize != field.offset {
+            mismatches.push(LayoutMismatch::Offset { field: field.name, host: field.offset, kernel: member.offset });
+        }
+        // A runtime-sized array has no size to compare
+        if member.size != 0 && member.size as usize != field.size {
+            mismatches.push(LayoutMismatch::Size { field: field.name, host: field.size, kernel: member.size });
+        }
+    }
+    for (index, (member, matched)) in block.members.iter().zip(matched).enumerate() {
+        if !matched {
+            let member = member.name.clone().unwrap_or_else(|| format!("#{}", index));
+            mismatches.push(LayoutMismatch::MissingField { member });
+        }
+    }
+    mismatches
+}
+
+fn mismatch_error(what: &str, mismatches: Vec<LayoutMismatch>) -> KronosError {
+    let details: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+    KronosError::CommandExecutionFailed(format!("Layout of {} does not match the kernel: {}", what, details.join("; ")))
+}
+
+impl From<&PushConstantBlock> for BlockLayout {
+    fn from(block: &PushConstantBlock) -> Self {
+        BlockLayout {
+            name: block.name.clone(),
+            size: block.size,
+            members: block
+                .members
+                .iter()
+                .map(|member| BlockMember {
+                    name: member.name.clone(),
+                    offset: member.offset,
+                    size: member.size,
+                    array_stride: None,
+                    element: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KernelInterface {
+    /// Check `T` against the buffer binding named `name`
+    ///
+    /// When the block ends in a runtime-sized array of structs, `T` is
+    /// checked against one element, including the array stride; otherwise
+    /// against the whole block.
+    pub fn check_buffer_layout<T: Std430>(&self, name: &str) -> Result<()> {
+        let block = self
+            .binding(name)
+            .and_then(|binding| binding.layout.as_ref())
+            .ok_or_else(|| KronosError::CommandExecutionFailed(format!(
+                "Kernel '{}' has no buffer binding named '{}'",
+                self.entry_point, name
+            )))?;
+
+        let array = block.members.last().filter(|member| member.size == 0);
+        let mismatches = match array.and_then(|member| Some((member.element.as_deref()?, member.array_stride))) {
+            Some((element, stride)) => {
+                let mut mismatches = check_std430::<T>(element);
+                let size = std::mem::size_of::<T>();
+                if let Some(stride) = stride.filter(|&stride| stride as usize != size) {
+                    mismatches.push(LayoutMismatch::Stride { host: size, kernel: stride });
+                }
+                mismatches
+            }
+            None => check_std430::<T>(block),
+        };
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatch_error(&format!("binding '{}'", name), mismatches))
+        }
+    }
+
+    /// Check `T` against the kernel's push constant block
+    pub fn check_push_constant_layout<T: Std430>(&self) -> Result<()> {
+        let block = self.push_constants.as_ref().ok_or_else(|| {
+            KronosError::CommandExecutionFailed(format!("Kernel '{}' has no push constants", self.entry_point))
+        })?;
+        let mismatches = check_std430::<T>(&BlockLayout::from(block));
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatch_error("the push constants", mismatches))
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Linking kernels with shared SPIR-V utility modules
+//!
+//! Linking is delegated to the `spirv-link` tool from SPIRV-Tools, which
+//! resolves functions a kernel imports (`LinkageAttributes Import`) against
+//! modules that export them. Linked results are cached on disk, keyed by a
+//! SHA-256 hash of the input modules, so the tool only runs once per
+//! combination.
+//!
+//! The cache directory is created private to the user (mode 0700 on Unix)
+//! and not used if other users can write to it, and a
+//! cached module that is not well-formed SPIR-V is ignored and linked again.
+
+use super::*;
+use super::reflect::SPIRV_MAGIC;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable overriding the `spirv-link` executable
+pub const SPIRV_LINK_ENV: &str = "KRONOS_SPIRV_LINK";
+
+/// Environment variable overriding the pipeline cache directory
+pub const PIPELINE_CACHE_DIR_ENV: &str = "KRONOS_PIPELINE_CACHE_DIR";
+
+/// Default pipeline cache directory
+///
+/// `$KRONOS_PIPELINE_CACHE_DIR` if set, otherwise `kronos/pipelines` under
+/// the user's cache directory (`$XDG_CACHE_HOME`, or `~/.cache`). Without
+/// a home directory, a per-user directory under the system temporary
+/// directory is used.
+pub fn pipeline_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(PIPELINE_CACHE_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    let absolute = |var: &str| std::env::var_os(var).map(PathBuf::from).filter(|path| path.is_absolute());
+    absolute("XDG_CACHE_HOME")
+        .or_else(|| absolute("HOME").map(|home| home.join(".cache")))
+        .map(|cache| cache.join("kronos").join("pipelines"))
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("kronos-pipeline-cache-{}", user_id())))
+}
+
+#[cfg(unix)]
+fn user_id() -> u32 {
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn user_id() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
+
+/// Create `dir` accessible only by the current user, and make sure an
+/// existing one cannot be written by anybody else
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        let metadata = fs::metadata(dir)?;
+        if metadata.uid() != user_id() || metadata.mode() & 0o022 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{:?} is writable by other users", dir),
+            ));
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        fs::create_dir_all(dir)
+    }
+}
+
+/// Whether `bytes` can be a SPIR-V module: whole words and the magic number
+fn is_spirv(bytes: &[u8]) -> bool {
+    bytes.len() % 4 == 0
+        && bytes.len() >= 20
+        && u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == SPIRV_MAGIC
+}
+
+/// Links SPIR-V modules with `spirv-link`, caching the results
+#[derive(Debug, Clone)]
+pub struct SpirvLinker {
+    tool: PathBuf,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Default for SpirvLinker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpirvLinker {
+    /// Use `$KRONOS_SPIRV_LINK` (or `spirv-link` from `PATH`) and the default cache directory
+    pub fn new() -> Self {
+        Self {
+            tool: std::env::var_os(SPIRV_LINK_ENV)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("spirv-link")),
+            cache_dir: Some(pipeline_cache_dir()),
+        }
+    }
+
+    /// Use a specific `spirv-link` executable
+    pub fn tool<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.tool = path.into();
+        self
+    }
+
+    /// Cache linked modules in `dir`
+    pub fn cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Always run the linker, never reading or writing the cache
+    pub fn without_cache(mut self) -> Self {
+        self.cache_dir = None;
+        self
+    }
+
+    /// Stable cache key for a list of modules (order matters), the
+    /// hex-encoded SHA-256 of their lengths and contents
+    pub fn cache_key(modules: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for module in modules {
+            hasher.update((module.len() as u64).to_le_bytes());
+            hasher.update(module);
+        }
+        hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Link a kernel module with the utility modules it imports from
+    ///
+    /// `modules` are SPIR-V binaries, typically the kernel first and its
+    /// libraries after it. Returns the linked SPIR-V.
+    pub fn link(&self, modules: &[&[u8]]) -> Result<Vec<u8>> {
+        if modules.is_empty() {
+            return Err(KronosError::ShaderCompilationFailed(
+                "Linking needs at least one SPIR-V module".into(),
+            ));
+        }
+        if let Some(index) = modules.iter().position(|m| m.len() % 4 != 0) {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "SPIR-V module {} is not 4-byte aligned",
+                index
+            )));
+        }
+
+        let key = Self::cache_key(modules);
+        let cached = self.cache_dir.as_ref().map(|dir| dir.join(format!("{}.spv", key)));
+        if let Some(path) = &cached {
+            match Self::load(path) {
+                Ok(Some(linked)) => {
+                    kronos_log!(Debug, "Using cached linked SPIR-V {:?}", path);
+                    return Ok(linked);
+                }
+                Ok(None) => kronos_log!(Warn, "Ignoring cached linked SPIR-V {:?}: not a SPIR-V module", path),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => kronos_log!(Warn, "Ignoring cached linked SPIR-V {:?}: {}", path, e),
+            }
+        }
+
+        let work_dir = std::env::temp_dir().join(format!("kronos-link-{}-{}", &key[..16], std::process::id()));
+        let result = self.run_tool(&work_dir, modules);
+        let _ = fs::remove_dir_all(&work_dir);
+        let linked = result?;
+
+        if let Some(path) = &cached {
+            // A failed cache write only costs a re-link next time
+            if let Err(e) = Self::store(path, &linked) {
+                kronos_log!(Warn, "Failed to cache linked SPIR-V at {:?}: {}", path, e);
+            }
+        }
+        Ok(linked)
+    }
+
+    fn run_tool(&self, work_dir: &Path, modules: &[&[u8]]) -> Result<Vec<u8>> {
+        let io_error = |e: io::Error| {
+            KronosError::ShaderCompilationFailed(format!("SPIR-V link staging failed: {}", e))
+        };
+        create_private_dir(work_dir).map_err(io_error)?;
+
+        let mut inputs = Vec::with_capacity(modules.len());
+        for (index, module) in modules.iter().enumerate() {
+            let path = work_dir.join(format!("module{}.spv", index));
+            fs::write(&path, module).map_err(io_error)?;
+            inputs.push(path);
+        }
+        let output_path = work_dir.join("linked.spv");
+
+        let output = Command::new(&self.tool)
+            .args(&inputs)
+            .arg("-o")
+            .arg(&output_path)
+            .output()
+            .map_err(|e| KronosError::ShaderCompilationFailed(format!(
+                "Failed to run {:?} (set {} to its path): {}",
+                self.tool, SPIRV_LINK_ENV, e
+            )))?;
+        if !output.status.success() {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "spirv-link failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        fs::read(&output_path).map_err(io_error)
+    }
+
+    /// A cached module, `None` if the file is not SPIR-V
+    fn load(path: &Path) -> io::Result<Option<Vec<u8>>> {
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+        let linked = fs::read(path)?;
+        Ok(is_spirv(&linked).then_some(linked))
+    }
+
+    fn store(path: &Path, linked: &[u8]) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+        // Write then rename so concurrent readers never see a partial module
+        let partial = path.with_extension(format!("spv.{}", std::process::id()));
+        fs::write(&partial, linked)?;
+        fs::rename(&partial, path)
+    }
+}
+
+impl ComputeContext {
+    /// Link SPIR-V modules with the default [`SpirvLinker`] and create a shader
+    pub fn create_linked_shader(&self, modules: &[&[u8]]) -> Result<Shader> {
+        let linked = SpirvLinker::new().link(modules)?;
+        self.create_shader_from_spirv(&linked)
+    }
+}
@@ -15,15 +15,103 @@ pub mod buffer;
 pub mod pipeline;
 pub mod command;
 pub mod sync;
+pub mod features;
+pub mod image;
+pub mod queue;
+pub mod reflect;
+pub mod link;
+pub mod kernels;
+pub mod ops;
+pub mod timing;
+pub mod perf;
+pub mod plan;
+pub mod progress;
+pub mod stats;
+pub mod devices;
+pub mod events;
+pub mod worker;
+pub mod owned;
+pub mod version;
+#[cfg(feature = "object-registry")]
+pub mod registry;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+pub mod scheduler;
+pub mod checkpoint;
+pub mod threads;
+pub mod conformance;
+pub mod validation;
+pub mod readback;
+pub mod ping_pong;
+pub mod scatter;
+pub mod host_copy;
+pub mod hashing;
+pub mod residency;
+pub mod descriptor_pools;
+pub mod submit_plan;
+pub mod predication;
+pub mod constants;
+pub mod layout;
+pub mod asserts;
+pub mod interop;
+pub mod plugin;
+#[cfg(feature = "opencl")]
+pub mod opencl;
+mod reaper;
+mod deferred;
+mod upload;
 
 #[cfg(test)]
 mod tests;
 
 pub use context::ComputeContext;
-pub use buffer::{Buffer, BufferUsage};
-pub use pipeline::{Pipeline, Shader, PipelineConfig, BufferBinding};
-pub use command::CommandBuilder;
-pub use sync::{Fence, Semaphore};
+pub use buffer::{Buffer, BufferSlice, BufferUsage, DirectMapping, MemoryHeapInfo, MemoryReport};
+pub use pipeline::{Pipeline, Shader, PipelineConfig, BufferBinding, CreationFeedback, DescriptorSet, SharedMemorySize};
+pub use command::{CommandBuffer, CommandBuilder, SplitDispatch};
+pub use sync::{Fence, FenceStatus, Semaphore, FlightLimiter, FlightPermit};
+pub use features::Features;
+pub use image::{Image, Sampler};
+pub use queue::{Queue, QueueFamilyInfo};
+pub use link::SpirvLinker;
+pub use reflect::{BlockLayout, BlockMember, KernelInterface, InterfaceBinding, PushConstantBlock, PushConstantMember, SharedVariable};
+pub use timing::{DispatchTrace, OptimizationReport, PipelineTiming};
+pub use perf::{PerformanceCounter, CounterValue, CounterResult};
+pub use plan::{CommandListing, PlannedCommand, PlannedDispatch, PlannedResource};
+pub use progress::Progress;
+pub use stats::{CommandStats, CommandStatsReport, StatsSnapshot};
+pub use devices::{refresh_devices, DriverInfo, PciBusAddress};
+pub use events::DeviceEvent;
+pub use worker::Worker;
+pub use scheduler::{Job, JobHandle, JobStatus, Scheduler};
+pub use threads::ThreadConfig;
+pub use conformance::ConformanceIssue;
+pub use readback::ReadbackChannel;
+pub use ping_pong::PingPong;
+pub use residency::{BufferOptions, MemoryPriority};
+pub use descriptor_pools::{DescriptorPoolUsage, DescriptorTypeUsage};
+pub use submit_plan::SubmitPlan;
+pub use constants::{Constants, DEFAULT_CONSTANTS_SLOTS};
+pub use host_copy::CopyStrategy;
+pub use hashing::{first_divergence, hash_bytes, DispatchHashes, HashDivergence};
+pub use layout::{HostField, LayoutMismatch, Std430};
+pub use asserts::{AssertFailure, DeviceAsserts};
+#[cfg(unix)]
+pub use interop::{ExportedMemory, ExternalHandleType, ExternalSemaphore};
+pub use plugin::{
+    KronosAllocatorVtable, KronosPlugin, KronosPluginHost, KronosPluginInit, KronosSchedulerVtable, PluginSource,
+    KRONOS_PLUGIN_ABI_VERSION, KRONOS_PLUGIN_ENTRY_POINT,
+};
+pub use kronos_compute_derive::Std430;
+pub use crate::implementation::pool_allocator::{FitStrategy, MemoryConfig, MemoryLimitExceeded, PoolConfig, SlabGrowth, TagUsage};
+pub use crate::implementation::icd_loader::LibrarySearchDir;
+pub use crate::implementation::logging::LogVerbosity;
+pub use crate::implementation::quirks::Quirks;
+pub use owned::{DeviceId, Owned};
+pub use version::Version;
+#[cfg(feature = "telemetry")]
+pub use telemetry::{TelemetrySample, TelemetrySampler, TelemetrySummary};
+#[cfg(feature = "opencl")]
+pub use opencl::{ClBuffer, OpenClBridge};
 
 /// Result type for the unified API
 pub type Result<T> = std::result::Result<T, KronosError>;
@@ -70,6 +158,13 @@ impl From<VkResult> for KronosError {
 pub struct ContextConfig {
     /// Application name
     pub app_name: String,
+    /// Application version reported to the driver (1.0.0 if unset)
+    pub application_version: Option<Version>,
+    /// Engine name reported to the driver ("Kronos Compute" if unset)
+    pub engine_name: Option<String>,
+    /// Highest Vulkan version the application uses; if unset, the highest
+    /// version both Kronos and the loader support
+    pub api_version: Option<Version>,
     /// Enable validation layers
     pub enable_validation: bool,
     /// Preferred GPU vendor (AMD, NVIDIA, Apple)
@@ -78,6 +173,38 @@ pub struct ContextConfig {
     pub preferred_icd_path: Option<std::path::PathBuf>,
     /// Preferred ICD by index (only works in aggregated mode or before first initialization)
     pub preferred_icd_index: Option<usize>,
+    /// Extra directories searched for ICD libraries and manifests
+    pub library_search_dirs: Vec<LibrarySearchDir>,
+    /// How much Kronos logs, process-wide; `KRONOS_LOG` or normal if unset
+    pub log_verbosity: Option<LogVerbosity>,
+    /// Features the selected device must support; they are enabled on the device
+    pub required_features: Features,
+    /// Queue family of the context's compute queue; devices without a
+    /// compute queue in it are not selected
+    pub queue_family: Option<u32>,
+    /// Without `queue_family`, prefer a compute family without graphics
+    /// support over the first compute family
+    pub prefer_dedicated_compute_family: bool,
+    /// Enable VK_KHR_performance_query when the device exposes it
+    pub performance_counters: bool,
+    /// Enable sharing memory and semaphores through file descriptors when
+    /// the device supports it
+    pub external_memory: bool,
+    /// Slab size, growth and fit strategy of the device's memory pools
+    pub memory: MemoryConfig,
+    /// Soft cap on the device memory the context allocates, across all pools
+    pub memory_limit_bytes: Option<u64>,
+    /// Size of the upload ring in bytes; 4 MiB if unset
+    pub staging_capacity: Option<u64>,
+    /// Sets a new descriptor pool has room for at least; 64 if unset
+    pub descriptor_pool_sets: Option<u32>,
+    /// Plugin replacing buffer allocation or queue submission
+    pub plugin: Option<PluginSource>,
+    /// Cores and priority of the context's background threads
+    pub threads: ThreadConfig,
+    /// Turn off Kronos-specific reorderings and report API usage stock
+    /// Vulkan would reject, see [`conformance`]
+    pub strict_conformance: bool,
 }
 
 /// Builder for ComputeContext
@@ -97,6 +224,27 @@ impl ContextBuilder {
         self
     }
     
+    /// Version of the application, reported to the driver
+    pub fn application_version(mut self, version: Version) -> Self {
+        self.config.application_version = Some(version);
+        self
+    }
+    
+    /// Engine name reported to the driver in place of "Kronos Compute"
+    pub fn engine_name(mut self, name: impl Into<String>) -> Self {
+        self.config.engine_name = Some(name.into());
+        self
+    }
+    
+    /// Create the instance for Vulkan `version` instead of negotiating
+    ///
+    /// Context creation fails if the loader does not support the version.
+    /// The patch level is ignored.
+    pub fn api_version(mut self, version: Version) -> Self {
+        self.config.api_version = Some(version);
+        self
+    }
+    
     pub fn enable_validation(mut self) -> Self {
         self.config.enable_validation = true;
         self
@@ -119,6 +267,148 @@ impl ContextBuilder {
         self
     }
     
+    /// Also search `dir` for ICD libraries and manifests
+    ///
+    /// Use [`LibrarySearchDir::ExecutableRelative`] for drivers shipped next
+    /// to the application, such as a bundled SwiftShader. Only affects ICDs
+    /// loaded by the first context of the process, unless aggregated mode is
+    /// enabled.
+    pub fn library_search_dir(mut self, dir: LibrarySearchDir) -> Self {
+        self.config.library_search_dirs.push(dir);
+        self
+    }
+    
+    /// Set how much Kronos logs for the whole process
+    ///
+    /// `LogVerbosity::Quiet` keeps only warnings and errors and redacts the
+    /// paths in them, for services that must not log their environment.
+    pub fn log_verbosity(mut self, verbosity: LogVerbosity) -> Self {
+        self.config.log_verbosity = Some(verbosity);
+        self
+    }
+    
+    /// Only select devices supporting every requested feature
+    ///
+    /// Context creation fails with a per-device list of missing features
+    /// if no compute device qualifies.
+    pub fn require_features(mut self, features: Features) -> Self {
+        self.config.required_features = features;
+        self
+    }
+    
+    /// Run the context's dispatches on queue family `index`
+    ///
+    /// The family must support compute and have at least one queue; devices
+    /// where it does not are skipped, and context creation fails with the
+    /// reason per device if none qualifies. See
+    /// [`ComputeContext::queue_families`] for what a device offers.
+    pub fn queue_family(mut self, index: u32) -> Self {
+        self.config.queue_family = Some(index);
+        self
+    }
+    
+    /// Prefer a compute family without graphics support
+    ///
+    /// On devices with a dedicated (async) compute family, dispatches then
+    /// do not share a queue with rendering. Devices without one use their
+    /// first compute family as usual. Ignored when
+    /// [`queue_family`](Self::queue_family) is set.
+    pub fn prefer_dedicated_compute_family(mut self, prefer: bool) -> Self {
+        self.config.prefer_dedicated_compute_family = prefer;
+        self
+    }
+    
+    /// Enable hardware performance counters where the ICD exposes them
+    ///
+    /// Devices without VK_KHR_performance_query are still selected;
+    /// [`ComputeContext::profile_dispatch`] then reports them as unsupported.
+    pub fn enable_performance_counters(mut self) -> Self {
+        self.config.performance_counters = true;
+        self
+    }
+    
+    /// Enable sharing memory and semaphores with other APIs, such as HIP and CUDA
+    ///
+    /// Enables VK_KHR_external_memory_fd, plus VK_EXT_external_memory_dma_buf
+    /// and VK_KHR_external_semaphore_fd where available. Devices without them
+    /// are still selected; importing and exporting then fail.
+    pub fn enable_external_memory(mut self) -> Self {
+        self.config.external_memory = true;
+        self
+    }
+    
+    /// Configure suballocation in the device's memory pools
+    ///
+    /// Applies to allocations made through
+    /// [`pool_allocator`](crate::implementation::pool_allocator) on the
+    /// context's device.
+    pub fn memory_config(mut self, config: MemoryConfig) -> Self {
+        self.config.memory = config;
+        self
+    }
+    
+    /// Cap the device memory the context's buffers and pools may hold
+    ///
+    /// An allocation that would take the live total past `bytes` fails
+    /// fast with
+    /// [`IcdError::MemoryLimitExceeded`](implementation::error::IcdError::MemoryLimitExceeded), whose message lists
+    /// the current usage per tag. Keeps one runaway workload from starving
+    /// the others sharing a device; the cap is not a driver budget, so the
+    /// device may still run out of memory below it.
+    pub fn memory_limit_bytes(mut self, bytes: u64) -> Self {
+        self.config.memory_limit_bytes = Some(bytes);
+        self
+    }
+    
+    /// Size the ring small uploads are staged through, 4 MiB by default
+    ///
+    /// The ring is one mapped allocation that never grows. Uploads of up to
+    /// a quarter of `bytes` take a region of it; larger ones, and any that
+    /// find it full, get a staging buffer of their own. Zero turns the ring
+    /// off. See [`ComputeContext::prewarm`] to allocate it up front.
+    pub fn staging_capacity(mut self, bytes: u64) -> Self {
+        self.config.staging_capacity = Some(bytes);
+        self
+    }
+    
+    /// Give each descriptor pool room for at least `sets` sets, 64 by
+    /// default
+    ///
+    /// Pools grow on demand, each new one as large as all before it, so
+    /// this only sets where they start; see [`descriptor_pools`] to size
+    /// one for known pipelines instead.
+    pub fn descriptor_pool_sets(mut self, sets: u32) -> Self {
+        self.config.descriptor_pool_sets = Some(sets);
+        self
+    }
+    
+    /// Load a plugin library that replaces buffer allocation or queue
+    /// submission, see [`plugin`]
+    pub fn plugin(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.plugin = Some(PluginSource::Library(path.into()));
+        self
+    }
+    
+    /// Use a plugin linked into the application, given its init function
+    pub fn plugin_init(mut self, init: KronosPluginInit) -> Self {
+        self.config.plugin = Some(PluginSource::Init(init));
+        self
+    }
+    
+    /// Pin the context's background threads to cores or raise their
+    /// priority, see [`threads`]
+    pub fn thread_config(mut self, config: ThreadConfig) -> Self {
+        self.config.threads = config;
+        self
+    }
+    
+    /// Behave like stock Vulkan and report reliance on Kronos-specific
+    /// leniency, see [`conformance`]
+    pub fn strict_conformance(mut self, strict: bool) -> Self {
+        self.config.strict_conformance = strict;
+        self
+    }
+    
     pub fn build(self) -> Result<ComputeContext> {
         ComputeContext::new_with_config(self.config)
     }
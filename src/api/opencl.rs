@@ -0,0 +1,442 @@
+//! Handing buffers between Kronos and OpenCL queues
+//!
+//! [`OpenClBridge`] connects a context to an application's OpenCL context,
+//! device and queue; [`OpenClBridge::share`] gives a Kronos buffer an OpenCL
+//! counterpart to pass to OpenCL kernels. Work alternates between the two
+//! runtimes with [`ClBuffer::to_opencl`] and [`ClBuffer::to_kronos`].
+//!
+//! The handoff is zero-copy when the OpenCL device supports
+//! `cl_khr_external_memory_opaque_fd` and the buffer came from
+//! [`ComputeContext::create_exportable_buffer`]: the OpenCL buffer then
+//! imports the Kronos memory, and handoffs only transfer ownership of it.
+//! Otherwise the OpenCL buffer is a separate allocation and every handoff
+//! copies the contents through the host.
+//!
+//! Both runtimes must drive the same GPU for the zero-copy path: the OpenCL
+//! device must report the Vulkan `deviceUUID` through `cl_khr_device_uuid`,
+//! and the bridge falls back to copies when it differs or is unknown. OpenCL is loaded at
+//! runtime, so nothing is linked unless a bridge is created.
+
+use super::*;
+use libloading::Library;
+use std::ffi::{c_char, c_void, CString};
+use std::ptr;
+
+// OpenCL entry points (cl.h, cl_ext.h); every call returns cl_int, 0 on success
+type ClContext = *mut c_void;
+type ClDevice = *mut c_void;
+type ClQueue = *mut c_void;
+type ClMem = *mut c_void;
+type ClPlatform = *mut c_void;
+type ClEvent = *mut c_void;
+type ClGetDeviceInfo = unsafe extern "C" fn(ClDevice, u32, usize, *mut c_void, *mut usize) -> i32;
+type ClRetainContext = unsafe extern "C" fn(ClContext) -> i32;
+type ClReleaseContext = unsafe extern "C" fn(ClContext) -> i32;
+type ClRetainCommandQueue = unsafe extern "C" fn(ClQueue) -> i32;
+type ClReleaseCommandQueue = unsafe extern "C" fn(ClQueue) -> i32;
+type ClCreateBuffer = unsafe extern "C" fn(ClContext, u64, usize, *mut c_void, *mut i32) -> ClMem;
+type ClCreateBufferWithProperties = unsafe extern "C" fn(ClContext, *const u64, u64, usize, *mut c_void, *mut i32) -> ClMem;
+type ClReleaseMemObject = unsafe extern "C" fn(ClMem) -> i32;
+type ClEnqueueReadBuffer =
+    unsafe extern "C" fn(ClQueue, ClMem, u32, usize, usize, *mut c_void, u32, *const ClEvent, *mut ClEvent) -> i32;
+type ClEnqueueWriteBuffer =
+    unsafe extern "C" fn(ClQueue, ClMem, u32, usize, usize, *const c_void, u32, *const ClEvent, *mut ClEvent) -> i32;
+type ClFinish = unsafe extern "C" fn(ClQueue) -> i32;
+type ClGetExtensionFunctionAddressForPlatform = unsafe extern "C" fn(ClPlatform, *const c_char) -> *mut c_void;
+/// clEnqueueAcquireExternalMemObjectsKHR and clEnqueueReleaseExternalMemObjectsKHR
+type ClEnqueueExternalMemObjects = unsafe extern "C" fn(ClQueue, u32, *const ClMem, u32, *const ClEvent, *mut ClEvent) -> i32;
+
+const CL_TRUE: u32 = 1;
+const CL_MEM_READ_WRITE: u64 = 1 << 0;
+const CL_DEVICE_EXTENSIONS: u32 = 0x1030;
+const CL_DEVICE_PLATFORM: u32 = 0x1031;
+const CL_DEVICE_UUID_KHR: u32 = 0x106A;
+const CL_UUID_SIZE_KHR: usize = 16;
+#[cfg(unix)]
+const CL_EXTERNAL_MEMORY_HANDLE_OPAQUE_FD_KHR: u64 = 0x2060;
+#[cfg(unix)]
+const CL_DEVICE_HANDLE_LIST_KHR: u64 = 0x2051;
+#[cfg(unix)]
+const CL_DEVICE_HANDLE_LIST_END_KHR: u64 = 0;
+
+/// OpenCL extension the zero-copy path needs
+const OPAQUE_FD_EXTENSION: &str = "cl_khr_external_memory_opaque_fd";
+
+/// OpenCL extension that reports the device UUID
+const DEVICE_UUID_EXTENSION: &str = "cl_khr_device_uuid";
+
+/// Whether the space-separated extension list `extensions` contains `name`
+fn has_extension(extensions: &str, name: &str) -> bool {
+    extensions.split_whitespace().any(|extension| extension == name)
+}
+
+/// Whether an OpenCL device with `extensions` and `cl_uuid` can import the
+/// memory of the Vulkan device with `vk_uuid`
+///
+/// Both UUIDs must be known and equal; anything else means copies.
+fn can_import(extensions: &str, cl_uuid: Option<[u8; CL_UUID_SIZE_KHR]>, vk_uuid: Option<[u8; VK_UUID_SIZE]>) -> bool {
+    has_extension(extensions, OPAQUE_FD_EXTENSION) && cl_uuid.is_some() && cl_uuid == vk_uuid
+}
+
+fn cl_error(call: &str, code: i32) -> KronosError {
+    KronosError::CommandExecutionFailed(format!("{} failed: {}", call, code))
+}
+
+/// OpenCL entry points and the functions for external memory
+struct ClApi {
+    get_device_info: ClGetDeviceInfo,
+    release_context: ClReleaseContext,
+    release_command_queue: ClReleaseCommandQueue,
+    create_buffer: ClCreateBuffer,
+    release_mem_object: ClReleaseMemObject,
+    enqueue_read_buffer: ClEnqueueReadBuffer,
+    enqueue_write_buffer: ClEnqueueWriteBuffer,
+    finish: ClFinish,
+    // Keeps the function pointers above valid
+    _library: Library,
+}
+
+/// Functions of the zero-copy path, present when the device supports it
+#[derive(Clone, Copy)]
+struct ExternalMemoryApi {
+    create_buffer_with_properties: ClCreateBufferWithProperties,
+    acquire: ClEnqueueExternalMemObjects,
+    release: ClEnqueueExternalMemObjects,
+}
+
+/// Connection between a Kronos context and an OpenCL queue
+pub struct OpenClBridge {
+    context: ComputeContext,
+    cl_context: ClContext,
+    cl_device: ClDevice,
+    cl_queue: ClQueue,
+    api: ClApi,
+    external: Option<ExternalMemoryApi>,
+}
+
+impl OpenClBridge {
+    const LIBRARIES: &'static [&'static str] = &[
+        "libOpenCL.so.1",
+        "libOpenCL.so",
+        "OpenCL.dll",
+        "/System/Library/Frameworks/OpenCL.framework/OpenCL",
+    ];
+
+    /// Bridge `context` to an OpenCL queue of the application
+    ///
+    /// The bridge retains the OpenCL context and queue until it is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `cl_context`, `cl_device` and `cl_queue` must be valid OpenCL
+    /// handles, with the queue created on the device in the context.
+    pub unsafe fn new(context: &ComputeContext, cl_context: *mut c_void, cl_device: *mut c_void, cl_queue: *mut c_void) -> Result<Self> {
+        let library = Self::LIBRARIES
+            .iter()
+            .find_map(|name| Library::new(name).ok())
+            .ok_or_else(|| KronosError::InitializationFailed("OpenCL library not found".into()))?;
+        let symbol_error = |e: libloading::Error| KronosError::InitializationFailed(format!("OpenCL library is incomplete: {}", e));
+        let retain_context = *library.get::<ClRetainContext>(b"clRetainContext\0").map_err(symbol_error)?;
+        let retain_command_queue = *library.get::<ClRetainCommandQueue>(b"clRetainCommandQueue\0").map_err(symbol_error)?;
+        let api = ClApi {
+            get_device_info: *library.get::<ClGetDeviceInfo>(b"clGetDeviceInfo\0").map_err(symbol_error)?,
+            release_context: *library.get::<ClReleaseContext>(b"clReleaseContext\0").map_err(symbol_error)?,
+            release_command_queue: *library.get::<ClReleaseCommandQueue>(b"clReleaseCommandQueue\0").map_err(symbol_error)?,
+            create_buffer: *library.get::<ClCreateBuffer>(b"clCreateBuffer\0").map_err(symbol_error)?,
+            release_mem_object: *library.get::<ClReleaseMemObject>(b"clReleaseMemObject\0").map_err(symbol_error)?,
+            enqueue_read_buffer: *library.get::<ClEnqueueReadBuffer>(b"clEnqueueReadBuffer\0").map_err(symbol_error)?,
+            enqueue_write_buffer: *library.get::<ClEnqueueWriteBuffer>(b"clEnqueueWriteBuffer\0").map_err(symbol_error)?,
+            finish: *library.get::<ClFinish>(b"clFinish\0").map_err(symbol_error)?,
+            _library: library,
+        };
+        let external = Self::external_memory_api(&api, context, cl_device);
+        if external.is_none() {
+            kronos_log!(Info, "[OpenCL] Zero-copy handoff unavailable; buffers are copied through the host");
+        }
+
+        let result = retain_context(cl_context);
+        if result != 0 {
+            return Err(cl_error("clRetainContext", result));
+        }
+        let result = retain_command_queue(cl_queue);
+        if result != 0 {
+            (api.release_context)(cl_context);
+            return Err(cl_error("clRetainCommandQueue", result));
+        }
+        Ok(Self {
+            context: context.clone(),
+            cl_context,
+            cl_device,
+            cl_queue,
+            api,
+            external,
+        })
+    }
+
+    /// Look up the zero-copy functions if the device can import Kronos memory
+    unsafe fn external_memory_api(api: &ClApi, context: &ComputeContext, cl_device: ClDevice) -> Option<ExternalMemoryApi> {
+        if !cfg!(unix) {
+            return None;
+        }
+        let mut size = 0usize;
+        if (api.get_device_info)(cl_device, CL_DEVICE_EXTENSIONS, 0, ptr::null_mut(), &mut size) != 0 {
+            return None;
+        }
+        let mut extensions = vec![0u8; size];
+        if (api.get_device_info)(cl_device, CL_DEVICE_EXTENSIONS, size, extensions.as_mut_ptr() as *mut c_void, ptr::null_mut()) != 0 {
+            return None;
+        }
+        let extensions = String::from_utf8_lossy(&extensions);
+        let extensions = extensions.trim_end_matches('\0');
+        let mut uuid = [0u8; CL_UUID_SIZE_KHR];
+        let cl_uuid = (has_extension(extensions, DEVICE_UUID_EXTENSION)
+            && (api.get_device_info)(cl_device, CL_DEVICE_UUID_KHR, uuid.len(), uuid.as_mut_ptr() as *mut c_void, ptr::null_mut()) == 0)
+            .then_some(uuid);
+        if !can_import(extensions, cl_uuid, context.device_uuid()) {
+            return None;
+        }
+
+        // OpenCL 3.0 core, and extension functions resolved per platform
+        let create_buffer_with_properties = *api._library.get::<ClCreateBufferWithProperties>(b"clCreateBufferWithProperties\0").ok()?;
+        let get_extension_function = *api
+            ._library
+            .get::<ClGetExtensionFunctionAddressForPlatform>(b"clGetExtensionFunctionAddressForPlatform\0")
+            .ok()?;
+        let mut platform: ClPlatform = ptr::null_mut();
+        let result = (api.get_device_info)(
+            cl_device,
+            CL_DEVICE_PLATFORM,
+            std::mem::size_of::<ClPlatform>(),
+            &mut platform as *mut ClPlatform as *mut c_void,
+            ptr::null_mut(),
+        );
+        if result != 0 {
+            return None;
+        }
+        let extension_function = |name: &str| {
+            let name = CString::new(name).unwrap();
+            let function = get_extension_function(platform, name.as_ptr());
+            (!function.is_null()).then(|| std::mem::transmute::<*mut c_void, ClEnqueueExternalMemObjects>(function))
+        };
+        Some(ExternalMemoryApi {
+            create_buffer_with_properties,
+            acquire: extension_function("clEnqueueAcquireExternalMemObjectsKHR")?,
+            release: extension_function("clEnqueueReleaseExternalMemObjectsKHR")?,
+        })
+    }
+
+    /// Whether exportable buffers are handed over without copies
+    pub fn is_zero_copy(&self) -> bool {
+        self.external.is_some()
+    }
+
+    /// Create the OpenCL counterpart of `buffer`
+    ///
+    /// The buffer starts out with Kronos; call [`ClBuffer::to_opencl`] before
+    /// OpenCL uses it.
+    pub fn share<'a>(&'a self, buffer: &'a mut Buffer) -> Result<ClBuffer<'a>> {
+        if buffer.context.device_id() != self.context.device_id() {
+            return Err(KronosError::BufferCreationFailed("Buffer belongs to a different context than the bridge".into()));
+        }
+        #[cfg(unix)]
+        if let Some(mem) = self.import(buffer) {
+            return Ok(ClBuffer { bridge: self, buffer, mem, zero_copy: true });
+        }
+
+        let mut result = 0;
+        let mem = unsafe { (self.api.create_buffer)(self.cl_context, CL_MEM_READ_WRITE, buffer.size(), ptr::null_mut(), &mut result) };
+        if mem.is_null() || result != 0 {
+            return Err(cl_error("clCreateBuffer", result));
+        }
+        Ok(ClBuffer { bridge: self, buffer, mem, zero_copy: false })
+    }
+
+    /// Import the memory of an exportable buffer into OpenCL
+    #[cfg(unix)]
+    fn import(&self, buffer: &Buffer) -> Option<ClMem> {
+        let external = self.external?;
+        if !buffer.export_handle_types.contains(VkExternalMemoryHandleTypeFlags::OPAQUE_FD) {
+            return None;
+        }
+        let exported = match buffer.export_fd() {
+            Ok(exported) => exported,
+            Err(e) => {
+                kronos_log!(Warn, "[OpenCL] Exporting buffer failed, falling back to copies: {}", e);
+                return None;
+            }
+        };
+        let properties = [
+            CL_EXTERNAL_MEMORY_HANDLE_OPAQUE_FD_KHR,
+            exported.fd as u64,
+            CL_DEVICE_HANDLE_LIST_KHR,
+            self.cl_device as u64,
+            CL_DEVICE_HANDLE_LIST_END_KHR,
+            0,
+        ];
+        let mut result = 0;
+        let mem = unsafe {
+            (external.create_buffer_with_properties)(
+                self.cl_context,
+                properties.as_ptr(),
+                CL_MEM_READ_WRITE,
+                buffer.size(),
+                ptr::null_mut(),
+                &mut result,
+            )
+        };
+        if mem.is_null() || result != 0 {
+            // A failed import leaves the fd with us
+            unsafe { libc::close(exported.fd) };
+            kronos_log!(Warn, "[OpenCL] Importing buffer failed ({}), falling back to copies", result);
+            return None;
+        }
+        Some(mem)
+    }
+}
+
+impl Drop for OpenClBridge {
+    fn drop(&mut self) {
+        unsafe {
+            (self.api.release_command_queue)(self.cl_queue);
+            (self.api.release_context)(self.cl_context);
+        }
+    }
+}
+
+/// OpenCL counterpart of a Kronos buffer, created by [`OpenClBridge::share`]
+pub struct ClBuffer<'a> {
+    bridge: &'a OpenClBridge,
+    buffer: &'a mut Buffer,
+    mem: ClMem,
+    zero_copy: bool,
+}
+
+impl ClBuffer<'_> {
+    /// The `cl_mem` to pass to OpenCL kernels
+    pub fn cl_mem(&self) -> *mut c_void {
+        self.mem
+    }
+
+    /// Whether the OpenCL buffer aliases the Kronos memory
+    pub fn is_zero_copy(&self) -> bool {
+        self.zero_copy
+    }
+
+    /// Hand the buffer to OpenCL once Kronos is done with it
+    ///
+    /// Zero-copy buffers are released by Kronos and acquired on the OpenCL
+    /// queue, ahead of anything enqueued after this call; otherwise the
+    /// contents are copied into the OpenCL buffer.
+    pub fn to_opencl(&mut self) -> Result<()> {
+        let bridge = self.bridge;
+        match bridge.external.filter(|_| self.zero_copy) {
+            Some(external) => {
+                self.buffer.release_to_external()?;
+                let result = unsafe { (external.acquire)(bridge.cl_queue, 1, &self.mem, 0, ptr::null(), ptr::null_mut()) };
+                if result != 0 {
+                    return Err(cl_error("clEnqueueAcquireExternalMemObjectsKHR", result));
+                }
+            }
+            None => {
+                let bytes = self.buffer.read::<u8>()?;
+                let result = unsafe {
+                    (bridge.api.enqueue_write_buffer)(
+                        bridge.cl_queue,
+                        self.mem,
+                        CL_TRUE,
+                        0,
+                        bytes.len(),
+                        bytes.as_ptr() as *const c_void,
+                        0,
+                        ptr::null(),
+                        ptr::null_mut(),
+                    )
+                };
+                if result != 0 {
+                    return Err(cl_error("clEnqueueWriteBuffer", result));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Hand the buffer back to Kronos, waiting for the OpenCL queue
+    ///
+    /// Zero-copy buffers are released on the OpenCL queue and acquired by
+    /// Kronos; otherwise the contents are copied back from the OpenCL buffer.
+    pub fn to_kronos(&mut self) -> Result<()> {
+        let bridge = self.bridge;
+        match bridge.external.filter(|_| self.zero_copy) {
+            Some(external) => {
+                let result = unsafe { (external.release)(bridge.cl_queue, 1, &self.mem, 0, ptr::null(), ptr::null_mut()) };
+                if result != 0 {
+                    return Err(cl_error("clEnqueueReleaseExternalMemObjectsKHR", result));
+                }
+                let result = unsafe { (bridge.api.finish)(bridge.cl_queue) };
+                if result != 0 {
+                    return Err(cl_error("clFinish", result));
+                }
+                self.buffer.acquire_from_external()
+            }
+            None => {
+                let mut bytes = vec![0u8; self.buffer.size()];
+                let result = unsafe {
+                    (bridge.api.enqueue_read_buffer)(
+                        bridge.cl_queue,
+                        self.mem,
+                        CL_TRUE,
+                        0,
+                        bytes.len(),
+                        bytes.as_mut_ptr() as *mut c_void,
+                        0,
+                        ptr::null(),
+                        ptr::null_mut(),
+                    )
+                };
+                if result != 0 {
+                    return Err(cl_error("clEnqueueReadBuffer", result));
+                }
+                self.buffer.write(&bytes)
+            }
+        }
+    }
+}
+
+impl Drop for ClBuffer<'_> {
+    fn drop(&mut self) {
+        unsafe { (self.bridge.api.release_mem_object)(self.mem) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_list_matches_whole_names() {
+        let extensions = "cl_khr_fp64 cl_khr_external_memory cl_khr_external_memory_opaque_fd ";
+        assert!(has_extension(extensions, OPAQUE_FD_EXTENSION));
+        assert!(has_extension(extensions, "cl_khr_external_memory"));
+        assert!(!has_extension(extensions, "cl_khr_external_memory_dma_buf"));
+        assert!(!has_extension("cl_khr_external_memory_opaque_fd_v2", OPAQUE_FD_EXTENSION));
+        assert!(!has_extension("", OPAQUE_FD_EXTENSION));
+    }
+
+    #[test]
+    fn test_zero_copy_needs_the_same_device_uuid() {
+        let extensions = "cl_khr_device_uuid cl_khr_external_memory_opaque_fd";
+        let uuid = *b"kronos-test-gpu\0";
+        let mut other = uuid;
+        other[0] ^= 1;
+        assert!(can_import(extensions, Some(uuid), Some(uuid)));
+
+        // A different device, even from the same vendor, gets copies
+        assert!(!can_import(extensions, Some(uuid), Some(other)));
+        // So does a device that cannot tell which one it is
+        assert!(!can_import(extensions, None, Some(uuid)));
+        assert!(!can_import(extensions, Some(uuid), None));
+        assert!(!can_import(extensions, None, None));
+        // And one that cannot import at all
+        assert!(!can_import("cl_khr_device_uuid", Some(uuid), Some(uuid)));
+    }
+}
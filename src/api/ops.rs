@@ -0,0 +1,374 @@
+//! Ready-made GPU operations built on the [`kernels`](super::kernels)
+//!
+//! Multi-pass operations run each pass as its own dispatch and wait for it,
+//! so every pass sees the previous one's writes. Temporaries are allocated
+//! per call and freed on return.
+
+use super::*;
+use super::kernels::batched::{self, BatchedParams};
+use super::kernels::compact::{self, CompactParams};
+use super::kernels::histogram::{self, HistogramParams};
+use super::kernels::rng::{self, RngParams};
+use super::kernels::scan::{self, ScanParams};
+use super::kernels::sort::{self, BitonicParams, FloatKeyParams, RadixParams};
+
+pub use super::kernels::batched::{AxpyBatch, GemvBatch};
+pub use super::kernels::rng::Distribution;
+
+/// Create a pipeline for a built-in kernel with `bindings` storage buffers
+///
+/// The pipeline's GPU time is reported as `ops::<label>`.
+fn kernel_pipeline<P>(ctx: &ComputeContext, label: &str, spirv: &[u8], local_size: u32, bindings: u32) -> Result<Pipeline> {
+    let shader = ctx.create_shader_from_spirv(spirv)?;
+    let mut pipeline = ctx.create_pipeline_with_config(&shader, PipelineConfig {
+        local_size: (local_size, 1, 1),
+        bindings: (0..bindings)
+            .map(|binding| BufferBinding { binding, ..Default::default() })
+            .collect(),
+        push_constant_size: std::mem::size_of::<P>() as u32,
+        ..Default::default()
+    })?;
+    pipeline.set_label(format!("ops::{}", label));
+    Ok(pipeline)
+}
+
+/// Number of 32-bit elements in `buffer`
+fn element_count(buffer: &Buffer, op: &str) -> Result<u32> {
+    u32::try_from(buffer.size() / std::mem::size_of::<u32>()).map_err(|_| {
+        KronosError::CommandExecutionFailed(format!(
+            "{} supports at most {} elements",
+            op,
+            u32::MAX
+        ))
+    })
+}
+
+/// Fail unless `len` `f32` elements from `offset` fit in `buffer`
+fn check_range(buffer: &Buffer, offset: u32, len: u64, op: &str, problem: usize, name: &str) -> Result<()> {
+    let end = (offset as u64 + len) * std::mem::size_of::<f32>() as u64;
+    if end > buffer.size() as u64 {
+        return Err(KronosError::CommandExecutionFailed(format!(
+            "{} problem {} needs {} bytes of {}, which has {}",
+            op,
+            problem,
+            end,
+            name,
+            buffer.size()
+        )));
+    }
+    Ok(())
+}
+
+/// Workgroups of `local_size` invocations covering `count` invocations
+fn groups(count: u32, local_size: u32) -> u32 {
+    count / local_size + (count % local_size != 0) as u32
+}
+
+/// Fill `buffer` with random 32-bit values drawn from `distribution`
+///
+/// The buffer is treated as an array of `u32` (for [`Distribution::Bits`])
+/// or `f32`; a trailing partial element is left untouched. The same `seed`
+/// always produces the same contents.
+pub fn fill_random(ctx: &ComputeContext, buffer: &Buffer, distribution: Distribution, seed: u64) -> Result<()> {
+    fill_random_stream(ctx, buffer, distribution, seed, 0)
+}
+
+/// Like [`fill_random`], drawing from independent sequence `stream` of `seed`
+pub fn fill_random_stream(
+    ctx: &ComputeContext,
+    buffer: &Buffer,
+    distribution: Distribution,
+    seed: u64,
+    stream: u32,
+) -> Result<()> {
+    let count = element_count(buffer, "fill_random")?;
+    if count == 0 {
+        return Ok(());
+    }
+
+    let params = RngParams::new(distribution, seed, count, stream);
+    let pipeline = kernel_pipeline::<RngParams>(ctx, "fill_random", rng::PHILOX_SPIRV, rng::LOCAL_SIZE, 1)?;
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, buffer)
+        .push_constants(&params)
+        .workgroups(params.workgroups(), 1, 1)
+        .execute()
+}
+
+/// Sort a buffer of `u32` in ascending order (radix sort)
+pub fn sort_u32(ctx: &ComputeContext, keys: &Buffer) -> Result<()> {
+    radix_sort(ctx, keys, None, false)
+}
+
+/// Sort a buffer of `f32` in ascending order (radix sort)
+///
+/// Orders by IEEE-754 total order: `-0.0` sorts before `0.0` and NaNs with
+/// the sign bit clear sort after `+inf`.
+pub fn sort_f32(ctx: &ComputeContext, keys: &Buffer) -> Result<()> {
+    radix_sort(ctx, keys, None, true)
+}
+
+/// Sort `u32` keys ascending, moving the matching 32-bit `values` with them
+///
+/// The sort is stable: values of equal keys keep their relative order.
+pub fn sort_pairs(ctx: &ComputeContext, keys: &Buffer, values: &Buffer) -> Result<()> {
+    radix_sort(ctx, keys, Some(values), false)
+}
+
+fn radix_sort(ctx: &ComputeContext, keys: &Buffer, values: Option<&Buffer>, float_keys: bool) -> Result<()> {
+    let count = element_count(keys, "sort")?;
+    if let Some(values) = values {
+        if values.size() < keys.size() {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "sort_pairs values buffer is {} bytes, keys need {}",
+                values.size(),
+                keys.size()
+            )));
+        }
+    }
+    if count < 2 {
+        return Ok(());
+    }
+
+    let num_groups = RadixParams::groups_for(count);
+    let element_bytes = count as usize * std::mem::size_of::<u32>();
+    let temp_keys = ctx.create_buffer_uninit(element_bytes)?;
+    let temp_values = values.map(|_| ctx.create_buffer_uninit(element_bytes)).transpose()?;
+    let histogram = ctx.create_buffer_uninit((sort::RADIX_DIGITS * num_groups) as usize * std::mem::size_of::<u32>())?;
+    let total = ctx.create_buffer_uninit(std::mem::size_of::<u32>())?;
+
+    let count_pipeline = kernel_pipeline::<RadixParams>(ctx, "radix_count", sort::RADIX_COUNT_SPIRV, sort::LOCAL_SIZE, 2)?;
+    let scan_pipeline = kernel_pipeline::<ScanParams>(ctx, "scan", scan::SCAN_SPIRV, scan::LOCAL_SIZE, 2)?;
+    let scatter_pipeline = kernel_pipeline::<RadixParams>(ctx, "radix_scatter", sort::RADIX_SCATTER_SPIRV, sort::LOCAL_SIZE, 5)?;
+
+    if float_keys {
+        map_float_keys(ctx, keys, count, false)?;
+    }
+
+    // Passes ping-pong between the caller's buffers and the temporaries; an
+    // even number of passes leaves the result in the caller's buffers
+    let (mut src_keys, mut dst_keys) = (keys, &temp_keys);
+    let (mut src_values, mut dst_values) = match (values, &temp_values) {
+        (Some(values), Some(temp)) => (values, temp),
+        // Unused without values; any valid buffer fills the bindings
+        _ => (keys, &temp_keys),
+    };
+    for pass in 0..sort::RADIX_PASSES {
+        let params = RadixParams {
+            count,
+            shift: pass * sort::RADIX_BITS,
+            num_groups,
+            has_values: values.is_some() as u32,
+        };
+        ctx.dispatch(&count_pipeline)
+            .bind_buffer(0, src_keys)
+            .bind_buffer(1, &histogram)
+            .push_constants(&params)
+            .workgroups(num_groups, 1, 1)
+            .execute()?;
+        ctx.dispatch(&scan_pipeline)
+            .bind_buffer(0, &histogram)
+            .bind_buffer(1, &total)
+            .push_constants(&ScanParams { count: sort::RADIX_DIGITS * num_groups })
+            .workgroups(1, 1, 1)
+            .execute()?;
+        ctx.dispatch(&scatter_pipeline)
+            .bind_buffer(0, src_keys)
+            .bind_buffer(1, dst_keys)
+            .bind_buffer(2, &histogram)
+            .bind_buffer(3, src_values)
+            .bind_buffer(4, dst_values)
+            .push_constants(&params)
+            .workgroups(num_groups, 1, 1)
+            .execute()?;
+        std::mem::swap(&mut src_keys, &mut dst_keys);
+        std::mem::swap(&mut src_values, &mut dst_values);
+    }
+
+    if float_keys {
+        map_float_keys(ctx, keys, count, true)?;
+    }
+    Ok(())
+}
+
+/// Convert `f32` bit patterns to sortable keys, or back with `inverse`
+fn map_float_keys(ctx: &ComputeContext, keys: &Buffer, count: u32, inverse: bool) -> Result<()> {
+    let pipeline = kernel_pipeline::<FloatKeyParams>(ctx, "float_keys", sort::FLOAT_KEYS_SPIRV, sort::LOCAL_SIZE, 1)?;
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, keys)
+        .push_constants(&FloatKeyParams { count, inverse: inverse as u32 })
+        .workgroups(groups(count, sort::LOCAL_SIZE), 1, 1)
+        .execute()
+}
+
+/// Sort a buffer of `u32` in ascending order in place (bitonic sort)
+///
+/// Needs no temporary memory, but runs O(log² n) dispatches; prefer
+/// [`sort_u32`] for large inputs.
+pub fn bitonic_sort_u32(ctx: &ComputeContext, keys: &Buffer) -> Result<()> {
+    let count = element_count(keys, "bitonic_sort")?;
+    if count < 2 {
+        return Ok(());
+    }
+    if count > 1 << 31 {
+        return Err(KronosError::CommandExecutionFailed(
+            "bitonic_sort supports at most 2^31 elements".into(),
+        ));
+    }
+
+    let pipeline = kernel_pipeline::<BitonicParams>(ctx, "bitonic_sort", sort::BITONIC_SPIRV, sort::LOCAL_SIZE, 1)?;
+    let pairs = count.next_power_of_two() / 2;
+    for params in sort::bitonic_steps(count) {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(0, keys)
+            .push_constants(&params)
+            .workgroups(groups(pairs, sort::LOCAL_SIZE), 1, 1)
+            .execute()?;
+    }
+    Ok(())
+}
+
+/// Exclusive prefix sum of a `u32` buffer, in place; returns the total
+///
+/// Runs in a single workgroup, so it suits moderate sizes and auxiliary
+/// arrays better than very large inputs.
+pub fn exclusive_scan(ctx: &ComputeContext, data: &Buffer) -> Result<u32> {
+    let count = element_count(data, "exclusive_scan")?;
+    let mut total = ctx.create_buffer_uninit(std::mem::size_of::<u32>())?;
+    total.write(&[0u32])?;
+    if count > 0 {
+        let pipeline = kernel_pipeline::<ScanParams>(ctx, "scan", scan::SCAN_SPIRV, scan::LOCAL_SIZE, 2)?;
+        ctx.dispatch(&pipeline)
+            .bind_buffer(0, data)
+            .bind_buffer(1, &total)
+            .push_constants(&ScanParams { count })
+            .workgroups(1, 1, 1)
+            .execute()?;
+    }
+    Ok(total.read::<u32>()?[0])
+}
+
+/// Count occurrences of each bin index in a `u32` buffer
+///
+/// Returns a buffer of `bins` `u32` counts; values `>= bins` are ignored.
+pub fn histogram(ctx: &ComputeContext, data: &Buffer, bins: u32) -> Result<Buffer> {
+    if bins == 0 {
+        return Err(KronosError::CommandExecutionFailed(
+            "histogram needs at least one bin".into(),
+        ));
+    }
+    let count = element_count(data, "histogram")?;
+    // Readable by the caller, unlike create_buffer's transfer-destination buffers
+    let mut counts = ctx.create_buffer_uninit(bins as usize * std::mem::size_of::<u32>())?;
+    counts.write(&vec![0u32; bins as usize])?;
+    if count == 0 {
+        return Ok(counts);
+    }
+
+    let pipeline = kernel_pipeline::<HistogramParams>(ctx, "histogram", histogram::HISTOGRAM_SPIRV, histogram::LOCAL_SIZE, 2)?;
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, data)
+        .bind_buffer(1, &counts)
+        .push_constants(&HistogramParams { count, bins })
+        .workgroups(groups(count, histogram::TILE), 1, 1)
+        .execute()?;
+    Ok(counts)
+}
+
+/// Keep the 32-bit elements of `data` whose `predicate_mask` entry is non-zero
+///
+/// Returns a buffer the size of `data` whose first `count` elements are the
+/// kept ones, in their original order, together with `count`.
+pub fn compact(ctx: &ComputeContext, data: &Buffer, predicate_mask: &Buffer) -> Result<(Buffer, u32)> {
+    let count = element_count(data, "compact")?;
+    if predicate_mask.size() < data.size() {
+        return Err(KronosError::CommandExecutionFailed(format!(
+            "compact mask is {} bytes, data needs {}",
+            predicate_mask.size(),
+            data.size()
+        )));
+    }
+    if count == 0 {
+        return Err(KronosError::CommandExecutionFailed(
+            "compact needs at least one element".into(),
+        ));
+    }
+
+    let element_bytes = count as usize * std::mem::size_of::<u32>();
+    let offsets = ctx.create_buffer_uninit(element_bytes)?;
+    let output = ctx.create_buffer_uninit(element_bytes)?;
+    let params = CompactParams { count };
+    let workgroups = groups(count, compact::LOCAL_SIZE);
+
+    let flags_pipeline = kernel_pipeline::<CompactParams>(ctx, "compact_flags", compact::COMPACT_FLAGS_SPIRV, compact::LOCAL_SIZE, 2)?;
+    ctx.dispatch(&flags_pipeline)
+        .bind_buffer(0, predicate_mask)
+        .bind_buffer(1, &offsets)
+        .push_constants(&params)
+        .workgroups(workgroups, 1, 1)
+        .execute()?;
+    let kept = exclusive_scan(ctx, &offsets)?;
+
+    let scatter_pipeline = kernel_pipeline::<CompactParams>(ctx, "compact_scatter", compact::COMPACT_SCATTER_SPIRV, compact::LOCAL_SIZE, 4)?;
+    ctx.dispatch(&scatter_pipeline)
+        .bind_buffer(0, data)
+        .bind_buffer(1, predicate_mask)
+        .bind_buffer(2, &offsets)
+        .bind_buffer(3, &output)
+        .push_constants(&params)
+        .workgroups(workgroups, 1, 1)
+        .execute()?;
+    Ok((output, kept))
+}
+
+/// Run every `y = alpha * x + y` problem in `batches` in a single dispatch
+///
+/// The vectors of all problems are packed into the `f32` buffers `x` and
+/// `y` at the offsets their [`AxpyBatch`] gives. Problems must not write
+/// overlapping ranges of `y`.
+pub fn axpy_batched(ctx: &ComputeContext, batches: &[AxpyBatch], x: &Buffer, y: &Buffer) -> Result<()> {
+    for (problem, batch) in batches.iter().enumerate() {
+        check_range(x, batch.x_offset, batch.len as u64, "axpy_batched", problem, "x")?;
+        check_range(y, batch.y_offset, batch.len as u64, "axpy_batched", problem, "y")?;
+    }
+    let pipeline = kernel_pipeline::<BatchedParams>(ctx, "axpy_batched", batched::AXPY_BATCHED_SPIRV, batched::LOCAL_SIZE, 3)?;
+    dispatch_batched(ctx, &pipeline, batches, &[x, y])
+}
+
+/// Run every `y = alpha * A * x + beta * y` problem in `batches` in a
+/// single dispatch
+///
+/// The row-major matrices and vectors of all problems are packed into the
+/// `f32` buffers `a`, `x` and `y` at the offsets their [`GemvBatch`] gives.
+/// Problems must not write overlapping ranges of `y`.
+pub fn gemv_batched(ctx: &ComputeContext, batches: &[GemvBatch], a: &Buffer, x: &Buffer, y: &Buffer) -> Result<()> {
+    for (problem, batch) in batches.iter().enumerate() {
+        check_range(a, batch.a_offset, batch.rows as u64 * batch.cols as u64, "gemv_batched", problem, "a")?;
+        check_range(x, batch.x_offset, batch.cols as u64, "gemv_batched", problem, "x")?;
+        check_range(y, batch.y_offset, batch.rows as u64, "gemv_batched", problem, "y")?;
+    }
+    let pipeline = kernel_pipeline::<BatchedParams>(ctx, "gemv_batched", batched::GEMV_BATCHED_SPIRV, batched::LOCAL_SIZE, 4)?;
+    dispatch_batched(ctx, &pipeline, batches, &[a, x, y])
+}
+
+/// Upload `batches` to binding 0 and solve them with `buffers` bound after it
+fn dispatch_batched<T: Copy + 'static>(ctx: &ComputeContext, pipeline: &Pipeline, batches: &[T], buffers: &[&Buffer]) -> Result<()> {
+    let count = u32::try_from(batches.len()).map_err(|_| {
+        KronosError::CommandExecutionFailed(format!("batched ops support at most {} problems", u32::MAX))
+    })?;
+    if count == 0 {
+        return Ok(());
+    }
+
+    let descriptors = ctx.create_buffer(batches)?;
+    let max_x = ctx.device_properties().limits.maxComputeWorkGroupCount[0];
+    let (groups_x, groups_y, _) = batched::workgroups(count, max_x);
+    let mut builder = ctx.dispatch(pipeline).bind_buffer(0, &descriptors);
+    for (binding, buffer) in (1..).zip(buffers) {
+        builder = builder.bind_buffer(binding, buffer);
+    }
+    builder
+        .push_constants(&BatchedParams { count })
+        .workgroups(groups_x, groups_y, 1)
+        .execute()
+}
@@ -0,0 +1,102 @@
+//! Device-tagged ownership of raw handles
+//!
+//! The sys handles are `Copy` and carry no record of the device that created
+//! them, so a buffer from one context bound to a dispatch on another reaches
+//! the driver unchecked and usually crashes it. Safe-API objects keep their
+//! handles in an [`Owned`], which cannot be copied and remembers the
+//! [`DeviceId`] of its context. Handles are taken out for use on a device
+//! through [`Owned::on`], which asserts in debug builds that the devices
+//! match.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::sys::{Handle, HandleType};
+
+/// Identity of a context's logical device
+///
+/// Every context gets a new identity, so two contexts on the same physical
+/// device do not compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceId(u64);
+
+impl DeviceId {
+    pub(super) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "device #{}", self.0)
+    }
+}
+
+/// A raw handle owned by an object of one context
+///
+/// Not `Copy` or `Clone`: the handle is destroyed by the object that holds
+/// it.
+pub struct Owned<H> {
+    raw: H,
+    device: DeviceId,
+    /// Type name, raw value and number of the handle in the registry
+    #[cfg(feature = "object-registry")]
+    registered: (&'static str, u64, u64),
+}
+
+impl<T: HandleType> Owned<Handle<T>> {
+    pub(super) fn new(raw: Handle<T>, device: DeviceId) -> Self {
+        #[cfg(feature = "object-registry")]
+        let registered = (T::NAME, raw.as_raw(), super::registry::register(T::NAME, raw.as_raw(), device));
+        Self {
+            raw,
+            device,
+            #[cfg(feature = "object-registry")]
+            registered,
+        }
+    }
+}
+
+impl<H: Copy> Owned<H> {
+    /// Device the handle was created on
+    pub fn device(&self) -> DeviceId {
+        self.device
+    }
+
+    /// The raw handle, without a device check
+    pub fn raw(&self) -> H {
+        self.raw
+    }
+
+    /// The raw handle for use on `device`
+    ///
+    /// Panics in debug builds if the handle belongs to another device.
+    #[track_caller]
+    pub(super) fn on(&self, device: DeviceId) -> H {
+        debug_assert_eq!(
+            self.device, device,
+            "handle created on {} used with {}",
+            self.device, device
+        );
+        self.raw
+    }
+}
+
+impl<H: fmt::Debug> fmt::Debug for Owned<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Registered handles already name their device
+        if cfg!(feature = "object-registry") {
+            write!(f, "{:?}", self.raw)
+        } else {
+            write!(f, "{:?} ({})", self.raw, self.device)
+        }
+    }
+}
+
+#[cfg(feature = "object-registry")]
+impl<H> Drop for Owned<H> {
+    fn drop(&mut self) {
+        let (name, raw, index) = self.registered;
+        super::registry::unregister(name, raw, index);
+    }
+}
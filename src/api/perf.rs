@@ -0,0 +1,444 @@
+//! Hardware performance counters (VK_KHR_performance_query)
+//!
+//! Counters are exposed per queue family by the ICD. A set of counters may
+//! need several passes to collect, so [`ComputeContext::profile_dispatch`]
+//! runs the dispatch once per pass while holding the device profiling lock
+//! and returns one value per requested counter.
+//!
+//! The extension is only enabled when the context was built with
+//! [`ContextBuilder::enable_performance_counters`](super::ContextBuilder::enable_performance_counters).
+
+use super::*;
+use crate::*; // Need all the type definitions
+use std::ffi::CStr;
+use std::fmt;
+use std::ptr;
+
+/// A performance counter exposed by the device
+#[derive(Debug, Clone)]
+pub struct PerformanceCounter {
+    /// Index passed to the query pool
+    pub index: u32,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub unit: VkPerformanceCounterUnitKHR,
+    pub storage: VkPerformanceCounterStorageKHR,
+    pub scope: VkPerformanceCounterScopeKHR,
+    pub uuid: [u8; VK_UUID_SIZE],
+    pub flags: VkPerformanceCounterDescriptionFlagsKHR,
+}
+
+impl PerformanceCounter {
+    /// Whether `name` refers to this counter
+    ///
+    /// Vendors spell counters differently ("L2 Cache Hit", "l2_cache_hit"),
+    /// so case and non-alphanumeric characters are ignored.
+    pub fn matches(&self, name: &str) -> bool {
+        normalize_counter_name(&self.name) == normalize_counter_name(name)
+    }
+}
+
+fn normalize_counter_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// A counter result, in the storage type the driver reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CounterResult {
+    Int32(i32),
+    Int64(i64),
+    Uint32(u32),
+    Uint64(u64),
+    Float32(f32),
+    Float64(f64),
+}
+
+impl CounterResult {
+    /// Decode the active member of a raw result
+    pub fn from_raw(storage: VkPerformanceCounterStorageKHR, raw: VkPerformanceCounterResultKHR) -> Self {
+        // SAFETY: every member is plain data and the storage names the one the driver wrote
+        unsafe {
+            match storage {
+                VkPerformanceCounterStorageKHR::Int32 => Self::Int32(raw.int32),
+                VkPerformanceCounterStorageKHR::Int64 => Self::Int64(raw.int64),
+                VkPerformanceCounterStorageKHR::Uint32 => Self::Uint32(raw.uint32),
+                VkPerformanceCounterStorageKHR::Uint64 => Self::Uint64(raw.uint64),
+                VkPerformanceCounterStorageKHR::Float32 => Self::Float32(raw.float32),
+                VkPerformanceCounterStorageKHR::Float64 => Self::Float64(raw.float64),
+            }
+        }
+    }
+
+    /// The value as a float, for display and comparisons across storage types
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Int32(value) => value as f64,
+            Self::Int64(value) => value as f64,
+            Self::Uint32(value) => value as f64,
+            Self::Uint64(value) => value as f64,
+            Self::Float32(value) => value as f64,
+            Self::Float64(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for CounterResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int32(value) => write!(f, "{}", value),
+            Self::Int64(value) => write!(f, "{}", value),
+            Self::Uint32(value) => write!(f, "{}", value),
+            Self::Uint64(value) => write!(f, "{}", value),
+            Self::Float32(value) => write!(f, "{}", value),
+            Self::Float64(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Value of one counter collected by [`ComputeContext::profile_dispatch`]
+#[derive(Debug, Clone)]
+pub struct CounterValue {
+    /// The counter name as reported by the driver
+    pub name: String,
+    pub unit: VkPerformanceCounterUnitKHR,
+    pub value: CounterResult,
+}
+
+/// Device profiling lock, released on drop
+struct ProfilingLock {
+    device: VkDevice,
+}
+
+impl ProfilingLock {
+    unsafe fn acquire(device: VkDevice) -> Result<Self> {
+        let info = VkAcquireProfilingLockInfoKHR {
+            sType: VkStructureType::AcquireProfilingLockInfoKHR,
+            pNext: ptr::null(),
+            flags: 0,
+            timeout: u64::MAX,
+        };
+        let result = vkAcquireProfilingLockKHR(device, &info);
+        if result != VkResult::Success {
+            return Err(KronosError::SynchronizationError(format!(
+                "vkAcquireProfilingLockKHR failed: {:?}",
+                result
+            )));
+        }
+        Ok(Self { device })
+    }
+}
+
+impl Drop for ProfilingLock {
+    fn drop(&mut self) {
+        unsafe { vkReleaseProfilingLockKHR(self.device) };
+    }
+}
+
+/// Performance query pool with a single query, destroyed on drop
+struct CounterPool {
+    device: VkDevice,
+    pool: VkQueryPool,
+}
+
+impl Drop for CounterPool {
+    fn drop(&mut self) {
+        unsafe { vkDestroyQueryPool(self.device, self.pool, ptr::null()) };
+    }
+}
+
+/// Enumerate the counters a queue family exposes
+///
+/// # Safety
+///
+/// The physical_device must be a valid VkPhysicalDevice handle
+unsafe fn enumerate_counters(physical_device: VkPhysicalDevice, family: u32) -> Result<Vec<PerformanceCounter>> {
+    let mut count = 0u32;
+    let result = vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR(
+        physical_device,
+        family,
+        &mut count,
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    if result != VkResult::Success {
+        return Err(KronosError::from(result));
+    }
+
+    let mut counters = vec![VkPerformanceCounterKHR::default(); count as usize];
+    let mut descriptions = vec![VkPerformanceCounterDescriptionKHR::default(); count as usize];
+    let result = vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR(
+        physical_device,
+        family,
+        &mut count,
+        counters.as_mut_ptr(),
+        descriptions.as_mut_ptr(),
+    );
+    if !matches!(result, VkResult::Success | VkResult::Incomplete) {
+        return Err(KronosError::from(result));
+    }
+
+    let text = |chars: &[std::os::raw::c_char]| CStr::from_ptr(chars.as_ptr()).to_string_lossy().into_owned();
+    Ok(descriptions
+        .iter()
+        .take(count as usize)
+        .enumerate()
+        .filter_map(|(index, description)| {
+            let counter = counters.as_ptr().add(index);
+            let name = text(&description.name);
+            // Counters of a storage or scope newer than Kronos cannot be
+            // collected; an unknown unit is only reported as generic
+            let (storage, scope) = match (
+                VkPerformanceCounterStorageKHR::try_from(raw_field(ptr::addr_of!((*counter).storage))),
+                VkPerformanceCounterScopeKHR::try_from(raw_field(ptr::addr_of!((*counter).scope))),
+            ) {
+                (Ok(storage), Ok(scope)) => (storage, scope),
+                (Err(unknown), _) | (_, Err(unknown)) => {
+                    kronos_log!(Warn, "Skipping performance counter '{}': {}", name, unknown);
+                    return None;
+                }
+            };
+            let unit = VkPerformanceCounterUnitKHR::try_from(raw_field(ptr::addr_of!((*counter).unit)))
+                .unwrap_or(VkPerformanceCounterUnitKHR::Generic);
+            Some(PerformanceCounter {
+                index: index as u32,
+                name,
+                category: text(&description.category),
+                description: text(&description.description),
+                unit,
+                storage,
+                scope,
+                uuid: ptr::addr_of!((*counter).uuid).read(),
+                flags: description.flags,
+            })
+        })
+        .collect())
+}
+
+/// Raw value of a 32-bit enum field the driver wrote, which may name no
+/// variant of the enum
+///
+/// # Safety
+///
+/// `field` must point to an initialized field of a `#[repr(i32)]` enum.
+unsafe fn raw_field<T>(field: *const T) -> i32 {
+    field.cast::<i32>().read()
+}
+
+impl ComputeContext {
+    /// Performance counters available on the compute queue family
+    ///
+    /// Empty unless the context was built with
+    /// [`enable_performance_counters`](super::ContextBuilder::enable_performance_counters)
+    /// and the device supports VK_KHR_performance_query.
+    pub fn performance_counters(&self) -> Result<Vec<PerformanceCounter>> {
+        self.with_inner(|inner| {
+            if !inner.performance_query {
+                return Ok(Vec::new());
+            }
+            unsafe { enumerate_counters(inner.physical_device, inner.queue_family_index) }
+        })
+    }
+
+    /// Execute a dispatch and collect hardware performance counters for it
+    ///
+    /// Counters are looked up by name as described in
+    /// [`PerformanceCounter::matches`], and values are returned in the order
+    /// requested. When the driver needs several passes to collect the set,
+    /// the dispatch runs once per pass, so kernels that accumulate into
+    /// their outputs see repeated effects. Completion callbacks run once.
+    ///
+    /// ```no_run
+    /// # use kronos_compute::api::*;
+    /// # fn run(ctx: &ComputeContext, pipeline: &Pipeline) -> Result<()> {
+    /// let values = ctx.profile_dispatch(
+    ///     ctx.dispatch(pipeline).workgroups(64, 1, 1),
+    ///     &["L2CacheHit", "VALUBusy"],
+    /// )?;
+    /// for counter in &values {
+    ///     println!("{}: {}", counter.name, counter.value);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn profile_dispatch(&self, dispatch: CommandBuilder, counters: &[&str]) -> Result<Vec<CounterValue>> {
+        let (device, physical_device, enabled, dry_run, (queue, command_pool, family)) = self.with_inner(|inner| {
+            (
+                inner.device,
+                inner.physical_device,
+                inner.performance_query,
+                inner.dry_run.load(std::sync::atomic::Ordering::Acquire),
+                dispatch.queue_target(inner, &inner.pools.lock().unwrap()),
+            )
+        });
+        if dry_run {
+            return Err(KronosError::CommandExecutionFailed(
+                "Performance counters cannot be collected in dry-run mode".into(),
+            ));
+        }
+        if !enabled {
+            return Err(KronosError::UnsupportedHardware(
+                "Performance counters are not enabled on this context (requires VK_KHR_performance_query)".into(),
+            ));
+        }
+        if counters.is_empty() {
+            dispatch.execute()?;
+            return Ok(Vec::new());
+        }
+
+        unsafe {
+            let available = enumerate_counters(physical_device, family)?;
+            let selected = counters
+                .iter()
+                .map(|name| {
+                    available.iter().find(|counter| counter.matches(name)).ok_or_else(|| {
+                        KronosError::UnsupportedHardware(format!(
+                            "Performance counter '{}' is not available on queue family {}",
+                            name, family
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let indices: Vec<u32> = selected.iter().map(|counter| counter.index).collect();
+
+            let mut performance_info = VkQueryPoolPerformanceCreateInfoKHR {
+                sType: VkStructureType::QueryPoolPerformanceCreateInfoKHR,
+                pNext: ptr::null(),
+                queueFamilyIndex: family,
+                counterIndexCount: indices.len() as u32,
+                pCounterIndices: indices.as_ptr(),
+            };
+            let mut passes = 0u32;
+            vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR(physical_device, &performance_info, &mut passes);
+            let passes = passes.max(1);
+            kronos_log!(Debug, "[SAFE API] Collecting {} counters in {} passes", indices.len(), passes);
+
+            let mut create_info = VkQueryPoolCreateInfo {
+                sType: VkStructureType::QueryPoolCreateInfo,
+                pNext: ptr::null(),
+                flags: 0,
+                queryType: VkQueryType::PerformanceQueryKHR,
+                queryCount: 1,
+                pipelineStatistics: 0,
+            };
+            let create_info = Chain::new(&mut create_info).push(&mut performance_info);
+            let mut pool = VkQueryPool::NULL;
+            let result = vkCreateQueryPool(device, create_info.as_ptr(), ptr::null(), &mut pool);
+            if result != VkResult::Success {
+                return Err(KronosError::from(result));
+            }
+            let pool = CounterPool { device, pool };
+
+            let _lock = ProfilingLock::acquire(device)?;
+            // A performance query cannot be reset in the command buffer that begins it
+            self.reset_counter_pool(queue, command_pool, pool.pool)?;
+            dispatch.execute_counter_passes(pool.pool, passes)?;
+
+            let mut results = vec![VkPerformanceCounterResultKHR::default(); indices.len()];
+            let stride = std::mem::size_of_val(results.as_slice());
+            let result = vkGetQueryPoolResults(
+                device,
+                pool.pool,
+                0,
+                1,
+                stride,
+                results.as_mut_ptr() as *mut std::ffi::c_void,
+                stride as VkDeviceSize,
+                VkQueryResultFlags::WAIT,
+            );
+            if result != VkResult::Success {
+                return Err(KronosError::from(result));
+            }
+
+            Ok(selected
+                .iter()
+                .zip(results)
+                .map(|(counter, raw)| CounterValue {
+                    name: counter.name.clone(),
+                    unit: counter.unit,
+                    value: CounterResult::from_raw(counter.storage, raw),
+                })
+                .collect())
+        }
+    }
+
+    /// Reset the counter query in its own command buffer and wait for it
+    ///
+    /// # Safety
+    ///
+    /// The queue and command pool must belong to this context and the pool
+    /// must not be in use by the GPU
+    unsafe fn reset_counter_pool(&self, queue: VkQueue, command_pool: VkCommandPool, pool: VkQueryPool) -> Result<()> {
+        self.with_inner(|inner| {
+            // Guards the context's and the queues' command pools alike
+            let _pools = inner.pools.lock().unwrap();
+            let alloc_info = VkCommandBufferAllocateInfo {
+                sType: VkStructureType::CommandBufferAllocateInfo,
+                pNext: ptr::null(),
+                commandPool: command_pool,
+                level: VkCommandBufferLevel::Primary,
+                commandBufferCount: 1,
+            };
+
+            let mut command_buffer = VkCommandBuffer::NULL;
+            let result = vkAllocateCommandBuffers(inner.device, &alloc_info, &mut command_buffer);
+            if result != VkResult::Success {
+                return Err(KronosError::from(result));
+            }
+
+            let begin_info = VkCommandBufferBeginInfo {
+                sType: VkStructureType::CommandBufferBeginInfo,
+                pNext: ptr::null(),
+                flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                pInheritanceInfo: ptr::null(),
+            };
+
+            let result = vkBeginCommandBuffer(command_buffer, &begin_info);
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            vkCmdResetQueryPool(command_buffer, pool, 0, 1);
+
+            let result = vkEndCommandBuffer(command_buffer);
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            let submit_info = VkSubmitInfo {
+                sType: VkStructureType::SubmitInfo,
+                pNext: ptr::null(),
+                waitSemaphoreCount: 0,
+                pWaitSemaphores: ptr::null(),
+                pWaitDstStageMask: ptr::null(),
+                commandBufferCount: 1,
+                pCommandBuffers: &command_buffer,
+                signalSemaphoreCount: 0,
+                pSignalSemaphores: ptr::null(),
+            };
+
+            let _queue = inner.queue_lock.lock().unwrap();
+            let result = inner.device_events.check(inner.queue_submit(queue, 1, &submit_info, VkFence::NULL));
+            if result != VkResult::Success {
+                vkFreeCommandBuffers(inner.device, command_pool, 1, &command_buffer);
+                return Err(KronosError::from(result));
+            }
+
+            let result = inner.device_events.check(vkQueueWaitIdle(queue));
+            vkFreeCommandBuffers(inner.device, command_pool, 1, &command_buffer);
+            if result != VkResult::Success {
+                return Err(KronosError::SynchronizationError(format!(
+                    "vkQueueWaitIdle failed: {:?}",
+                    result
+                )));
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,79 @@
+//! Buffer pairs for iterative kernels
+//!
+//! An iterative kernel reads the previous iteration's results and writes
+//! the next ones. [`PingPong`] owns both buffers and tracks which holds the
+//! current results; [`CommandBuilder::bind_ping_pong`] binds them as input
+//! and output, swaps them, and makes the dispatch wait for the previous
+//! iteration's shader writes and reads of the two buffers, so no barrier has
+//! to be inserted by hand.
+
+use super::*;
+
+/// Two equally sized buffers that trade places every iteration
+pub struct PingPong {
+    buffers: [Buffer; 2],
+    /// Index of the buffer holding the current results
+    current: usize,
+    /// Dispatches bound so far
+    iterations: u64,
+}
+
+impl PingPong {
+    /// Create two uninitialized buffers of `size` bytes
+    pub fn new(ctx: &ComputeContext, size: usize) -> Result<Self> {
+        Ok(Self {
+            buffers: [ctx.create_buffer_uninit(size)?, ctx.create_buffer_uninit(size)?],
+            current: 0,
+            iterations: 0,
+        })
+    }
+
+    /// Buffer holding the current results, read by the next iteration
+    pub fn current(&self) -> &Buffer {
+        &self.buffers[self.current]
+    }
+
+    /// Mutable access to the current buffer, e.g. to upload initial data
+    pub fn current_mut(&mut self) -> &mut Buffer {
+        &mut self.buffers[self.current]
+    }
+
+    /// Buffer the next iteration writes
+    pub fn next(&self) -> &Buffer {
+        &self.buffers[1 - self.current]
+    }
+
+    /// Make the next buffer current
+    ///
+    /// [`CommandBuilder::bind_ping_pong`] swaps on its own; call this only
+    /// when binding the buffers by hand.
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    /// Number of dispatches bound with [`CommandBuilder::bind_ping_pong`]
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+}
+
+impl CommandBuilder {
+    /// Bind the current buffer of `buffers` to `input` and the next one to
+    /// `output`, then swap them
+    ///
+    /// After the call, [`PingPong::current`] is this dispatch's output. From
+    /// the second iteration on, the dispatch waits for the compute work of
+    /// the previous submissions on both buffers. Build exactly one dispatch
+    /// per iteration.
+    pub fn bind_ping_pong(mut self, input: u32, output: u32, buffers: &mut PingPong) -> Self {
+        if buffers.iterations > 0 {
+            for buffer in &buffers.buffers {
+                self = self.after_shader_access(buffer);
+            }
+        }
+        self = self.bind_buffer(input, buffers.current()).bind_buffer(output, buffers.next());
+        buffers.swap();
+        buffers.iterations += 1;
+        self
+    }
+}
@@ -2,33 +2,166 @@
 
 use super::*;
 use crate::*; // Import all functions from the crate root
-use std::ffi::CString;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
 use std::ptr;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use super::deferred::LastUse;
+use super::descriptor_pools::{layout_counts, DescriptorCounts};
 
 /// Compiled shader module
+///
+/// Shaders created from the same SPIR-V on one context share a
+/// VkShaderModule, which is destroyed with the last of them.
 pub struct Shader {
+    module: Arc<ShaderModule>,
+}
+
+/// A VkShaderModule and the SPIR-V it was created from
+pub(super) struct ShaderModule {
     context: ComputeContext,
-    module: VkShaderModule,
+    module: Owned<VkShaderModule>,
+    /// Key of the module in the context's cache
+    hash: u64,
+    /// SPIR-V words kept for reflection and to tell hash collisions apart
+    spirv: Vec<u32>,
 }
 
+/// Shader modules of a context by SPIR-V hash
+pub(super) type ShaderModuleCache = HashMap<u64, Vec<Weak<ShaderModule>>>;
+
 // Send + Sync for thread safety
-unsafe impl Send for Shader {}
-unsafe impl Sync for Shader {}
+unsafe impl Send for ShaderModule {}
+unsafe impl Sync for ShaderModule {}
 
 /// Compute pipeline with shader and layout
 pub struct Pipeline {
     pub(super) context: ComputeContext,
-    pub(super) pipeline: VkPipeline,
+    pub(super) pipeline: Owned<VkPipeline>,
     pub(super) layout: VkPipelineLayout,
     pub(super) descriptor_set_layout: VkDescriptorSetLayout,
+    /// Owner of `layout` and `descriptor_set_layout`, shared with derivatives
+    pub(super) layouts: Arc<PipelineLayouts>,
+    pub(super) entry_point: CString,
+    pub(super) allow_derivatives: bool,
+    /// Created with `DISPATCH_BASE`, see [`CommandBuilder::base`]
+    pub(super) dispatch_base: bool,
+    pub(super) interface: Arc<KernelInterface>,
+    /// Shared memory the kernel uses with the pipeline's specialization
+    pub(super) shared_memory_size: u64,
+    /// Name GPU time is attributed to in the optimization report
+    pub(super) label: Arc<str>,
+    pub(super) creation_feedback: Option<CreationFeedback>,
 }
 
+/// How the driver created a pipeline, from VK_EXT_pipeline_creation_feedback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreationFeedback {
+    /// Time the driver spent creating the pipeline
+    pub duration: Duration,
+    /// The pipeline was found in the pipeline cache rather than compiled
+    pub cache_hit: bool,
+    /// Creation reused work from the base pipeline of a derivative
+    pub base_pipeline_acceleration: bool,
+    /// Time spent on the compute stage, if the driver reports it separately
+    pub stage_duration: Option<Duration>,
+}
+
+impl CreationFeedback {
+    /// Feedback the driver wrote, if it marked the pipeline's as valid
+    fn from_raw(pipeline: &VkPipelineCreationFeedback, stage: &VkPipelineCreationFeedback) -> Option<Self> {
+        if !pipeline.flags.contains(VkPipelineCreationFeedbackFlags::VALID) {
+            return None;
+        }
+        Some(Self {
+            duration: Duration::from_nanos(pipeline.duration),
+            cache_hit: pipeline.flags.contains(VkPipelineCreationFeedbackFlags::APPLICATION_PIPELINE_CACHE_HIT),
+            base_pipeline_acceleration: pipeline.flags.contains(VkPipelineCreationFeedbackFlags::BASE_PIPELINE_ACCELERATION),
+            stage_duration: stage
+                .flags
+                .contains(VkPipelineCreationFeedbackFlags::VALID)
+                .then(|| Duration::from_nanos(stage.duration)),
+        })
+    }
+}
+
+/// Pipeline and descriptor set layouts shared by pipelines with the same
+/// bindings and push constant size
+pub(super) struct PipelineLayouts {
+    context: ComputeContext,
+    layout: VkPipelineLayout,
+    descriptor_set_layout: VkDescriptorSetLayout,
+    /// Owner of `descriptor_set_layout`
+    set_layout: Arc<SetLayout>,
+    push_constant_size: u32,
+}
+
+/// A descriptor set layout shared by pipeline layouts with the same bindings
+pub(super) struct SetLayout {
+    context: ComputeContext,
+    layout: VkDescriptorSetLayout,
+    key: SetLayoutKey,
+    /// Descriptors of each type a set of the layout holds
+    counts: DescriptorCounts,
+}
+
+/// Bindings of a descriptor set layout, in binding order
+type SetLayoutKey = Vec<(u32, VkDescriptorType)>;
+
+/// Whether bindings of `descriptor_type` take a buffer
+fn is_buffer_descriptor(descriptor_type: VkDescriptorType) -> bool {
+    matches!(
+        descriptor_type,
+        VkDescriptorType::StorageBuffer
+            | VkDescriptorType::UniformBuffer
+            | VkDescriptorType::StorageBufferDynamic
+            | VkDescriptorType::UniformBufferDynamic
+    )
+}
+
+/// Layouts of a context by their contents
+#[derive(Default)]
+pub(super) struct LayoutCache {
+    set_layouts: HashMap<SetLayoutKey, Weak<SetLayout>>,
+    /// Keyed by bindings and push constant size
+    pipeline_layouts: HashMap<(SetLayoutKey, u32), Weak<PipelineLayouts>>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for PipelineLayouts {}
+unsafe impl Sync for PipelineLayouts {}
+unsafe impl Send for SetLayout {}
+unsafe impl Sync for SetLayout {}
+
 // Send + Sync for thread safety  
 unsafe impl Send for Pipeline {}
 unsafe impl Sync for Pipeline {}
 
+/// Descriptor set with a buffer bound to each binding of a pipeline's layout
+///
+/// Created by [`Pipeline::bind_all`] and reusable across dispatches through
+/// [`CommandBuilder::descriptor_set`](super::CommandBuilder::descriptor_set).
+pub struct DescriptorSet {
+    pub(super) context: ComputeContext,
+    pub(super) set: Owned<VkDescriptorSet>,
+    pub(super) layout: VkDescriptorSetLayout,
+    pub(super) buffers: Vec<(VkBuffer, VkDeviceSize)>,
+    /// Bindings of a dynamic descriptor type, bound at offset zero
+    pub(super) dynamic_bindings: u32,
+    /// Last submission that used each bound buffer, pushed by every builder
+    /// the set is used with so dropped buffers outlive their dispatches
+    pub(super) uses: Vec<Arc<LastUse>>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for DescriptorSet {}
+unsafe impl Sync for DescriptorSet {}
+
 /// Information about buffer bindings for a pipeline
 #[derive(Debug, Clone)]
 pub struct BufferBinding {
@@ -53,8 +186,36 @@ pub struct PipelineConfig {
     pub local_size: (u32, u32, u32),
     /// Buffer bindings
     pub bindings: Vec<BufferBinding>,
-    /// Push constant size in bytes (max 128)
+    /// Push constant size in bytes, at most
+    /// [`ComputeContext::max_push_constants_size`]
     pub push_constant_size: u32,
+    /// Specialization constants as (constant_id, 32-bit value)
+    pub specialization: Vec<(u32, u32)>,
+    /// Allow [`Pipeline::derive`] to use this pipeline as a base
+    pub allow_derivatives: bool,
+    /// Allow dispatches with a non-zero [`CommandBuilder::base`]; needs
+    /// [`ComputeContext::supports_dispatch_base`]
+    pub dispatch_base: bool,
+    /// Size a shared memory array whose length is a specialization constant
+    pub shared_memory: Option<SharedMemorySize>,
+}
+
+/// Shared memory for an array sized by a specialization constant, as in
+///
+/// ```glsl
+/// layout(constant_id = 0) const uint TILE = 256;
+/// shared float tile[TILE];
+/// ```
+///
+/// Kronos sets the constant to the number of elements that fit in `bytes`.
+/// Arrays sharing the constant split the bytes between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedMemorySize {
+    /// `constant_id` of the array length
+    pub constant_id: u32,
+    /// Bytes to give the array, or `None` for all the shared memory the
+    /// device has left after the kernel's other variables
+    pub bytes: Option<u32>,
 }
 
 impl Default for PipelineConfig {
@@ -64,6 +225,10 @@ impl Default for PipelineConfig {
             local_size: (64, 1, 1),
             bindings: Vec::new(),
             push_constant_size: 0,
+            specialization: Vec::new(),
+            allow_derivatives: false,
+            dispatch_base: false,
+            shared_memory: None,
         }
     }
 }
@@ -78,6 +243,9 @@ impl ComputeContext {
     }
     
     /// Create a shader from SPIR-V bytes
+    ///
+    /// SPIR-V this context already has a module for reuses that module
+    /// instead of creating another.
     pub fn create_shader_from_spirv(&self, spirv: &[u8]) -> Result<Shader> {
         if spirv.len() % 4 != 0 {
             return Err(KronosError::ShaderCompilationFailed(
@@ -85,14 +253,29 @@ impl ComputeContext {
             ));
         }
         
+        // Copy into words so the code pointer is 4-byte aligned
+        let words = reflect::spirv_words(spirv);
+        let mut hasher = DefaultHasher::new();
+        words.hash(&mut hasher);
+        let hash = hasher.finish();
         unsafe {
             self.with_inner(|inner| {
+                // Declared before the guard so a module whose last other
+                // reference goes away meanwhile is dropped after the unlock
+                let mut live = Vec::new();
+                let mut modules = inner.shader_modules.lock().unwrap();
+                live.extend(modules.get(&hash).into_iter().flatten().filter_map(Weak::upgrade));
+                if let Some(module) = live.iter().find(|module| module.spirv == words) {
+                    kronos_log!(Debug, "[SAFE API] Reusing shader module {:?}", module.module.raw());
+                    return Ok(Shader { module: module.clone() });
+                }
+                
                 let create_info = VkShaderModuleCreateInfo {
                     sType: VkStructureType::ShaderModuleCreateInfo,
                     pNext: ptr::null(),
                     flags: 0,
                     codeSize: spirv.len(),
-                    pCode: spirv.as_ptr() as *const u32,
+                    pCode: words.as_ptr(),
                 };
                 
                 let mut module = VkShaderModule::NULL;
@@ -104,10 +287,14 @@ impl ComputeContext {
                     ));
                 }
                 
-                Ok(Shader {
+                let module = Arc::new(ShaderModule {
                     context: self.clone(),
-                    module,
-                })
+                    module: Owned::new(module, inner.id),
+                    hash,
+                    spirv: words,
+                });
+                modules.entry(hash).or_default().push(Arc::downgrade(&module));
+                Ok(Shader { module })
             })
         }
     }
@@ -119,39 +306,179 @@ impl ComputeContext {
     
     /// Create a compute pipeline with custom configuration
     pub fn create_pipeline_with_config(&self, shader: &Shader, config: PipelineConfig) -> Result<Pipeline> {
-        if config.push_constant_size > 128 {
+        let layouts = self.create_pipeline_layouts(&config)?;
+        let entry_point = Self::entry_point_name(&config.entry_point)?;
+        let specialization = self.size_shared_memory(shader, &config, &config.specialization)?;
+        let mut flags = self.dispatch_base_flags(&config)?;
+        if config.allow_derivatives {
+            flags |= VkPipelineCreateFlags::ALLOW_DERIVATIVES;
+        }
+        let mut pipelines = self.create_specialized_pipelines(
+            &layouts,
+            shader,
+            &entry_point,
+            &[specialization.as_slice()],
+            flags,
+            None,
+        )?;
+        Ok(pipelines.remove(0))
+    }
+    
+    /// Create one pipeline per specialization in a single driver call
+    ///
+    /// All pipelines share the layout described by `config`; the first is
+    /// the base and the rest are created as its derivatives, letting the
+    /// driver reuse compilation work across the family. `config.specialization`
+    /// is ignored in favor of `variants`.
+    ///
+    /// Members share one shader module. Compute has no pipeline libraries,
+    /// and VK_EXT_shader_module_identifier only skips module creation for
+    /// pipelines already in a `VkPipelineCache`, which Kronos does not
+    /// keep, so neither is used.
+    pub fn create_pipeline_family(
+        &self,
+        shader: &Shader,
+        config: PipelineConfig,
+        variants: &[&[(u32, u32)]],
+    ) -> Result<Vec<Pipeline>> {
+        if variants.is_empty() {
             return Err(KronosError::ShaderCompilationFailed(
-                format!("Push constant size {} exceeds maximum 128 bytes", config.push_constant_size)
+                "A pipeline family needs at least one variant".into(),
             ));
         }
+        let flags = self.dispatch_base_flags(&config)? | VkPipelineCreateFlags::ALLOW_DERIVATIVES;
+        let layouts = self.create_pipeline_layouts(&config)?;
+        let entry_point = Self::entry_point_name(&config.entry_point)?;
+        let variants = variants
+            .iter()
+            .map(|constants| self.size_shared_memory(shader, &config, constants))
+            .collect::<Result<Vec<_>>>()?;
+        let variants: Vec<&[(u32, u32)]> = variants.iter().map(Vec::as_slice).collect();
+        self.create_specialized_pipelines(&layouts, shader, &entry_point, &variants, flags, None)
+    }
+    
+    /// `DISPATCH_BASE` if `config` asks for it and the device supports it
+    fn dispatch_base_flags(&self, config: &PipelineConfig) -> Result<VkPipelineCreateFlags> {
+        if !config.dispatch_base {
+            return Ok(VkPipelineCreateFlags::empty());
+        }
+        if !self.supports_dispatch_base() {
+            return Err(KronosError::UnsupportedHardware(
+                "Dispatch base offsets need Vulkan 1.1 on the instance and device".into(),
+            ));
+        }
+        Ok(VkPipelineCreateFlags::DISPATCH_BASE)
+    }
+    
+    /// `specialization` with the constant of `config.shared_memory` set
+    fn size_shared_memory(&self, shader: &Shader, config: &PipelineConfig, specialization: &[(u32, u32)]) -> Result<Vec<(u32, u32)>> {
+        let mut constants: Vec<(u32, u32)> = specialization.to_vec();
+        let Some(size) = config.shared_memory else {
+            return Ok(constants);
+        };
+        constants.retain(|&(constant_id, _)| constant_id != size.constant_id);
+        let interface = shader.interface(&config.entry_point)?;
+        let (sized, others): (Vec<&SharedVariable>, Vec<&SharedVariable>) = interface
+            .shared_memory
+            .iter()
+            .partition(|variable| variable.length_constant == Some(size.constant_id));
+        let element_bytes: u32 = sized.iter().filter_map(|variable| variable.element_size).sum();
+        if element_bytes == 0 {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "Kernel '{}' has no shared array sized by specialization constant {}",
+                config.entry_point, size.constant_id
+            )));
+        }
+        let bytes = size.bytes.map_or_else(
+            || {
+                let used: u64 = others.iter().map(|variable| variable.specialized_size(&constants)).sum();
+                (self.max_shared_memory_size() as u64).saturating_sub(used)
+            },
+            u64::from,
+        );
+        let length = bytes / element_bytes as u64;
+        if length == 0 {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "{} bytes of shared memory do not fit one element of the arrays sized by specialization constant {}",
+                bytes, size.constant_id
+            )));
+        }
+        constants.push((size.constant_id, length.min(u32::MAX as u64) as u32));
+        Ok(constants)
+    }
+    
+    fn entry_point_name(name: &str) -> Result<CString> {
+        CString::new(name)
+            .map_err(|_| KronosError::ShaderCompilationFailed("Invalid entry point name".into()))
+    }
+    
+    /// Get the descriptor set layout and pipeline layout for `config`
+    ///
+    /// Both are shared with every other pipeline of the context whose
+    /// configuration has the same bindings (and, for the pipeline layout,
+    /// push constant size), so their descriptor sets are interchangeable.
+    fn create_pipeline_layouts(&self, config: &PipelineConfig) -> Result<Arc<PipelineLayouts>> {
+        let max_push_constants_size = self.max_push_constants_size();
+        if config.push_constant_size > max_push_constants_size {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "Push constant size {} exceeds maximum {} bytes",
+                config.push_constant_size, max_push_constants_size
+            )));
+        }
+        
+        let mut bindings: SetLayoutKey = config.bindings.iter().map(|b| (b.binding, b.descriptor_type)).collect();
+        bindings.sort_by_key(|&(binding, _)| binding);
+        let key = (bindings, config.push_constant_size);
         
         unsafe {
             self.with_inner(|inner| {
-                // Create descriptor set layout for Set0 (persistent descriptors)
-                let bindings: Vec<VkDescriptorSetLayoutBinding> = config.bindings.iter().map(|b| {
-                    VkDescriptorSetLayoutBinding {
-                        binding: b.binding,
-                        descriptorType: b.descriptor_type,
-                        descriptorCount: 1,
-                        stageFlags: VkShaderStageFlags::COMPUTE,
-                        pImmutableSamplers: ptr::null(),
-                    }
-                }).collect();
-                
-                let layout_info = VkDescriptorSetLayoutCreateInfo {
-                    sType: VkStructureType::DescriptorSetLayoutCreateInfo,
-                    pNext: ptr::null(),
-                    flags: 0,
-                    bindingCount: bindings.len() as u32,
-                    pBindings: if bindings.is_empty() { ptr::null() } else { bindings.as_ptr() },
-                };
-                
-                let mut descriptor_set_layout = VkDescriptorSetLayout::NULL;
-                let result = vkCreateDescriptorSetLayout(inner.device, &layout_info, ptr::null(), &mut descriptor_set_layout);
+                // Declared before the guard so layouts whose last other
+                // reference goes away meanwhile are dropped after the unlock
+                let mut live_layouts = Vec::new();
+                let mut live_set_layouts = Vec::new();
+                let mut cache = inner.layouts.lock().unwrap();
+                live_layouts.extend(cache.pipeline_layouts.get(&key).and_then(Weak::upgrade));
+                if let Some(layouts) = live_layouts.first() {
+                    return Ok(layouts.clone());
+                }
                 
-                if result != VkResult::Success {
-                    return Err(KronosError::from(result));
+                live_set_layouts.extend(cache.set_layouts.get(&key.0).and_then(Weak::upgrade));
+                if live_set_layouts.is_empty() {
+                    // Create descriptor set layout for Set0 (persistent descriptors)
+                    let layout_bindings: Vec<VkDescriptorSetLayoutBinding> = key.0.iter().map(|&(binding, descriptor_type)| {
+                        VkDescriptorSetLayoutBinding {
+                            binding,
+                            descriptorType: descriptor_type,
+                            descriptorCount: 1,
+                            stageFlags: VkShaderStageFlags::COMPUTE,
+                            pImmutableSamplers: ptr::null(),
+                        }
+                    }).collect();
+                    
+                    let layout_info = VkDescriptorSetLayoutCreateInfo {
+                        sType: VkStructureType::DescriptorSetLayoutCreateInfo,
+                        pNext: ptr::null(),
+                        flags: 0,
+                        bindingCount: layout_bindings.len() as u32,
+                        pBindings: if layout_bindings.is_empty() { ptr::null() } else { layout_bindings.as_ptr() },
+                    };
+                    
+                    let mut descriptor_set_layout = VkDescriptorSetLayout::NULL;
+                    let result = vkCreateDescriptorSetLayout(inner.device, &layout_info, ptr::null(), &mut descriptor_set_layout);
+                    
+                    if result != VkResult::Success {
+                        return Err(KronosError::from(result));
+                    }
+                    let set_layout = Arc::new(SetLayout {
+                        context: self.clone(),
+                        layout: descriptor_set_layout,
+                        key: key.0.clone(),
+                        counts: layout_counts(&key.0),
+                    });
+                    cache.set_layouts.insert(key.0.clone(), Arc::downgrade(&set_layout));
+                    live_set_layouts.push(set_layout);
                 }
+                let set_layout = live_set_layouts[0].clone();
                 
                 // Create pipeline layout
                 let push_constant_range = if config.push_constant_size > 0 {
@@ -169,7 +496,7 @@ impl ComputeContext {
                     pNext: ptr::null(),
                     flags: 0,
                     setLayoutCount: 1,
-                    pSetLayouts: &descriptor_set_layout,
+                    pSetLayouts: &set_layout.layout,
                     pushConstantRangeCount: if push_constant_range.is_some() { 1 } else { 0 },
                     pPushConstantRanges: push_constant_range.as_ref().map_or(ptr::null(), |r| r as *const _),
                 };
@@ -178,65 +505,290 @@ impl ComputeContext {
                 let result = vkCreatePipelineLayout(inner.device, &pipeline_layout_info, ptr::null(), &mut pipeline_layout);
                 
                 if result != VkResult::Success {
-                    vkDestroyDescriptorSetLayout(inner.device, descriptor_set_layout, ptr::null());
                     return Err(KronosError::from(result));
                 }
                 
-                // Create compute pipeline
-                let entry_point = CString::new(config.entry_point.clone())
-                    .map_err(|_| KronosError::ShaderCompilationFailed("Invalid entry point name".into()))?;
-                
-                let stage_info = VkPipelineShaderStageCreateInfo {
-                    sType: VkStructureType::PipelineShaderStageCreateInfo,
-                    pNext: ptr::null(),
-                    flags: VkPipelineShaderStageCreateFlags::empty(),
-                    stage: VkShaderStageFlagBits::Compute,
-                    module: shader.module,
-                    pName: entry_point.as_ptr(),
-                    pSpecializationInfo: ptr::null(),
-                };
+                let layouts = Arc::new(PipelineLayouts {
+                    context: self.clone(),
+                    layout: pipeline_layout,
+                    descriptor_set_layout: set_layout.layout,
+                    set_layout,
+                    push_constant_size: config.push_constant_size,
+                });
+                cache.pipeline_layouts.insert(key, Arc::downgrade(&layouts));
+                Ok(layouts)
+            })
+        }
+    }
+    
+    /// Create one pipeline per entry of `variants` in a single vkCreateComputePipelines call
+    ///
+    /// With `base` every pipeline derives from that handle; otherwise the
+    /// first pipeline is the base of the others (via basePipelineIndex).
+    /// `flags` apply to every pipeline; derivative flags are added as needed.
+    fn create_specialized_pipelines(
+        &self,
+        layouts: &Arc<PipelineLayouts>,
+        shader: &Shader,
+        entry_point: &CStr,
+        variants: &[&[(u32, u32)]],
+        flags: VkPipelineCreateFlags,
+        base: Option<VkPipeline>,
+    ) -> Result<Vec<Pipeline>> {
+        let interface = Arc::new(shader.interface(&entry_point.to_string_lossy()).unwrap_or_else(|e| {
+            kronos_log!(Warn, "Kernel reflection failed, interface will be empty: {}", e);
+            KernelInterface {
+                entry_point: entry_point.to_string_lossy().into_owned(),
+                ..Default::default()
+            }
+        }));
+        // Drivers only fail on too much shared memory at dispatch, if at all
+        let shared_memory_sizes: Vec<u64> = variants
+            .iter()
+            .map(|constants| interface.shared_memory_size(constants))
+            .collect();
+        let limit = self.max_shared_memory_size();
+        if let Some(size) = shared_memory_sizes.iter().find(|&&size| size > limit as u64) {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "Kernel '{}' uses {} bytes of shared memory, more than the device's {}",
+                entry_point.to_string_lossy(),
+                size,
+                limit
+            )));
+        }
+        
+        // Keep the specialization data alive until the pipelines are created
+        let entries: Vec<Vec<VkSpecializationMapEntry>> = variants
+            .iter()
+            .map(|constants| Self::specialization_entries(constants))
+            .collect();
+        let data: Vec<Vec<u32>> = variants
+            .iter()
+            .map(|constants| constants.iter().map(|&(_, value)| value).collect())
+            .collect();
+        let specializations: Vec<VkSpecializationInfo> = entries
+            .iter()
+            .zip(&data)
+            .map(|(entries, data)| VkSpecializationInfo {
+                mapEntryCount: entries.len() as u32,
+                pMapEntries: entries.as_ptr(),
+                dataSize: std::mem::size_of_val(data.as_slice()),
+                pData: data.as_ptr() as *const _,
+            })
+            .collect();
+        
+        let derives_within_batch = base.is_none() && variants.len() > 1;
+        let allow_derivatives = flags.contains(VkPipelineCreateFlags::ALLOW_DERIVATIVES);
+        let mut pipeline_infos: Vec<VkComputePipelineCreateInfo> = specializations
+            .iter()
+            .enumerate()
+            .map(|(index, specialization)| {
+                let mut flags = flags;
+                if derives_within_batch && index == 0 {
+                    flags |= VkPipelineCreateFlags::ALLOW_DERIVATIVES;
+                }
+                let is_derivative = base.is_some() || (derives_within_batch && index > 0);
+                if is_derivative {
+                    flags |= VkPipelineCreateFlags::DERIVATIVE;
+                }
                 
-                let pipeline_info = VkComputePipelineCreateInfo {
+                VkComputePipelineCreateInfo {
                     sType: VkStructureType::ComputePipelineCreateInfo,
                     pNext: ptr::null(),
-                    flags: VkPipelineCreateFlags::empty(),
-                    stage: stage_info,
-                    layout: pipeline_layout,
-                    basePipelineHandle: VkPipeline::NULL,
-                    basePipelineIndex: -1,
-                };
-                
-                let mut pipeline = VkPipeline::NULL;
+                    flags,
+                    stage: VkPipelineShaderStageCreateInfo {
+                        sType: VkStructureType::PipelineShaderStageCreateInfo,
+                        pNext: ptr::null(),
+                        flags: VkPipelineShaderStageCreateFlags::empty(),
+                        stage: VkShaderStageFlagBits::Compute,
+                        module: shader.module.module.on(self.device_id()),
+                        pName: entry_point.as_ptr(),
+                        pSpecializationInfo: if specialization.mapEntryCount == 0 {
+                            ptr::null()
+                        } else {
+                            specialization
+                        },
+                    },
+                    layout: layouts.layout,
+                    basePipelineHandle: base.unwrap_or(VkPipeline::NULL),
+                    basePipelineIndex: if is_derivative && base.is_none() { 0 } else { -1 },
+                }
+            })
+            .collect();
+        
+        // One pipeline and one stage feedback per pipeline, filled in by the driver
+        let mut pipeline_feedback = vec![VkPipelineCreationFeedback::default(); pipeline_infos.len()];
+        let mut stage_feedback = vec![VkPipelineCreationFeedback::default(); pipeline_infos.len()];
+        let mut feedback_infos: Vec<VkPipelineCreationFeedbackCreateInfo> = pipeline_feedback
+            .iter_mut()
+            .zip(stage_feedback.iter_mut())
+            .map(|(pipeline, stage)| VkPipelineCreationFeedbackCreateInfo {
+                sType: VkStructureType::PipelineCreationFeedbackCreateInfo,
+                pNext: ptr::null(),
+                pPipelineCreationFeedback: pipeline,
+                pipelineStageCreationFeedbackCount: 1,
+                pPipelineStageCreationFeedbacks: stage,
+            })
+            .collect();
+        let creation_feedback = self.with_inner(|inner| inner.pipeline_creation_feedback);
+        if creation_feedback {
+            for (info, feedback) in pipeline_infos.iter_mut().zip(feedback_infos.iter_mut()) {
+                Chain::new(info).push(feedback);
+            }
+        }
+        
+        let handles = unsafe {
+            self.with_inner(|inner| {
+                let mut handles = vec![VkPipeline::NULL; pipeline_infos.len()];
                 let result = vkCreateComputePipelines(
                     inner.device,
                     VkPipelineCache::NULL,
-                    1,
-                    &pipeline_info,
+                    pipeline_infos.len() as u32,
+                    pipeline_infos.as_ptr(),
                     ptr::null(),
-                    &mut pipeline,
+                    handles.as_mut_ptr(),
                 );
                 
                 if result != VkResult::Success {
-                    vkDestroyPipelineLayout(inner.device, pipeline_layout, ptr::null());
-                    vkDestroyDescriptorSetLayout(inner.device, descriptor_set_layout, ptr::null());
+                    // Creation may partially succeed; release what was made
+                    for handle in handles.iter().filter(|h| **h != VkPipeline::NULL) {
+                        vkDestroyPipeline(inner.device, *handle, ptr::null());
+                    }
                     return Err(KronosError::from(result));
                 }
-                
-                Ok(Pipeline {
+                Ok(handles)
+            })?
+        };
+        
+        let label: Arc<str> = Arc::from(entry_point.to_string_lossy());
+        Ok(handles
+            .into_iter()
+            .enumerate()
+            .map(|(index, pipeline)| {
+                let creation_feedback = if creation_feedback {
+                    CreationFeedback::from_raw(&pipeline_feedback[index], &stage_feedback[index])
+                } else {
+                    None
+                };
+                if let Some(feedback) = &creation_feedback {
+                    kronos_log!(
+                        Debug,
+                        "[SAFE API] Pipeline {} created in {:?}{}",
+                        label,
+                        feedback.duration,
+                        if feedback.cache_hit { " (pipeline cache hit)" } else { "" }
+                    );
+                }
+                Pipeline {
                     context: self.clone(),
-                    pipeline,
-                    layout: pipeline_layout,
-                    descriptor_set_layout,
-                })
+                    pipeline: Owned::new(pipeline, self.device_id()),
+                    layout: layouts.layout,
+                    descriptor_set_layout: layouts.descriptor_set_layout,
+                    layouts: layouts.clone(),
+                    entry_point: entry_point.to_owned(),
+                    allow_derivatives: allow_derivatives || derives_within_batch,
+                    dispatch_base: flags.contains(VkPipelineCreateFlags::DISPATCH_BASE),
+                    interface: interface.clone(),
+                    shared_memory_size: shared_memory_sizes[index],
+                    label: label.clone(),
+                    creation_feedback,
+                }
             })
-        }
+            .collect())
+    }
+    
+    /// Map entries for tightly packed 32-bit specialization constants
+    pub(super) fn specialization_entries(constants: &[(u32, u32)]) -> Vec<VkSpecializationMapEntry> {
+        constants
+            .iter()
+            .enumerate()
+            .map(|(index, &(constant_id, _))| VkSpecializationMapEntry {
+                constantID: constant_id,
+                offset: (index * std::mem::size_of::<u32>()) as u32,
+                size: std::mem::size_of::<u32>(),
+            })
+            .collect()
+    }
+}
+
+impl Shader {
+    /// Reflect the bindings, push constants and workgroup size of `entry_point`
+    pub fn interface(&self, entry_point: &str) -> Result<KernelInterface> {
+        reflect::reflect_spirv(&self.module.spirv, entry_point)
+    }
+    
+    /// SPIR-V words the shader was created from
+    pub(super) fn spirv(&self) -> &[u32] {
+        &self.module.spirv
     }
 }
 
 impl Pipeline {
     /// Get the raw Vulkan pipeline handle (for advanced usage)
     pub fn raw(&self) -> VkPipeline {
-        self.pipeline
+        self.pipeline.raw()
+    }
+    
+    /// Take ownership of a compute pipeline created outside Kronos
+    ///
+    /// `config` describes the pipeline: its entry point, bindings, push
+    /// constant size and whether it allows derivatives. The pipeline and
+    /// both layouts are destroyed when the returned pipeline is dropped;
+    /// the layouts are not shared with pipelines Kronos creates. Without
+    /// the SPIR-V the kernel interface is empty, so
+    /// [`CommandBuilder::bind_named`] and [`CommandBuilder::push_named`]
+    /// fail on it. If the entry point name is
+    /// invalid, the caller keeps ownership of the handles.
+    ///
+    /// # Safety
+    ///
+    /// - All three handles must have been created on `context`'s device,
+    ///   and must not be destroyed elsewhere
+    /// - `layout` must consist of `descriptor_set_layout` as set 0 and a
+    ///   compute push constant range of `config.push_constant_size` bytes,
+    ///   and `descriptor_set_layout` of the bindings in `config.bindings`
+    /// - `pipeline` must have been created with `layout`, and with
+    ///   `ALLOW_DERIVATIVES` if `config.allow_derivatives` is set and
+    ///   `DISPATCH_BASE` if `config.dispatch_base` is set
+    pub unsafe fn from_raw_parts(
+        context: &ComputeContext,
+        pipeline: VkPipeline,
+        layout: VkPipelineLayout,
+        descriptor_set_layout: VkDescriptorSetLayout,
+        config: &PipelineConfig,
+    ) -> Result<Pipeline> {
+        let entry_point = ComputeContext::entry_point_name(&config.entry_point)?;
+        let mut key: SetLayoutKey = config.bindings.iter().map(|b| (b.binding, b.descriptor_type)).collect();
+        key.sort_by_key(|&(binding, _)| binding);
+        let layouts = Arc::new(PipelineLayouts {
+            context: context.clone(),
+            layout,
+            descriptor_set_layout,
+            set_layout: Arc::new(SetLayout {
+                context: context.clone(),
+                layout: descriptor_set_layout,
+                counts: layout_counts(&key),
+                key,
+            }),
+            push_constant_size: config.push_constant_size,
+        });
+        Ok(Pipeline {
+            context: context.clone(),
+            pipeline: Owned::new(pipeline, context.device_id()),
+            layout,
+            descriptor_set_layout,
+            layouts,
+            entry_point,
+            allow_derivatives: config.allow_derivatives,
+            dispatch_base: config.dispatch_base,
+            interface: Arc::new(KernelInterface {
+                entry_point: config.entry_point.clone(),
+                ..Default::default()
+            }),
+            shared_memory_size: 0,
+            label: Arc::from(config.entry_point.as_str()),
+            creation_feedback: None,
+        })
     }
     
     /// Get the pipeline layout
@@ -248,13 +800,213 @@ impl Pipeline {
     pub fn descriptor_set_layout(&self) -> VkDescriptorSetLayout {
         self.descriptor_set_layout
     }
+    
+    /// Kernel interface reflected from the shader's SPIR-V
+    ///
+    /// Names are only available when the module was compiled with debug
+    /// info; the workgroup size reflects default specialization values.
+    pub fn interface(&self) -> &KernelInterface {
+        &self.interface
+    }
+    
+    /// Bytes of shared memory the kernel uses with this pipeline's
+    /// specialization, as [`KernelInterface::shared_memory_size`] counts them
+    pub fn shared_memory_size(&self) -> u64 {
+        self.shared_memory_size
+    }
+    
+    /// Name this pipeline's GPU time is reported under
+    ///
+    /// Defaults to the entry point name.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+    
+    /// Report this pipeline's GPU time under `label`
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = Arc::from(label.into());
+    }
+    
+    /// How long the driver took to create this pipeline and whether it
+    /// came from a cache
+    ///
+    /// `None` unless the device supports VK_EXT_pipeline_creation_feedback
+    /// and the driver filled it in.
+    pub fn creation_feedback(&self) -> Option<CreationFeedback> {
+        self.creation_feedback
+    }
+    
+    /// Whether this pipeline can be the base of [`derive`](Self::derive)
+    pub fn allows_derivatives(&self) -> bool {
+        self.allow_derivatives
+    }
+    
+    /// Whether dispatches of this pipeline can set a [`CommandBuilder::base`]
+    pub fn allows_dispatch_base(&self) -> bool {
+        self.dispatch_base
+    }
+    
+    /// Create a derivative pipeline with the same layout and entry point
+    ///
+    /// The pipeline must have been created with
+    /// [`PipelineConfig::allow_derivatives`] or by
+    /// [`ComputeContext::create_pipeline_family`]. The derivative shares this
+    /// pipeline's layout, so descriptor sets from [`bind_all`](Self::bind_all)
+    /// work with both, and allows a dispatch base if this pipeline does.
+    pub fn derive(&self, shader: &Shader, specialization: &[(u32, u32)]) -> Result<Pipeline> {
+        if !self.allow_derivatives {
+            return Err(KronosError::ShaderCompilationFailed(
+                "Base pipeline was not created with allow_derivatives".into(),
+            ));
+        }
+        let mut flags = VkPipelineCreateFlags::ALLOW_DERIVATIVES;
+        if self.dispatch_base {
+            flags |= VkPipelineCreateFlags::DISPATCH_BASE;
+        }
+        self.context.create_specialized_pipelines(
+            &self.layouts,
+            shader,
+            &self.entry_point,
+            &[specialization],
+            flags,
+            Some(self.pipeline.on(self.context.device_id())),
+        )
+        .map(|mut pipelines| pipelines.remove(0))
+    }
+    
+    /// Bind `buffers[i]` to the `i`th binding of the pipeline's layout, in
+    /// binding order
+    ///
+    /// The layout must declare only buffer bindings, one per buffer; each
+    /// is written as the descriptor type it was declared with, and dynamic
+    /// ones are bound at offset zero. All writes are submitted in a single
+    /// vkUpdateDescriptorSets call.
+    pub fn bind_all(&self, buffers: &[&Buffer]) -> Result<DescriptorSet> {
+        if buffers.is_empty() {
+            return Err(KronosError::CommandExecutionFailed(
+                "bind_all requires at least one buffer".into(),
+            ));
+        }
+        if self.descriptor_set_layout == VkDescriptorSetLayout::NULL {
+            return Err(KronosError::CommandExecutionFailed(
+                "Buffer bindings require a valid descriptor set layout".into(),
+            ));
+        }
+        if let Some(index) = buffers.iter().position(|b| b.buffer.raw() == VkBuffer::NULL) {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Binding {} has a NULL Vulkan buffer",
+                index
+            )));
+        }
+        let device = self.context.device_id();
+        let uses = buffers.iter().map(|buffer| buffer.last_use.clone()).collect();
+        let buffers: Vec<(VkBuffer, VkDeviceSize)> = buffers
+            .iter()
+            .map(|buffer| (buffer.buffer.on(device), buffer.size as VkDeviceSize))
+            .collect();
+        self.bind_raw(&buffers, uses)
+    }
+    
+    /// [`bind_all`](Self::bind_all) for buffer handles of this pipeline's
+    /// device and their sizes, which the caller keeps alive
+    ///
+    /// `uses` are the last-use stamps of the buffers, if they are tracked.
+    pub(super) fn bind_raw(
+        &self,
+        buffers: &[(VkBuffer, VkDeviceSize)],
+        uses: Vec<Arc<LastUse>>,
+    ) -> Result<DescriptorSet> {
+        let bindings = self.layouts.bindings();
+        if let Some(&(binding, descriptor_type)) = bindings.iter().find(|&&(_, descriptor_type)| !is_buffer_descriptor(descriptor_type)) {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Binding {} is a {:?} binding, which cannot be bound to a buffer",
+                binding, descriptor_type
+            )));
+        }
+        if buffers.len() != bindings.len() {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Pipeline layout has {} buffer bindings, but {} buffers were given",
+                bindings.len(),
+                buffers.len()
+            )));
+        }
+        unsafe {
+            self.context.with_inner(|inner| {
+                let pools = inner.pools.lock().unwrap();
+                let set = pools.descriptors.allocate(self.descriptor_set_layout, self.layouts.descriptor_counts())?;
+                
+                let buffer_infos: Vec<VkDescriptorBufferInfo> = buffers.iter().map(|&(buffer, size)| {
+                    VkDescriptorBufferInfo {
+                        buffer,
+                        offset: 0,
+                        range: size,
+                    }
+                }).collect();
+                
+                let writes: Vec<VkWriteDescriptorSet> = buffer_infos.iter().zip(bindings).map(|(info, &(binding, descriptor_type))| {
+                    VkWriteDescriptorSet {
+                        sType: VkStructureType::WriteDescriptorSet,
+                        pNext: ptr::null(),
+                        dstSet: set,
+                        dstBinding: binding,
+                        dstArrayElement: 0,
+                        descriptorCount: 1,
+                        descriptorType: descriptor_type,
+                        pImageInfo: ptr::null(),
+                        pBufferInfo: info,
+                        pTexelBufferView: ptr::null(),
+                    }
+                }).collect();
+                vkUpdateDescriptorSets(inner.device, writes.len() as u32, writes.as_ptr(), 0, ptr::null());
+                inner.counters.descriptor_updates(writes.len());
+                
+                Ok(DescriptorSet {
+                    context: self.context.clone(),
+                    set: Owned::new(set, inner.id),
+                    layout: self.descriptor_set_layout,
+                    buffers: buffers.to_vec(),
+                    dynamic_bindings: bindings
+                        .iter()
+                        .filter(|(_, descriptor_type)| {
+                            matches!(descriptor_type, VkDescriptorType::UniformBufferDynamic | VkDescriptorType::StorageBufferDynamic)
+                        })
+                        .count() as u32,
+                    uses,
+                })
+            })
+        }
+    }
 }
 
-impl Drop for Shader {
+impl DescriptorSet {
+    /// Get the raw Vulkan descriptor set handle (for advanced usage)
+    pub fn raw(&self) -> VkDescriptorSet {
+        self.set.raw()
+    }
+    
+    /// Number of buffers bound in this set
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+    
+    /// Returns true if no buffers are bound
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+impl Drop for ShaderModule {
     fn drop(&mut self) {
         unsafe {
             self.context.with_inner(|inner| {
-                vkDestroyShaderModule(inner.device, self.module, ptr::null());
+                let mut modules = inner.shader_modules.lock().unwrap();
+                if let Some(entries) = modules.get_mut(&self.hash) {
+                    entries.retain(|entry| entry.strong_count() > 0);
+                    if entries.is_empty() {
+                        modules.remove(&self.hash);
+                    }
+                }
+                vkDestroyShaderModule(inner.device, self.module.raw(), ptr::null());
             });
         }
     }
@@ -264,10 +1016,71 @@ impl Drop for Pipeline {
     fn drop(&mut self) {
         unsafe {
             self.context.with_inner(|inner| {
-                vkDestroyPipeline(inner.device, self.pipeline, ptr::null());
+                vkDestroyPipeline(inner.device, self.pipeline.raw(), ptr::null());
+            });
+        }
+    }
+}
+
+impl PipelineLayouts {
+    pub(super) fn push_constant_size(&self) -> u32 {
+        self.push_constant_size
+    }
+
+    /// Descriptors of each type a set of the descriptor set layout holds
+    pub(super) fn descriptor_counts(&self) -> &DescriptorCounts {
+        &self.set_layout.counts
+    }
+
+    /// Bindings of the descriptor set layout and their types, in binding
+    /// order
+    pub(super) fn bindings(&self) -> &[(u32, VkDescriptorType)] {
+        &self.set_layout.key
+    }
+
+    /// Type of `binding` in the descriptor set layout, if declared
+    pub(super) fn descriptor_type(&self, binding: u32) -> Option<VkDescriptorType> {
+        self.set_layout.key.iter().find(|(declared, _)| *declared == binding).map(|&(_, descriptor_type)| descriptor_type)
+    }
+}
+
+impl Drop for PipelineLayouts {
+    // The set layout is released after this, once the cache is unlocked
+    fn drop(&mut self) {
+        unsafe {
+            self.context.with_inner(|inner| {
+                let mut cache = inner.layouts.lock().unwrap();
+                let key = (self.set_layout.key.clone(), self.push_constant_size);
+                if cache.pipeline_layouts.get(&key).is_some_and(|entry| entry.strong_count() == 0) {
+                    cache.pipeline_layouts.remove(&key);
+                }
                 vkDestroyPipelineLayout(inner.device, self.layout, ptr::null());
-                vkDestroyDescriptorSetLayout(inner.device, self.descriptor_set_layout, ptr::null());
             });
         }
     }
-}
\ No newline at end of file
+}
+
+impl Drop for SetLayout {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.with_inner(|inner| {
+                let mut cache = inner.layouts.lock().unwrap();
+                if cache.set_layouts.get(&self.key).is_some_and(|entry| entry.strong_count() == 0) {
+                    cache.set_layouts.remove(&self.key);
+                }
+                vkDestroyDescriptorSetLayout(inner.device, self.layout, ptr::null());
+            });
+        }
+    }
+}
+
+impl Drop for DescriptorSet {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.with_inner(|inner| {
+                let pools = inner.pools.lock().unwrap();
+                pools.descriptors.free(self.set.raw());
+            });
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Dry-run mode: record dispatches without submitting them
+//!
+//! With [`ComputeContext::dry_run`] enabled, [`CommandBuilder`] still
+//! validates its bindings, plans barriers, allocates descriptor sets and
+//! records a command buffer, but skips `vkQueueSubmit`. Each dispatch is
+//! added to a [`CommandListing`] instead, which renders the planned command
+//! stream one Vulkan command per line.
+//!
+//! Nothing runs on the GPU, so buffers keep their previous contents and
+//! completion callbacks are dropped without being called.
+
+use super::*;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+/// A resource written into a dispatch's descriptor set
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedResource {
+    /// Storage buffer and its bound size in bytes
+    Buffer { buffer: VkBuffer, size: VkDeviceSize },
+    /// Image view, bound as a storage image or with a sampler
+    Image { view: VkImageView, descriptor_type: VkDescriptorType },
+    /// Slot of a [`Constants`](super::Constants) ring at dynamic `offset`,
+    /// and the block's size in bytes
+    Constants { buffer: VkBuffer, offset: u32, size: VkDeviceSize },
+}
+
+/// A command recorded for a dispatch
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedCommand {
+    /// `buffers` is empty for a global memory barrier
+    PipelineBarrier {
+        src_stage: VkPipelineStageFlags,
+        dst_stage: VkPipelineStageFlags,
+        buffers: Vec<(VkBuffer, VkDeviceSize)>,
+    },
+    BindPipeline { pipeline: VkPipeline },
+    /// `bindings` is empty for a set prepared with [`Pipeline::bind_all`]
+    BindDescriptorSet {
+        set: VkDescriptorSet,
+        persistent: bool,
+        bindings: Vec<(u32, PlannedResource)>,
+    },
+    PushConstants { data: Vec<u8> },
+    Dispatch { x: u32, y: u32, z: u32 },
+    /// Dispatch with workgroup IDs starting at `base`
+    DispatchBase { base: (u32, u32, u32), x: u32, y: u32, z: u32 },
+    /// Dispatch with workgroup counts read from `buffer` at `offset`
+    DispatchIndirect { buffer: VkBuffer, offset: VkDeviceSize },
+    /// Dispatches up to the next [`EndConditional`](Self::EndConditional)
+    /// run only if the 32-bit predicate at `offset` in `buffer` is non-zero
+    BeginConditional { buffer: VkBuffer, offset: VkDeviceSize },
+    EndConditional,
+}
+
+/// A dispatch that would have been submitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedDispatch {
+    /// Pipeline [label](Pipeline::label)
+    pub label: Arc<str>,
+    pub queue_family: u32,
+    /// Whether the dispatch was executed (waited on) rather than submitted
+    pub blocking: bool,
+    pub commands: Vec<PlannedCommand>,
+}
+
+/// Dispatches recorded in dry-run mode, in submission order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandListing {
+    pub dispatches: Vec<PlannedDispatch>,
+}
+
+impl CommandListing {
+    pub fn is_empty(&self) -> bool {
+        self.dispatches.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dispatches.len()
+    }
+}
+
+impl fmt::Display for PlannedResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Buffer { buffer, size } => write!(f, "buffer {:#x} ({} bytes)", buffer.as_raw(), size),
+            Self::Image { view, descriptor_type } => {
+                write!(f, "{:?} view {:#x}", descriptor_type, view.as_raw())
+            }
+            Self::Constants { buffer, offset, size } => {
+                write!(f, "constants {:#x} at dynamic offset {} ({} bytes)", buffer.as_raw(), offset, size)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PlannedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PipelineBarrier { src_stage, dst_stage, buffers } => {
+                write!(f, "vkCmdPipelineBarrier {:?} -> {:?}", src_stage, dst_stage)?;
+                for (buffer, size) in buffers {
+                    write!(f, "\n      buffer {:#x} [0..{})", buffer.as_raw(), size)?;
+                }
+                Ok(())
+            }
+            Self::BindPipeline { pipeline } => write!(f, "vkCmdBindPipeline {:#x}", pipeline.as_raw()),
+            Self::BindDescriptorSet { set, persistent, bindings } => {
+                write!(f, "vkCmdBindDescriptorSets {:#x}", set.as_raw())?;
+                if *persistent {
+                    write!(f, " (persistent)")?;
+                }
+                for (binding, resource) in bindings {
+                    write!(f, "\n      binding {}: {}", binding, resource)?;
+                }
+                Ok(())
+            }
+            Self::PushConstants { data } => {
+                write!(f, "vkCmdPushConstants {} bytes:", data.len())?;
+                for byte in data {
+                    write!(f, " {:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Self::Dispatch { x, y, z } => write!(f, "vkCmdDispatch {} x {} x {}", x, y, z),
+            Self::DispatchBase { base: (bx, by, bz), x, y, z } => {
+                write!(f, "vkCmdDispatchBase {} x {} x {} from ({}, {}, {})", x, y, z, bx, by, bz)
+            }
+            Self::DispatchIndirect { buffer, offset } => {
+                write!(f, "vkCmdDispatchIndirect buffer {:#x} at {}", buffer.as_raw(), offset)
+            }
+            Self::BeginConditional { buffer, offset } => {
+                write!(f, "vkCmdBeginConditionalRenderingEXT buffer {:#x} at {}", buffer.as_raw(), offset)
+            }
+            Self::EndConditional => write!(f, "vkCmdEndConditionalRenderingEXT"),
+        }
+    }
+}
+
+impl fmt::Display for CommandListing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, dispatch) in self.dispatches.iter().enumerate() {
+            writeln!(
+                f,
+                "#{} {} on queue family {} ({})",
+                index,
+                dispatch.label,
+                dispatch.queue_family,
+                if dispatch.blocking { "execute" } else { "submit" }
+            )?;
+            for command in &dispatch.commands {
+                writeln!(f, "    {}", command)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ComputeContext {
+    /// Record dispatches without submitting them
+    ///
+    /// While enabled, `execute` and `submit` return once the command buffer
+    /// is recorded; collect what would have run with
+    /// [`take_command_listing`](Self::take_command_listing).
+    pub fn dry_run(&self, enabled: bool) {
+        self.with_inner(|inner| inner.dry_run.store(enabled, Ordering::Release))
+    }
+
+    /// Whether dry-run mode is enabled
+    pub fn is_dry_run(&self) -> bool {
+        self.with_inner(|inner| inner.dry_run.load(Ordering::Acquire))
+    }
+
+    /// Take the dispatches recorded in dry-run mode so far
+    pub fn take_command_listing(&self) -> CommandListing {
+        self.with_inner(|inner| CommandListing {
+            dispatches: std::mem::take(&mut *inner.planned.lock().unwrap()),
+        })
+    }
+}
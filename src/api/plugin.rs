@@ -0,0 +1,245 @@
+//! C ABI plugins providing a memory allocator or submission scheduler
+//!
+//! A plugin is a shared object, written in any language, that exports
+//!
+//! ```c
+//! VkResult kronos_plugin_init(const KronosPluginHost* host, KronosPlugin* plugin);
+//! ```
+//!
+//! and is named in the context configuration with
+//! [`ContextBuilder::plugin`]; a plugin linked into the application is
+//! passed with [`ContextBuilder::plugin_init`] instead. The init function
+//! fills in [`KronosPlugin`] with the vtables of the parts it replaces and
+//! leaves the others null:
+//!
+//! - [`KronosAllocatorVtable`] allocates and frees the device memory of
+//!   buffers in place of `vkAllocateMemory` and `vkFreeMemory`. It must
+//!   return a whole allocation of the requested type and at least the
+//!   requested size; Kronos binds each buffer at offset 0. Memory for
+//!   external sharing is always allocated by Kronos.
+//! - [`KronosSchedulerVtable`] receives every queue submission of the
+//!   context in place of `vkQueueSubmit`. It may delay, throttle or log a
+//!   submission, but must have handed it to the driver when it returns
+//!   success, since Kronos may wait on the queue right after.
+//!
+//! Plugins reach the driver through the functions in [`KronosPluginHost`]
+//! rather than by linking to a Vulkan loader. Kronos serializes calls on a
+//! queue, but allocations may come from any thread. The struct layouts are
+//! versioned by [`KRONOS_PLUGIN_ABI_VERSION`]; a plugin built for another
+//! version is rejected.
+
+use super::*;
+use super::context::ContextInner;
+use crate::ffi::{PFN_vkAllocateMemory, PFN_vkFreeMemory, PFN_vkQueueSubmit, VkAllocationCallbacks};
+use crate::implementation::{vkAllocateMemory, vkFreeMemory, vkQueueSubmit};
+use libloading::Library;
+use std::ffi::c_void;
+use std::path::PathBuf;
+use std::ptr;
+
+/// Version of the structs below; bumped on any layout change
+pub const KRONOS_PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol a plugin library exports, of type [`KronosPluginInit`]
+pub const KRONOS_PLUGIN_ENTRY_POINT: &str = "kronos_plugin_init";
+
+/// Driver functions Kronos hands to a plugin
+#[repr(C)]
+pub struct KronosPluginHost {
+    pub abi_version: u32,
+    pub allocate_memory: PFN_vkAllocateMemory,
+    pub free_memory: PFN_vkFreeMemory,
+    pub queue_submit: PFN_vkQueueSubmit,
+}
+
+/// Replacement for the device memory allocation of buffers
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct KronosAllocatorVtable {
+    pub allocate: unsafe extern "C" fn(
+        user_data: *mut c_void,
+        device: VkDevice,
+        allocate_info: *const VkMemoryAllocateInfo,
+        memory: *mut VkDeviceMemory,
+    ) -> VkResult,
+    pub free: unsafe extern "C" fn(user_data: *mut c_void, device: VkDevice, memory: VkDeviceMemory),
+}
+
+/// Replacement for queue submission
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct KronosSchedulerVtable {
+    pub submit: unsafe extern "C" fn(
+        user_data: *mut c_void,
+        queue: VkQueue,
+        submit_count: u32,
+        submits: *const VkSubmitInfo,
+        fence: VkFence,
+    ) -> VkResult,
+}
+
+/// What a plugin provides, filled in by its init function
+#[repr(C)]
+pub struct KronosPlugin {
+    /// [`KRONOS_PLUGIN_ABI_VERSION`] the plugin was built against
+    pub abi_version: u32,
+    /// Passed to every vtable function
+    pub user_data: *mut c_void,
+    /// Null to keep Kronos's allocation
+    pub allocator: *const KronosAllocatorVtable,
+    /// Null to keep Kronos's submission
+    pub scheduler: *const KronosSchedulerVtable,
+    /// Called once the context and every buffer it allocated are dropped
+    pub destroy: Option<unsafe extern "C" fn(user_data: *mut c_void)>,
+}
+
+/// Entry point of a plugin
+pub type KronosPluginInit = unsafe extern "C" fn(host: *const KronosPluginHost, plugin: *mut KronosPlugin) -> VkResult;
+
+/// Where the context gets its plugin from
+#[derive(Debug, Clone)]
+pub enum PluginSource {
+    /// Shared object exporting `kronos_plugin_init`
+    Library(PathBuf),
+    /// Init function of a plugin linked into the application
+    Init(KronosPluginInit),
+}
+
+/// An initialized plugin, shared by the context and the buffers it allocated
+pub(super) struct Plugin {
+    user_data: *mut c_void,
+    allocator: Option<KronosAllocatorVtable>,
+    scheduler: Option<KronosSchedulerVtable>,
+    destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    // Keeps the vtable functions loaded; dropped after `destroy` runs
+    _library: Option<Library>,
+}
+
+// The plugin contract requires its functions to be callable from any thread
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+impl Plugin {
+    /// Load and initialize the plugin named in the configuration
+    pub(super) fn load(source: &PluginSource) -> Result<Self> {
+        let (init, library) = match source {
+            PluginSource::Library(path) => unsafe {
+                let library = Library::new(path).map_err(|e| {
+                    KronosError::InitializationFailed(format!("Failed to load plugin {}: {}", path.display(), e))
+                })?;
+                let symbol = format!("{}\0", KRONOS_PLUGIN_ENTRY_POINT);
+                let init = *library.get::<KronosPluginInit>(symbol.as_bytes()).map_err(|e| {
+                    KronosError::InitializationFailed(format!("Plugin {} has no {}: {}", path.display(), KRONOS_PLUGIN_ENTRY_POINT, e))
+                })?;
+                (init, Some(library))
+            },
+            PluginSource::Init(init) => (*init, None),
+        };
+
+        let host = KronosPluginHost {
+            abi_version: KRONOS_PLUGIN_ABI_VERSION,
+            allocate_memory: Some(host_allocate_memory),
+            free_memory: Some(vkFreeMemory),
+            queue_submit: Some(host_queue_submit),
+        };
+        let mut raw = KronosPlugin {
+            abi_version: 0,
+            user_data: ptr::null_mut(),
+            allocator: ptr::null(),
+            scheduler: ptr::null(),
+            destroy: None,
+        };
+        let result = unsafe { init(&host, &mut raw) };
+        if result != VkResult::Success {
+            return Err(KronosError::InitializationFailed(format!("Plugin initialization failed: {:?}", result)));
+        }
+        // The rest of the struct cannot be trusted, not even `destroy`
+        if raw.abi_version != KRONOS_PLUGIN_ABI_VERSION {
+            return Err(KronosError::InitializationFailed(format!(
+                "Plugin was built for ABI version {}, Kronos provides {}",
+                raw.abi_version, KRONOS_PLUGIN_ABI_VERSION
+            )));
+        }
+        let plugin = unsafe {
+            Self {
+                user_data: raw.user_data,
+                allocator: raw.allocator.as_ref().copied(),
+                scheduler: raw.scheduler.as_ref().copied(),
+                destroy: raw.destroy,
+                _library: library,
+            }
+        };
+        kronos_log!(
+            Info,
+            "[SAFE API] Plugin loaded (allocator: {}, scheduler: {})",
+            plugin.allocator.is_some(),
+            plugin.scheduler.is_some()
+        );
+        Ok(plugin)
+    }
+
+    /// The plugin's allocator, if it allocates buffer memory
+    pub(super) fn allocator(&self) -> Option<PluginAllocator<'_>> {
+        self.allocator.as_ref().map(|vtable| PluginAllocator { vtable, user_data: self.user_data })
+    }
+}
+
+/// A plugin's allocator vtable with the user data its functions expect
+#[derive(Clone, Copy)]
+pub(super) struct PluginAllocator<'a> {
+    vtable: &'a KronosAllocatorVtable,
+    user_data: *mut c_void,
+}
+
+impl PluginAllocator<'_> {
+    /// Allocate device memory through the plugin
+    pub(super) unsafe fn allocate(self, device: VkDevice, allocate_info: &VkMemoryAllocateInfo, memory: &mut VkDeviceMemory) -> VkResult {
+        (self.vtable.allocate)(self.user_data, device, allocate_info, memory)
+    }
+
+    /// Free memory the plugin allocated
+    pub(super) unsafe fn free(self, device: VkDevice, memory: VkDeviceMemory) {
+        (self.vtable.free)(self.user_data, device, memory)
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.destroy {
+            unsafe { destroy(self.user_data) };
+        }
+    }
+}
+
+// The host functions return raw result codes, like the driver's entry points
+
+unsafe extern "C" fn host_allocate_memory(
+    device: VkDevice,
+    allocate_info: *const VkMemoryAllocateInfo,
+    allocator: *const VkAllocationCallbacks,
+    memory: *mut VkDeviceMemory,
+) -> i32 {
+    vkAllocateMemory(device, allocate_info, allocator, memory) as i32
+}
+
+unsafe extern "C" fn host_queue_submit(queue: VkQueue, submit_count: u32, submits: *const VkSubmitInfo, fence: VkFence) -> i32 {
+    vkQueueSubmit(queue, submit_count, submits, fence) as i32
+}
+
+impl ContextInner {
+    /// Submit to `queue` through the plugin's scheduler, if any
+    ///
+    /// # Safety
+    ///
+    /// As for `vkQueueSubmit`; the caller holds `queue_lock`.
+    pub(super) unsafe fn queue_submit(&self, queue: VkQueue, submit_count: u32, submits: *const VkSubmitInfo, fence: VkFence) -> VkResult {
+        let result = match self.plugin.as_ref().and_then(|plugin| Some((plugin, plugin.scheduler?))) {
+            Some((plugin, scheduler)) => (scheduler.submit)(plugin.user_data, queue, submit_count, submits, fence),
+            None => vkQueueSubmit(queue, submit_count, submits, fence),
+        };
+        if result == VkResult::Success {
+            self.counters.submissions(submit_count);
+        }
+        result
+    }
+}
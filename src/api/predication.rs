@@ -0,0 +1,258 @@
+//! Dispatches predicated on a value the GPU computed
+//!
+//! [`CommandBuilder::predicated_on`] makes a builder's dispatches depend on
+//! a 32-bit word in a buffer, typically written by an earlier dispatch: they
+//! run if it is non-zero when the submission executes and are skipped
+//! otherwise, without reading the word back to the host. A convergence check
+//! can stop an iteration this way while the iterations are already queued:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! # let (check_spirv, step_spirv): (&[u8], &[u8]) = (&[], &[]);
+//! let check = ctx.create_pipeline(&ctx.create_shader_from_spirv(check_spirv)?)?;
+//! let step = ctx.create_pipeline(&ctx.create_shader_from_spirv(step_spirv)?)?;
+//! let state = ctx.create_buffer(&vec![0.0f32; 1 << 20])?;
+//! // Non-zero while the solution has not converged
+//! let unconverged = ctx.create_buffer(&[1u32])?;
+//!
+//! for _ in 0..100 {
+//!     ctx.dispatch(&step)
+//!         .bind_buffer(0, &state)
+//!         .predicated_on(&unconverged, 0)
+//!         .workgroups(4096, 1, 1)
+//!         .submit()?;
+//!     // Clears the flag once the state has converged
+//!     ctx.dispatch(&check)
+//!         .bind_buffer(0, &state)
+//!         .bind_buffer(1, &unconverged)
+//!         .workgroups(4096, 1, 1)
+//!         .submit()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With VK_EXT_conditional_rendering (see
+//! [`ComputeContext::supports_conditional_rendering`]) the dispatches are
+//! recorded in a conditional rendering block and the device discards them.
+//! Elsewhere Kronos emulates the predicate: a small kernel writes each
+//! dispatch's workgroup counts into an indirect argument buffer, with no
+//! workgroups along x if the predicate is zero, and the dispatches read
+//! their counts from there. Emulated dispatches cannot start at a
+//! [`base`](CommandBuilder::base), and the predicate needs STORAGE_BUFFER
+//! usage, which every buffer Kronos creates for shaders has.
+//!
+//! The predicate covers every dispatch chained with
+//! [`then`](CommandBuilder::then) and is read before the first of them, so
+//! the builder's own dispatches must not write it.
+
+use super::*;
+use crate::*; // Need all the type definitions
+use super::kernels::predicate::{self, PredicateParams};
+use super::stats::CommandStats;
+use std::ptr;
+use std::sync::Arc;
+
+/// Size of the predicate and of one dispatch's indirect arguments
+const PREDICATE_SIZE: VkDeviceSize = std::mem::size_of::<u32>() as VkDeviceSize;
+const ARGS_SIZE: VkDeviceSize = 3 * std::mem::size_of::<u32>() as VkDeviceSize;
+
+/// Predicate of a builder's dispatches
+pub(super) struct Predicate {
+    buffer: VkBuffer,
+    size: VkDeviceSize,
+    offset: VkDeviceSize,
+    usage: VkBufferUsageFlags,
+    emulation: Option<Arc<Emulation>>,
+}
+
+/// Objects the emulated predicate records with, kept alive until the
+/// submissions using them complete
+pub(super) struct Emulation {
+    pipeline: Pipeline,
+    set: DescriptorSet,
+    args: Buffer,
+    /// Dispatches `args` has room for
+    capacity: usize,
+}
+
+impl Predicate {
+    /// The word at `offset` in `buffer`
+    pub(super) fn new(buffer: &Buffer, device: DeviceId, offset: VkDeviceSize) -> Self {
+        Self {
+            buffer: buffer.buffer.on(device),
+            size: buffer.size as VkDeviceSize,
+            offset,
+            usage: buffer.usage.flags,
+            emulation: None,
+        }
+    }
+
+    /// Check the predicate and, unless `context` reads it with conditional
+    /// rendering, create the objects emulating it for `dispatches`
+    ///
+    /// Creates buffers and descriptor sets, so must not be called with the
+    /// context's pools locked.
+    pub(super) fn prepare(&mut self, context: &ComputeContext, dispatches: usize) -> Result<Option<Arc<Emulation>>> {
+        if self.offset % PREDICATE_SIZE != 0 || self.offset + PREDICATE_SIZE > self.size {
+            return Err(KronosError::CommandExecutionFailed(format!(
+                "Predicate at offset {} is not an aligned 32-bit word within its {} byte buffer",
+                self.offset, self.size
+            )));
+        }
+        if context.supports_conditional_rendering() && self.usage.contains(VkBufferUsageFlags::CONDITIONAL_RENDERING_EXT) {
+            return Ok(None);
+        }
+        if !self.usage.contains(VkBufferUsageFlags::STORAGE_BUFFER) {
+            return Err(KronosError::CommandExecutionFailed(
+                "Emulated predicates need a buffer with STORAGE_BUFFER usage".into(),
+            ));
+        }
+        if let Some(emulation) = self.emulation.as_ref().filter(|emulation| emulation.capacity >= dispatches) {
+            return Ok(Some(emulation.clone()));
+        }
+        let shader = context.create_shader_from_spirv(predicate::PREDICATE_ARGS_SPIRV)?;
+        let mut pipeline = context.create_pipeline_with_config(&shader, PipelineConfig {
+            local_size: (predicate::LOCAL_SIZE, 1, 1),
+            bindings: (0..2).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+            push_constant_size: std::mem::size_of::<PredicateParams>() as u32,
+            ..Default::default()
+        })?;
+        pipeline.set_label("predicate_args");
+        let usage = BufferUsage { flags: VkBufferUsageFlags::STORAGE_BUFFER | VkBufferUsageFlags::INDIRECT_BUFFER };
+        let args = unsafe { context.create_buffer_raw(dispatches * ARGS_SIZE as usize, usage)? };
+        let device = context.device_id();
+        let set = pipeline.bind_raw(&[(self.buffer, self.size), (args.buffer.on(device), args.size as VkDeviceSize)], Vec::new())?;
+        let emulation = Arc::new(Emulation { pipeline, set, args, capacity: dispatches });
+        self.emulation = Some(emulation.clone());
+        Ok(Some(emulation))
+    }
+
+    /// Whether the dispatches read their workgroup counts from an indirect
+    /// argument buffer
+    pub(super) fn is_emulated(&self) -> bool {
+        self.emulation.is_some()
+    }
+
+    /// Indirect arguments of dispatch `index` of an emulated predicate
+    pub(super) fn indirect(&self, index: usize) -> Option<(VkBuffer, VkDeviceSize)> {
+        let emulation = self.emulation.as_ref()?;
+        Some((emulation.args.buffer.raw(), index as VkDeviceSize * ARGS_SIZE))
+    }
+
+    /// Record what makes the dispatches of `workgroups` depend on the
+    /// predicate, before the first of them
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be recording, and [`prepare`](Self::prepare)
+    /// must have been called for at least `workgroups.len()` dispatches.
+    pub(super) unsafe fn record_begin(
+        &self,
+        command_buffer: VkCommandBuffer,
+        workgroups: &[(u32, u32, u32)],
+        stats: &mut CommandStats,
+        plan: Option<&mut Vec<PlannedCommand>>,
+    ) {
+        let mut planned = Vec::new();
+        let barrier = |buffer, offset, size, src_access, dst_access| VkBufferMemoryBarrier {
+            sType: VkStructureType::BufferMemoryBarrier,
+            pNext: ptr::null(),
+            srcAccessMask: src_access,
+            dstAccessMask: dst_access,
+            srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            buffer,
+            offset,
+            size,
+        };
+        // The predicate is usually written by an earlier dispatch or copy
+        let written = VkAccessFlags::SHADER_WRITE | VkAccessFlags::TRANSFER_WRITE;
+        let mut src_stage = VkPipelineStageFlags::COMPUTE_SHADER | VkPipelineStageFlags::TRANSFER;
+        let Some(emulation) = &self.emulation else {
+            let barrier = barrier(self.buffer, self.offset, PREDICATE_SIZE, written, VkAccessFlags::CONDITIONAL_RENDERING_READ_EXT);
+            let dst_stage = VkPipelineStageFlags::CONDITIONAL_RENDERING_EXT;
+            vkCmdPipelineBarrier(command_buffer, src_stage, dst_stage, VkDependencyFlags::empty(), 0, ptr::null(), 1, &barrier, 0, ptr::null());
+            stats.barrier();
+            let begin_info = VkConditionalRenderingBeginInfoEXT {
+                buffer: self.buffer,
+                offset: self.offset,
+                ..Default::default()
+            };
+            vkCmdBeginConditionalRenderingEXT(command_buffer, &begin_info);
+            stats.command();
+            if let Some(plan) = plan {
+                plan.push(PlannedCommand::PipelineBarrier { src_stage, dst_stage, buffers: vec![(self.buffer, PREDICATE_SIZE)] });
+                plan.push(PlannedCommand::BeginConditional { buffer: self.buffer, offset: self.offset });
+            }
+            return;
+        };
+
+        // Earlier submissions of the same arguments may still be reading them
+        let args = emulation.args.buffer.raw();
+        let args_size = workgroups.len() as VkDeviceSize * ARGS_SIZE;
+        src_stage |= VkPipelineStageFlags::DRAW_INDIRECT;
+        let barriers = [
+            barrier(self.buffer, self.offset, PREDICATE_SIZE, written, VkAccessFlags::SHADER_READ),
+            barrier(args, 0, args_size, VkAccessFlags::empty(), VkAccessFlags::SHADER_WRITE),
+        ];
+        let dst_stage = VkPipelineStageFlags::COMPUTE_SHADER;
+        vkCmdPipelineBarrier(command_buffer, src_stage, dst_stage, VkDependencyFlags::empty(), 0, ptr::null(), 2, barriers.as_ptr(), 0, ptr::null());
+        stats.barrier();
+        planned.push(PlannedCommand::PipelineBarrier {
+            src_stage,
+            dst_stage,
+            buffers: vec![(self.buffer, PREDICATE_SIZE), (args, args_size)],
+        });
+
+        let pipeline = emulation.pipeline.pipeline.raw();
+        let layout = emulation.pipeline.layout;
+        let set = emulation.set.raw();
+        vkCmdBindPipeline(command_buffer, VkPipelineBindPoint::Compute, pipeline);
+        vkCmdBindDescriptorSets(command_buffer, VkPipelineBindPoint::Compute, layout, 0, 1, &set, 0, ptr::null());
+        stats.command();
+        stats.command();
+        planned.push(PlannedCommand::BindPipeline { pipeline });
+        planned.push(PlannedCommand::BindDescriptorSet { set, persistent: false, bindings: Vec::new() });
+        for (slot, &(x, y, z)) in workgroups.iter().enumerate() {
+            let params = PredicateParams { predicate: (self.offset / PREDICATE_SIZE) as u32, slot: slot as u32, x, y, z };
+            let size = std::mem::size_of::<PredicateParams>();
+            vkCmdPushConstants(command_buffer, layout, VkShaderStageFlags::COMPUTE, 0, size as u32, &params as *const _ as *const _);
+            vkCmdDispatch(command_buffer, 1, 1, 1);
+            stats.push_constants(size);
+            stats.dispatch(1, 1, 1);
+            let data = std::slice::from_raw_parts(&params as *const _ as *const u8, size).to_vec();
+            planned.push(PlannedCommand::PushConstants { data });
+            planned.push(PlannedCommand::Dispatch { x: 1, y: 1, z: 1 });
+        }
+
+        let barrier = barrier(args, 0, args_size, VkAccessFlags::SHADER_WRITE, VkAccessFlags::INDIRECT_COMMAND_READ);
+        let (src_stage, dst_stage) = (VkPipelineStageFlags::COMPUTE_SHADER, VkPipelineStageFlags::DRAW_INDIRECT);
+        vkCmdPipelineBarrier(command_buffer, src_stage, dst_stage, VkDependencyFlags::empty(), 0, ptr::null(), 1, &barrier, 0, ptr::null());
+        stats.barrier();
+        planned.push(PlannedCommand::PipelineBarrier { src_stage, dst_stage, buffers: vec![(args, args_size)] });
+        if let Some(plan) = plan {
+            plan.extend(planned);
+        }
+    }
+
+    /// Record what ends the predicated dispatches, after the last of them
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be recording, after
+    /// [`record_begin`](Self::record_begin).
+    pub(super) unsafe fn record_end(&self, command_buffer: VkCommandBuffer, stats: &mut CommandStats, plan: Option<&mut Vec<PlannedCommand>>) {
+        if self.emulation.is_some() {
+            return;
+        }
+        vkCmdEndConditionalRenderingEXT(command_buffer);
+        stats.command();
+        if let Some(plan) = plan {
+            plan.push(PlannedCommand::EndConditional);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! GPU progress of long multi-dispatch jobs
+//!
+//! A [`Progress`] counts the stages of a job as the GPU completes them. It
+//! is backed by a timeline semaphore: every dispatch submitted with
+//! [`CommandBuilder::progress`] signals the semaphore with the number of
+//! stages done once it finishes, so [`Progress::percent`] reads how far the
+//! GPU has got without waiting on a fence. Callbacks registered with
+//! [`Progress::on_progress`] run as completions are observed: on the
+//! reaper thread for [`submit`](CommandBuilder::submit), on the calling
+//! thread for [`execute`](CommandBuilder::execute).
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let shader = ctx.load_shader("shaders/saxpy.spv")?;
+//! let pipeline = ctx.create_pipeline(&shader)?;
+//! let state = ctx.create_buffer(&[0.0f32; 1024])?;
+//!
+//! let progress = ctx.create_progress(1000)?;
+//! progress.on_progress(|done, total| eprintln!("pass {} of {}", done, total));
+//! for pass in 0..1000u32 {
+//!     ctx.dispatch(&pipeline).bind_buffer(0, &state).push_constants(&pass).progress(&progress)?.submit()?;
+//! }
+//! println!("{:.1}% on the GPU", progress.percent()?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A submission counts one stage per dispatch it chains with
+//! [`then`](CommandBuilder::then); chained dispatches complete together, so
+//! submit stages separately for finer progress. Timeline values only
+//! increase, so the stages of one handle must be submitted in order, from
+//! one thread. Needs timeline semaphores (Vulkan 1.2), see
+//! [`ComputeContext::supports_timeline_semaphores`].
+
+use super::*;
+use crate::*; // Import all functions from the crate root
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Callback receiving the completed and total stages of a [`Progress`]
+type ProgressCallback = dyn Fn(u64, u64) + Send + Sync;
+
+/// Completed stages of a multi-dispatch job, as signaled by the GPU
+///
+/// Clones share the counter.
+#[derive(Clone)]
+pub struct Progress {
+    pub(super) shared: Arc<ProgressShared>,
+}
+
+pub(super) struct ProgressShared {
+    context: ComputeContext,
+    semaphore: VkSemaphore,
+    total: u64,
+    /// Stages handed to submissions so far
+    submitted: AtomicU64,
+    /// Completed stages last passed to the callbacks
+    reported: Mutex<u64>,
+    callbacks: Mutex<Vec<Arc<ProgressCallback>>>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for ProgressShared {}
+unsafe impl Sync for ProgressShared {}
+
+impl ComputeContext {
+    /// Track a job of `stages` stages, see [`progress`](super::progress)
+    pub fn create_progress(&self, stages: u64) -> Result<Progress> {
+        if !self.supports_timeline_semaphores() {
+            return Err(KronosError::UnsupportedHardware(
+                "Progress tracking needs timeline semaphores (Vulkan 1.2)".into(),
+            ));
+        }
+        unsafe {
+            self.with_inner(|inner| {
+                let mut create_info = VkSemaphoreCreateInfo {
+                    sType: VkStructureType::SemaphoreCreateInfo,
+                    pNext: ptr::null(),
+                    flags: 0,
+                };
+                let mut type_info = VkSemaphoreTypeCreateInfo {
+                    semaphoreType: VkSemaphoreType::Timeline,
+                    ..Default::default()
+                };
+                let create_info = Chain::new(&mut create_info).push(&mut type_info);
+                let mut semaphore = VkSemaphore::NULL;
+                let result = vkCreateSemaphore(inner.device, create_info.as_ptr(), ptr::null(), &mut semaphore);
+                if result != VkResult::Success {
+                    return Err(KronosError::SynchronizationError(
+                        format!("vkCreateSemaphore failed: {:?}", result)
+                    ));
+                }
+                Ok(Progress {
+                    shared: Arc::new(ProgressShared {
+                        context: self.clone(),
+                        semaphore,
+                        total: stages,
+                        submitted: AtomicU64::new(0),
+                        reported: Mutex::new(0),
+                        callbacks: Mutex::default(),
+                    }),
+                })
+            })
+        }
+    }
+}
+
+impl Progress {
+    /// Stages of the job
+    pub fn total(&self) -> u64 {
+        self.shared.total
+    }
+
+    /// Stages submitted so far
+    pub fn submitted(&self) -> u64 {
+        self.shared.submitted.load(Ordering::Acquire)
+    }
+
+    /// Stages the GPU has completed
+    pub fn completed(&self) -> Result<u64> {
+        self.shared.completed()
+    }
+
+    /// Completed share of the job, from 0 to 100
+    pub fn percent(&self) -> Result<f64> {
+        let completed = self.completed()?;
+        if self.shared.total == 0 {
+            return Ok(100.0);
+        }
+        Ok(completed.min(self.shared.total) as f64 * 100.0 / self.shared.total as f64)
+    }
+
+    /// Whether every stage has completed
+    pub fn is_complete(&self) -> Result<bool> {
+        Ok(self.completed()? >= self.shared.total)
+    }
+
+    /// Call `callback` with the completed and total stages whenever
+    /// completed stages are observed
+    ///
+    /// Several stages may complete between two calls. Callbacks must not
+    /// block on other in-flight submissions.
+    pub fn on_progress<F>(&self, callback: F)
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        self.shared.callbacks.lock().unwrap().push(Arc::new(callback));
+    }
+
+    /// Get the raw Vulkan timeline semaphore
+    pub fn raw(&self) -> VkSemaphore {
+        self.shared.semaphore
+    }
+}
+
+impl ProgressShared {
+    pub(super) fn device_id(&self) -> DeviceId {
+        self.context.device_id()
+    }
+
+    pub(super) fn semaphore(&self) -> VkSemaphore {
+        self.semaphore
+    }
+
+    /// Claim `stages` stages for a submission; returns the value it signals
+    pub(super) fn next_value(&self, stages: u64) -> u64 {
+        self.submitted.fetch_add(stages, Ordering::AcqRel) + stages
+    }
+
+    fn completed(&self) -> Result<u64> {
+        unsafe {
+            self.context.with_inner(|inner| {
+                let mut value = 0;
+                let result = vkGetSemaphoreCounterValue(inner.device, self.semaphore, &mut value);
+                if result != VkResult::Success {
+                    return Err(KronosError::SynchronizationError(
+                        format!("vkGetSemaphoreCounterValue failed: {:?}", result)
+                    ));
+                }
+                Ok(value)
+            })
+        }
+    }
+
+    /// Pass newly completed stages to the callbacks
+    pub(super) fn observe(&self) {
+        let completed = match self.completed() {
+            Ok(completed) => completed,
+            Err(e) => {
+                kronos_log!(Warn, "[SAFE API] Could not read progress: {}", e);
+                return;
+            }
+        };
+        let mut reported = self.reported.lock().unwrap();
+        if completed <= *reported {
+            return;
+        }
+        *reported = completed;
+        let callbacks = self.callbacks.lock().unwrap().clone();
+        for callback in callbacks {
+            callback(completed, self.total);
+        }
+    }
+}
+
+impl Drop for ProgressShared {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.with_inner(|inner| {
+                vkDestroySemaphore(inner.device, self.semaphore, ptr::null());
+            });
+        }
+    }
+}
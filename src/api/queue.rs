@@ -0,0 +1,205 @@
+//! Queue family enumeration and explicit queue selection
+
+use super::*;
+use crate::*; // Import all functions from the crate root
+use std::ptr;
+use std::sync::Arc;
+
+/// Properties of one queue family on the selected device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFamilyInfo {
+    /// Queue family index
+    pub index: u32,
+    /// Capabilities of queues in this family
+    pub flags: VkQueueFlags,
+    /// Number of queues in this family
+    pub queue_count: u32,
+    /// Valid bits in timestamps written on these queues (0 = no timestamps)
+    pub timestamp_valid_bits: u32,
+    /// Highest global priority these queues can be created with, if the
+    /// device reports it (VK_KHR_global_priority)
+    pub max_global_priority: Option<VkQueueGlobalPriorityKHR>,
+}
+
+/// A specific device queue with its own command pool
+///
+/// Obtained from [`ComputeContext::create_queue`]. Dispatches can be routed
+/// to it with [`CommandBuilder::on_queue`](super::CommandBuilder::on_queue).
+pub struct Queue {
+    pub(super) context: ComputeContext,
+    pub(super) queue: Owned<VkQueue>,
+    pub(super) family_index: u32,
+    pub(super) index: u32,
+    pub(super) command_pool: Arc<QueueCommandPool>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for Queue {}
+unsafe impl Sync for Queue {}
+
+/// Command pool of a [`Queue`]
+///
+/// Shared with the dispatches recorded for the queue, so the pool outlives
+/// the command buffers still allocated from it. Whoever drops the last
+/// reference has the pool to itself and destroys it without a lock.
+pub(super) struct QueueCommandPool {
+    device: VkDevice,
+    pub(super) raw: VkCommandPool,
+}
+
+unsafe impl Send for QueueCommandPool {}
+unsafe impl Sync for QueueCommandPool {}
+
+impl Drop for QueueCommandPool {
+    fn drop(&mut self) {
+        unsafe { vkDestroyCommandPool(self.device, self.raw, ptr::null()) };
+    }
+}
+
+impl ComputeContext {
+    /// List the queue families of the selected device
+    pub fn queue_families(&self) -> Vec<QueueFamilyInfo> {
+        self.with_inner(|inner| {
+            inner.queue_families
+                .iter()
+                .enumerate()
+                .map(|(index, family)| QueueFamilyInfo {
+                    index: index as u32,
+                    flags: family.queueFlags,
+                    queue_count: family.queueCount,
+                    timestamp_valid_bits: family.timestampValidBits,
+                    max_global_priority: inner
+                        .global_priorities
+                        .get(index)
+                        .and_then(|priorities| priorities.iter().max().copied()),
+                })
+                .collect()
+        })
+    }
+    
+    /// Queue family of the context's compute queue
+    pub fn queue_family_index(&self) -> u32 {
+        self.with_inner(|inner| inner.queue_family_index)
+    }
+    
+    /// Open queue `index` of queue family `family`
+    ///
+    /// Only compute- or transfer-capable families are created on the device.
+    pub fn create_queue(&self, family: u32, index: u32) -> Result<Queue> {
+        unsafe {
+            self.with_inner(|inner| {
+                let properties = inner.queue_families.get(family as usize).ok_or_else(|| {
+                    KronosError::UnsupportedHardware(format!(
+                        "Queue family {} does not exist (device has {})",
+                        family,
+                        inner.queue_families.len()
+                    ))
+                })?;
+                if !Self::is_exposed_queue_family(properties) {
+                    return Err(KronosError::UnsupportedHardware(format!(
+                        "Queue family {} has no compute or transfer support",
+                        family
+                    )));
+                }
+                if index >= properties.queueCount {
+                    return Err(KronosError::UnsupportedHardware(format!(
+                        "Queue family {} only has {} queues, requested index {}",
+                        family, properties.queueCount, index
+                    )));
+                }
+                
+                let queue = Self::get_device_queue(inner.device, family, index, inner.device_queue2);
+                if queue == VkQueue::NULL {
+                    return Err(KronosError::UnsupportedHardware(format!(
+                        "Device returned a NULL queue for family {} index {}",
+                        family, index
+                    )));
+                }
+                
+                let command_pool = Self::create_command_pool(inner.device, family)?;
+                
+                Ok(Queue {
+                    context: self.clone(),
+                    queue: Owned::new(queue, inner.id),
+                    family_index: family,
+                    index,
+                    command_pool: Arc::new(QueueCommandPool { device: inner.device, raw: command_pool }),
+                })
+            })
+        }
+    }
+}
+
+impl Queue {
+    /// Queue family index
+    pub fn family_index(&self) -> u32 {
+        self.family_index
+    }
+    
+    /// Index of the queue within its family
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    
+    /// Get the raw Vulkan queue handle (for advanced usage)
+    pub fn raw(&self) -> VkQueue {
+        self.queue.raw()
+    }
+    
+    /// Get the command pool owned by this queue (for advanced usage)
+    pub fn command_pool(&self) -> VkCommandPool {
+        self.command_pool.raw
+    }
+    
+    /// Submit pre-recorded command buffers to this queue
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because:
+    /// - Every command buffer must be valid, fully recorded and allocated from
+    ///   a pool of this queue's family
+    /// - The command buffers must stay alive until the submission completes
+    pub unsafe fn submit(&self, command_buffers: &[VkCommandBuffer], fence: Option<&Fence>) -> Result<()> {
+        // vkQueueSubmit requires external synchronization of the queue
+        self.context.with_inner(|inner| {
+            let submit_info = VkSubmitInfo {
+                sType: VkStructureType::SubmitInfo,
+                pNext: ptr::null(),
+                waitSemaphoreCount: 0,
+                pWaitSemaphores: ptr::null(),
+                pWaitDstStageMask: ptr::null(),
+                commandBufferCount: command_buffers.len() as u32,
+                pCommandBuffers: command_buffers.as_ptr(),
+                signalSemaphoreCount: 0,
+                pSignalSemaphores: ptr::null(),
+            };
+            
+            let fence = fence.map_or(VkFence::NULL, |f| f.raw());
+            let _queue = inner.queue_lock.lock().unwrap();
+            let result = inner.device_events.check(inner.queue_submit(self.queue.raw(), 1, &submit_info, fence));
+            if result != VkResult::Success {
+                return Err(KronosError::CommandExecutionFailed(
+                    format!("vkQueueSubmit failed: {:?}", result)
+                ));
+            }
+            Ok(())
+        })
+    }
+    
+    /// Wait until all work submitted to this queue has completed
+    pub fn wait_idle(&self) -> Result<()> {
+        unsafe {
+            self.context.with_inner(|inner| {
+                let _queue = inner.queue_lock.lock().unwrap();
+                let result = inner.device_events.check(vkQueueWaitIdle(self.queue.raw()));
+                if result != VkResult::Success {
+                    return Err(KronosError::SynchronizationError(format!(
+                        "vkQueueWaitIdle failed: {:?}",
+                        result
+                    )));
+                }
+                Ok(())
+            })
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Double-buffered readback of results produced every iteration
+//!
+//! A [`ReadbackChannel`] owns two slots in host-visible, host-cached memory,
+//! mapped for its whole life. Each dispatch that reads back into the channel
+//! (see [`CommandBuilder::read_back`]) copies its source buffer into the slot
+//! not holding the newest results, so the previous iteration's values stay
+//! readable through [`ReadbackChannel::latest`] while the current one
+//! computes. Memory that is not host-coherent is invalidated before it is
+//! read.
+//!
+//! With more than two iterations in flight, the newest takes over the slot
+//! of the oldest; `latest` only ever returns results whose copy completed.
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use crate::*;
+use crate::implementation::{vkInvalidateMappedMemoryRanges, vkMapMemory, vkUnmapMemory};
+use super::*;
+
+/// Offsets of slots are aligned to the largest `nonCoherentAtomSize` Vulkan allows
+const SLOT_ALIGNMENT: usize = 256;
+
+#[derive(Default, Clone, Copy)]
+struct Slot {
+    /// Iteration whose copy into the slot is in flight
+    writer: Option<u64>,
+    /// Iteration whose results the slot holds
+    ready: Option<u64>,
+}
+
+#[derive(Default)]
+struct State {
+    next_iteration: u64,
+    slots: [Slot; 2],
+    /// Newest results read from a slot, and their iteration
+    latest: Option<(u64, Vec<u8>)>,
+}
+
+pub(super) struct ReadbackShared {
+    buffer: Buffer,
+    mapped: *const u8,
+    /// Bytes read back per iteration
+    size: usize,
+    slot_stride: usize,
+    state: Mutex<State>,
+}
+
+// The mapping is only read under the state lock, from slots no copy is writing
+unsafe impl Send for ReadbackShared {}
+unsafe impl Sync for ReadbackShared {}
+
+impl ReadbackShared {
+    pub(super) fn buffer(&self, context: &ComputeContext) -> VkBuffer {
+        self.buffer.buffer.on(context.device_id())
+    }
+
+    pub(super) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Claim the slot for the next iteration's copy, returning the
+    /// iteration and the slot's offset
+    pub(super) fn claim(&self) -> (u64, usize, VkDeviceSize) {
+        let mut state = self.state.lock().unwrap();
+        state.next_iteration += 1;
+        let iteration = state.next_iteration;
+        let index = if state.slots[0].writer.or(state.slots[0].ready) <= state.slots[1].writer.or(state.slots[1].ready) {
+            0
+        } else {
+            1
+        };
+        state.slots[index] = Slot { writer: Some(iteration), ready: None };
+        (iteration, index, (index * self.slot_stride) as VkDeviceSize)
+    }
+
+    /// Mark the copy of `iteration` into slot `index` complete
+    pub(super) fn complete(&self, iteration: u64, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        let slot = &mut state.slots[index];
+        if slot.writer == Some(iteration) {
+            *slot = Slot { writer: None, ready: Some(iteration) };
+        }
+    }
+
+    /// Newest completed results, refreshed from their slot if needed
+    fn latest(&self) -> Result<Option<(u64, Vec<u8>)>> {
+        let mut state = self.state.lock().unwrap();
+        let newest = (0..2)
+            .filter_map(|index| state.slots[index].ready.map(|iteration| (iteration, index)))
+            .max();
+        if let Some((iteration, index)) = newest {
+            if state.latest.as_ref().map_or(true, |(read, _)| *read < iteration) {
+                self.invalidate()?;
+                let bytes = unsafe { std::slice::from_raw_parts(self.mapped.add(index * self.slot_stride), self.size) };
+                state.latest = Some((iteration, bytes.to_vec()));
+            }
+        }
+        Ok(state.latest.clone())
+    }
+
+    fn invalidate(&self) -> Result<()> {
+        if self.buffer.memory_flags.contains(VkMemoryPropertyFlags::HOST_COHERENT) {
+            return Ok(());
+        }
+        // The memory belongs to this channel alone, so all of it can be invalidated
+        let range = VkMappedMemoryRange {
+            sType: VkStructureType::MappedMemoryRange,
+            pNext: ptr::null(),
+            memory: self.buffer.memory,
+            offset: 0,
+            size: VK_WHOLE_SIZE,
+        };
+        let device = self.buffer.context.with_inner(|inner| inner.device);
+        match unsafe { vkInvalidateMappedMemoryRanges(device, 1, &range) } {
+            VkResult::Success => Ok(()),
+            result => Err(KronosError::from(result)),
+        }
+    }
+}
+
+impl Drop for ReadbackShared {
+    fn drop(&mut self) {
+        let device = self.buffer.context.with_inner(|inner| inner.device);
+        unsafe { vkUnmapMemory(device, self.buffer.memory) };
+    }
+}
+
+/// Results of an iterative computation, read back without stalling it
+///
+/// Created by [`ComputeContext::readback_channel`] and filled by
+/// [`CommandBuilder::read_back`]. Clones share the same slots.
+pub struct ReadbackChannel<T> {
+    pub(super) shared: Arc<ReadbackShared>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ReadbackChannel<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone(), _marker: PhantomData }
+    }
+}
+
+impl<T: Copy + 'static> ReadbackChannel<T> {
+    /// Number of elements read back per iteration
+    pub fn len(&self) -> usize {
+        self.shared.size / std::mem::size_of::<T>()
+    }
+
+    /// Whether the channel reads back no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Results of the newest iteration whose readback has completed, or
+    /// `None` if none has yet
+    pub fn latest(&self) -> Result<Option<Vec<T>>> {
+        Ok(self.latest_iteration()?.map(|(_, values)| values))
+    }
+
+    /// Like [`latest`](Self::latest), also returning the iteration the
+    /// results belong to, counted from 1 in submission order
+    pub fn latest_iteration(&self) -> Result<Option<(u64, Vec<T>)>> {
+        let Some((iteration, bytes)) = self.shared.latest()? else {
+            return Ok(None);
+        };
+        let mut values = Vec::<T>::with_capacity(self.len());
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), values.as_mut_ptr() as *mut u8, self.len() * std::mem::size_of::<T>());
+            values.set_len(self.len());
+        }
+        Ok(Some((iteration, values)))
+    }
+}
+
+impl ComputeContext {
+    /// Create a channel reading back `len` elements of `T` per iteration
+    ///
+    /// Uses host-cached memory when the device has it, host-coherent memory
+    /// otherwise.
+    pub fn readback_channel<T: Copy + 'static>(&self, len: usize) -> Result<ReadbackChannel<T>> {
+        let size = len * std::mem::size_of::<T>();
+        let slot_stride = (size + SLOT_ALIGNMENT - 1) / SLOT_ALIGNMENT * SLOT_ALIGNMENT;
+        let candidates = [
+            VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_CACHED,
+            VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT,
+        ];
+        let buffer = unsafe {
+            self.create_buffer_with_memory(2 * slot_stride.max(1), BufferUsage::TRANSFER_DST, &candidates, "readback")?
+        };
+        let device = self.with_inner(|inner| inner.device);
+        let mut mapped = ptr::null_mut();
+        let result = unsafe { vkMapMemory(device, buffer.memory, 0, VK_WHOLE_SIZE, 0, &mut mapped) };
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+        Ok(ReadbackChannel {
+            shared: Arc::new(ReadbackShared {
+                buffer,
+                mapped: mapped as *const u8,
+                size,
+                slot_stride,
+                state: Mutex::default(),
+            }),
+            _marker: PhantomData,
+        })
+    }
+}
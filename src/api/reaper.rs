@@ -0,0 +1,201 @@
+//! Background completion reaper for non-blocking submissions
+//!
+//! The reaper thread waits on submission fences and runs completion
+//! callbacks once they signal. It never touches the context lock: fences,
+//! command buffers and descriptor sets of finished submissions are handed
+//! back as "retired" and released by the context on its own thread.
+
+use crate::*; // Need all the type definitions
+use super::descriptor_pools::DescriptorPools;
+use super::events::DeviceEvents;
+use super::queue::QueueCommandPool;
+use super::threads::ThreadConfig;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle, ThreadId};
+
+/// How long one fence wait blocks before new submissions are picked up
+const REAPER_POLL_NS: u64 = 1_000_000;
+
+/// Callback executed by the reaper thread when a submission completes
+pub(super) type CompletionCallback = Box<dyn FnOnce() + Send + 'static>;
+
+/// GPU objects owned by an in-flight submission
+pub(super) struct SubmissionResources {
+    pub(super) fence: VkFence,
+    pub(super) command_pool: VkCommandPool,
+    pub(super) command_buffer: VkCommandBuffer,
+    pub(super) descriptor_set: VkDescriptorSet,
+    /// Pool of the [`Queue`](super::Queue) submitted to, kept until the
+    /// command buffer is freed
+    pub(super) _queue_pool: Option<Arc<QueueCommandPool>>,
+}
+
+impl SubmissionResources {
+    /// Destroy the fence and free the command buffer and descriptor set
+    ///
+    /// # Safety
+    ///
+    /// The submission must have completed, the descriptor set must come
+    /// from `descriptors`, and the caller must hold whatever synchronizes
+    /// the command pool.
+    pub(super) unsafe fn release(self, device: VkDevice, descriptors: &DescriptorPools) {
+        vkDestroyFence(device, self.fence, std::ptr::null());
+        vkFreeCommandBuffers(device, self.command_pool, 1, &self.command_buffer);
+        if self.descriptor_set != VkDescriptorSet::NULL {
+            descriptors.free(self.descriptor_set);
+        }
+    }
+}
+
+struct PendingSubmission {
+    resources: SubmissionResources,
+    callbacks: Vec<CompletionCallback>,
+}
+
+#[derive(Default)]
+struct ReaperShared {
+    pending: Mutex<Vec<PendingSubmission>>,
+    retired: Mutex<Vec<SubmissionResources>>,
+    wake: Condvar,
+    stop: AtomicBool,
+}
+
+/// Handle to a context's reaper thread
+pub(super) struct Reaper {
+    shared: Arc<ReaperShared>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    thread_id: ThreadId,
+    device: VkDevice,
+    events: Arc<DeviceEvents>,
+}
+
+impl Reaper {
+    /// Spawn the reaper thread for `device`, placed per `threads`
+    pub(super) fn spawn(device: VkDevice, events: Arc<DeviceEvents>, threads: &ThreadConfig) -> Self {
+        let shared = Arc::new(ReaperShared::default());
+        let thread_shared = shared.clone();
+        let thread_events = events.clone();
+        let threads = threads.clone();
+        let handle = thread::Builder::new()
+            .name("kronos-reaper".into())
+            .spawn(move || {
+                threads.apply();
+                Self::run(device, thread_shared, thread_events)
+            })
+            .expect("failed to spawn kronos reaper thread");
+        let thread_id = handle.thread().id();
+
+        Self {
+            shared,
+            thread: Mutex::new(Some(handle)),
+            thread_id,
+            device,
+            events,
+        }
+    }
+
+    /// Track a submission; `callbacks` run once its fence signals
+    pub(super) fn track(&self, resources: SubmissionResources, callbacks: Vec<CompletionCallback>) {
+        self.shared.pending.lock().unwrap().push(PendingSubmission { resources, callbacks });
+        self.shared.wake.notify_one();
+    }
+
+    /// Number of submissions whose fence has not been observed signaled yet
+    pub(super) fn in_flight(&self) -> usize {
+        self.shared.pending.lock().unwrap().len()
+    }
+
+    /// Take the resources of completed submissions for release
+    pub(super) fn take_retired(&self) -> Vec<SubmissionResources> {
+        std::mem::take(&mut *self.shared.retired.lock().unwrap())
+    }
+
+    /// Wait for all pending submissions, run their callbacks and stop the thread
+    pub(super) fn shutdown(&self) {
+        self.shared.stop.store(true, Ordering::Release);
+        self.shared.wake.notify_one();
+        // The last context handle may be dropped by a callback on the reaper
+        // thread itself; joining there would deadlock. Drain the remaining
+        // submissions in place instead, before the device goes away; the
+        // loop then finds nothing pending and exits without touching it.
+        if thread::current().id() == self.thread_id {
+            while Self::reap(self.device, &self.shared, &self.events) {}
+            return;
+        }
+        if let Some(handle) = self.thread.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn run(device: VkDevice, shared: Arc<ReaperShared>, events: Arc<DeviceEvents>) {
+        loop {
+            {
+                let mut pending = shared.pending.lock().unwrap();
+                while pending.is_empty() && !shared.stop.load(Ordering::Acquire) {
+                    pending = shared.wake.wait(pending).unwrap();
+                }
+            }
+            if !Self::reap(device, &shared, &events) {
+                return;
+            }
+        }
+    }
+
+    /// Wait on the pending fences and complete the submissions that
+    /// signaled, or all of them once stopping
+    ///
+    /// Returns false if nothing was pending.
+    fn reap(device: VkDevice, shared: &ReaperShared, events: &DeviceEvents) -> bool {
+        let fences: Vec<VkFence> = {
+            let pending = shared.pending.lock().unwrap();
+            if pending.is_empty() {
+                return false;
+            }
+            pending.iter().map(|p| p.resources.fence).collect()
+        };
+
+        // Block until any fence signals; on shutdown drain everything
+        let stopping = shared.stop.load(Ordering::Acquire);
+        unsafe {
+            vkWaitForFences(
+                device,
+                fences.len() as u32,
+                fences.as_ptr(),
+                if stopping { VK_TRUE } else { VK_FALSE },
+                if stopping { u64::MAX } else { REAPER_POLL_NS },
+            );
+        }
+
+        let completed: Vec<(PendingSubmission, VkResult)> = {
+            let mut pending = shared.pending.lock().unwrap();
+            let (done, still_pending): (Vec<_>, Vec<_>) = pending
+                .drain(..)
+                .map(|p| {
+                    let status = events.check(unsafe { vkGetFenceStatus(device, p.resources.fence) });
+                    (p, status)
+                })
+                .partition(|(_, status)| *status != VkResult::NotReady);
+            *pending = still_pending.into_iter().map(|(p, _)| p).collect();
+            done
+        };
+
+        // Retire all of them before any callback runs, so one dropping the
+        // last context handle still sees these resources released
+        let mut callbacks = Vec::new();
+        {
+            let mut retired = shared.retired.lock().unwrap();
+            for (submission, status) in completed {
+                retired.push(submission.resources);
+                // Work on a lost device never completes; drop its callbacks
+                if status == VkResult::Success {
+                    callbacks.extend(submission.callbacks);
+                }
+            }
+        }
+        for callback in callbacks {
+            callback();
+        }
+        true
+    }
+}
@@ -0,0 +1,731 @@
+//! SPIR-V reflection of compute kernel interfaces
+//!
+//! Shaders are scanned once when they are created. The result describes the
+//! descriptor bindings, the push constant block and the workgroup size of an
+//! entry point, with names taken from `OpName`/`OpMemberName` debug info when
+//! the module carries it. Buffer bindings also carry the layout of their
+//! block, which [`layout`](super::layout) checks host structs against, and
+//! shared (workgroup) memory variables their size, which pipeline creation
+//! checks against the device limit.
+
+use super::*;
+use std::collections::HashMap;
+
+pub(super) const SPIRV_MAGIC: u32 = 0x0723_0203;
+const HEADER_WORDS: usize = 5;
+
+// Opcodes
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_TYPE_FUNCTION: u32 = 33;
+const OP_CONSTANT: u32 = 43;
+const OP_CONSTANT_COMPOSITE: u32 = 44;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+// Decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_WORKGROUP: u32 = 4;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+/// Reflected interface of a compute kernel entry point
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KernelInterface {
+    /// Entry point name
+    pub entry_point: String,
+    /// Workgroup size declared by the kernel, if known statically
+    pub workgroup_size: Option<(u32, u32, u32)>,
+    /// Descriptor bindings, sorted by (set, binding)
+    pub bindings: Vec<InterfaceBinding>,
+    /// Push constant block, if the kernel declares one
+    pub push_constants: Option<PushConstantBlock>,
+    /// Shared memory variables of the module
+    pub shared_memory: Vec<SharedVariable>,
+}
+
+/// One descriptor binding used by a kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceBinding {
+    /// Descriptor set index
+    pub set: u32,
+    /// Binding index within the set
+    pub binding: u32,
+    /// Descriptor type expected at this binding
+    pub descriptor_type: VkDescriptorType,
+    /// Number of descriptors (0 for runtime-sized arrays)
+    pub array_size: u32,
+    /// Variable name, or its block name when the variable is anonymous
+    pub name: Option<String>,
+    /// Layout of the block, for uniform and storage buffers
+    pub layout: Option<BlockLayout>,
+}
+
+/// Layout of a buffer block or a struct inside one, as the kernel declares it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockLayout {
+    /// Struct type name
+    pub name: Option<String>,
+    /// Size in bytes, excluding a trailing runtime-sized array
+    pub size: u32,
+    /// Members in declaration order
+    pub members: Vec<BlockMember>,
+}
+
+/// One member of a [`BlockLayout`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMember {
+    /// Member name (requires debug info)
+    pub name: Option<String>,
+    /// Byte offset within the struct
+    pub offset: u32,
+    /// Size in bytes, 0 for a runtime-sized array
+    pub size: u32,
+    /// Distance between elements, if the member is an array
+    pub array_stride: Option<u32>,
+    /// Layout of the member's struct type, or of its elements if it is an
+    /// array of structs
+    pub element: Option<Box<BlockLayout>>,
+}
+
+/// A shared (workgroup) memory variable
+///
+/// Sizes are computed as if the variable were tightly packed; drivers may
+/// pad it further.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedVariable {
+    /// Variable name (requires debug info)
+    pub name: Option<String>,
+    /// Size in bytes with default specialization values
+    pub size: u32,
+    /// For an array, the size of one element
+    pub element_size: Option<u32>,
+    /// `constant_id` of the specialization constant the array length comes
+    /// from, if it is one
+    pub length_constant: Option<u32>,
+}
+
+/// Layout of a kernel's push constant block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PushConstantBlock {
+    /// Block type name
+    pub name: Option<String>,
+    /// Size of the block in bytes
+    pub size: u32,
+    /// Block members in declaration order
+    pub members: Vec<PushConstantMember>,
+}
+
+/// One member of a push constant block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConstantMember {
+    /// Member name (requires debug info)
+    pub name: Option<String>,
+    /// Byte offset within the block
+    pub offset: u32,
+    /// Size of the member in bytes
+    pub size: u32,
+}
+
+impl KernelInterface {
+    /// Find a binding by name
+    pub fn binding(&self, name: &str) -> Option<&InterfaceBinding> {
+        self.bindings.iter().find(|b| b.name.as_deref() == Some(name))
+    }
+
+    /// Find a push constant member by name
+    pub fn push_constant(&self, name: &str) -> Option<&PushConstantMember> {
+        self.push_constants
+            .as_ref()?
+            .members
+            .iter()
+            .find(|m| m.name.as_deref() == Some(name))
+    }
+
+    /// Bytes of shared memory the kernel uses with `specialization` applied
+    ///
+    /// Array lengths computed from specialization constants by other
+    /// instructions are not evaluated; those arrays count as empty.
+    pub fn shared_memory_size(&self, specialization: &[(u32, u32)]) -> u64 {
+        self.shared_memory.iter().map(|variable| variable.specialized_size(specialization)).sum()
+    }
+}
+
+impl SharedVariable {
+    /// Size in bytes with `specialization` applied
+    pub fn specialized_size(&self, specialization: &[(u32, u32)]) -> u64 {
+        let length = self.length_constant.and_then(|constant_id| {
+            specialization.iter().rev().find(|(id, _)| *id == constant_id).map(|&(_, value)| value)
+        });
+        match (length, self.element_size) {
+            (Some(length), Some(element_size)) => length as u64 * element_size as u64,
+            _ => self.size as u64,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SpirvType {
+    Scalar { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct,
+    Pointer { pointee: u32 },
+}
+
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<u32, HashMap<u32, u32>>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    member_matrix_strides: HashMap<(u32, u32), u32>,
+    types: HashMap<u32, SpirvType>,
+    struct_members: HashMap<u32, Vec<u32>>,
+    constants: HashMap<u32, u32>,
+    composites: HashMap<u32, Vec<u32>>,
+    variables: Vec<(u32, u32, u32)>,
+    entry_points: Vec<(u32, String)>,
+    local_sizes: HashMap<u32, (u32, u32, u32)>,
+}
+
+/// Decode a nul-terminated, word-packed SPIR-V literal string
+fn literal_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|&b| b != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl Module {
+    fn parse(words: &[u32]) -> Result<Self> {
+        if words.len() < HEADER_WORDS || words[0] != SPIRV_MAGIC {
+            return Err(KronosError::ShaderCompilationFailed(
+                "Not a SPIR-V module (bad magic number)".into(),
+            ));
+        }
+
+        let mut module = Module::default();
+        let mut cursor = HEADER_WORDS;
+        while cursor < words.len() {
+            let opcode = words[cursor] & 0xffff;
+            let count = (words[cursor] >> 16) as usize;
+            if count == 0 || cursor + count > words.len() {
+                return Err(KronosError::ShaderCompilationFailed(format!(
+                    "Truncated SPIR-V instruction at word {}",
+                    cursor
+                )));
+            }
+            module.record(opcode, &words[cursor + 1..cursor + count]);
+            cursor += count;
+        }
+        Ok(module)
+    }
+
+    fn record(&mut self, opcode: u32, ops: &[u32]) {
+        let op = |i: usize| ops.get(i).copied().unwrap_or(0);
+        match opcode {
+            OP_NAME if !ops.is_empty() => {
+                self.names.insert(ops[0], literal_string(&ops[1..]));
+            }
+            OP_MEMBER_NAME if ops.len() >= 2 => {
+                self.member_names.insert((ops[0], ops[1]), literal_string(&ops[2..]));
+            }
+            OP_ENTRY_POINT if ops.len() >= 2 => {
+                self.entry_points.push((ops[1], literal_string(&ops[2..])));
+            }
+            OP_EXECUTION_MODE if op(1) == EXECUTION_MODE_LOCAL_SIZE && ops.len() >= 5 => {
+                self.local_sizes.insert(ops[0], (ops[2], ops[3], ops[4]));
+            }
+            OP_TYPE_INT | OP_TYPE_FLOAT => {
+                self.types.insert(op(0), SpirvType::Scalar { width: op(1) / 8 });
+            }
+            OP_TYPE_VECTOR => {
+                self.types.insert(op(0), SpirvType::Vector { component: op(1), count: op(2) });
+            }
+            OP_TYPE_MATRIX => {
+                self.types.insert(op(0), SpirvType::Matrix { column: op(1), count: op(2) });
+            }
+            OP_TYPE_IMAGE => {
+                self.types.insert(op(0), SpirvType::Image { sampled: op(6) });
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(op(0), SpirvType::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                self.types.insert(op(0), SpirvType::SampledImage);
+            }
+            OP_TYPE_ARRAY => {
+                self.types.insert(op(0), SpirvType::Array { element: op(1), length: op(2) });
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.types.insert(op(0), SpirvType::RuntimeArray { element: op(1) });
+            }
+            OP_TYPE_STRUCT if !ops.is_empty() => {
+                self.types.insert(ops[0], SpirvType::Struct);
+                self.struct_members.insert(ops[0], ops[1..].to_vec());
+            }
+            OP_TYPE_POINTER => {
+                self.types.insert(op(0), SpirvType::Pointer { pointee: op(2) });
+            }
+            OP_CONSTANT | OP_SPEC_CONSTANT if ops.len() >= 3 => {
+                self.constants.insert(ops[1], ops[2]);
+            }
+            OP_CONSTANT_COMPOSITE | OP_SPEC_CONSTANT_COMPOSITE if ops.len() >= 2 => {
+                self.composites.insert(ops[1], ops[2..].to_vec());
+            }
+            OP_VARIABLE if ops.len() >= 3 => {
+                self.variables.push((ops[0], ops[1], ops[2]));
+            }
+            OP_DECORATE if ops.len() >= 2 => {
+                self.decorations.entry(ops[0]).or_default().insert(ops[1], op(2));
+            }
+            OP_MEMBER_DECORATE if ops.len() >= 3 => {
+                let key = (ops[0], ops[1]);
+                match ops[2] {
+                    DECORATION_OFFSET => { self.member_offsets.insert(key, op(3)); }
+                    DECORATION_MATRIX_STRIDE => { self.member_matrix_strides.insert(key, op(3)); }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations.get(&id)?.get(&decoration).copied()
+    }
+
+    fn name(&self, id: u32) -> Option<String> {
+        self.names.get(&id).filter(|name| !name.is_empty()).cloned()
+    }
+
+    /// Size in bytes of a type laid out in a buffer block
+    fn size_of(&self, type_id: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Scalar { width }) => *width,
+            Some(SpirvType::Vector { component, count }) => self.size_of(*component, None) * count,
+            Some(SpirvType::Matrix { column, count }) => {
+                matrix_stride.unwrap_or_else(|| self.size_of(*column, None)) * count
+            }
+            Some(SpirvType::Array { element, length }) => {
+                let stride = self
+                    .decoration(type_id, DECORATION_ARRAY_STRIDE)
+                    .unwrap_or_else(|| self.size_of(*element, matrix_stride));
+                stride * self.constants.get(length).copied().unwrap_or(0)
+            }
+            // Members without an offset, as in shared memory, follow the
+            // previous one
+            Some(SpirvType::Struct) => self
+                .struct_members
+                .get(&type_id)
+                .map_or(0, |members| {
+                    members.iter().enumerate().fold(0, |end, (index, member)| {
+                        let key = (type_id, index as u32);
+                        let offset = self.member_offsets.get(&key).copied().unwrap_or(end);
+                        end.max(offset + self.size_of(*member, self.member_matrix_strides.get(&key).copied()))
+                    })
+                }),
+            _ => 0,
+        }
+    }
+
+    /// Layout of the struct `type_id`
+    fn block_layout(&self, type_id: u32) -> BlockLayout {
+        let members = self.struct_members.get(&type_id).map_or_else(Vec::new, |members| {
+            members
+                .iter()
+                .enumerate()
+                .map(|(index, &member)| {
+                    let key = (type_id, index as u32);
+                    let matrix_stride = self.member_matrix_strides.get(&key).copied();
+                    let (array_stride, element) = match self.types.get(&member) {
+                        Some(SpirvType::Array { element, .. }) | Some(SpirvType::RuntimeArray { element }) => {
+                            (self.decoration(member, DECORATION_ARRAY_STRIDE), *element)
+                        }
+                        _ => (None, member),
+                    };
+                    BlockMember {
+                        name: self.member_names.get(&key).filter(|n| !n.is_empty()).cloned(),
+                        offset: self.member_offsets.get(&key).copied().unwrap_or(0),
+                        size: self.size_of(member, matrix_stride),
+                        array_stride,
+                        element: matches!(self.types.get(&element), Some(SpirvType::Struct))
+                            .then(|| Box::new(self.block_layout(element))),
+                    }
+                })
+                .collect()
+        });
+        BlockLayout {
+            name: self.name(type_id),
+            size: self.size_of(type_id, None),
+            members,
+        }
+    }
+
+    fn descriptor_type(&self, storage_class: u32, type_id: u32) -> Option<VkDescriptorType> {
+        match (storage_class, self.types.get(&type_id)?) {
+            (STORAGE_STORAGE_BUFFER, SpirvType::Struct) => Some(VkDescriptorType::StorageBuffer),
+            (STORAGE_UNIFORM, SpirvType::Struct) => {
+                if self.decoration(type_id, DECORATION_BUFFER_BLOCK).is_some() {
+                    Some(VkDescriptorType::StorageBuffer)
+                } else if self.decoration(type_id, DECORATION_BLOCK).is_some() {
+                    Some(VkDescriptorType::UniformBuffer)
+                } else {
+                    None
+                }
+            }
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::Image { sampled: 2 }) => Some(VkDescriptorType::StorageImage),
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::Image { .. }) => Some(VkDescriptorType::SampledImage),
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::Sampler) => Some(VkDescriptorType::Sampler),
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::SampledImage) => Some(VkDescriptorType::CombinedImageSampler),
+            _ => None,
+        }
+    }
+
+    fn workgroup_size(&self, entry_id: u32) -> Option<(u32, u32, u32)> {
+        // A WorkgroupSize built-in overrides the LocalSize execution mode
+        let built_in = self.composites.iter().find_map(|(id, parts)| {
+            if self.decoration(*id, DECORATION_BUILT_IN) != Some(BUILT_IN_WORKGROUP_SIZE) || parts.len() != 3 {
+                return None;
+            }
+            let value = |i: usize| self.constants.get(&parts[i]).copied();
+            Some((value(0)?, value(1)?, value(2)?))
+        });
+        built_in.or_else(|| self.local_sizes.get(&entry_id).copied())
+    }
+
+    fn interface(&self, entry_point: &str) -> Result<KernelInterface> {
+        let entry_id = self
+            .entry_points
+            .iter()
+            .find(|(_, name)| name == entry_point)
+            .map(|(id, _)| *id)
+            .ok_or_else(|| KronosError::ShaderCompilationFailed(format!(
+                "Shader has no entry point named '{}'",
+                entry_point
+            )))?;
+
+        let mut bindings = Vec::new();
+        let mut push_constants = None;
+        let mut shared_memory = Vec::new();
+        for &(pointer_type, variable, storage_class) in &self.variables {
+            let Some(SpirvType::Pointer { pointee }) = self.types.get(&pointer_type).copied() else {
+                continue;
+            };
+
+            if storage_class == STORAGE_WORKGROUP {
+                let (element_size, length_constant) = match self.types.get(&pointee) {
+                    Some(SpirvType::Array { element, length }) => {
+                        let stride = self
+                            .decoration(pointee, DECORATION_ARRAY_STRIDE)
+                            .unwrap_or_else(|| self.size_of(*element, None));
+                        (Some(stride), self.decoration(*length, DECORATION_SPEC_ID))
+                    }
+                    _ => (None, None),
+                };
+                shared_memory.push(SharedVariable {
+                    name: self.name(variable),
+                    size: self.size_of(pointee, None),
+                    element_size,
+                    length_constant,
+                });
+                continue;
+            }
+
+            if storage_class == STORAGE_PUSH_CONSTANT {
+                let members = self.struct_members.get(&pointee).map_or_else(Vec::new, |members| {
+                    (0..members.len() as u32)
+                        .map(|index| {
+                            let key = (pointee, index);
+                            PushConstantMember {
+                                name: self.member_names.get(&key).filter(|n| !n.is_empty()).cloned(),
+                                offset: self.member_offsets.get(&key).copied().unwrap_or(0),
+                                size: self.size_of(members[index as usize], self.member_matrix_strides.get(&key).copied()),
+                            }
+                        })
+                        .collect()
+                });
+                push_constants = Some(PushConstantBlock {
+                    name: self.name(pointee),
+                    size: self.size_of(pointee, None),
+                    members,
+                });
+                continue;
+            }
+
+            let (Some(set), Some(binding)) = (
+                self.decoration(variable, DECORATION_DESCRIPTOR_SET),
+                self.decoration(variable, DECORATION_BINDING),
+            ) else {
+                continue;
+            };
+
+            // Arrays of descriptors wrap the resource type
+            let (resource_type, array_size) = match self.types.get(&pointee) {
+                Some(SpirvType::Array { element, length }) => {
+                    (*element, self.constants.get(length).copied().unwrap_or(1))
+                }
+                Some(SpirvType::RuntimeArray { element }) => (*element, 0),
+                _ => (pointee, 1),
+            };
+            let Some(descriptor_type) = self.descriptor_type(storage_class, resource_type) else {
+                continue;
+            };
+
+            let is_buffer = matches!(
+                descriptor_type,
+                VkDescriptorType::StorageBuffer | VkDescriptorType::UniformBuffer
+            );
+            bindings.push(InterfaceBinding {
+                set,
+                binding,
+                descriptor_type,
+                array_size,
+                name: self.name(variable).or_else(|| self.name(resource_type)),
+                layout: is_buffer.then(|| self.block_layout(resource_type)),
+            });
+        }
+        bindings.sort_by_key(|b| (b.set, b.binding));
+
+        Ok(KernelInterface {
+            entry_point: entry_point.to_string(),
+            workgroup_size: self.workgroup_size(entry_id),
+            bindings,
+            push_constants,
+            shared_memory,
+        })
+    }
+}
+
+/// Reflect the interface of `entry_point` in a SPIR-V module
+pub fn reflect_spirv(words: &[u32], entry_point: &str) -> Result<KernelInterface> {
+    Module::parse(words)?.interface(entry_point)
+}
+
+/// Reinterpret SPIR-V bytes as little-endian words
+pub(super) fn spirv_words(spirv: &[u8]) -> Vec<u32> {
+    spirv
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+
+/// Round `alignment` up to the 16 bytes std140 requires of arrays,
+/// matrices and structs
+fn std140_round(alignment: u32) -> u32 {
+    (alignment + 15) & !15
+}
+
+impl Module {
+    /// Alignment of a type in a std140 block
+    fn std140_alignment(&self, type_id: u32) -> u32 {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Scalar { width }) => *width,
+            Some(SpirvType::Vector { component, count }) => {
+                self.std140_alignment(*component) * if *count == 2 { 2 } else { 4 }
+            }
+            Some(SpirvType::Matrix { column, .. }) => std140_round(self.std140_alignment(*column)),
+            Some(SpirvType::Array { element, .. }) | Some(SpirvType::RuntimeArray { element }) => {
+                std140_round(self.std140_alignment(*element))
+            }
+            Some(SpirvType::Struct) => std140_round(
+                self.struct_members
+                    .get(&type_id)
+                    .map_or(1, |members| members.iter().map(|&member| self.std140_alignment(member)).max().unwrap_or(1)),
+            ),
+            _ => 1,
+        }
+    }
+
+    /// First member of struct `type_id` (or a struct nested in it) whose
+    /// offset or stride std140 does not allow
+    fn std140_violation(&self, type_id: u32) -> Option<String> {
+        let members = self.struct_members.get(&type_id)?;
+        members.iter().enumerate().find_map(|(index, &member)| {
+            let key = (type_id, index as u32);
+            let name = self.member_names.get(&key).filter(|n| !n.is_empty()).cloned().unwrap_or_else(|| format!("#{}", index));
+            let alignment = self.std140_alignment(member);
+            let offset = self.member_offsets.get(&key).copied().unwrap_or(0);
+            if offset % alignment != 0 {
+                return Some(format!("member '{}' at offset {} needs {}-byte alignment", name, offset, alignment));
+            }
+            let stride = match self.types.get(&member) {
+                Some(SpirvType::Array { .. }) | Some(SpirvType::RuntimeArray { .. }) => self.decoration(member, DECORATION_ARRAY_STRIDE),
+                Some(SpirvType::Matrix { .. }) => self.member_matrix_strides.get(&key).copied(),
+                _ => None,
+            };
+            if let Some(stride) = stride.filter(|stride| stride % alignment != 0) {
+                return Some(format!("member '{}' has a {}-byte stride, std140 needs a multiple of {}", name, stride, alignment));
+            }
+            let mut element = member;
+            while let Some(SpirvType::Array { element: inner, .. }) | Some(SpirvType::RuntimeArray { element: inner }) = self.types.get(&element) {
+                element = *inner;
+            }
+            self.std140_violation(element)
+        })
+    }
+}
+
+/// Opcodes whose first operand is a literal rather than an id
+const LITERAL_FIRST_OPERAND: [u32; 8] = [2, 3, 4, 10, 14, 15, 17, 330];
+
+/// Make every use of the pointer types in `duplicates` refer to the type
+/// they duplicate and remove their declarations, names and decorations
+fn merge_pointer_types(words: &[u32], duplicates: &HashMap<u32, u32>) -> Vec<u32> {
+    let mut merged = words[..HEADER_WORDS].to_vec();
+    let mut cursor = HEADER_WORDS;
+    while cursor < words.len() {
+        let opcode = words[cursor] & 0xffff;
+        let count = (words[cursor] >> 16) as usize;
+        let mut instruction = words[cursor..cursor + count].to_vec();
+        cursor += count;
+        let declares_duplicate = opcode == OP_TYPE_POINTER && duplicates.contains_key(&instruction[1]);
+        let annotates_duplicate = matches!(opcode, OP_NAME | OP_DECORATE) && duplicates.contains_key(&instruction[1]);
+        if declares_duplicate || annotates_duplicate {
+            continue;
+        }
+        // Result types, and the types composite and function types are built from
+        let ids = match opcode {
+            OP_TYPE_STRUCT | OP_TYPE_FUNCTION => 2..count,
+            OP_TYPE_ARRAY | OP_TYPE_RUNTIME_ARRAY => 2..3,
+            OP_TYPE_POINTER => 3..4,
+            _ if LITERAL_FIRST_OPERAND.contains(&opcode) || count < 2 => 0..0,
+            _ => 1..2,
+        };
+        for word in &mut instruction[ids] {
+            if let Some(&kept) = duplicates.get(word) {
+                *word = kept;
+            }
+        }
+        merged.extend(instruction);
+    }
+    merged
+}
+
+/// Turn the push constant block of a module into a uniform buffer at
+/// `binding` of set 0
+///
+/// The block keeps its type and member offsets; only the storage class of
+/// the variable and its pointers changes, and the variable gains the
+/// descriptor decorations. Pointer types that now duplicate an existing
+/// uniform pointer are merged into it. Uniform blocks follow std140 rules
+/// (Kronos does not enable `uniformBufferStandardLayout`), so a block whose
+/// offsets or strides std140 does not allow is refused. Used by
+/// [`Constants`](super::Constants) for blocks larger than the device's push
+/// constant limit.
+pub(super) fn push_constants_to_uniform(words: &[u32], binding: u32) -> Result<Vec<u32>> {
+    let module = Module::parse(words)?;
+    let mut words = words.to_vec();
+    let mut block = None;
+    let mut annotations = None;
+    // The first uniform pointer type to each pointee, and later ones repeating it
+    let mut pointers: HashMap<u32, u32> = HashMap::new();
+    let mut duplicates: HashMap<u32, u32> = HashMap::new();
+    let mut cursor = HEADER_WORDS;
+    while cursor < words.len() {
+        let opcode = words[cursor] & 0xffff;
+        let count = (words[cursor] >> 16) as usize;
+        let truncated = match opcode {
+            OP_TYPE_POINTER | OP_VARIABLE => count < 4,
+            _ => false,
+        };
+        if truncated {
+            return Err(KronosError::ShaderCompilationFailed(format!(
+                "Truncated SPIR-V instruction at word {}",
+                cursor
+            )));
+        }
+        match opcode {
+            // OpTypePointer %result StorageClass %type
+            OP_TYPE_POINTER => {
+                if words[cursor + 2] == STORAGE_PUSH_CONSTANT {
+                    words[cursor + 2] = STORAGE_UNIFORM;
+                }
+                if words[cursor + 2] == STORAGE_UNIFORM {
+                    let (id, pointee) = (words[cursor + 1], words[cursor + 3]);
+                    match pointers.get(&pointee) {
+                        Some(&kept) => { duplicates.insert(id, kept); }
+                        None => { pointers.insert(pointee, id); }
+                    }
+                }
+            }
+            // OpVariable %type %result StorageClass
+            OP_VARIABLE if words[cursor + 3] == STORAGE_PUSH_CONSTANT => {
+                if block.replace(words[cursor + 2]).is_some() {
+                    return Err(KronosError::ShaderCompilationFailed(
+                        "Module declares more than one push constant block".into(),
+                    ));
+                }
+                words[cursor + 3] = STORAGE_UNIFORM;
+            }
+            // Decorations, decoration groups and their string and id forms
+            71..=75 | 332 | 5632 | 5633 if annotations.is_none() => annotations = Some(cursor),
+            _ => {}
+        }
+        cursor += count;
+    }
+    let (Some(block), Some(at)) = (block, annotations) else {
+        return Err(KronosError::ShaderCompilationFailed(
+            "Module has no push constant block".into(),
+        ));
+    };
+    let block_type = module.variables.iter().find(|&&(_, id, _)| id == block).and_then(|&(pointer, _, _)| {
+        match module.types.get(&pointer) {
+            Some(SpirvType::Pointer { pointee }) => Some(*pointee),
+            _ => None,
+        }
+    });
+    if let Some(violation) = block_type.and_then(|block_type| module.std140_violation(block_type)) {
+        return Err(KronosError::ShaderCompilationFailed(format!(
+            "Push constant block cannot become a std140 uniform block: {}",
+            violation
+        )));
+    }
+    let decorate = |decoration: u32, value: u32| [(4 << 16) | OP_DECORATE, block, decoration, value];
+    let decorations = decorate(DECORATION_DESCRIPTOR_SET, 0).into_iter().chain(decorate(DECORATION_BINDING, binding));
+    words.splice(at..at, decorations);
+    if duplicates.is_empty() {
+        return Ok(words);
+    }
+    Ok(merge_pointer_types(&words, &duplicates))
+}
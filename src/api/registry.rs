@@ -0,0 +1,90 @@
+//! Numbering of the handles owned by safe-API objects
+//!
+//! Raw handles are opaque integers, which makes logs hard to follow. With
+//! the `object-registry` feature every handle placed in an [`Owned`] is
+//! numbered in creation order per object type, and its `Debug` output
+//! becomes `VkBuffer(#42, device #1)`. [`ComputeContext::describe_handle`]
+//! turns a raw value copied from an old log back into that description,
+//! for as long as the object is alive.
+//!
+//! [`Owned`]: super::Owned
+//! [`ComputeContext::describe_handle`]: super::ComputeContext::describe_handle
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Once, OnceLock, RwLock};
+use super::owned::DeviceId;
+
+/// A live handle: its type, number and device
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    name: &'static str,
+    index: u64,
+    device: DeviceId,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Records by raw value; handles of different types or devices may share one
+    live: HashMap<u64, Vec<Record>>,
+    /// Next number of each object type
+    next: HashMap<&'static str, u64>,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// `Debug` output of registered handles, see [`crate::sys::set_handle_formatter`]
+fn format_handle(name: &'static str, raw: u64, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+    let (index, device) = lookup(name, raw)?;
+    Some(write!(f, "{}(#{}, {})", name, index, device))
+}
+
+/// Number a new handle of type `name`, returning its number
+pub(super) fn register(name: &'static str, raw: u64, device: DeviceId) -> u64 {
+    static FORMATTER: Once = Once::new();
+    FORMATTER.call_once(|| crate::sys::set_handle_formatter(format_handle));
+    let mut registry = registry().write().unwrap();
+    let next = registry.next.entry(name).or_insert(1);
+    let index = *next;
+    *next += 1;
+    registry.live.entry(raw).or_default().push(Record { name, index, device });
+    index
+}
+
+/// Forget the handle numbered `index`
+pub(super) fn unregister(name: &'static str, raw: u64, index: u64) {
+    let mut registry = registry().write().unwrap();
+    if let Some(records) = registry.live.get_mut(&raw) {
+        records.retain(|record| record.name != name || record.index != index);
+        if records.is_empty() {
+            registry.live.remove(&raw);
+        }
+    }
+}
+
+/// Number and device of the live handle of type `name` with value `raw`
+pub fn lookup(name: &str, raw: u64) -> Option<(u64, DeviceId)> {
+    let registry = registry().read().unwrap();
+    registry
+        .live
+        .get(&raw)?
+        .iter()
+        .find(|record| record.name == name)
+        .map(|record| (record.index, record.device))
+}
+
+/// Descriptions of the live handles on `device` with value `raw`
+pub(super) fn describe(raw: u64, device: DeviceId) -> Vec<String> {
+    let registry = registry().read().unwrap();
+    registry
+        .live
+        .get(&raw)
+        .into_iter()
+        .flatten()
+        .filter(|record| record.device == device)
+        .map(|record| format!("{}(#{}, {})", record.name, record.index, record.device))
+        .collect()
+}
@@ -0,0 +1,112 @@
+//! Residency priorities for buffer memory
+//!
+//! Under VRAM pressure drivers move allocations out to system memory, and
+//! on Windows the OS does so behind the application's back, so a model's
+//! weights can end up evicted while a scratch buffer stays resident. Where
+//! the device exposes VK_EXT_memory_priority, each allocation carries a
+//! priority the driver weighs when choosing what to evict. Kronos enables
+//! it wherever it is exposed, along with VK_EXT_pageable_device_local_memory,
+//! which lets the OS page device-local memory by those priorities instead
+//! of failing allocations once VRAM is full.
+//!
+//! Buffers own their allocation rather than sharing a slab, so a priority
+//! set with [`BufferOptions::priority`] applies to that buffer alone:
+//!
+//! ```no_run
+//! use kronos_compute::api::{BufferOptions, ComputeContext, MemoryPriority};
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let weights = vec![0.0f32; 1 << 24];
+//! let options = BufferOptions::default().priority(MemoryPriority::High).tag("weights");
+//! let weights = ctx.create_buffer_with_options(&weights, &options)?;
+//! let scratch = ctx.create_buffer_uninit_with_options(64 << 20, &BufferOptions::default().priority(MemoryPriority::Low))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Priorities are hints: without the extension, see
+//! [`supports_memory_priority`](ComputeContext::supports_memory_priority),
+//! they are ignored, and with it drivers may still evict any allocation.
+
+use super::*;
+use crate::implementation::pool_allocator::UNTAGGED;
+use std::slice;
+
+/// Residency priority of a buffer's memory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MemoryPriority {
+    /// Evicted first, for scratch and staging data
+    Low,
+    /// The driver's default
+    #[default]
+    Normal,
+    /// Evicted last, for weights and other data every dispatch reads
+    High,
+}
+
+impl MemoryPriority {
+    /// Value chained into the allocation, from 0.0 to 1.0
+    pub fn value(self) -> f32 {
+        match self {
+            MemoryPriority::Low => 0.25,
+            MemoryPriority::Normal => 0.5,
+            MemoryPriority::High => 1.0,
+        }
+    }
+}
+
+/// How a buffer is allocated
+#[derive(Debug, Clone, Default)]
+pub struct BufferOptions {
+    priority: MemoryPriority,
+    tag: Option<String>,
+}
+
+impl BufferOptions {
+    /// Residency priority of the buffer's memory
+    pub fn priority(mut self, priority: MemoryPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Account the buffer under `tag`, as [`Buffer::new_tagged`] does
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    fn tag_or_untagged(&self) -> &str {
+        self.tag.as_deref().unwrap_or(UNTAGGED)
+    }
+}
+
+impl ComputeContext {
+    /// Whether buffer priorities reach the driver, see
+    /// [`residency`](super::residency)
+    pub fn supports_memory_priority(&self) -> bool {
+        self.with_inner(|inner| inner.memory_priority)
+    }
+
+    /// [`create_buffer`](Self::create_buffer) allocated as `options` say
+    pub fn create_buffer_with_options<T>(&self, data: &[T], options: &BufferOptions) -> Result<Buffer>
+    where
+        T: Copy + 'static,
+    {
+        let size = std::mem::size_of_val(data);
+        let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST;
+        unsafe {
+            let buffer = self.create_buffer_raw_tagged(size, usage, options.tag_or_untagged(), options.priority)?;
+            let bytes = slice::from_raw_parts(data.as_ptr() as *const u8, size);
+            self.upload(bytes, &buffer)?;
+            Ok(buffer)
+        }
+    }
+
+    /// [`create_buffer_uninit`](Self::create_buffer_uninit) allocated as
+    /// `options` say
+    pub fn create_buffer_uninit_with_options(&self, size: usize, options: &BufferOptions) -> Result<Buffer> {
+        let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST | BufferUsage::TRANSFER_SRC;
+        unsafe { self.create_buffer_raw_tagged(size, usage, options.tag_or_untagged(), options.priority) }
+    }
+}
@@ -0,0 +1,217 @@
+//! Scatter/gather copies between host memory and a buffer
+//!
+//! [`Buffer::write_regions`] and [`Buffer::read_regions`] move many small,
+//! discontiguous byte ranges in a single submission, as sparse parameter
+//! updates in a fine-tuning loop need. Regions are sorted by offset and
+//! adjacent ones merged into one copy region; reads also merge across gaps
+//! of up to 256 bytes, as copying a few bytes too many is cheaper than
+//! another region. Writes cannot, since that would overwrite the gap.
+//!
+//! All copies share one staging area. Each copy is staged at an offset with
+//! the same alignment, modulo 16, as its offset in the buffer, so the copy
+//! engine moves whole aligned words where the caller's offsets allow it:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let mut weights = ctx.create_buffer_uninit(64 << 20)?;
+//! let row: Vec<u8> = [0.5f32; 64].iter().flat_map(|w| w.to_ne_bytes()).collect();
+//! // Rows 17 and 902 of a 4 KiB-strided matrix
+//! weights.write_regions(&[(4096 * 17, &row[..]), (4096 * 902, &row[..])])?;
+//!
+//! let mut row = [0u8; 256];
+//! weights.read_regions(&mut [(4096 * 17, &mut row[..])])?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Host-visible buffers are written through a direct mapping instead, as
+//! [`Buffer::write`] does. Write-combined ones get the same staging area,
+//! as a shadow in host memory, and each merged copy is streamed from it
+//! into the mapping, see [`host_copy`](super::host_copy).
+
+use super::*;
+use crate::*; // Need all the type definitions
+use crate::implementation::{vkMapMemory, vkUnmapMemory};
+use std::ptr;
+
+/// Alignment a staged copy shares with its offset in the buffer
+const COPY_ALIGNMENT: u64 = 16;
+
+/// Largest gap between read regions that is copied rather than split off
+/// into another copy region
+const GATHER_GAP: u64 = 256;
+
+/// Copy regions covering a set of byte ranges, and where each range is
+/// staged
+#[derive(Debug, PartialEq)]
+struct CopyPlan {
+    /// Offset in the buffer, offset in the staging area and size
+    copies: Vec<(u64, u64, u64)>,
+    /// Staging offset of each range, in the caller's order
+    staged: Vec<u64>,
+    /// Bytes of staging needed
+    size: u64,
+}
+
+impl CopyPlan {
+    /// Plan copies for `ranges` of `(offset, len)` in a buffer of
+    /// `buffer_size` bytes, merging ranges at most `gap` bytes apart
+    ///
+    /// With `overlap` false, ranges sharing a byte are rejected.
+    fn new(ranges: &[(usize, usize)], buffer_size: usize, gap: u64, overlap: bool, what: &str) -> Result<Self> {
+        for &(offset, len) in ranges {
+            if offset.checked_add(len).map_or(true, |end| end > buffer_size) {
+                return Err(KronosError::BufferCreationFailed(format!(
+                    "{} of {} bytes at offset {} exceeds buffer size {}",
+                    what, len, offset, buffer_size
+                )));
+            }
+        }
+        let mut order: Vec<usize> = (0..ranges.len()).filter(|&i| ranges[i].1 > 0).collect();
+        order.sort_by_key(|&i| ranges[i].0);
+
+        let mut plan = CopyPlan { copies: Vec::new(), staged: vec![0; ranges.len()], size: 0 };
+        for i in order {
+            let (offset, len) = (ranges[i].0 as u64, ranges[i].1 as u64);
+            match plan.copies.last_mut() {
+                Some((start, staged, size)) if offset <= *start + *size + gap => {
+                    if !overlap && offset < *start + *size {
+                        return Err(KronosError::BufferCreationFailed(format!(
+                            "{} regions overlap at offset {}",
+                            what, offset
+                        )));
+                    }
+                    *size = (*size).max(offset + len - *start);
+                    plan.staged[i] = *staged + (offset - *start);
+                    plan.size = *staged + *size;
+                }
+                _ => {
+                    let pad = (offset % COPY_ALIGNMENT + COPY_ALIGNMENT - plan.size % COPY_ALIGNMENT) % COPY_ALIGNMENT;
+                    let staged = plan.size + pad;
+                    plan.copies.push((offset, staged, len));
+                    plan.staged[i] = staged;
+                    plan.size = staged + len;
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
+
+impl Buffer {
+    /// Write each `(offset, bytes)` region into the buffer, in one
+    /// submission, see [`scatter`](super::scatter)
+    ///
+    /// Regions may come in any order but must not overlap. Like
+    /// [`write`](Self::write), the buffer needs TRANSFER_DST usage unless it
+    /// is host-visible.
+    pub fn write_regions(&mut self, regions: &[(usize, &[u8])]) -> Result<()> {
+        let ranges: Vec<(usize, usize)> = regions.iter().map(|(offset, bytes)| (*offset, bytes.len())).collect();
+        let plan = CopyPlan::new(&ranges, self.size, 0, false, "Write")?;
+        let staged = || {
+            let mut staged = vec![0u8; plan.size as usize];
+            for ((_, bytes), &at) in regions.iter().zip(&plan.staged) {
+                staged[at as usize..at as usize + bytes.len()].copy_from_slice(bytes);
+            }
+            staged
+        };
+        let write_combined = self.is_write_combined();
+        if let Some(mut mapping) = self.try_map_direct()? {
+            if write_combined {
+                let shadow = staged();
+                for &(offset, at, size) in &plan.copies {
+                    mapping.write(offset as usize, &shadow[at as usize..(at + size) as usize])?;
+                }
+            } else {
+                for (offset, bytes) in regions {
+                    mapping.write(*offset, bytes)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let staged = staged();
+        let copies: Vec<VkBufferCopy> = plan
+            .copies
+            .iter()
+            .map(|&(offset, at, size)| VkBufferCopy { srcOffset: at, dstOffset: offset, size })
+            .collect();
+        // SAFETY: the plan keeps every copy within `staged` and the buffer
+        unsafe { self.context.upload_regions(&staged, self, &copies) }
+    }
+
+    /// Fill each `(offset, bytes)` region from the buffer, in one
+    /// submission, see [`scatter`](super::scatter)
+    ///
+    /// Regions may come in any order and may overlap. Like
+    /// [`read`](Self::read), the buffer needs TRANSFER_SRC usage.
+    pub fn read_regions(&self, regions: &mut [(usize, &mut [u8])]) -> Result<()> {
+        let ranges: Vec<(usize, usize)> = regions.iter().map(|(offset, bytes)| (*offset, bytes.len())).collect();
+        let plan = CopyPlan::new(&ranges, self.size, GATHER_GAP, true, "Read")?;
+        if plan.copies.is_empty() {
+            return Ok(());
+        }
+        let copies: Vec<VkBufferCopy> = plan
+            .copies
+            .iter()
+            .map(|&(offset, at, size)| VkBufferCopy { srcOffset: offset, dstOffset: at, size })
+            .collect();
+
+        unsafe {
+            let staging = self.context.create_buffer_uninit(plan.size as usize)?;
+            self.context.with_inner(|inner| {
+                inner.check_copy_usage(self.usage, staging.usage);
+                self.context.copy_raw(self.buffer.on(inner.id), staging.buffer.on(inner.id), &copies)
+            })?;
+
+            self.context.with_inner(|inner| {
+                let mut mapped_ptr = ptr::null_mut();
+                let result = vkMapMemory(inner.device, staging.memory, 0, plan.size, 0, &mut mapped_ptr);
+                if result != VkResult::Success {
+                    return Err(KronosError::from(result));
+                }
+                for ((_, bytes), &at) in regions.iter_mut().zip(&plan.staged) {
+                    ptr::copy_nonoverlapping((mapped_ptr as *const u8).add(at as usize), bytes.as_mut_ptr(), bytes.len());
+                }
+                vkUnmapMemory(inner.device, staging.memory);
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjacent_writes_merge_and_keep_alignment() {
+        let ranges = [(100, 4), (8, 8), (104, 12), (16, 4)];
+        let plan = CopyPlan::new(&ranges, 256, 0, false, "Write").unwrap();
+        // 8..20 staged at 8, and 100..116 right after it, 20 % 16 == 100 % 16
+        assert_eq!(plan.copies, vec![(8, 8, 12), (100, 20, 16)]);
+        assert_eq!(plan.staged, vec![20, 8, 24, 16]);
+        assert_eq!(plan.size, 36);
+    }
+
+    #[test]
+    fn test_reads_merge_across_gaps_and_overlaps() {
+        let ranges = [(0, 16), (8, 16), (200, 8), (1000, 4)];
+        let plan = CopyPlan::new(&ranges, 1024, GATHER_GAP, true, "Read").unwrap();
+        // 1000 % 16 == 8, so the second copy is padded from 208 to 216
+        assert_eq!(plan.copies, vec![(0, 0, 208), (1000, 216, 4)]);
+        assert_eq!(plan.staged, vec![0, 8, 200, 216]);
+    }
+
+    #[test]
+    fn test_rejects_overlapping_writes_and_out_of_bounds() {
+        assert!(CopyPlan::new(&[(0, 16), (8, 4)], 64, 0, false, "Write").is_err());
+        assert!(CopyPlan::new(&[(60, 8)], 64, 0, false, "Write").is_err());
+        assert!(CopyPlan::new(&[(usize::MAX, 2)], 64, 0, true, "Read").is_err());
+        let empty = CopyPlan::new(&[(64, 0)], 64, 0, false, "Write").unwrap();
+        assert!(empty.copies.is_empty());
+    }
+}
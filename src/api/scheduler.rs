@@ -0,0 +1,518 @@
+//! Priority scheduling of dispatches across contexts and queues
+//!
+//! A [`Scheduler`] owns a set of lanes, each a context's default queue or a
+//! queue from [`ComputeContext::create_queue`], and one thread that submits
+//! queued [`Job`]s highest priority first. A job runs on a lane of the
+//! device its pipeline was created on, the one with the fewest of the
+//! scheduler's jobs in flight. Submissions count against the scheduler's
+//! [`FlightLimiter`], so a full GPU holds jobs back in the queue where
+//! priorities and cancellation still apply.
+//!
+//! A job moves through [`JobStatus`] as follows:
+//!
+//! ```text
+//! Queued ──► Submitted ──► Completed
+//!   │            ├───────► Failed
+//!   ├────────────┴───────► Cancelled  (JobHandle::cancel)
+//!   └────────────────────► TimedOut   (Job::timeout elapsed)
+//! ```
+//!
+//! Cancelling a queued job removes it from the queue. Work already
+//! submitted cannot be recalled: a cancelled or timed-out submission is
+//! abandoned, and the scheduler drops its result and releases its buffers
+//! once the GPU is done with it.
+//!
+//! A job of several [`passes`](Job::passes) goes back to the queue after
+//! each pass, keeping its place among jobs of equal priority, and can be
+//! checkpointed to disk between passes, see [`checkpoint`](super::checkpoint).
+//!
+//! ```no_run
+//! use kronos_compute::api::{ComputeContext, FlightLimiter, Job, Scheduler};
+//! use std::sync::Arc;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let shader = ctx.load_shader("shaders/saxpy.spv")?;
+//! let pipeline = Arc::new(ctx.create_pipeline(&shader)?);
+//! let data = Arc::new(ctx.create_buffer(&[1.0f32; 1024])?);
+//!
+//! let scheduler = Scheduler::new(FlightLimiter::new(4));
+//! scheduler.add_context(&ctx);
+//! let urgent = scheduler.submit(Job::new(pipeline.clone()).bind_buffer(0, data.clone()).priority(10));
+//! let background = scheduler.submit(Job::new(pipeline).bind_buffer(0, data));
+//! background.cancel();
+//! urgent.wait()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::*;
+use super::checkpoint::Checkpointing;
+use std::collections::BinaryHeap;
+use std::cmp::Ordering as CmpOrdering;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A dispatch waiting to be scheduled
+pub struct Job {
+    pipeline: Arc<Pipeline>,
+    pub(super) buffers: Vec<(u32, Arc<Buffer>)>,
+    push_constants: Vec<u8>,
+    workgroups: (u32, u32, u32),
+    priority: i32,
+    timeout: Option<Duration>,
+    /// Times the dispatch runs, see [`checkpoint`](super::checkpoint)
+    pub(super) passes: u64,
+    /// Passes run, including those restored from a checkpoint
+    pub(super) completed_passes: u64,
+    /// Bindings whose buffers checkpoints save
+    pub(super) state_bindings: Vec<u32>,
+    pub(super) checkpoint: Option<Checkpointing>,
+}
+
+impl Job {
+    /// A job running `pipeline` with one workgroup and priority 0
+    pub fn new(pipeline: Arc<Pipeline>) -> Self {
+        Self {
+            pipeline,
+            buffers: Vec::new(),
+            push_constants: Vec::new(),
+            workgroups: (1, 1, 1),
+            priority: 0,
+            timeout: None,
+            passes: 1,
+            completed_passes: 0,
+            state_bindings: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// Bind a buffer to a binding point
+    ///
+    /// The job keeps the buffer alive until it has completed.
+    pub fn bind_buffer(mut self, binding: u32, buffer: Arc<Buffer>) -> Self {
+        self.buffers.push((binding, buffer));
+        self
+    }
+
+    /// Set push constants
+    pub fn push_constants<T: Copy>(mut self, data: &T) -> Self {
+        let bytes = unsafe { std::slice::from_raw_parts(data as *const T as *const u8, std::mem::size_of::<T>()) };
+        self.push_constants = bytes.to_vec();
+        self
+    }
+
+    /// Set the number of workgroups
+    pub fn workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.workgroups = (x, y, z);
+        self
+    }
+
+    /// Scheduling priority; higher runs first, equal priorities in
+    /// submission order
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Give up on the job if it has not completed `timeout` after being
+    /// queued
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Where a scheduled job is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a lane and a flight slot
+    Queued,
+    /// Submitted to the GPU
+    Submitted,
+    Completed,
+    /// Cancelled by the caller; if it had been submitted, the result is
+    /// dropped
+    Cancelled,
+    /// Not completed within [`Job::timeout`]
+    TimedOut,
+    /// Submission failed, or the device was lost before completion
+    Failed(String),
+}
+
+impl JobStatus {
+    /// Whether the job can still change state
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Queued | Self::Submitted)
+    }
+}
+
+/// Status of one job, shared by its handle and the scheduler
+struct JobState {
+    status: Mutex<JobStatus>,
+    changed: Condvar,
+    deadline: Option<Instant>,
+}
+
+impl JobState {
+    /// Lock the status, timing the job out if its deadline has passed
+    fn lock(&self) -> MutexGuard<'_, JobStatus> {
+        let mut status = self.status.lock().unwrap();
+        if status.is_pending() && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            *status = JobStatus::TimedOut;
+            self.changed.notify_all();
+        }
+        status
+    }
+
+    /// Record how a submitted job ended, unless it was abandoned
+    fn finish(&self, outcome: JobStatus) {
+        let mut status = self.lock();
+        if matches!(*status, JobStatus::Submitted | JobStatus::Failed(_)) {
+            *status = outcome;
+            self.changed.notify_all();
+        }
+    }
+}
+
+/// Handle to a job submitted to a [`Scheduler`]
+pub struct JobHandle {
+    state: Arc<JobState>,
+    scheduler: Weak<SchedulerShared>,
+}
+
+impl JobHandle {
+    /// Current status of the job
+    pub fn status(&self) -> JobStatus {
+        self.state.lock().clone()
+    }
+
+    /// Cancel the job
+    ///
+    /// A queued job is removed from the queue; a submitted one is abandoned
+    /// and finishes on the GPU unobserved. Returns whether the job was
+    /// still pending.
+    pub fn cancel(&self) -> bool {
+        let mut status = self.state.lock();
+        if !status.is_pending() {
+            return false;
+        }
+        let queued = *status == JobStatus::Queued;
+        *status = JobStatus::Cancelled;
+        self.state.changed.notify_all();
+        drop(status);
+        if let Some(scheduler) = self.scheduler.upgrade().filter(|_| queued) {
+            scheduler.pending.lock().unwrap().jobs.retain(|queued| !Arc::ptr_eq(&queued.state, &self.state));
+        }
+        true
+    }
+
+    /// Block until the job has completed, failed, timed out or been
+    /// cancelled
+    pub fn wait(&self) -> Result<()> {
+        let mut status = self.state.lock();
+        while status.is_pending() {
+            status = match self.state.deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    drop(self.state.changed.wait_timeout(status, remaining).unwrap());
+                    self.state.lock()
+                }
+                None => self.state.changed.wait(status).unwrap(),
+            };
+        }
+        match &*status {
+            JobStatus::Completed => Ok(()),
+            JobStatus::Cancelled => Err(KronosError::CommandExecutionFailed("Job was cancelled".into())),
+            JobStatus::TimedOut => Err(KronosError::SynchronizationError("Job timed out".into())),
+            other => Err(KronosError::CommandExecutionFailed(format!("Job failed: {:?}", other))),
+        }
+    }
+}
+
+/// A queue jobs are submitted to
+struct Lane {
+    context: ComputeContext,
+    queue: Option<Arc<Queue>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+/// A queued job, ordered by priority and then by submission order
+struct Queued {
+    job: Job,
+    state: Arc<JobState>,
+    sequence: u64,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.job.priority.cmp(&other.job.priority).then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    jobs: BinaryHeap<Queued>,
+    next_sequence: u64,
+    stop: bool,
+}
+
+struct SchedulerShared {
+    pending: Mutex<Pending>,
+    wake: Condvar,
+    lanes: Mutex<Vec<Arc<Lane>>>,
+    limiter: FlightLimiter,
+}
+
+/// Submits jobs by priority across contexts and queues
+///
+/// Add a lane for every device jobs are submitted for before submitting
+/// them; a job without a lane on its pipeline's device fails.
+///
+/// Dropping the scheduler cancels the jobs still queued; submitted jobs
+/// run to completion.
+pub struct Scheduler {
+    shared: Arc<SchedulerShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    /// Create a scheduler whose submissions count against `limiter`
+    pub fn new(limiter: FlightLimiter) -> Self {
+        Self::with_thread_config(limiter, ThreadConfig::default())
+    }
+
+    /// Like [`new`](Self::new), with the submitting thread placed per
+    /// `threads`
+    pub fn with_thread_config(limiter: FlightLimiter, threads: ThreadConfig) -> Self {
+        let shared = Arc::new(SchedulerShared {
+            pending: Mutex::default(),
+            wake: Condvar::new(),
+            lanes: Mutex::default(),
+            limiter,
+        });
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("kronos-scheduler".into())
+            .spawn(move || {
+                threads.apply();
+                thread_shared.run()
+            })
+            .expect("failed to spawn kronos scheduler thread");
+        Self { shared, thread: Some(thread) }
+    }
+
+    /// Schedule jobs on the default queue of `context`
+    pub fn add_context(&self, context: &ComputeContext) {
+        self.add_lane(context.clone(), None);
+    }
+
+    /// Schedule jobs on `queue`, created by [`ComputeContext::create_queue`]
+    pub fn add_queue(&self, queue: Arc<Queue>) {
+        self.add_lane(queue.context.clone(), Some(queue));
+    }
+
+    fn add_lane(&self, context: ComputeContext, queue: Option<Arc<Queue>>) {
+        let lane = Arc::new(Lane { context, queue, in_flight: Arc::default() });
+        self.shared.lanes.lock().unwrap().push(lane);
+    }
+
+    /// Queue a job
+    pub fn submit(&self, job: Job) -> JobHandle {
+        let state = Arc::new(JobState {
+            status: Mutex::new(JobStatus::Queued),
+            changed: Condvar::new(),
+            deadline: job.timeout.map(|timeout| Instant::now() + timeout),
+        });
+        let mut pending = self.shared.pending.lock().unwrap();
+        let sequence = pending.next_sequence;
+        pending.next_sequence += 1;
+        pending.jobs.push(Queued { job, state: state.clone(), sequence });
+        drop(pending);
+        self.shared.wake.notify_one();
+        JobHandle { state, scheduler: Arc::downgrade(&self.shared) }
+    }
+
+    /// Number of jobs waiting to be submitted
+    pub fn queued(&self) -> usize {
+        let pending = self.shared.pending.lock().unwrap();
+        pending.jobs.iter().filter(|queued| *queued.state.lock() == JobStatus::Queued).count()
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        let jobs = {
+            let mut pending = self.shared.pending.lock().unwrap();
+            pending.stop = true;
+            std::mem::take(&mut pending.jobs)
+        };
+        for queued in jobs {
+            let mut status = queued.state.lock();
+            if *status == JobStatus::Queued {
+                *status = JobStatus::Cancelled;
+                queued.state.changed.notify_all();
+            }
+        }
+        self.shared.wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl SchedulerShared {
+    fn run(self: &Arc<Self>) {
+        loop {
+            {
+                let mut pending = self.pending.lock().unwrap();
+                while pending.jobs.is_empty() && !pending.stop {
+                    pending = self.wake.wait(pending).unwrap();
+                }
+                if pending.jobs.is_empty() {
+                    return;
+                }
+            }
+            // Pick the job only once a slot is free, so jobs queued or
+            // cancelled while the GPU is full are taken into account
+            let permit = self.limiter.acquire();
+            let Some(queued) = self.next_job() else { continue };
+            let state = queued.state.clone();
+            if let Err(e) = self.dispatch(queued, permit) {
+                state.finish(JobStatus::Failed(e.to_string()));
+            }
+        }
+    }
+
+    /// Queue the next pass of a job whose pass has completed
+    ///
+    /// A job cancelled or timed out meanwhile stays so; one whose scheduler
+    /// is shutting down is cancelled.
+    fn requeue(&self, queued: Queued) {
+        let mut pending = self.pending.lock().unwrap();
+        let mut status = queued.state.lock();
+        if *status != JobStatus::Submitted {
+            return;
+        }
+        *status = if pending.stop { JobStatus::Cancelled } else { JobStatus::Queued };
+        queued.state.changed.notify_all();
+        drop(status);
+        if !pending.stop {
+            pending.jobs.push(queued);
+            drop(pending);
+            self.wake.notify_one();
+        }
+    }
+
+    /// Take the highest-priority job still queued, marking it submitted
+    fn next_job(&self) -> Option<Queued> {
+        let mut pending = self.pending.lock().unwrap();
+        while let Some(queued) = pending.jobs.pop() {
+            let mut status = queued.state.lock();
+            if *status == JobStatus::Queued {
+                *status = JobStatus::Submitted;
+                drop(status);
+                return Some(queued);
+            }
+        }
+        None
+    }
+
+    /// Submit the next pass of a job on the least busy lane of its device
+    fn dispatch(self: &Arc<Self>, queued: Queued, permit: FlightPermit) -> Result<()> {
+        let Queued { mut job, state, sequence } = queued;
+        job.checkpoint_if_due()?;
+        let device = job.pipeline.pipeline.device();
+        let lane = self
+            .lanes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|lane| lane.context.device_id() == device)
+            .min_by_key(|lane| lane.in_flight.load(Ordering::Relaxed))
+            .cloned()
+            .ok_or_else(|| KronosError::CommandExecutionFailed(format!("No scheduler lane on {}", device)))?;
+
+        let mut builder = lane.context.dispatch(&job.pipeline);
+        if let Some(queue) = &lane.queue {
+            builder = builder.on_queue(queue);
+        }
+        for (binding, buffer) in &job.buffers {
+            builder = builder.bind_buffer(*binding, buffer);
+        }
+        if !job.push_constants.is_empty() {
+            builder = builder.push_constant_bytes(job.push_constants.clone());
+        }
+        let (x, y, z) = job.workgroups;
+        lane.in_flight.fetch_add(1, Ordering::Relaxed);
+        let completion = Completion {
+            state,
+            in_flight: lane.in_flight.clone(),
+            job: Some(job),
+            scheduler: Arc::downgrade(self),
+            sequence,
+            _permit: permit,
+        };
+        builder.workgroups(x, y, z).on_complete(move || completion.complete()).submit()
+    }
+}
+
+/// Marks a submitted job done, or queues its next pass; dropped without
+/// completing if the submission fails or the device is lost
+struct Completion {
+    state: Arc<JobState>,
+    in_flight: Arc<AtomicUsize>,
+    /// Keeps the pipeline and buffers alive while the GPU uses them
+    job: Option<Job>,
+    scheduler: Weak<SchedulerShared>,
+    sequence: u64,
+    _permit: FlightPermit,
+}
+
+impl Completion {
+    fn complete(mut self) {
+        let Some(mut job) = self.job.take() else { return };
+        job.completed_passes += 1;
+        if job.completed_passes < job.passes {
+            let queued = Queued { job, state: self.state.clone(), sequence: self.sequence };
+            match self.scheduler.upgrade() {
+                Some(scheduler) => scheduler.requeue(queued),
+                None => queued.state.finish(JobStatus::Cancelled),
+            }
+            return;
+        }
+        job.remove_checkpoint();
+        self.state.finish(JobStatus::Completed);
+    }
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        // A completed pass may already have been resubmitted
+        if self.job.is_none() {
+            return;
+        }
+        let mut status = self.state.lock();
+        if *status == JobStatus::Submitted {
+            *status = JobStatus::Failed("Job was dropped before it completed".into());
+            self.state.changed.notify_all();
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Size and complexity of recorded command buffers
+//!
+//! Every command buffer a [`CommandBuilder`] submits is counted while it is
+//! recorded: Vulkan commands, dispatches, pipeline barriers, push constant
+//! bytes and workgroups. The counts are kept per command buffer for the
+//! most recent submissions and summed over the context's lifetime, so
+//! pathological patterns, such as thousands of single-dispatch command
+//! buffers or dispatches of a handful of workgroups, show up in
+//! [`ComputeContext::command_stats`] without a capture tool.
+//!
+//! Dry runs are not counted; their [`CommandListing`] already shows every
+//! command.
+//!
+//! [`ComputeContext::stats_snapshot`] adds running totals of the context's
+//! driver work: memory allocations, queue submissions, barriers,
+//! descriptor writes and GPU time. Diffing two snapshots turns performance
+//! goals into assertions:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let shader = ctx.load_shader("shaders/saxpy.spv")?;
+//! let pipeline = ctx.create_pipeline(&shader)?;
+//! let data = ctx.create_buffer(&[1.0f32; 1024])?;
+//!
+//! let before = ctx.stats_snapshot();
+//! ctx.dispatch(&pipeline).bind_buffer(0, &data).workgroups(4, 1, 1).execute()?;
+//! let step = ctx.stats_snapshot().diff(&before);
+//! assert_eq!(step.allocations, 0);
+//! assert_eq!(step.submissions, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::*;
+use super::context::ContextInner;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Command buffers kept in [`CommandStatsReport::recent`]
+pub const COMMAND_STATS_CAPACITY: usize = 1024;
+
+/// Dispatches with fewer workgroups than this count as tiny
+pub const TINY_DISPATCH_WORKGROUPS: u64 = 8;
+
+/// What one submitted command buffer recorded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    /// [Label](Pipeline::label) of the first dispatched pipeline
+    pub label: Arc<str>,
+    /// Every `vkCmd*` call, including the ones counted below
+    pub commands: u32,
+    pub dispatches: u32,
+    pub barriers: u32,
+    pub push_constant_bytes: u32,
+    /// Workgroups over all dispatches
+    pub workgroups: u64,
+    /// Dispatches of fewer than [`TINY_DISPATCH_WORKGROUPS`] workgroups
+    pub tiny_dispatches: u32,
+}
+
+impl CommandStats {
+    pub(super) fn new(label: Arc<str>) -> Self {
+        Self {
+            label,
+            commands: 0,
+            dispatches: 0,
+            barriers: 0,
+            push_constant_bytes: 0,
+            workgroups: 0,
+            tiny_dispatches: 0,
+        }
+    }
+
+    /// Count a command that is not a barrier, push or dispatch
+    pub(super) fn command(&mut self) {
+        self.commands += 1;
+    }
+
+    pub(super) fn barrier(&mut self) {
+        self.commands += 1;
+        self.barriers += 1;
+    }
+
+    pub(super) fn push_constants(&mut self, bytes: usize) {
+        self.commands += 1;
+        self.push_constant_bytes += bytes as u32;
+    }
+
+    pub(super) fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        let workgroups = x as u64 * y as u64 * z as u64;
+        self.commands += 1;
+        self.dispatches += 1;
+        self.workgroups += workgroups;
+        if workgroups < TINY_DISPATCH_WORKGROUPS {
+            self.tiny_dispatches += 1;
+        }
+    }
+}
+
+/// Command buffers submitted since the context was created or
+/// [`ComputeContext::reset_command_stats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandStatsReport {
+    /// The most recent command buffers, oldest first, up to
+    /// [`COMMAND_STATS_CAPACITY`]
+    pub recent: Vec<CommandStats>,
+    /// Totals over every command buffer, including those past `recent`
+    pub command_buffers: u64,
+    pub commands: u64,
+    pub dispatches: u64,
+    pub barriers: u64,
+    pub push_constant_bytes: u64,
+    pub workgroups: u64,
+    pub tiny_dispatches: u64,
+}
+
+impl CommandStatsReport {
+    fn add(&mut self, stats: &CommandStats) {
+        self.command_buffers += 1;
+        self.commands += stats.commands as u64;
+        self.dispatches += stats.dispatches as u64;
+        self.barriers += stats.barriers as u64;
+        self.push_constant_bytes += stats.push_constant_bytes as u64;
+        self.workgroups += stats.workgroups;
+        self.tiny_dispatches += stats.tiny_dispatches as u64;
+    }
+
+    /// Average dispatches recorded into one command buffer
+    pub fn dispatches_per_command_buffer(&self) -> f64 {
+        match self.command_buffers {
+            0 => 0.0,
+            n => self.dispatches as f64 / n as f64,
+        }
+    }
+
+    /// Average workgroups of one dispatch
+    pub fn workgroups_per_dispatch(&self) -> f64 {
+        match self.dispatches {
+            0 => 0.0,
+            n => self.workgroups as f64 / n as f64,
+        }
+    }
+}
+
+impl fmt::Display for CommandStatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} command buffers: {} commands, {} dispatches, {} barriers, {} push constant bytes",
+            self.command_buffers, self.commands, self.dispatches, self.barriers, self.push_constant_bytes
+        )?;
+        writeln!(
+            f,
+            "{:.1} dispatches per command buffer, {:.1} workgroups per dispatch",
+            self.dispatches_per_command_buffer(),
+            self.workgroups_per_dispatch()
+        )?;
+        if self.tiny_dispatches > 0 {
+            writeln!(
+                f,
+                "{} dispatches had fewer than {} workgroups",
+                self.tiny_dispatches, TINY_DISPATCH_WORKGROUPS
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Running totals of a context's driver work, see
+/// [`ComputeContext::stats_snapshot`]
+///
+/// Totals only grow, including across
+/// [`reset_command_stats`](ComputeContext::reset_command_stats) and
+/// [`reset_gpu_timing`](ComputeContext::reset_gpu_timing).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Device memory allocations, by the driver or an allocator plugin
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    /// Successful queue submissions of any kind, including transfers
+    pub submissions: u64,
+    /// Dispatches in submitted [`CommandBuilder`] command buffers
+    pub dispatches: u64,
+    /// Pipeline barriers in submitted [`CommandBuilder`] command buffers
+    pub barriers: u64,
+    /// Descriptors written with `vkUpdateDescriptorSets`
+    pub descriptor_updates: u64,
+    /// GPU time of timed dispatches; zero unless
+    /// [`enable_gpu_timing`](ComputeContext::enable_gpu_timing) was called
+    pub gpu_time: Duration,
+}
+
+impl StatsSnapshot {
+    /// What happened between `earlier` and this snapshot
+    pub fn diff(&self, earlier: &StatsSnapshot) -> StatsSnapshot {
+        StatsSnapshot {
+            allocations: self.allocations.saturating_sub(earlier.allocations),
+            allocated_bytes: self.allocated_bytes.saturating_sub(earlier.allocated_bytes),
+            submissions: self.submissions.saturating_sub(earlier.submissions),
+            dispatches: self.dispatches.saturating_sub(earlier.dispatches),
+            barriers: self.barriers.saturating_sub(earlier.barriers),
+            descriptor_updates: self.descriptor_updates.saturating_sub(earlier.descriptor_updates),
+            gpu_time: self.gpu_time.saturating_sub(earlier.gpu_time),
+        }
+    }
+}
+
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} allocations ({} bytes), {} submissions, {} dispatches, {} barriers, {} descriptor updates, {:.3?} GPU time",
+            self.allocations,
+            self.allocated_bytes,
+            self.submissions,
+            self.dispatches,
+            self.barriers,
+            self.descriptor_updates,
+            self.gpu_time
+        )
+    }
+}
+
+/// Counters behind [`ComputeContext::stats_snapshot`]
+#[derive(Default)]
+pub(super) struct ContextCounters {
+    allocations: AtomicU64,
+    allocated_bytes: AtomicU64,
+    submissions: AtomicU64,
+    dispatches: AtomicU64,
+    barriers: AtomicU64,
+    descriptor_updates: AtomicU64,
+}
+
+impl ContextCounters {
+    pub(super) fn allocation(&self, bytes: VkDeviceSize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.allocated_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(super) fn submissions(&self, count: u32) {
+        self.submissions.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn descriptor_updates(&self, count: usize) {
+        self.descriptor_updates.fetch_add(count as u64, Ordering::Relaxed);
+    }
+}
+
+/// Per-context log behind [`ComputeContext::command_stats`]
+#[derive(Default)]
+pub(super) struct CommandStatsLog {
+    recent: VecDeque<CommandStats>,
+    totals: CommandStatsReport,
+}
+
+impl ContextInner {
+    /// Count a command buffer once it has been submitted
+    pub(super) fn record_command_stats(&self, stats: CommandStats) {
+        self.counters.dispatches.fetch_add(stats.dispatches as u64, Ordering::Relaxed);
+        self.counters.barriers.fetch_add(stats.barriers as u64, Ordering::Relaxed);
+        let mut log = self.command_stats.lock().unwrap();
+        log.totals.add(&stats);
+        if log.recent.len() == COMMAND_STATS_CAPACITY {
+            log.recent.pop_front();
+        }
+        log.recent.push_back(stats);
+    }
+}
+
+impl ComputeContext {
+    /// Size of the command buffers submitted so far
+    pub fn command_stats(&self) -> CommandStatsReport {
+        self.with_inner(|inner| {
+            let log = inner.command_stats.lock().unwrap();
+            CommandStatsReport {
+                recent: log.recent.iter().cloned().collect(),
+                ..log.totals.clone()
+            }
+        })
+    }
+
+    /// Discard the command buffer statistics collected so far
+    pub fn reset_command_stats(&self) {
+        self.with_inner(|inner| *inner.command_stats.lock().unwrap() = CommandStatsLog::default())
+    }
+
+    /// Totals of the driver work done by the context so far
+    ///
+    /// Diff two snapshots with [`StatsSnapshot::diff`] to see what the code
+    /// between them did.
+    pub fn stats_snapshot(&self) -> StatsSnapshot {
+        self.with_inner(|inner| {
+            let counters = &inner.counters;
+            StatsSnapshot {
+                allocations: counters.allocations.load(Ordering::Relaxed),
+                allocated_bytes: counters.allocated_bytes.load(Ordering::Relaxed),
+                submissions: counters.submissions.load(Ordering::Relaxed),
+                dispatches: counters.dispatches.load(Ordering::Relaxed),
+                barriers: counters.barriers.load(Ordering::Relaxed),
+                descriptor_updates: counters.descriptor_updates.load(Ordering::Relaxed),
+                gpu_time: inner.gpu_timer.get().map_or(Duration::ZERO, |timer| timer.total_gpu_time()),
+            }
+        })
+    }
+}
@@ -0,0 +1,441 @@
+//! Copies and dispatches across several queues in one call
+//!
+//! Uploading on a transfer queue, dispatching on the compute queue and
+//! reading back on the transfer queue again takes three submissions, a
+//! semaphore between each, and, where the queues belong to different
+//! families, a release and an acquire barrier for every buffer moving
+//! between them. A [`SubmitPlan`] lists the steps with their queues, and
+//! [`ComputeContext::submit_plan`] generates the rest:
+//!
+//! ```no_run
+//! use kronos_compute::api::{ComputeContext, SubmitPlan};
+//! use kronos_compute::core::VkQueueFlags;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let compute = ctx.create_queue(ctx.queue_family_index(), 0)?;
+//! let transfer_family = ctx
+//!     .queue_families()
+//!     .iter()
+//!     .find(|family| !family.flags.contains(VkQueueFlags::COMPUTE))
+//!     .map_or(compute.family_index(), |family| family.index);
+//! let transfer = ctx.create_queue(transfer_family, 0)?;
+//!
+//! let pipeline = ctx.create_pipeline(&ctx.load_shader("scale.spv")?)?;
+//! let mut input = ctx.create_buffer_uninit(4096)?;
+//! input.write(&[1.0f32; 1024])?;
+//! let data = ctx.create_buffer(&[0.0f32; 1024])?;
+//! let output = ctx.create_buffer_uninit(4096)?;
+//!
+//! let plan = SubmitPlan::new()
+//!     .copy(&transfer, &input, &data)
+//!     .dispatch(&compute, ctx.dispatch(&pipeline).bind_buffer(0, &data).workgroups(16, 1, 1))
+//!     .copy(&transfer, &data, &output);
+//! ctx.submit_plan(plan)?;
+//! let result: Vec<f32> = output.read()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Consecutive steps on one queue share a submission, with a barrier
+//! between them, and each submission waits for the one before it on a
+//! semaphore. Buffers rest with the context's queue family between plans:
+//! one first used on another family is released by a submission to the
+//! context's queue ahead of the plan, and one last used on another family
+//! is acquired back by a submission after it.
+
+use super::*;
+use crate::*; // Import all functions from the crate root
+use super::context::ContextInner;
+use super::deferred::LastUse;
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::Arc;
+
+/// A batch of copies and dispatches, each on its own queue, submitted
+/// with [`ComputeContext::submit_plan`]
+#[derive(Default)]
+pub struct SubmitPlan<'a> {
+    steps: Vec<(&'a Queue, Step<'a>)>,
+}
+
+enum Step<'a> {
+    Copy { src: &'a Buffer, dst: &'a Buffer },
+    Dispatch(Box<CommandBuilder>),
+}
+
+impl<'a> SubmitPlan<'a> {
+    /// Start an empty plan
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy all of `src` to the start of `dst` on `queue`
+    ///
+    /// `src` needs TRANSFER_SRC usage and `dst` TRANSFER_DST usage.
+    pub fn copy(mut self, queue: &'a Queue, src: &'a Buffer, dst: &'a Buffer) -> Self {
+        self.steps.push((queue, Step::Copy { src, dst }));
+        self
+    }
+
+    /// Run `dispatch` on `queue`, which must support compute
+    ///
+    /// As with [`CommandBuilder::into_command_buffer`], dispatches with
+    /// work to do on completion or ordered with
+    /// [`after`](CommandBuilder::after) are refused.
+    pub fn dispatch(mut self, queue: &'a Queue, dispatch: CommandBuilder) -> Self {
+        self.steps.push((queue, Step::Dispatch(Box::new(dispatch))));
+        self
+    }
+
+    /// Number of steps in the plan
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether the plan has no steps
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// Work of one step, ready to record
+enum Work {
+    Copy { src: VkBuffer, dst: VkBuffer, size: VkDeviceSize },
+    Dispatch(CommandBuffer),
+}
+
+/// Steps for one queue, submitted together
+struct Batch {
+    queue: VkQueue,
+    family: u32,
+    command_pool: VkCommandPool,
+    /// Stages and accesses the family's queues support
+    access: FamilyAccess,
+    /// Each step's work, after acquiring the buffers it takes over; the
+    /// batch returning buffers to the context's family has no work
+    steps: Vec<(Vec<VkBufferMemoryBarrier>, Option<Work>)>,
+    /// Buffers released to the family of a later batch
+    releases: Vec<VkBufferMemoryBarrier>,
+}
+
+impl Batch {
+    fn new(queue: VkQueue, family: u32, command_pool: VkCommandPool, inner: &ContextInner) -> Self {
+        let flags = inner.queue_families.get(family as usize).map_or(VkQueueFlags::empty(), |family| family.queueFlags);
+        Self { queue, family, command_pool, access: FamilyAccess::new(flags), steps: Vec::new(), releases: Vec::new() }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.releases.is_empty() && self.steps.iter().all(|(acquires, work)| acquires.is_empty() && work.is_none())
+    }
+}
+
+/// Pipeline stages and accesses steps on a queue family use
+#[derive(Clone, Copy)]
+struct FamilyAccess {
+    stages: VkPipelineStageFlags,
+    writes: VkAccessFlags,
+    all: VkAccessFlags,
+}
+
+impl FamilyAccess {
+    fn new(flags: VkQueueFlags) -> Self {
+        if flags.contains(VkQueueFlags::COMPUTE) {
+            Self {
+                stages: VkPipelineStageFlags::COMPUTE_SHADER | VkPipelineStageFlags::TRANSFER,
+                writes: VkAccessFlags::SHADER_WRITE | VkAccessFlags::TRANSFER_WRITE,
+                all: VkAccessFlags::SHADER_READ
+                    | VkAccessFlags::SHADER_WRITE
+                    | VkAccessFlags::TRANSFER_READ
+                    | VkAccessFlags::TRANSFER_WRITE,
+            }
+        } else {
+            // Transfer-only families know nothing of shaders
+            Self {
+                stages: VkPipelineStageFlags::TRANSFER,
+                writes: VkAccessFlags::TRANSFER_WRITE,
+                all: VkAccessFlags::TRANSFER_READ | VkAccessFlags::TRANSFER_WRITE,
+            }
+        }
+    }
+}
+
+/// Barrier moving `buffer` from queue family `from` to `to`, with the
+/// release's access mask `src_access` and the acquire's `dst_access`
+fn ownership_barrier(buffer: VkBuffer, from: u32, to: u32, src_access: VkAccessFlags, dst_access: VkAccessFlags) -> VkBufferMemoryBarrier {
+    VkBufferMemoryBarrier {
+        sType: VkStructureType::BufferMemoryBarrier,
+        pNext: ptr::null(),
+        srcAccessMask: src_access,
+        dstAccessMask: dst_access,
+        srcQueueFamilyIndex: from,
+        dstQueueFamilyIndex: to,
+        buffer,
+        offset: 0,
+        size: VK_WHOLE_SIZE,
+    }
+}
+
+/// Move `buffers`, used by a step of batch `at`, to that batch's family,
+/// returning the step's acquire barriers
+///
+/// `owners` holds the batch that last used each buffer; buffers not in it
+/// come from the batch at 0.
+fn take_ownership(batches: &mut [Batch], owners: &mut HashMap<VkBuffer, usize>, buffers: &[VkBuffer], at: usize) -> Vec<VkBufferMemoryBarrier> {
+    let mut acquires = Vec::new();
+    for &buffer in buffers {
+        let previous = owners.insert(buffer, at).unwrap_or(0);
+        let (from, to) = (batches[previous].family, batches[at].family);
+        if from == to {
+            continue;
+        }
+        let writes = batches[previous].access.writes;
+        batches[previous].releases.push(ownership_barrier(buffer, from, to, writes, VkAccessFlags::empty()));
+        acquires.push(ownership_barrier(buffer, from, to, VkAccessFlags::empty(), batches[at].access.all));
+    }
+    acquires
+}
+
+/// Allocate and begin a one-time command buffer from `command_pool`,
+/// adding it to `allocated`
+unsafe fn begin(device: VkDevice, command_pool: VkCommandPool, allocated: &mut Vec<(VkCommandPool, VkCommandBuffer)>) -> Result<VkCommandBuffer> {
+    let alloc_info = VkCommandBufferAllocateInfo {
+        sType: VkStructureType::CommandBufferAllocateInfo,
+        pNext: ptr::null(),
+        commandPool: command_pool,
+        level: VkCommandBufferLevel::Primary,
+        commandBufferCount: 1,
+    };
+    let mut command_buffer = VkCommandBuffer::NULL;
+    let result = vkAllocateCommandBuffers(device, &alloc_info, &mut command_buffer);
+    if result != VkResult::Success {
+        return Err(KronosError::from(result));
+    }
+    allocated.push((command_pool, command_buffer));
+    let begin_info = VkCommandBufferBeginInfo {
+        sType: VkStructureType::CommandBufferBeginInfo,
+        pNext: ptr::null(),
+        flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+        pInheritanceInfo: ptr::null(),
+    };
+    let result = vkBeginCommandBuffer(command_buffer, &begin_info);
+    if result != VkResult::Success {
+        return Err(KronosError::from(result));
+    }
+    Ok(command_buffer)
+}
+
+unsafe fn end(command_buffer: VkCommandBuffer) -> Result<()> {
+    let result = vkEndCommandBuffer(command_buffer);
+    if result != VkResult::Success {
+        return Err(KronosError::from(result));
+    }
+    Ok(())
+}
+
+/// Record `buffers` barriers, after the writes of earlier steps on the
+/// queue if `after_previous`
+unsafe fn record_barriers(command_buffer: VkCommandBuffer, access: FamilyAccess, buffers: &[VkBufferMemoryBarrier], after_previous: bool) {
+    let memory = VkMemoryBarrier {
+        sType: VkStructureType::MemoryBarrier,
+        pNext: ptr::null(),
+        srcAccessMask: access.writes,
+        dstAccessMask: access.all,
+    };
+    vkCmdPipelineBarrier(
+        command_buffer,
+        access.stages,
+        access.stages,
+        VkDependencyFlags::empty(),
+        after_previous as u32,
+        &memory,
+        buffers.len() as u32,
+        buffers.as_ptr(),
+        0,
+        ptr::null(),
+    );
+}
+
+/// Record the command buffers of `batch`, in submission order
+unsafe fn record(device: VkDevice, batch: &Batch, allocated: &mut Vec<(VkCommandPool, VkCommandBuffer)>) -> Result<Vec<VkCommandBuffer>> {
+    let mut command_buffers = Vec::new();
+    for (index, (acquires, work)) in batch.steps.iter().enumerate() {
+        let after_previous = index > 0;
+        match work {
+            Some(Work::Copy { src, dst, size }) => {
+                let command_buffer = begin(device, batch.command_pool, allocated)?;
+                if after_previous || !acquires.is_empty() {
+                    record_barriers(command_buffer, batch.access, acquires, after_previous);
+                }
+                let region = VkBufferCopy { srcOffset: 0, dstOffset: 0, size: *size };
+                vkCmdCopyBuffer(command_buffer, *src, *dst, 1, &region);
+                end(command_buffer)?;
+                command_buffers.push(command_buffer);
+            }
+            _ => {
+                if after_previous || !acquires.is_empty() {
+                    let command_buffer = begin(device, batch.command_pool, allocated)?;
+                    record_barriers(command_buffer, batch.access, acquires, after_previous);
+                    end(command_buffer)?;
+                    command_buffers.push(command_buffer);
+                }
+                if let Some(Work::Dispatch(dispatch)) = work {
+                    command_buffers.push(dispatch.raw());
+                }
+            }
+        }
+    }
+    if !batch.releases.is_empty() {
+        let command_buffer = begin(device, batch.command_pool, allocated)?;
+        vkCmdPipelineBarrier(
+            command_buffer,
+            batch.access.stages,
+            VkPipelineStageFlags::BOTTOM_OF_PIPE,
+            VkDependencyFlags::empty(),
+            0,
+            ptr::null(),
+            batch.releases.len() as u32,
+            batch.releases.as_ptr(),
+            0,
+            ptr::null(),
+        );
+        end(command_buffer)?;
+        command_buffers.push(command_buffer);
+    }
+    Ok(command_buffers)
+}
+
+/// Submit `batches`, each waiting for the one before it, the last one
+/// signaling `fence`; returns how many were submitted and the error that
+/// stopped the rest
+unsafe fn submit(
+    inner: &ContextInner,
+    batches: &[Batch],
+    command_buffers: &[Vec<VkCommandBuffer>],
+    semaphores: &[Semaphore],
+    fence: VkFence,
+) -> (usize, Result<()>) {
+    let _queue = inner.queue_lock.lock().unwrap();
+    for (index, (batch, command_buffers)) in batches.iter().zip(command_buffers).enumerate() {
+        let wait = index.checked_sub(1).map(|previous| semaphores[previous].raw());
+        let signal = semaphores.get(index).map(|semaphore| semaphore.raw());
+        let wait_stage = batch.access.stages;
+        let submit_info = VkSubmitInfo {
+            sType: VkStructureType::SubmitInfo,
+            pNext: ptr::null(),
+            waitSemaphoreCount: wait.is_some() as u32,
+            pWaitSemaphores: wait.as_ref().map_or(ptr::null(), |wait| wait),
+            pWaitDstStageMask: &wait_stage,
+            commandBufferCount: command_buffers.len() as u32,
+            pCommandBuffers: command_buffers.as_ptr(),
+            signalSemaphoreCount: signal.is_some() as u32,
+            pSignalSemaphores: signal.as_ref().map_or(ptr::null(), |signal| signal),
+        };
+        let fence = if index + 1 == batches.len() { fence } else { VkFence::NULL };
+        let result = inner.device_events.check(inner.queue_submit(batch.queue, 1, &submit_info, fence));
+        if result != VkResult::Success {
+            return (index, Err(KronosError::CommandExecutionFailed(format!("vkQueueSubmit failed: {:?}", result))));
+        }
+    }
+    (batches.len(), Ok(()))
+}
+
+impl ComputeContext {
+    /// Submit the steps of `plan` in order and wait for them to complete,
+    /// see [`submit_plan`](super::submit_plan)
+    pub fn submit_plan(&self, plan: SubmitPlan<'_>) -> Result<()> {
+        if plan.is_empty() {
+            return Ok(());
+        }
+        if self.is_dry_run() {
+            return Err(KronosError::CommandExecutionFailed("Dry runs cannot submit plans".into()));
+        }
+        self.with_inner(|inner| unsafe {
+            // The context's own queue opens and closes the plan
+            let home_pool = inner.pools.lock().unwrap().command_pool;
+            let home = || Batch::new(inner.queue, inner.queue_family_index, home_pool, inner);
+            let mut batches = vec![home()];
+            let mut owners = HashMap::new();
+            let mut uses: Vec<Arc<LastUse>> = Vec::new();
+
+            for (queue, step) in plan.steps {
+                let raw_queue = queue.queue.on(inner.id);
+                if batches.len() == 1 || batches[batches.len() - 1].queue != raw_queue {
+                    batches.push(Batch::new(raw_queue, queue.family_index, queue.command_pool.raw, inner));
+                }
+                let at = batches.len() - 1;
+                let (work, buffers) = match step {
+                    Step::Copy { src, dst } => {
+                        if src.size > dst.size {
+                            return Err(KronosError::CommandExecutionFailed(format!(
+                                "Copy of {} bytes exceeds destination buffer of {} bytes",
+                                src.size, dst.size
+                            )));
+                        }
+                        inner.check_copy_usage(src.usage, dst.usage);
+                        let (size, src, dst) = (src.size as VkDeviceSize, src.buffer.on(inner.id), dst.buffer.on(inner.id));
+                        (Work::Copy { src, dst, size }, vec![src, dst])
+                    }
+                    Step::Dispatch(dispatch) => {
+                        if !batches[at].access.stages.contains(VkPipelineStageFlags::COMPUTE_SHADER) {
+                            return Err(KronosError::UnsupportedHardware(format!(
+                                "Queue family {} has no compute support",
+                                queue.family_index
+                            )));
+                        }
+                        let (buffers, dispatch_uses) = dispatch.bound_resources();
+                        uses.extend(dispatch_uses);
+                        (Work::Dispatch(dispatch.on_queue(queue).into_command_buffer()?), buffers)
+                    }
+                };
+                let acquires = take_ownership(&mut batches, &mut owners, &buffers, at);
+                batches[at].steps.push((acquires, Some(work)));
+            }
+            let closing = batches.len();
+            batches.push(home());
+            let buffers: Vec<VkBuffer> = owners.keys().copied().collect();
+            let acquires = take_ownership(&mut batches, &mut owners, &buffers, closing);
+            batches[closing].steps.push((acquires, None));
+            batches.retain(|batch| !batch.is_empty());
+
+            let fence = self.create_fence(false)?;
+            let semaphores = (1..batches.len()).map(|_| self.create_semaphore()).collect::<Result<Vec<_>>>()?;
+            let mut allocated = Vec::new();
+            let mut command_buffers = Vec::new();
+            let recorded = {
+                let _pools = inner.pools.lock().unwrap();
+                batches.iter().try_for_each(|batch| {
+                    command_buffers.push(record(inner.device, batch, &mut allocated)?);
+                    Ok(())
+                })
+            };
+            let serial = inner.deferred.begin_submission(&uses);
+            let result = match recorded {
+                Ok(()) => match submit(inner, &batches, &command_buffers, &semaphores, fence.raw()) {
+                    (_, Ok(())) => fence.wait_forever(),
+                    (submitted, Err(e)) => {
+                        if submitted > 0 {
+                            // The batches already submitted may still run;
+                            // waiting for the device needs every queue
+                            let _queue = inner.queue_lock.lock().unwrap();
+                            inner.device_events.check(vkDeviceWaitIdle(inner.device));
+                        }
+                        Err(e)
+                    }
+                },
+                Err(e) => Err(e),
+            };
+            drop(serial);
+
+            {
+                let _pools = inner.pools.lock().unwrap();
+                for (command_pool, command_buffer) in allocated {
+                    vkFreeCommandBuffers(inner.device, command_pool, 1, &command_buffer);
+                }
+            }
+            // Recorded dispatches free their command buffers on drop
+            drop(batches);
+            result
+        })
+    }
+}
@@ -3,6 +3,7 @@
 use super::*;
 use crate::*; // Import all functions from the crate root
 use std::ptr;
+use std::sync::{Arc, Condvar, Mutex};
 
 /// A GPU fence for CPU-GPU synchronization
 pub struct Fence {
@@ -14,16 +15,89 @@ pub struct Fence {
 unsafe impl Send for Fence {}
 unsafe impl Sync for Fence {}
 
+/// State of a fence, as polled by [`Fence::status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenceStatus {
+    Signaled,
+    Unsignaled,
+}
+
 /// A GPU semaphore for GPU-GPU synchronization
 pub struct Semaphore {
-    context: ComputeContext,
-    semaphore: VkSemaphore,
+    pub(super) context: ComputeContext,
+    pub(super) semaphore: VkSemaphore,
 }
 
 // Send + Sync for thread safety
 unsafe impl Send for Semaphore {}
 unsafe impl Sync for Semaphore {}
 
+/// Bounds the number of outstanding submissions
+///
+/// Attach to dispatches with
+/// [`CommandBuilder::flight_limit`](super::CommandBuilder::flight_limit) so
+/// that `submit()` blocks once `limit` submissions are in flight, instead of
+/// queueing work (and its buffers) faster than the GPU retires it.
+#[derive(Clone)]
+pub struct FlightLimiter {
+    state: Arc<(Mutex<usize>, Condvar)>,
+    limit: usize,
+}
+
+/// One in-flight slot of a [`FlightLimiter`], released on drop
+pub struct FlightPermit {
+    state: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl FlightLimiter {
+    /// Allow at most `limit` submissions in flight (minimum 1)
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Arc::new((Mutex::new(0), Condvar::new())),
+            limit: limit.max(1),
+        }
+    }
+    
+    /// Maximum number of outstanding submissions
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+    
+    /// Number of permits currently held
+    pub fn in_flight(&self) -> usize {
+        *self.state.0.lock().unwrap()
+    }
+    
+    /// Block until a slot is free and take it
+    pub fn acquire(&self) -> FlightPermit {
+        let (count, freed) = &*self.state;
+        let mut count = count.lock().unwrap();
+        while *count >= self.limit {
+            count = freed.wait(count).unwrap();
+        }
+        *count += 1;
+        FlightPermit { state: self.state.clone() }
+    }
+    
+    /// Take a slot if one is free, without blocking
+    pub fn try_acquire(&self) -> Option<FlightPermit> {
+        let mut count = self.state.0.lock().unwrap();
+        if *count >= self.limit {
+            return None;
+        }
+        *count += 1;
+        Some(FlightPermit { state: self.state.clone() })
+    }
+}
+
+impl Drop for FlightPermit {
+    fn drop(&mut self) {
+        let (count, freed) = &*self.state;
+        *count.lock().unwrap() -= 1;
+        freed.notify_one();
+    }
+}
+
 impl ComputeContext {
     /// Create a new fence
     pub fn create_fence(&self, signaled: bool) -> Result<Fence> {
@@ -80,26 +154,71 @@ impl ComputeContext {
     }
 }
 
+/// Wait until every fence is signaled
+///
+/// The fences must belong to one context. An empty slice returns at once.
+pub fn wait_all(fences: &[&Fence], timeout_ns: u64) -> Result<()> {
+    wait_fences(fences, true, timeout_ns)
+}
+
+/// Wait until at least one fence is signaled, returning the index of a
+/// signaled fence
+///
+/// The fences must belong to one context. When several are signaled the
+/// lowest index is returned, so a scheduler polling the same set should
+/// retire that job before waiting again.
+pub fn wait_any(fences: &[&Fence], timeout_ns: u64) -> Result<usize> {
+    if fences.is_empty() {
+        return Err(KronosError::SynchronizationError("wait_any needs at least one fence".into()));
+    }
+    wait_fences(fences, false, timeout_ns)?;
+    for (index, fence) in fences.iter().enumerate() {
+        if fence.status()? == FenceStatus::Signaled {
+            return Ok(index);
+        }
+    }
+    // A fence reset by another thread between the wait and the poll
+    Err(KronosError::SynchronizationError("Signaled fence was reset before it was polled".into()))
+}
+
+/// One vkWaitForFences call over fences of a single context
+fn wait_fences(fences: &[&Fence], wait_all: bool, timeout_ns: u64) -> Result<()> {
+    let Some(first) = fences.first() else {
+        return Ok(());
+    };
+    let device = first.context.device_id();
+    if let Some(other) = fences.iter().find(|fence| fence.context.device_id() != device) {
+        return Err(KronosError::SynchronizationError(format!(
+            "Fences of {} and {} cannot be waited on together",
+            device,
+            other.context.device_id()
+        )));
+    }
+    
+    let handles: Vec<VkFence> = fences.iter().map(|fence| fence.fence).collect();
+    unsafe {
+        first.context.with_inner(|inner| {
+            let result = inner.device_events.check(vkWaitForFences(
+                inner.device,
+                handles.len() as u32,
+                handles.as_ptr(),
+                if wait_all { VK_TRUE } else { VK_FALSE },
+                timeout_ns,
+            ));
+            
+            match result {
+                VkResult::Success => Ok(()),
+                VkResult::Timeout => Err(KronosError::SynchronizationError("Timeout waiting for fence".into())),
+                _ => Err(KronosError::from(result)),
+            }
+        })
+    }
+}
+
 impl Fence {
     /// Wait for the fence to be signaled
     pub fn wait(&self, timeout_ns: u64) -> Result<()> {
-        unsafe {
-            self.context.with_inner(|inner| {
-                let result = vkWaitForFences(
-                    inner.device,
-                    1,
-                    &self.fence,
-                    VK_TRUE,
-                    timeout_ns,
-                );
-                
-                match result {
-                    VkResult::Success => Ok(()),
-                    VkResult::Timeout => Err(KronosError::SynchronizationError("Timeout waiting for fence".into())),
-                    _ => Err(KronosError::from(result)),
-                }
-            })
-        }
+        wait_fences(&[self], true, timeout_ns)
     }
     
     /// Wait indefinitely for the fence
@@ -122,25 +241,46 @@ impl Fence {
         }
     }
     
-    /// Check if the fence is signaled without waiting
-    pub fn is_signaled(&self) -> Result<bool> {
+    /// Poll the fence without waiting
+    pub fn status(&self) -> Result<FenceStatus> {
         unsafe {
             self.context.with_inner(|inner| {
-                let result = vkGetFenceStatus(inner.device, self.fence);
+                let result = inner.device_events.check(vkGetFenceStatus(inner.device, self.fence));
                 
                 match result {
-                    VkResult::Success => Ok(true),
-                    VkResult::NotReady => Ok(false),
+                    VkResult::Success => Ok(FenceStatus::Signaled),
+                    VkResult::NotReady => Ok(FenceStatus::Unsignaled),
                     _ => Err(KronosError::from(result)),
                 }
             })
         }
     }
     
+    /// Check if the fence is signaled without waiting
+    pub fn is_signaled(&self) -> Result<bool> {
+        Ok(self.status()? == FenceStatus::Signaled)
+    }
+    
     /// Get the raw Vulkan fence handle
     pub fn raw(&self) -> VkFence {
         self.fence
     }
+    
+    /// Take ownership of a fence created outside Kronos
+    ///
+    /// The fence is destroyed when the returned fence is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `fence` must have been created on `context`'s device, must not be
+    /// destroyed elsewhere, and no submission may still signal it when the
+    /// returned fence is dropped.
+    pub unsafe fn from_raw_parts(context: &ComputeContext, fence: VkFence) -> Fence {
+        Fence {
+            context: context.clone(),
+            fence,
+        }
+    }
 }
 
 impl Semaphore {
@@ -0,0 +1,461 @@
+//! GPU clock, power and temperature sampling
+//!
+//! A background thread samples the device through the vendor interface
+//! available on the host: the kernel driver's sysfs files on Linux (amdgpu,
+//! i915) or NVML (`libnvidia-ml`) for NVIDIA. Samples are correlated with
+//! timed dispatches in [`ComputeContext::dispatch_trace`], so a dispatch
+//! that ran while clocks dropped shows up as throttled.
+//!
+//! The sampled device is found by its PCI address or, for NVML, its UUID,
+//! so identical GPUs in one machine are never confused with each other.
+
+use super::*;
+use libloading::Library;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::ffi::{c_char, c_void, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Samples kept by a sampler; older samples are discarded
+pub const TELEMETRY_CAPACITY: usize = 8192;
+
+/// Graphics clock below this fraction of the run's peak counts as throttled
+pub const THROTTLE_CLOCK_RATIO: f32 = 0.9;
+
+/// One reading of the device's clocks and power
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TelemetrySample {
+    pub at: Instant,
+    pub graphics_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    pub power_watts: Option<f32>,
+    pub temperature_c: Option<f32>,
+}
+
+impl TelemetrySample {
+    fn empty(at: Instant) -> Self {
+        Self {
+            at,
+            graphics_clock_mhz: None,
+            memory_clock_mhz: None,
+            power_watts: None,
+            temperature_c: None,
+        }
+    }
+}
+
+/// Device state while a dispatch ran
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TelemetrySummary {
+    /// Samples the summary is built from
+    pub samples: usize,
+    pub min_graphics_clock_mhz: Option<u32>,
+    pub max_graphics_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    pub average_power_watts: Option<f32>,
+    pub max_temperature_c: Option<f32>,
+    /// Lowest graphics clock as a fraction of the highest seen during the run
+    pub clock_ratio: Option<f32>,
+}
+
+impl TelemetrySummary {
+    /// Summarize `samples`, comparing clocks against the run's `peak_clock_mhz`
+    pub fn from_samples(samples: &[TelemetrySample], peak_clock_mhz: Option<u32>) -> Self {
+        let clocks = samples.iter().filter_map(|s| s.graphics_clock_mhz);
+        let min_clock = clocks.clone().min();
+        let powers: Vec<f32> = samples.iter().filter_map(|s| s.power_watts).collect();
+        Self {
+            samples: samples.len(),
+            min_graphics_clock_mhz: min_clock,
+            max_graphics_clock_mhz: clocks.max(),
+            memory_clock_mhz: samples.iter().rev().find_map(|s| s.memory_clock_mhz),
+            average_power_watts: if powers.is_empty() {
+                None
+            } else {
+                Some(powers.iter().sum::<f32>() / powers.len() as f32)
+            },
+            max_temperature_c: samples
+                .iter()
+                .filter_map(|s| s.temperature_c)
+                .fold(None, |max: Option<f32>, t| Some(max.map_or(t, |m| m.max(t)))),
+            clock_ratio: match (min_clock, peak_clock_mhz) {
+                (Some(min), Some(peak)) if peak > 0 => Some(min as f32 / peak as f32),
+                _ => None,
+            },
+        }
+    }
+
+    /// Whether clocks dropped below [`THROTTLE_CLOCK_RATIO`] of the run's peak
+    pub fn is_throttled(&self) -> bool {
+        matches!(self.clock_ratio, Some(ratio) if ratio < THROTTLE_CLOCK_RATIO)
+    }
+}
+
+/// A vendor interface the sampler can read
+pub(super) trait TelemetryProbe: Send {
+    /// Interface name for logs
+    fn name(&self) -> &'static str;
+    fn read(&mut self, at: Instant) -> TelemetrySample;
+}
+
+/// Background sampler for one device
+pub struct TelemetrySampler {
+    source: &'static str,
+    shared: Arc<SamplerShared>,
+    stop: Mutex<Option<Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Default)]
+struct SamplerShared {
+    samples: Mutex<VecDeque<TelemetrySample>>,
+    /// Highest graphics clock seen, 0 if unknown
+    peak_clock_mhz: AtomicU32,
+}
+
+impl TelemetrySampler {
+    /// Start sampling the device at PCI address `pci_bus` or with Vulkan
+    /// `device_uuid` every `interval`
+    ///
+    /// Returns `None` if no supported interface exposes the device.
+    pub fn start(pci_bus: Option<PciBusAddress>, device_uuid: Option<[u8; VK_UUID_SIZE]>, interval: Duration) -> Option<Self> {
+        let sysfs = pci_bus.and_then(|address| SysfsProbe::find(Path::new(SysfsProbe::DRM_ROOT), address));
+        let probe: Box<dyn TelemetryProbe> = match sysfs {
+            Some(probe) => Box::new(probe),
+            None => Box::new(NvmlProbe::open(pci_bus, device_uuid)?),
+        };
+        Some(Self::with_probe(probe, interval))
+    }
+
+    pub(super) fn with_probe(mut probe: Box<dyn TelemetryProbe>, interval: Duration) -> Self {
+        let source = probe.name();
+        let shared = Arc::new(SamplerShared::default());
+        let thread_shared = shared.clone();
+        let (stop, stop_rx) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("kronos-telemetry".into())
+            .spawn(move || loop {
+                let sample = probe.read(Instant::now());
+                thread_shared.record(sample);
+                match stop_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            })
+            .expect("failed to spawn kronos telemetry thread");
+        kronos_log!(Info, "GPU telemetry sampling via {} every {:?}", source, interval);
+
+        Self {
+            source,
+            shared,
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(Some(thread)),
+        }
+    }
+
+    /// Name of the vendor interface being sampled
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// Samples collected so far, oldest first
+    pub fn samples(&self) -> Vec<TelemetrySample> {
+        self.shared.samples.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Summarize the samples covering `start..=end`
+    ///
+    /// Dispatches are often shorter than the sampling interval, so the
+    /// latest sample taken before `start` is included as well.
+    pub fn summarize(&self, start: Instant, end: Instant) -> TelemetrySummary {
+        let samples = self.shared.samples.lock().unwrap();
+        let first = samples.partition_point(|s| s.at < start).saturating_sub(1);
+        let last = samples.partition_point(|s| s.at <= end);
+        let window: Vec<TelemetrySample> = samples.range(first..last.max(first)).copied().collect();
+        let peak = self.shared.peak_clock_mhz.load(Ordering::Relaxed);
+        TelemetrySummary::from_samples(&window, (peak > 0).then_some(peak))
+    }
+
+    /// Stop the sampling thread; collected samples are kept
+    pub fn stop(&self) {
+        drop(self.stop.lock().unwrap().take());
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl SamplerShared {
+    fn record(&self, sample: TelemetrySample) {
+        if let Some(clock) = sample.graphics_clock_mhz {
+            self.peak_clock_mhz.fetch_max(clock, Ordering::Relaxed);
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == TELEMETRY_CAPACITY {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
+
+impl Drop for TelemetrySampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Read a sysfs file holding one integer
+fn read_sysfs_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// PCI address of a DRM card, from the name its `device` link points to
+fn card_pci_address(card: &Path) -> Option<PciBusAddress> {
+    let device = fs::read_link(card.join("device")).ok()?;
+    PciBusAddress::parse(device.file_name()?.to_str()?)
+}
+
+/// Kernel driver sysfs files (amdgpu and i915 hwmon, i915 GT frequency)
+pub(super) struct SysfsProbe {
+    card: PathBuf,
+    hwmon: Option<PathBuf>,
+    /// Previous energy counter reading, for drivers without a power file
+    last_energy: Option<(u64, Instant)>,
+}
+
+impl SysfsProbe {
+    /// Root of the DRM class directory
+    const DRM_ROOT: &'static str = "/sys/class/drm";
+
+    /// Find the DRM card under `root` for the device at `address`
+    pub(super) fn find(root: &Path, address: PciBusAddress) -> Option<Self> {
+        let mut cards: Vec<PathBuf> = fs::read_dir(root)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                // Skip connectors such as card0-DP-1
+                name.starts_with("card") && !name.contains('-')
+            })
+            .map(|entry| entry.path())
+            .collect();
+        cards.sort();
+        cards.into_iter()
+            .find(|card| card_pci_address(card) == Some(address))
+            .map(|card| Self::at(&card))
+    }
+
+    /// Read the DRM card directory `card` (e.g. `/sys/class/drm/card0`)
+    pub(super) fn at(card: &Path) -> Self {
+        let hwmon = fs::read_dir(card.join("device/hwmon"))
+            .ok()
+            .and_then(|mut entries| entries.find_map(|entry| entry.ok()))
+            .map(|entry| entry.path());
+        Self {
+            card: card.to_path_buf(),
+            hwmon,
+            last_energy: None,
+        }
+    }
+}
+
+impl TelemetryProbe for SysfsProbe {
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
+
+    fn read(&mut self, at: Instant) -> TelemetrySample {
+        let mut sample = TelemetrySample::empty(at);
+        // i915 reports the actual GT frequency on the card itself
+        sample.graphics_clock_mhz = read_sysfs_u64(&self.card.join("gt_act_freq_mhz")).map(|mhz| mhz as u32);
+
+        let Some(hwmon) = &self.hwmon else { return sample };
+        let hz_to_mhz = |hz: u64| (hz / 1_000_000) as u32;
+        if sample.graphics_clock_mhz.is_none() {
+            sample.graphics_clock_mhz = read_sysfs_u64(&hwmon.join("freq1_input")).map(hz_to_mhz);
+        }
+        sample.memory_clock_mhz = read_sysfs_u64(&hwmon.join("freq2_input")).map(hz_to_mhz);
+        sample.temperature_c = read_sysfs_u64(&hwmon.join("temp1_input")).map(|milli| milli as f32 / 1000.0);
+
+        let microwatts = read_sysfs_u64(&hwmon.join("power1_average"))
+            .or_else(|| read_sysfs_u64(&hwmon.join("power1_input")));
+        sample.power_watts = match microwatts {
+            Some(microwatts) => Some(microwatts as f32 / 1_000_000.0),
+            None => {
+                // Average power from the energy counter (microjoules)
+                let energy = read_sysfs_u64(&hwmon.join("energy1_input"));
+                let previous = std::mem::replace(&mut self.last_energy, energy.map(|e| (e, at)));
+                match (previous, energy) {
+                    (Some((before, then)), Some(now)) if at > then && now >= before => {
+                        Some((now - before) as f32 / 1_000_000.0 / (at - then).as_secs_f32())
+                    }
+                    _ => None,
+                }
+            }
+        };
+        sample
+    }
+}
+
+// NVML entry points (nvml.h); every call returns nvmlReturn_t, 0 on success
+type NvmlDevice = *mut c_void;
+type NvmlInit = unsafe extern "C" fn() -> i32;
+type NvmlShutdown = unsafe extern "C" fn() -> i32;
+type NvmlDeviceGetHandleByUuid = unsafe extern "C" fn(*const c_char, *mut NvmlDevice) -> i32;
+type NvmlDeviceGetHandleByPciBusId = unsafe extern "C" fn(*const c_char, *mut NvmlDevice) -> i32;
+type NvmlDeviceGetClockInfo = unsafe extern "C" fn(NvmlDevice, u32, *mut u32) -> i32;
+type NvmlDeviceGetPowerUsage = unsafe extern "C" fn(NvmlDevice, *mut u32) -> i32;
+type NvmlDeviceGetTemperature = unsafe extern "C" fn(NvmlDevice, u32, *mut u32) -> i32;
+
+const NVML_CLOCK_GRAPHICS: u32 = 0;
+const NVML_CLOCK_MEM: u32 = 2;
+const NVML_TEMPERATURE_GPU: u32 = 0;
+
+/// NVML's name for the device with Vulkan `deviceUUID` `uuid`, e.g.
+/// `GPU-6b1c1d5e-...`; NVIDIA reports the same UUID through both APIs
+pub(super) fn nvml_uuid(uuid: &[u8; VK_UUID_SIZE]) -> String {
+    let hex: String = uuid.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("GPU-{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// NVIDIA Management Library, loaded at runtime
+pub(super) struct NvmlProbe {
+    device: NvmlDevice,
+    get_clock_info: NvmlDeviceGetClockInfo,
+    get_power_usage: NvmlDeviceGetPowerUsage,
+    get_temperature: NvmlDeviceGetTemperature,
+    shutdown: NvmlShutdown,
+    // Keeps the function pointers above valid
+    _library: Library,
+}
+
+// NVML handles are usable from any thread
+unsafe impl Send for NvmlProbe {}
+
+impl NvmlProbe {
+    const LIBRARIES: &'static [&'static str] = &["libnvidia-ml.so.1", "libnvidia-ml.so", "nvml.dll"];
+
+    /// Open the device with Vulkan `device_uuid`, or at PCI address `pci_bus`
+    fn open(pci_bus: Option<PciBusAddress>, device_uuid: Option<[u8; VK_UUID_SIZE]>) -> Option<Self> {
+        if pci_bus.is_none() && device_uuid.is_none() {
+            return None;
+        }
+        unsafe {
+            let library = Self::LIBRARIES.iter().find_map(|name| Library::new(name).ok())?;
+            let init = *library.get::<NvmlInit>(b"nvmlInit_v2\0").ok()?;
+            let shutdown = *library.get::<NvmlShutdown>(b"nvmlShutdown\0").ok()?;
+            let by_uuid = *library.get::<NvmlDeviceGetHandleByUuid>(b"nvmlDeviceGetHandleByUUID\0").ok()?;
+            let by_pci_bus = *library.get::<NvmlDeviceGetHandleByPciBusId>(b"nvmlDeviceGetHandleByPciBusId_v2\0").ok()?;
+            let get_clock_info = *library.get::<NvmlDeviceGetClockInfo>(b"nvmlDeviceGetClockInfo\0").ok()?;
+            let get_power_usage = *library.get::<NvmlDeviceGetPowerUsage>(b"nvmlDeviceGetPowerUsage\0").ok()?;
+            let get_temperature = *library.get::<NvmlDeviceGetTemperature>(b"nvmlDeviceGetTemperature\0").ok()?;
+            if init() != 0 {
+                return None;
+            }
+
+            let lookup = |get: unsafe extern "C" fn(*const c_char, *mut NvmlDevice) -> i32, id: String| {
+                let id = CString::new(id).ok()?;
+                let mut device: NvmlDevice = std::ptr::null_mut();
+                (get(id.as_ptr(), &mut device) == 0).then_some(device)
+            };
+            let device = device_uuid
+                .and_then(|uuid| lookup(by_uuid, nvml_uuid(&uuid)))
+                .or_else(|| pci_bus.and_then(|address| lookup(by_pci_bus, address.to_string())));
+            let Some(device) = device else {
+                shutdown();
+                return None;
+            };
+
+            Some(Self {
+                device,
+                get_clock_info,
+                get_power_usage,
+                get_temperature,
+                shutdown,
+                _library: library,
+            })
+        }
+    }
+}
+
+impl TelemetryProbe for NvmlProbe {
+    fn name(&self) -> &'static str {
+        "NVML"
+    }
+
+    fn read(&mut self, at: Instant) -> TelemetrySample {
+        let query = |f: &dyn Fn(*mut u32) -> i32| {
+            let mut value = 0u32;
+            (f(&mut value) == 0).then_some(value)
+        };
+        let device = self.device;
+        unsafe {
+            TelemetrySample {
+                at,
+                graphics_clock_mhz: query(&|out| (self.get_clock_info)(device, NVML_CLOCK_GRAPHICS, out)),
+                memory_clock_mhz: query(&|out| (self.get_clock_info)(device, NVML_CLOCK_MEM, out)),
+                power_watts: query(&|out| (self.get_power_usage)(device, out)).map(|mw| mw as f32 / 1000.0),
+                temperature_c: query(&|out| (self.get_temperature)(device, NVML_TEMPERATURE_GPU, out)).map(|c| c as f32),
+            }
+        }
+    }
+}
+
+impl Drop for NvmlProbe {
+    fn drop(&mut self) {
+        unsafe {
+            (self.shutdown)();
+        }
+    }
+}
+
+impl ComputeContext {
+    /// Sample GPU clocks and power every `interval` while timing dispatches
+    ///
+    /// Enables GPU timing as well; each entry of
+    /// [`dispatch_trace`](Self::dispatch_trace) then carries a
+    /// [`TelemetrySummary`]. Fails if neither sysfs nor NVML exposes the
+    /// device, or it reports neither its PCI address nor its UUID.
+    pub fn enable_telemetry(&self, interval: Duration) -> Result<()> {
+        self.enable_gpu_timing()?;
+        self.with_inner(|inner| {
+            let properties = &inner.device_properties;
+            let sampler = TelemetrySampler::start(inner.pci_bus, inner.device_uuid, interval)
+                .ok_or_else(|| KronosError::UnsupportedHardware(format!(
+                    "No telemetry interface for device {:04x}:{:04x}",
+                    properties.vendorID, properties.deviceID
+                )))?;
+            if let Some(timer) = inner.gpu_timer.get() {
+                timer.set_telemetry(Some(Arc::new(sampler)));
+            }
+            Ok(())
+        })
+    }
+
+    /// Stop sampling; traces recorded so far keep their telemetry
+    pub fn disable_telemetry(&self) {
+        self.with_inner(|inner| {
+            if let Some(timer) = inner.gpu_timer.get() {
+                timer.set_telemetry(None);
+            }
+        })
+    }
+
+    /// Raw telemetry samples of the active sampler, oldest first
+    pub fn telemetry_samples(&self) -> Vec<TelemetrySample> {
+        self.with_inner(|inner| {
+            inner.gpu_timer
+                .get()
+                .and_then(|timer| timer.telemetry())
+                .map(|sampler| sampler.samples())
+                .unwrap_or_default()
+        })
+    }
+}
@@ -24,7 +24,24 @@ mod tests {
             enable_validation: true,
             preferred_vendor: None,
             preferred_icd_index: None,
+            library_search_dirs: Vec::new(),
+            log_verbosity: None,
+            application_version: None,
+            engine_name: None,
+            api_version: None,
             preferred_icd_path: None,
+            required_features: Features::default(),
+            queue_family: None,
+            prefer_dedicated_compute_family: false,
+            performance_counters: false,
+            external_memory: false,
+            memory: MemoryConfig::default(),
+            memory_limit_bytes: None,
+            staging_capacity: None,
+            descriptor_pool_sets: None,
+            plugin: None,
+            threads: ThreadConfig::default(),
+            strict_conformance: false,
         };
         
         assert_eq!(config.app_name, "Test App");
@@ -75,4 +92,698 @@ mod tests {
         assert!(builder.config.enable_validation);
         assert_eq!(builder.config.preferred_vendor, Some("AMD".to_string()));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_require_features_builder() {
+        let builder = ComputeContext::builder()
+            .require_features(Features { shader_int64: true, ..Default::default() });
+        
+        assert!(builder.config.required_features.shader_int64);
+        assert!(!builder.config.required_features.shader_float64);
+        assert!(ContextConfig::default().required_features.is_empty());
+    }
+    
+    #[test]
+    fn test_features_missing_from() {
+        let required = Features { shader_int64: true, shader_float64: true, ..Default::default() };
+        let available = Features { shader_float64: true, shader_int16: true, ..Default::default() };
+        
+        assert_eq!(required.missing_from(&available), vec!["shaderInt64"]);
+        assert!(Features::default().missing_from(&available).is_empty());
+        
+        let vk = VkPhysicalDeviceFeatures::from(required);
+        assert_eq!(vk.shaderInt64, VK_TRUE);
+        assert_eq!(Features::from(vk), required);
+    }
+    
+    #[test]
+    fn test_negotiate_api_version() {
+        use crate::api::context::ComputeContext as Ctx;
+        assert_eq!(Ctx::negotiate_api_version(VK_API_VERSION_1_0), VK_API_VERSION_1_0);
+        assert_eq!(Ctx::negotiate_api_version(VK_MAKE_VERSION(1, 2, 176)), VK_API_VERSION_1_2);
+        assert_eq!(Ctx::negotiate_api_version(VK_MAKE_VERSION(1, 4, 300)), VK_API_VERSION_1_3);
+        assert_eq!(Ctx::negotiate_api_version(0), VK_API_VERSION_1_0);
+    }
+    
+    #[test]
+    fn test_requested_api_version() {
+        use crate::api::context::ComputeContext as Ctx;
+        assert_eq!(Version::from_raw(VK_MAKE_VERSION(1, 2, 176)), Version::new(1, 2, 176));
+        assert_eq!(Version::V1_3.to_raw(), VK_API_VERSION_1_3);
+        assert_eq!(Version::new(1, 2, 176).to_string(), "1.2.176");
+        
+        let loader = VK_MAKE_VERSION(1, 3, 250);
+        assert_eq!(Ctx::requested_api_version(loader, Version::new(1, 1, 5)).unwrap(), VK_API_VERSION_1_1);
+        assert_eq!(Ctx::requested_api_version(loader, Version::new(1, 3, 999)).unwrap(), VK_API_VERSION_1_3);
+        assert!(Ctx::requested_api_version(VK_API_VERSION_1_2, Version::V1_3).is_err());
+    }
+    
+    #[test]
+    fn test_storage_image_defaults() {
+        assert_eq!(VkFormat::R32Sfloat.texel_size(), 4);
+        assert_eq!(VkFormat::R32G32B32A32Sfloat.texel_size(), 16);
+        assert_eq!(VkFormat::Undefined.texel_size(), 0);
+        
+        let info = VkImageCreateInfo::default();
+        assert_eq!(info.sType, VkStructureType::ImageCreateInfo);
+        assert_eq!(info.imageType, VkImageType::Type2D);
+        assert_eq!(info.initialLayout, VkImageLayout::Undefined);
+        
+        let barrier = VkImageMemoryBarrier::default();
+        assert_eq!(barrier.sType, VkStructureType::ImageMemoryBarrier);
+        assert_eq!(barrier.newLayout, VkImageLayout::General);
+        assert_eq!(barrier.srcQueueFamilyIndex, VK_QUEUE_FAMILY_IGNORED);
+        assert_eq!(barrier.subresourceRange.aspectMask, VkImageAspectFlags::COLOR);
+    }
+    
+    #[test]
+    fn test_sampler_create_info_default() {
+        let info = VkSamplerCreateInfo::default();
+        assert_eq!(info.sType, VkStructureType::SamplerCreateInfo);
+        assert_eq!(info.magFilter, VkFilter::Nearest);
+        assert_eq!(info.addressModeU, VkSamplerAddressMode::ClampToEdge);
+        assert_eq!(info.unnormalizedCoordinates, VK_FALSE);
+        assert_eq!(VkDescriptorType::CombinedImageSampler as i32, 1);
+    }
+    
+    #[test]
+    fn test_exposed_queue_families() {
+        use crate::api::context::ComputeContext as Ctx;
+        let family = |flags: VkQueueFlags, count: u32| VkQueueFamilyProperties {
+            queueFlags: flags,
+            queueCount: count,
+            timestampValidBits: 64,
+            minImageTransferGranularity: VkExtent3D::default(),
+        };
+        
+        assert!(Ctx::is_exposed_queue_family(&family(VkQueueFlags::COMPUTE, 4)));
+        assert!(Ctx::is_exposed_queue_family(&family(VkQueueFlags::TRANSFER, 2)));
+        assert!(!Ctx::is_exposed_queue_family(&family(VkQueueFlags::SPARSE_BINDING, 1)));
+        assert!(!Ctx::is_exposed_queue_family(&family(VkQueueFlags::COMPUTE, 0)));
+    }
+    
+    #[test]
+    fn test_reaper_idle_shutdown() {
+        use crate::api::events::DeviceEvents;
+        use crate::api::reaper::Reaper;
+        let events = DeviceEvents::new(VkInstance::NULL, &VkPhysicalDeviceProperties::default());
+        let reaper = Reaper::spawn(VkDevice::NULL, std::sync::Arc::new(events), &ThreadConfig::default());
+        assert_eq!(reaper.in_flight(), 0);
+        assert!(reaper.take_retired().is_empty());
+        reaper.shutdown();
+        // A second shutdown is a no-op
+        reaper.shutdown();
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_thread_config_pins_calling_thread() {
+        let cores = std::thread::spawn(|| {
+            ThreadConfig { cores: vec![0], high_priority: false }.apply();
+            unsafe {
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                assert_eq!(libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set), 0);
+                libc::CPU_COUNT(&set)
+            }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(cores, 1);
+        // Unsupported requests leave the thread running
+        std::thread::spawn(|| ThreadConfig { cores: vec![usize::MAX], high_priority: false }.apply()).join().unwrap();
+    }
+    
+    #[test]
+    fn test_flight_limiter_bounds_permits() {
+        let limiter = FlightLimiter::new(2);
+        let first = limiter.acquire();
+        let second = limiter.try_acquire().expect("second slot is free");
+        assert_eq!(limiter.in_flight(), 2);
+        assert!(limiter.try_acquire().is_none());
+        
+        drop(first);
+        assert_eq!(limiter.in_flight(), 1);
+        let third = limiter.try_acquire();
+        assert!(third.is_some());
+        drop((second, third));
+        assert_eq!(limiter.in_flight(), 0);
+        
+        // A zero limit still admits one submission
+        assert_eq!(FlightLimiter::new(0).limit(), 1);
+    }
+    
+    #[test]
+    fn test_flight_limiter_unblocks_on_release() {
+        let limiter = FlightLimiter::new(1);
+        let permit = limiter.acquire();
+        let waiter = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || drop(limiter.acquire()))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        drop(permit);
+        waiter.join().unwrap();
+        assert_eq!(limiter.in_flight(), 0);
+    }
+    
+    #[test]
+    fn test_heap_infos_detect_direct_heap() {
+        type Ctx = ComputeContext;
+        let mut properties = VkPhysicalDeviceMemoryProperties::default();
+        properties.memoryHeapCount = 2;
+        properties.memoryHeaps[0] = VkMemoryHeap { size: 8 << 30, flags: 0x1 };
+        properties.memoryHeaps[1] = VkMemoryHeap { size: 16 << 30, flags: 0 };
+        properties.memoryTypeCount = 2;
+        properties.memoryTypes[0] = VkMemoryType {
+            propertyFlags: VkMemoryPropertyFlags::DEVICE_LOCAL | VkMemoryPropertyFlags::HOST_VISIBLE,
+            heapIndex: 0,
+        };
+        properties.memoryTypes[1] = VkMemoryType {
+            propertyFlags: VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT,
+            heapIndex: 1,
+        };
+        
+        let heaps = Ctx::heap_infos(&properties);
+        assert_eq!(heaps.len(), 2);
+        assert!(heaps[0].is_direct());
+        assert_eq!(heaps[0].size, 8 << 30);
+        assert!(!heaps[1].is_direct());
+        assert!(heaps[1].host_visible);
+        
+        // Without the host-visible device-local type there is no direct heap
+        properties.memoryTypes[0].propertyFlags = VkMemoryPropertyFlags::DEVICE_LOCAL;
+        assert!(!Ctx::heap_infos(&properties)[0].is_direct());
+    }
+    
+    #[test]
+    fn test_specialization_entries_are_packed() {
+        let entries = ComputeContext::specialization_entries(&[(3, 64), (7, 1)]);
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].constantID, entries[0].offset, entries[0].size), (3, 0, 4));
+        assert_eq!((entries[1].constantID, entries[1].offset, entries[1].size), (7, 4, 4));
+        
+        let config = PipelineConfig::default();
+        assert!(config.specialization.is_empty());
+        assert!(!config.allow_derivatives);
+    }
+    
+    #[test]
+    fn test_reflect_saxpy_interface() {
+        use crate::api::reflect::{reflect_spirv, spirv_words};
+        let words = spirv_words(include_bytes!("../../shaders/saxpy.spv"));
+        let interface = reflect_spirv(&words, "main").unwrap();
+        
+        assert_eq!(interface.workgroup_size, Some((256, 1, 1)));
+        assert_eq!(interface.bindings.len(), 3);
+        for (index, binding) in interface.bindings.iter().enumerate() {
+            assert_eq!((binding.set, binding.binding), (0, index as u32));
+            assert_eq!(binding.descriptor_type, VkDescriptorType::StorageBuffer);
+            assert_eq!(binding.array_size, 1);
+        }
+        assert_eq!(interface.binding("BufferB").map(|b| b.binding), Some(1));
+        
+        let push = interface.push_constants.as_ref().unwrap();
+        assert_eq!(push.size, 8);
+        assert_eq!(interface.push_constant("alpha").map(|m| (m.offset, m.size)), Some((0, 4)));
+        assert_eq!(interface.push_constant("count").map(|m| (m.offset, m.size)), Some((4, 4)));
+        
+        assert!(reflect_spirv(&words, "missing").is_err());
+        assert!(reflect_spirv(&[0xdead_beef], "main").is_err());
+    }
+    
+    #[test]
+    fn test_push_constants_to_uniform() {
+        use crate::api::reflect::{push_constants_to_uniform, reflect_spirv, spirv_words};
+        let words = spirv_words(include_bytes!("../../shaders/saxpy.spv"));
+        let push = reflect_spirv(&words, "main").unwrap().push_constants.unwrap();
+        
+        let rewritten = push_constants_to_uniform(&words, 3).unwrap();
+        let interface = reflect_spirv(&rewritten, "main").unwrap();
+        assert!(interface.push_constants.is_none());
+        assert_eq!(interface.bindings.len(), 4);
+        let uniform = &interface.bindings[3];
+        assert_eq!((uniform.set, uniform.binding, uniform.descriptor_type), (0, 3, VkDescriptorType::UniformBuffer));
+        let layout = uniform.layout.as_ref().unwrap();
+        assert_eq!(layout.size, push.size);
+        let offsets: Vec<u32> = layout.members.iter().map(|member| member.offset).collect();
+        assert_eq!(offsets, push.members.iter().map(|member| member.offset).collect::<Vec<_>>());
+        
+        // Only one block can move, and it has to exist
+        assert!(push_constants_to_uniform(&rewritten, 3).is_err());
+        
+        let instruction = |opcode: u32, operands: &[u32]| {
+            let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+            words.extend_from_slice(operands);
+            words
+        };
+        // `float scale; float weights[4]` with std430 offsets and strides
+        let module = |array_stride: u32, weights_offset: u32| -> Vec<u32> {
+            [
+                vec![0x0723_0203, 0x0001_0000, 0, 20, 0],
+                instruction(71, &[5, 2]),                     // OpDecorate %5 Block
+                instruction(72, &[5, 0, 35, 0]),              // OpMemberDecorate %5 0 Offset 0
+                instruction(72, &[5, 1, 35, weights_offset]), // OpMemberDecorate %5 1 Offset
+                instruction(71, &[4, 6, array_stride]),       // OpDecorate %4 ArrayStride
+                instruction(22, &[1, 32]),                    // %1 = OpTypeFloat 32
+                instruction(21, &[2, 32, 0]),                 // %2 = OpTypeInt 32 0
+                instruction(43, &[2, 3, 4]),                  // %3 = OpConstant %2 4
+                instruction(28, &[4, 1, 3]),                  // %4 = OpTypeArray %1 %3
+                instruction(30, &[5, 1, 4]),                  // %5 = OpTypeStruct %1 %4
+                instruction(32, &[6, 9, 5]),                  // %6 = OpTypePointer PushConstant %5
+                instruction(32, &[7, 2, 1]),                  // %7 = OpTypePointer Uniform %1
+                instruction(32, &[8, 9, 1]),                  // %8 = OpTypePointer PushConstant %1
+                instruction(59, &[6, 10, 9]),                 // %10 = OpVariable %6 PushConstant
+                instruction(1, &[8, 11]),                     // %11 = OpUndef %8
+            ]
+            .concat()
+        };
+        
+        // std140 needs 16-byte array strides and alignment
+        assert!(matches!(push_constants_to_uniform(&module(4, 4), 0), Err(KronosError::ShaderCompilationFailed(_))));
+        assert!(push_constants_to_uniform(&module(16, 4), 0).is_err());
+        let rewritten = push_constants_to_uniform(&module(16, 16), 0).unwrap();
+        
+        // The rewritten %8 repeats %7 and is merged into it
+        let mut instructions = Vec::new();
+        let mut cursor = 5;
+        while cursor < rewritten.len() {
+            let count = (rewritten[cursor] >> 16) as usize;
+            instructions.push(&rewritten[cursor..cursor + count]);
+            cursor += count;
+        }
+        let uniform_floats = instructions.iter().filter(|words| words[0] & 0xffff == 32 && words[2] == 2 && words[3] == 1).count();
+        assert_eq!(uniform_floats, 1);
+        assert!(instructions.contains(&&[(3 << 16) | 1, 7, 11][..]));
+        
+        // Operands past the end of an instruction are refused
+        let mut truncated = module(16, 16);
+        truncated.extend(instruction(32, &[12, 9]));
+        assert!(push_constants_to_uniform(&truncated, 0).is_err());
+    }
+    
+    #[test]
+    fn test_spirv_linker_cache() {
+        let a: &[u8] = &[1, 2, 3, 4];
+        let b: &[u8] = &[5, 6, 7, 8];
+        let key = SpirvLinker::cache_key(&[a, b]);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, SpirvLinker::cache_key(&[a, b]));
+        assert_ne!(key, SpirvLinker::cache_key(&[b, a]));
+        
+        // A cached result is returned without running the tool
+        let dir = std::env::temp_dir().join(format!("kronos-link-test-{}", std::process::id()));
+        let linked: Vec<u8> = [0x0723_0203u32, 0x0001_0000, 0, 1, 0].iter().flat_map(|w| w.to_le_bytes()).collect();
+        let linker = SpirvLinker::new().tool("/nonexistent/spirv-link").cache_dir(&dir);
+        assert!(linker.link(&[a, b]).is_err());
+        std::fs::write(dir.join(format!("{}.spv", key)), &linked).unwrap();
+        assert_eq!(linker.link(&[a, b]).unwrap(), linked);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        
+        // A cached file that is not SPIR-V is linked again
+        std::fs::write(dir.join(format!("{}.spv", key)), [9, 9, 9, 9]).unwrap();
+        assert!(linker.link(&[a, b]).is_err());
+        std::fs::write(dir.join(format!("{}.spv", key)), &linked[..18]).unwrap();
+        assert!(linker.link(&[a, b]).is_err());
+        
+        // A missing tool is reported rather than panicking
+        assert!(linker.without_cache().link(&[a, b]).is_err());
+        assert!(SpirvLinker::new().link(&[]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_philox_reference_and_params() {
+        use crate::api::kernels::rng::{philox4x32_10, Distribution, RngParams, PHILOX_SPIRV};
+        // Random123 known-answer vectors for philox4x32_10
+        assert_eq!(philox4x32_10([0; 4], [0; 2]), [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]);
+        assert_eq!(
+            philox4x32_10([0xffffffff; 4], [0xffffffff; 2]),
+            [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]
+        );
+        
+        let params = RngParams::new(Distribution::Normal { mean: 1.0, std_dev: 0.5 }, 0x1234_5678_9abc_def0, 1025, 3);
+        assert_eq!((params.seed_lo, params.seed_hi), (0x9abc_def0, 0x1234_5678));
+        assert_eq!((params.distribution, params.param_a, params.param_b), (2, 1.0, 0.5));
+        assert_eq!(params.workgroups(), 2);
+        assert_eq!(std::mem::size_of::<RngParams>(), 28);
+        
+        let words = crate::api::reflect::spirv_words(PHILOX_SPIRV);
+        let interface = crate::api::reflect::reflect_spirv(&words, "main").unwrap();
+        assert_eq!(interface.push_constants.map(|block| block.size), Some(28));
+    }
+    
+    #[test]
+    fn test_sort_kernel_helpers() {
+        use crate::api::kernels::sort::{bitonic_steps, float_to_key, key_to_float, RadixParams};
+        
+        // Simulate the bitonic network on the CPU, including non-power-of-two sizes
+        for count in [2u32, 3, 7, 64, 100] {
+            let mut keys: Vec<u32> = (0..count).map(|i| i.wrapping_mul(2_654_435_761) >> 7).collect();
+            let padded = count.next_power_of_two();
+            for step in bitonic_steps(count) {
+                for t in 0..padded / 2 {
+                    let (lo, hi) = if step.flip != 0 {
+                        let base = (t / step.step) * step.block;
+                        (base + t % step.step, base + step.block - 1 - t % step.step)
+                    } else {
+                        let lo = (t / step.step) * 2 * step.step + t % step.step;
+                        (lo, lo + step.step)
+                    };
+                    if hi < count && keys[lo as usize] > keys[hi as usize] {
+                        keys.swap(lo as usize, hi as usize);
+                    }
+                }
+            }
+            assert!(keys.windows(2).all(|w| w[0] <= w[1]), "count {}", count);
+        }
+        
+        let floats = [-3.5f32, -0.0, 0.0, 1.0e-30, 2.0, f32::INFINITY, f32::NEG_INFINITY];
+        let mut keys: Vec<u32> = floats.iter().map(|f| float_to_key(f.to_bits())).collect();
+        keys.sort_unstable();
+        let sorted: Vec<f32> = keys.iter().map(|&k| f32::from_bits(key_to_float(k))).collect();
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(sorted[0], f32::NEG_INFINITY);
+        
+        assert_eq!(RadixParams::groups_for(1024), 1);
+        assert_eq!(RadixParams::groups_for(1025), 2);
+    }
+    
+    #[test]
+    fn test_builtin_kernel_interfaces() {
+        use crate::api::kernels::{batched, compact, histogram, predicate, rng, scan, sort};
+        use crate::api::reflect::{reflect_spirv, spirv_words};
+        let kernels: [(&[u8], usize, usize); 9] = [
+            (rng::PHILOX_SPIRV, 1, std::mem::size_of::<rng::RngParams>()),
+            (scan::SCAN_SPIRV, 2, std::mem::size_of::<scan::ScanParams>()),
+            (sort::RADIX_COUNT_SPIRV, 2, std::mem::size_of::<sort::RadixParams>()),
+            (sort::RADIX_SCATTER_SPIRV, 5, std::mem::size_of::<sort::RadixParams>()),
+            (sort::FLOAT_KEYS_SPIRV, 1, std::mem::size_of::<sort::FloatKeyParams>()),
+            (sort::BITONIC_SPIRV, 1, std::mem::size_of::<sort::BitonicParams>()),
+            (histogram::HISTOGRAM_SPIRV, 2, std::mem::size_of::<histogram::HistogramParams>()),
+            (compact::COMPACT_FLAGS_SPIRV, 2, std::mem::size_of::<compact::CompactParams>()),
+            (compact::COMPACT_SCATTER_SPIRV, 4, std::mem::size_of::<compact::CompactParams>()),
+        ];
+        for (index, (spirv, bindings, push_size)) in kernels.into_iter().enumerate() {
+            let interface = reflect_spirv(&spirv_words(spirv), "main").unwrap();
+            assert_eq!(interface.workgroup_size, Some((256, 1, 1)), "kernel {}", index);
+            assert_eq!(interface.bindings.len(), bindings, "kernel {}", index);
+            assert!(interface.bindings.iter().enumerate().all(|(i, b)| b.binding == i as u32 && b.set == 0));
+            assert_eq!(interface.push_constants.map(|p| p.size as usize), Some(push_size), "kernel {}", index);
+        }
+        
+        let interface = reflect_spirv(&spirv_words(predicate::PREDICATE_ARGS_SPIRV), "main").unwrap();
+        assert_eq!(interface.workgroup_size, Some((predicate::LOCAL_SIZE, 1, 1)));
+        assert_eq!(interface.bindings.len(), 2);
+        assert_eq!(
+            interface.push_constants.map(|p| p.size as usize),
+            Some(std::mem::size_of::<predicate::PredicateParams>())
+        );
+        
+        for (spirv, bindings) in [(batched::AXPY_BATCHED_SPIRV, 3), (batched::GEMV_BATCHED_SPIRV, 4)] {
+            let interface = reflect_spirv(&spirv_words(spirv), "main").unwrap();
+            assert_eq!(interface.workgroup_size, Some((batched::LOCAL_SIZE, 1, 1)));
+            assert_eq!(interface.bindings.len(), bindings);
+            assert_eq!(interface.push_constants.map(|p| p.size as usize), Some(std::mem::size_of::<batched::BatchedParams>()));
+        }
+        assert_eq!(batched::workgroups(5000, 65535), (5000, 1, 1));
+        assert_eq!(batched::workgroups(65536, 65535), (65535, 2, 1));
+        
+        // scan.comp declares `shared uint partial[256]`
+        let interface = reflect_spirv(&spirv_words(scan::SCAN_SPIRV), "main").unwrap();
+        assert_eq!(interface.shared_memory.len(), 1);
+        assert_eq!((interface.shared_memory[0].element_size, interface.shared_memory[0].length_constant), (Some(4), None));
+        assert_eq!(interface.shared_memory_size(&[]), 1024);
+    }
+    
+    #[test]
+    fn test_optimization_report_breakdown() {
+        use crate::implementation::timeline_batching::BatchStats;
+        use std::time::Duration;
+        
+        let scan: Vec<u64> = (1..=100).collect();
+        let sort = [500u64, 700];
+        let report = OptimizationReport::from_samples(
+            [("scan", scan.as_slice()), ("sort", &sort[..]), ("idle", &[][..])],
+            BatchStats::default(),
+            0,
+        );
+        
+        let labels: Vec<&str> = report.pipelines.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["scan", "sort", "idle"]);
+        let timing = report.pipeline("scan").unwrap();
+        assert_eq!(timing.dispatches, 100);
+        assert_eq!(timing.total, Duration::from_nanos(5050));
+        assert_eq!(timing.average, Duration::from_nanos(50));
+        assert_eq!(timing.p95, Duration::from_nanos(95));
+        assert_eq!(report.pipeline("sort").unwrap().p95, Duration::from_nanos(700));
+        assert_eq!(report.pipeline("idle").unwrap().average, Duration::ZERO);
+        assert_eq!(report.total_gpu_time(), Duration::from_nanos(6250));
+        assert!(report.to_string().contains("scan"));
+    }
+    
+    #[test]
+    fn test_pipeline_samples_are_bounded() {
+        use super::super::timing::{LabelSamples, SAMPLES_PER_PIPELINE};
+        use std::time::Duration;
+        
+        // Early slow dispatches age out of the percentile, not the totals
+        let mut samples = LabelSamples::default();
+        for _ in 0..100 {
+            samples.push(1000);
+        }
+        for _ in 0..SAMPLES_PER_PIPELINE {
+            samples.push(10);
+        }
+        let timing = samples.timing("scan");
+        assert_eq!(timing.dispatches, SAMPLES_PER_PIPELINE as u64 + 100);
+        assert_eq!(timing.total, Duration::from_nanos(100 * 1000 + SAMPLES_PER_PIPELINE as u64 * 10));
+        assert_eq!(timing.p95, Duration::from_nanos(10));
+    }
+    
+    #[test]
+    fn test_performance_counter_lookup() {
+        let counter = PerformanceCounter {
+            index: 7,
+            name: "L2 Cache Hit".to_string(),
+            category: "Memory".to_string(),
+            description: String::new(),
+            unit: VkPerformanceCounterUnitKHR::Percentage,
+            storage: VkPerformanceCounterStorageKHR::Float64,
+            scope: VkPerformanceCounterScopeKHR::CommandBuffer,
+            uuid: [0; VK_UUID_SIZE],
+            flags: VkPerformanceCounterDescriptionFlagsKHR::empty(),
+        };
+        assert!(counter.matches("L2CacheHit"));
+        assert!(counter.matches("l2_cache_hit"));
+        assert!(!counter.matches("L2CacheMiss"));
+        
+        let raw = VkPerformanceCounterResultKHR { float64: 87.5 };
+        assert_eq!(CounterResult::from_raw(counter.storage, raw), CounterResult::Float64(87.5));
+        let raw = VkPerformanceCounterResultKHR { uint64: 1 << 40 };
+        let value = CounterResult::from_raw(VkPerformanceCounterStorageKHR::Uint64, raw);
+        assert_eq!(value.as_f64(), (1u64 << 40) as f64);
+        let raw = VkPerformanceCounterResultKHR { int32: -3 };
+        assert_eq!(CounterResult::from_raw(VkPerformanceCounterStorageKHR::Int32, raw).to_string(), "-3");
+    }
+    
+    #[test]
+    fn test_command_listing_display() {
+        let listing = CommandListing {
+            dispatches: vec![PlannedDispatch {
+                label: "ops::scan".into(),
+                queue_family: 0,
+                blocking: true,
+                commands: vec![
+                    PlannedCommand::PipelineBarrier {
+                        src_stage: VkPipelineStageFlags::TOP_OF_PIPE,
+                        dst_stage: VkPipelineStageFlags::COMPUTE_SHADER,
+                        buffers: vec![(VkBuffer::from_raw(0x10), 4096)],
+                    },
+                    PlannedCommand::BindPipeline { pipeline: VkPipeline::from_raw(0x20) },
+                    PlannedCommand::BindDescriptorSet {
+                        set: VkDescriptorSet::from_raw(0x30),
+                        persistent: false,
+                        bindings: vec![(0, PlannedResource::Buffer { buffer: VkBuffer::from_raw(0x10), size: 4096 })],
+                    },
+                    PlannedCommand::PushConstants { data: vec![1, 0, 0, 0] },
+                    PlannedCommand::Dispatch { x: 16, y: 1, z: 1 },
+                ],
+            }],
+        };
+        
+        let text = listing.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "#0 ops::scan on queue family 0 (execute)");
+        assert!(lines[1].starts_with("    vkCmdPipelineBarrier"));
+        assert_eq!(lines[2], "      buffer 0x10 [0..4096)");
+        assert_eq!(lines[3], "    vkCmdBindPipeline 0x20");
+        assert_eq!(lines[4], "    vkCmdBindDescriptorSets 0x30");
+        assert_eq!(lines[5], "      binding 0: buffer 0x10 (4096 bytes)");
+        assert_eq!(lines[6], "    vkCmdPushConstants 4 bytes: 01 00 00 00");
+        assert_eq!(lines[7], "    vkCmdDispatch 16 x 1 x 1");
+        assert_eq!(listing.len(), 1);
+    }
+    
+    #[test]
+    fn test_bind_state_skips_repeated_state() {
+        use crate::api::command::BindState;
+        let pipeline = VkPipeline::from_raw(0x20);
+        let layout = VkPipelineLayout::from_raw(0x21);
+        let set = VkDescriptorSet::from_raw(0x30);
+        let mut state = BindState::default();
+        
+        assert!(state.bind_pipeline(pipeline));
+        assert!(!state.bind_pipeline(pipeline));
+        assert!(state.bind_pipeline(VkPipeline::from_raw(0x22)));
+        
+        assert!(state.bind_descriptor_set(layout, set, None));
+        assert!(!state.bind_descriptor_set(layout, set, None));
+        assert!(state.bind_descriptor_set(layout, set, Some(256)));
+        // A different layout needs the set bound again
+        assert!(state.bind_descriptor_set(VkPipelineLayout::from_raw(0x23), set, Some(256)));
+        
+        assert!(state.push_constants(layout, &[1, 0, 0, 0]));
+        assert!(!state.push_constants(layout, &[1, 0, 0, 0]));
+        assert!(state.push_constants(layout, &[2, 0, 0, 0]));
+    }
+    
+    #[cfg(feature = "telemetry")]
+    #[test]
+    fn test_sysfs_telemetry_probe() {
+        use crate::api::telemetry::{SysfsProbe, TelemetryProbe};
+        use std::time::{Duration, Instant};
+        
+        let card = std::env::temp_dir().join(format!("kronos-sysfs-{}", std::process::id()));
+        let hwmon = card.join("device/hwmon/hwmon3");
+        std::fs::create_dir_all(&hwmon).unwrap();
+        for (file, value) in [
+            ("freq1_input", "1800000000\n"),
+            ("freq2_input", "1000000000\n"),
+            ("power1_average", "152500000\n"),
+            ("temp1_input", "65000\n"),
+        ] {
+            std::fs::write(hwmon.join(file), value).unwrap();
+        }
+        
+        let start = Instant::now();
+        let mut probe = SysfsProbe::at(&card);
+        let fast = probe.read(start);
+        std::fs::write(hwmon.join("freq1_input"), "1200000000\n").unwrap();
+        let slow = probe.read(start + Duration::from_millis(10));
+        std::fs::remove_dir_all(&card).unwrap();
+        
+        assert_eq!(fast.graphics_clock_mhz, Some(1800));
+        assert_eq!(fast.memory_clock_mhz, Some(1000));
+        assert_eq!(fast.power_watts, Some(152.5));
+        assert_eq!(fast.temperature_c, Some(65.0));
+        
+        let steady = TelemetrySummary::from_samples(&[fast], Some(1800));
+        assert!(!steady.is_throttled());
+        let throttled = TelemetrySummary::from_samples(&[fast, slow], Some(1800));
+        assert_eq!(throttled.min_graphics_clock_mhz, Some(1200));
+        assert_eq!(throttled.average_power_watts, Some(152.5));
+        assert!(throttled.is_throttled());
+    }
+    
+    #[cfg(all(feature = "telemetry", unix))]
+    #[test]
+    fn test_telemetry_finds_the_device_by_pci_address() {
+        use crate::api::telemetry::{nvml_uuid, SysfsProbe, TelemetryProbe};
+        use std::time::Instant;
+        
+        let address = PciBusAddress::parse("0000:04:00.0").unwrap();
+        assert_eq!(address, PciBusAddress { domain: 0, bus: 4, device: 0, function: 0 });
+        assert_eq!(address.to_string(), "0000:04:00.0");
+        assert_eq!(PciBusAddress::parse("0000:04:00"), None);
+        assert_eq!(
+            nvml_uuid(b"\x6b\x1c\x1d\x5e\x00\x11\x22\x33\x44\x55\x66\x77\x88\x99\xaa\xbb"),
+            "GPU-6b1c1d5e-0011-2233-4455-66778899aabb"
+        );
+        
+        // Two identical GPUs: only the address tells them apart
+        let root = std::env::temp_dir().join(format!("kronos-drm-{}", std::process::id()));
+        for (card, slot, clock) in [("card0", "0000:03:00.0", "1000000000\n"), ("card1", "0000:04:00.0", "2000000000\n")] {
+            let device = root.join("devices").join(slot);
+            std::fs::create_dir_all(device.join("hwmon/hwmon0")).unwrap();
+            std::fs::write(device.join("vendor"), "0x1002\n").unwrap();
+            std::fs::write(device.join("device"), "0x744c\n").unwrap();
+            std::fs::write(device.join("hwmon/hwmon0/freq1_input"), clock).unwrap();
+            std::fs::create_dir_all(root.join(card)).unwrap();
+            std::os::unix::fs::symlink(&device, root.join(card).join("device")).unwrap();
+        }
+        let found = SysfsProbe::find(&root, address).map(|mut probe| probe.read(Instant::now()));
+        let missing = SysfsProbe::find(&root, PciBusAddress::parse("0000:05:00.0").unwrap()).is_none();
+        std::fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(found.unwrap().graphics_clock_mhz, Some(2000));
+        assert!(missing);
+    }
+    
+    #[test]
+    fn test_deferred_destruction_waits_for_submissions() {
+        use super::super::deferred::{DeferredDestruction, Destructor, LastUse};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        
+        let queue = Arc::new(DeferredDestruction::default());
+        let destroyed = Arc::new(AtomicUsize::new(0));
+        let destructor = || -> Destructor {
+            let destroyed = destroyed.clone();
+            Box::new(move || {
+                destroyed.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        
+        queue.destroy(Arc::new(LastUse::default()), destructor());
+        assert_eq!(destroyed.load(Ordering::Relaxed), 1);
+        
+        let used = Arc::new(LastUse::default());
+        let bound = used.clone();
+        let serial = queue.begin_submission([&bound]);
+        // A later submission finishing first does not retire the earlier one
+        let later = queue.begin_submission([]);
+        queue.destroy(used, destructor());
+        drop(bound);
+        drop(later);
+        assert_eq!((destroyed.load(Ordering::Relaxed), queue.pending()), (1, 1));
+        drop(serial);
+        assert_eq!((destroyed.load(Ordering::Relaxed), queue.pending()), (2, 0));
+    }
+    
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_owned_handle_checks_device() {
+        let (first, second) = (DeviceId::next(), DeviceId::next());
+        assert_ne!(first, second);
+        
+        let buffer = Owned::new(VkBuffer::from_raw(7), first);
+        assert_eq!(buffer.on(first), VkBuffer::from_raw(7));
+        let mixed = std::panic::catch_unwind(|| buffer.on(second));
+        assert!(mixed.is_err());
+    }
+    
+    #[test]
+    #[cfg(feature = "object-registry")]
+    fn test_owned_handles_are_numbered() {
+        let device = DeviceId::next();
+        let raw = 0xdead_0000 + std::process::id() as u64;
+        let first = Owned::new(VkBuffer::from_raw(raw), device);
+        let (index, _) = registry::lookup("VkBuffer", raw).unwrap();
+        assert_eq!(format!("{:?}", first.raw()), format!("VkBuffer(#{}, {})", index, device));
+        
+        // The same value as another type is numbered separately
+        let memory = Owned::new(VkDeviceMemory::from_raw(raw), device);
+        assert_eq!(registry::describe(raw, device).len(), 2);
+        assert!(registry::describe(raw, DeviceId::next()).is_empty());
+        
+        drop(first);
+        assert!(registry::lookup("VkBuffer", raw).is_none());
+        assert!(registry::lookup("VkDeviceMemory", raw).is_some());
+        drop(memory);
+        assert!(format!("{:?}", VkBuffer::from_raw(raw)).contains("raw:"));
+    }
+}
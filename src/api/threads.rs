@@ -0,0 +1,104 @@
+//! Placement of Kronos's background threads
+//!
+//! Non-blocking submissions are completed by a reaper thread that polls
+//! their fences, and a [`Scheduler`](super::Scheduler) submits from a
+//! thread of its own. Both are named (`kronos-reaper`, `kronos-scheduler`)
+//! and by default left to the OS. Latency-sensitive pipelines can keep
+//! them off the cores that record work, or away from cores shared with
+//! noisy neighbours, with a [`ThreadConfig`]:
+//!
+//! ```no_run
+//! use kronos_compute::api::{ComputeContext, ThreadConfig};
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::builder()
+//!     .thread_config(ThreadConfig { cores: vec![2, 3], high_priority: true })
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Pinning is soft: cores are applied where the platform supports
+//! affinity (Linux and Android) and priorities where it lets the process
+//! raise them (Linux with `CAP_SYS_NICE`, macOS). Anything else is logged
+//! and the thread runs unpinned.
+
+/// Cores and priority of Kronos's background threads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadConfig {
+    /// Cores the threads may run on; empty leaves placement to the OS
+    pub cores: Vec<usize>,
+    /// Run the threads ahead of normal-priority threads
+    pub high_priority: bool,
+}
+
+/// Nice value of high-priority threads on Linux and Android
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const HIGH_PRIORITY_NICE: libc::c_int = -10;
+
+impl ThreadConfig {
+    /// Apply the configuration to the calling thread
+    pub(super) fn apply(&self) {
+        if !self.cores.is_empty() {
+            if let Err(reason) = self.pin() {
+                kronos_log!(Warn, "[SAFE API] Could not pin thread to cores {:?}: {}", self.cores, reason);
+            }
+        }
+        if self.high_priority {
+            if let Err(reason) = raise_priority() {
+                kronos_log!(Warn, "[SAFE API] Could not raise thread priority: {}", reason);
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn pin(&self) -> Result<(), String> {
+        // SAFETY: the set is plain data, zeroed and filled with the CPU_*
+        // helpers, and sched_setaffinity only reads it
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_ZERO(&mut set);
+            for &core in &self.cores {
+                if core >= libc::CPU_SETSIZE as usize {
+                    return Err(format!("core {} is beyond CPU_SETSIZE", core));
+                }
+                libc::CPU_SET(core, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn pin(&self) -> Result<(), String> {
+        Err("thread affinity is not supported on this platform".into())
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn raise_priority() -> Result<(), String> {
+    // SAFETY: gettid has no preconditions; on Linux setpriority with a
+    // thread ID changes only that thread
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, HIGH_PRIORITY_NICE) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_vendor = "apple")]
+fn raise_priority() -> Result<(), String> {
+    // SAFETY: only changes the QoS class of the calling thread
+    let result = unsafe { libc::pthread_set_qos_class_self_np(libc::qos_class_t::QOS_CLASS_USER_INTERACTIVE, 0) };
+    if result != 0 {
+        return Err(std::io::Error::from_raw_os_error(result).to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+fn raise_priority() -> Result<(), String> {
+    Err("thread priorities are not supported on this platform".into())
+}
@@ -0,0 +1,500 @@
+//! Per-dispatch GPU timing and the optimization report
+//!
+//! When timing is enabled every dispatch, and every step of a
+//! [`then`](CommandBuilder::then) chain, writes a begin and end timestamp
+//! into a slot of a shared query pool. Slots are read back once the
+//! dispatch completes (after `execute` returns, or on the reaper thread for
+//! `submit`) and the elapsed GPU time is attributed to the pipeline's
+//! [label](Pipeline::label). Dispatches that find every slot in use run
+//! untimed rather than waiting.
+//!
+//! Recent timed dispatches are also kept as a trace, which carries device
+//! telemetry when the `telemetry` feature is enabled. `minimal` builds
+//! keep no trace, only the per-pipeline timing.
+
+use super::*;
+use crate::*; // Need all the type definitions
+use crate::implementation::timeline_batching::BatchStats;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+#[cfg(feature = "telemetry")]
+use super::telemetry::{TelemetrySampler, TelemetrySummary};
+
+/// Dispatches that can be timed concurrently (two queries each)
+pub const TIMING_SLOTS: u32 = 1024;
+
+/// Most recent dispatches kept in the trace
+pub const TRACE_CAPACITY: usize = 4096;
+
+/// Most recent GPU times kept per pipeline for its percentile; counts and
+/// totals cover every dispatch
+pub const SAMPLES_PER_PIPELINE: usize = 1024;
+
+/// Timestamp query pool and the samples collected from it
+pub(super) struct GpuTimer {
+    device: VkDevice,
+    pool: VkQueryPool,
+    /// Nanoseconds per timestamp tick
+    period_ns: f64,
+    /// Trace times are relative to this instant
+    #[cfg_attr(feature = "minimal", allow(dead_code))]
+    epoch: Instant,
+    enabled: AtomicBool,
+    /// GPU time of every timed dispatch, kept across `reset`
+    total_ns: AtomicU64,
+    state: Mutex<TimerState>,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for GpuTimer {}
+unsafe impl Sync for GpuTimer {}
+
+#[derive(Default)]
+struct TimerState {
+    free_slots: Vec<u32>,
+    /// Elapsed nanoseconds per pipeline label
+    samples: HashMap<Arc<str>, LabelSamples>,
+    batching: BatchStats,
+    untimed_dispatches: u64,
+    trace: VecDeque<DispatchTrace>,
+    #[cfg(feature = "telemetry")]
+    telemetry: Option<Arc<TelemetrySampler>>,
+}
+
+/// GPU times of one pipeline: exact totals and a window of recent samples
+#[derive(Default)]
+pub(super) struct LabelSamples {
+    dispatches: u64,
+    total_ns: u64,
+    /// The last [`SAMPLES_PER_PIPELINE`] samples, in completion order
+    recent: VecDeque<u64>,
+}
+
+impl LabelSamples {
+    pub(super) fn push(&mut self, nanos: u64) {
+        self.dispatches += 1;
+        self.total_ns += nanos;
+        if self.recent.len() == SAMPLES_PER_PIPELINE {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(nanos);
+    }
+
+    pub(super) fn timing(&self, label: &str) -> PipelineTiming {
+        let recent: Vec<u64> = self.recent.iter().copied().collect();
+        PipelineTiming {
+            dispatches: self.dispatches,
+            total: Duration::from_nanos(self.total_ns),
+            average: Duration::from_nanos(self.total_ns.checked_div(self.dispatches).unwrap_or(0)),
+            ..PipelineTiming::from_samples(label, &recent)
+        }
+    }
+}
+
+impl GpuTimer {
+    /// Create the query pool for `device`
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must be the device `device` was created from.
+    pub(super) unsafe fn new(physical_device: VkPhysicalDevice, device: VkDevice) -> Result<Self> {
+        let mut properties = [0u64; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8];
+        vkGetPhysicalDeviceProperties(physical_device, properties.as_mut_ptr() as *mut VkPhysicalDeviceProperties);
+        let offset = VK_PHYSICAL_DEVICE_PROPERTIES_TIMESTAMP_PERIOD_OFFSET;
+        let bytes = std::slice::from_raw_parts(properties.as_ptr() as *const u8, VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE);
+        let period = f32::from_ne_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        if period.is_nan() || period <= 0.0 {
+            return Err(KronosError::UnsupportedHardware(
+                "Device reports no timestamp period".into(),
+            ));
+        }
+
+        let create_info = VkQueryPoolCreateInfo {
+            sType: VkStructureType::QueryPoolCreateInfo,
+            pNext: ptr::null(),
+            flags: 0,
+            queryType: VkQueryType::Timestamp,
+            queryCount: TIMING_SLOTS * 2,
+            pipelineStatistics: 0,
+        };
+        let mut pool = VkQueryPool::NULL;
+        let result = vkCreateQueryPool(device, &create_info, ptr::null(), &mut pool);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+
+        Ok(Self {
+            device,
+            pool,
+            period_ns: period as f64,
+            epoch: Instant::now(),
+            enabled: AtomicBool::new(false),
+            total_ns: AtomicU64::new(0),
+            state: Mutex::new(TimerState {
+                // Popped from the back, so slot 0 is used first
+                free_slots: (0..TIMING_SLOTS).rev().collect(),
+                ..Default::default()
+            }),
+        })
+    }
+
+    pub(super) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+
+    /// Reserve a slot for a dispatch of `label`, if timing is on and one is free
+    pub(super) fn begin(self: &Arc<Self>, label: Arc<str>) -> Option<TimedDispatch> {
+        if !self.enabled.load(Ordering::Acquire) {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        match state.free_slots.pop() {
+            Some(slot) => Some(TimedDispatch {
+                timer: self.clone(),
+                slot,
+                label,
+                started: Instant::now(),
+            }),
+            None => {
+                state.untimed_dispatches += 1;
+                None
+            }
+        }
+    }
+
+    /// Count a submission of `dispatches` dispatches, some of them timed
+    pub(super) fn record_submission(&self, dispatches: usize) {
+        self.state.lock().unwrap().batching.record_submission(dispatches);
+    }
+
+    /// Forget all samples collected so far
+    pub(super) fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.samples.clear();
+        state.batching = BatchStats::default();
+        state.untimed_dispatches = 0;
+        state.trace.clear();
+    }
+
+    /// GPU time of every dispatch timed so far, including before a reset
+    pub(super) fn total_gpu_time(&self) -> Duration {
+        Duration::from_nanos(self.total_ns.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn trace(&self) -> Vec<DispatchTrace> {
+        self.state.lock().unwrap().trace.iter().cloned().collect()
+    }
+
+    /// Attach (or with `None`, stop and detach) a telemetry sampler
+    #[cfg(feature = "telemetry")]
+    pub(super) fn set_telemetry(&self, sampler: Option<Arc<TelemetrySampler>>) {
+        let previous = std::mem::replace(&mut self.state.lock().unwrap().telemetry, sampler);
+        if let Some(previous) = previous {
+            previous.stop();
+        }
+    }
+
+    #[cfg(feature = "telemetry")]
+    pub(super) fn telemetry(&self) -> Option<Arc<TelemetrySampler>> {
+        self.state.lock().unwrap().telemetry.clone()
+    }
+
+    pub(super) fn report(&self) -> OptimizationReport {
+        let state = self.state.lock().unwrap();
+        OptimizationReport::from_timings(
+            state.samples.iter().map(|(label, samples)| samples.timing(label)).collect(),
+            state.batching.clone(),
+            state.untimed_dispatches,
+        )
+    }
+
+    /// Destroy the query pool
+    ///
+    /// # Safety
+    ///
+    /// No submitted command buffer may still reference the pool.
+    pub(super) unsafe fn destroy(&self) {
+        vkDestroyQueryPool(self.device, self.pool, ptr::null());
+    }
+}
+
+/// Timestamp slot held by one dispatch
+///
+/// Dropping it without [`finish`](Self::finish) (e.g. when submission
+/// fails) returns the slot without recording a sample.
+pub(super) struct TimedDispatch {
+    timer: Arc<GpuTimer>,
+    slot: u32,
+    label: Arc<str>,
+    /// When the dispatch was recorded, bounding its GPU start
+    #[cfg_attr(feature = "minimal", allow(dead_code))]
+    started: Instant,
+}
+
+impl TimedDispatch {
+    /// Record the begin timestamp; call before any dispatch commands
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be recording on a queue family with nonzero
+    /// `timestampValidBits`.
+    pub(super) unsafe fn write_begin(&self, command_buffer: VkCommandBuffer) {
+        vkCmdResetQueryPool(command_buffer, self.timer.pool, self.slot * 2, 2);
+        vkCmdWriteTimestamp(command_buffer, VkPipelineStageFlags::TOP_OF_PIPE, self.timer.pool, self.slot * 2);
+    }
+
+    /// Record the end timestamp; call after the dispatch
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`write_begin`](Self::write_begin).
+    pub(super) unsafe fn write_end(&self, command_buffer: VkCommandBuffer) {
+        vkCmdWriteTimestamp(command_buffer, VkPipelineStageFlags::BOTTOM_OF_PIPE, self.timer.pool, self.slot * 2 + 1);
+    }
+
+    /// Read back the timestamps of a completed dispatch and record them
+    ///
+    /// `valid_bits` is the queue family's `timestampValidBits`.
+    pub(super) fn finish(self, valid_bits: u32) {
+        let mut ticks = [0u64; 2];
+        let result = unsafe {
+            vkGetQueryPoolResults(
+                self.timer.device,
+                self.timer.pool,
+                self.slot * 2,
+                2,
+                std::mem::size_of_val(&ticks),
+                ticks.as_mut_ptr() as *mut _,
+                std::mem::size_of::<u64>() as VkDeviceSize,
+                VkQueryResultFlags::TYPE_64 | VkQueryResultFlags::WAIT,
+            )
+        };
+        if result != VkResult::Success {
+            kronos_log!(Warn, "Failed to read GPU timestamps for '{}': {:?}", self.label, result);
+            return;
+        }
+
+        let mask = if valid_bits >= 64 { u64::MAX } else { (1u64 << valid_bits) - 1 };
+        let elapsed = (ticks[1] & mask).wrapping_sub(ticks[0] & mask) & mask;
+        let nanos = (elapsed as f64 * self.timer.period_ns).round() as u64;
+
+        self.timer.total_ns.fetch_add(nanos, Ordering::Relaxed);
+        let mut state = self.timer.state.lock().unwrap();
+        state.samples.entry(self.label.clone()).or_default().push(nanos);
+        #[cfg(not(feature = "minimal"))]
+        {
+            let gpu_time = Duration::from_nanos(nanos);
+            let completed = Instant::now();
+            let gpu_start = completed.checked_sub(gpu_time).map_or(self.started, |start| start.max(self.started));
+            if state.trace.len() == TRACE_CAPACITY {
+                state.trace.pop_front();
+            }
+            let entry = DispatchTrace {
+                label: self.label.to_string(),
+                start: gpu_start.saturating_duration_since(self.timer.epoch),
+                gpu_time,
+                #[cfg(feature = "telemetry")]
+                telemetry: state.telemetry.as_ref().map(|sampler| sampler.summarize(gpu_start, completed)),
+            };
+            state.trace.push_back(entry);
+        }
+        // Unlock before the slot is returned on drop
+        drop(state);
+    }
+}
+
+impl Drop for TimedDispatch {
+    fn drop(&mut self) {
+        self.timer.state.lock().unwrap().free_slots.push(self.slot);
+    }
+}
+
+/// One timed dispatch in the trace
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DispatchTrace {
+    pub label: String,
+    /// Approximate start on the host clock, relative to when timing was first enabled
+    pub start: Duration,
+    pub gpu_time: Duration,
+    /// Device clocks and power while the dispatch ran
+    #[cfg(feature = "telemetry")]
+    pub telemetry: Option<TelemetrySummary>,
+}
+
+/// GPU time spent in one pipeline over a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineTiming {
+    /// Pipeline label (see [`Pipeline::set_label`])
+    pub label: String,
+    /// Number of timed dispatches
+    pub dispatches: u64,
+    pub total: Duration,
+    pub average: Duration,
+    /// 95th percentile (nearest rank) of per-dispatch GPU time, over the
+    /// last [`SAMPLES_PER_PIPELINE`] dispatches
+    pub p95: Duration,
+}
+
+impl PipelineTiming {
+    /// Summarize per-dispatch GPU times in nanoseconds
+    pub fn from_samples(label: &str, samples_ns: &[u64]) -> Self {
+        let mut sorted = samples_ns.to_vec();
+        sorted.sort_unstable();
+        let dispatches = sorted.len() as u64;
+        let total: u64 = sorted.iter().sum();
+        let p95 = match sorted.len() {
+            0 => 0,
+            n => sorted[(n * 95 + 99) / 100 - 1],
+        };
+        Self {
+            label: label.to_owned(),
+            dispatches,
+            total: Duration::from_nanos(total),
+            average: Duration::from_nanos(total.checked_div(dispatches).unwrap_or(0)),
+            p95: Duration::from_nanos(p95),
+        }
+    }
+}
+
+/// GPU timing breakdown collected with [`ComputeContext::enable_gpu_timing`]
+#[derive(Debug, Clone, Default)]
+pub struct OptimizationReport {
+    /// Per-pipeline timings, largest total first
+    pub pipelines: Vec<PipelineTiming>,
+    /// Submission batching of the timed dispatches
+    pub batching: BatchStats,
+    /// Dispatches that ran while every timing slot was in use
+    pub untimed_dispatches: u64,
+}
+
+impl OptimizationReport {
+    /// Build a report from per-label samples in nanoseconds
+    pub fn from_samples<'a, I>(samples: I, batching: BatchStats, untimed_dispatches: u64) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a [u64])>,
+    {
+        let pipelines = samples
+            .into_iter()
+            .map(|(label, samples)| PipelineTiming::from_samples(label, samples))
+            .collect();
+        Self::from_timings(pipelines, batching, untimed_dispatches)
+    }
+
+    fn from_timings(mut pipelines: Vec<PipelineTiming>, batching: BatchStats, untimed_dispatches: u64) -> Self {
+        pipelines.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.label.cmp(&b.label)));
+        Self { pipelines, batching, untimed_dispatches }
+    }
+
+    /// Timing for the pipeline labelled `label`
+    pub fn pipeline(&self, label: &str) -> Option<&PipelineTiming> {
+        self.pipelines.iter().find(|timing| timing.label == label)
+    }
+
+    /// GPU time across all pipelines
+    pub fn total_gpu_time(&self) -> Duration {
+        self.pipelines.iter().map(|timing| timing.total).sum()
+    }
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<32} {:>10} {:>12} {:>12} {:>12}",
+            "pipeline", "dispatches", "total", "average", "p95"
+        )?;
+        for timing in &self.pipelines {
+            writeln!(
+                f,
+                "{:<32} {:>10} {:>12} {:>12} {:>12}",
+                timing.label,
+                timing.dispatches,
+                format!("{:.3?}", timing.total),
+                format!("{:.3?}", timing.average),
+                format!("{:.3?}", timing.p95),
+            )?;
+        }
+        writeln!(
+            f,
+            "{} submissions, {:.1} dispatches per submission",
+            self.batching.total_submissions, self.batching.average_batch_size
+        )?;
+        if self.untimed_dispatches > 0 {
+            writeln!(f, "{} dispatches ran untimed (all timing slots busy)", self.untimed_dispatches)?;
+        }
+        Ok(())
+    }
+}
+
+impl ComputeContext {
+    /// Time every following dispatch on the GPU
+    ///
+    /// Results accumulate until [`reset_gpu_timing`](Self::reset_gpu_timing)
+    /// and are summarized by [`optimization_report`](Self::optimization_report).
+    /// Fails if the compute queue family cannot write timestamps.
+    pub fn enable_gpu_timing(&self) -> Result<()> {
+        self.with_inner(|inner| {
+            let family = &inner.queue_families[inner.queue_family_index as usize];
+            if family.timestampValidBits == 0 {
+                return Err(KronosError::UnsupportedHardware(
+                    "Compute queue family does not support timestamps".into(),
+                ));
+            }
+            if inner.gpu_timer.get().is_none() {
+                let timer = unsafe { GpuTimer::new(inner.physical_device, inner.device)? };
+                // Another thread may have installed its timer first
+                if let Err(timer) = inner.gpu_timer.set(Arc::new(timer)) {
+                    unsafe { timer.destroy() };
+                }
+            }
+            if let Some(timer) = inner.gpu_timer.get() {
+                timer.set_enabled(true);
+            }
+            Ok(())
+        })
+    }
+
+    /// Stop timing new dispatches; collected samples are kept
+    pub fn disable_gpu_timing(&self) {
+        self.with_inner(|inner| {
+            if let Some(timer) = inner.gpu_timer.get() {
+                timer.set_enabled(false);
+            }
+        })
+    }
+
+    /// Discard the GPU timing collected so far
+    pub fn reset_gpu_timing(&self) {
+        self.with_inner(|inner| {
+            if let Some(timer) = inner.gpu_timer.get() {
+                timer.reset();
+            }
+        })
+    }
+
+    /// The most recent timed dispatches, oldest first
+    ///
+    /// Holds up to [`TRACE_CAPACITY`] entries; serialize it (e.g. with
+    /// `serde_json`) to inspect a run. Always empty with the `minimal`
+    /// feature.
+    pub fn dispatch_trace(&self) -> Vec<DispatchTrace> {
+        self.with_inner(|inner| {
+            inner.gpu_timer.get().map(|timer| timer.trace()).unwrap_or_default()
+        })
+    }
+
+    /// Per-pipeline GPU time of the dispatches timed so far
+    ///
+    /// Only completed dispatches are included; in-flight submissions are
+    /// added once the reaper observes them.
+    pub fn optimization_report(&self) -> OptimizationReport {
+        self.with_inner(|inner| {
+            inner.gpu_timer.get().map(|timer| timer.report()).unwrap_or_default()
+        })
+    }
+}
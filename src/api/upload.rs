@@ -0,0 +1,267 @@
+//! Persistently mapped ring for small uploads
+//!
+//! Uploading through a fresh staging buffer costs an allocation, a map and
+//! an unmap per call. Uploads of up to a quarter of the ring's capacity,
+//! [`DEFAULT_UPLOAD_RING_SIZE`] unless set with
+//! [`ContextBuilder::staging_capacity`], instead take a region of one
+//! transfer-source buffer in the context's host-visible coherent pool,
+//! which stays mapped for the life of the context.
+//!
+//! Regions are reserved by a compare-and-swap on the ring's head, so
+//! threads never wait for each other to reserve, and are reclaimed when
+//! the [`UploadRegion`] guarding them is dropped, which the copy path does
+//! once the fence or queue wait covering the copy has returned. Regions
+//! retire in any order; the tail advances over each contiguous run of
+//! retired regions.
+//!
+//! An upload that is too large, or finds the ring full, falls back to a
+//! dedicated staging buffer.
+
+use std::collections::BTreeMap;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use super::*;
+use crate::*;
+use crate::implementation::pool_allocator::{self, PoolType};
+use super::host_copy;
+
+/// Size of a context's upload ring in bytes, unless configured
+pub(super) const DEFAULT_UPLOAD_RING_SIZE: u64 = 4 << 20;
+
+/// Alignment of regions within the ring
+const REGION_ALIGNMENT: u64 = 16;
+
+/// Reservation and reclamation of ring positions
+///
+/// Positions grow without wrapping; a position's offset in the ring is the
+/// position modulo the capacity.
+struct RingCursor {
+    capacity: u64,
+    /// End of the newest reservation
+    head: AtomicU64,
+    /// Start of the oldest reservation not yet retired
+    tail: AtomicU64,
+    /// Retired reservations past the tail, from start to end
+    retired: Mutex<BTreeMap<u64, u64>>,
+}
+
+impl RingCursor {
+    fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            head: AtomicU64::new(0),
+            tail: AtomicU64::new(0),
+            retired: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Reserve `size` contiguous bytes, returning the reservation's start,
+    /// the position of its data and its end
+    fn reserve(&self, size: u64) -> Option<(u64, u64, u64)> {
+        if size > self.capacity {
+            return None;
+        }
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            let mut start = (head + REGION_ALIGNMENT - 1) & !(REGION_ALIGNMENT - 1);
+            // A region never wraps; skip the rest of the ring instead
+            let offset = start % self.capacity;
+            if offset + size > self.capacity {
+                start += self.capacity - offset;
+            }
+            let end = start + size;
+            if end - self.tail.load(Ordering::Acquire) > self.capacity {
+                return None;
+            }
+            match self.head.compare_exchange_weak(head, end, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some((head, start, end)),
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Release the reservation from `start` to `end`
+    fn retire(&self, start: u64, end: u64) {
+        let mut retired = self.retired.lock().unwrap();
+        retired.insert(start, end);
+        let mut tail = self.tail.load(Ordering::Acquire);
+        while let Some(end) = retired.remove(&tail) {
+            tail = end;
+        }
+        self.tail.store(tail, Ordering::Release);
+    }
+}
+
+/// A transfer-source buffer in the host-visible coherent pool, mapped once
+pub(super) struct UploadRing {
+    buffer: VkBuffer,
+    allocation: u64,
+    mapped: *mut u8,
+    /// Property flags of the mapped memory's type
+    memory_flags: VkMemoryPropertyFlags,
+    cursor: RingCursor,
+}
+
+// The mapping is only written through regions, which never overlap
+unsafe impl Send for UploadRing {}
+unsafe impl Sync for UploadRing {}
+
+impl UploadRing {
+    /// Create a ring of `capacity` bytes, or `None` if the device has no mappable
+    /// host-coherent pool
+    ///
+    /// # Safety
+    ///
+    /// The device must be valid and its memory pools initialized.
+    pub(super) unsafe fn new(device: VkDevice, capacity: u64) -> Option<Self> {
+        if capacity == 0 {
+            return None;
+        }
+        let create_info = VkBufferCreateInfo {
+            sType: VkStructureType::BufferCreateInfo,
+            pNext: ptr::null(),
+            flags: VkBufferCreateFlags::empty(),
+            size: capacity,
+            usage: VkBufferUsageFlags::TRANSFER_SRC,
+            sharingMode: VkSharingMode::Exclusive,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: ptr::null(),
+        };
+        let mut buffer = VkBuffer::NULL;
+        if vkCreateBuffer(device, &create_info, ptr::null(), &mut buffer) != VkResult::Success {
+            return None;
+        }
+        let mapped = pool_allocator::allocate_buffer_memory(device, buffer, PoolType::HostVisibleCoherent)
+            .ok()
+            .and_then(|allocation| {
+                let handle = pool_allocator::get_allocation(allocation).ok();
+                match handle.as_ref().and_then(|handle| handle.mapped_ptr().map(|mapped| (mapped, handle.memory_flags()))) {
+                    Some((mapped, flags)) => Some((allocation, mapped as *mut u8, flags)),
+                    None => {
+                        let _ = pool_allocator::free_allocation(device, allocation);
+                        None
+                    }
+                }
+            });
+        let Some((allocation, mapped, memory_flags)) = mapped else {
+            vkDestroyBuffer(device, buffer, ptr::null());
+            return None;
+        };
+        Some(Self { buffer, allocation, mapped, memory_flags, cursor: RingCursor::new(capacity) })
+    }
+
+    pub(super) fn buffer(&self) -> VkBuffer {
+        self.buffer
+    }
+
+    /// Reserve a region of `size` bytes, or `None` if the ring is full
+    pub(super) fn allocate(&self, size: usize) -> Option<UploadRegion<'_>> {
+        let (start, data, end) = self.cursor.reserve(size as u64)?;
+        Some(UploadRegion { ring: self, start, data, end })
+    }
+
+    /// Destroy the buffer and return its memory to the pool
+    ///
+    /// # Safety
+    ///
+    /// No copy from the ring may be in flight.
+    pub(super) unsafe fn destroy(&self, device: VkDevice) {
+        vkDestroyBuffer(device, self.buffer, ptr::null());
+        let _ = pool_allocator::free_allocation(device, self.allocation);
+    }
+}
+
+/// A reserved region of the upload ring, reclaimed on drop
+///
+/// Keep the region until the copy reading it has completed.
+pub(super) struct UploadRegion<'a> {
+    ring: &'a UploadRing,
+    start: u64,
+    data: u64,
+    end: u64,
+}
+
+impl UploadRegion<'_> {
+    /// Offset of the region in the ring's buffer
+    pub(super) fn offset(&self) -> VkDeviceSize {
+        self.data % self.ring.cursor.capacity
+    }
+
+    /// Copy `bytes` into the region
+    pub(super) fn write(&mut self, bytes: &[u8]) {
+        assert!(bytes.len() as u64 <= self.end - self.data, "upload exceeds its ring region");
+        unsafe {
+            host_copy::write_mapped(self.ring.memory_flags, bytes, self.ring.mapped.add(self.offset() as usize));
+        }
+    }
+}
+
+impl Drop for UploadRegion<'_> {
+    fn drop(&mut self) {
+        self.ring.cursor.retire(self.start, self.end);
+    }
+}
+
+impl ComputeContext {
+    /// Allocate up front what the context otherwise allocates on first use
+    ///
+    /// Creates the upload ring, gives each of the device's memory pools its
+    /// first slab and starts the completion reaper, so the first iteration
+    /// of a latency-critical loop does not pay for them.
+    ///
+    /// Call it before
+    /// [`declare_steady_state`](crate::audit::declare_steady_state). Uploads
+    /// then allocate nothing as long as each fits in a quarter of the ring,
+    /// see [`ContextBuilder::staging_capacity`], and finds room in it; a
+    /// larger one, or one that finds the ring full, still creates a staging
+    /// buffer of its own and breaks the steady state. Buffers, pipelines and
+    /// workers are the caller's to create during setup.
+    ///
+    /// Fails if the ring is turned off or the device has no host-visible
+    /// coherent memory to put it in.
+    pub fn prewarm(&self) -> Result<()> {
+        self.with_inner(|inner| {
+            unsafe { pool_allocator::prewarm_pools(inner.device)? };
+            if inner.upload_ring().is_none() {
+                return Err(KronosError::BufferCreationFailed(format!(
+                    "No upload ring of {} bytes could be created",
+                    inner.staging_capacity
+                )));
+            }
+            inner.reaper();
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_reclaims_in_any_order() {
+        let cursor = RingCursor::new(256);
+        let (a, a_data, a_end) = cursor.reserve(100).unwrap();
+        let (b, b_data, b_end) = cursor.reserve(100).unwrap();
+        assert_eq!((a_data, b_data), (0, 112));
+        // 212..256 is too short; the region starts over at the front
+        assert!(cursor.reserve(100).is_none());
+
+        // Retiring the newer region first keeps the older one reserved
+        cursor.retire(b, b_end);
+        assert!(cursor.reserve(100).is_none());
+        cursor.retire(a, a_end);
+        let (_, c_data, c_end) = cursor.reserve(100).unwrap();
+        assert_eq!((c_data % 256, c_end), (0, 356));
+    }
+
+    #[test]
+    fn test_ring_rejects_oversized_regions() {
+        let cursor = RingCursor::new(256);
+        assert!(cursor.reserve(257).is_none());
+        let (start, _, end) = cursor.reserve(256).unwrap();
+        cursor.retire(start, end);
+        assert!(cursor.reserve(256).is_some());
+    }
+}
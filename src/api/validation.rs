@@ -0,0 +1,241 @@
+//! Validation layer messages and shader debug printf
+//!
+//! Kronos forwards to drivers directly, so validation layers only take part
+//! when it runs on a Vulkan loader, as with
+//! [`InitOptions::with_loader`](crate::implementation::InitOptions::with_loader).
+//! A context built with
+//! [`enable_validation`](super::ContextBuilder::enable_validation) then
+//! enables the Khronos validation layer if the loader found it, and routes
+//! the layer's messages through a VK_EXT_debug_utils messenger into Kronos's
+//! log: errors and warnings at their level, everything else at `debug`.
+//!
+//! The layer's debug printf is turned on as well. Shaders calling
+//! `debugPrintfEXT` (GL_EXT_debug_printf in GLSL, `printf` in HLSL) have
+//! their output logged at `info` with a `[SHADER]` prefix once the
+//! submission completes. Devices exposing VK_KHR_shader_non_semantic_info,
+//! which such shaders need before Vulkan 1.3, get it enabled:
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! env_logger::init();
+//! let ctx = ComputeContext::builder().enable_validation().build()?;
+//! if !ctx.debug_printf_enabled() {
+//!     eprintln!("validation layer not found; shader printf output is lost");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Debug printf takes the place of the layer's GPU-assisted validation and
+//! slows dispatches down considerably, so keep it to debug runs. Without
+//! the layer a warning is logged and the context is created as usual.
+
+use super::*;
+use crate::*; // Import all functions from the crate root
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+
+/// Validation features turned on with the layer
+const VALIDATION_FEATURES: [VkValidationFeatureEnableEXT; 1] = [VkValidationFeatureEnableEXT::DebugPrintf];
+
+/// Layer, extensions and debug printf set up for an instance
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct Validation {
+    /// Whether VK_LAYER_KHRONOS_validation is enabled
+    layer: bool,
+    /// Whether VK_EXT_debug_utils is enabled, so messages can be routed
+    debug_utils: bool,
+    /// Whether VK_EXT_validation_features is enabled with debug printf
+    debug_printf: bool,
+}
+
+impl Validation {
+    /// Decide what to enable on the instance
+    ///
+    /// # Safety
+    ///
+    /// The ICDs must be loaded.
+    pub(super) unsafe fn configure(config: &ContextConfig, instance_extensions: &[String]) -> Self {
+        if !config.enable_validation {
+            return Self::default();
+        }
+        if !instance_layers().iter().any(|layer| layer == VK_LAYER_KHRONOS_VALIDATION_NAME) {
+            kronos_log!(
+                Warn,
+                "[SAFE API] Validation requested but {} is not present; validation layers need Kronos to run on a Vulkan loader",
+                VK_LAYER_KHRONOS_VALIDATION_NAME
+            );
+            return Self::default();
+        }
+        let layer_extensions = layer_extensions(VK_LAYER_KHRONOS_VALIDATION_NAME);
+        let provides = |name: &str| {
+            instance_extensions.iter().chain(&layer_extensions).any(|extension| extension == name)
+        };
+        let validation = Self {
+            layer: true,
+            debug_utils: provides(VK_EXT_DEBUG_UTILS_EXTENSION_NAME),
+            debug_printf: provides(VK_EXT_VALIDATION_FEATURES_EXTENSION_NAME),
+        };
+        if !validation.debug_utils {
+            kronos_log!(Warn, "[SAFE API] {} is not supported; validation messages stay with the layer", VK_EXT_DEBUG_UTILS_EXTENSION_NAME);
+        }
+        if !validation.debug_printf {
+            kronos_log!(Warn, "[SAFE API] {} is not supported; shader debug printf is off", VK_EXT_VALIDATION_FEATURES_EXTENSION_NAME);
+        }
+        kronos_log!(Info, "[SAFE API] Enabling {} (debug printf: {})", VK_LAYER_KHRONOS_VALIDATION_NAME, validation.debug_printf);
+        validation
+    }
+
+    /// Instance layers to enable
+    pub(super) fn layers(&self) -> Vec<&'static str> {
+        if self.layer {
+            vec![VK_LAYER_KHRONOS_VALIDATION_NAME]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Instance extensions to enable
+    pub(super) fn extensions(&self) -> Vec<&'static str> {
+        let mut extensions = Vec::new();
+        if self.debug_utils {
+            extensions.push(VK_EXT_DEBUG_UTILS_EXTENSION_NAME);
+        }
+        if self.debug_printf {
+            extensions.push(VK_EXT_VALIDATION_FEATURES_EXTENSION_NAME);
+        }
+        extensions
+    }
+
+    pub(super) fn debug_printf(&self) -> bool {
+        self.debug_printf
+    }
+
+    /// Create the instance through `create`, with debug printf and a
+    /// messenger for instance creation chained onto `create_info`
+    pub(super) fn chain_instance_create_info<R>(
+        &self,
+        create_info: &mut VkInstanceCreateInfo,
+        create: impl FnOnce(*const VkInstanceCreateInfo) -> R,
+    ) -> R {
+        let mut features = VkValidationFeaturesEXT {
+            enabledValidationFeatureCount: VALIDATION_FEATURES.len() as u32,
+            pEnabledValidationFeatures: VALIDATION_FEATURES.as_ptr(),
+            ..Default::default()
+        };
+        let mut messenger = messenger_create_info();
+        let mut chain = Chain::new(create_info);
+        if self.debug_printf {
+            chain = chain.push(&mut features);
+        }
+        if self.debug_utils {
+            chain = chain.push(&mut messenger);
+        }
+        create(chain.as_ptr())
+    }
+
+    /// Create the messenger routing messages into the log, or a null
+    /// handle without VK_EXT_debug_utils
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created with [`extensions`](Self::extensions).
+    pub(super) unsafe fn create_messenger(&self, instance: VkInstance) -> VkDebugUtilsMessengerEXT {
+        if !self.debug_utils {
+            return VkDebugUtilsMessengerEXT::NULL;
+        }
+        let create_info = messenger_create_info();
+        let mut messenger = VkDebugUtilsMessengerEXT::NULL;
+        let result = vkCreateDebugUtilsMessengerEXT(instance, &create_info, ptr::null(), &mut messenger);
+        if result != VkResult::Success {
+            kronos_log!(Warn, "[SAFE API] vkCreateDebugUtilsMessengerEXT failed ({:?}); validation messages are not logged", result);
+            return VkDebugUtilsMessengerEXT::NULL;
+        }
+        messenger
+    }
+}
+
+fn messenger_create_info() -> VkDebugUtilsMessengerCreateInfoEXT {
+    VkDebugUtilsMessengerCreateInfoEXT {
+        messageSeverity: VkDebugUtilsMessageSeverityFlagsEXT::all(),
+        messageType: VkDebugUtilsMessageTypeFlagsEXT::all(),
+        pfnUserCallback: Some(log_message),
+        ..Default::default()
+    }
+}
+
+/// Names of the instance layers the loader found
+unsafe fn instance_layers() -> Vec<String> {
+    let mut count = 0u32;
+    if vkEnumerateInstanceLayerProperties(&mut count, ptr::null_mut()) != VkResult::Success || count == 0 {
+        return Vec::new();
+    }
+    let mut layers = vec![VkLayerProperties::default(); count as usize];
+    let result = vkEnumerateInstanceLayerProperties(&mut count, layers.as_mut_ptr());
+    if !matches!(result, VkResult::Success | VkResult::Incomplete) {
+        return Vec::new();
+    }
+    layers.truncate(count as usize);
+    layers.iter().map(|layer| CStr::from_ptr(layer.layerName.as_ptr()).to_string_lossy().into_owned()).collect()
+}
+
+/// Names of the instance extensions `layer` provides
+unsafe fn layer_extensions(layer: &str) -> Vec<String> {
+    let layer = CString::new(layer).unwrap();
+    let mut count = 0u32;
+    if vkEnumerateInstanceExtensionProperties(layer.as_ptr(), &mut count, ptr::null_mut()) != VkResult::Success {
+        return Vec::new();
+    }
+    let mut extensions = vec![VkExtensionProperties::default(); count as usize];
+    let result = vkEnumerateInstanceExtensionProperties(layer.as_ptr(), &mut count, extensions.as_mut_ptr());
+    if !matches!(result, VkResult::Success | VkResult::Incomplete) {
+        return Vec::new();
+    }
+    extensions.truncate(count as usize);
+    extensions
+        .iter()
+        .map(|extension| CStr::from_ptr(extension.extensionName.as_ptr()).to_string_lossy().into_owned())
+        .collect()
+}
+
+unsafe fn c_str_or_empty<'a>(s: *const std::ffi::c_char) -> std::borrow::Cow<'a, str> {
+    if s.is_null() {
+        "".into()
+    } else {
+        CStr::from_ptr(s).to_string_lossy()
+    }
+}
+
+/// Debug messenger callback: log the message at its severity, and shader
+/// debug printf output at info
+unsafe extern "C" fn log_message(
+    severity: VkDebugUtilsMessageSeverityFlagsEXT,
+    _types: VkDebugUtilsMessageTypeFlagsEXT,
+    data: *const VkDebugUtilsMessengerCallbackDataEXT,
+    _user_data: *mut c_void,
+) -> VkBool32 {
+    let Some(data) = data.as_ref() else { return VK_FALSE };
+    let message = c_str_or_empty(data.pMessage);
+    // WARNING-DEBUG-PRINTF, or UNASSIGNED-DEBUG-PRINTF from older layers
+    if c_str_or_empty(data.pMessageIdName).contains("DEBUG-PRINTF") {
+        kronos_log!(Info, "[SHADER] {}", message.trim_end());
+    } else if severity.contains(VkDebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        kronos_log!(Error, "[VALIDATION] {}", message);
+    } else if severity.contains(VkDebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        kronos_log!(Warn, "[VALIDATION] {}", message);
+    } else {
+        kronos_log!(Debug, "[VALIDATION] {}", message);
+    }
+    // Never abort the call that triggered the message
+    VK_FALSE
+}
+
+impl ComputeContext {
+    /// Whether shader debug printf output reaches the log, see
+    /// [`validation`](super::validation)
+    pub fn debug_printf_enabled(&self) -> bool {
+        self.with_inner(|inner| inner.debug_printf)
+    }
+}
@@ -0,0 +1,39 @@
+//! Vulkan version numbers for the application info
+
+use crate::sys::VK_MAKE_VERSION;
+use std::fmt;
+
+/// A major.minor.patch version, packed the way Vulkan packs versions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const V1_0: Self = Self::new(1, 0, 0);
+    pub const V1_1: Self = Self::new(1, 1, 0);
+    pub const V1_2: Self = Self::new(1, 2, 0);
+    pub const V1_3: Self = Self::new(1, 3, 0);
+
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// Unpack a version from its Vulkan encoding
+    pub const fn from_raw(raw: u32) -> Self {
+        Self::new(raw >> 22, (raw >> 12) & 0x3FF, raw & 0xFFF)
+    }
+
+    /// The Vulkan encoding of the version
+    pub const fn to_raw(self) -> u32 {
+        VK_MAKE_VERSION(self.major, self.minor, self.patch)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
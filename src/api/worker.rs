@@ -0,0 +1,426 @@
+//! Per-thread workers sharing a context's device
+//!
+//! Dispatches and transfers through a [`ComputeContext`] share its command
+//! and descriptor pools, so the threads of a pool that share one context
+//! take turns recording, updating descriptors and waiting. A [`Worker`]
+//! shares the context's device and queue but owns a command pool, a
+//! descriptor pool and a staging ring of its own; it meets other threads
+//! only at the queue lock, for the submission itself.
+//!
+//! ```no_run
+//! use kronos_compute::api::ComputeContext;
+//!
+//! # fn main() -> kronos_compute::api::Result<()> {
+//! let ctx = ComputeContext::new()?;
+//! let workers = (0..4).map(|_| ctx.worker()).collect::<Result<Vec<_>, _>>()?;
+//! std::thread::scope(|scope| {
+//!     for worker in &workers {
+//!         scope.spawn(move || {
+//!             let buffer = worker.create_buffer(&[1.0f32; 1024])?;
+//!             worker.read::<f32>(&buffer)
+//!         });
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use super::*;
+use crate::*; // Import all functions from the crate root
+use super::context::Pools;
+use super::events::DeviceEvents;
+use super::host_copy;
+use super::reaper::Reaper;
+use std::ptr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Size of a worker's staging ring in bytes
+const STAGING_RING_SIZE: usize = 4 << 20;
+
+/// Transfers in flight at once through the staging ring
+const STAGING_SLOTS: usize = 2;
+
+/// A per-thread handle for submitting work on a shared device
+///
+/// Obtained from [`ComputeContext::worker`]. Buffers and pipelines created
+/// through the context can be used with any of its workers. A worker is
+/// meant for one thread at a time; calls from several threads are safe but
+/// serialize on the worker.
+pub struct Worker {
+    shared: Arc<WorkerShared>,
+}
+
+/// State shared by a worker and the dispatches it builds
+pub(super) struct WorkerShared {
+    device: VkDevice,
+    pub(super) queue: VkQueue,
+    pub(super) queue_family: u32,
+    device_events: Arc<DeviceEvents>,
+    pub(super) pools: Mutex<Pools>,
+    staging: Mutex<StagingRing>,
+    /// Completion reaper for the worker's non-blocking submissions
+    reaper: OnceLock<Reaper>,
+    /// Declared last so the pools are destroyed before the device
+    context: ComputeContext,
+}
+
+// Send + Sync for thread safety
+unsafe impl Send for Worker {}
+unsafe impl Sync for Worker {}
+unsafe impl Send for WorkerShared {}
+unsafe impl Sync for WorkerShared {}
+
+/// Host-visible staging memory split into slots used round-robin
+///
+/// Filling one slot overlaps with the GPU copy out of the previous one.
+struct StagingRing {
+    device: VkDevice,
+    buffer: Buffer,
+    mapped: *mut u8,
+    slot_size: usize,
+    slots: Vec<StagingSlot>,
+    next: usize,
+}
+
+struct StagingSlot {
+    fence: VkFence,
+    /// Command buffer of the copy in flight through this slot
+    command_buffer: VkCommandBuffer,
+    /// Output range the slot's contents go to once a readback completes
+    readback: Option<(usize, usize)>,
+}
+
+impl ComputeContext {
+    /// Create a worker for one thread of a thread pool
+    ///
+    /// The worker shares this context's device and queue and keeps the
+    /// context alive.
+    pub fn worker(&self) -> Result<Worker> {
+        unsafe {
+            let (device, queue, queue_family, device_events, descriptor_sets) = self.with_inner(|inner| {
+                (inner.device, inner.queue, inner.queue_family_index, inner.device_events.clone(), inner.descriptor_pool_sets)
+            });
+            let staging = StagingRing::new(self, device)?;
+            let pools = Pools::new(device, queue_family, descriptor_sets)?;
+
+            Ok(Worker {
+                shared: Arc::new(WorkerShared {
+                    device,
+                    queue,
+                    queue_family,
+                    device_events,
+                    pools: Mutex::new(pools),
+                    staging: Mutex::new(staging),
+                    reaper: OnceLock::new(),
+                    context: self.clone(),
+                }),
+            })
+        }
+    }
+}
+
+impl Worker {
+    /// The context this worker belongs to
+    pub fn context(&self) -> &ComputeContext {
+        &self.shared.context
+    }
+
+    /// Start building a compute dispatch recorded with this worker's pools
+    ///
+    /// The dispatch always runs on the context's default queue, so
+    /// [`CommandBuilder::on_queue`] cannot be used with it.
+    pub fn dispatch(&self, pipeline: &Pipeline) -> CommandBuilder {
+        let mut builder = self.shared.context.dispatch(pipeline);
+        builder.worker = Some(self.shared.clone());
+        builder
+    }
+
+    /// Create a buffer with data, uploaded through the staging ring
+    pub fn create_buffer<T>(&self, data: &[T]) -> Result<Buffer>
+    where
+        T: Copy + 'static,
+    {
+        let size = std::mem::size_of_val(data);
+        let usage = BufferUsage::STORAGE | BufferUsage::TRANSFER_DST;
+        unsafe {
+            let buffer = self.shared.context.create_buffer_raw(size, usage)?;
+            self.shared.upload(&buffer, slice_bytes(data))?;
+            Ok(buffer)
+        }
+    }
+
+    /// Write data to the start of a buffer through the staging ring
+    pub fn write<T>(&self, buffer: &mut Buffer, data: &[T]) -> Result<()>
+    where
+        T: Copy + 'static,
+    {
+        let size = std::mem::size_of_val(data);
+        if size > buffer.size {
+            return Err(KronosError::BufferCreationFailed(format!(
+                "Write of {} bytes exceeds buffer size {}",
+                size, buffer.size
+            )));
+        }
+        unsafe { self.shared.upload(buffer, slice_bytes(data)) }
+    }
+
+    /// Read a buffer back through the staging ring
+    pub fn read<T>(&self, buffer: &Buffer) -> Result<Vec<T>>
+    where
+        T: Copy + 'static,
+    {
+        let element_size = std::mem::size_of::<T>();
+        if buffer.size % element_size != 0 {
+            return Err(KronosError::BufferCreationFailed(
+                format!("Buffer size {} is not a multiple of element size {}", buffer.size, element_size)
+            ));
+        }
+
+        let element_count = buffer.size / element_size;
+        let mut data: Vec<T> = Vec::with_capacity(element_count);
+        unsafe {
+            let bytes = std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, buffer.size);
+            self.shared.download(buffer, bytes)?;
+            data.set_len(element_count);
+        }
+        Ok(data)
+    }
+
+    /// Number of this worker's non-blocking submissions still in flight
+    pub fn in_flight_submissions(&self) -> usize {
+        self.shared.reaper.get().map_or(0, Reaper::in_flight)
+    }
+}
+
+fn slice_bytes<T: Copy>(data: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data)) }
+}
+
+impl WorkerShared {
+    /// Get the completion reaper, spawning its thread on first use
+    pub(super) fn reaper(&self) -> &Reaper {
+        self.reaper.get_or_init(|| {
+            self.context.with_inner(|inner| Reaper::spawn(self.device, self.device_events.clone(), &inner.threads))
+        })
+    }
+
+    /// Release GPU objects of submissions the reaper has seen complete
+    pub(super) fn release_retired(&self, pools: &Pools) {
+        let Some(reaper) = self.reaper.get() else { return };
+        for resources in reaper.take_retired() {
+            unsafe { resources.release(self.device, &pools.descriptors) };
+        }
+    }
+
+    /// Submit to the shared queue
+    ///
+    /// # Safety
+    ///
+    /// `submit_info` must describe fully recorded command buffers of the
+    /// queue's family.
+    pub(super) unsafe fn submit(&self, submit_info: &VkSubmitInfo, fence: VkFence) -> VkResult {
+        // The queue is shared with the context and other workers
+        self.context.with_inner(|inner| {
+            let _queue = inner.queue_lock.lock().unwrap();
+            self.device_events.check(inner.queue_submit(self.queue, 1, submit_info, fence))
+        })
+    }
+
+    /// Block until `fence` signals
+    pub(super) fn wait(&self, fence: VkFence) -> VkResult {
+        self.device_events.check(unsafe { vkWaitForFences(self.device, 1, &fence, VK_TRUE, u64::MAX) })
+    }
+
+    /// Copy `data` to the start of `dst` through the staging ring
+    unsafe fn upload(&self, dst: &Buffer, data: &[u8]) -> Result<()> {
+        self.context.with_inner(|inner| inner.check_copy_usage(BufferUsage::TRANSFER_SRC, dst.usage));
+        let mut ring = self.staging.lock().unwrap();
+        let slot_size = ring.slot_size;
+        let result = data.chunks(slot_size).enumerate().try_for_each(|(index, chunk)| {
+            let slot = self.next_slot(&mut ring, &mut [])?;
+            host_copy::write_mapped(ring.buffer.memory_flags, chunk, ring.mapped.add(slot * slot_size));
+            let region = VkBufferCopy {
+                srcOffset: (slot * slot_size) as VkDeviceSize,
+                dstOffset: (index * slot_size) as VkDeviceSize,
+                size: chunk.len() as VkDeviceSize,
+            };
+            let (src, fence) = (ring.buffer.buffer.raw(), ring.slots[slot].fence);
+            ring.slots[slot].command_buffer = self.submit_copy(src, dst.buffer.on(self.context.device_id()), region, fence)?;
+            Ok(())
+        });
+        let drained = self.drain(&mut ring, &mut []);
+        result.and(drained)
+    }
+
+    /// Copy the start of `src` into `out` through the staging ring
+    unsafe fn download(&self, src: &Buffer, out: &mut [u8]) -> Result<()> {
+        self.context.with_inner(|inner| inner.check_copy_usage(src.usage, BufferUsage::TRANSFER_DST));
+        let mut ring = self.staging.lock().unwrap();
+        let slot_size = ring.slot_size;
+        let result = (0..out.len()).step_by(slot_size).try_for_each(|offset| {
+            let len = slot_size.min(out.len() - offset);
+            let slot = self.next_slot(&mut ring, out)?;
+            let region = VkBufferCopy {
+                srcOffset: offset as VkDeviceSize,
+                dstOffset: (slot * slot_size) as VkDeviceSize,
+                size: len as VkDeviceSize,
+            };
+            let (dst, fence) = (ring.buffer.buffer.raw(), ring.slots[slot].fence);
+            ring.slots[slot].command_buffer = self.submit_copy(src.buffer.on(self.context.device_id()), dst, region, fence)?;
+            ring.slots[slot].readback = Some((offset, len));
+            Ok(())
+        });
+        let drained = self.drain(&mut ring, out);
+        result.and(drained)
+    }
+
+    /// Claim the next slot of the ring, finishing the copy it last carried
+    unsafe fn next_slot(&self, ring: &mut StagingRing, out: &mut [u8]) -> Result<usize> {
+        let slot = ring.next;
+        ring.next = (slot + 1) % ring.slots.len();
+        self.retire_slot(ring, slot, out)?;
+        Ok(slot)
+    }
+
+    /// Finish every copy in flight through the ring
+    unsafe fn drain(&self, ring: &mut StagingRing, out: &mut [u8]) -> Result<()> {
+        let mut result = Ok(());
+        for slot in 0..ring.slots.len() {
+            let retired = self.retire_slot(ring, slot, out);
+            result = result.and(retired);
+        }
+        result
+    }
+
+    /// Wait for the copy in flight through `slot`, if any, and deliver its
+    /// readback into `out`
+    unsafe fn retire_slot(&self, ring: &mut StagingRing, slot: usize, out: &mut [u8]) -> Result<()> {
+        let mapped = ring.mapped.add(slot * ring.slot_size);
+        let state = &mut ring.slots[slot];
+        if state.command_buffer == VkCommandBuffer::NULL {
+            return Ok(());
+        }
+        let result = self.wait(state.fence);
+        {
+            let pools = self.pools.lock().unwrap();
+            vkFreeCommandBuffers(self.device, pools.command_pool, 1, &state.command_buffer);
+        }
+        state.command_buffer = VkCommandBuffer::NULL;
+        vkResetFences(self.device, 1, &state.fence);
+        let readback = state.readback.take();
+        if result != VkResult::Success {
+            return Err(KronosError::SynchronizationError(format!(
+                "vkWaitForFences failed: {:?}",
+                result
+            )));
+        }
+        if let Some((offset, len)) = readback {
+            ptr::copy_nonoverlapping(mapped, out[offset..offset + len].as_mut_ptr(), len);
+        }
+        Ok(())
+    }
+
+    /// Record and submit a buffer copy on the worker's command pool
+    ///
+    /// Returns the command buffer, which must be freed once `fence` signals.
+    unsafe fn submit_copy(&self, src: VkBuffer, dst: VkBuffer, region: VkBufferCopy, fence: VkFence) -> Result<VkCommandBuffer> {
+        let pools = self.pools.lock().unwrap();
+        let alloc_info = VkCommandBufferAllocateInfo {
+            sType: VkStructureType::CommandBufferAllocateInfo,
+            pNext: ptr::null(),
+            commandPool: pools.command_pool,
+            level: VkCommandBufferLevel::Primary,
+            commandBufferCount: 1,
+        };
+        let mut command_buffer = VkCommandBuffer::NULL;
+        let result = vkAllocateCommandBuffers(self.device, &alloc_info, &mut command_buffer);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+
+        let begin_info = VkCommandBufferBeginInfo {
+            sType: VkStructureType::CommandBufferBeginInfo,
+            pNext: ptr::null(),
+            flags: VkCommandBufferUsageFlags::ONE_TIME_SUBMIT,
+            pInheritanceInfo: ptr::null(),
+        };
+        let mut result = vkBeginCommandBuffer(command_buffer, &begin_info);
+        if result == VkResult::Success {
+            vkCmdCopyBuffer(command_buffer, src, dst, 1, &region);
+            result = vkEndCommandBuffer(command_buffer);
+        }
+        if result == VkResult::Success {
+            let submit_info = VkSubmitInfo {
+                sType: VkStructureType::SubmitInfo,
+                pNext: ptr::null(),
+                waitSemaphoreCount: 0,
+                pWaitSemaphores: ptr::null(),
+                pWaitDstStageMask: ptr::null(),
+                commandBufferCount: 1,
+                pCommandBuffers: &command_buffer,
+                signalSemaphoreCount: 0,
+                pSignalSemaphores: ptr::null(),
+            };
+            result = self.submit(&submit_info, fence);
+        }
+        if result != VkResult::Success {
+            vkFreeCommandBuffers(self.device, pools.command_pool, 1, &command_buffer);
+            return Err(KronosError::from(result));
+        }
+        Ok(command_buffer)
+    }
+}
+
+impl Drop for WorkerShared {
+    fn drop(&mut self) {
+        if let Some(reaper) = self.reaper.get() {
+            reaper.shutdown();
+            self.release_retired(&self.pools.lock().unwrap());
+        }
+    }
+}
+
+impl StagingRing {
+    /// Allocate and map the ring, with one fence per slot
+    ///
+    /// # Safety
+    ///
+    /// The device must be the valid VkDevice of `context`
+    unsafe fn new(context: &ComputeContext, device: VkDevice) -> Result<Self> {
+        let buffer = context.create_buffer_raw(STAGING_RING_SIZE, BufferUsage::TRANSFER_SRC | BufferUsage::TRANSFER_DST)?;
+        let mut mapped = ptr::null_mut();
+        let result = vkMapMemory(device, buffer.memory, 0, STAGING_RING_SIZE as VkDeviceSize, 0, &mut mapped);
+        if result != VkResult::Success {
+            return Err(KronosError::from(result));
+        }
+
+        let mut ring = Self {
+            device,
+            buffer,
+            mapped: mapped as *mut u8,
+            slot_size: STAGING_RING_SIZE / STAGING_SLOTS,
+            slots: Vec::with_capacity(STAGING_SLOTS),
+            next: 0,
+        };
+        for _ in 0..STAGING_SLOTS {
+            ring.slots.push(StagingSlot {
+                fence: command::create_fence(device)?,
+                command_buffer: VkCommandBuffer::NULL,
+                readback: None,
+            });
+        }
+        Ok(ring)
+    }
+}
+
+impl Drop for StagingRing {
+    fn drop(&mut self) {
+        // Transfers drain before returning, so no slot is in flight here
+        unsafe {
+            for slot in &self.slots {
+                vkDestroyFence(self.device, slot.fence, ptr::null());
+            }
+            vkUnmapMemory(self.device, self.buffer.memory);
+        }
+    }
+}
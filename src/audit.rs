@@ -0,0 +1,241 @@
+//! Auditing allocations and driver calls in steady state
+//!
+//! Safety-critical integrators in the Vulkan SC mould set everything up
+//! front and then require the processing loop to neither allocate nor
+//! create driver objects. With the `audit` feature, an application calls
+//! [`declare_steady_state`] once setup is done; every driver call Kronos
+//! makes afterwards is logged and recorded, and those that allocate device
+//! memory or create driver objects are flagged as allocations. The
+//! [`audit_report`] at the end of a run is the evidence that the steady
+//! state held, or the list of what broke it:
+//!
+//! ```no_run
+//! kronos_compute::audit::declare_steady_state();
+//! // ... the processing loop ...
+//! let report = kronos_compute::audit::audit_report();
+//! assert!(report.is_allocation_free(), "{}", report);
+//! ```
+//!
+//! The safe API allocates its upload ring and the first slab of each
+//! memory pool on first use; call
+//! [`ComputeContext::prewarm`](crate::api::ComputeContext::prewarm) during
+//! setup so the loop's first uploads do not.
+//!
+//! Host memory is not audited: sub-allocations served from slabs the
+//! memory pools already hold and Rust heap allocations are not driver
+//! calls, while a pool growing by a new slab shows up as `vkAllocateMemory`.
+//! The `minimal` feature takes precedence: nothing is recorded.
+
+// Driver calls are not recorded in `minimal` builds
+#![cfg_attr(feature = "minimal", allow(dead_code))]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Events kept in full; later ones are only counted
+const MAX_EVENTS: usize = 4096;
+
+/// What a recorded driver call did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AuditEventKind {
+    /// Allocated device memory or created a driver object
+    Allocation,
+    /// Any other driver call, such as a submission or a fence wait
+    DriverCall,
+}
+
+impl AuditEventKind {
+    fn of(entry_point: &str) -> Self {
+        if entry_point.starts_with("vkAllocate") || entry_point.starts_with("vkCreate") {
+            Self::Allocation
+        } else {
+            Self::DriverCall
+        }
+    }
+}
+
+/// One driver call made in steady state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub kind: AuditEventKind,
+    /// Vulkan entry point, e.g. `vkAllocateMemory`
+    pub entry_point: &'static str,
+    /// Time since [`declare_steady_state`]
+    pub at: Duration,
+    /// Name of the calling thread, if it has one
+    pub thread: Option<String>,
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AuditEventKind::Allocation => "allocation",
+            AuditEventKind::DriverCall => "driver call",
+        };
+        write!(f, "+{:?} {} {} on thread {}", self.at, kind, self.entry_point, self.thread.as_deref().unwrap_or("<unnamed>"))
+    }
+}
+
+struct AuditLog {
+    started: Instant,
+    events: Vec<AuditEvent>,
+    counts: BTreeMap<&'static str, u64>,
+    dropped: u64,
+}
+
+static STEADY: AtomicBool = AtomicBool::new(false);
+static LOG: Mutex<Option<AuditLog>> = Mutex::new(None);
+
+/// Start auditing: every later driver call is recorded
+///
+/// Discards the events of an earlier steady state.
+pub fn declare_steady_state() {
+    *LOG.lock().unwrap() = Some(AuditLog {
+        started: Instant::now(),
+        events: Vec::new(),
+        counts: BTreeMap::new(),
+        dropped: 0,
+    });
+    STEADY.store(true, Ordering::Release);
+    kronos_log!(Info, "[audit] Steady state declared; driver calls are audited from here");
+}
+
+/// Stop auditing, e.g. before tearing down; the events are kept for
+/// [`audit_report`]
+pub fn leave_steady_state() {
+    STEADY.store(false, Ordering::Release);
+}
+
+/// Whether driver calls are being audited
+pub fn in_steady_state() -> bool {
+    STEADY.load(Ordering::Acquire)
+}
+
+/// Record one driver call of `entry_point` if in steady state
+pub(crate) fn record_icd_call(entry_point: &'static str) {
+    if !STEADY.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut log = LOG.lock().unwrap();
+    let Some(log) = log.as_mut() else { return };
+    let event = AuditEvent {
+        kind: AuditEventKind::of(entry_point),
+        entry_point,
+        at: log.started.elapsed(),
+        thread: std::thread::current().name().map(str::to_owned),
+    };
+    match event.kind {
+        AuditEventKind::Allocation => kronos_log!(Warn, "[audit] {}", event),
+        AuditEventKind::DriverCall => kronos_log!(Debug, "[audit] {}", event),
+    }
+    *log.counts.entry(entry_point).or_default() += 1;
+    if log.events.len() < MAX_EVENTS {
+        log.events.push(event);
+    } else {
+        log.dropped += 1;
+    }
+}
+
+/// What happened since [`declare_steady_state`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    /// Whether auditing is still running
+    pub active: bool,
+    /// How long the steady state has lasted
+    pub duration: Duration,
+    /// Calls in order, up to the first 4096
+    pub events: Vec<AuditEvent>,
+    /// Calls per entry point, including those past `events`
+    pub counts: BTreeMap<&'static str, u64>,
+    /// Calls not kept in `events`
+    pub dropped: u64,
+}
+
+impl AuditReport {
+    /// Calls that allocated device memory or created driver objects
+    pub fn allocations(&self) -> impl Iterator<Item = &AuditEvent> {
+        self.events.iter().filter(|event| event.kind == AuditEventKind::Allocation)
+    }
+
+    /// Number of allocating calls, including those past `events`
+    pub fn allocation_count(&self) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(entry_point, _)| AuditEventKind::of(entry_point) == AuditEventKind::Allocation)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Whether the steady state held: nothing was allocated
+    pub fn is_allocation_free(&self) -> bool {
+        self.allocation_count() == 0
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: u64 = self.counts.values().sum();
+        writeln!(
+            f,
+            "Steady state audit over {:?}: {} driver calls, {} allocations",
+            self.duration,
+            total,
+            self.allocation_count()
+        )?;
+        for (entry_point, count) in &self.counts {
+            writeln!(f, "  {:<40} {:>10}", entry_point, count)?;
+        }
+        for event in self.allocations() {
+            writeln!(f, "  {}", event)?;
+        }
+        if self.dropped > 0 {
+            writeln!(f, "  ({} later calls counted but not listed)", self.dropped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Events recorded since [`declare_steady_state`]
+///
+/// Empty if no steady state was declared.
+pub fn audit_report() -> AuditReport {
+    let log = LOG.lock().unwrap();
+    match log.as_ref() {
+        Some(log) => AuditReport {
+            active: in_steady_state(),
+            duration: log.started.elapsed(),
+            events: log.events.clone(),
+            counts: log.counts.clone(),
+            dropped: log.dropped,
+        },
+        None => AuditReport::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steady_state_records_allocations() {
+        record_icd_call("vkAuditTestBefore");
+        declare_steady_state();
+        record_icd_call("vkCreateAuditTest");
+        record_icd_call("vkAuditTestSubmit");
+        record_icd_call("vkAuditTestSubmit");
+        leave_steady_state();
+        record_icd_call("vkCreateAuditTest");
+
+        let report = audit_report();
+        assert!(!report.active);
+        assert!(!report.counts.contains_key("vkAuditTestBefore"));
+        assert_eq!(report.counts.get("vkCreateAuditTest"), Some(&1));
+        assert_eq!(report.counts.get("vkAuditTestSubmit"), Some(&2));
+        assert!(report.allocations().any(|event| event.entry_point == "vkCreateAuditTest"));
+        assert!(!report.is_allocation_free());
+        assert!(report.to_string().contains("vkCreateAuditTest"));
+    }
+}
@@ -0,0 +1,383 @@
+//! Flat C API over the safe layer
+//!
+//! The exported `vk*` symbols give C code all of Vulkan, and all of its
+//! ceremony. With the `capi` feature Kronos also exports a handful of
+//! `kronos_*` functions wrapping [`ComputeContext`], [`Buffer`] and
+//! [`Pipeline`] in opaque handles, so embedders get the safe layer's
+//! optimized path (persistent descriptors, smart barriers, pooled memory)
+//! from a context, a few buffers and a dispatch:
+//!
+//! ```c
+//! #include "kronos_capi.h"
+//!
+//! KronosContext *ctx;
+//! KronosBuffer *data;
+//! KronosPipeline *pipeline;
+//! if (kronos_ctx_create(&ctx) != KRONOS_SUCCESS) {
+//!     fprintf(stderr, "%s\n", kronos_last_error());
+//!     return 1;
+//! }
+//! kronos_buffer_create(ctx, input, sizeof(input), &data);
+//! kronos_pipeline_create(ctx, spirv, spirv_size, &pipeline);
+//! const KronosBuffer *bindings[] = { data };
+//! kronos_dispatch(ctx, pipeline, bindings, 1, &count, sizeof(count), 4, 1, 1);
+//! kronos_buffer_read(data, output, sizeof(output));
+//!
+//! kronos_pipeline_destroy(pipeline);
+//! kronos_buffer_destroy(data);
+//! kronos_ctx_destroy(ctx);
+//! ```
+//!
+//! Functions that can fail return a [`KronosStatus`]; the message of the
+//! last failure on the calling thread is kept for [`kronos_last_error`].
+//! Panics are caught at the boundary and reported as
+//! `KRONOS_ERROR_INTERNAL`. Handles keep what they need alive, so they may
+//! be destroyed in any order, and may move between threads but must not be
+//! used from two at once.
+//!
+//! The header is generated by cbindgen when the crate is built with the
+//! feature, into `$OUT_DIR/include/kronos_capi.h` and, if
+//! `KRONOS_CAPI_HEADER` is set, to that path too. The copy at the
+//! repository root is refreshed by `scripts/prepare_release.sh`.
+
+use crate::api::{Buffer, ComputeContext, KronosError, Pipeline};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Result of a `kronos_*` call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KronosStatus {
+    KRONOS_SUCCESS = 0,
+    /// A handle or pointer was null, or a size did not fit
+    KRONOS_ERROR_INVALID_ARGUMENT = 1,
+    KRONOS_ERROR_INITIALIZATION = 2,
+    KRONOS_ERROR_DEVICE_NOT_FOUND = 3,
+    KRONOS_ERROR_SHADER = 4,
+    KRONOS_ERROR_BUFFER = 5,
+    KRONOS_ERROR_EXECUTION = 6,
+    KRONOS_ERROR_SYNCHRONIZATION = 7,
+    KRONOS_ERROR_UNSUPPORTED = 8,
+    KRONOS_ERROR_VULKAN = 9,
+    /// Kronos panicked; the context may be unusable
+    KRONOS_ERROR_INTERNAL = 10,
+}
+
+/// Opaque handle to a [`ComputeContext`]
+pub struct KronosContext {
+    context: ComputeContext,
+}
+
+/// Opaque handle to a [`Buffer`]
+pub struct KronosBuffer {
+    buffer: Buffer,
+}
+
+/// Opaque handle to a [`Pipeline`]
+pub struct KronosPipeline {
+    pipeline: Pipeline,
+}
+
+thread_local! {
+    /// Message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failed call: its status and message
+struct Failure {
+    status: KronosStatus,
+    message: String,
+}
+
+impl Failure {
+    fn invalid(message: impl Into<String>) -> Self {
+        Failure { status: KronosStatus::KRONOS_ERROR_INVALID_ARGUMENT, message: message.into() }
+    }
+}
+
+impl From<KronosError> for Failure {
+    fn from(error: KronosError) -> Self {
+        let status = match &error {
+            KronosError::InitializationFailed(_) | KronosError::ImplementationError(_) => {
+                KronosStatus::KRONOS_ERROR_INITIALIZATION
+            }
+            KronosError::DeviceNotFound => KronosStatus::KRONOS_ERROR_DEVICE_NOT_FOUND,
+            KronosError::ShaderCompilationFailed(_) => KronosStatus::KRONOS_ERROR_SHADER,
+            KronosError::BufferCreationFailed(_) => KronosStatus::KRONOS_ERROR_BUFFER,
+            KronosError::CommandExecutionFailed(_) => KronosStatus::KRONOS_ERROR_EXECUTION,
+            KronosError::SynchronizationError(_) => KronosStatus::KRONOS_ERROR_SYNCHRONIZATION,
+            KronosError::UnsupportedHardware(_) => KronosStatus::KRONOS_ERROR_UNSUPPORTED,
+            KronosError::VulkanError(_) => KronosStatus::KRONOS_ERROR_VULKAN,
+        };
+        Failure { status, message: error.to_string() }
+    }
+}
+
+fn set_last_error(message: String) {
+    // Interior NULs would truncate the message in C; drop them instead
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run the body of an exported function, turning errors and panics into a
+/// status and the thread's last error
+fn ffi_call<F>(body: F) -> KronosStatus
+where
+    F: FnOnce() -> Result<(), Failure>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => KronosStatus::KRONOS_SUCCESS,
+        Ok(Err(failure)) => {
+            set_last_error(failure.message);
+            failure.status
+        }
+        Err(payload) => {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+            kronos_log!(Error, "[C API] Panic at the C boundary: {}", reason);
+            set_last_error(format!("Kronos panicked: {}", reason));
+            KronosStatus::KRONOS_ERROR_INTERNAL
+        }
+    }
+}
+
+/// Borrow a handle, failing on null
+unsafe fn handle<'a, T>(pointer: *const T, name: &str) -> Result<&'a T, Failure> {
+    pointer.as_ref().ok_or_else(|| Failure::invalid(format!("{} is null", name)))
+}
+
+/// Borrow `size` bytes at `data`, failing on null unless `size` is zero
+unsafe fn bytes<'a>(data: *const c_void, size: usize, name: &str) -> Result<&'a [u8], Failure> {
+    if size == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(Failure::invalid(format!("{} is null", name)));
+    }
+    Ok(std::slice::from_raw_parts(data as *const u8, size))
+}
+
+/// Store a new handle in `*out`, failing on a null `out`
+unsafe fn emit<T>(out: *mut *mut T, value: T) -> Result<(), Failure> {
+    if out.is_null() {
+        return Err(Failure::invalid("out is null"));
+    }
+    *out = Box::into_raw(Box::new(value));
+    Ok(())
+}
+
+/// Create a context on the best available device
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_ctx_create(out: *mut *mut KronosContext) -> KronosStatus {
+    ffi_call(|| {
+        let context = ComputeContext::new()?;
+        emit(out, KronosContext { context })
+    })
+}
+
+/// Destroy a context; buffers and pipelines created from it stay usable
+/// until destroyed themselves
+///
+/// # Safety
+///
+/// `ctx` must be null or a handle from [`kronos_ctx_create`] not yet
+/// destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_ctx_destroy(ctx: *mut KronosContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// Create a device-local storage buffer of `size` bytes, filled from
+/// `data` unless it is null
+///
+/// # Safety
+///
+/// `ctx` must be null or a live context, `data` null or valid for `size`
+/// bytes of reads, and `out` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_buffer_create(
+    ctx: *const KronosContext,
+    data: *const c_void,
+    size: usize,
+    out: *mut *mut KronosBuffer,
+) -> KronosStatus {
+    ffi_call(|| {
+        let ctx = handle(ctx, "ctx")?;
+        let mut buffer = ctx.context.create_buffer_uninit(size)?;
+        if !data.is_null() {
+            buffer.write(bytes(data, size, "data")?)?;
+        }
+        emit(out, KronosBuffer { buffer })
+    })
+}
+
+/// Size of a buffer in bytes; 0 for a null handle
+///
+/// # Safety
+///
+/// `buffer` must be null or a live buffer.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_buffer_size(buffer: *const KronosBuffer) -> usize {
+    buffer.as_ref().map_or(0, |buffer| buffer.buffer.size())
+}
+
+/// Write `size` bytes from `data` to the start of a buffer
+///
+/// # Safety
+///
+/// `buffer` must be null or a live buffer not in use by another thread,
+/// and `data` null or valid for `size` bytes of reads.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_buffer_write(buffer: *mut KronosBuffer, data: *const c_void, size: usize) -> KronosStatus {
+    ffi_call(|| {
+        let buffer = buffer.as_mut().ok_or_else(|| Failure::invalid("buffer is null"))?;
+        buffer.buffer.write(bytes(data, size, "data")?)?;
+        Ok(())
+    })
+}
+
+/// Read the first `size` bytes of a buffer into `out`
+///
+/// # Safety
+///
+/// `buffer` must be null or a live buffer, and `out` null or valid for
+/// `size` bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_buffer_read(buffer: *const KronosBuffer, out: *mut c_void, size: usize) -> KronosStatus {
+    ffi_call(|| {
+        let buffer = handle(buffer, "buffer")?;
+        if size > buffer.buffer.size() {
+            return Err(Failure::invalid(format!(
+                "Read of {} bytes exceeds buffer size {}",
+                size,
+                buffer.buffer.size()
+            )));
+        }
+        if size == 0 {
+            return Ok(());
+        }
+        if out.is_null() {
+            return Err(Failure::invalid("out is null"));
+        }
+        let contents = buffer.buffer.read::<u8>()?;
+        ptr::copy_nonoverlapping(contents.as_ptr(), out as *mut u8, size);
+        Ok(())
+    })
+}
+
+/// Destroy a buffer
+///
+/// # Safety
+///
+/// `buffer` must be null or a handle from [`kronos_buffer_create`] not yet
+/// destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_buffer_destroy(buffer: *mut KronosBuffer) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(buffer));
+    }
+}
+
+/// Create a compute pipeline from `code_size` bytes of SPIR-V
+///
+/// Bindings and push constants are taken from the shader's reflection.
+///
+/// # Safety
+///
+/// `ctx` must be null or a live context, `code` null or valid for
+/// `code_size` bytes of reads, and `out` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_pipeline_create(
+    ctx: *const KronosContext,
+    code: *const u32,
+    code_size: usize,
+    out: *mut *mut KronosPipeline,
+) -> KronosStatus {
+    ffi_call(|| {
+        let ctx = handle(ctx, "ctx")?;
+        let shader = ctx.context.create_shader_from_spirv(bytes(code as *const c_void, code_size, "code")?)?;
+        let pipeline = ctx.context.create_pipeline(&shader)?;
+        emit(out, KronosPipeline { pipeline })
+    })
+}
+
+/// Destroy a pipeline
+///
+/// # Safety
+///
+/// `pipeline` must be null or a handle from [`kronos_pipeline_create`] not
+/// yet destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn kronos_pipeline_destroy(pipeline: *mut KronosPipeline) {
+    if !pipeline.is_null() {
+        drop(Box::from_raw(pipeline));
+    }
+}
+
+/// Dispatch `x * y * z` workgroups of a pipeline and wait for them
+///
+/// `buffers[i]` is bound to binding `i`; `push_constants` may be null when
+/// `push_constant_size` is 0.
+///
+/// # Safety
+///
+/// `ctx` and `pipeline` must be null or live handles, `buffers` null or
+/// valid for `buffer_count` reads of live buffers, and `push_constants`
+/// null or valid for `push_constant_size` bytes of reads.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn kronos_dispatch(
+    ctx: *const KronosContext,
+    pipeline: *const KronosPipeline,
+    buffers: *const *const KronosBuffer,
+    buffer_count: u32,
+    push_constants: *const c_void,
+    push_constant_size: usize,
+    x: u32,
+    y: u32,
+    z: u32,
+) -> KronosStatus {
+    ffi_call(|| {
+        let ctx = handle(ctx, "ctx")?;
+        let pipeline = handle(pipeline, "pipeline")?;
+        let buffers: &[*const KronosBuffer] = if buffer_count == 0 {
+            &[]
+        } else if buffers.is_null() {
+            return Err(Failure::invalid("buffers is null"));
+        } else {
+            std::slice::from_raw_parts(buffers, buffer_count as usize)
+        };
+
+        let mut builder = ctx.context.dispatch(&pipeline.pipeline);
+        for (binding, &buffer) in buffers.iter().enumerate() {
+            let buffer = handle(buffer, "buffers[i]")?;
+            builder = builder.bind_buffer(binding as u32, &buffer.buffer);
+        }
+        let push_constants = bytes(push_constants, push_constant_size, "push_constants")?;
+        if !push_constants.is_empty() {
+            builder = builder.push_constant_bytes(push_constants.to_vec());
+        }
+        builder.workgroups(x, y, z).execute()?;
+        Ok(())
+    })
+}
+
+/// Message of the last failed call on the calling thread, or null if none
+/// has failed
+///
+/// The string stays valid until the next failing call on the thread.
+#[no_mangle]
+pub extern "C" fn kronos_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
@@ -2,37 +2,50 @@
 //! 
 //! This module provides C-compatible function signatures for interop
 
-use std::ffi::{c_char, c_void};
+use std::ffi::{c_char, c_int, c_void};
 use crate::sys::*;
 use crate::core::*;
+use kronos_compute_types::vk_enum;
 
 // Vulkan error constants
 pub const VK_ERROR_OUT_OF_POOL_MEMORY: i32 = -1000069000;
+pub const VK_ERROR_INVALID_EXTERNAL_HANDLE: i32 = -1000072003;
+
+vk_enum! {
+    /// Result codes for Kronos API operations
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkResult {
+        Success = 0,
+        NotReady = 1,
+        Timeout = 2,
+        EventSet = 3,
+        EventReset = 4,
+        Incomplete = 5,
+        ErrorOutOfHostMemory = -1,
+        ErrorOutOfDeviceMemory = -2,
+        ErrorInitializationFailed = -3,
+        ErrorDeviceLost = -4,
+        ErrorMemoryMapFailed = -5,
+        ErrorLayerNotPresent = -6,
+        ErrorExtensionNotPresent = -7,
+        ErrorFeatureNotPresent = -8,
+        ErrorIncompatibleDriver = -9,
+        ErrorTooManyObjects = -10,
+        ErrorFormatNotSupported = -11,
+        ErrorFragmentedPool = -12,
+        ErrorUnknown = -13,
+        ErrorOutOfPoolMemory = VK_ERROR_OUT_OF_POOL_MEMORY,
+        ErrorInvalidExternalHandle = VK_ERROR_INVALID_EXTERNAL_HANDLE,
+    }
+}
 
-/// Result codes for Kronos API operations
-#[repr(i32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum VkResult {
-    Success = 0,
-    NotReady = 1,
-    Timeout = 2,
-    EventSet = 3,
-    EventReset = 4,
-    Incomplete = 5,
-    ErrorOutOfHostMemory = -1,
-    ErrorOutOfDeviceMemory = -2,
-    ErrorInitializationFailed = -3,
-    ErrorDeviceLost = -4,
-    ErrorMemoryMapFailed = -5,
-    ErrorLayerNotPresent = -6,
-    ErrorExtensionNotPresent = -7,
-    ErrorFeatureNotPresent = -8,
-    ErrorIncompatibleDriver = -9,
-    ErrorTooManyObjects = -10,
-    ErrorFormatNotSupported = -11,
-    ErrorFragmentedPool = -12,
-    ErrorUnknown = -13,
-    ErrorOutOfPoolMemory = VK_ERROR_OUT_OF_POOL_MEMORY,
+impl VkResult {
+    /// Result an ICD returned as a raw code, with codes this crate does not
+    /// know as `ErrorUnknown`
+    pub fn from_raw(raw: i32) -> Self {
+        Self::try_from(raw).unwrap_or(Self::ErrorUnknown)
+    }
 }
 
 /// Allocation callbacks (optional)
@@ -51,23 +64,31 @@ pub struct VkAllocationCallbacks {
 unsafe impl Send for VkAllocationCallbacks {}
 unsafe impl Sync for VkAllocationCallbacks {}
 
-#[repr(i32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum VkSystemAllocationScope {
-    Command = 0,
-    Object = 1,
-    Cache = 2,
-    Device = 3,
-    Instance = 4,
+vk_enum! {
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkSystemAllocationScope {
+        Command = 0,
+        Object = 1,
+        Cache = 2,
+        Device = 3,
+        Instance = 4,
+    }
 }
 
-#[repr(i32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum VkInternalAllocationType {
-    Executable = 0,
+vk_enum! {
+    #[repr(i32)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum VkInternalAllocationType {
+        Executable = 0,
+    }
 }
 
 // Function pointer types
+//
+// Entry points return their VkResult as a raw i32: drivers may return codes
+// this crate does not know, which are not valid VkResult values. Convert
+// with VkResult::from_raw.
 pub type PFN_vkVoidFunction = Option<unsafe extern "C" fn()>;
 
 // Core function pointers
@@ -82,34 +103,73 @@ pub type PFN_vkGetDeviceProcAddr = Option<unsafe extern "C" fn(
 ) -> PFN_vkVoidFunction>;
 
 // Instance functions
+pub type PFN_vkEnumerateInstanceVersion = Option<unsafe extern "C" fn(
+    pApiVersion: *mut u32,
+) -> i32>;
+
+pub type PFN_vkEnumerateInstanceExtensionProperties = Option<unsafe extern "C" fn(
+    pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> i32>;
+
+pub type PFN_vkEnumerateInstanceLayerProperties = Option<unsafe extern "C" fn(
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkLayerProperties,
+) -> i32>;
+
 pub type PFN_vkCreateInstance = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkInstanceCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pInstance: *mut VkInstance,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyInstance = Option<unsafe extern "C" fn(
     instance: VkInstance,
     pAllocator: *const VkAllocationCallbacks,
 )>;
 
+pub type PFN_vkCreateDebugUtilsMessengerEXT = Option<unsafe extern "C" fn(
+    instance: VkInstance,
+    pCreateInfo: *const VkDebugUtilsMessengerCreateInfoEXT,
+    pAllocator: *const VkAllocationCallbacks,
+    pMessenger: *mut VkDebugUtilsMessengerEXT,
+) -> i32>;
+
+pub type PFN_vkDestroyDebugUtilsMessengerEXT = Option<unsafe extern "C" fn(
+    instance: VkInstance,
+    messenger: VkDebugUtilsMessengerEXT,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
+
 pub type PFN_vkEnumeratePhysicalDevices = Option<unsafe extern "C" fn(
     instance: VkInstance,
     pPhysicalDeviceCount: *mut u32,
     pPhysicalDevices: *mut VkPhysicalDevice,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkGetPhysicalDeviceProperties = Option<unsafe extern "C" fn(
     physicalDevice: VkPhysicalDevice,
     pProperties: *mut VkPhysicalDeviceProperties,
 )>;
 
+pub type PFN_vkGetPhysicalDeviceProperties2 = Option<unsafe extern "C" fn(
+    physicalDevice: VkPhysicalDevice,
+    pProperties: *mut VkPhysicalDeviceProperties2,
+)>;
+
 pub type PFN_vkGetPhysicalDeviceQueueFamilyProperties = Option<unsafe extern "C" fn(
     physicalDevice: VkPhysicalDevice,
     pQueueFamilyPropertyCount: *mut u32,
     pQueueFamilyProperties: *mut VkQueueFamilyProperties,
 )>;
 
+pub type PFN_vkGetPhysicalDeviceQueueFamilyProperties2 = Option<unsafe extern "C" fn(
+    physicalDevice: VkPhysicalDevice,
+    pQueueFamilyPropertyCount: *mut u32,
+    pQueueFamilyProperties: *mut VkQueueFamilyProperties2,
+)>;
+
 pub type PFN_vkGetPhysicalDeviceMemoryProperties = Option<unsafe extern "C" fn(
     physicalDevice: VkPhysicalDevice,
     pMemoryProperties: *mut VkPhysicalDeviceMemoryProperties,
@@ -126,7 +186,7 @@ pub type PFN_vkCreateDevice = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkDeviceCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pDevice: *mut VkDevice,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyDevice = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -140,20 +200,26 @@ pub type PFN_vkGetDeviceQueue = Option<unsafe extern "C" fn(
     pQueue: *mut VkQueue,
 )>;
 
+pub type PFN_vkGetDeviceQueue2 = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pQueueInfo: *const VkDeviceQueueInfo2,
+    pQueue: *mut VkQueue,
+)>;
+
 pub type PFN_vkQueueSubmit = Option<unsafe extern "C" fn(
     queue: VkQueue,
     submitCount: u32,
     pSubmits: *const VkSubmitInfo,
     fence: VkFence,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkQueueWaitIdle = Option<unsafe extern "C" fn(
     queue: VkQueue,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDeviceWaitIdle = Option<unsafe extern "C" fn(
     device: VkDevice,
-) -> VkResult>;
+) -> i32>;
 
 // Memory functions
 pub type PFN_vkAllocateMemory = Option<unsafe extern "C" fn(
@@ -161,7 +227,7 @@ pub type PFN_vkAllocateMemory = Option<unsafe extern "C" fn(
     pAllocateInfo: *const VkMemoryAllocateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pMemory: *mut VkDeviceMemory,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkFreeMemory = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -176,20 +242,39 @@ pub type PFN_vkMapMemory = Option<unsafe extern "C" fn(
     size: VkDeviceSize,
     flags: VkMemoryMapFlags,
     ppData: *mut *mut c_void,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkUnmapMemory = Option<unsafe extern "C" fn(
     device: VkDevice,
     memory: VkDeviceMemory,
 )>;
 
+pub type PFN_vkInvalidateMappedMemoryRanges = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    memoryRangeCount: u32,
+    pMemoryRanges: *const VkMappedMemoryRange,
+) -> i32>;
+
+pub type PFN_vkGetMemoryFdPropertiesKHR = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    handleType: VkExternalMemoryHandleTypeFlags,
+    fd: c_int,
+    pMemoryFdProperties: *mut VkMemoryFdPropertiesKHR,
+) -> i32>;
+
+pub type PFN_vkGetMemoryFdKHR = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pGetFdInfo: *const VkMemoryGetFdInfoKHR,
+    pFd: *mut c_int,
+) -> i32>;
+
 // Buffer functions
 pub type PFN_vkCreateBuffer = Option<unsafe extern "C" fn(
     device: VkDevice,
     pCreateInfo: *const VkBufferCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pBuffer: *mut VkBuffer,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyBuffer = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -208,7 +293,60 @@ pub type PFN_vkBindBufferMemory = Option<unsafe extern "C" fn(
     buffer: VkBuffer,
     memory: VkDeviceMemory,
     memoryOffset: VkDeviceSize,
-) -> VkResult>;
+) -> i32>;
+
+// Image functions
+pub type PFN_vkCreateImage = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pCreateInfo: *const VkImageCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pImage: *mut VkImage,
+) -> i32>;
+
+pub type PFN_vkDestroyImage = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    image: VkImage,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
+
+pub type PFN_vkGetImageMemoryRequirements = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    image: VkImage,
+    pMemoryRequirements: *mut VkMemoryRequirements,
+)>;
+
+pub type PFN_vkBindImageMemory = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    image: VkImage,
+    memory: VkDeviceMemory,
+    memoryOffset: VkDeviceSize,
+) -> i32>;
+
+pub type PFN_vkCreateImageView = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pCreateInfo: *const VkImageViewCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pView: *mut VkImageView,
+) -> i32>;
+
+pub type PFN_vkDestroyImageView = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    imageView: VkImageView,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
+
+pub type PFN_vkCreateSampler = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pCreateInfo: *const VkSamplerCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pSampler: *mut VkSampler,
+) -> i32>;
+
+pub type PFN_vkDestroySampler = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    sampler: VkSampler,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
 
 // Command functions
 pub type PFN_vkCreateCommandPool = Option<unsafe extern "C" fn(
@@ -216,7 +354,7 @@ pub type PFN_vkCreateCommandPool = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkCommandPoolCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pCommandPool: *mut VkCommandPool,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyCommandPool = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -228,7 +366,7 @@ pub type PFN_vkAllocateCommandBuffers = Option<unsafe extern "C" fn(
     device: VkDevice,
     pAllocateInfo: *const VkCommandBufferAllocateInfo,
     pCommandBuffers: *mut VkCommandBuffer,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkFreeCommandBuffers = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -240,11 +378,11 @@ pub type PFN_vkFreeCommandBuffers = Option<unsafe extern "C" fn(
 pub type PFN_vkBeginCommandBuffer = Option<unsafe extern "C" fn(
     commandBuffer: VkCommandBuffer,
     pBeginInfo: *const VkCommandBufferBeginInfo,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkEndCommandBuffer = Option<unsafe extern "C" fn(
     commandBuffer: VkCommandBuffer,
-) -> VkResult>;
+) -> i32>;
 
 // Command buffer commands
 pub type PFN_vkCmdCopyBuffer = Option<unsafe extern "C" fn(
@@ -255,6 +393,106 @@ pub type PFN_vkCmdCopyBuffer = Option<unsafe extern "C" fn(
     pRegions: *const VkBufferCopy,
 )>;
 
+pub type PFN_vkCmdCopyBufferToImage = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    srcBuffer: VkBuffer,
+    dstImage: VkImage,
+    dstImageLayout: VkImageLayout,
+    regionCount: u32,
+    pRegions: *const VkBufferImageCopy,
+)>;
+
+pub type PFN_vkCmdCopyImageToBuffer = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    srcImage: VkImage,
+    srcImageLayout: VkImageLayout,
+    dstBuffer: VkBuffer,
+    regionCount: u32,
+    pRegions: *const VkBufferImageCopy,
+)>;
+
+pub type PFN_vkCreateQueryPool = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pCreateInfo: *const VkQueryPoolCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pQueryPool: *mut VkQueryPool,
+) -> i32>;
+
+pub type PFN_vkDestroyQueryPool = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
+
+pub type PFN_vkGetQueryPoolResults = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+    dataSize: usize,
+    pData: *mut c_void,
+    stride: VkDeviceSize,
+    flags: VkQueryResultFlags,
+) -> i32>;
+
+pub type PFN_vkCmdResetQueryPool = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+)>;
+
+pub type PFN_vkCmdWriteTimestamp = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    pipelineStage: VkPipelineStageFlags,
+    queryPool: VkQueryPool,
+    query: u32,
+)>;
+
+pub type PFN_vkCmdBeginQuery = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+    flags: VkQueryControlFlags,
+)>;
+
+pub type PFN_vkCmdEndQuery = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+)>;
+
+pub type PFN_vkEnumerateDeviceExtensionProperties = Option<unsafe extern "C" fn(
+    physicalDevice: VkPhysicalDevice,
+    pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> i32>;
+
+// VK_KHR_performance_query
+pub type PFN_vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR = Option<unsafe extern "C" fn(
+    physicalDevice: VkPhysicalDevice,
+    queueFamilyIndex: u32,
+    pCounterCount: *mut u32,
+    pCounters: *mut VkPerformanceCounterKHR,
+    pCounterDescriptions: *mut VkPerformanceCounterDescriptionKHR,
+) -> i32>;
+
+pub type PFN_vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR = Option<unsafe extern "C" fn(
+    physicalDevice: VkPhysicalDevice,
+    pPerformanceQueryCreateInfo: *const VkQueryPoolPerformanceCreateInfoKHR,
+    pNumPasses: *mut u32,
+)>;
+
+pub type PFN_vkAcquireProfilingLockKHR = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pInfo: *const VkAcquireProfilingLockInfoKHR,
+) -> i32>;
+
+pub type PFN_vkReleaseProfilingLockKHR = Option<unsafe extern "C" fn(
+    device: VkDevice,
+)>;
+
 pub type PFN_vkCmdPipelineBarrier = Option<unsafe extern "C" fn(
     commandBuffer: VkCommandBuffer,
     srcStageMask: VkPipelineStageFlags,
@@ -265,7 +503,7 @@ pub type PFN_vkCmdPipelineBarrier = Option<unsafe extern "C" fn(
     bufferMemoryBarrierCount: u32,
     pBufferMemoryBarriers: *const VkBufferMemoryBarrier,
     imageMemoryBarrierCount: u32,
-    pImageMemoryBarriers: *const c_void, // We don't support images
+    pImageMemoryBarriers: *const VkImageMemoryBarrier,
 )>;
 
 pub type PFN_vkCmdBindPipeline = Option<unsafe extern "C" fn(
@@ -298,7 +536,7 @@ pub type PFN_vkCreateFence = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkFenceCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pFence: *mut VkFence,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyFence = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -310,12 +548,12 @@ pub type PFN_vkResetFences = Option<unsafe extern "C" fn(
     device: VkDevice,
     fenceCount: u32,
     pFences: *const VkFence,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkGetFenceStatus = Option<unsafe extern "C" fn(
     device: VkDevice,
     fence: VkFence,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkWaitForFences = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -323,14 +561,14 @@ pub type PFN_vkWaitForFences = Option<unsafe extern "C" fn(
     pFences: *const VkFence,
     waitAll: VkBool32,
     timeout: u64,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkCreateSemaphore = Option<unsafe extern "C" fn(
     device: VkDevice,
     pCreateInfo: *const VkSemaphoreCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pSemaphore: *mut VkSemaphore,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroySemaphore = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -338,12 +576,24 @@ pub type PFN_vkDestroySemaphore = Option<unsafe extern "C" fn(
     pAllocator: *const VkAllocationCallbacks,
 )>;
 
+pub type PFN_vkGetSemaphoreFdKHR = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pGetFdInfo: *const VkSemaphoreGetFdInfoKHR,
+    pFd: *mut c_int,
+) -> i32>;
+
+pub type PFN_vkGetSemaphoreCounterValue = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    semaphore: VkSemaphore,
+    pValue: *mut u64,
+) -> i32>;
+
 pub type PFN_vkCreateEvent = Option<unsafe extern "C" fn(
     device: VkDevice,
     pCreateInfo: *const VkEventCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pEvent: *mut VkEvent,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyEvent = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -354,17 +604,17 @@ pub type PFN_vkDestroyEvent = Option<unsafe extern "C" fn(
 pub type PFN_vkGetEventStatus = Option<unsafe extern "C" fn(
     device: VkDevice,
     event: VkEvent,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkSetEvent = Option<unsafe extern "C" fn(
     device: VkDevice,
     event: VkEvent,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkResetEvent = Option<unsafe extern "C" fn(
     device: VkDevice,
     event: VkEvent,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkCmdSetEvent = Option<unsafe extern "C" fn(
     commandBuffer: VkCommandBuffer,
@@ -389,7 +639,7 @@ pub type PFN_vkCmdWaitEvents = Option<unsafe extern "C" fn(
     bufferMemoryBarrierCount: u32,
     pBufferMemoryBarriers: *const VkBufferMemoryBarrier,
     imageMemoryBarrierCount: u32,
-    pImageMemoryBarriers: *const c_void,
+    pImageMemoryBarriers: *const VkImageMemoryBarrier,
 )>;
 
 pub type PFN_vkCmdDispatchIndirect = Option<unsafe extern "C" fn(
@@ -398,13 +648,32 @@ pub type PFN_vkCmdDispatchIndirect = Option<unsafe extern "C" fn(
     offset: VkDeviceSize,
 )>;
 
+pub type PFN_vkCmdDispatchBase = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    baseGroupX: u32,
+    baseGroupY: u32,
+    baseGroupZ: u32,
+    groupCountX: u32,
+    groupCountY: u32,
+    groupCountZ: u32,
+)>;
+
+pub type PFN_vkCmdBeginConditionalRenderingEXT = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+    pConditionalRenderingBegin: *const VkConditionalRenderingBeginInfoEXT,
+)>;
+
+pub type PFN_vkCmdEndConditionalRenderingEXT = Option<unsafe extern "C" fn(
+    commandBuffer: VkCommandBuffer,
+)>;
+
 // Compute pipeline functions
 pub type PFN_vkCreateShaderModule = Option<unsafe extern "C" fn(
     device: VkDevice,
     pCreateInfo: *const VkShaderModuleCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pShaderModule: *mut VkShaderModule,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyShaderModule = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -419,7 +688,7 @@ pub type PFN_vkCreateComputePipelines = Option<unsafe extern "C" fn(
     pCreateInfos: *const VkComputePipelineCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pPipelines: *mut VkPipeline,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyPipeline = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -432,7 +701,7 @@ pub type PFN_vkCreatePipelineLayout = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkPipelineLayoutCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pPipelineLayout: *mut VkPipelineLayout,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyPipelineLayout = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -446,7 +715,7 @@ pub type PFN_vkCreateDescriptorSetLayout = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkDescriptorSetLayoutCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pSetLayout: *mut VkDescriptorSetLayout,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyDescriptorSetLayout = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -459,7 +728,7 @@ pub type PFN_vkCreateDescriptorPool = Option<unsafe extern "C" fn(
     pCreateInfo: *const VkDescriptorPoolCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pDescriptorPool: *mut VkDescriptorPool,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkDestroyDescriptorPool = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -471,7 +740,7 @@ pub type PFN_vkAllocateDescriptorSets = Option<unsafe extern "C" fn(
     device: VkDevice,
     pAllocateInfo: *const VkDescriptorSetAllocateInfo,
     pDescriptorSets: *mut VkDescriptorSet,
-) -> VkResult>;
+) -> i32>;
 
 pub type PFN_vkUpdateDescriptorSets = Option<unsafe extern "C" fn(
     device: VkDevice,
@@ -479,4 +748,24 @@ pub type PFN_vkUpdateDescriptorSets = Option<unsafe extern "C" fn(
     pDescriptorWrites: *const VkWriteDescriptorSet,
     descriptorCopyCount: u32,
     pDescriptorCopies: *const VkCopyDescriptorSet,
+)>;
+
+pub type PFN_vkCreateDescriptorUpdateTemplate = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    pCreateInfo: *const VkDescriptorUpdateTemplateCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pDescriptorUpdateTemplate: *mut VkDescriptorUpdateTemplate,
+) -> i32>;
+
+pub type PFN_vkDestroyDescriptorUpdateTemplate = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
+    pAllocator: *const VkAllocationCallbacks,
+)>;
+
+pub type PFN_vkUpdateDescriptorSetWithTemplate = Option<unsafe extern "C" fn(
+    device: VkDevice,
+    descriptorSet: VkDescriptorSet,
+    descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
+    pData: *const c_void,
 )>;
\ No newline at end of file
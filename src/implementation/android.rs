@@ -0,0 +1,79 @@
+//! Android's system Vulkan loader
+//!
+//! Android has no ICD manifests: drivers are only reachable through the
+//! platform loader, `libvulkan.so`, which the system linker namespace makes
+//! available to every app. On Android, [`initialize_icd_loader`] skips
+//! manifest discovery and builds its dispatch from the loader's
+//! `vkGetInstanceProcAddr`, as [`InitOptions::with_loader`] does for other
+//! loaders. Manifest discovery and the bundled software fallback are not
+//! built for Android, and the library trust policy and ICD preferences do
+//! not apply.
+//!
+//! Kronos requires API level [`MIN_API_LEVEL`] (Android 9), the first
+//! release on which every Vulkan device supports Vulkan 1.1. Older systems
+//! are refused rather than left to fail on a missing entry point.
+//!
+//! [`initialize_icd_loader`]: super::icd_loader::initialize_icd_loader
+//! [`InitOptions::with_loader`]: super::InitOptions::with_loader
+
+use std::ffi::{CStr, CString};
+use std::path::PathBuf;
+use libc::c_char;
+use crate::ffi::PFN_vkGetInstanceProcAddr;
+use super::error::IcdError;
+use super::icd_loader::{load_icd_from_entry_point, LoadedICD};
+
+/// Library name of the platform loader
+pub const SYSTEM_LOADER: &str = "libvulkan.so";
+
+/// Lowest Android API level Kronos runs on
+pub const MIN_API_LEVEL: u32 = 28;
+
+/// API level of the running system, from `ro.build.version.sdk`
+pub fn api_level() -> Option<u32> {
+    let mut value = [0 as c_char; libc::PROP_VALUE_MAX as usize];
+    // SAFETY: the name is NUL-terminated and the buffer holds PROP_VALUE_MAX
+    // bytes, the most the call writes
+    let len = unsafe { libc::__system_property_get(b"ro.build.version.sdk\0".as_ptr() as *const c_char, value.as_mut_ptr()) };
+    if len <= 0 {
+        return None;
+    }
+    // SAFETY: the call NUL-terminates the value
+    unsafe { CStr::from_ptr(value.as_ptr()) }.to_str().ok()?.trim().parse().ok()
+}
+
+/// Load the platform loader as an ICD
+pub fn load_system_loader() -> Result<LoadedICD, IcdError> {
+    match api_level() {
+        Some(level) if level < MIN_API_LEVEL => {
+            return Err(IcdError::LibraryLoadFailed(format!(
+                "{}: Android API level {} is below the required {}",
+                SYSTEM_LOADER, level, MIN_API_LEVEL
+            )));
+        }
+        Some(level) => kronos_log!(Info, "Android API level {}", level),
+        None => kronos_log!(Warn, "Could not read the Android API level; assuming at least {}", MIN_API_LEVEL),
+    }
+
+    let name = CString::new(SYSTEM_LOADER)?;
+    let symbol = CString::new("vkGetInstanceProcAddr")?;
+    // SAFETY: dlopen and dlsym get NUL-terminated names, and the symbol is
+    // the loader's vkGetInstanceProcAddr, whose signature the PFN matches
+    unsafe {
+        let handle = libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            let error = CStr::from_ptr(libc::dlerror()).to_string_lossy().into_owned();
+            return Err(IcdError::LibraryLoadFailed(format!("{}: {}", SYSTEM_LOADER, error)));
+        }
+        let ptr = libc::dlsym(handle, symbol.as_ptr());
+        if ptr.is_null() {
+            libc::dlclose(handle);
+            return Err(IcdError::MissingFunction("vkGetInstanceProcAddr"));
+        }
+        let get_instance_proc_addr: PFN_vkGetInstanceProcAddr = std::mem::transmute(ptr);
+        let mut icd = load_icd_from_entry_point(PathBuf::from(SYSTEM_LOADER), get_instance_proc_addr)?;
+        // Kept open for the life of the process, like loaded ICDs
+        icd.handle = handle;
+        Ok(icd)
+    }
+}
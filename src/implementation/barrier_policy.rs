@@ -184,7 +184,7 @@ impl BarrierBatch {
         
         if let Some(icd) = super::icd_loader::get_icd() {
             if let Some(barrier_fn) = icd.cmd_pipeline_barrier {
-                barrier_fn(
+                icd_call!("vkCmdPipelineBarrier", barrier_fn(
                     command_buffer,
                     config.src_stage,
                     config.dst_stage,
@@ -203,7 +203,7 @@ impl BarrierBatch {
                     },
                     0, // No image barriers for compute
                     std::ptr::null(),
-                );
+                ));
             }
         }
     }
@@ -265,8 +265,8 @@ impl BarrierTracker {
         } else if last_access.contains(VkAccessFlags::SHADER_WRITE)
             && new_access.contains(VkAccessFlags::SHADER_READ) {
             Some(BarrierType::WriteToRead)
-        } else if last_access == new_access {
-            None // No barrier needed
+        } else if last_access == new_access && !new_access.contains(VkAccessFlags::SHADER_WRITE) {
+            None // No barrier needed; repeated shader writes still race
         } else {
             Some(BarrierType::WriteToRead) // Conservative default
         };
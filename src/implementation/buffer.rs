@@ -3,6 +3,7 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use crate::implementation::child_objects;
 use crate::implementation::icd_loader;
 
 /// Create a buffer
@@ -12,44 +13,47 @@ use crate::implementation::icd_loader;
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pBuffer points to valid memory for writing the buffer handle
 // 5. All fields in pCreateInfo are valid (size > 0, valid usage flags, etc.)
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateBuffer(
     device: VkDevice,
     pCreateInfo: *const VkBufferCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
     pBuffer: *mut VkBuffer,
 ) -> VkResult {
-    log::info!("=== KRONOS vkCreateBuffer called ===");
-    log::info!("device: {:?}, pCreateInfo: {:?}, pBuffer: {:?}", device, pCreateInfo, pBuffer);
+    kronos_log!(Info, "=== KRONOS vkCreateBuffer called ===");
+    kronos_log!(Info, "device: {:?}, pCreateInfo: {:?}, pBuffer: {:?}", device, pCreateInfo, pBuffer);
     
     if device.is_null() || pCreateInfo.is_null() || pBuffer.is_null() {
-        log::error!("vkCreateBuffer: NULL parameter detected, returning ErrorInitializationFailed");
+        kronos_log!(Error, "vkCreateBuffer: NULL parameter detected, returning ErrorInitializationFailed");
         return VkResult::ErrorInitializationFailed;
     }
+    if let Some(result) = crate::testing::injected_failure(crate::testing::Site::CreateBuffer) {
+        return result;
+    }
     
     // Route via owning ICD if known
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        log::debug!("Found ICD for device {:?}", device);
+        kronos_log!(Debug, "Found ICD for device {:?}", device);
         if let Some(f) = icd.create_buffer { 
-            log::debug!("ICD has create_buffer function, calling it");
-            return f(device, pCreateInfo, pAllocator, pBuffer); 
+            kronos_log!(Debug, "ICD has create_buffer function, calling it");
+            return child_objects::created(device, icd_call!("vkCreateBuffer", f(device, pCreateInfo, pAllocator, pBuffer)), pBuffer, 1); 
         } else {
-            log::error!("ICD for device {:?} does not have create_buffer function!", device);
+            kronos_log!(Error, "ICD for device {:?} does not have create_buffer function!", device);
         }
     } else {
-        log::warn!("No ICD found for device {:?} - checking fallback", device);
+        kronos_log!(Warn, "No ICD found for device {:?} - checking fallback", device);
     }
     // Fallback
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        log::info!("Using fallback ICD for buffer creation");
+        kronos_log!(Info, "Using fallback ICD for buffer creation");
         if let Some(create_buffer) = icd.create_buffer { 
-            log::info!("Fallback ICD has create_buffer function, calling it");
-            return create_buffer(device, pCreateInfo, pAllocator, pBuffer); 
+            kronos_log!(Info, "Fallback ICD has create_buffer function, calling it");
+            return child_objects::created(device, icd_call!("vkCreateBuffer", create_buffer(device, pCreateInfo, pAllocator, pBuffer)), pBuffer, 1); 
         } else {
-            log::error!("Fallback ICD does not have create_buffer function!");
+            kronos_log!(Error, "Fallback ICD does not have create_buffer function!");
         }
     }
-    log::error!("No ICD available for buffer creation - returning ErrorInitializationFailed");
+    kronos_log!(Error, "No ICD available for buffer creation - returning ErrorInitializationFailed");
     VkResult::ErrorInitializationFailed
 }
 
@@ -60,7 +64,7 @@ pub unsafe extern "C" fn vkCreateBuffer(
 // 3. pAllocator matches the allocator used in vkCreateBuffer (or both are null)
 // 4. The buffer is not currently bound to memory or in use by any operations
 // 5. All command buffers using this buffer have completed execution
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyBuffer(
     device: VkDevice,
     buffer: VkBuffer,
@@ -69,13 +73,14 @@ pub unsafe extern "C" fn vkDestroyBuffer(
     if device.is_null() || buffer.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, buffer);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_buffer { f(device, buffer, pAllocator); }
+        if let Some(f) = icd.destroy_buffer { icd_call!("vkDestroyBuffer", f(device, buffer, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_buffer) = icd.destroy_buffer { destroy_buffer(device, buffer, pAllocator); }
+        if let Some(destroy_buffer) = icd.destroy_buffer { icd_call!("vkDestroyBuffer", destroy_buffer(device, buffer, pAllocator)); }
     }
 }
 
@@ -85,7 +90,7 @@ pub unsafe extern "C" fn vkDestroyBuffer(
 // 2. buffer is a valid VkBuffer created by vkCreateBuffer
 // 3. pMemoryRequirements points to valid memory for a VkMemoryRequirements structure
 // 4. The buffer has not been destroyed
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetBufferMemoryRequirements(
     device: VkDevice,
     buffer: VkBuffer,
@@ -96,11 +101,11 @@ pub unsafe extern "C" fn vkGetBufferMemoryRequirements(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.get_buffer_memory_requirements { f(device, buffer, pMemoryRequirements); }
+        if let Some(f) = icd.get_buffer_memory_requirements { icd_call!("vkGetBufferMemoryRequirements", f(device, buffer, pMemoryRequirements)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(get_buffer_memory_requirements) = icd.get_buffer_memory_requirements { get_buffer_memory_requirements(device, buffer, pMemoryRequirements); }
+        if let Some(get_buffer_memory_requirements) = icd.get_buffer_memory_requirements { icd_call!("vkGetBufferMemoryRequirements", get_buffer_memory_requirements(device, buffer, pMemoryRequirements)); }
     }
 }
 
@@ -112,7 +117,7 @@ pub unsafe extern "C" fn vkGetBufferMemoryRequirements(
 // 4. memoryOffset + buffer.size <= memory.size (fits within allocated memory)
 // 5. The memory type is compatible with the buffer's memory requirements
 // 6. Neither buffer nor memory are currently in use by GPU operations
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkBindBufferMemory(
     device: VkDevice,
     buffer: VkBuffer,
@@ -124,10 +129,10 @@ pub unsafe extern "C" fn vkBindBufferMemory(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.bind_buffer_memory { return f(device, buffer, memory, memoryOffset); }
+        if let Some(f) = icd.bind_buffer_memory { return icd_call!("vkBindBufferMemory", f(device, buffer, memory, memoryOffset)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(bind_buffer_memory) = icd.bind_buffer_memory { return bind_buffer_memory(device, buffer, memory, memoryOffset); }
+        if let Some(bind_buffer_memory) = icd.bind_buffer_memory { return icd_call!("vkBindBufferMemory", bind_buffer_memory(device, buffer, memory, memoryOffset)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -0,0 +1,148 @@
+//! Fallback to a software ICD shipped with the application
+//!
+//! With the `bundled-swiftshader` feature, [`initialize_icd_loader`] looks
+//! for a software renderer next to the executable when no hardware ICD
+//! loads, so an application that ships SwiftShader or lavapipe always has a
+//! compute path. The bundle is searched in the executable's directory and
+//! its `vulkan`, `swiftshader` and `lib` subdirectories, or only in
+//! `KRONOS_BUNDLED_ICD_DIR` when that is set. A manifest whose file name
+//! names a known renderer is preferred; otherwise the renderer's library is
+//! loaded directly.
+//!
+//! The directory a bundled driver is loaded from is registered with
+//! [`add_library_search_dir`], so it passes the library trust policy.
+//! Set `KRONOS_SOFTWARE_FALLBACK=0` or call [`set_software_fallback`] to
+//! turn the fallback off.
+//!
+//! [`initialize_icd_loader`]: super::icd_loader::initialize_icd_loader
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use super::logging::redact;
+use super::icd_loader::{
+    add_library_search_dir, is_software_library, library_candidates, load_icd, parse_icd_manifest,
+    LibrarySearchDir, LoadedICD,
+};
+
+/// Directories, relative to the executable's, searched for a bundled driver
+pub const BUNDLE_DIRS: &[&str] = &[".", "vulkan", "swiftshader", "lib"];
+
+/// Library names of the software renderers, in order of preference
+#[cfg(target_os = "windows")]
+const LIBRARY_NAMES: &[&str] = &["vk_swiftshader.dll", "vulkan_lvp.dll"];
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: &[&str] = &["libvk_swiftshader.dylib", "libvulkan_lvp.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_NAMES: &[&str] = &["libvk_swiftshader.so", "libvulkan_lvp.so"];
+
+static SOFTWARE_FALLBACK: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid loading a bundled software ICD
+///
+/// Takes effect the next time the loader is initialized.
+pub fn set_software_fallback(allowed: bool) {
+    SOFTWARE_FALLBACK.store(allowed, Ordering::Relaxed);
+}
+
+/// Whether a bundled software ICD may be loaded
+pub fn software_fallback_allowed() -> bool {
+    let env_allows = env::var("KRONOS_SOFTWARE_FALLBACK").map(|v| v != "0").unwrap_or(true);
+    env_allows && SOFTWARE_FALLBACK.load(Ordering::Relaxed)
+}
+
+/// Existing directories searched for a bundled driver, canonicalized
+pub fn bundle_dirs() -> Vec<PathBuf> {
+    let dirs: Vec<PathBuf> = match env::var_os("KRONOS_BUNDLED_ICD_DIR") {
+        Some(dir) => vec![PathBuf::from(dir)],
+        None => BUNDLE_DIRS
+            .iter()
+            .filter_map(|dir| LibrarySearchDir::ExecutableRelative(PathBuf::from(dir)).resolve())
+            .collect(),
+    };
+
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .filter(|dir| dir.is_dir() && seen.insert(dir.clone()))
+        .collect()
+}
+
+/// Libraries to try in `dir`: those named by renderer manifests, then the
+/// known library names
+fn bundle_candidates(dir: &Path) -> Vec<PathBuf> {
+    let mut manifests: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+                .filter(|path| is_software_library(Path::new(path.file_name().unwrap_or_default())))
+                .collect()
+        })
+        .unwrap_or_default();
+    manifests.sort();
+
+    let mut candidates = Vec::new();
+    for manifest_path in &manifests {
+        if let Some(manifest) = parse_icd_manifest(manifest_path) {
+            kronos_log!(Debug, "Found bundled ICD manifest: {}", redact(&manifest_path));
+            candidates.extend(library_candidates(&manifest.library_path, manifest_path));
+        }
+    }
+    candidates.extend(LIBRARY_NAMES.iter().map(|name| dir.join(name)));
+    candidates
+}
+
+/// Load the first bundled software ICD found
+pub fn load_bundled_icd() -> Option<LoadedICD> {
+    for dir in bundle_dirs() {
+        for candidate in bundle_candidates(&dir) {
+            let Ok(library) = fs::canonicalize(&candidate) else {
+                continue;
+            };
+            add_library_search_dir(LibrarySearchDir::Path(dir.clone()));
+            kronos_log!(Info, "Attempting to load bundled ICD library: {}", redact(&library));
+            match load_icd(&library) {
+                Ok(icd) => return Some(icd),
+                Err(e) => kronos_log!(Warn, "Failed to load bundled ICD {}: {}", redact(&library), e),
+            }
+        }
+    }
+    kronos_log!(Debug, "No bundled software ICD found");
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_candidates_prefer_manifests() {
+        let dir = env::temp_dir().join(format!("kronos-bundle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("vk_swiftshader_icd.json"),
+            r#"{"file_format_version": "1.0.0", "ICD": {"library_path": "./custom_swiftshader.so"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("unrelated.json"), "{}").unwrap();
+
+        let candidates = bundle_candidates(&dir);
+        let manifest_relative = candidates.iter().position(|c| *c == dir.join("./custom_swiftshader.so"));
+        let known_name = candidates.iter().position(|c| *c == dir.join(LIBRARY_NAMES[0]));
+        assert!(manifest_relative.unwrap() < known_name.unwrap());
+        assert_eq!(candidates.last(), Some(&dir.join(LIBRARY_NAMES[LIBRARY_NAMES.len() - 1])));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_software_fallback_toggle() {
+        set_software_fallback(false);
+        assert!(!software_fallback_allowed());
+        set_software_fallback(true);
+        assert_eq!(software_fallback_allowed(), env::var("KRONOS_SOFTWARE_FALLBACK").as_deref() != Ok("0"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Child object tracking for vkDestroyDevice
+//!
+//! Destroying a device while buffers, memory or pipelines created from it
+//! are still alive is undefined behaviour, which drivers answer with
+//! anything from a leak to a crash several calls later. The safe API never
+//! does so, since its objects keep their context alive, but callers of the
+//! raw entry points have no such guarantee. Every object created through
+//! them is recorded against its device until it is destroyed, and
+//! vkDestroyDevice applies a [`DestroyPolicy`] to whatever is left.
+//!
+//! Command buffers and descriptor sets are freed with their pools and are
+//! not tracked themselves.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use crate::sys::*;
+use crate::ffi::*;
+
+/// What vkDestroyDevice does with a device that still has child objects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DestroyPolicy {
+    /// Log a warning listing the children, then destroy the device
+    #[default]
+    Warn,
+    /// Destroy the device once its last child has been destroyed
+    Defer,
+    /// Log an error listing the children and leave the device alive
+    Fail,
+}
+
+#[derive(Default)]
+struct Children {
+    /// Vulkan type name and raw handle of each live child
+    live: BTreeSet<(&'static str, u64)>,
+    /// Allocator of a vkDestroyDevice deferred until `live` empties
+    deferred: Option<usize>,
+}
+
+lazy_static::lazy_static! {
+    static ref POLICY: Mutex<DestroyPolicy> = Mutex::new(DestroyPolicy::default());
+    static ref CHILDREN: Mutex<HashMap<u64, Children>> = Mutex::new(HashMap::new());
+}
+
+/// Set what vkDestroyDevice does with devices that have live children
+pub fn set_destroy_policy(policy: DestroyPolicy) {
+    *POLICY.lock().unwrap() = policy;
+}
+
+/// Current [`DestroyPolicy`]
+pub fn destroy_policy() -> DestroyPolicy {
+    *POLICY.lock().unwrap()
+}
+
+/// Vulkan type name and raw handle of each live child of `device`
+pub fn live_children(device: VkDevice) -> Vec<(&'static str, u64)> {
+    CHILDREN
+        .lock()
+        .unwrap()
+        .get(&device.as_raw())
+        .map(|children| children.live.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Record the `count` handles at `handles` as children of `device` if
+/// `result` says they were created, and pass `result` on
+///
+/// # Safety
+///
+/// On success, `handles` must point to `count` handles.
+pub(crate) unsafe fn created<T: HandleType>(device: VkDevice, result: VkResult, handles: *const Handle<T>, count: u32) -> VkResult {
+    if result != VkResult::Success || handles.is_null() {
+        return result;
+    }
+    let mut children = CHILDREN.lock().unwrap();
+    let live = &mut children.entry(device.as_raw()).or_default().live;
+    for handle in std::slice::from_raw_parts(handles, count as usize) {
+        if !handle.is_null() {
+            live.insert((T::NAME, handle.as_raw()));
+        }
+    }
+    result
+}
+
+/// Forgets a child of a device once dropped, at the end of the destroy
+/// call, and finishes a deferred vkDestroyDevice if it was the last one
+#[must_use]
+pub(crate) struct Destroyed {
+    device: VkDevice,
+    child: (&'static str, u64),
+}
+
+/// Forget `handle` as a child of `device` when the returned guard drops
+pub(crate) fn destroyed<T: HandleType>(device: VkDevice, handle: Handle<T>) -> Destroyed {
+    Destroyed { device, child: (T::NAME, handle.as_raw()) }
+}
+
+impl Drop for Destroyed {
+    fn drop(&mut self) {
+        let deferred = {
+            let mut children = CHILDREN.lock().unwrap();
+            let Some(entry) = children.get_mut(&self.device.as_raw()) else { return };
+            entry.live.remove(&self.child);
+            if !entry.live.is_empty() || entry.deferred.is_none() {
+                return;
+            }
+            children.remove(&self.device.as_raw()).and_then(|entry| entry.deferred)
+        };
+        if let Some(allocator) = deferred {
+            kronos_log!(Info, "Last child of device {:?} destroyed; destroying the device", self.device);
+            // SAFETY: the allocator was passed to vkDestroyDevice for this device
+            unsafe { super::device::destroy_device(self.device, allocator as *const VkAllocationCallbacks) };
+        }
+    }
+}
+
+/// Apply the [`DestroyPolicy`] to `device`, returning whether
+/// vkDestroyDevice should go ahead
+pub(crate) fn may_destroy(device: VkDevice, allocator: *const VkAllocationCallbacks) -> bool {
+    let mut children = CHILDREN.lock().unwrap();
+    let Some(entry) = children.get_mut(&device.as_raw()).filter(|entry| !entry.live.is_empty()) else {
+        children.remove(&device.as_raw());
+        return true;
+    };
+    let list = entry
+        .live
+        .iter()
+        .map(|(name, raw)| format!("{}({:#x})", name, raw))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match destroy_policy() {
+        DestroyPolicy::Warn => {
+            kronos_log!(Warn, "vkDestroyDevice({:?}) with {} live children: {}", device, entry.live.len(), list);
+            children.remove(&device.as_raw());
+            true
+        }
+        DestroyPolicy::Defer => {
+            kronos_log!(Info, "vkDestroyDevice({:?}) deferred until {} live children are destroyed: {}", device, entry.live.len(), list);
+            entry.deferred = Some(allocator as usize);
+            false
+        }
+        DestroyPolicy::Fail => {
+            kronos_log!(Error, "vkDestroyDevice({:?}) refused with {} live children: {}", device, entry.live.len(), list);
+            false
+        }
+    }
+}
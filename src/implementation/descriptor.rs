@@ -3,6 +3,7 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use crate::implementation::child_objects;
 use crate::implementation::icd_loader;
 
 /// Create descriptor set layout
@@ -13,7 +14,7 @@ use crate::implementation::icd_loader;
 // 4. pSetLayout points to valid memory for writing the layout handle
 // 5. All binding descriptions in pCreateInfo are valid
 // 6. Descriptor types and shader stages are appropriate for compute
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateDescriptorSetLayout(
     device: VkDevice,
     pCreateInfo: *const VkDescriptorSetLayoutCreateInfo,
@@ -25,10 +26,10 @@ pub unsafe extern "C" fn vkCreateDescriptorSetLayout(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.create_descriptor_set_layout { return f(device, pCreateInfo, pAllocator, pSetLayout); }
+        if let Some(f) = icd.create_descriptor_set_layout { return child_objects::created(device, icd_call!("vkCreateDescriptorSetLayout", f(device, pCreateInfo, pAllocator, pSetLayout)), pSetLayout, 1); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(create_descriptor_set_layout) = icd.create_descriptor_set_layout { return create_descriptor_set_layout(device, pCreateInfo, pAllocator, pSetLayout); }
+        if let Some(create_descriptor_set_layout) = icd.create_descriptor_set_layout { return child_objects::created(device, icd_call!("vkCreateDescriptorSetLayout", create_descriptor_set_layout(device, pCreateInfo, pAllocator, pSetLayout)), pSetLayout, 1); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -40,7 +41,7 @@ pub unsafe extern "C" fn vkCreateDescriptorSetLayout(
 // 3. pAllocator matches the allocator used in vkCreateDescriptorSetLayout
 // 4. No descriptor sets using this layout are currently allocated
 // 5. No pipelines reference this layout
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyDescriptorSetLayout(
     device: VkDevice,
     descriptorSetLayout: VkDescriptorSetLayout,
@@ -49,13 +50,14 @@ pub unsafe extern "C" fn vkDestroyDescriptorSetLayout(
     if device.is_null() || descriptorSetLayout.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, descriptorSetLayout);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_descriptor_set_layout { f(device, descriptorSetLayout, pAllocator); }
+        if let Some(f) = icd.destroy_descriptor_set_layout { icd_call!("vkDestroyDescriptorSetLayout", f(device, descriptorSetLayout, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_descriptor_set_layout) = icd.destroy_descriptor_set_layout { destroy_descriptor_set_layout(device, descriptorSetLayout, pAllocator); }
+        if let Some(destroy_descriptor_set_layout) = icd.destroy_descriptor_set_layout { icd_call!("vkDestroyDescriptorSetLayout", destroy_descriptor_set_layout(device, descriptorSetLayout, pAllocator)); }
     }
 }
 
@@ -67,7 +69,7 @@ pub unsafe extern "C" fn vkDestroyDescriptorSetLayout(
 // 4. pDescriptorPool points to valid memory for writing the pool handle
 // 5. Pool sizes and max sets are reasonable values
 // 6. Descriptor types match what will be allocated from this pool
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateDescriptorPool(
     device: VkDevice,
     pCreateInfo: *const VkDescriptorPoolCreateInfo,
@@ -79,10 +81,10 @@ pub unsafe extern "C" fn vkCreateDescriptorPool(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.create_descriptor_pool { return f(device, pCreateInfo, pAllocator, pDescriptorPool); }
+        if let Some(f) = icd.create_descriptor_pool { return child_objects::created(device, icd_call!("vkCreateDescriptorPool", f(device, pCreateInfo, pAllocator, pDescriptorPool)), pDescriptorPool, 1); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(create_descriptor_pool) = icd.create_descriptor_pool { return create_descriptor_pool(device, pCreateInfo, pAllocator, pDescriptorPool); }
+        if let Some(create_descriptor_pool) = icd.create_descriptor_pool { return child_objects::created(device, icd_call!("vkCreateDescriptorPool", create_descriptor_pool(device, pCreateInfo, pAllocator, pDescriptorPool)), pDescriptorPool, 1); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -94,7 +96,7 @@ pub unsafe extern "C" fn vkCreateDescriptorPool(
 // 3. pAllocator matches the allocator used in vkCreateDescriptorPool
 // 4. All descriptor sets allocated from this pool have been freed or will be freed
 // 5. No command buffers are using descriptor sets from this pool
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyDescriptorPool(
     device: VkDevice,
     descriptorPool: VkDescriptorPool,
@@ -103,13 +105,14 @@ pub unsafe extern "C" fn vkDestroyDescriptorPool(
     if device.is_null() || descriptorPool.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, descriptorPool);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_descriptor_pool { f(device, descriptorPool, pAllocator); }
+        if let Some(f) = icd.destroy_descriptor_pool { icd_call!("vkDestroyDescriptorPool", f(device, descriptorPool, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_descriptor_pool) = icd.destroy_descriptor_pool { destroy_descriptor_pool(device, descriptorPool, pAllocator); }
+        if let Some(destroy_descriptor_pool) = icd.destroy_descriptor_pool { icd_call!("vkDestroyDescriptorPool", destroy_descriptor_pool(device, descriptorPool, pAllocator)); }
     }
 }
 
@@ -120,7 +123,7 @@ pub unsafe extern "C" fn vkDestroyDescriptorPool(
 // 3. flags is a valid VkDescriptorPoolResetFlags value
 // 4. All descriptor sets allocated from this pool become invalid after reset
 // 5. No command buffers are currently using descriptor sets from this pool
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkResetDescriptorPool(
     device: VkDevice,
     descriptorPool: VkDescriptorPool,
@@ -131,10 +134,10 @@ pub unsafe extern "C" fn vkResetDescriptorPool(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.reset_descriptor_pool { return f(device, descriptorPool, flags); }
+        if let Some(f) = icd.reset_descriptor_pool { return icd_call!("vkResetDescriptorPool", f(device, descriptorPool, flags)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(reset_descriptor_pool) = icd.reset_descriptor_pool { return reset_descriptor_pool(device, descriptorPool, flags); }
+        if let Some(reset_descriptor_pool) = icd.reset_descriptor_pool { return icd_call!("vkResetDescriptorPool", reset_descriptor_pool(device, descriptorPool, flags)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -147,7 +150,7 @@ pub unsafe extern "C" fn vkResetDescriptorPool(
 // 4. The descriptor pool has sufficient space for the requested sets
 // 5. All descriptor set layouts in pAllocateInfo are valid
 // 6. The descriptor pool was not created with FREE_DESCRIPTOR_SET_BIT if individual freeing is needed
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkAllocateDescriptorSets(
     device: VkDevice,
     pAllocateInfo: *const VkDescriptorSetAllocateInfo,
@@ -158,10 +161,10 @@ pub unsafe extern "C" fn vkAllocateDescriptorSets(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.allocate_descriptor_sets { return f(device, pAllocateInfo, pDescriptorSets); }
+        if let Some(f) = icd.allocate_descriptor_sets { return icd_call!("vkAllocateDescriptorSets", f(device, pAllocateInfo, pDescriptorSets)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(allocate_descriptor_sets) = icd.allocate_descriptor_sets { return allocate_descriptor_sets(device, pAllocateInfo, pDescriptorSets); }
+        if let Some(allocate_descriptor_sets) = icd.allocate_descriptor_sets { return icd_call!("vkAllocateDescriptorSets", allocate_descriptor_sets(device, pAllocateInfo, pDescriptorSets)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -175,7 +178,7 @@ pub unsafe extern "C" fn vkAllocateDescriptorSets(
 // 5. All descriptor sets were allocated from the specified pool
 // 6. The pool was created with VK_DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT
 // 7. No command buffers are currently using these descriptor sets
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkFreeDescriptorSets(
     device: VkDevice,
     descriptorPool: VkDescriptorPool,
@@ -187,10 +190,10 @@ pub unsafe extern "C" fn vkFreeDescriptorSets(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.free_descriptor_sets { return f(device, descriptorPool, descriptorSetCount, pDescriptorSets); }
+        if let Some(f) = icd.free_descriptor_sets { return icd_call!("vkFreeDescriptorSets", f(device, descriptorPool, descriptorSetCount, pDescriptorSets)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(free_descriptor_sets) = icd.free_descriptor_sets { return free_descriptor_sets(device, descriptorPool, descriptorSetCount, pDescriptorSets); }
+        if let Some(free_descriptor_sets) = icd.free_descriptor_sets { return icd_call!("vkFreeDescriptorSets", free_descriptor_sets(device, descriptorPool, descriptorSetCount, pDescriptorSets)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -204,7 +207,7 @@ pub unsafe extern "C" fn vkFreeDescriptorSets(
 // 5. Buffer, image, and sampler resources referenced in writes are valid
 // 6. Descriptor types match the layout bindings
 // 7. No command buffers are currently using the descriptor sets being updated
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkUpdateDescriptorSets(
     device: VkDevice,
     descriptorWriteCount: u32,
@@ -217,12 +220,96 @@ pub unsafe extern "C" fn vkUpdateDescriptorSets(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.update_descriptor_sets { f(device, descriptorWriteCount, pDescriptorWrites, descriptorCopyCount, pDescriptorCopies); }
+        if let Some(f) = icd.update_descriptor_sets { icd_call!("vkUpdateDescriptorSets", f(device, descriptorWriteCount, pDescriptorWrites, descriptorCopyCount, pDescriptorCopies)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(update_descriptor_sets) = icd.update_descriptor_sets {
-            update_descriptor_sets(device, descriptorWriteCount, pDescriptorWrites, descriptorCopyCount, pDescriptorCopies);
+            icd_call!("vkUpdateDescriptorSets", update_descriptor_sets(device, descriptorWriteCount, pDescriptorWrites, descriptorCopyCount, pDescriptorCopies));
+        }
+    }
+}
+
+/// Create descriptor update template
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. pCreateInfo points to a valid VkDescriptorUpdateTemplateCreateInfo structure
+// 3. pAllocator is either null or points to valid allocation callbacks
+// 4. pDescriptorUpdateTemplate points to valid memory for writing the template handle
+// 5. Every entry matches a binding of the descriptor set layout
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateDescriptorUpdateTemplate(
+    device: VkDevice,
+    pCreateInfo: *const VkDescriptorUpdateTemplateCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pDescriptorUpdateTemplate: *mut VkDescriptorUpdateTemplate,
+) -> VkResult {
+    if device.is_null() || pCreateInfo.is_null() || pDescriptorUpdateTemplate.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.create_descriptor_update_template { return child_objects::created(device, icd_call!("vkCreateDescriptorUpdateTemplate", f(device, pCreateInfo, pAllocator, pDescriptorUpdateTemplate)), pDescriptorUpdateTemplate, 1); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(create_descriptor_update_template) = icd.create_descriptor_update_template {
+            return child_objects::created(device, icd_call!("vkCreateDescriptorUpdateTemplate", create_descriptor_update_template(device, pCreateInfo, pAllocator, pDescriptorUpdateTemplate)), pDescriptorUpdateTemplate, 1);
+        }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Destroy descriptor update template
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. descriptorUpdateTemplate is a valid VkDescriptorUpdateTemplate, or VK_NULL_HANDLE
+// 3. pAllocator matches the allocator used in vkCreateDescriptorUpdateTemplate
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroyDescriptorUpdateTemplate(
+    device: VkDevice,
+    descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if device.is_null() || descriptorUpdateTemplate.is_null() {
+        return;
+    }
+    let _destroyed = child_objects::destroyed(device, descriptorUpdateTemplate);
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.destroy_descriptor_update_template { icd_call!("vkDestroyDescriptorUpdateTemplate", f(device, descriptorUpdateTemplate, pAllocator)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(destroy_descriptor_update_template) = icd.destroy_descriptor_update_template {
+            icd_call!("vkDestroyDescriptorUpdateTemplate", destroy_descriptor_update_template(device, descriptorUpdateTemplate, pAllocator));
+        }
+    }
+}
+
+/// Update a descriptor set from raw data laid out by a template
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. descriptorSet is a valid VkDescriptorSet not in use by pending command buffers
+// 3. descriptorUpdateTemplate was created for the layout of descriptorSet
+// 4. pData holds a descriptor info at the offset and stride of every template entry
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkUpdateDescriptorSetWithTemplate(
+    device: VkDevice,
+    descriptorSet: VkDescriptorSet,
+    descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
+    pData: *const std::ffi::c_void,
+) {
+    if device.is_null() || descriptorSet.is_null() || descriptorUpdateTemplate.is_null() {
+        return;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.update_descriptor_set_with_template { icd_call!("vkUpdateDescriptorSetWithTemplate", f(device, descriptorSet, descriptorUpdateTemplate, pData)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(update_descriptor_set_with_template) = icd.update_descriptor_set_with_template {
+            icd_call!("vkUpdateDescriptorSetWithTemplate", update_descriptor_set_with_template(device, descriptorSet, descriptorUpdateTemplate, pData));
         }
     }
 }
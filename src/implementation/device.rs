@@ -11,7 +11,7 @@ use crate::implementation::icd_loader;
 // 2. pCreateInfo points to a valid VkDeviceCreateInfo structure
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pDevice points to valid memory for writing the device handle
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateDevice(
     physicalDevice: VkPhysicalDevice,
     pCreateInfo: *const VkDeviceCreateInfo,
@@ -25,28 +25,29 @@ pub unsafe extern "C" fn vkCreateDevice(
     // Aggregated-aware: prefer ICD owning the physical device
     if let Some(icd_arc) = icd_loader::icd_for_physical_device(physicalDevice) {
         if let Some(create_device_fn) = icd_arc.create_device {
-            let result = create_device_fn(physicalDevice, pCreateInfo, pAllocator, pDevice);
+            let result = icd_call!("vkCreateDevice", create_device_fn(physicalDevice, pCreateInfo, pAllocator, pDevice));
             if result == VkResult::Success {
-                log::info!("Device creation successful for physical device {:?}, new device: {:?}", physicalDevice, *pDevice);
+                kronos_log!(Info, "Device creation successful for physical device {:?}, new device: {:?}", physicalDevice, *pDevice);
                 // Load device-level functions into a cloned ICD and register device → ICD mapping
                 let mut cloned = (*icd_arc).clone();
-                match icd_loader::load_device_functions_inner(&mut cloned, *pDevice) {
+                let api = icd_loader::DeviceApi::new(&icd_arc, physicalDevice, &*pCreateInfo);
+                match icd_loader::load_device_functions_inner(&mut cloned, *pDevice, api) {
                     Ok(()) => {
-                        log::info!("Successfully loaded device functions for device {:?}", *pDevice);
+                        kronos_log!(Info, "Successfully loaded device functions for device {:?}", *pDevice);
                         // Check if create_buffer was loaded
                         if cloned.create_buffer.is_some() {
-                            log::info!("create_buffer function loaded successfully");
+                            kronos_log!(Info, "create_buffer function loaded successfully");
                         } else {
-                            log::warn!("create_buffer function NOT loaded!");
+                            kronos_log!(Warn, "create_buffer function NOT loaded!");
                         }
                     }
                     Err(e) => {
-                        log::error!("Failed to load device functions: {:?}", e);
+                        kronos_log!(Error, "Failed to load device functions: {:?}", e);
                     }
                 }
                 let updated = std::sync::Arc::new(cloned);
                 icd_loader::register_device_icd(*pDevice, &updated);
-                log::info!("Registered device {:?} with ICD", *pDevice);
+                kronos_log!(Info, "Registered device {:?} with ICD", *pDevice);
             }
             return result;
         }
@@ -55,9 +56,10 @@ pub unsafe extern "C" fn vkCreateDevice(
     // Fallback to single-ICD driver
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(create_device_fn) = icd.create_device {
-            let result = create_device_fn(physicalDevice, pCreateInfo, pAllocator, pDevice);
+            let result = icd_call!("vkCreateDevice", create_device_fn(physicalDevice, pCreateInfo, pAllocator, pDevice));
             if result == VkResult::Success {
-                let _ = super::icd_loader::update_device_functions(*pDevice);
+                let api = icd_loader::DeviceApi::new(&icd, physicalDevice, &*pCreateInfo);
+                let _ = super::icd_loader::update_device_functions(*pDevice, api);
             }
             return result;
         }
@@ -70,8 +72,9 @@ pub unsafe extern "C" fn vkCreateDevice(
 // SAFETY: This function is called from C code. Caller must ensure:
 // 1. device is a valid VkDevice created by vkCreateDevice
 // 2. pAllocator matches the allocator used in vkCreateDevice (or both are null)
-// 3. All objects created from this device have been destroyed
-#[no_mangle]
+// 3. All objects created from this device have been destroyed, or the
+//    child_objects::DestroyPolicy decides what happens to the device
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyDevice(
     device: VkDevice,
     pAllocator: *const VkAllocationCallbacks,
@@ -79,11 +82,17 @@ pub unsafe extern "C" fn vkDestroyDevice(
     if device.is_null() {
         return;
     }
-    
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    if super::child_objects::may_destroy(device, pAllocator) {
+        destroy_device(device, pAllocator);
+    }
+}
+
+/// Destroy `device` in its ICD, whatever children it has left
+pub(crate) unsafe fn destroy_device(device: VkDevice, pAllocator: *const VkAllocationCallbacks) {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = icd_loader::icd_for_device(device) {
         if let Some(destroy_device) = icd.destroy_device {
-            destroy_device(device, pAllocator);
+            icd_call!("vkDestroyDevice", destroy_device(device, pAllocator));
         }
     }
 
@@ -96,7 +105,7 @@ pub unsafe extern "C" fn vkDestroyDevice(
 // 1. device is a valid VkDevice
 // 2. queueFamilyIndex and queueIndex are valid for this device
 // 3. pQueue points to valid memory for writing the queue handle
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetDeviceQueue(
     device: VkDevice,
     queueFamilyIndex: u32,
@@ -110,7 +119,7 @@ pub unsafe extern "C" fn vkGetDeviceQueue(
     // Route via owning ICD if known
     if let Some(icd) = icd_loader::icd_for_device(device) {
         if let Some(f) = icd.get_device_queue {
-            f(device, queueFamilyIndex, queueIndex, pQueue);
+            icd_call!("vkGetDeviceQueue", f(device, queueFamilyIndex, queueIndex, pQueue));
             if let Some(queue) = pQueue.as_ref() {
                 // Register queue → ICD mapping
                 icd_loader::register_queue_icd(unsafe { *queue }, &icd);
@@ -121,7 +130,41 @@ pub unsafe extern "C" fn vkGetDeviceQueue(
     // Fallback
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(get_device_queue) = icd.get_device_queue {
-            get_device_queue(device, queueFamilyIndex, queueIndex, pQueue);
+            icd_call!("vkGetDeviceQueue", get_device_queue(device, queueFamilyIndex, queueIndex, pQueue));
+        }
+    }
+}
+
+/// Get a device queue by family, index and creation flags
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. pQueueInfo points to a valid VkDeviceQueueInfo2 naming a created queue
+// 3. pQueue points to valid memory for writing the queue handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetDeviceQueue2(
+    device: VkDevice,
+    pQueueInfo: *const VkDeviceQueueInfo2,
+    pQueue: *mut VkQueue,
+) {
+    if device.is_null() || pQueueInfo.is_null() || pQueue.is_null() {
+        return;
+    }
+
+    // Route via owning ICD if known
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.get_device_queue2 {
+            icd_call!("vkGetDeviceQueue2", f(device, pQueueInfo, pQueue));
+            if let Some(queue) = pQueue.as_ref() {
+                // Register queue → ICD mapping
+                icd_loader::register_queue_icd(*queue, &icd);
+            }
+            return;
+        }
+    }
+    // Fallback
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(get_device_queue2) = icd.get_device_queue2 {
+            icd_call!("vkGetDeviceQueue2", get_device_queue2(device, pQueueInfo, pQueue));
         }
     }
 }
@@ -132,7 +175,7 @@ pub unsafe extern "C" fn vkGetDeviceQueue(
 // 2. If submitCount > 0, pSubmits points to an array of valid VkSubmitInfo structures
 // 3. fence is either VK_NULL_HANDLE or a valid VkFence
 // 4. All command buffers, semaphores, and other resources referenced are valid
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkQueueSubmit(
     queue: VkQueue,
     submitCount: u32,
@@ -142,46 +185,49 @@ pub unsafe extern "C" fn vkQueueSubmit(
     if queue.is_null() {
         return VkResult::ErrorDeviceLost;
     }
+    if let Some(result) = crate::testing::injected_failure(crate::testing::Site::Submit) {
+        return result;
+    }
 
     // Route via queue owner if known
     if let Some(icd) = icd_loader::icd_for_queue(queue) {
-        if let Some(f) = icd.queue_submit { return f(queue, submitCount, pSubmits, fence); }
+        if let Some(f) = icd.queue_submit { return icd_call!("vkQueueSubmit", f(queue, submitCount, pSubmits, fence)); }
     }
     // Fallback
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(f) = icd.queue_submit { return f(queue, submitCount, pSubmits, fence); }
+        if let Some(f) = icd.queue_submit { return icd_call!("vkQueueSubmit", f(queue, submitCount, pSubmits, fence)); }
     }
     VkResult::ErrorInitializationFailed
 }
 
 /// Wait for queue to become idle
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkQueueWaitIdle(queue: VkQueue) -> VkResult {
     if queue.is_null() {
         return VkResult::ErrorDeviceLost;
     }
 
     if let Some(icd) = icd_loader::icd_for_queue(queue) {
-        if let Some(f) = icd.queue_wait_idle { return f(queue); }
+        if let Some(f) = icd.queue_wait_idle { return icd_call!("vkQueueWaitIdle", f(queue)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(f) = icd.queue_wait_idle { return f(queue); }
+        if let Some(f) = icd.queue_wait_idle { return icd_call!("vkQueueWaitIdle", f(queue)); }
     }
     VkResult::ErrorInitializationFailed
 }
 
 /// Wait for device to become idle
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDeviceWaitIdle(device: VkDevice) -> VkResult {
     if device.is_null() {
         return VkResult::ErrorDeviceLost;
     }
 
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.device_wait_idle { return f(device); }
+        if let Some(f) = icd.device_wait_idle { return icd_call!("vkDeviceWaitIdle", f(device)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(f) = icd.device_wait_idle { return f(device); }
+        if let Some(f) = icd.device_wait_idle { return icd_call!("vkDeviceWaitIdle", f(device)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -26,6 +26,8 @@ pub enum IcdError {
     InvalidOperation(&'static str),
     /// No ICD loaded
     NoIcdLoaded,
+    /// Allocation refused by the device's memory limit
+    MemoryLimitExceeded(super::pool_allocator::MemoryLimitExceeded),
 }
 
 impl fmt::Display for IcdError {
@@ -41,6 +43,7 @@ impl fmt::Display for IcdError {
             IcdError::VulkanError(result) => write!(f, "Vulkan error: {:?}", result),
             IcdError::InvalidOperation(op) => write!(f, "Invalid operation: {}", op),
             IcdError::NoIcdLoaded => write!(f, "No ICD loaded"),
+            IcdError::MemoryLimitExceeded(e) => write!(f, "Memory limit exceeded: {}", e),
         }
     }
 }
@@ -10,7 +10,10 @@ use std::env;
 use libc::{c_void, c_char};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, Weak};
-use log::{info, warn, debug};
+use super::logging::redact;
+#[cfg(not(target_os = "android"))]
+use super::logging::redact_all;
+#[cfg(not(target_os = "android"))]
 use serde::{Deserialize, Serialize};
 use crate::sys::*;
 use crate::core::*;
@@ -18,6 +21,7 @@ use crate::ffi::*;
 use super::error::IcdError;
 
 /// Get platform-specific ICD search paths
+#[cfg(not(target_os = "android"))]
 fn get_icd_search_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
     
@@ -76,7 +80,7 @@ fn get_icd_search_paths() -> Vec<PathBuf> {
             Err(_) => canon.push(p), // keep original if canonicalize fails
         }
     }
-    log::info!("ICD search paths: {:#?}", canon);
+    kronos_log!(Info, "ICD search paths: {:#?}", redact_all(&canon));
     canon
 }
 
@@ -86,23 +90,39 @@ pub struct LoadedICD {
     pub library_path: PathBuf,
     pub handle: *mut c_void,
     pub api_version: u32,
+    /// Whether the driver is a portability driver such as MoltenVK, which
+    /// instances only enumerate with VK_KHR_portability_enumeration
+    pub is_portability_driver: bool,
     
     // Core function pointers
     pub vk_get_instance_proc_addr: PFN_vkGetInstanceProcAddr,
     
     // Instance functions
+    pub enumerate_instance_version: PFN_vkEnumerateInstanceVersion,
+    pub enumerate_instance_extension_properties: PFN_vkEnumerateInstanceExtensionProperties,
+    /// Only loaders expose layers; drivers leave this unset
+    pub enumerate_instance_layer_properties: PFN_vkEnumerateInstanceLayerProperties,
     pub create_instance: PFN_vkCreateInstance,
     pub destroy_instance: PFN_vkDestroyInstance,
     pub enumerate_physical_devices: PFN_vkEnumeratePhysicalDevices,
     pub get_physical_device_properties: PFN_vkGetPhysicalDeviceProperties,
+    pub get_physical_device_properties2: PFN_vkGetPhysicalDeviceProperties2,
+    pub get_physical_device_features: PFN_vkGetPhysicalDeviceFeatures,
     pub get_physical_device_queue_family_properties: PFN_vkGetPhysicalDeviceQueueFamilyProperties,
+    pub get_physical_device_queue_family_properties2: PFN_vkGetPhysicalDeviceQueueFamilyProperties2,
     pub get_physical_device_memory_properties: PFN_vkGetPhysicalDeviceMemoryProperties,
+    pub enumerate_device_extension_properties: PFN_vkEnumerateDeviceExtensionProperties,
+    pub enumerate_queue_family_performance_query_counters: PFN_vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR,
+    pub get_queue_family_performance_query_passes: PFN_vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR,
+    pub create_debug_utils_messenger: PFN_vkCreateDebugUtilsMessengerEXT,
+    pub destroy_debug_utils_messenger: PFN_vkDestroyDebugUtilsMessengerEXT,
     
     // Device functions
     pub create_device: PFN_vkCreateDevice,
     pub destroy_device: PFN_vkDestroyDevice,
     pub get_device_proc_addr: PFN_vkGetDeviceProcAddr,
     pub get_device_queue: PFN_vkGetDeviceQueue,
+    pub get_device_queue2: PFN_vkGetDeviceQueue2,
     
     // Queue functions
     pub queue_submit: PFN_vkQueueSubmit,
@@ -114,6 +134,9 @@ pub struct LoadedICD {
     pub free_memory: PFN_vkFreeMemory,
     pub map_memory: PFN_vkMapMemory,
     pub unmap_memory: PFN_vkUnmapMemory,
+    pub invalidate_mapped_memory_ranges: PFN_vkInvalidateMappedMemoryRanges,
+    pub get_memory_fd_properties: PFN_vkGetMemoryFdPropertiesKHR,
+    pub get_memory_fd: PFN_vkGetMemoryFdKHR,
     
     // Buffer functions
     pub create_buffer: PFN_vkCreateBuffer,
@@ -121,15 +144,35 @@ pub struct LoadedICD {
     pub get_buffer_memory_requirements: PFN_vkGetBufferMemoryRequirements,
     pub bind_buffer_memory: PFN_vkBindBufferMemory,
     
+    // Image functions
+    pub create_image: PFN_vkCreateImage,
+    pub destroy_image: PFN_vkDestroyImage,
+    pub get_image_memory_requirements: PFN_vkGetImageMemoryRequirements,
+    pub bind_image_memory: PFN_vkBindImageMemory,
+    pub create_image_view: PFN_vkCreateImageView,
+    pub destroy_image_view: PFN_vkDestroyImageView,
+    pub create_sampler: PFN_vkCreateSampler,
+    pub destroy_sampler: PFN_vkDestroySampler,
+    
+    // Query functions
+    pub create_query_pool: PFN_vkCreateQueryPool,
+    pub destroy_query_pool: PFN_vkDestroyQueryPool,
+    pub get_query_pool_results: PFN_vkGetQueryPoolResults,
+    pub acquire_profiling_lock: PFN_vkAcquireProfilingLockKHR,
+    pub release_profiling_lock: PFN_vkReleaseProfilingLockKHR,
+    
     // Descriptor functions
     pub create_descriptor_set_layout: PFN_vkCreateDescriptorSetLayout,
     pub destroy_descriptor_set_layout: PFN_vkDestroyDescriptorSetLayout,
     pub create_descriptor_pool: PFN_vkCreateDescriptorPool,
     pub destroy_descriptor_pool: PFN_vkDestroyDescriptorPool,
-    pub reset_descriptor_pool: Option<unsafe extern "C" fn(VkDevice, VkDescriptorPool, VkDescriptorPoolResetFlags) -> VkResult>,
+    pub reset_descriptor_pool: Option<unsafe extern "C" fn(VkDevice, VkDescriptorPool, VkDescriptorPoolResetFlags) -> i32>,
     pub allocate_descriptor_sets: PFN_vkAllocateDescriptorSets,
-    pub free_descriptor_sets: Option<unsafe extern "C" fn(VkDevice, VkDescriptorPool, u32, *const VkDescriptorSet) -> VkResult>,
+    pub free_descriptor_sets: Option<unsafe extern "C" fn(VkDevice, VkDescriptorPool, u32, *const VkDescriptorSet) -> i32>,
     pub update_descriptor_sets: PFN_vkUpdateDescriptorSets,
+    pub create_descriptor_update_template: PFN_vkCreateDescriptorUpdateTemplate,
+    pub destroy_descriptor_update_template: PFN_vkDestroyDescriptorUpdateTemplate,
+    pub update_descriptor_set_with_template: PFN_vkUpdateDescriptorSetWithTemplate,
     
     // Pipeline functions
     pub create_pipeline_layout: PFN_vkCreatePipelineLayout,
@@ -152,8 +195,17 @@ pub struct LoadedICD {
     pub cmd_bind_descriptor_sets: PFN_vkCmdBindDescriptorSets,
     pub cmd_dispatch: PFN_vkCmdDispatch,
     pub cmd_dispatch_indirect: Option<unsafe extern "C" fn(VkCommandBuffer, VkBuffer, VkDeviceSize)>,
+    pub cmd_dispatch_base: PFN_vkCmdDispatchBase,
+    pub cmd_begin_conditional_rendering: PFN_vkCmdBeginConditionalRenderingEXT,
+    pub cmd_end_conditional_rendering: PFN_vkCmdEndConditionalRenderingEXT,
     pub cmd_pipeline_barrier: PFN_vkCmdPipelineBarrier,
     pub cmd_copy_buffer: Option<unsafe extern "C" fn(VkCommandBuffer, VkBuffer, VkBuffer, u32, *const VkBufferCopy)>,
+    pub cmd_copy_buffer_to_image: PFN_vkCmdCopyBufferToImage,
+    pub cmd_copy_image_to_buffer: PFN_vkCmdCopyImageToBuffer,
+    pub cmd_reset_query_pool: PFN_vkCmdResetQueryPool,
+    pub cmd_write_timestamp: PFN_vkCmdWriteTimestamp,
+    pub cmd_begin_query: PFN_vkCmdBeginQuery,
+    pub cmd_end_query: PFN_vkCmdEndQuery,
     pub cmd_push_constants: Option<unsafe extern "C" fn(VkCommandBuffer, VkPipelineLayout, VkShaderStageFlags, u32, u32, *const c_void)>,
     
     // Sync functions
@@ -164,6 +216,7 @@ pub struct LoadedICD {
     pub wait_for_fences: PFN_vkWaitForFences,
     pub create_semaphore: PFN_vkCreateSemaphore,
     pub destroy_semaphore: PFN_vkDestroySemaphore,
+    pub get_semaphore_fd: PFN_vkGetSemaphoreFdKHR,
     pub create_event: PFN_vkCreateEvent,
     pub destroy_event: PFN_vkDestroyEvent,
     pub get_event_status: PFN_vkGetEventStatus,
@@ -174,7 +227,8 @@ pub struct LoadedICD {
     pub cmd_wait_events: PFN_vkCmdWaitEvents,
     
     // Timeline semaphore functions
-    pub wait_semaphores: Option<unsafe extern "C" fn(VkDevice, *const VkSemaphoreWaitInfo, u64) -> VkResult>,
+    pub wait_semaphores: Option<unsafe extern "C" fn(VkDevice, *const VkSemaphoreWaitInfo, u64) -> i32>,
+    pub get_semaphore_counter_value: PFN_vkGetSemaphoreCounterValue,
 }
 
 // SAFETY: LoadedICD is safe to send between threads because:
@@ -185,6 +239,125 @@ pub struct LoadedICD {
 unsafe impl Send for LoadedICD {}
 unsafe impl Sync for LoadedICD {}
 
+impl LoadedICD {
+    /// An ICD with only its entry point resolved; every other function is unset
+    fn unloaded(library_path: PathBuf, handle: *mut c_void, vk_get_instance_proc_addr: PFN_vkGetInstanceProcAddr) -> Self {
+        Self {
+            is_portability_driver: is_portability_library(&library_path),
+            library_path,
+            handle,
+            api_version: VK_API_VERSION_1_0,
+            vk_get_instance_proc_addr,
+            enumerate_instance_version: None,
+            enumerate_instance_extension_properties: None,
+            enumerate_instance_layer_properties: None,
+            create_instance: None,
+            destroy_instance: None,
+            enumerate_physical_devices: None,
+            get_physical_device_properties: None,
+            get_physical_device_properties2: None,
+            get_physical_device_features: None,
+            get_physical_device_queue_family_properties: None,
+            get_physical_device_queue_family_properties2: None,
+            get_physical_device_memory_properties: None,
+            enumerate_device_extension_properties: None,
+            enumerate_queue_family_performance_query_counters: None,
+            get_queue_family_performance_query_passes: None,
+            create_debug_utils_messenger: None,
+            destroy_debug_utils_messenger: None,
+            create_device: None,
+            destroy_device: None,
+            get_device_proc_addr: None,
+            get_device_queue: None,
+            get_device_queue2: None,
+            queue_submit: None,
+            queue_wait_idle: None,
+            device_wait_idle: None,
+            allocate_memory: None,
+            free_memory: None,
+            map_memory: None,
+            unmap_memory: None,
+            invalidate_mapped_memory_ranges: None,
+            get_memory_fd_properties: None,
+            get_memory_fd: None,
+            create_buffer: None,
+            destroy_buffer: None,
+            get_buffer_memory_requirements: None,
+            bind_buffer_memory: None,
+            create_image: None,
+            destroy_image: None,
+            get_image_memory_requirements: None,
+            bind_image_memory: None,
+            create_image_view: None,
+            destroy_image_view: None,
+            create_sampler: None,
+            destroy_sampler: None,
+            create_query_pool: None,
+            destroy_query_pool: None,
+            get_query_pool_results: None,
+            acquire_profiling_lock: None,
+            release_profiling_lock: None,
+            create_descriptor_set_layout: None,
+            destroy_descriptor_set_layout: None,
+            create_descriptor_pool: None,
+            destroy_descriptor_pool: None,
+            reset_descriptor_pool: None,
+            allocate_descriptor_sets: None,
+            free_descriptor_sets: None,
+            update_descriptor_sets: None,
+            create_descriptor_update_template: None,
+            destroy_descriptor_update_template: None,
+            update_descriptor_set_with_template: None,
+            create_pipeline_layout: None,
+            destroy_pipeline_layout: None,
+            create_compute_pipelines: None,
+            destroy_pipeline: None,
+            create_shader_module: None,
+            destroy_shader_module: None,
+            create_command_pool: None,
+            destroy_command_pool: None,
+            allocate_command_buffers: None,
+            free_command_buffers: None,
+            begin_command_buffer: None,
+            end_command_buffer: None,
+            cmd_bind_pipeline: None,
+            cmd_bind_descriptor_sets: None,
+            cmd_dispatch: None,
+            cmd_dispatch_indirect: None,
+            cmd_dispatch_base: None,
+            cmd_begin_conditional_rendering: None,
+            cmd_end_conditional_rendering: None,
+            cmd_pipeline_barrier: None,
+            cmd_copy_buffer: None,
+            cmd_copy_buffer_to_image: None,
+            cmd_copy_image_to_buffer: None,
+            cmd_reset_query_pool: None,
+            cmd_write_timestamp: None,
+            cmd_begin_query: None,
+            cmd_end_query: None,
+            cmd_push_constants: None,
+            create_fence: None,
+            destroy_fence: None,
+            reset_fences: None,
+            get_fence_status: None,
+            wait_for_fences: None,
+            create_semaphore: None,
+            destroy_semaphore: None,
+            get_semaphore_fd: None,
+            create_event: None,
+            destroy_event: None,
+            get_event_status: None,
+            set_event: None,
+            reset_event: None,
+            cmd_set_event: None,
+            cmd_reset_event: None,
+            cmd_wait_events: None,
+            wait_semaphores: None,
+            get_semaphore_counter_value: None,
+        }
+    }
+}
+
 // NOTE: No Drop implementation - we intentionally leak the library handles
 // because function pointers from the library may still be in use elsewhere.
 // This is standard practice for dynamically loaded Vulkan ICDs.
@@ -196,9 +369,11 @@ pub struct IcdInfo {
     pub manifest_path: Option<PathBuf>,
     pub api_version: u32,
     pub is_software: bool,
+    pub is_portability_driver: bool,
 }
 
 /// ICD manifest root structure
+#[cfg(not(target_os = "android"))]
 #[derive(Debug, Deserialize, Serialize)]
 struct ICDManifestRoot {
     file_format_version: String,
@@ -207,10 +382,13 @@ struct ICDManifestRoot {
 }
 
 /// ICD manifest structure
+#[cfg(not(target_os = "android"))]
 #[derive(Debug, Deserialize, Serialize)]
-struct ICDManifest {
-    library_path: String,
+pub(crate) struct ICDManifest {
+    pub(crate) library_path: String,
     api_version: Option<String>,
+    #[serde(default)]
+    is_portability_driver: bool,
 }
 
 lazy_static::lazy_static! {
@@ -253,6 +431,32 @@ pub fn meta_instance_for(meta_id: u64) -> Option<Vec<(Arc<LoadedICD>, VkInstance
     META_INSTANCES.lock().ok()?.get(&meta_id).cloned()
 }
 
+/// The ICD's own instance behind `instance`, which is a meta instance in
+/// aggregated mode
+pub fn icd_instance(instance: VkInstance, icd: &LoadedICD) -> VkInstance {
+    meta_instance_for(instance.as_raw())
+        .and_then(|inners| inners.into_iter().find(|(inner, _)| inner.library_path == icd.library_path))
+        .map_or(instance, |(_, inner)| inner)
+}
+
+/// Resolve `name` from the ICD that owns `device`: through its
+/// vkGetDeviceProcAddr for device-level entry points, and through its
+/// vkGetInstanceProcAddr on its own instance for the rest
+///
+/// # Safety
+///
+/// `device` must be a valid device created from `instance`.
+pub unsafe fn resolve_entry_point(instance: VkInstance, device: VkDevice, name: &CStr) -> PFN_vkVoidFunction {
+    let icd = icd_for_device(device)?;
+    if let Some(get_device_proc_addr) = icd.get_device_proc_addr {
+        if let Some(entry) = icd_call!("vkGetDeviceProcAddr", get_device_proc_addr(device, name.as_ptr())) {
+            return Some(entry);
+        }
+    }
+    let get_instance_proc_addr = icd.vk_get_instance_proc_addr?;
+    icd_call!("vkGetInstanceProcAddr", get_instance_proc_addr(icd_instance(instance, &icd), name.as_ptr()))
+}
+
 pub fn get_all_icds() -> Vec<Arc<LoadedICD>> {
     if let Ok(guard) = ALL_ICDS.lock() {
         guard.clone()
@@ -266,11 +470,23 @@ pub fn discover_and_load_all_icds() -> Vec<Arc<LoadedICD>> {
     // First try to get already loaded ICDs
     let existing = get_all_icds();
     if !existing.is_empty() {
-        info!("Using {} already loaded ICDs for aggregated mode", existing.len());
+        kronos_log!(Info, "Using {} already loaded ICDs for aggregated mode", existing.len());
         return existing;
     }
     
     // If none loaded, do the discovery (fallback for direct calls)
+    load_discovered_icds()
+}
+
+/// Android has no manifests; the system loader is the only ICD
+#[cfg(target_os = "android")]
+fn load_discovered_icds() -> Vec<Arc<LoadedICD>> {
+    get_icd().into_iter().collect()
+}
+
+/// Load one ICD per discovered manifest
+#[cfg(not(target_os = "android"))]
+fn load_discovered_icds() -> Vec<Arc<LoadedICD>> {
     let mut out = Vec::new();
     let icd_files = discover_icds();
     if icd_files.is_empty() { return out; }
@@ -279,16 +495,11 @@ pub fn discover_and_load_all_icds() -> Vec<Arc<LoadedICD>> {
 
     for icd_file in &icd_files {
         if let Some(manifest) = parse_icd_manifest(icd_file) {
-            let mut candidates: Vec<PathBuf> = Vec::new();
-            if Path::new(&manifest.library_path).is_absolute() {
-                candidates.push(PathBuf::from(&manifest.library_path));
-            } else {
-                candidates.push(PathBuf::from(&manifest.library_path));
-                if let Some(parent) = icd_file.parent() { candidates.push(parent.join(&manifest.library_path)); }
-            }
+            let candidates = library_candidates(&manifest.library_path, icd_file);
             for cand in &candidates {
                 let can = fs::canonicalize(cand).unwrap_or(cand.clone());
-                if let Ok(icd) = load_icd(&can) {
+                if let Ok(mut icd) = load_icd(&can) {
+                    icd.is_portability_driver |= manifest.is_portability_driver;
                     let arc = Arc::new(icd);
                     out.push(arc);
                     break;
@@ -298,21 +509,16 @@ pub fn discover_and_load_all_icds() -> Vec<Arc<LoadedICD>> {
     }
 
     if prefer_hardware {
-        let any_hw = out.iter().any(|icd| {
-            let s = icd.library_path.to_string_lossy();
-            !(s.contains("lvp") || s.contains("swrast") || s.contains("llvmpipe"))
-        });
+        let any_hw = out.iter().any(|icd| !is_software_library(&icd.library_path));
         if any_hw {
-            out.retain(|icd| {
-                let s = icd.library_path.to_string_lossy();
-                !(s.contains("lvp") || s.contains("swrast") || s.contains("llvmpipe"))
-            });
+            out.retain(|icd| !is_software_library(&icd.library_path));
         }
     }
     out
 }
 
 /// Find and load Vulkan ICDs
+#[cfg(not(target_os = "android"))]
 pub fn discover_icds() -> Vec<PathBuf> {
     let mut icd_files = Vec::new();
     let mut env_icds = Vec::new();
@@ -327,16 +533,18 @@ pub fn discover_icds() -> Vec<PathBuf> {
                 env_icds.push(can.clone());
                 icd_files.push(can);
             } else {
-                warn!("VK_ICD_FILENAMES contains non-existent path: {}", path);
+                kronos_log!(Warn, "VK_ICD_FILENAMES contains non-existent path: {}", redact(Path::new(path)));
             }
         }
         if !env_icds.is_empty() {
-            info!("Found {} ICD files from VK_ICD_FILENAMES (will be prioritized)", env_icds.len());
+            kronos_log!(Info, "Found {} ICD files from VK_ICD_FILENAMES (will be prioritized)", env_icds.len());
         }
     }
     
-    // Always search platform-specific paths for all available ICDs
-    let search_paths = get_icd_search_paths();
+    // Always search platform-specific paths for all available ICDs, then
+    // directories registered with add_library_search_dir
+    let mut search_paths = get_icd_search_paths();
+    search_paths.extend(library_search_dirs());
     for search_path in &search_paths {
         if let Ok(entries) = fs::read_dir(search_path) {
             let mut path_count = 0;
@@ -346,47 +554,49 @@ pub fn discover_icds() -> Vec<PathBuf> {
                     // Skip if already added from environment variable
                     if !env_icds.contains(&path) {
                         let can = fs::canonicalize(&path).unwrap_or(path);
-                        log::debug!("Discovered ICD candidate: {}", can.display());
+                        kronos_log!(Debug, "Discovered ICD candidate: {}", redact(&can));
                         icd_files.push(can);
                         path_count += 1;
                     }
                 }
             }
             if path_count > 0 {
-                info!("Found {} additional ICD manifest files in {}", path_count, search_path.display());
+                kronos_log!(Info, "Found {} additional ICD manifest files in {}", path_count, redact(search_path));
             }
         }
     }
     
     if icd_files.is_empty() {
-        warn!("No ICD manifest files found in any search paths: {:#?}", search_paths);
+        kronos_log!(Warn, "No ICD manifest files found in any search paths: {:#?}", redact_all(&search_paths));
     }
     
     icd_files
 }
 
 /// Parse ICD manifest JSON
-fn parse_icd_manifest(path: &Path) -> Option<ICDManifest> {
+#[cfg(not(target_os = "android"))]
+pub(crate) fn parse_icd_manifest(path: &Path) -> Option<ICDManifest> {
     let content = fs::read_to_string(path).ok()?;
     
     // Parse JSON using serde_json
     match serde_json::from_str::<ICDManifestRoot>(&content) {
         Ok(manifest_root) => {
             if manifest_root.icd.library_path.is_empty() {
-                warn!("ICD manifest has empty library_path: {}", path.display());
+                kronos_log!(Warn, "ICD manifest has empty library_path: {}", redact(path));
                 return None;
             }
-            debug!("Successfully parsed ICD manifest: {} -> {}", path.display(), manifest_root.icd.library_path);
+            kronos_log!(Debug, "Successfully parsed ICD manifest: {} -> {}", redact(path), manifest_root.icd.library_path);
             Some(manifest_root.icd)
         }
         Err(e) => {
-            warn!("Failed to parse ICD manifest {}: {}", path.display(), e);
+            kronos_log!(Warn, "Failed to parse ICD manifest {}: {}", redact(path), e);
             None
         }
     }
 }
 
 /// Parse API version from manifest string like "1.3.268" into VK_MAKE_VERSION
+#[cfg(not(target_os = "android"))]
 fn parse_api_version(version: &str) -> Option<u32> {
     let mut parts = version.split('.');
     let major = parts.next()?.parse::<u32>().ok()?;
@@ -399,6 +609,7 @@ fn parse_api_version(version: &str) -> Option<u32> {
 mod tests {
     use super::*;
 
+    #[cfg(not(target_os = "android"))]
     #[test]
     fn test_parse_api_version() {
         assert_eq!(parse_api_version("1.2.176"), Some(VK_MAKE_VERSION(1,2,176)));
@@ -408,37 +619,93 @@ mod tests {
         assert_eq!(parse_api_version("a.b.c"), None);
     }
 
+    #[cfg(not(target_os = "android"))]
+    #[test]
+    fn test_portability_driver_manifest() {
+        let dir = env::temp_dir().join(format!("kronos-portability-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("MoltenVK_icd.json");
+        fs::write(
+            &manifest,
+            r#"{"file_format_version": "1.0.1", "ICD": {"library_path": "libvk_portable.dylib", "is_portability_driver": true}}"#,
+        )
+        .unwrap();
+        assert!(parse_icd_manifest(&manifest).unwrap().is_portability_driver);
+        fs::write(&manifest, r#"{"file_format_version": "1.0.0", "ICD": {"library_path": "libvk_native.so"}}"#).unwrap();
+        assert!(!parse_icd_manifest(&manifest).unwrap().is_portability_driver);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(is_portability_library(Path::new("/usr/local/lib/libMoltenVK.dylib")));
+        assert!(!is_portability_library(Path::new("/usr/lib/libvulkan_radeon.so")));
+    }
+
     #[test]
     fn test_aggregated_mode_default_off() {
         // By default, aggregated mode should be disabled unless env var is set
         std::env::remove_var("KRONOS_AGGREGATE_ICD");
         assert!(!aggregated_mode_enabled());
     }
+
+    #[cfg(not(target_os = "android"))]
+    #[test]
+    fn test_library_search_dirs() {
+        let dir = env::temp_dir().join(format!("kronos-search-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let library = dir.join("libvk_bundled.so");
+        fs::write(&library, b"").unwrap();
+        let dir = fs::canonicalize(&dir).unwrap();
+        let manifest = Path::new("/etc/vulkan/icd.d/bundled.json");
+
+        add_library_search_dir(LibrarySearchDir::Path(dir.clone()));
+        add_library_search_dir(LibrarySearchDir::Path(dir.join("missing")));
+        assert_eq!(library_search_dirs(), vec![dir.clone()]);
+
+        // Relative names are tried in the search directory after the manifest's
+        let candidates = library_candidates("libvk_bundled.so", manifest);
+        assert_eq!(candidates[1], Path::new("/etc/vulkan/icd.d/libvk_bundled.so"));
+        assert_eq!(candidates[2], dir.join("libvk_bundled.so"));
+        // Absolute names fall back to the bare file name
+        let candidates = library_candidates("/usr/lib/libvk_bundled.so", manifest);
+        assert_eq!(candidates, vec![PathBuf::from("/usr/lib/libvk_bundled.so"), dir.join("libvk_bundled.so")]);
+
+        assert_eq!(resolve_library_path(Path::new("libvk_bundled.so")), dir.join("libvk_bundled.so"));
+        assert!(is_trusted_library(&fs::canonicalize(&library).unwrap()));
+
+        clear_library_search_dirs();
+        assert!(library_search_dirs().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_executable_relative_search_dir() {
+        let exe_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        let dir = LibrarySearchDir::ExecutableRelative(PathBuf::from("drivers"));
+        assert_eq!(dir.resolve(), Some(exe_dir.join("drivers")));
+    }
+}
+
+/// Return all loadable ICDs with metadata (does not mutate global state)
+///
+/// On Android that is the system loader, once it is loaded.
+#[cfg(target_os = "android")]
+pub fn available_icds() -> Vec<IcdInfo> {
+    selected_icd_info().into_iter().collect()
 }
 
 /// Return all loadable ICDs with metadata (does not mutate global state)
+#[cfg(not(target_os = "android"))]
 pub fn available_icds() -> Vec<IcdInfo> {
     let mut out = Vec::new();
     let icd_files = discover_icds();
 
     for icd_file in &icd_files {
         if let Some(manifest) = parse_icd_manifest(icd_file) {
-            // Build candidate library paths; prefer absolute, else try as-provided and manifest-relative
-            let mut candidates: Vec<PathBuf> = Vec::new();
-            if Path::new(&manifest.library_path).is_absolute() {
-                candidates.push(PathBuf::from(&manifest.library_path));
-            } else {
-                candidates.push(PathBuf::from(&manifest.library_path));
-                if let Some(parent) = icd_file.parent() {
-                    candidates.push(parent.join(&manifest.library_path));
-                }
-            }
+            let candidates = library_candidates(&manifest.library_path, icd_file);
 
             // Attempt to load first working candidate for this manifest
             for cand in &candidates {
                 if let Ok(icd) = load_icd(cand) {
-                    let path_str = icd.library_path.to_string_lossy();
-                    let is_software = path_str.contains("lvp") || path_str.contains("swrast") || path_str.contains("llvmpipe");
+                    let is_software = is_software_library(&icd.library_path);
                     let api_version = manifest
                         .api_version
                         .as_deref()
@@ -450,6 +717,7 @@ pub fn available_icds() -> Vec<IcdInfo> {
                         manifest_path: Some(icd_file.clone()),
                         api_version,
                         is_software,
+                        is_portability_driver: icd.is_portability_driver || manifest.is_portability_driver,
                     });
                     break; // one entry per manifest
                 }
@@ -460,8 +728,127 @@ pub fn available_icds() -> Vec<IcdInfo> {
     out
 }
 
-// Preferred ICD selection (process-wide for now)
+/// Whether a driver library is a CPU renderer, judged by its file name
+pub(crate) fn is_software_library(path: &Path) -> bool {
+    let s = path.to_string_lossy();
+    s.contains("lvp") || s.contains("swrast") || s.contains("llvmpipe") || s.contains("swiftshader")
+}
+
+/// Whether a driver library is a portability driver, judged by its file
+/// name where its manifest does not say
+pub(crate) fn is_portability_library(path: &Path) -> bool {
+    path.to_string_lossy().contains("MoltenVK")
+}
+
+/// An extra directory searched for ICD libraries and manifests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibrarySearchDir {
+    /// A directory used as given
+    Path(PathBuf),
+    /// A directory relative to the running executable's, for drivers
+    /// bundled with an application
+    ExecutableRelative(PathBuf),
+}
+
+impl LibrarySearchDir {
+    /// The directory on this system, if it can be determined
+    pub fn resolve(&self) -> Option<PathBuf> {
+        match self {
+            Self::Path(dir) => Some(dir.clone()),
+            Self::ExecutableRelative(dir) => {
+                let exe = env::current_exe().ok()?;
+                Some(exe.parent()?.join(dir))
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LIBRARY_SEARCH_DIRS: Mutex<Vec<LibrarySearchDir>> = Mutex::new(Vec::new());
+}
+
+/// Search `dir` for ICD libraries and manifests
+///
+/// Relative `library_path`s in manifests are looked up in these directories,
+/// in the order added, after the path as given and the manifest's own
+/// directory; `*.json` manifests in them are discovered alongside the
+/// platform paths. Libraries inside these directories pass the library
+/// trust policy, as the application chose them. Takes effect for ICDs
+/// loaded afterwards.
+pub fn add_library_search_dir(dir: LibrarySearchDir) {
+    kronos_log!(Info, "Adding ICD library search directory: {:?}", dir);
+    if let Ok(mut dirs) = LIBRARY_SEARCH_DIRS.lock() {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+}
+
+/// Forget all directories added with [`add_library_search_dir`]
+pub fn clear_library_search_dirs() {
+    if let Ok(mut dirs) = LIBRARY_SEARCH_DIRS.lock() {
+        dirs.clear();
+    }
+}
+
+/// Existing directories added with [`add_library_search_dir`], canonicalized
+pub fn library_search_dirs() -> Vec<PathBuf> {
+    let dirs = match LIBRARY_SEARCH_DIRS.lock() {
+        Ok(dirs) => dirs.clone(),
+        Err(_) => return Vec::new(),
+    };
+    dirs.iter()
+        .filter_map(LibrarySearchDir::resolve)
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .collect()
+}
+
+/// Library paths to try for a manifest's `library_path`, in order
+///
+/// An absolute path is tried as given. A relative one is tried as given,
+/// next to the manifest, then in each search directory. Search directories
+/// are also tried with the bare file name, so a bundled driver is found even
+/// when its manifest names a system location.
+#[cfg(not(target_os = "android"))]
+pub(crate) fn library_candidates(library_path: &str, manifest: &Path) -> Vec<PathBuf> {
+    let path = Path::new(library_path);
+    let mut candidates = vec![path.to_path_buf()];
+    if path.is_relative() {
+        if let Some(parent) = manifest.parent() {
+            candidates.push(parent.join(path));
+        }
+    }
+    for dir in library_search_dirs() {
+        if path.is_relative() {
+            candidates.push(dir.join(path));
+        }
+        if let Some(file_name) = path.file_name() {
+            let bare = dir.join(file_name);
+            if !candidates.contains(&bare) {
+                candidates.push(bare);
+            }
+        }
+    }
+    candidates
+}
+
+/// `library_path` itself if it exists, else the first search directory
+/// containing it when it is relative
+fn resolve_library_path(library_path: &Path) -> PathBuf {
+    if library_path.exists() || library_path.is_absolute() {
+        return library_path.to_path_buf();
+    }
+    library_search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(library_path))
+        .find(|candidate| candidate.exists())
+        .unwrap_or_else(|| library_path.to_path_buf())
+}
+
+// Preferred ICD selection (process-wide for now); Android has only the
+// system loader, so preferences are recorded there but never read
 #[derive(Debug, Clone)]
+#[cfg_attr(target_os = "android", allow(dead_code))]
 enum IcdPreference {
     Path(PathBuf),
     Index(usize),
@@ -473,14 +860,14 @@ lazy_static::lazy_static! {
 
 pub fn set_preferred_icd_path<P: Into<PathBuf>>(path: P) {
     let path_buf = path.into();
-    log::info!("Setting preferred ICD path: {:?}", path_buf);
+    kronos_log!(Info, "Setting preferred ICD path: {:?}", redact(&path_buf));
     if let Ok(mut pref) = PREFERRED_ICD.lock() {
         *pref = Some(IcdPreference::Path(path_buf));
     }
 }
 
 pub fn set_preferred_icd_index(index: usize) {
-    log::info!("Setting preferred ICD index: {}", index);
+    kronos_log!(Info, "Setting preferred ICD index: {}", index);
     if let Ok(mut pref) = PREFERRED_ICD.lock() {
         *pref = Some(IcdPreference::Index(index));
     }
@@ -496,13 +883,13 @@ pub fn clear_preferred_icd() {
 pub fn selected_icd_info() -> Option<IcdInfo> {
     let icd = get_icd()?;
     let path = icd.library_path.clone();
-    let path_str = path.to_string_lossy();
-    let is_software = path_str.contains("lvp") || path_str.contains("swrast") || path_str.contains("llvmpipe");
+    let is_software = is_software_library(&path);
     Some(IcdInfo {
         library_path: path,
         manifest_path: None,
         api_version: icd.api_version,
         is_software,
+        is_portability_driver: icd.is_portability_driver,
     })
 }
 
@@ -518,22 +905,22 @@ pub fn register_physical_device_icd(phys: VkPhysicalDevice, icd: &Arc<LoadedICD>
 }
 pub fn register_device_icd(device: VkDevice, icd: &Arc<LoadedICD>) {
     let device_raw = device.as_raw();
-    log::debug!("Registering device {} with ICD", device_raw);
+    kronos_log!(Debug, "Registering device {} with ICD", device_raw);
     
     // Store the Arc to keep the ICD alive
     if let Ok(mut device_icds) = DEVICE_ICDS.lock() {
         device_icds.insert(device_raw, icd.clone());
-        log::debug!("Stored device ICD Arc for device {}", device_raw);
+        kronos_log!(Debug, "Stored device ICD Arc for device {}", device_raw);
     }
     
     // Register the weak reference for lookups
     match REG_DEVICES.lock() {
         Ok(mut m) => {
             m.insert(device_raw, Arc::downgrade(icd));
-            log::debug!("Device {} registered successfully", device_raw);
+            kronos_log!(Debug, "Device {} registered successfully", device_raw);
         }
         Err(e) => {
-            log::error!("Failed to lock REG_DEVICES: {:?}", e);
+            kronos_log!(Error, "Failed to lock REG_DEVICES: {:?}", e);
         }
     }
 }
@@ -568,26 +955,26 @@ pub fn icd_for_physical_device(phys: VkPhysicalDevice) -> Option<Arc<LoadedICD>>
 }
 pub fn icd_for_device(device: VkDevice) -> Option<Arc<LoadedICD>> {
     let device_raw = device.as_raw();
-    log::trace!("Looking up ICD for device {:?} (raw: {})", device, device_raw);
+    kronos_log!(Trace, "Looking up ICD for device {:?} (raw: {})", device, device_raw);
     
     if let Ok(guard) = REG_DEVICES.lock() {
-        log::trace!("REG_DEVICES has {} entries", guard.len());
+        kronos_log!(Trace, "REG_DEVICES has {} entries", guard.len());
         if let Some(weak_icd) = guard.get(&device_raw) {
-            log::trace!("Found weak reference for device {}", device_raw);
+            kronos_log!(Trace, "Found weak reference for device {}", device_raw);
             if let Some(arc_icd) = upgrade_icd(weak_icd) {
-                log::trace!("Successfully upgraded weak reference to Arc");
+                kronos_log!(Trace, "Successfully upgraded weak reference to Arc");
                 return Some(arc_icd);
             } else {
-                log::warn!("Weak reference for device {} could not be upgraded (ICD dropped?)", device_raw);
+                kronos_log!(Warn, "Weak reference for device {} could not be upgraded (ICD dropped?)", device_raw);
             }
         } else {
-            log::trace!("Device {} not found in registry", device_raw);
+            kronos_log!(Trace, "Device {} not found in registry", device_raw);
         }
     } else {
-        log::error!("Failed to lock REG_DEVICES");
+        kronos_log!(Error, "Failed to lock REG_DEVICES");
     }
     
-    log::trace!("Device not found in registry, using fallback");
+    kronos_log!(Trace, "Device not found in registry, using fallback");
     get_icd()
 }
 pub fn icd_for_queue(queue: VkQueue) -> Option<Arc<LoadedICD>> {
@@ -608,6 +995,9 @@ fn is_trusted_library(path: &Path) -> bool {
     if env::var("KRONOS_ALLOW_UNTRUSTED_LIBS").map(|v| v == "1").unwrap_or(false) {
         return true;
     }
+    if library_search_dirs().iter().any(|dir| path.starts_with(dir)) {
+        return true;
+    }
     #[cfg(target_os = "linux")]
     {
         const TRUSTED_PREFIXES: &[&str] = &[
@@ -650,6 +1040,7 @@ pub fn load_icd(library_path: &Path) -> Result<LoadedICD, IcdError> {
     // 3. The loaded library handle is kept alive for the lifetime of LoadedICD
     unsafe {
         // Resolve and validate the library path
+        let library_path = &resolve_library_path(library_path);
         let canon = fs::canonicalize(library_path).unwrap_or_else(|_| library_path.to_path_buf());
         let meta = fs::metadata(&canon)
             .map_err(|_| IcdError::LibraryLoadFailed(format!("{} (metadata not found)", canon.display())))?;
@@ -701,86 +1092,12 @@ pub fn load_icd(library_path: &Path) -> Result<LoadedICD, IcdError> {
         };
         let vk_get_instance_proc_addr: PFN_vkGetInstanceProcAddr = get_proc;
         
-        // Get global functions
-        let mut icd = LoadedICD {
-            library_path: canon,
-            // Keep library alive for process lifetime. On Windows, store as opaque pointer.
-            handle: {
-                #[cfg(windows)]
-                {
-                    let boxed = Box::new(lib);
-                    Box::into_raw(boxed) as *mut c_void
-                }
-                #[cfg(not(windows))]
-                { handle as *mut c_void }
-            },
-            api_version: VK_API_VERSION_1_0,
-            vk_get_instance_proc_addr,
-            create_instance: None,
-            destroy_instance: None,
-            enumerate_physical_devices: None,
-            get_physical_device_properties: None,
-            get_physical_device_queue_family_properties: None,
-            get_physical_device_memory_properties: None,
-            create_device: None,
-            destroy_device: None,
-            get_device_proc_addr: None,
-            get_device_queue: None,
-            queue_submit: None,
-            queue_wait_idle: None,
-            device_wait_idle: None,
-            allocate_memory: None,
-            free_memory: None,
-            map_memory: None,
-            unmap_memory: None,
-            create_buffer: None,
-            destroy_buffer: None,
-            get_buffer_memory_requirements: None,
-            bind_buffer_memory: None,
-            create_descriptor_set_layout: None,
-            destroy_descriptor_set_layout: None,
-            create_descriptor_pool: None,
-            destroy_descriptor_pool: None,
-            reset_descriptor_pool: None,
-            allocate_descriptor_sets: None,
-            free_descriptor_sets: None,
-            update_descriptor_sets: None,
-            create_pipeline_layout: None,
-            destroy_pipeline_layout: None,
-            create_compute_pipelines: None,
-            destroy_pipeline: None,
-            create_shader_module: None,
-            destroy_shader_module: None,
-            create_command_pool: None,
-            destroy_command_pool: None,
-            allocate_command_buffers: None,
-            free_command_buffers: None,
-            begin_command_buffer: None,
-            end_command_buffer: None,
-            cmd_bind_pipeline: None,
-            cmd_bind_descriptor_sets: None,
-            cmd_dispatch: None,
-            cmd_dispatch_indirect: None,
-            cmd_pipeline_barrier: None,
-            cmd_copy_buffer: None,
-            cmd_push_constants: None,
-            create_fence: None,
-            destroy_fence: None,
-            reset_fences: None,
-            get_fence_status: None,
-            wait_for_fences: None,
-            create_semaphore: None,
-            destroy_semaphore: None,
-            create_event: None,
-            destroy_event: None,
-            get_event_status: None,
-            set_event: None,
-            reset_event: None,
-            cmd_set_event: None,
-            cmd_reset_event: None,
-            cmd_wait_events: None,
-            wait_semaphores: None,
-        };
+        // Keep library alive for process lifetime. On Windows, store as opaque pointer.
+        #[cfg(windows)]
+        let handle = Box::into_raw(Box::new(lib)) as *mut c_void;
+        #[cfg(not(windows))]
+        let handle = handle as *mut c_void;
+        let mut icd = LoadedICD::unloaded(canon, handle, vk_get_instance_proc_addr);
         
         // Load global functions and propagate failure instead of silently ignoring it
         load_global_functions_inner(&mut icd)?;
@@ -788,6 +1105,29 @@ pub fn load_icd(library_path: &Path) -> Result<LoadedICD, IcdError> {
     }
 }
 
+/// Build an ICD from an in-process `vk_icdGetInstanceProcAddr`
+///
+/// Used for drivers linked into the process (such as the mock ICD);
+/// `name` stands in for the library path in logs and `IcdInfo`.
+pub fn load_icd_from_entry_point(name: PathBuf, get_instance_proc_addr: PFN_vkGetInstanceProcAddr) -> Result<LoadedICD, IcdError> {
+    if get_instance_proc_addr.is_none() {
+        return Err(IcdError::MissingFunction("vk_icdGetInstanceProcAddr"));
+    }
+    let mut icd = LoadedICD::unloaded(name, std::ptr::null_mut(), get_instance_proc_addr);
+    // SAFETY: the entry point is provided by the caller and resolves functions
+    // with the signatures the loader expects
+    unsafe { load_global_functions_inner(&mut icd)?; }
+    Ok(icd)
+}
+
+/// Make `icd` the only loaded ICD, bypassing manifest discovery
+pub fn install_icd(icd: LoadedICD) -> Result<(), IcdError> {
+    let icd = Arc::new(icd);
+    *ALL_ICDS.lock()? = vec![icd.clone()];
+    *ICD_LOADER.lock()? = Some(icd);
+    Ok(())
+}
+
 /// Load global function pointers
 ///
 /// # Safety
@@ -816,10 +1156,21 @@ unsafe fn load_global_functions_inner(icd: &mut LoadedICD) -> Result<(), IcdErro
     }
     
     // Load instance creation functions
+    load_fn!(enumerate_instance_version, "vkEnumerateInstanceVersion");
+    load_fn!(enumerate_instance_extension_properties, "vkEnumerateInstanceExtensionProperties");
+    load_fn!(enumerate_instance_layer_properties, "vkEnumerateInstanceLayerProperties");
     load_fn!(create_instance, "vkCreateInstance");
     
-    debug!("Loaded global functions - create_instance: {:?}",
-           icd.create_instance.is_some());
+    // Vulkan 1.0 ICDs do not expose vkEnumerateInstanceVersion and stay at 1.0
+    if let Some(enumerate_instance_version) = icd.enumerate_instance_version {
+        let mut version = VK_API_VERSION_1_0;
+        if VkResult::from_raw(enumerate_instance_version(&mut version)) == VkResult::Success {
+            icd.api_version = version;
+        }
+    }
+    
+    kronos_log!(Debug, "Loaded global functions - create_instance: {:?}, api_version: 0x{:x}",
+           icd.create_instance.is_some(), icd.api_version);
     
     Ok(())
 }
@@ -854,17 +1205,70 @@ pub unsafe fn load_instance_functions_inner(icd: &mut LoadedICD, instance: VkIns
     load_fn!(destroy_instance, "vkDestroyInstance");
     load_fn!(enumerate_physical_devices, "vkEnumeratePhysicalDevices");
     load_fn!(get_physical_device_properties, "vkGetPhysicalDeviceProperties");
+    load_fn!(get_physical_device_properties2, "vkGetPhysicalDeviceProperties2");
+    if icd.get_physical_device_properties2.is_none() {
+        load_fn!(get_physical_device_properties2, "vkGetPhysicalDeviceProperties2KHR");
+    }
+    load_fn!(get_physical_device_features, "vkGetPhysicalDeviceFeatures");
     load_fn!(get_physical_device_queue_family_properties, "vkGetPhysicalDeviceQueueFamilyProperties");
+    load_fn!(get_physical_device_queue_family_properties2, "vkGetPhysicalDeviceQueueFamilyProperties2");
+    if icd.get_physical_device_queue_family_properties2.is_none() {
+        load_fn!(get_physical_device_queue_family_properties2, "vkGetPhysicalDeviceQueueFamilyProperties2KHR");
+    }
     load_fn!(get_physical_device_memory_properties, "vkGetPhysicalDeviceMemoryProperties");
     load_fn!(create_device, "vkCreateDevice");
     load_fn!(get_device_proc_addr, "vkGetDeviceProcAddr");
+    load_fn!(enumerate_device_extension_properties, "vkEnumerateDeviceExtensionProperties");
+    load_fn!(enumerate_queue_family_performance_query_counters, "vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR");
+    load_fn!(get_queue_family_performance_query_passes, "vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR");
+    load_fn!(create_debug_utils_messenger, "vkCreateDebugUtilsMessengerEXT");
+    load_fn!(destroy_debug_utils_messenger, "vkDestroyDebugUtilsMessengerEXT");
     
-    debug!("Loaded instance functions - enumerate_physical_devices: {:?}",
+    kronos_log!(Debug, "Loaded instance functions - enumerate_physical_devices: {:?}",
            icd.enumerate_physical_devices.is_some());
     
     Ok(())
 }
 
+/// Version and extensions of a created device, which decide the entry
+/// points loaded for it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceApi {
+    /// Lower of the ICD's and the physical device's Vulkan versions
+    pub api_version: u32,
+    /// Whether VK_KHR_timeline_semaphore was enabled
+    pub timeline_semaphore_khr: bool,
+}
+
+impl DeviceApi {
+    /// Read from the physical device and the device's create info
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must belong to `icd`, and `create_info` must be
+    /// the valid create info the device was created with.
+    pub unsafe fn new(icd: &LoadedICD, physical_device: VkPhysicalDevice, create_info: &VkDeviceCreateInfo) -> Self {
+        // Drivers write the full structure; apiVersion is its first member
+        let mut properties = [0u64; VK_PHYSICAL_DEVICE_PROPERTIES_FULL_SIZE / 8];
+        let device_version = match icd.get_physical_device_properties {
+            Some(f) => {
+                icd_call!("vkGetPhysicalDeviceProperties", f(physical_device, properties.as_mut_ptr() as *mut VkPhysicalDeviceProperties));
+                properties[0] as u32
+            }
+            None => VK_API_VERSION_1_0,
+        };
+        let names = if create_info.enabledExtensionCount == 0 || create_info.ppEnabledExtensionNames.is_null() {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts(create_info.ppEnabledExtensionNames, create_info.enabledExtensionCount as usize)
+        };
+        let timeline_semaphore_khr = names
+            .iter()
+            .any(|&name| CStr::from_ptr(name).to_bytes() == VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME.as_bytes());
+        Self { api_version: icd.api_version.min(device_version & !0xFFF), timeline_semaphore_khr }
+    }
+}
+
 /// Load device-level functions
 ///
 /// # Safety
@@ -878,7 +1282,7 @@ pub unsafe fn load_instance_functions_inner(icd: &mut LoadedICD, instance: VkIns
 /// - Using an invalid device handle will cause undefined behavior
 /// - Function signatures must match the Vulkan specification exactly
 /// - The fallback to instance proc addr requires a valid instance context
-pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice) -> Result<(), IcdError> {
+pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice, api: DeviceApi) -> Result<(), IcdError> {
     // Prefer device-level loader; do not fall back to NULL instance, which is invalid.
     let get_device_proc_fn = icd
         .get_device_proc_addr
@@ -902,6 +1306,7 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     // Device functions
     load_fn!(destroy_device, "vkDestroyDevice");
     load_fn!(get_device_queue, "vkGetDeviceQueue");
+    load_fn!(get_device_queue2, "vkGetDeviceQueue2");
     load_fn!(device_wait_idle, "vkDeviceWaitIdle");
     
     // Queue functions
@@ -913,6 +1318,9 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     load_fn!(free_memory, "vkFreeMemory");
     load_fn!(map_memory, "vkMapMemory");
     load_fn!(unmap_memory, "vkUnmapMemory");
+    load_fn!(invalidate_mapped_memory_ranges, "vkInvalidateMappedMemoryRanges");
+    load_fn!(get_memory_fd_properties, "vkGetMemoryFdPropertiesKHR");
+    load_fn!(get_memory_fd, "vkGetMemoryFdKHR");
     
     // Buffer functions
     load_fn!(create_buffer, "vkCreateBuffer");
@@ -920,6 +1328,23 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     load_fn!(get_buffer_memory_requirements, "vkGetBufferMemoryRequirements");
     load_fn!(bind_buffer_memory, "vkBindBufferMemory");
     
+    // Image functions
+    load_fn!(create_image, "vkCreateImage");
+    load_fn!(destroy_image, "vkDestroyImage");
+    load_fn!(get_image_memory_requirements, "vkGetImageMemoryRequirements");
+    load_fn!(bind_image_memory, "vkBindImageMemory");
+    load_fn!(create_image_view, "vkCreateImageView");
+    load_fn!(destroy_image_view, "vkDestroyImageView");
+    load_fn!(create_sampler, "vkCreateSampler");
+    load_fn!(destroy_sampler, "vkDestroySampler");
+    
+    // Query functions
+    load_fn!(create_query_pool, "vkCreateQueryPool");
+    load_fn!(destroy_query_pool, "vkDestroyQueryPool");
+    load_fn!(get_query_pool_results, "vkGetQueryPoolResults");
+    load_fn!(acquire_profiling_lock, "vkAcquireProfilingLockKHR");
+    load_fn!(release_profiling_lock, "vkReleaseProfilingLockKHR");
+    
     // Compute-specific functions
     load_fn!(create_descriptor_set_layout, "vkCreateDescriptorSetLayout");
     load_fn!(destroy_descriptor_set_layout, "vkDestroyDescriptorSetLayout");
@@ -929,6 +1354,9 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     load_fn!(allocate_descriptor_sets, "vkAllocateDescriptorSets");
     load_fn!(free_descriptor_sets, "vkFreeDescriptorSets");
     load_fn!(update_descriptor_sets, "vkUpdateDescriptorSets");
+    load_fn!(create_descriptor_update_template, "vkCreateDescriptorUpdateTemplate");
+    load_fn!(destroy_descriptor_update_template, "vkDestroyDescriptorUpdateTemplate");
+    load_fn!(update_descriptor_set_with_template, "vkUpdateDescriptorSetWithTemplate");
     
     load_fn!(create_pipeline_layout, "vkCreatePipelineLayout");
     load_fn!(destroy_pipeline_layout, "vkDestroyPipelineLayout");
@@ -949,8 +1377,20 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     load_fn!(cmd_bind_descriptor_sets, "vkCmdBindDescriptorSets");
     load_fn!(cmd_dispatch, "vkCmdDispatch");
     load_fn!(cmd_dispatch_indirect, "vkCmdDispatchIndirect");
+    load_fn!(cmd_dispatch_base, "vkCmdDispatchBase");
+    if icd.cmd_dispatch_base.is_none() {
+        load_fn!(cmd_dispatch_base, "vkCmdDispatchBaseKHR");
+    }
+    load_fn!(cmd_begin_conditional_rendering, "vkCmdBeginConditionalRenderingEXT");
+    load_fn!(cmd_end_conditional_rendering, "vkCmdEndConditionalRenderingEXT");
     load_fn!(cmd_pipeline_barrier, "vkCmdPipelineBarrier");
     load_fn!(cmd_copy_buffer, "vkCmdCopyBuffer");
+    load_fn!(cmd_copy_buffer_to_image, "vkCmdCopyBufferToImage");
+    load_fn!(cmd_copy_image_to_buffer, "vkCmdCopyImageToBuffer");
+    load_fn!(cmd_reset_query_pool, "vkCmdResetQueryPool");
+    load_fn!(cmd_write_timestamp, "vkCmdWriteTimestamp");
+    load_fn!(cmd_begin_query, "vkCmdBeginQuery");
+    load_fn!(cmd_end_query, "vkCmdEndQuery");
     load_fn!(cmd_push_constants, "vkCmdPushConstants");
     
     // Sync functions
@@ -962,6 +1402,7 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     
     load_fn!(create_semaphore, "vkCreateSemaphore");
     load_fn!(destroy_semaphore, "vkDestroySemaphore");
+    load_fn!(get_semaphore_fd, "vkGetSemaphoreFdKHR");
     
     load_fn!(create_event, "vkCreateEvent");
     load_fn!(destroy_event, "vkDestroyEvent");
@@ -973,10 +1414,19 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
     load_fn!(cmd_reset_event, "vkCmdResetEvent");
     load_fn!(cmd_wait_events, "vkCmdWaitEvents");
     
-    // Timeline semaphore functions (optional)
-    load_fn!(wait_semaphores, "vkWaitSemaphores");
+    // Timeline semaphore functions, core from Vulkan 1.2 and otherwise
+    // from VK_KHR_timeline_semaphore; another device's must not carry over
+    icd.wait_semaphores = None;
+    icd.get_semaphore_counter_value = None;
+    if api.api_version >= VK_API_VERSION_1_2 {
+        load_fn!(wait_semaphores, "vkWaitSemaphores");
+        load_fn!(get_semaphore_counter_value, "vkGetSemaphoreCounterValue");
+    } else if api.timeline_semaphore_khr {
+        load_fn!(wait_semaphores, "vkWaitSemaphoresKHR");
+        load_fn!(get_semaphore_counter_value, "vkGetSemaphoreCounterValueKHR");
+    }
     
-    log::debug!("Device functions loaded - create_buffer: {}, create_command_pool: {}",
+    kronos_log!(Debug, "Device functions loaded - create_buffer: {}, create_command_pool: {}",
         icd.create_buffer.is_some(),
         icd.create_command_pool.is_some());
     
@@ -984,16 +1434,29 @@ pub unsafe fn load_device_functions_inner(icd: &mut LoadedICD, device: VkDevice)
 }
 
 /// Initialize the ICD loader
+///
+/// Android has no manifests; the system Vulkan loader stands in for the
+/// ICDs there.
+#[cfg(target_os = "android")]
+pub fn initialize_icd_loader() -> Result<(), IcdError> {
+    kronos_log!(Info, "Initializing ICD loader from {}", super::android::SYSTEM_LOADER);
+    install_icd(super::android::load_system_loader()?)
+}
+
+/// Initialize the ICD loader
+#[cfg(not(target_os = "android"))]
 pub fn initialize_icd_loader() -> Result<(), IcdError> {
-    info!("Initializing ICD loader...");
+    kronos_log!(Info, "Initializing ICD loader...");
     let icd_files = discover_icds();
     
     if icd_files.is_empty() {
-        warn!("No ICD manifest files found");
-        return Err(IcdError::NoManifestsFound);
+        kronos_log!(Warn, "No ICD manifest files found");
+        if !cfg!(feature = "bundled-swiftshader") {
+            return Err(IcdError::NoManifestsFound);
+        }
     }
     
-    info!("Found {} ICD manifest files", icd_files.len());
+    kronos_log!(Info, "Found {} ICD manifest files", icd_files.len());
     
     // Check if we have environment variable override
     let env_icd_count = if let Ok(icd_filenames) = env::var("VK_ICD_FILENAMES") {
@@ -1008,60 +1471,60 @@ pub fn initialize_icd_loader() -> Result<(), IcdError> {
     
     // Try to load each ICD
     for (idx, icd_file) in icd_files.iter().enumerate() {
-        if let Some(manifest) = parse_icd_manifest(&icd_file) {
-            // Build candidate library paths. Prefer the path as provided to allow
-            // dynamic linker search to resolve common locations (e.g. /usr/lib). As a
-            // fallback, try relative to the manifest directory.
-            let mut candidates: Vec<PathBuf> = Vec::new();
-            if Path::new(&manifest.library_path).is_absolute() {
-                candidates.push(PathBuf::from(&manifest.library_path));
-            } else {
-                // As provided (lets dlopen search LD_LIBRARY_PATH etc.)
-                candidates.push(PathBuf::from(&manifest.library_path));
-                // Fallback relative to manifest directory
-                if let Some(parent) = icd_file.parent() {
-                    candidates.push(parent.join(&manifest.library_path));
-                }
-            }
+        if let Some(manifest) = parse_icd_manifest(icd_file) {
+            let candidates = library_candidates(&manifest.library_path, icd_file);
 
             let mut loaded_ok: Option<LoadedICD> = None;
             for cand in &candidates {
                 // Canonicalize candidate for validation
                 let can = fs::canonicalize(cand).unwrap_or(cand.clone());
-                log::info!("Attempting to load ICD library: {} (from {})", can.display(), icd_file.display());
+                kronos_log!(Info, "Attempting to load ICD library: {} (from {})", redact(&can), redact(icd_file));
                 match load_icd(&can) {
-                    Ok(icd) => {
+                    Ok(mut icd) => {
+                        icd.is_portability_driver |= manifest.is_portability_driver;
                         loaded_ok = Some(icd);
                         break;
                     }
                     Err(e) => {
-                        warn!("Failed to load candidate {}: {}", can.display(), e);
+                        kronos_log!(Warn, "Failed to load candidate {}: {}", redact(&can), e);
                     }
                 }
             }
 
             if let Some(icd) = loaded_ok {
                     // Check if this is a software renderer
-                    let path_str = icd.library_path.to_string_lossy();
-                    let is_software = path_str.contains("lvp") ||
-                                     path_str.contains("swrast") ||
-                                     path_str.contains("llvmpipe");
+                    let is_software = is_software_library(&icd.library_path);
                     
                     // Environment variable ICDs are prioritized (first N entries from discover_icds)
                     let is_env_priority = idx < env_icd_count;
                     
                     let icd_type = if is_software { "software" } else { "hardware" };
                     let priority_str = if is_env_priority { " (VK_ICD_FILENAMES priority)" } else { "" };
-                    info!("Successfully loaded {} Vulkan ICD: {}{}", icd_type, icd.library_path.display(), priority_str);
+                    kronos_log!(Info, "Successfully loaded {} Vulkan ICD: {}{}", icd_type, redact(&icd.library_path), priority_str);
                     
                     loaded_icds.push((icd, is_software, is_env_priority));
             } else {
-                warn!("Failed to load ICD from any candidate for manifest {}", icd_file.display());
+                kronos_log!(Warn, "Failed to load ICD from any candidate for manifest {}", redact(icd_file));
             }
         }
     }
     
+    // Without a hardware driver, fall back to a software renderer shipped
+    // with the application
+    #[cfg(feature = "bundled-swiftshader")]
+    if loaded_icds.iter().all(|(_, is_sw, _)| *is_sw) && super::bundled_icd::software_fallback_allowed() {
+        if let Some(icd) = super::bundled_icd::load_bundled_icd() {
+            if !loaded_icds.iter().any(|(loaded, _, _)| loaded.library_path == icd.library_path) {
+                kronos_log!(Info, "Loaded bundled software ICD: {}", redact(&icd.library_path));
+                loaded_icds.push((icd, true, false));
+            }
+        }
+    }
+
     if loaded_icds.is_empty() {
+        if icd_files.is_empty() {
+            return Err(IcdError::NoManifestsFound);
+        }
         return Err(IcdError::InvalidManifest("Failed to load any Vulkan ICD".to_string()));
     }
 
@@ -1071,7 +1534,7 @@ pub fn initialize_icd_loader() -> Result<(), IcdError> {
         let any_hw = loaded_icds.iter().any(|(_, is_sw, _)| !*is_sw);
         if any_hw {
             loaded_icds.retain(|(_, is_sw, _)| !*is_sw);
-            info!("Hardware ICDs available; software ICDs will be ignored (set KRONOS_PREFER_HARDWARE=0 to disable)");
+            kronos_log!(Info, "Hardware ICDs available; software ICDs will be ignored (set KRONOS_PREFER_HARDWARE=0 to disable)");
         }
     }
 
@@ -1081,7 +1544,7 @@ pub fn initialize_icd_loader() -> Result<(), IcdError> {
     });
     
     // Log all available ICDs
-    info!("Available ICDs: {} hardware, {} software", 
+    kronos_log!(Info, "Available ICDs: {} hardware, {} software", 
           loaded_icds.iter().filter(|(_, is_sw, _)| !is_sw).count(),
           loaded_icds.iter().filter(|(_, is_sw, _)| *is_sw).count());
     
@@ -1100,39 +1563,39 @@ pub fn initialize_icd_loader() -> Result<(), IcdError> {
                 if let Some((idx, _)) = loaded_icds.iter().enumerate().find(|(_, (icd, _, _))| icd.library_path == want) {
                     loaded_icds.into_iter().nth(idx).unwrap()
                 } else {
-                    warn!("Preferred ICD path not found: {} — falling back to default selection", want.display());
+                    kronos_log!(Warn, "Preferred ICD path not found: {} — falling back to default selection", redact(&want));
                     loaded_icds.into_iter().next().unwrap()
                 }
             }
             IcdPreference::Index(i) => {
-                log::info!("Applying ICD preference: index {} (from available_icds order)", i);
+                kronos_log!(Info, "Applying ICD preference: index {} (from available_icds order)", i);
                 
                 // The index refers to the order in available_icds(), not loaded_icds
                 // We need to find the matching ICD by path from ALL_ICDS
                 if let Ok(all_icds) = ALL_ICDS.lock() {
-                    log::info!("ALL_ICDS contains {} ICDs:", all_icds.len());
+                    kronos_log!(Info, "ALL_ICDS contains {} ICDs:", all_icds.len());
                     for (idx, icd) in all_icds.iter().enumerate() {
-                        log::info!("  [{}] {}", idx, icd.library_path.display());
+                        kronos_log!(Info, "  [{}] {}", idx, redact(&icd.library_path));
                     }
                     
                     if let Some(target_icd) = all_icds.get(i) {
                         let target_path = &target_icd.library_path;
-                        log::info!("Selected index {} points to: {}", i, target_path.display());
+                        kronos_log!(Info, "Selected index {} points to: {}", i, redact(target_path));
                         
                         // Find this ICD in loaded_icds
                         if let Some((idx, _)) = loaded_icds.iter().enumerate()
                             .find(|(_, (icd, _, _))| &icd.library_path == target_path) {
                             loaded_icds.into_iter().nth(idx).unwrap()
                         } else {
-                            warn!("Preferred ICD not found in loaded set; falling back to default");
+                            kronos_log!(Warn, "Preferred ICD not found in loaded set; falling back to default");
                             loaded_icds.into_iter().next().unwrap()
                         }
                     } else {
-                        warn!("Preferred ICD index {} out of range ({}); falling back to default", i, all_icds.len());
+                        kronos_log!(Warn, "Preferred ICD index {} out of range ({}); falling back to default", i, all_icds.len());
                         loaded_icds.into_iter().next().unwrap()
                     }
                 } else {
-                    warn!("Could not access ALL_ICDS; falling back to default");
+                    kronos_log!(Warn, "Could not access ALL_ICDS; falling back to default");
                     loaded_icds.into_iter().next().unwrap()
                 }
             }
@@ -1143,19 +1606,19 @@ pub fn initialize_icd_loader() -> Result<(), IcdError> {
     };
     
     if is_env_priority {
-        info!("Using ICD specified by VK_ICD_FILENAMES: {}", best_icd.library_path.display());
+        kronos_log!(Info, "Using ICD specified by VK_ICD_FILENAMES: {}", redact(&best_icd.library_path));
     } else if is_software {
-        warn!("Using software renderer - no hardware Vulkan drivers found");
-        info!("To use hardware drivers, ensure they are installed and ICD files are in /usr/share/vulkan/icd.d/");
+        kronos_log!(Warn, "Using software renderer - no hardware Vulkan drivers found");
+        kronos_log!(Info, "To use hardware drivers, ensure they are installed and ICD files are in /usr/share/vulkan/icd.d/");
     } else {
-        info!("Selected hardware Vulkan driver: {}", best_icd.library_path.display());
+        kronos_log!(Info, "Selected hardware Vulkan driver: {}", redact(&best_icd.library_path));
     }
     
     // In aggregated mode, log that we're using multiple ICDs
     if aggregated_mode_enabled() {
         let icd_count = ALL_ICDS.lock()?.len();
-        info!("Aggregated mode enabled: {} ICDs available for multi-GPU support", icd_count);
-        info!("Using {} as fallback ICD", best_icd.library_path.display());
+        kronos_log!(Info, "Aggregated mode enabled: {} ICDs available for multi-GPU support", icd_count);
+        kronos_log!(Info, "Using {} as fallback ICD", redact(&best_icd.library_path));
     }
     
     *ICD_LOADER.lock()? = Some(Arc::new(best_icd));
@@ -1167,31 +1630,31 @@ pub fn get_icd() -> Option<Arc<LoadedICD>> {
     // Always use the main ICD from ICD_LOADER
     // This ensures we get the ICD with properly loaded instance/device functions
     // Preferences are already applied during initialization in initialize_icd_loader()
-    log::trace!("Using main ICD from ICD_LOADER");
+    kronos_log!(Trace, "Using main ICD from ICD_LOADER");
     ICD_LOADER.lock().ok()?.as_ref().cloned()
 }
 
 /// Get a specific ICD based on preference
 fn get_preferred_icd(pref: &IcdPreference) -> Option<Arc<LoadedICD>> {
     let all_icds = ALL_ICDS.lock().ok()?;
-    log::debug!("Looking for preferred ICD among {} loaded ICDs", all_icds.len());
+    kronos_log!(Debug, "Looking for preferred ICD among {} loaded ICDs", all_icds.len());
     
     match pref {
         IcdPreference::Path(want) => {
-            log::debug!("Looking for ICD with path: {:?}", want);
+            kronos_log!(Debug, "Looking for ICD with path: {:?}", redact(want));
             let result = all_icds.iter()
                 .find(|icd| &icd.library_path == want)
                 .cloned();
             if result.is_none() {
-                log::warn!("Preferred ICD path not found: {:?}", want);
+                kronos_log!(Warn, "Preferred ICD path not found: {:?}", redact(want));
             }
             result
         }
         IcdPreference::Index(i) => {
-            log::debug!("Looking for ICD at index: {}", i);
+            kronos_log!(Debug, "Looking for ICD at index: {}", i);
             let result = all_icds.get(*i).cloned();
             if result.is_none() {
-                log::warn!("Preferred ICD index {} out of range (have {} ICDs)", i, all_icds.len());
+                kronos_log!(Warn, "Preferred ICD index {} out of range (have {} ICDs)", i, all_icds.len());
             }
             result
         }
@@ -1217,8 +1680,8 @@ pub unsafe fn update_instance_functions(instance: VkInstance) -> Result<(), IcdE
 }
 
 /// Update device-level function pointers for the current ICD
-pub unsafe fn update_device_functions(device: VkDevice) -> Result<(), IcdError> {
-    replace_icd(|icd| load_device_functions_inner(icd, device))
+pub unsafe fn update_device_functions(device: VkDevice, api: DeviceApi) -> Result<(), IcdError> {
+    replace_icd(|icd| load_device_functions_inner(icd, device, api))
 }
 
 /// Load instance functions for a specific ICD (used in aggregated mode)
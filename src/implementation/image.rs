@@ -0,0 +1,277 @@
+//! Storage image creation and management
+//!
+//! Only the subset needed for compute kernels is exposed: images, views,
+//! samplers, memory binding and buffer<->image copies. No render passes or
+//! attachments.
+
+use crate::sys::*;
+use crate::core::*;
+use crate::ffi::*;
+use crate::implementation::child_objects;
+use crate::implementation::icd_loader;
+
+/// Create an image
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice created by vkCreateDevice
+// 2. pCreateInfo points to a valid VkImageCreateInfo structure
+// 3. pAllocator is either null or points to valid allocation callbacks
+// 4. pImage points to valid memory for writing the image handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateImage(
+    device: VkDevice,
+    pCreateInfo: *const VkImageCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pImage: *mut VkImage,
+) -> VkResult {
+    if device.is_null() || pCreateInfo.is_null() || pImage.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.create_image { return child_objects::created(device, icd_call!("vkCreateImage", f(device, pCreateInfo, pAllocator, pImage)), pImage, 1); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(create_image) = icd.create_image { return child_objects::created(device, icd_call!("vkCreateImage", create_image(device, pCreateInfo, pAllocator, pImage)), pImage, 1); }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Destroy an image
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. image is a valid VkImage created by vkCreateImage, or VK_NULL_HANDLE
+// 3. pAllocator matches the allocator used in vkCreateImage (or both are null)
+// 4. All views of the image have been destroyed and no GPU work references it
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroyImage(
+    device: VkDevice,
+    image: VkImage,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if device.is_null() || image.is_null() {
+        return;
+    }
+    let _destroyed = child_objects::destroyed(device, image);
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.destroy_image { icd_call!("vkDestroyImage", f(device, image, pAllocator)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(destroy_image) = icd.destroy_image { icd_call!("vkDestroyImage", destroy_image(device, image, pAllocator)); }
+    }
+}
+
+/// Get image memory requirements
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. image is a valid VkImage created by vkCreateImage
+// 3. pMemoryRequirements points to valid memory for a VkMemoryRequirements structure
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetImageMemoryRequirements(
+    device: VkDevice,
+    image: VkImage,
+    pMemoryRequirements: *mut VkMemoryRequirements,
+) {
+    if device.is_null() || image.is_null() || pMemoryRequirements.is_null() {
+        return;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.get_image_memory_requirements { icd_call!("vkGetImageMemoryRequirements", f(device, image, pMemoryRequirements)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(get_image_memory_requirements) = icd.get_image_memory_requirements { icd_call!("vkGetImageMemoryRequirements", get_image_memory_requirements(device, image, pMemoryRequirements)); }
+    }
+}
+
+/// Bind image to memory
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. image is a valid VkImage that has not been bound to memory yet
+// 3. memory is a valid VkDeviceMemory allocated with vkAllocateMemory
+// 4. memoryOffset satisfies the image's size and alignment requirements
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkBindImageMemory(
+    device: VkDevice,
+    image: VkImage,
+    memory: VkDeviceMemory,
+    memoryOffset: VkDeviceSize,
+) -> VkResult {
+    if device.is_null() || image.is_null() || memory.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.bind_image_memory { return icd_call!("vkBindImageMemory", f(device, image, memory, memoryOffset)); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(bind_image_memory) = icd.bind_image_memory { return icd_call!("vkBindImageMemory", bind_image_memory(device, image, memory, memoryOffset)); }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Create an image view
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. pCreateInfo points to a valid VkImageViewCreateInfo referencing a bound image
+// 3. pAllocator is either null or points to valid allocation callbacks
+// 4. pView points to valid memory for writing the view handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateImageView(
+    device: VkDevice,
+    pCreateInfo: *const VkImageViewCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pView: *mut VkImageView,
+) -> VkResult {
+    if device.is_null() || pCreateInfo.is_null() || pView.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.create_image_view { return child_objects::created(device, icd_call!("vkCreateImageView", f(device, pCreateInfo, pAllocator, pView)), pView, 1); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(create_image_view) = icd.create_image_view { return child_objects::created(device, icd_call!("vkCreateImageView", create_image_view(device, pCreateInfo, pAllocator, pView)), pView, 1); }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Destroy an image view
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. imageView is a valid VkImageView, or VK_NULL_HANDLE
+// 3. pAllocator matches the allocator used in vkCreateImageView (or both are null)
+// 4. No descriptor sets in use by pending GPU work reference the view
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroyImageView(
+    device: VkDevice,
+    imageView: VkImageView,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if device.is_null() || imageView.is_null() {
+        return;
+    }
+    let _destroyed = child_objects::destroyed(device, imageView);
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.destroy_image_view { icd_call!("vkDestroyImageView", f(device, imageView, pAllocator)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(destroy_image_view) = icd.destroy_image_view { icd_call!("vkDestroyImageView", destroy_image_view(device, imageView, pAllocator)); }
+    }
+}
+
+/// Create a sampler
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. pCreateInfo points to a valid VkSamplerCreateInfo structure
+// 3. pAllocator is either null or points to valid allocation callbacks
+// 4. pSampler points to valid memory for writing the sampler handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateSampler(
+    device: VkDevice,
+    pCreateInfo: *const VkSamplerCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pSampler: *mut VkSampler,
+) -> VkResult {
+    if device.is_null() || pCreateInfo.is_null() || pSampler.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.create_sampler { return child_objects::created(device, icd_call!("vkCreateSampler", f(device, pCreateInfo, pAllocator, pSampler)), pSampler, 1); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(create_sampler) = icd.create_sampler { return child_objects::created(device, icd_call!("vkCreateSampler", create_sampler(device, pCreateInfo, pAllocator, pSampler)), pSampler, 1); }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Destroy a sampler
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. sampler is a valid VkSampler created by vkCreateSampler, or VK_NULL_HANDLE
+// 3. pAllocator matches the allocator used in vkCreateSampler (or both are null)
+// 4. No descriptor sets in use by pending GPU work reference the sampler
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroySampler(
+    device: VkDevice,
+    sampler: VkSampler,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if device.is_null() || sampler.is_null() {
+        return;
+    }
+    let _destroyed = child_objects::destroyed(device, sampler);
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.destroy_sampler { icd_call!("vkDestroySampler", f(device, sampler, pAllocator)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(destroy_sampler) = icd.destroy_sampler { icd_call!("vkDestroySampler", destroy_sampler(device, sampler, pAllocator)); }
+    }
+}
+
+/// Copy buffer contents into an image
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. srcBuffer was created with TRANSFER_SRC usage and dstImage with TRANSFER_DST usage
+// 3. dstImage is in dstImageLayout (General or TransferDstOptimal) when the copy executes
+// 4. regionCount > 0 and pRegions points to that many VkBufferImageCopy structures
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdCopyBufferToImage(
+    commandBuffer: VkCommandBuffer,
+    srcBuffer: VkBuffer,
+    dstImage: VkImage,
+    dstImageLayout: VkImageLayout,
+    regionCount: u32,
+    pRegions: *const VkBufferImageCopy,
+) {
+    if commandBuffer.is_null() || srcBuffer.is_null() || dstImage.is_null() ||
+       regionCount == 0 || pRegions.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_copy_buffer_to_image { icd_call!("vkCmdCopyBufferToImage", f(commandBuffer, srcBuffer, dstImage, dstImageLayout, regionCount, pRegions)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_copy_buffer_to_image) = icd.cmd_copy_buffer_to_image {
+            icd_call!("vkCmdCopyBufferToImage", cmd_copy_buffer_to_image(commandBuffer, srcBuffer, dstImage, dstImageLayout, regionCount, pRegions));
+        }
+    }
+}
+
+/// Copy image contents into a buffer
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. srcImage was created with TRANSFER_SRC usage and dstBuffer with TRANSFER_DST usage
+// 3. srcImage is in srcImageLayout (General or TransferSrcOptimal) when the copy executes
+// 4. regionCount > 0 and pRegions points to that many VkBufferImageCopy structures
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdCopyImageToBuffer(
+    commandBuffer: VkCommandBuffer,
+    srcImage: VkImage,
+    srcImageLayout: VkImageLayout,
+    dstBuffer: VkBuffer,
+    regionCount: u32,
+    pRegions: *const VkBufferImageCopy,
+) {
+    if commandBuffer.is_null() || srcImage.is_null() || dstBuffer.is_null() ||
+       regionCount == 0 || pRegions.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_copy_image_to_buffer { icd_call!("vkCmdCopyImageToBuffer", f(commandBuffer, srcImage, srcImageLayout, dstBuffer, regionCount, pRegions)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_copy_image_to_buffer) = icd.cmd_copy_image_to_buffer {
+            icd_call!("vkCmdCopyImageToBuffer", cmd_copy_image_to_buffer(commandBuffer, srcImage, srcImageLayout, dstBuffer, regionCount, pRegions));
+        }
+    }
+}
@@ -3,16 +3,166 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use std::ffi::c_char;
 use std::ptr;
 use std::sync::Arc;
 
+/// Query the instance-level Vulkan version supported by the loaded ICD(s)
+///
+/// In aggregated mode the lowest version across all ICDs is reported, since
+/// every ICD receives the same VkApplicationInfo.
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. pApiVersion points to valid memory for writing a u32
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkEnumerateInstanceVersion(
+    pApiVersion: *mut u32,
+) -> VkResult {
+    if pApiVersion.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    if crate::implementation::icd_loader::aggregated_mode_enabled() {
+        let all = crate::implementation::icd_loader::discover_and_load_all_icds();
+        if let Some(version) = all.iter().map(|icd| icd.api_version).min() {
+            *pApiVersion = version;
+            return VkResult::Success;
+        }
+    }
+    if let Some(icd) = super::icd_loader::get_icd() {
+        *pApiVersion = icd.api_version;
+        return VkResult::Success;
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Query the instance extensions of the loaded ICD(s)
+///
+/// In aggregated mode only extensions every ICD supports are reported, for
+/// the same reason as in [`vkEnumerateInstanceVersion`]. Kronos implements
+/// no layers; the extensions of a layer are those of the driver's loader,
+/// see [`vkEnumerateInstanceLayerProperties`].
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. pLayerName is null or a null-terminated string
+// 2. pPropertyCount points to valid memory for reading and writing a u32
+// 3. pProperties is null or points to *pPropertyCount writable elements
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkEnumerateInstanceExtensionProperties(
+    pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> VkResult {
+    if pPropertyCount.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    if !pLayerName.is_null() {
+        return match loader_icd() {
+            Some(icd) => match icd.enumerate_instance_extension_properties {
+                Some(enumerate) => icd_call!(
+                    "vkEnumerateInstanceExtensionProperties",
+                    enumerate(pLayerName, pPropertyCount, pProperties)
+                ),
+                None => VkResult::ErrorLayerNotPresent,
+            },
+            None => VkResult::ErrorLayerNotPresent,
+        };
+    }
+    let icds = if crate::implementation::icd_loader::aggregated_mode_enabled() {
+        crate::implementation::icd_loader::discover_and_load_all_icds()
+    } else {
+        super::icd_loader::get_icd().into_iter().collect()
+    };
+    let Some((first, rest)) = icds.split_first() else {
+        return VkResult::ErrorInitializationFailed;
+    };
+    let mut extensions = icd_instance_extensions(first);
+    for icd in rest {
+        let supported = icd_instance_extensions(icd);
+        extensions.retain_mut(|extension| {
+            match supported.iter().find(|other| other.extensionName == extension.extensionName) {
+                Some(other) => {
+                    extension.specVersion = extension.specVersion.min(other.specVersion);
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    if pProperties.is_null() {
+        *pPropertyCount = extensions.len() as u32;
+        return VkResult::Success;
+    }
+    let count = (*pPropertyCount as usize).min(extensions.len());
+    ptr::copy_nonoverlapping(extensions.as_ptr(), pProperties, count);
+    *pPropertyCount = count as u32;
+    if count < extensions.len() {
+        VkResult::Incomplete
+    } else {
+        VkResult::Success
+    }
+}
+
+/// Instance extensions one ICD reports, empty if it cannot be asked
+unsafe fn icd_instance_extensions(icd: &super::icd_loader::LoadedICD) -> Vec<VkExtensionProperties> {
+    let Some(enumerate) = icd.enumerate_instance_extension_properties else {
+        return Vec::new();
+    };
+    let mut count = 0;
+    let result = icd_call!("vkEnumerateInstanceExtensionProperties", enumerate(ptr::null(), &mut count, ptr::null_mut()));
+    if result != VkResult::Success {
+        return Vec::new();
+    }
+    let mut extensions = vec![VkExtensionProperties::default(); count as usize];
+    let result = icd_call!(
+        "vkEnumerateInstanceExtensionProperties",
+        enumerate(ptr::null(), &mut count, extensions.as_mut_ptr())
+    );
+    if result != VkResult::Success && result != VkResult::Incomplete {
+        return Vec::new();
+    }
+    extensions.truncate(count as usize);
+    extensions
+}
+
+/// The driver, if Kronos forwards to a single one that is itself a Vulkan
+/// loader, such as one given to `InitOptions::with_loader`
+fn loader_icd() -> Option<Arc<super::icd_loader::LoadedICD>> {
+    if crate::implementation::icd_loader::aggregated_mode_enabled() {
+        return None;
+    }
+    super::icd_loader::get_icd().filter(|icd| icd.enumerate_instance_layer_properties.is_some())
+}
+
+/// Query the instance layers
+///
+/// Kronos implements no layers. When it forwards to a single driver that is
+/// a Vulkan loader, the layers that loader found (the validation layers,
+/// typically) are reported and may be enabled in `vkCreateInstance`, which
+/// passes the create info through. Aggregated mode reports none.
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. pPropertyCount points to valid memory for reading and writing a u32
+// 2. pProperties is null or points to *pPropertyCount writable elements
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkEnumerateInstanceLayerProperties(
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkLayerProperties,
+) -> VkResult {
+    if pPropertyCount.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    if let Some(enumerate) = loader_icd().and_then(|icd| icd.enumerate_instance_layer_properties) {
+        return icd_call!("vkEnumerateInstanceLayerProperties", enumerate(pPropertyCount, pProperties));
+    }
+    *pPropertyCount = 0;
+    VkResult::Success
+}
+
 /// Create a Kronos instance
 // SAFETY: This function is called from C code. Caller must ensure:
 // 1. pCreateInfo points to a valid VkInstanceCreateInfo structure
 // 2. pAllocator is either null or points to valid allocation callbacks
 // 3. pInstance points to valid memory for writing the instance handle
 // 4. All pointers remain valid for the duration of this call
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateInstance(
     pCreateInfo: *const VkInstanceCreateInfo,
     pAllocator: *const VkAllocationCallbacks,
@@ -29,12 +179,12 @@ pub unsafe extern "C" fn vkCreateInstance(
         for icd_arc in all {
             if let Some(create_instance_fn) = icd_arc.create_instance {
                 let mut inner_inst = VkInstance::NULL;
-                let res = create_instance_fn(pCreateInfo, pAllocator, &mut inner_inst);
+                let res = icd_call!("vkCreateInstance", create_instance_fn(pCreateInfo, pAllocator, &mut inner_inst));
                 if res == VkResult::Success && !inner_inst.is_null() {
                     // Clone the ICD and load instance functions
                     let mut icd_copy = (*icd_arc).clone();
                     if let Err(e) = crate::implementation::icd_loader::load_instance_functions_for_icd(&mut icd_copy, inner_inst) {
-                        log::warn!("Failed to load instance functions for ICD: {:?}", e);
+                        kronos_log!(Warn, "Failed to load instance functions for ICD: {:?}", e);
                         // Still include it, some functions might work
                     }
                     inners.push((Arc::new(icd_copy), inner_inst));
@@ -53,14 +203,14 @@ pub unsafe extern "C" fn vkCreateInstance(
     // Try to use real Vulkan driver (single ICD)
     if let Some(icd) = super::icd_loader::get_icd() {
         if let Some(create_instance_fn) = icd.create_instance {
-            let result = create_instance_fn(pCreateInfo, pAllocator, pInstance);
+            let result = icd_call!("vkCreateInstance", create_instance_fn(pCreateInfo, pAllocator, pInstance));
             
             // If successful, load instance functions
             if result == VkResult::Success {
-                log::info!("[vkCreateInstance] Single-ICD mode: Loading instance functions for instance {:?}", *pInstance);
+                kronos_log!(Info, "[vkCreateInstance] Single-ICD mode: Loading instance functions for instance {:?}", *pInstance);
                 match super::icd_loader::update_instance_functions(*pInstance) {
-                    Ok(()) => log::info!("[vkCreateInstance] Successfully loaded instance functions"),
-                    Err(e) => log::error!("[vkCreateInstance] Failed to load instance functions: {:?}", e),
+                    Ok(()) => kronos_log!(Info, "[vkCreateInstance] Successfully loaded instance functions"),
+                    Err(e) => kronos_log!(Error, "[vkCreateInstance] Failed to load instance functions: {:?}", e),
                 }
             }
             
@@ -77,7 +227,7 @@ pub unsafe extern "C" fn vkCreateInstance(
 // 1. instance is a valid VkInstance created by vkCreateInstance
 // 2. pAllocator matches the allocator used in vkCreateInstance (or both are null)
 // 3. All objects created from this instance have been destroyed
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyInstance(
     instance: VkInstance,
     pAllocator: *const VkAllocationCallbacks,
@@ -89,7 +239,7 @@ pub unsafe extern "C" fn vkDestroyInstance(
     if crate::implementation::icd_loader::aggregated_mode_enabled() {
         if let Some(inners) = crate::implementation::icd_loader::take_meta_instance(instance.as_raw()) {
             for (icd, inner) in inners {
-                if let Some(f) = icd.destroy_instance { f(inner, pAllocator); }
+                if let Some(f) = icd.destroy_instance { icd_call!("vkDestroyInstance", f(inner, pAllocator)); }
             }
             return;
         }
@@ -98,7 +248,7 @@ pub unsafe extern "C" fn vkDestroyInstance(
     // Forward to real ICD if available
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(destroy_instance) = icd.destroy_instance {
-            destroy_instance(instance, pAllocator);
+            icd_call!("vkDestroyInstance", destroy_instance(instance, pAllocator));
         }
     }
 }
@@ -108,7 +258,7 @@ pub unsafe extern "C" fn vkDestroyInstance(
 // 1. instance is a valid VkInstance
 // 2. pPhysicalDeviceCount points to valid memory
 // 3. If pPhysicalDevices is not null, it points to an array of at least *pPhysicalDeviceCount elements
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
     instance: VkInstance,
     pPhysicalDeviceCount: *mut u32,
@@ -125,9 +275,10 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
             for (icd, inner) in &inners {
                 if let Some(f) = icd.enumerate_physical_devices {
                     let mut count = 0u32;
-                    let result = f(*inner, &mut count, ptr::null_mut());
+                    let result = icd_call!("vkEnumeratePhysicalDevices", f(*inner, &mut count, ptr::null_mut()));
                     if result != VkResult::Success {
-                        log::error!(
+                        kronos_log!(
+                            Error,
                             "[vkEnumeratePhysicalDevices] Failed to query physical device count from ICD {:?}: {:?}",
                             icd.library_path,
                             result
@@ -150,7 +301,7 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
                     if filled >= cap { break; }
                     let mut count = (cap - filled) as u32;
                     let buf_ptr = unsafe { pPhysicalDevices.add(filled) };
-                    let res = f(*inner, &mut count, buf_ptr);
+                    let res = icd_call!("vkEnumeratePhysicalDevices", f(*inner, &mut count, buf_ptr));
                     match res {
                         VkResult::Success | VkResult::Incomplete => {
                             if res == VkResult::Incomplete {
@@ -164,7 +315,8 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
                             filled += count as usize;
                         }
                         _ => {
-                            log::error!(
+                            kronos_log!(
+                                Error,
                                 "[vkEnumeratePhysicalDevices] Failed to enumerate physical devices from ICD {:?}: {:?}",
                                 icd.library_path,
                                 res
@@ -184,21 +336,21 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
     }
     
     // Forward to real ICD (single)
-    log::debug!("[vkEnumeratePhysicalDevices] Single-ICD mode, forwarding to ICD");
+    kronos_log!(Debug, "[vkEnumeratePhysicalDevices] Single-ICD mode, forwarding to ICD");
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        log::debug!("[vkEnumeratePhysicalDevices] Got ICD, checking enumerate function");
+        kronos_log!(Debug, "[vkEnumeratePhysicalDevices] Got ICD, checking enumerate function");
         if let Some(enumerate_physical_devices) = icd.enumerate_physical_devices {
-            log::debug!("[vkEnumeratePhysicalDevices] Calling ICD's enumerate function");
-            let result = enumerate_physical_devices(instance, pPhysicalDeviceCount, pPhysicalDevices);
+            kronos_log!(Debug, "[vkEnumeratePhysicalDevices] Calling ICD's enumerate function");
+            let result = icd_call!("vkEnumeratePhysicalDevices", enumerate_physical_devices(instance, pPhysicalDeviceCount, pPhysicalDevices));
             if pPhysicalDevices.is_null() {
-                log::info!("[vkEnumeratePhysicalDevices] Query returned {} devices", unsafe { *pPhysicalDeviceCount });
+                kronos_log!(Info, "[vkEnumeratePhysicalDevices] Query returned {} devices", unsafe { *pPhysicalDeviceCount });
             }
             return result;
         } else {
-            log::warn!("[vkEnumeratePhysicalDevices] ICD loaded but enumerate_physical_devices function pointer is null");
+            kronos_log!(Warn, "[vkEnumeratePhysicalDevices] ICD loaded but enumerate_physical_devices function pointer is null");
         }
     } else {
-        log::warn!("No ICD available for enumerate_physical_devices");
+        kronos_log!(Warn, "No ICD available for enumerate_physical_devices");
     }
     
     // No ICD available
@@ -209,36 +361,92 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
 // SAFETY: This function is called from C code. Caller must ensure:
 // 1. physicalDevice is a valid VkPhysicalDevice obtained from vkEnumeratePhysicalDevices
 // 2. pProperties points to valid memory for a VkPhysicalDeviceProperties structure
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetPhysicalDeviceProperties(
     physicalDevice: VkPhysicalDevice,
     pProperties: *mut VkPhysicalDeviceProperties,
 ) {
-    log::debug!("[vkGetPhysicalDeviceProperties] Called with device {:?}", physicalDevice);
+    kronos_log!(Debug, "[vkGetPhysicalDeviceProperties] Called with device {:?}", physicalDevice);
     if physicalDevice.is_null() || pProperties.is_null() {
-        log::error!("[vkGetPhysicalDeviceProperties] Null pointer provided");
+        kronos_log!(Error, "[vkGetPhysicalDeviceProperties] Null pointer provided");
         return;
     }
     // Route by owning ICD if known
     if let Some(icd) = crate::implementation::icd_loader::icd_for_physical_device(physicalDevice) {
-        log::debug!("[vkGetPhysicalDeviceProperties] Found ICD for device, routing call");
+        kronos_log!(Debug, "[vkGetPhysicalDeviceProperties] Found ICD for device, routing call");
         if let Some(f) = icd.get_physical_device_properties { 
-            f(physicalDevice, pProperties); 
+            icd_call!("vkGetPhysicalDeviceProperties", f(physicalDevice, pProperties)); 
         } else {
-            log::error!("[vkGetPhysicalDeviceProperties] ICD has no get_physical_device_properties function!");
+            kronos_log!(Error, "[vkGetPhysicalDeviceProperties] ICD has no get_physical_device_properties function!");
         }
         return;
     }
-    log::debug!("[vkGetPhysicalDeviceProperties] No ICD found for device, using fallback");
+    kronos_log!(Debug, "[vkGetPhysicalDeviceProperties] No ICD found for device, using fallback");
     // Fallback to single ICD
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(f) = icd.get_physical_device_properties { 
-            f(physicalDevice, pProperties); 
+            icd_call!("vkGetPhysicalDeviceProperties", f(physicalDevice, pProperties)); 
         } else {
-            log::error!("[vkGetPhysicalDeviceProperties] Fallback ICD has no get_physical_device_properties function!");
+            kronos_log!(Error, "[vkGetPhysicalDeviceProperties] Fallback ICD has no get_physical_device_properties function!");
         }
     } else {
-        log::error!("[vkGetPhysicalDeviceProperties] No fallback ICD available!");
+        kronos_log!(Error, "[vkGetPhysicalDeviceProperties] No fallback ICD available!");
+    }
+}
+
+/// Get physical device properties with extension chains
+///
+/// ICDs without the Vulkan 1.1 query are answered from the 1.0 one; the
+/// chained structures are then left as the caller initialised them.
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice
+// 2. pProperties points to a VkPhysicalDeviceProperties2 with sType and
+//    pNext initialised
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetPhysicalDeviceProperties2(
+    physicalDevice: VkPhysicalDevice,
+    pProperties: *mut VkPhysicalDeviceProperties2,
+) {
+    if physicalDevice.is_null() || pProperties.is_null() {
+        return;
+    }
+    let icd = crate::implementation::icd_loader::icd_for_physical_device(physicalDevice)
+        .or_else(super::forward::get_icd_if_enabled);
+    if let Some(f) = icd.as_ref().and_then(|icd| icd.get_physical_device_properties2) {
+        icd_call!("vkGetPhysicalDeviceProperties2", f(physicalDevice, pProperties));
+        return;
+    }
+
+    kronos_log!(Debug, "[vkGetPhysicalDeviceProperties2] Falling back to vkGetPhysicalDeviceProperties");
+    vkGetPhysicalDeviceProperties(physicalDevice, (*pProperties).properties.as_mut_ptr() as *mut VkPhysicalDeviceProperties);
+}
+
+/// Get physical device features
+///
+/// Like the driver, this writes the full Vulkan 1.0 structure of
+/// [`VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT`] members, which C callers pass.
+/// Rust callers query into a buffer of that size and take the compute
+/// subset with [`VkPhysicalDeviceFeatures::from_full`].
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice obtained from vkEnumeratePhysicalDevices
+// 2. pFeatures points to valid memory for the full Vulkan 1.0
+//    VkPhysicalDeviceFeatures structure
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetPhysicalDeviceFeatures(
+    physicalDevice: VkPhysicalDevice,
+    pFeatures: *mut VkPhysicalDeviceFeatures,
+) {
+    if physicalDevice.is_null() || pFeatures.is_null() {
+        return;
+    }
+    let f = match crate::implementation::icd_loader::icd_for_physical_device(physicalDevice) {
+        Some(icd) => icd.get_physical_device_features,
+        None => super::forward::get_icd_if_enabled().and_then(|icd| icd.get_physical_device_features),
+    };
+    if let Some(f) = f {
+        icd_call!("vkGetPhysicalDeviceFeatures", f(physicalDevice, pFeatures));
+    } else {
+        kronos_log!(Warn, "[vkGetPhysicalDeviceFeatures] No ICD provides vkGetPhysicalDeviceFeatures");
     }
 }
 
@@ -246,7 +454,7 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceProperties(
 // SAFETY: This function is called from C code. Caller must ensure:
 // 1. physicalDevice is a valid VkPhysicalDevice obtained from vkEnumeratePhysicalDevices
 // 2. pMemoryProperties points to valid memory for a VkPhysicalDeviceMemoryProperties structure
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetPhysicalDeviceMemoryProperties(
     physicalDevice: VkPhysicalDevice,
     pMemoryProperties: *mut VkPhysicalDeviceMemoryProperties,
@@ -255,16 +463,45 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceMemoryProperties(
         return;
     }
     if let Some(icd) = crate::implementation::icd_loader::icd_for_physical_device(physicalDevice) {
-        if let Some(f) = icd.get_physical_device_memory_properties { f(physicalDevice, pMemoryProperties); }
+        if let Some(f) = icd.get_physical_device_memory_properties { icd_call!("vkGetPhysicalDeviceMemoryProperties", f(physicalDevice, pMemoryProperties)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(f) = icd.get_physical_device_memory_properties { f(physicalDevice, pMemoryProperties); }
+        if let Some(f) = icd.get_physical_device_memory_properties { icd_call!("vkGetPhysicalDeviceMemoryProperties", f(physicalDevice, pMemoryProperties)); }
+    }
+}
+
+/// Enumerate the device extensions an ICD exposes for a physical device
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice obtained from vkEnumeratePhysicalDevices
+// 2. pLayerName is null or a valid null-terminated string
+// 3. pPropertyCount points to a valid u32
+// 4. pProperties is null or points to at least *pPropertyCount VkExtensionProperties
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkEnumerateDeviceExtensionProperties(
+    physicalDevice: VkPhysicalDevice,
+    pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> VkResult {
+    if physicalDevice.is_null() || pPropertyCount.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    let f = match crate::implementation::icd_loader::icd_for_physical_device(physicalDevice) {
+        Some(icd) => icd.enumerate_device_extension_properties,
+        None => super::forward::get_icd_if_enabled().and_then(|icd| icd.enumerate_device_extension_properties),
+    };
+    match f {
+        Some(f) => icd_call!("vkEnumerateDeviceExtensionProperties", f(physicalDevice, pLayerName, pPropertyCount, pProperties)),
+        None => {
+            *pPropertyCount = 0;
+            VkResult::Success
+        }
     }
 }
 
 /// Get physical device queue family properties
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties(
     physicalDevice: VkPhysicalDevice,
     pQueueFamilyPropertyCount: *mut u32,
@@ -275,22 +512,111 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties(
     }
     // Try to route by physical device ownership first
     if let Some(icd) = crate::implementation::icd_loader::icd_for_physical_device(physicalDevice) {
-        log::debug!("[vkGetPhysicalDeviceQueueFamilyProperties] Found ICD for physical device");
+        kronos_log!(Debug, "[vkGetPhysicalDeviceQueueFamilyProperties] Found ICD for physical device");
         if let Some(f) = icd.get_physical_device_queue_family_properties { 
-            f(physicalDevice, pQueueFamilyPropertyCount, pQueueFamilyProperties); 
+            icd_call!("vkGetPhysicalDeviceQueueFamilyProperties", f(physicalDevice, pQueueFamilyPropertyCount, pQueueFamilyProperties)); 
         }
         return;
     }
     // Fallback to single ICD
-    log::debug!("[vkGetPhysicalDeviceQueueFamilyProperties] Using fallback single ICD");
+    kronos_log!(Debug, "[vkGetPhysicalDeviceQueueFamilyProperties] Using fallback single ICD");
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(f) = icd.get_physical_device_queue_family_properties { 
-            log::debug!("[vkGetPhysicalDeviceQueueFamilyProperties] Calling ICD function");
-            f(physicalDevice, pQueueFamilyPropertyCount, pQueueFamilyProperties); 
+            kronos_log!(Debug, "[vkGetPhysicalDeviceQueueFamilyProperties] Calling ICD function");
+            icd_call!("vkGetPhysicalDeviceQueueFamilyProperties", f(physicalDevice, pQueueFamilyPropertyCount, pQueueFamilyProperties)); 
         } else {
-            log::warn!("[vkGetPhysicalDeviceQueueFamilyProperties] Function pointer is null");
+            kronos_log!(Warn, "[vkGetPhysicalDeviceQueueFamilyProperties] Function pointer is null");
         }
     } else {
-        log::warn!("[vkGetPhysicalDeviceQueueFamilyProperties] No ICD available");
+        kronos_log!(Warn, "[vkGetPhysicalDeviceQueueFamilyProperties] No ICD available");
+    }
+}
+
+/// Get physical device queue family properties with extension chains
+///
+/// ICDs without the Vulkan 1.1 query are answered from the 1.0 one; the
+/// structures chained to each element are then left as the caller
+/// initialised them.
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice
+// 2. pQueueFamilyPropertyCount points to a valid u32
+// 3. pQueueFamilyProperties is null or points to *pQueueFamilyPropertyCount
+//    structures with sType and pNext initialised
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties2(
+    physicalDevice: VkPhysicalDevice,
+    pQueueFamilyPropertyCount: *mut u32,
+    pQueueFamilyProperties: *mut VkQueueFamilyProperties2,
+) {
+    if physicalDevice.is_null() || pQueueFamilyPropertyCount.is_null() {
+        return;
+    }
+    let icd = crate::implementation::icd_loader::icd_for_physical_device(physicalDevice)
+        .or_else(super::forward::get_icd_if_enabled);
+    if let Some(f) = icd.as_ref().and_then(|icd| icd.get_physical_device_queue_family_properties2) {
+        icd_call!("vkGetPhysicalDeviceQueueFamilyProperties2", f(physicalDevice, pQueueFamilyPropertyCount, pQueueFamilyProperties));
+        return;
+    }
+
+    kronos_log!(Debug, "[vkGetPhysicalDeviceQueueFamilyProperties2] Falling back to vkGetPhysicalDeviceQueueFamilyProperties");
+    if pQueueFamilyProperties.is_null() {
+        vkGetPhysicalDeviceQueueFamilyProperties(physicalDevice, pQueueFamilyPropertyCount, ptr::null_mut());
+        return;
+    }
+    let mut families = vec![VkQueueFamilyProperties::default(); *pQueueFamilyPropertyCount as usize];
+    vkGetPhysicalDeviceQueueFamilyProperties(physicalDevice, pQueueFamilyPropertyCount, families.as_mut_ptr());
+    for (index, family) in families.iter().take(*pQueueFamilyPropertyCount as usize).enumerate() {
+        (*pQueueFamilyProperties.add(index)).queueFamilyProperties = *family;
+    }
+}
+
+/// Create a debug messenger
+///
+/// Needs VK_EXT_debug_utils enabled on the instance; not available in
+/// aggregated mode.
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. instance is a valid VkInstance with VK_EXT_debug_utils enabled
+// 2. pCreateInfo points to a valid VkDebugUtilsMessengerCreateInfoEXT whose
+//    callback may be called from any thread until the messenger is destroyed
+// 3. pMessenger points to valid memory for writing the handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateDebugUtilsMessengerEXT(
+    instance: VkInstance,
+    pCreateInfo: *const VkDebugUtilsMessengerCreateInfoEXT,
+    pAllocator: *const VkAllocationCallbacks,
+    pMessenger: *mut VkDebugUtilsMessengerEXT,
+) -> VkResult {
+    if instance.is_null() || pCreateInfo.is_null() || pMessenger.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    if crate::implementation::icd_loader::aggregated_mode_enabled() {
+        return VkResult::ErrorExtensionNotPresent;
+    }
+    if let Some(icd) = super::icd_loader::icd_for_instance(instance) {
+        if let Some(create) = icd.create_debug_utils_messenger {
+            return icd_call!("vkCreateDebugUtilsMessengerEXT", create(instance, pCreateInfo, pAllocator, pMessenger));
+        }
+    }
+    VkResult::ErrorExtensionNotPresent
+}
+
+/// Destroy a debug messenger
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. instance is the VkInstance messenger was created on
+// 2. messenger is null or a messenger not yet destroyed
+// 3. pAllocator matches the allocator used at creation (or both are null)
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroyDebugUtilsMessengerEXT(
+    instance: VkInstance,
+    messenger: VkDebugUtilsMessengerEXT,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if instance.is_null() || messenger.is_null() {
+        return;
+    }
+    if let Some(icd) = super::icd_loader::icd_for_instance(instance) {
+        if let Some(destroy) = icd.destroy_debug_utils_messenger {
+            icd_call!("vkDestroyDebugUtilsMessengerEXT", destroy(instance, messenger, pAllocator));
+        }
     }
 }
@@ -0,0 +1,177 @@
+//! Verbosity and redaction of Kronos's own log output
+//!
+//! The loader reports every manifest, library path and search directory it
+//! looks at, which is useful while setting up a machine and a leak of
+//! environment details in a production service. [`LogVerbosity`] decides
+//! which of these messages reach the `log` facade; in
+//! [`LogVerbosity::Quiet`] only warnings and errors are emitted and paths
+//! in them are reduced to their file names.
+//!
+//! The verbosity is read from `KRONOS_LOG` (`quiet`, `normal`, `debug` or
+//! `trace`) and can be set with [`set_log_verbosity`] or
+//! `ContextBuilder::log_verbosity`. It filters before the application's
+//! logger, so a message must pass both.
+//!
+//! The last [`LOG_HISTORY_CAPACITY`] messages that pass are also kept in
+//! memory for `report::bug_report_string`, whether or not the application
+//! installed a logger.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use log::Level;
+
+/// How much Kronos logs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogVerbosity {
+    /// Warnings and errors only, with user paths redacted
+    Quiet,
+    /// Informational messages, including driver paths
+    #[default]
+    Normal,
+    /// Loader and device registration details
+    Debug,
+    /// Every handle lookup
+    Trace,
+}
+
+impl LogVerbosity {
+    /// Most detailed level logged at this verbosity
+    pub fn max_level(self) -> Level {
+        match self {
+            Self::Quiet => Level::Warn,
+            Self::Normal => Level::Info,
+            Self::Debug => Level::Debug,
+            Self::Trace => Level::Trace,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "quiet" => Some(Self::Quiet),
+            "normal" => Some(Self::Normal),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+}
+
+/// Verbosity + 1, or 0 while `KRONOS_LOG` has not been read
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+const LEVELS: [LogVerbosity; 4] = [LogVerbosity::Quiet, LogVerbosity::Normal, LogVerbosity::Debug, LogVerbosity::Trace];
+
+/// Set how much Kronos logs, overriding `KRONOS_LOG`
+pub fn set_log_verbosity(verbosity: LogVerbosity) {
+    VERBOSITY.store(verbosity as u8 + 1, Ordering::Relaxed);
+}
+
+/// Current verbosity
+pub fn log_verbosity() -> LogVerbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => {
+            let verbosity = env::var("KRONOS_LOG").ok().and_then(|v| LogVerbosity::parse(&v)).unwrap_or_default();
+            let _ = VERBOSITY.compare_exchange(0, verbosity as u8 + 1, Ordering::Relaxed, Ordering::Relaxed);
+            verbosity
+        }
+        stored => LEVELS[stored as usize - 1],
+    }
+}
+
+/// Whether Kronos logs messages at `level`
+pub fn enabled(level: Level) -> bool {
+    level <= log_verbosity().max_level()
+}
+
+/// Messages kept by [`remember`]
+pub const LOG_HISTORY_CAPACITY: usize = 100;
+
+static HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Keep a logged message for bug reports, dropping the oldest
+pub fn remember(level: Level, message: fmt::Arguments<'_>) {
+    let mut history = HISTORY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if history.len() == LOG_HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(format!("[{}] {}", level, message));
+}
+
+/// The remembered messages, oldest first
+pub fn recent_messages() -> Vec<String> {
+    HISTORY.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().cloned().collect()
+}
+
+/// A path as it may appear in the log
+pub struct Redacted<'a>(&'a Path);
+
+/// Display `path` in full, or only its file name in quiet mode
+pub fn redact(path: &Path) -> Redacted<'_> {
+    Redacted(path)
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if log_verbosity() > LogVerbosity::Quiet {
+            return write!(f, "{}", self.0.display());
+        }
+        match self.0.file_name() {
+            Some(name) => write!(f, "<redacted>/{}", name.to_string_lossy()),
+            None => f.write_str("<redacted>"),
+        }
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// Display a list of paths, each as by [`redact`]
+pub fn redact_all(paths: &[PathBuf]) -> Vec<Redacted<'_>> {
+    paths.iter().map(|path| redact(path)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbosity_levels() {
+        assert_eq!(LogVerbosity::parse(" Quiet "), Some(LogVerbosity::Quiet));
+        assert_eq!(LogVerbosity::parse("loud"), None);
+        assert!(LogVerbosity::Quiet.max_level() < LogVerbosity::Normal.max_level());
+        assert_eq!(LogVerbosity::Trace.max_level(), Level::Trace);
+        for verbosity in LEVELS {
+            assert_eq!(LEVELS[verbosity as usize], verbosity);
+        }
+    }
+
+    #[test]
+    fn test_quiet_mode_redacts_paths() {
+        let path = Path::new("/home/alice/drivers/libvulkan_radeon.so");
+        let previous = log_verbosity();
+        set_log_verbosity(LogVerbosity::Quiet);
+        assert_eq!(redact(path).to_string(), "<redacted>/libvulkan_radeon.so");
+        assert!(!enabled(Level::Info) && enabled(Level::Warn));
+        set_log_verbosity(LogVerbosity::Normal);
+        assert_eq!(redact(path).to_string(), path.display().to_string());
+        set_log_verbosity(previous);
+    }
+
+    #[test]
+    fn test_history_keeps_the_latest_messages() {
+        for i in 0..LOG_HISTORY_CAPACITY + 1 {
+            remember(Level::Info, format_args!("history test {}", i));
+        }
+        let history = recent_messages();
+        assert_eq!(history.len(), LOG_HISTORY_CAPACITY);
+        assert!(history.contains(&format!("[INFO] history test {}", LOG_HISTORY_CAPACITY)));
+        assert!(!history.iter().any(|message| message == "[INFO] history test 0"));
+    }
+}
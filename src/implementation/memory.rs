@@ -3,6 +3,7 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use crate::implementation::child_objects;
 use crate::implementation::icd_loader;
 
 /// Allocate device memory
@@ -11,7 +12,7 @@ use crate::implementation::icd_loader;
 // 2. pAllocateInfo points to a valid VkMemoryAllocateInfo structure
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pMemory points to valid memory for writing the memory handle
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkAllocateMemory(
     device: VkDevice,
     pAllocateInfo: *const VkMemoryAllocateInfo,
@@ -21,12 +22,15 @@ pub unsafe extern "C" fn vkAllocateMemory(
     if device.is_null() || pAllocateInfo.is_null() || pMemory.is_null() {
         return VkResult::ErrorInitializationFailed;
     }
+    if let Some(result) = crate::testing::injected_failure(crate::testing::Site::Allocate) {
+        return result;
+    }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.allocate_memory { return f(device, pAllocateInfo, pAllocator, pMemory); }
+        if let Some(f) = icd.allocate_memory { return child_objects::created(device, icd_call!("vkAllocateMemory", f(device, pAllocateInfo, pAllocator, pMemory)), pMemory, 1); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(allocate_memory) = icd.allocate_memory { return allocate_memory(device, pAllocateInfo, pAllocator, pMemory); }
+        if let Some(allocate_memory) = icd.allocate_memory { return child_objects::created(device, icd_call!("vkAllocateMemory", allocate_memory(device, pAllocateInfo, pAllocator, pMemory)), pMemory, 1); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -37,7 +41,7 @@ pub unsafe extern "C" fn vkAllocateMemory(
 // 2. memory is a valid VkDeviceMemory allocated with vkAllocateMemory
 // 3. pAllocator matches the allocator used in vkAllocateMemory (or both are null)
 // 4. The memory is not currently mapped
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkFreeMemory(
     device: VkDevice,
     memory: VkDeviceMemory,
@@ -46,13 +50,14 @@ pub unsafe extern "C" fn vkFreeMemory(
     if device.is_null() || memory.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, memory);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.free_memory { f(device, memory, pAllocator); }
+        if let Some(f) = icd.free_memory { icd_call!("vkFreeMemory", f(device, memory, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(free_memory) = icd.free_memory { free_memory(device, memory, pAllocator); }
+        if let Some(free_memory) = icd.free_memory { icd_call!("vkFreeMemory", free_memory(device, memory, pAllocator)); }
     }
 }
 
@@ -63,7 +68,7 @@ pub unsafe extern "C" fn vkFreeMemory(
 // 3. offset and size are within the allocated memory range
 // 4. ppData points to valid memory for writing the mapped pointer
 // 5. The memory is not already mapped
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkMapMemory(
     device: VkDevice,
     memory: VkDeviceMemory,
@@ -75,12 +80,15 @@ pub unsafe extern "C" fn vkMapMemory(
     if device.is_null() || memory.is_null() || ppData.is_null() {
         return VkResult::ErrorInitializationFailed;
     }
+    if let Some(result) = crate::testing::injected_failure(crate::testing::Site::Map) {
+        return result;
+    }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.map_memory { return f(device, memory, offset, size, flags, ppData); }
+        if let Some(f) = icd.map_memory { return icd_call!("vkMapMemory", f(device, memory, offset, size, flags, ppData)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(map_memory) = icd.map_memory { return map_memory(device, memory, offset, size, flags, ppData); }
+        if let Some(map_memory) = icd.map_memory { return icd_call!("vkMapMemory", map_memory(device, memory, offset, size, flags, ppData)); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -90,7 +98,7 @@ pub unsafe extern "C" fn vkMapMemory(
 // 1. device is a valid VkDevice
 // 2. memory is a valid VkDeviceMemory that is currently mapped
 // 3. Any host writes to the mapped memory are complete
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkUnmapMemory(
     device: VkDevice,
     memory: VkDeviceMemory,
@@ -100,10 +108,96 @@ pub unsafe extern "C" fn vkUnmapMemory(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.unmap_memory { f(device, memory); }
+        if let Some(f) = icd.unmap_memory { icd_call!("vkUnmapMemory", f(device, memory)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(unmap_memory) = icd.unmap_memory { unmap_memory(device, memory); }
+        if let Some(unmap_memory) = icd.unmap_memory { icd_call!("vkUnmapMemory", unmap_memory(device, memory)); }
+    }
+}
+
+/// Make device writes to mapped, non-coherent memory visible to the host
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. pMemoryRanges points to memoryRangeCount valid VkMappedMemoryRange structures
+// 3. Each range lies within memory that is currently mapped
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkInvalidateMappedMemoryRanges(
+    device: VkDevice,
+    memoryRangeCount: u32,
+    pMemoryRanges: *const VkMappedMemoryRange,
+) -> VkResult {
+    if device.is_null() || (memoryRangeCount > 0 && pMemoryRanges.is_null()) {
+        return VkResult::ErrorInitializationFailed;
+    }
+    
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.invalidate_mapped_memory_ranges {
+            return icd_call!("vkInvalidateMappedMemoryRanges", f(device, memoryRangeCount, pMemoryRanges));
+        }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(invalidate) = icd.invalidate_mapped_memory_ranges {
+            return icd_call!("vkInvalidateMappedMemoryRanges", invalidate(device, memoryRangeCount, pMemoryRanges));
+        }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Query the memory types an external file descriptor can be imported into
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice with VK_KHR_external_memory_fd enabled
+// 2. fd is a valid handle of type handleType
+// 3. pMemoryFdProperties points to a valid VkMemoryFdPropertiesKHR structure
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetMemoryFdPropertiesKHR(
+    device: VkDevice,
+    handleType: VkExternalMemoryHandleTypeFlags,
+    fd: std::ffi::c_int,
+    pMemoryFdProperties: *mut VkMemoryFdPropertiesKHR,
+) -> VkResult {
+    if device.is_null() || pMemoryFdProperties.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.get_memory_fd_properties {
+            return icd_call!("vkGetMemoryFdPropertiesKHR", f(device, handleType, fd, pMemoryFdProperties));
+        }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(get_properties) = icd.get_memory_fd_properties {
+            return icd_call!("vkGetMemoryFdPropertiesKHR", get_properties(device, handleType, fd, pMemoryFdProperties));
+        }
+    }
+    VkResult::ErrorExtensionNotPresent
+}
+
+/// Export device memory as a file descriptor
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice with VK_KHR_external_memory_fd enabled
+// 2. pGetFdInfo points to a valid VkMemoryGetFdInfoKHR naming memory allocated
+//    exportable as its handleType
+// 3. pFd points to valid memory for writing the file descriptor
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetMemoryFdKHR(
+    device: VkDevice,
+    pGetFdInfo: *const VkMemoryGetFdInfoKHR,
+    pFd: *mut std::ffi::c_int,
+) -> VkResult {
+    if device.is_null() || pGetFdInfo.is_null() || pFd.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.get_memory_fd {
+            return icd_call!("vkGetMemoryFdKHR", f(device, pGetFdInfo, pFd));
+        }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(get_memory_fd) = icd.get_memory_fd {
+            return icd_call!("vkGetMemoryFdKHR", get_memory_fd(device, pGetFdInfo, pFd));
+        }
     }
+    VkResult::ErrorExtensionNotPresent
 }
@@ -0,0 +1,1839 @@
+//! In-process mock ICD for deterministic tests
+//!
+//! [`MockIcd::install`] registers a fake driver as the only ICD, so the
+//! loader, batching and pool code paths can run in CI without a GPU. The
+//! driver implements the entry points Kronos uses for buffers, descriptors,
+//! pipelines, command buffers and synchronization. Memory is backed by host
+//! allocations and `vkCmdCopyBuffer` is carried out at submit time;
+//! dispatches are recorded but no shader code runs. Dispatches inside a
+//! conditional rendering block only count as run if their predicate passes
+//! at submit time.
+//!
+//! Behavior is scriptable: any entry point can be made to fail on its Nth
+//! call, fences can be delayed, and the reported device is configured
+//! through [`MockConfig`]. [`MockConfig::virtual_devices`] reports several
+//! devices with distinct vendor IDs and limits, so multi-GPU selection and
+//! splitting logic can be tested on machines with one GPU or none.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use crate::sys::*;
+use crate::core::*;
+use crate::ffi::*;
+use crate::implementation::icd_loader;
+use crate::implementation::error::IcdError;
+
+/// Name the mock ICD reports in place of a library path
+pub const MOCK_ICD_NAME: &str = "<mock-icd>";
+
+/// Handle of the first physical device; later ones follow consecutively
+const MOCK_PHYSICAL_DEVICE: u64 = 0x1;
+
+/// Vendor IDs given to virtual devices, in order; later devices get IDs
+/// from the Khronos vendor ID range
+const VIRTUAL_VENDOR_IDS: &[u32] = &[0x10DE, 0x1002, 0x8086, 0x106B, 0x13B5, 0x5143];
+const MOCK_MEMORY_ALIGNMENT: VkDeviceSize = 256;
+
+/// Device the mock ICD reports
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// Version returned by `vkEnumerateInstanceVersion`
+    pub api_version: u32,
+    pub properties: VkPhysicalDeviceProperties,
+    /// Properties of further physical devices, enumerated after the one
+    /// above; they share the rest of the configuration
+    pub additional_devices: Vec<VkPhysicalDeviceProperties>,
+    pub features: VkPhysicalDeviceFeatures,
+    pub queue_families: Vec<VkQueueFamilyProperties>,
+    pub memory_properties: VkPhysicalDeviceMemoryProperties,
+    /// Device extensions reported by `vkEnumerateDeviceExtensionProperties`
+    pub extensions: Vec<String>,
+    /// Instance extensions reported by `vkEnumerateInstanceExtensionProperties`
+    pub instance_extensions: Vec<String>,
+    /// Instance layers reported by `vkEnumerateInstanceLayerProperties`,
+    /// as a loader would
+    pub layers: Vec<String>,
+    /// Install the mock as a portability driver, like MoltenVK
+    pub portability_driver: bool,
+    /// Global priorities of each queue family, reported through
+    /// `vkGetPhysicalDeviceQueueFamilyProperties2`
+    pub global_priorities: Vec<Vec<VkQueueGlobalPriorityKHR>>,
+    /// Driver reported through VK_KHR_driver_properties, if any
+    pub driver: Option<MockDriver>,
+    /// `deviceUUID` of the first device; later ones differ in the last byte
+    pub device_uuid: [u8; VK_UUID_SIZE],
+    /// PCI domain, bus, device and function of the first device, reported
+    /// through VK_EXT_pci_bus_info; later devices sit on the following buses
+    pub pci_bus: Option<[u32; 4]>,
+    /// Creation time each pipeline reports through
+    /// VK_EXT_pipeline_creation_feedback, if the extension is exposed
+    pub pipeline_creation_time: Option<Duration>,
+    /// Time between a submission and the signal of its fence
+    pub fence_delay: Duration,
+}
+
+/// Driver identification the mock ICD reports
+#[derive(Debug, Clone)]
+pub struct MockDriver {
+    pub id: VkDriverId,
+    pub name: String,
+    pub info: String,
+    pub conformance_version: VkConformanceVersion,
+}
+
+impl MockConfig {
+    /// Set the reported device name, truncated to fit `deviceName`
+    pub fn device_name(mut self, name: &str) -> Self {
+        write_c_string(&mut self.properties.deviceName, name);
+        self
+    }
+
+    /// Set the reported PCI vendor ID
+    pub fn vendor_id(mut self, vendor_id: u32) -> Self {
+        self.properties.vendorID = vendor_id;
+        self
+    }
+
+    /// Enumerate `count` physical devices in total, for multi-GPU logic
+    ///
+    /// The configured device comes first. Each virtual device after it has
+    /// its own vendor and device ID and name, and a larger shared memory
+    /// limit than the one before it.
+    pub fn virtual_devices(mut self, count: usize) -> Self {
+        let base = self.properties;
+        self.additional_devices = (1..count)
+            .map(|index| {
+                let mut properties = base;
+                properties.vendorID = VIRTUAL_VENDOR_IDS.get(index).copied().unwrap_or(0x10000 + index as u32);
+                properties.deviceID = base.deviceID + index as u32;
+                properties.limits.maxComputeSharedMemorySize = base.limits.maxComputeSharedMemorySize * (index as u32 + 1);
+                write_c_string(&mut properties.deviceName, &format!("Kronos Virtual Device {}", index));
+                properties
+            })
+            .collect();
+        self
+    }
+
+    /// Properties of the physical device `handle`, if the mock reports it
+    fn device_properties(&self, handle: VkPhysicalDevice) -> Option<&VkPhysicalDeviceProperties> {
+        match handle.as_raw().checked_sub(MOCK_PHYSICAL_DEVICE)? {
+            0 => Some(&self.properties),
+            index => self.additional_devices.get(index as usize - 1),
+        }
+    }
+
+    /// Identify as driver `id` through VK_KHR_driver_properties, which is
+    /// exposed
+    pub fn driver(mut self, id: VkDriverId, name: &str, info: &str) -> Self {
+        self.driver = Some(MockDriver {
+            id,
+            name: name.to_string(),
+            info: info.to_string(),
+            conformance_version: VkConformanceVersion { major: 1, minor: 3, subminor: 0, patch: 0 },
+        });
+        if !self.extensions.iter().any(|name| name == VK_KHR_DRIVER_PROPERTIES_EXTENSION_NAME) {
+            self.extensions.push(VK_KHR_DRIVER_PROPERTIES_EXTENSION_NAME.to_string());
+        }
+        self
+    }
+
+    /// Report the PCI address `domain:bus:device.function` through
+    /// VK_EXT_pci_bus_info, which is exposed
+    pub fn pci_bus(mut self, domain: u32, bus: u32, device: u32, function: u32) -> Self {
+        self.pci_bus = Some([domain, bus, device, function]);
+        if !self.extensions.iter().any(|name| name == VK_EXT_PCI_BUS_INFO_EXTENSION_NAME) {
+            self.extensions.push(VK_EXT_PCI_BUS_INFO_EXTENSION_NAME.to_string());
+        }
+        self
+    }
+
+    /// Report every pipeline as created in `duration` through
+    /// VK_EXT_pipeline_creation_feedback, which is exposed
+    pub fn pipeline_creation_feedback(mut self, duration: Duration) -> Self {
+        self.pipeline_creation_time = Some(duration);
+        if !self.extensions.iter().any(|name| name == VK_EXT_PIPELINE_CREATION_FEEDBACK_EXTENSION_NAME) {
+            self.extensions.push(VK_EXT_PIPELINE_CREATION_FEEDBACK_EXTENSION_NAME.to_string());
+        }
+        self
+    }
+
+    /// Act as a loader that found the Khronos validation layer: report the
+    /// layer, with VK_EXT_validation_features, and VK_EXT_debug_utils
+    pub fn validation_layer(mut self) -> Self {
+        if !self.layers.iter().any(|name| name == VK_LAYER_KHRONOS_VALIDATION_NAME) {
+            self.layers.push(VK_LAYER_KHRONOS_VALIDATION_NAME.to_string());
+        }
+        if !self.instance_extensions.iter().any(|name| name == VK_EXT_DEBUG_UTILS_EXTENSION_NAME) {
+            self.instance_extensions.push(VK_EXT_DEBUG_UTILS_EXTENSION_NAME.to_string());
+        }
+        self
+    }
+
+    /// Report the global priorities of queue family `family` and expose
+    /// VK_KHR_global_priority
+    pub fn global_priorities(mut self, family: usize, priorities: &[VkQueueGlobalPriorityKHR]) -> Self {
+        if self.global_priorities.len() <= family {
+            self.global_priorities.resize(family + 1, Vec::new());
+        }
+        self.global_priorities[family] = priorities.to_vec();
+        if !self.extensions.iter().any(|name| name == VK_KHR_GLOBAL_PRIORITY_EXTENSION_NAME) {
+            self.extensions.push(VK_KHR_GLOBAL_PRIORITY_EXTENSION_NAME.to_string());
+        }
+        self
+    }
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        let properties = VkPhysicalDeviceProperties {
+            apiVersion: VK_API_VERSION_1_3,
+            deviceID: 0x4B52,
+            deviceType: VkPhysicalDeviceType::DiscreteGpu as i32,
+            limits: VkPhysicalDeviceLimits {
+                maxComputeSharedMemorySize: 32768,
+                maxComputeWorkGroupCount: [65535; 3],
+                maxComputeWorkGroupInvocations: 1024,
+                maxComputeWorkGroupSize: [1024, 1024, 64],
+            },
+            ..Default::default()
+        };
+
+        let mut memory_properties = VkPhysicalDeviceMemoryProperties {
+            memoryTypeCount: 3,
+            memoryHeapCount: 2,
+            ..Default::default()
+        };
+        memory_properties.memoryHeaps[0] = VkMemoryHeap { size: 1 << 30, flags: 0x1 };
+        memory_properties.memoryHeaps[1] = VkMemoryHeap { size: 1 << 30, flags: 0 };
+        memory_properties.memoryTypes[0] = VkMemoryType {
+            propertyFlags: VkMemoryPropertyFlags::DEVICE_LOCAL,
+            heapIndex: 0,
+        };
+        memory_properties.memoryTypes[1] = VkMemoryType {
+            propertyFlags: VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT,
+            heapIndex: 1,
+        };
+        memory_properties.memoryTypes[2] = VkMemoryType {
+            propertyFlags: VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_CACHED,
+            heapIndex: 1,
+        };
+
+        Self {
+            api_version: VK_API_VERSION_1_3,
+            properties,
+            additional_devices: Vec::new(),
+            features: VkPhysicalDeviceFeatures::default(),
+            queue_families: vec![VkQueueFamilyProperties {
+                queueFlags: VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER,
+                queueCount: 1,
+                timestampValidBits: 0,
+                minImageTransferGranularity: VkExtent3D::default(),
+            }],
+            memory_properties,
+            extensions: Vec::new(),
+            instance_extensions: Vec::new(),
+            layers: Vec::new(),
+            portability_driver: false,
+            global_priorities: Vec::new(),
+            driver: None,
+            device_uuid: *b"kronos-mock-gpu\0",
+            pci_bus: None,
+            pipeline_creation_time: None,
+            fence_delay: Duration::ZERO,
+        }
+        .device_name("Kronos Mock Device")
+        .vendor_id(0x1002)
+    }
+}
+
+/// A scripted failure: the `call`th call of `entry_point` returns `result`
+struct ScriptedFailure {
+    entry_point: String,
+    call: u64,
+    result: VkResult,
+}
+
+struct MockBuffer {
+    size: VkDeviceSize,
+    memory: Option<(u64, VkDeviceSize)>,
+}
+
+#[derive(Default)]
+struct MockCommandBuffer {
+    pool: u64,
+    copies: Vec<(u64, u64, Vec<VkBufferCopy>)>,
+    dispatches: u32,
+    /// Predicate of the open conditional rendering block, as (buffer,
+    /// offset, inverted)
+    predicate: Option<(u64, VkDeviceSize, bool)>,
+    /// Predicate of each dispatch recorded inside a conditional block
+    conditional: Vec<(u64, VkDeviceSize, bool)>,
+}
+
+impl MockCommandBuffer {
+    fn dispatch(&mut self) {
+        match self.predicate {
+            Some(predicate) => self.conditional.push(predicate),
+            None => self.dispatches += 1,
+        }
+    }
+}
+
+struct MockState {
+    config: MockConfig,
+    next_handle: u64,
+    calls: HashMap<String, u64>,
+    failures: Vec<ScriptedFailure>,
+    /// Live objects by handle, with their type name
+    objects: HashMap<u64, &'static str>,
+    memory: HashMap<u64, Vec<u8>>,
+    buffers: HashMap<u64, MockBuffer>,
+    command_buffers: HashMap<u64, MockCommandBuffer>,
+    /// Descriptor set to owning pool
+    descriptor_sets: HashMap<u64, u64>,
+    /// Fence to the time it signals, `None` while unsignaled
+    fences: HashMap<u64, Option<Instant>>,
+    /// (device, family, index) to queue handle
+    queues: HashMap<(u64, u32, u32), u64>,
+    /// Queue to the time its last submission completes
+    queue_idle_at: HashMap<u64, Instant>,
+    /// Timeline semaphore to the values submissions signal, and when
+    timeline_signals: HashMap<u64, Vec<(u64, Instant)>>,
+    /// Set by `unplug`: the device is lost and no longer enumerated
+    removed: bool,
+    /// Writes the next submitted dispatch makes, as (buffer, offset, bytes)
+    dispatch_writes: Vec<(u64, VkDeviceSize, Vec<u8>)>,
+    /// File descriptors imported through `VkImportMemoryFdInfoKHR`
+    imported_fds: Vec<c_int>,
+    /// Priorities chained into allocations through `VkMemoryPriorityAllocateInfoEXT`
+    memory_priorities: Vec<f32>,
+    /// Queue family ownership transfers recorded in buffer barriers, as
+    /// (buffer, source family, destination family)
+    ownership_transfers: Vec<(u64, u32, u32)>,
+    /// Flags and extensions of the last `vkCreateInstance`
+    instance_create: Option<(VkInstanceCreateFlags, Vec<String>)>,
+    /// Layers and validation features enabled by the last `vkCreateInstance`
+    instance_layers: Vec<String>,
+    validation_features: Vec<VkValidationFeatureEnableEXT>,
+    /// Debug messengers, with their callback and user data
+    messengers: HashMap<u64, (PFN_vkDebugUtilsMessengerCallbackEXT, usize)>,
+    /// Extensions enabled by the last `vkCreateDevice`, and the structures
+    /// chained into it
+    device_extensions: Vec<String>,
+    device_create_chain: Vec<VkStructureType>,
+    /// Binding and type of each write of the last `vkUpdateDescriptorSets`
+    descriptor_writes: Vec<(u32, VkDescriptorType)>,
+    /// Dynamic offsets of the last `vkCmdBindDescriptorSets`
+    dynamic_offsets: Vec<u32>,
+    /// Next fake file descriptor handed out by an export
+    next_fd: c_int,
+}
+
+impl MockState {
+    fn new(config: MockConfig) -> Self {
+        Self {
+            config,
+            next_handle: 0x1000,
+            calls: HashMap::new(),
+            failures: Vec::new(),
+            objects: HashMap::new(),
+            memory: HashMap::new(),
+            buffers: HashMap::new(),
+            command_buffers: HashMap::new(),
+            descriptor_sets: HashMap::new(),
+            fences: HashMap::new(),
+            queues: HashMap::new(),
+            queue_idle_at: HashMap::new(),
+            timeline_signals: HashMap::new(),
+            removed: false,
+            dispatch_writes: Vec::new(),
+            imported_fds: Vec::new(),
+            memory_priorities: Vec::new(),
+            ownership_transfers: Vec::new(),
+            instance_create: None,
+            instance_layers: Vec::new(),
+            validation_features: Vec::new(),
+            messengers: HashMap::new(),
+            device_extensions: Vec::new(),
+            device_create_chain: Vec::new(),
+            descriptor_writes: Vec::new(),
+            dynamic_offsets: Vec::new(),
+            next_fd: 1000,
+        }
+    }
+
+    fn create(&mut self, kind: &'static str) -> u64 {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.objects.insert(handle, kind);
+        handle
+    }
+
+    fn destroy(&mut self, handle: u64) {
+        self.objects.remove(&handle);
+    }
+
+    /// Run the scripted dispatch writes and the copies recorded into a
+    /// command buffer
+    fn execute(&mut self, command_buffer: u64) {
+        let (copies, dispatches, conditional) = match self.command_buffers.get(&command_buffer) {
+            Some(cb) => (cb.copies.clone(), cb.dispatches, cb.conditional.clone()),
+            None => return,
+        };
+        let passed = conditional
+            .iter()
+            .filter(|&&(buffer, offset, inverted)| (self.read_u32(buffer, offset) != Some(0)) != inverted)
+            .count() as u32;
+        let dispatches = dispatches + passed;
+        if dispatches > 0 {
+            for (buffer, offset, bytes) in std::mem::take(&mut self.dispatch_writes) {
+                let Some((memory, base)) = self.bound_memory(buffer) else { continue };
+                let start = (base + offset) as usize;
+                if let Some(target) = self.memory.get_mut(&memory).and_then(|m| m.get_mut(start..start + bytes.len())) {
+                    target.copy_from_slice(&bytes);
+                }
+            }
+        }
+        for (src, dst, regions) in copies {
+            let (Some(src), Some(dst)) = (self.bound_memory(src), self.bound_memory(dst)) else {
+                kronos_log!(Warn, "[mock-icd] vkCmdCopyBuffer with unbound buffer");
+                continue;
+            };
+            for region in regions {
+                let src_start = (src.1 + region.srcOffset) as usize;
+                let dst_start = (dst.1 + region.dstOffset) as usize;
+                let size = region.size as usize;
+                let bytes = match self.memory.get(&src.0).and_then(|m| m.get(src_start..src_start + size)) {
+                    Some(bytes) => bytes.to_vec(),
+                    None => continue,
+                };
+                if let Some(target) = self.memory.get_mut(&dst.0).and_then(|m| m.get_mut(dst_start..dst_start + size)) {
+                    target.copy_from_slice(&bytes);
+                }
+            }
+        }
+    }
+
+    fn bound_memory(&self, buffer: u64) -> Option<(u64, VkDeviceSize)> {
+        self.buffers.get(&buffer).and_then(|b| b.memory)
+    }
+
+    fn read_u32(&self, buffer: u64, offset: VkDeviceSize) -> Option<u32> {
+        let (memory, base) = self.bound_memory(buffer)?;
+        let start = (base + offset) as usize;
+        let bytes = self.memory.get(&memory)?.get(start..start + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref STATE: Mutex<MockState> = Mutex::new(MockState::new(MockConfig::default()));
+}
+
+fn state() -> MutexGuard<'static, MockState> {
+    STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Count a call to `entry_point` and apply any failure scripted for it
+fn enter(entry_point: &str) -> Result<MutexGuard<'static, MockState>, VkResult> {
+    let mut state = state();
+    let count = state.calls.entry(entry_point.to_string()).or_insert(0);
+    *count += 1;
+    let call = *count;
+    let failure = state
+        .failures
+        .iter()
+        .position(|f| f.entry_point == entry_point && f.call == call);
+    match failure {
+        Some(index) => Err(state.failures.remove(index).result),
+        None => Ok(state),
+    }
+}
+
+/// Handle to the installed mock ICD
+///
+/// The driver state is process-wide; installing again resets it.
+#[derive(Debug)]
+pub struct MockIcd {
+    _private: (),
+}
+
+impl MockIcd {
+    /// Install the mock as the only ICD, replacing any loaded driver
+    ///
+    /// Objects created through a previously installed ICD must not be used
+    /// afterwards.
+    pub fn install(config: MockConfig) -> Result<Self, IcdError> {
+        *state() = MockState::new(config);
+        let mut icd = icd_loader::load_icd_from_entry_point(PathBuf::from(MOCK_ICD_NAME), Some(get_instance_proc_addr))?;
+        icd.is_portability_driver = state().config.portability_driver;
+        icd_loader::install_icd(icd)?;
+        *super::ICD_INITIALIZED.lock()? = true;
+        // Each install may report a different device under the same ICD name
+        crate::api::refresh_devices();
+        Ok(Self { _private: () })
+    }
+
+    /// The mock's `vk_icdGetInstanceProcAddr`, for loading it as an
+    /// external loader with [`InitOptions::with_loader`](super::InitOptions::with_loader)
+    pub fn entry_point(&self) -> super::GetInstanceProcAddr {
+        get_instance_proc_addr
+    }
+
+    /// Make the `n`th call (1-based) of `entry_point` from now return `result`
+    pub fn fail_nth(&self, entry_point: &str, n: u64, result: VkResult) {
+        let mut state = state();
+        let call = state.calls.get(entry_point).copied().unwrap_or(0) + n.max(1);
+        state.failures.push(ScriptedFailure {
+            entry_point: entry_point.to_string(),
+            call,
+            result,
+        });
+    }
+
+    /// Make the `n`th `vkAllocateMemory` from now run out of device memory
+    pub fn fail_allocation(&self, n: u64) {
+        self.fail_nth("vkAllocateMemory", n, VkResult::ErrorOutOfDeviceMemory);
+    }
+
+    /// Delay fence signals (and queue idle) after each submission
+    pub fn set_fence_delay(&self, delay: Duration) {
+        state().config.fence_delay = delay;
+    }
+
+    /// Make the next submitted dispatch write `bytes` to `buffer` at
+    /// `offset`, standing in for a kernel's output
+    pub fn write_on_next_dispatch(&self, buffer: VkBuffer, offset: VkDeviceSize, bytes: &[u8]) {
+        state().dispatch_writes.push((buffer.as_raw(), offset, bytes.to_vec()));
+    }
+
+    /// File descriptors imported into device memory since install, in order
+    pub fn imported_fds(&self) -> Vec<c_int> {
+        state().imported_fds.clone()
+    }
+
+    /// Priorities chained into allocations since install, in order
+    pub fn memory_priorities(&self) -> Vec<f32> {
+        state().memory_priorities.clone()
+    }
+
+    /// Queue family ownership transfers recorded in buffer barriers since
+    /// install, as (buffer, source family, destination family); a release
+    /// and its acquire each appear
+    pub fn ownership_transfers(&self) -> Vec<(VkBuffer, u32, u32)> {
+        state()
+            .ownership_transfers
+            .iter()
+            .map(|&(buffer, src, dst)| (VkBuffer::from_raw(buffer), src, dst))
+            .collect()
+    }
+
+    /// Flags and enabled extensions of the last instance created
+    pub fn instance_create_info(&self) -> Option<(VkInstanceCreateFlags, Vec<String>)> {
+        state().instance_create.clone()
+    }
+
+    /// Layers enabled on the last instance created
+    pub fn instance_layers(&self) -> Vec<String> {
+        state().instance_layers.clone()
+    }
+
+    /// Validation features enabled through `VkValidationFeaturesEXT` on the
+    /// last instance created
+    pub fn validation_features(&self) -> Vec<VkValidationFeatureEnableEXT> {
+        state().validation_features.clone()
+    }
+
+    /// Pass a message to every debug messenger, as the validation layer
+    /// would; debug printf output has the ID name `WARNING-DEBUG-PRINTF`
+    pub fn emit_debug_message(&self, severity: VkDebugUtilsMessageSeverityFlagsEXT, id_name: &str, message: &str) {
+        let messengers: Vec<_> = state().messengers.values().copied().collect();
+        let id_name = CString::new(id_name).unwrap();
+        let message = CString::new(message).unwrap();
+        let data = VkDebugUtilsMessengerCallbackDataEXT {
+            pMessageIdName: id_name.as_ptr(),
+            pMessage: message.as_ptr(),
+            ..Default::default()
+        };
+        // Called without the state lock, so callbacks may call back in
+        for (callback, user_data) in messengers {
+            if let Some(callback) = callback {
+                unsafe { callback(severity, VkDebugUtilsMessageTypeFlagsEXT::VALIDATION, &data, user_data as *mut c_void) };
+            }
+        }
+    }
+
+    /// Extensions enabled on the last device created
+    pub fn device_extensions(&self) -> Vec<String> {
+        state().device_extensions.clone()
+    }
+
+    /// Types of the structures chained into the last device created
+    pub fn device_create_chain(&self) -> Vec<VkStructureType> {
+        state().device_create_chain.clone()
+    }
+
+    /// Binding and descriptor type of each write of the last
+    /// `vkUpdateDescriptorSets`
+    pub fn descriptor_writes(&self) -> Vec<(u32, VkDescriptorType)> {
+        state().descriptor_writes.clone()
+    }
+
+    /// Dynamic offsets passed to the last `vkCmdBindDescriptorSets`
+    pub fn dynamic_offsets(&self) -> Vec<u32> {
+        state().dynamic_offsets.clone()
+    }
+
+    /// Simulate surprise removal of the device
+    ///
+    /// Queue and fence operations report `ErrorDeviceLost` from now on and
+    /// the device disappears from `vkEnumeratePhysicalDevices`.
+    pub fn unplug(&self) {
+        state().removed = true;
+    }
+
+    /// Number of times `entry_point` has been called since install
+    pub fn call_count(&self, entry_point: &str) -> u64 {
+        state().calls.get(entry_point).copied().unwrap_or(0)
+    }
+
+    /// Objects created and not yet destroyed, counted by type
+    pub fn live_objects(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for kind in state().objects.values() {
+            *counts.entry(*kind).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// View a caller array, allowing null when it is empty
+unsafe fn slice<'a, T>(data: *const T, len: u32) -> &'a [T] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len as usize)
+    }
+}
+
+/// Store a new handle of `kind` in `out`, unless a failure is scripted
+unsafe fn create_handle<T>(entry_point: &str, kind: &'static str, out: *mut Handle<T>) -> VkResult {
+    if out.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    match enter(entry_point) {
+        Ok(mut state) => {
+            *out = Handle::from_raw(state.create(kind));
+            VkResult::Success
+        }
+        Err(result) => result,
+    }
+}
+
+/// Count a call to an entry point that cannot report failure
+fn count(entry_point: &str) {
+    *state().calls.entry(entry_point.to_string()).or_insert(0) += 1;
+}
+
+fn destroy_handle(entry_point: &str, handle: u64) {
+    count(entry_point);
+    state().destroy(handle);
+}
+
+/// Create/destroy pair for an object with no state beyond its handle
+macro_rules! mock_object {
+    ($create:ident, $destroy:ident, $info:ty, $handle:ty, $kind:literal) => {
+        unsafe extern "C" fn $create(
+            _device: VkDevice,
+            _pCreateInfo: *const $info,
+            _pAllocator: *const VkAllocationCallbacks,
+            pHandle: *mut $handle,
+        ) -> VkResult {
+            create_handle(concat!("vkCreate", $kind), concat!("Vk", $kind), pHandle)
+        }
+
+        unsafe extern "C" fn $destroy(_device: VkDevice, handle: $handle, _pAllocator: *const VkAllocationCallbacks) {
+            destroy_handle(concat!("vkDestroy", $kind), handle.as_raw());
+        }
+    };
+}
+
+// ===== Global and instance functions =====
+
+unsafe extern "C" fn get_instance_proc_addr(_instance: VkInstance, pName: *const c_char) -> PFN_vkVoidFunction {
+    lookup(pName)
+}
+
+unsafe extern "C" fn get_device_proc_addr(_device: VkDevice, pName: *const c_char) -> PFN_vkVoidFunction {
+    if pName.is_null() {
+        return None;
+    }
+    // Like drivers, only resolve the commands the device's version or
+    // enabled extensions provide
+    let available = {
+        let state = state();
+        match CStr::from_ptr(pName).to_bytes() {
+            b"vkGetSemaphoreCounterValue" => state.config.properties.apiVersion >= VK_API_VERSION_1_2,
+            b"vkGetSemaphoreCounterValueKHR" => {
+                state.device_extensions.iter().any(|name| name == VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME)
+            }
+            _ => true,
+        }
+    };
+    if !available {
+        return None;
+    }
+    lookup(pName)
+}
+
+unsafe extern "C" fn enumerate_instance_version(pApiVersion: *mut u32) -> VkResult {
+    match enter("vkEnumerateInstanceVersion") {
+        Ok(state) => {
+            *pApiVersion = state.config.api_version;
+            VkResult::Success
+        }
+        Err(result) => result,
+    }
+}
+
+unsafe extern "C" fn enumerate_instance_extension_properties(
+    pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> VkResult {
+    let state = match enter("vkEnumerateInstanceExtensionProperties") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    if pLayerName.is_null() {
+        return write_extensions(&state.config.instance_extensions, pPropertyCount, pProperties);
+    }
+    let layer = CStr::from_ptr(pLayerName).to_string_lossy();
+    if !state.config.layers.iter().any(|name| *name == layer) {
+        return VkResult::ErrorLayerNotPresent;
+    }
+    let extensions = if layer == VK_LAYER_KHRONOS_VALIDATION_NAME {
+        vec![VK_EXT_DEBUG_UTILS_EXTENSION_NAME.to_string(), VK_EXT_VALIDATION_FEATURES_EXTENSION_NAME.to_string()]
+    } else {
+        Vec::new()
+    };
+    write_extensions(&extensions, pPropertyCount, pProperties)
+}
+
+unsafe extern "C" fn enumerate_instance_layer_properties(pPropertyCount: *mut u32, pProperties: *mut VkLayerProperties) -> VkResult {
+    let state = match enter("vkEnumerateInstanceLayerProperties") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let layers = &state.config.layers;
+    if pProperties.is_null() {
+        *pPropertyCount = layers.len() as u32;
+        return VkResult::Success;
+    }
+    let count = (*pPropertyCount as usize).min(layers.len());
+    for (index, name) in layers.iter().take(count).enumerate() {
+        let mut properties = VkLayerProperties { specVersion: VK_API_VERSION_1_3, implementationVersion: 1, ..Default::default() };
+        write_c_string(&mut properties.layerName, name);
+        *pProperties.add(index) = properties;
+    }
+    *pPropertyCount = count as u32;
+    if count < layers.len() {
+        VkResult::Incomplete
+    } else {
+        VkResult::Success
+    }
+}
+
+/// Names of the extensions a create info enables
+unsafe fn enabled_extensions(count: u32, names: *const PtrCStr) -> Vec<String> {
+    slice(names, count).iter().map(|name| CStr::from_ptr(*name).to_string_lossy().into_owned()).collect()
+}
+
+unsafe extern "C" fn create_instance(
+    pCreateInfo: *const VkInstanceCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pInstance: *mut VkInstance,
+) -> VkResult {
+    let result = create_handle("vkCreateInstance", "VkInstance", pInstance);
+    if result == VkResult::Success {
+        let info = &*pCreateInfo;
+        let extensions = enabled_extensions(info.enabledExtensionCount, info.ppEnabledExtensionNames);
+        let layers = enabled_extensions(info.enabledLayerCount, info.ppEnabledLayerNames);
+        let mut validation_features = Vec::new();
+        let mut next = info.pNext as *const VkBaseOutStructure;
+        while let Some(link) = next.as_ref() {
+            if link.sType == VkStructureType::ValidationFeaturesEXT {
+                let features = &*(next as *const VkValidationFeaturesEXT);
+                validation_features = slice(features.pEnabledValidationFeatures, features.enabledValidationFeatureCount).to_vec();
+            }
+            next = link.pNext;
+        }
+        let mut state = state();
+        state.instance_create = Some((info.flags, extensions));
+        state.instance_layers = layers;
+        state.validation_features = validation_features;
+    }
+    result
+}
+
+unsafe extern "C" fn create_debug_utils_messenger(
+    _instance: VkInstance,
+    pCreateInfo: *const VkDebugUtilsMessengerCreateInfoEXT,
+    _pAllocator: *const VkAllocationCallbacks,
+    pMessenger: *mut VkDebugUtilsMessengerEXT,
+) -> VkResult {
+    let result = create_handle("vkCreateDebugUtilsMessengerEXT", "VkDebugUtilsMessengerEXT", pMessenger);
+    if result == VkResult::Success {
+        let info = &*pCreateInfo;
+        state().messengers.insert((*pMessenger).as_raw(), (info.pfnUserCallback, info.pUserData as usize));
+    }
+    result
+}
+
+unsafe extern "C" fn destroy_debug_utils_messenger(
+    _instance: VkInstance,
+    messenger: VkDebugUtilsMessengerEXT,
+    _pAllocator: *const VkAllocationCallbacks,
+) {
+    destroy_handle("vkDestroyDebugUtilsMessengerEXT", messenger.as_raw());
+    state().messengers.remove(&messenger.as_raw());
+}
+
+unsafe extern "C" fn destroy_instance(instance: VkInstance, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkDestroyInstance", instance.as_raw());
+}
+
+unsafe extern "C" fn enumerate_physical_devices(
+    _instance: VkInstance,
+    pPhysicalDeviceCount: *mut u32,
+    pPhysicalDevices: *mut VkPhysicalDevice,
+) -> VkResult {
+    let total = match enter("vkEnumeratePhysicalDevices") {
+        Ok(state) if state.removed => 0,
+        Ok(state) => 1 + state.config.additional_devices.len(),
+        Err(result) => return result,
+    };
+    if pPhysicalDevices.is_null() {
+        *pPhysicalDeviceCount = total as u32;
+        return VkResult::Success;
+    }
+    let count = (*pPhysicalDeviceCount as usize).min(total);
+    for index in 0..count {
+        *pPhysicalDevices.add(index) = VkPhysicalDevice::from_raw(MOCK_PHYSICAL_DEVICE + index as u64);
+    }
+    *pPhysicalDeviceCount = count as u32;
+    if count < total {
+        VkResult::Incomplete
+    } else {
+        VkResult::Success
+    }
+}
+
+unsafe extern "C" fn get_physical_device_properties(
+    physicalDevice: VkPhysicalDevice,
+    pProperties: *mut VkPhysicalDeviceProperties,
+) {
+    if let Ok(state) = enter("vkGetPhysicalDeviceProperties") {
+        if let Some(properties) = state.config.device_properties(physicalDevice) {
+            *pProperties = *properties;
+        }
+    }
+}
+
+unsafe extern "C" fn get_physical_device_properties2(
+    physicalDevice: VkPhysicalDevice,
+    pProperties: *mut VkPhysicalDeviceProperties2,
+) {
+    let Ok(state) = enter("vkGetPhysicalDeviceProperties2") else {
+        return;
+    };
+    let Some(properties) = state.config.device_properties(physicalDevice) else {
+        return;
+    };
+    let index = (physicalDevice.as_raw() - MOCK_PHYSICAL_DEVICE) as u8;
+    let out = &mut *pProperties;
+    *(out.properties.as_mut_ptr() as *mut VkPhysicalDeviceProperties) = *properties;
+    let mut next = out.pNext as *mut VkBaseOutStructure;
+    while !next.is_null() {
+        match ((*next).sType, &state.config.driver) {
+            (VkStructureType::PhysicalDeviceDriverProperties, Some(driver)) => {
+                let chained = &mut *(next as *mut VkPhysicalDeviceDriverProperties);
+                chained.driverID = driver.id;
+                write_c_string(&mut chained.driverName, &driver.name);
+                write_c_string(&mut chained.driverInfo, &driver.info);
+                chained.conformanceVersion = driver.conformance_version;
+            }
+            (VkStructureType::PhysicalDeviceIdProperties, _) => {
+                let chained = &mut *(next as *mut VkPhysicalDeviceIDProperties);
+                chained.deviceUUID = state.config.device_uuid;
+                chained.deviceUUID[VK_UUID_SIZE - 1] ^= index;
+            }
+            (VkStructureType::PhysicalDevicePciBusInfoPropertiesEXT, _) => {
+                if let Some([domain, bus, device, function]) = state.config.pci_bus {
+                    let chained = &mut *(next as *mut VkPhysicalDevicePCIBusInfoPropertiesEXT);
+                    chained.pciDomain = domain;
+                    chained.pciBus = bus + index as u32;
+                    chained.pciDevice = device;
+                    chained.pciFunction = function;
+                }
+            }
+            _ => {}
+        }
+        next = (*next).pNext;
+    }
+}
+
+/// Copy `value` into a NUL-terminated fixed-size array, truncating it
+fn write_c_string(out: &mut [c_char], value: &str) {
+    let len = value.len().min(out.len() - 1);
+    out.fill(0);
+    for (dst, src) in out.iter_mut().zip(&value.as_bytes()[..len]) {
+        *dst = *src as c_char;
+    }
+}
+
+unsafe extern "C" fn get_physical_device_features(
+    _physicalDevice: VkPhysicalDevice,
+    pFeatures: *mut VkPhysicalDeviceFeatures,
+) {
+    // Callers pass the full Vulkan 1.0 structure
+    if let Ok(state) = enter("vkGetPhysicalDeviceFeatures") {
+        let full = state.config.features.to_full();
+        ptr::copy_nonoverlapping(full.as_ptr(), pFeatures as *mut VkBool32, full.len());
+    }
+}
+
+unsafe extern "C" fn get_physical_device_queue_family_properties(
+    _physicalDevice: VkPhysicalDevice,
+    pQueueFamilyPropertyCount: *mut u32,
+    pQueueFamilyProperties: *mut VkQueueFamilyProperties,
+) {
+    let Ok(state) = enter("vkGetPhysicalDeviceQueueFamilyProperties") else {
+        return;
+    };
+    let families = &state.config.queue_families;
+    if pQueueFamilyProperties.is_null() {
+        *pQueueFamilyPropertyCount = families.len() as u32;
+        return;
+    }
+    let count = (*pQueueFamilyPropertyCount as usize).min(families.len());
+    ptr::copy_nonoverlapping(families.as_ptr(), pQueueFamilyProperties, count);
+    *pQueueFamilyPropertyCount = count as u32;
+}
+
+unsafe extern "C" fn get_physical_device_queue_family_properties2(
+    _physicalDevice: VkPhysicalDevice,
+    pQueueFamilyPropertyCount: *mut u32,
+    pQueueFamilyProperties: *mut VkQueueFamilyProperties2,
+) {
+    let Ok(state) = enter("vkGetPhysicalDeviceQueueFamilyProperties2") else {
+        return;
+    };
+    let families = &state.config.queue_families;
+    if pQueueFamilyProperties.is_null() {
+        *pQueueFamilyPropertyCount = families.len() as u32;
+        return;
+    }
+    let count = (*pQueueFamilyPropertyCount as usize).min(families.len());
+    for (index, family) in families.iter().take(count).enumerate() {
+        let out = &mut *pQueueFamilyProperties.add(index);
+        out.queueFamilyProperties = *family;
+        let mut next = out.pNext as *mut VkQueueFamilyGlobalPriorityPropertiesKHR;
+        while !next.is_null() {
+            // Every chained structure starts with sType and pNext
+            let chained = &mut *next;
+            if chained.sType == VkStructureType::QueueFamilyGlobalPriorityPropertiesKHR {
+                let priorities = state.config.global_priorities.get(index).map(Vec::as_slice).unwrap_or(&[]);
+                let len = priorities.len().min(VK_MAX_GLOBAL_PRIORITY_SIZE_KHR);
+                chained.priorityCount = len as u32;
+                chained.priorities[..len].copy_from_slice(&priorities[..len]);
+            }
+            next = chained.pNext as *mut VkQueueFamilyGlobalPriorityPropertiesKHR;
+        }
+    }
+    *pQueueFamilyPropertyCount = count as u32;
+}
+
+unsafe extern "C" fn get_physical_device_memory_properties(
+    _physicalDevice: VkPhysicalDevice,
+    pMemoryProperties: *mut VkPhysicalDeviceMemoryProperties,
+) {
+    if let Ok(state) = enter("vkGetPhysicalDeviceMemoryProperties") {
+        *pMemoryProperties = state.config.memory_properties;
+    }
+}
+
+unsafe extern "C" fn enumerate_device_extension_properties(
+    _physicalDevice: VkPhysicalDevice,
+    _pLayerName: *const c_char,
+    pPropertyCount: *mut u32,
+    pProperties: *mut VkExtensionProperties,
+) -> VkResult {
+    match enter("vkEnumerateDeviceExtensionProperties") {
+        Ok(state) => write_extensions(&state.config.extensions, pPropertyCount, pProperties),
+        Err(result) => result,
+    }
+}
+
+/// Report `extensions` the way the extension enumeration functions do
+unsafe fn write_extensions(extensions: &[String], pPropertyCount: *mut u32, pProperties: *mut VkExtensionProperties) -> VkResult {
+    if pProperties.is_null() {
+        *pPropertyCount = extensions.len() as u32;
+        return VkResult::Success;
+    }
+    let count = (*pPropertyCount as usize).min(extensions.len());
+    for (index, name) in extensions.iter().take(count).enumerate() {
+        let mut properties = VkExtensionProperties { specVersion: 1, ..Default::default() };
+        write_c_string(&mut properties.extensionName, name);
+        *pProperties.add(index) = properties;
+    }
+    *pPropertyCount = count as u32;
+    if count < extensions.len() {
+        VkResult::Incomplete
+    } else {
+        VkResult::Success
+    }
+}
+
+// ===== Device and queue functions =====
+
+unsafe extern "C" fn create_device(
+    _physicalDevice: VkPhysicalDevice,
+    pCreateInfo: *const VkDeviceCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pDevice: *mut VkDevice,
+) -> VkResult {
+    let result = create_handle("vkCreateDevice", "VkDevice", pDevice);
+    if result == VkResult::Success {
+        let info = &*pCreateInfo;
+        let mut chain = Vec::new();
+        let mut next = info.pNext as *const VkBaseOutStructure;
+        while let Some(link) = next.as_ref() {
+            chain.push(link.sType);
+            next = link.pNext;
+        }
+        let mut state = state();
+        state.device_extensions = enabled_extensions(info.enabledExtensionCount, info.ppEnabledExtensionNames);
+        state.device_create_chain = chain;
+    }
+    result
+}
+
+unsafe extern "C" fn destroy_device(device: VkDevice, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkDestroyDevice", device.as_raw());
+    let mut state = state();
+    let device = device.as_raw();
+    state.queues.retain(|(owner, _, _), _| *owner != device);
+}
+
+unsafe extern "C" fn get_device_queue(device: VkDevice, queueFamilyIndex: u32, queueIndex: u32, pQueue: *mut VkQueue) {
+    let Ok(mut state) = enter("vkGetDeviceQueue") else {
+        return;
+    };
+    *pQueue = device_queue(&mut state, device, queueFamilyIndex, queueIndex);
+}
+
+unsafe extern "C" fn get_device_queue2(device: VkDevice, pQueueInfo: *const VkDeviceQueueInfo2, pQueue: *mut VkQueue) {
+    let Ok(mut state) = enter("vkGetDeviceQueue2") else {
+        return;
+    };
+    let info = &*pQueueInfo;
+    // Flags must match creation, and Kronos creates queues without any
+    *pQueue = if info.flags == 0 {
+        device_queue(&mut state, device, info.queueFamilyIndex, info.queueIndex)
+    } else {
+        VkQueue::NULL
+    };
+}
+
+/// The queue handle for a device, family and index, made on first use
+fn device_queue(state: &mut MockState, device: VkDevice, queueFamilyIndex: u32, queueIndex: u32) -> VkQueue {
+    let key = (device.as_raw(), queueFamilyIndex, queueIndex);
+    let queue = match state.queues.get(&key) {
+        Some(queue) => *queue,
+        None => {
+            let queue = state.next_handle;
+            state.next_handle += 1;
+            state.queues.insert(key, queue);
+            queue
+        }
+    };
+    VkQueue::from_raw(queue)
+}
+
+unsafe extern "C" fn queue_submit(queue: VkQueue, submitCount: u32, pSubmits: *const VkSubmitInfo, fence: VkFence) -> VkResult {
+    let mut state = match enter("vkQueueSubmit") {
+        Ok(state) if state.removed => return VkResult::ErrorDeviceLost,
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let done = Instant::now() + state.config.fence_delay;
+    for submit in slice(pSubmits, submitCount) {
+        for cb in slice(submit.pCommandBuffers, submit.commandBufferCount) {
+            state.execute(cb.as_raw());
+        }
+        // Every chained structure starts with sType and pNext
+        let mut next = submit.pNext as *const VkTimelineSemaphoreSubmitInfo;
+        while !next.is_null() && (*next).sType != VkStructureType::TimelineSemaphoreSubmitInfo {
+            next = (*next).pNext as *const VkTimelineSemaphoreSubmitInfo;
+        }
+        if let Some(timeline) = next.as_ref() {
+            let values = slice(timeline.pSignalSemaphoreValues, timeline.signalSemaphoreValueCount);
+            for (semaphore, value) in slice(submit.pSignalSemaphores, submit.signalSemaphoreCount).iter().zip(values) {
+                state.timeline_signals.entry(semaphore.as_raw()).or_default().push((*value, done));
+            }
+        }
+    }
+    if !fence.is_null() {
+        state.fences.insert(fence.as_raw(), Some(done));
+    }
+    let idle = state.queue_idle_at.entry(queue.as_raw()).or_insert(done);
+    *idle = (*idle).max(done);
+    VkResult::Success
+}
+
+fn sleep_until(deadline: Option<Instant>) {
+    if let Some(remaining) = deadline.and_then(|d| d.checked_duration_since(Instant::now())) {
+        std::thread::sleep(remaining);
+    }
+}
+
+unsafe extern "C" fn queue_wait_idle(queue: VkQueue) -> VkResult {
+    let idle = match enter("vkQueueWaitIdle") {
+        Ok(state) if state.removed => return VkResult::ErrorDeviceLost,
+        Ok(state) => state.queue_idle_at.get(&queue.as_raw()).copied(),
+        Err(result) => return result,
+    };
+    sleep_until(idle);
+    VkResult::Success
+}
+
+unsafe extern "C" fn device_wait_idle(_device: VkDevice) -> VkResult {
+    let idle = match enter("vkDeviceWaitIdle") {
+        Ok(state) if state.removed => return VkResult::ErrorDeviceLost,
+        Ok(state) => state.queue_idle_at.values().max().copied(),
+        Err(result) => return result,
+    };
+    sleep_until(idle);
+    VkResult::Success
+}
+
+// ===== Memory and buffers =====
+
+unsafe extern "C" fn allocate_memory(
+    _device: VkDevice,
+    pAllocateInfo: *const VkMemoryAllocateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pMemory: *mut VkDeviceMemory,
+) -> VkResult {
+    let mut state = match enter("vkAllocateMemory") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let info = &*pAllocateInfo;
+    if info.memoryTypeIndex >= state.config.memory_properties.memoryTypeCount {
+        return VkResult::ErrorOutOfDeviceMemory;
+    }
+    // Imported memory is backed by a fresh allocation; only the fd is recorded
+    let mut next = info.pNext as *const VkBaseOutStructure;
+    while let Some(link) = next.as_ref() {
+        match link.sType {
+            VkStructureType::ImportMemoryFdInfoKHR => state.imported_fds.push((*(next as *const VkImportMemoryFdInfoKHR)).fd),
+            VkStructureType::MemoryPriorityAllocateInfoEXT => {
+                state.memory_priorities.push((*(next as *const VkMemoryPriorityAllocateInfoEXT)).priority)
+            }
+            _ => {}
+        }
+        next = link.pNext;
+    }
+    let memory = state.create("VkDeviceMemory");
+    state.memory.insert(memory, vec![0; info.allocationSize as usize]);
+    *pMemory = VkDeviceMemory::from_raw(memory);
+    VkResult::Success
+}
+
+unsafe extern "C" fn free_memory(_device: VkDevice, memory: VkDeviceMemory, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkFreeMemory", memory.as_raw());
+    state().memory.remove(&memory.as_raw());
+}
+
+unsafe extern "C" fn map_memory(
+    _device: VkDevice,
+    memory: VkDeviceMemory,
+    offset: VkDeviceSize,
+    _size: VkDeviceSize,
+    _flags: VkMemoryMapFlags,
+    ppData: *mut *mut c_void,
+) -> VkResult {
+    let mut state = match enter("vkMapMemory") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    match state.memory.get_mut(&memory.as_raw()) {
+        // The allocation is never resized, so the pointer stays valid until it is freed
+        Some(bytes) if (offset as usize) <= bytes.len() => {
+            *ppData = bytes.as_mut_ptr().add(offset as usize) as *mut c_void;
+            VkResult::Success
+        }
+        _ => VkResult::ErrorMemoryMapFailed,
+    }
+}
+
+unsafe extern "C" fn unmap_memory(_device: VkDevice, _memory: VkDeviceMemory) {
+    count("vkUnmapMemory");
+}
+
+unsafe extern "C" fn invalidate_mapped_memory_ranges(
+    _device: VkDevice,
+    _memoryRangeCount: u32,
+    _pMemoryRanges: *const VkMappedMemoryRange,
+) -> VkResult {
+    // Mapped memory is the allocation itself, so there is nothing to invalidate
+    match enter("vkInvalidateMappedMemoryRanges") {
+        Ok(_) => VkResult::Success,
+        Err(result) => result,
+    }
+}
+
+unsafe extern "C" fn get_memory_fd_properties(
+    _device: VkDevice,
+    _handleType: VkExternalMemoryHandleTypeFlags,
+    fd: c_int,
+    pMemoryFdProperties: *mut VkMemoryFdPropertiesKHR,
+) -> VkResult {
+    let state = match enter("vkGetMemoryFdPropertiesKHR") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    if fd < 0 {
+        return VkResult::ErrorInvalidExternalHandle;
+    }
+    // Any fd can go into device-local memory
+    (*pMemoryFdProperties).memoryTypeBits = (0..state.config.memory_properties.memoryTypeCount)
+        .filter(|&i| state.config.memory_properties.memoryTypes[i as usize].propertyFlags.contains(VkMemoryPropertyFlags::DEVICE_LOCAL))
+        .fold(0, |bits, i| bits | (1 << i));
+    VkResult::Success
+}
+
+unsafe extern "C" fn get_memory_fd(_device: VkDevice, pGetFdInfo: *const VkMemoryGetFdInfoKHR, pFd: *mut c_int) -> VkResult {
+    let mut state = match enter("vkGetMemoryFdKHR") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    if !state.memory.contains_key(&(*pGetFdInfo).memory.as_raw()) {
+        return VkResult::ErrorInvalidExternalHandle;
+    }
+    // The fd is never opened; consumers of mock exports must not use it
+    *pFd = state.next_fd;
+    state.next_fd += 1;
+    VkResult::Success
+}
+
+unsafe extern "C" fn create_buffer(
+    _device: VkDevice,
+    pCreateInfo: *const VkBufferCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pBuffer: *mut VkBuffer,
+) -> VkResult {
+    let mut state = match enter("vkCreateBuffer") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let buffer = state.create("VkBuffer");
+    state.buffers.insert(buffer, MockBuffer { size: (*pCreateInfo).size, memory: None });
+    *pBuffer = VkBuffer::from_raw(buffer);
+    VkResult::Success
+}
+
+unsafe extern "C" fn destroy_buffer(_device: VkDevice, buffer: VkBuffer, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkDestroyBuffer", buffer.as_raw());
+    state().buffers.remove(&buffer.as_raw());
+}
+
+unsafe extern "C" fn get_buffer_memory_requirements(
+    _device: VkDevice,
+    buffer: VkBuffer,
+    pMemoryRequirements: *mut VkMemoryRequirements,
+) {
+    let Ok(state) = enter("vkGetBufferMemoryRequirements") else {
+        return;
+    };
+    let size = state.buffers.get(&buffer.as_raw()).map_or(0, |b| b.size);
+    *pMemoryRequirements = VkMemoryRequirements {
+        size: (size + MOCK_MEMORY_ALIGNMENT - 1) / MOCK_MEMORY_ALIGNMENT * MOCK_MEMORY_ALIGNMENT,
+        alignment: MOCK_MEMORY_ALIGNMENT,
+        memoryTypeBits: (1u32 << state.config.memory_properties.memoryTypeCount) - 1,
+    };
+}
+
+unsafe extern "C" fn bind_buffer_memory(
+    _device: VkDevice,
+    buffer: VkBuffer,
+    memory: VkDeviceMemory,
+    memoryOffset: VkDeviceSize,
+) -> VkResult {
+    let mut state = match enter("vkBindBufferMemory") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    match state.buffers.get_mut(&buffer.as_raw()) {
+        Some(b) => {
+            b.memory = Some((memory.as_raw(), memoryOffset));
+            VkResult::Success
+        }
+        None => VkResult::ErrorInitializationFailed,
+    }
+}
+
+// ===== Shaders, pipelines and descriptors =====
+
+mock_object!(create_shader_module, destroy_shader_module, VkShaderModuleCreateInfo, VkShaderModule, "ShaderModule");
+mock_object!(create_pipeline_layout, destroy_pipeline_layout, VkPipelineLayoutCreateInfo, VkPipelineLayout, "PipelineLayout");
+mock_object!(create_descriptor_set_layout, destroy_descriptor_set_layout, VkDescriptorSetLayoutCreateInfo, VkDescriptorSetLayout, "DescriptorSetLayout");
+mock_object!(create_descriptor_update_template, destroy_descriptor_update_template, VkDescriptorUpdateTemplateCreateInfo, VkDescriptorUpdateTemplate, "DescriptorUpdateTemplate");
+mock_object!(create_fence_handle, destroy_fence_handle, VkFenceCreateInfo, VkFence, "Fence");
+mock_object!(create_semaphore, destroy_semaphore, VkSemaphoreCreateInfo, VkSemaphore, "Semaphore");
+
+unsafe extern "C" fn get_semaphore_fd(_device: VkDevice, _pGetFdInfo: *const VkSemaphoreGetFdInfoKHR, pFd: *mut c_int) -> VkResult {
+    let mut state = match enter("vkGetSemaphoreFdKHR") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    *pFd = state.next_fd;
+    state.next_fd += 1;
+    VkResult::Success
+}
+
+unsafe extern "C" fn get_semaphore_counter_value(_device: VkDevice, semaphore: VkSemaphore, pValue: *mut u64) -> VkResult {
+    let state = match enter("vkGetSemaphoreCounterValue") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let now = Instant::now();
+    let signals = state.timeline_signals.get(&semaphore.as_raw()).map_or(&[][..], Vec::as_slice);
+    *pValue = signals.iter().filter(|(_, at)| *at <= now).map(|(value, _)| *value).max().unwrap_or(0);
+    VkResult::Success
+}
+
+unsafe extern "C" fn create_compute_pipelines(
+    _device: VkDevice,
+    _pipelineCache: VkPipelineCache,
+    createInfoCount: u32,
+    pCreateInfos: *const VkComputePipelineCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pPipelines: *mut VkPipeline,
+) -> VkResult {
+    let mut state = match enter("vkCreateComputePipelines") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    for i in 0..createInfoCount as usize {
+        *pPipelines.add(i) = VkPipeline::from_raw(state.create("VkPipeline"));
+        let info = &*pCreateInfos.add(i);
+        if let Some(duration) = state.config.pipeline_creation_time {
+            write_creation_feedback(info, duration);
+        }
+    }
+    VkResult::Success
+}
+
+/// Fill in the VkPipelineCreationFeedbackCreateInfo chained into `info`, if any
+unsafe fn write_creation_feedback(info: &VkComputePipelineCreateInfo, duration: Duration) {
+    // Every chained structure starts with sType and pNext
+    let mut next = info.pNext as *const VkPipelineCreationFeedbackCreateInfo;
+    while !next.is_null() && (*next).sType != VkStructureType::PipelineCreationFeedbackCreateInfo {
+        next = (*next).pNext as *const VkPipelineCreationFeedbackCreateInfo;
+    }
+    let Some(feedback) = next.as_ref() else {
+        return;
+    };
+    let mut flags = VkPipelineCreationFeedbackFlags::VALID;
+    if info.flags.contains(VkPipelineCreateFlags::DERIVATIVE) {
+        flags |= VkPipelineCreationFeedbackFlags::BASE_PIPELINE_ACCELERATION;
+    }
+    *feedback.pPipelineCreationFeedback = VkPipelineCreationFeedback {
+        flags,
+        duration: duration.as_nanos() as u64,
+    };
+    for stage in 0..feedback.pipelineStageCreationFeedbackCount as usize {
+        *feedback.pPipelineStageCreationFeedbacks.add(stage) = VkPipelineCreationFeedback {
+            flags: VkPipelineCreationFeedbackFlags::VALID,
+            duration: duration.as_nanos() as u64 / 2,
+        };
+    }
+}
+
+unsafe extern "C" fn destroy_pipeline(_device: VkDevice, pipeline: VkPipeline, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkDestroyPipeline", pipeline.as_raw());
+}
+
+unsafe extern "C" fn create_descriptor_pool(
+    _device: VkDevice,
+    _pCreateInfo: *const VkDescriptorPoolCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pDescriptorPool: *mut VkDescriptorPool,
+) -> VkResult {
+    create_handle("vkCreateDescriptorPool", "VkDescriptorPool", pDescriptorPool)
+}
+
+/// Free the sets of a destroyed or reset pool
+fn release_descriptor_sets(state: &mut MockState, pool: u64) {
+    let sets: Vec<u64> = state.descriptor_sets.iter().filter(|(_, p)| **p == pool).map(|(s, _)| *s).collect();
+    for set in sets {
+        state.descriptor_sets.remove(&set);
+        state.destroy(set);
+    }
+}
+
+unsafe extern "C" fn destroy_descriptor_pool(
+    _device: VkDevice,
+    descriptorPool: VkDescriptorPool,
+    _pAllocator: *const VkAllocationCallbacks,
+) {
+    destroy_handle("vkDestroyDescriptorPool", descriptorPool.as_raw());
+    release_descriptor_sets(&mut state(), descriptorPool.as_raw());
+}
+
+unsafe extern "C" fn reset_descriptor_pool(
+    _device: VkDevice,
+    descriptorPool: VkDescriptorPool,
+    _flags: VkDescriptorPoolResetFlags,
+) -> VkResult {
+    match enter("vkResetDescriptorPool") {
+        Ok(mut state) => {
+            release_descriptor_sets(&mut state, descriptorPool.as_raw());
+            VkResult::Success
+        }
+        Err(result) => result,
+    }
+}
+
+unsafe extern "C" fn allocate_descriptor_sets(
+    _device: VkDevice,
+    pAllocateInfo: *const VkDescriptorSetAllocateInfo,
+    pDescriptorSets: *mut VkDescriptorSet,
+) -> VkResult {
+    let mut state = match enter("vkAllocateDescriptorSets") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let info = &*pAllocateInfo;
+    for i in 0..info.descriptorSetCount as usize {
+        let set = state.create("VkDescriptorSet");
+        state.descriptor_sets.insert(set, info.descriptorPool.as_raw());
+        *pDescriptorSets.add(i) = VkDescriptorSet::from_raw(set);
+    }
+    VkResult::Success
+}
+
+unsafe extern "C" fn free_descriptor_sets(
+    _device: VkDevice,
+    _descriptorPool: VkDescriptorPool,
+    descriptorSetCount: u32,
+    pDescriptorSets: *const VkDescriptorSet,
+) -> VkResult {
+    let mut state = match enter("vkFreeDescriptorSets") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    for set in slice(pDescriptorSets, descriptorSetCount) {
+        state.descriptor_sets.remove(&set.as_raw());
+        state.destroy(set.as_raw());
+    }
+    VkResult::Success
+}
+
+unsafe extern "C" fn update_descriptor_sets(
+    _device: VkDevice,
+    descriptorWriteCount: u32,
+    pDescriptorWrites: *const VkWriteDescriptorSet,
+    _descriptorCopyCount: u32,
+    _pDescriptorCopies: *const VkCopyDescriptorSet,
+) {
+    count("vkUpdateDescriptorSets");
+    state().descriptor_writes = slice(pDescriptorWrites, descriptorWriteCount)
+        .iter().map(|write| (write.dstBinding, write.descriptorType)).collect();
+}
+
+unsafe extern "C" fn update_descriptor_set_with_template(
+    _device: VkDevice,
+    _descriptorSet: VkDescriptorSet,
+    _descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
+    _pData: *const c_void,
+) {
+    count("vkUpdateDescriptorSetWithTemplate");
+}
+
+// ===== Command pools and buffers =====
+
+unsafe extern "C" fn create_command_pool(
+    _device: VkDevice,
+    _pCreateInfo: *const VkCommandPoolCreateInfo,
+    _pAllocator: *const VkAllocationCallbacks,
+    pCommandPool: *mut VkCommandPool,
+) -> VkResult {
+    create_handle("vkCreateCommandPool", "VkCommandPool", pCommandPool)
+}
+
+unsafe extern "C" fn destroy_command_pool(_device: VkDevice, commandPool: VkCommandPool, _pAllocator: *const VkAllocationCallbacks) {
+    destroy_handle("vkDestroyCommandPool", commandPool.as_raw());
+    let mut state = state();
+    let pool = commandPool.as_raw();
+    let freed: Vec<u64> = state.command_buffers.iter().filter(|(_, cb)| cb.pool == pool).map(|(h, _)| *h).collect();
+    for cb in freed {
+        state.command_buffers.remove(&cb);
+        state.destroy(cb);
+    }
+}
+
+unsafe extern "C" fn allocate_command_buffers(
+    _device: VkDevice,
+    pAllocateInfo: *const VkCommandBufferAllocateInfo,
+    pCommandBuffers: *mut VkCommandBuffer,
+) -> VkResult {
+    let mut state = match enter("vkAllocateCommandBuffers") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    let info = &*pAllocateInfo;
+    for i in 0..info.commandBufferCount as usize {
+        let cb = state.create("VkCommandBuffer");
+        state.command_buffers.insert(cb, MockCommandBuffer { pool: info.commandPool.as_raw(), ..Default::default() });
+        *pCommandBuffers.add(i) = VkCommandBuffer::from_raw(cb);
+    }
+    VkResult::Success
+}
+
+unsafe extern "C" fn free_command_buffers(
+    _device: VkDevice,
+    _commandPool: VkCommandPool,
+    commandBufferCount: u32,
+    pCommandBuffers: *const VkCommandBuffer,
+) {
+    let Ok(mut state) = enter("vkFreeCommandBuffers") else {
+        return;
+    };
+    for cb in slice(pCommandBuffers, commandBufferCount) {
+        state.command_buffers.remove(&cb.as_raw());
+        state.destroy(cb.as_raw());
+    }
+}
+
+unsafe extern "C" fn begin_command_buffer(commandBuffer: VkCommandBuffer, _pBeginInfo: *const VkCommandBufferBeginInfo) -> VkResult {
+    let mut state = match enter("vkBeginCommandBuffer") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    match state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        Some(cb) => {
+            *cb = MockCommandBuffer { pool: cb.pool, ..Default::default() };
+            VkResult::Success
+        }
+        None => VkResult::ErrorInitializationFailed,
+    }
+}
+
+unsafe extern "C" fn end_command_buffer(_commandBuffer: VkCommandBuffer) -> VkResult {
+    match enter("vkEndCommandBuffer") {
+        Ok(_) => VkResult::Success,
+        Err(result) => result,
+    }
+}
+
+unsafe extern "C" fn cmd_copy_buffer(
+    commandBuffer: VkCommandBuffer,
+    srcBuffer: VkBuffer,
+    dstBuffer: VkBuffer,
+    regionCount: u32,
+    pRegions: *const VkBufferCopy,
+) {
+    let Ok(mut state) = enter("vkCmdCopyBuffer") else {
+        return;
+    };
+    let regions = slice(pRegions, regionCount).to_vec();
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        cb.copies.push((srcBuffer.as_raw(), dstBuffer.as_raw(), regions));
+    }
+}
+
+unsafe extern "C" fn cmd_bind_pipeline(_commandBuffer: VkCommandBuffer, _pipelineBindPoint: VkPipelineBindPoint, _pipeline: VkPipeline) {
+    count("vkCmdBindPipeline");
+}
+
+unsafe extern "C" fn cmd_bind_descriptor_sets(
+    _commandBuffer: VkCommandBuffer,
+    _pipelineBindPoint: VkPipelineBindPoint,
+    _layout: VkPipelineLayout,
+    _firstSet: u32,
+    _descriptorSetCount: u32,
+    _pDescriptorSets: *const VkDescriptorSet,
+    dynamicOffsetCount: u32,
+    pDynamicOffsets: *const u32,
+) {
+    count("vkCmdBindDescriptorSets");
+    state().dynamic_offsets = slice(pDynamicOffsets, dynamicOffsetCount).to_vec();
+}
+
+unsafe extern "C" fn cmd_push_constants(
+    _commandBuffer: VkCommandBuffer,
+    _layout: VkPipelineLayout,
+    _stageFlags: VkShaderStageFlags,
+    _offset: u32,
+    _size: u32,
+    _pValues: *const c_void,
+) {
+    count("vkCmdPushConstants");
+}
+
+unsafe extern "C" fn cmd_dispatch(commandBuffer: VkCommandBuffer, _groupCountX: u32, _groupCountY: u32, _groupCountZ: u32) {
+    let Ok(mut state) = enter("vkCmdDispatch") else {
+        return;
+    };
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        cb.dispatch();
+    }
+}
+
+unsafe extern "C" fn cmd_dispatch_base(
+    commandBuffer: VkCommandBuffer,
+    _baseGroupX: u32,
+    _baseGroupY: u32,
+    _baseGroupZ: u32,
+    _groupCountX: u32,
+    _groupCountY: u32,
+    _groupCountZ: u32,
+) {
+    let Ok(mut state) = enter("vkCmdDispatchBase") else {
+        return;
+    };
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        cb.dispatch();
+    }
+}
+
+unsafe extern "C" fn cmd_dispatch_indirect(commandBuffer: VkCommandBuffer, _buffer: VkBuffer, _offset: VkDeviceSize) {
+    let Ok(mut state) = enter("vkCmdDispatchIndirect") else {
+        return;
+    };
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        cb.dispatch();
+    }
+}
+
+unsafe extern "C" fn cmd_begin_conditional_rendering(
+    commandBuffer: VkCommandBuffer,
+    pConditionalRenderingBegin: *const VkConditionalRenderingBeginInfoEXT,
+) {
+    let Ok(mut state) = enter("vkCmdBeginConditionalRenderingEXT") else {
+        return;
+    };
+    let info = &*pConditionalRenderingBegin;
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        let inverted = info.flags.contains(VkConditionalRenderingFlagsEXT::INVERTED);
+        cb.predicate = Some((info.buffer.as_raw(), info.offset, inverted));
+    }
+}
+
+unsafe extern "C" fn cmd_end_conditional_rendering(commandBuffer: VkCommandBuffer) {
+    let Ok(mut state) = enter("vkCmdEndConditionalRenderingEXT") else {
+        return;
+    };
+    if let Some(cb) = state.command_buffers.get_mut(&commandBuffer.as_raw()) {
+        cb.predicate = None;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn cmd_pipeline_barrier(
+    _commandBuffer: VkCommandBuffer,
+    _srcStageMask: VkPipelineStageFlags,
+    _dstStageMask: VkPipelineStageFlags,
+    _dependencyFlags: VkDependencyFlags,
+    _memoryBarrierCount: u32,
+    _pMemoryBarriers: *const VkMemoryBarrier,
+    bufferMemoryBarrierCount: u32,
+    pBufferMemoryBarriers: *const VkBufferMemoryBarrier,
+    _imageMemoryBarrierCount: u32,
+    _pImageMemoryBarriers: *const VkImageMemoryBarrier,
+) {
+    let Ok(mut state) = enter("vkCmdPipelineBarrier") else {
+        return;
+    };
+    for barrier in slice(pBufferMemoryBarriers, bufferMemoryBarrierCount) {
+        if barrier.srcQueueFamilyIndex != barrier.dstQueueFamilyIndex && barrier.srcQueueFamilyIndex != VK_QUEUE_FAMILY_IGNORED {
+            let transfer = (barrier.buffer.as_raw(), barrier.srcQueueFamilyIndex, barrier.dstQueueFamilyIndex);
+            state.ownership_transfers.push(transfer);
+        }
+    }
+}
+
+// ===== Fences =====
+
+unsafe extern "C" fn create_fence(
+    device: VkDevice,
+    pCreateInfo: *const VkFenceCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pFence: *mut VkFence,
+) -> VkResult {
+    let result = create_fence_handle(device, pCreateInfo, pAllocator, pFence);
+    if result == VkResult::Success {
+        let signaled = (*pCreateInfo).flags.contains(VkFenceCreateFlags::SIGNALED);
+        state().fences.insert((*pFence).as_raw(), signaled.then(Instant::now));
+    }
+    result
+}
+
+unsafe extern "C" fn destroy_fence(device: VkDevice, fence: VkFence, pAllocator: *const VkAllocationCallbacks) {
+    destroy_fence_handle(device, fence, pAllocator);
+    state().fences.remove(&fence.as_raw());
+}
+
+unsafe extern "C" fn reset_fences(_device: VkDevice, fenceCount: u32, pFences: *const VkFence) -> VkResult {
+    let mut state = match enter("vkResetFences") {
+        Ok(state) => state,
+        Err(result) => return result,
+    };
+    for fence in slice(pFences, fenceCount) {
+        state.fences.insert(fence.as_raw(), None);
+    }
+    VkResult::Success
+}
+
+fn is_signaled(state: &MockState, fence: u64, now: Instant) -> bool {
+    matches!(state.fences.get(&fence), Some(Some(at)) if *at <= now)
+}
+
+unsafe extern "C" fn get_fence_status(_device: VkDevice, fence: VkFence) -> VkResult {
+    match enter("vkGetFenceStatus") {
+        Ok(state) if state.removed => VkResult::ErrorDeviceLost,
+        Ok(state) if is_signaled(&state, fence.as_raw(), Instant::now()) => VkResult::Success,
+        Ok(_) => VkResult::NotReady,
+        Err(result) => result,
+    }
+}
+
+unsafe extern "C" fn wait_for_fences(
+    _device: VkDevice,
+    fenceCount: u32,
+    pFences: *const VkFence,
+    waitAll: VkBool32,
+    timeout: u64,
+) -> VkResult {
+    match enter("vkWaitForFences") {
+        Ok(state) if state.removed => return VkResult::ErrorDeviceLost,
+        Ok(_) => {}
+        Err(result) => return result,
+    }
+    let fences: Vec<u64> = slice(pFences, fenceCount).iter().map(|f| f.as_raw()).collect();
+    let deadline = Instant::now().checked_add(Duration::from_nanos(timeout));
+    loop {
+        // Poll without holding the lock so submissions from other threads can land
+        let now = Instant::now();
+        let done = {
+            let state = state();
+            let mut signaled = fences.iter().map(|f| is_signaled(&state, *f, now));
+            if waitAll == VK_TRUE { signaled.all(|s| s) } else { signaled.any(|s| s) }
+        };
+        if done {
+            return VkResult::Success;
+        }
+        if matches!(deadline, Some(d) if now >= d) {
+            return VkResult::Timeout;
+        }
+        std::thread::sleep(Duration::from_micros(200));
+    }
+}
+
+/// Resolve an entry point by name; unsupported ones return null
+unsafe fn lookup(pName: *const c_char) -> PFN_vkVoidFunction {
+    if pName.is_null() {
+        return None;
+    }
+    let name = CStr::from_ptr(pName).to_str().ok()?;
+    let f: *const () = match name {
+        "vkGetInstanceProcAddr" => get_instance_proc_addr as *const (),
+        "vkGetDeviceProcAddr" => get_device_proc_addr as *const (),
+        "vkEnumerateInstanceVersion" => enumerate_instance_version as *const (),
+        "vkEnumerateInstanceExtensionProperties" => enumerate_instance_extension_properties as *const (),
+        "vkEnumerateInstanceLayerProperties" => enumerate_instance_layer_properties as *const (),
+        "vkCreateDebugUtilsMessengerEXT" => create_debug_utils_messenger as *const (),
+        "vkDestroyDebugUtilsMessengerEXT" => destroy_debug_utils_messenger as *const (),
+        "vkCreateInstance" => create_instance as *const (),
+        "vkDestroyInstance" => destroy_instance as *const (),
+        "vkEnumeratePhysicalDevices" => enumerate_physical_devices as *const (),
+        "vkGetPhysicalDeviceProperties" => get_physical_device_properties as *const (),
+        "vkGetPhysicalDeviceProperties2" => get_physical_device_properties2 as *const (),
+        "vkGetPhysicalDeviceFeatures" => get_physical_device_features as *const (),
+        "vkGetPhysicalDeviceQueueFamilyProperties" => get_physical_device_queue_family_properties as *const (),
+        "vkGetPhysicalDeviceQueueFamilyProperties2" => get_physical_device_queue_family_properties2 as *const (),
+        "vkGetPhysicalDeviceMemoryProperties" => get_physical_device_memory_properties as *const (),
+        "vkEnumerateDeviceExtensionProperties" => enumerate_device_extension_properties as *const (),
+        "vkCreateDevice" => create_device as *const (),
+        "vkDestroyDevice" => destroy_device as *const (),
+        "vkGetDeviceQueue" => get_device_queue as *const (),
+        "vkGetDeviceQueue2" => get_device_queue2 as *const (),
+        "vkQueueSubmit" => queue_submit as *const (),
+        "vkQueueWaitIdle" => queue_wait_idle as *const (),
+        "vkDeviceWaitIdle" => device_wait_idle as *const (),
+        "vkAllocateMemory" => allocate_memory as *const (),
+        "vkFreeMemory" => free_memory as *const (),
+        "vkMapMemory" => map_memory as *const (),
+        "vkUnmapMemory" => unmap_memory as *const (),
+        "vkInvalidateMappedMemoryRanges" => invalidate_mapped_memory_ranges as *const (),
+        "vkGetMemoryFdPropertiesKHR" => get_memory_fd_properties as *const (),
+        "vkGetMemoryFdKHR" => get_memory_fd as *const (),
+        "vkCreateBuffer" => create_buffer as *const (),
+        "vkDestroyBuffer" => destroy_buffer as *const (),
+        "vkGetBufferMemoryRequirements" => get_buffer_memory_requirements as *const (),
+        "vkBindBufferMemory" => bind_buffer_memory as *const (),
+        "vkCreateShaderModule" => create_shader_module as *const (),
+        "vkDestroyShaderModule" => destroy_shader_module as *const (),
+        "vkCreatePipelineLayout" => create_pipeline_layout as *const (),
+        "vkDestroyPipelineLayout" => destroy_pipeline_layout as *const (),
+        "vkCreateComputePipelines" => create_compute_pipelines as *const (),
+        "vkDestroyPipeline" => destroy_pipeline as *const (),
+        "vkCreateDescriptorSetLayout" => create_descriptor_set_layout as *const (),
+        "vkDestroyDescriptorSetLayout" => destroy_descriptor_set_layout as *const (),
+        "vkCreateDescriptorPool" => create_descriptor_pool as *const (),
+        "vkDestroyDescriptorPool" => destroy_descriptor_pool as *const (),
+        "vkResetDescriptorPool" => reset_descriptor_pool as *const (),
+        "vkAllocateDescriptorSets" => allocate_descriptor_sets as *const (),
+        "vkFreeDescriptorSets" => free_descriptor_sets as *const (),
+        "vkUpdateDescriptorSets" => update_descriptor_sets as *const (),
+        "vkCreateDescriptorUpdateTemplate" => create_descriptor_update_template as *const (),
+        "vkDestroyDescriptorUpdateTemplate" => destroy_descriptor_update_template as *const (),
+        "vkUpdateDescriptorSetWithTemplate" => update_descriptor_set_with_template as *const (),
+        "vkCreateCommandPool" => create_command_pool as *const (),
+        "vkDestroyCommandPool" => destroy_command_pool as *const (),
+        "vkAllocateCommandBuffers" => allocate_command_buffers as *const (),
+        "vkFreeCommandBuffers" => free_command_buffers as *const (),
+        "vkBeginCommandBuffer" => begin_command_buffer as *const (),
+        "vkEndCommandBuffer" => end_command_buffer as *const (),
+        "vkCmdCopyBuffer" => cmd_copy_buffer as *const (),
+        "vkCmdBindPipeline" => cmd_bind_pipeline as *const (),
+        "vkCmdBindDescriptorSets" => cmd_bind_descriptor_sets as *const (),
+        "vkCmdPushConstants" => cmd_push_constants as *const (),
+        "vkCmdDispatch" => cmd_dispatch as *const (),
+        "vkCmdDispatchBase" => cmd_dispatch_base as *const (),
+        "vkCmdDispatchIndirect" => cmd_dispatch_indirect as *const (),
+        "vkCmdBeginConditionalRenderingEXT" => cmd_begin_conditional_rendering as *const (),
+        "vkCmdEndConditionalRenderingEXT" => cmd_end_conditional_rendering as *const (),
+        "vkCmdPipelineBarrier" => cmd_pipeline_barrier as *const (),
+        "vkCreateFence" => create_fence as *const (),
+        "vkDestroyFence" => destroy_fence as *const (),
+        "vkResetFences" => reset_fences as *const (),
+        "vkGetFenceStatus" => get_fence_status as *const (),
+        "vkWaitForFences" => wait_for_fences as *const (),
+        "vkCreateSemaphore" => create_semaphore as *const (),
+        "vkDestroySemaphore" => destroy_semaphore as *const (),
+        "vkGetSemaphoreFdKHR" => get_semaphore_fd as *const (),
+        "vkGetSemaphoreCounterValue" | "vkGetSemaphoreCounterValueKHR" => get_semaphore_counter_value as *const (),
+        _ => return None,
+    };
+    // SAFETY: callers cast the pointer back to the entry point's real signature
+    Some(std::mem::transmute::<*const (), unsafe extern "C" fn()>(f))
+}
@@ -1,13 +1,67 @@
 //! Actual implementation of Kronos compute APIs
 
+use std::ffi::c_char;
+use std::path::PathBuf;
 use std::sync::Mutex;
-use log::error;
+use crate::sys::VkInstance;
+use crate::ffi::PFN_vkVoidFunction;
+
+/// Call an ICD function pointer, timing the call for
+/// [`metrics::icd_latency`](crate::metrics::icd_latency) with the
+/// `icd-profiling` feature and auditing it with the `audit` feature; the
+/// `minimal` feature turns both off
+macro_rules! icd_call {
+    ($entry_point:literal, $call:expr) => {{
+        #[cfg(all(feature = "audit", not(feature = "minimal")))]
+        crate::audit::record_icd_call($entry_point);
+        #[cfg(all(feature = "icd-profiling", not(feature = "minimal")))]
+        let start = std::time::Instant::now();
+        let result = crate::implementation::IcdReturn::convert($call);
+        #[cfg(all(feature = "icd-profiling", not(feature = "minimal")))]
+        crate::metrics::record_icd_call($entry_point, start.elapsed());
+        result
+    }};
+}
+
+/// What Kronos makes of the value an ICD entry point returns
+///
+/// Results come back as raw codes, see [`VkResult::from_raw`](crate::ffi::VkResult::from_raw).
+pub(crate) trait IcdReturn {
+    type Output;
+
+    fn convert(self) -> Self::Output;
+}
+
+impl IcdReturn for () {
+    type Output = ();
+
+    fn convert(self) {}
+}
+
+impl IcdReturn for PFN_vkVoidFunction {
+    type Output = Self;
+
+    fn convert(self) -> Self {
+        self
+    }
+}
+
+impl IcdReturn for i32 {
+    type Output = crate::ffi::VkResult;
+
+    fn convert(self) -> Self::Output {
+        crate::ffi::VkResult::from_raw(self)
+    }
+}
 
 pub mod error;
+pub mod logging;
 pub mod instance;
 pub mod device;
 pub mod memory;
 pub mod buffer;
+pub mod image;
+pub mod query;
 pub mod pipeline;
 pub mod descriptor;
 pub mod sync;
@@ -17,6 +71,14 @@ pub mod persistent_descriptors;
 pub mod barrier_policy;
 pub mod timeline_batching;
 pub mod pool_allocator;
+pub mod child_objects;
+pub mod quirks;
+#[cfg(all(feature = "bundled-swiftshader", not(target_os = "android")))]
+pub mod bundled_icd;
+#[cfg(target_os = "android")]
+pub mod android;
+#[cfg(feature = "mock-icd")]
+pub mod mock_icd;
 
 #[cfg(test)]
 mod tests;
@@ -26,6 +88,8 @@ pub use instance::*;
 pub use device::*;
 pub use memory::*;
 pub use buffer::*;
+pub use image::*;
+pub use query::*;
 pub use pipeline::*;
 pub use descriptor::*;
 pub use sync::*;
@@ -35,12 +99,58 @@ lazy_static::lazy_static! {
     pub static ref ICD_INITIALIZED: Mutex<bool> = Mutex::new(false);
 }
 
+/// Stands in for the library path of a driver reached through
+/// [`InitOptions::with_loader`]
+pub const EXTERNAL_LOADER_NAME: &str = "<external-loader>";
+
+/// A `vkGetInstanceProcAddr` implementation
+pub type GetInstanceProcAddr = unsafe extern "C" fn(VkInstance, *const c_char) -> PFN_vkVoidFunction;
+
+/// How [`initialize_kronos_with`] reaches the Vulkan driver
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InitOptions {
+    loader: Option<GetInstanceProcAddr>,
+}
+
+impl InitOptions {
+    /// Build the dispatch from `get_instance_proc_addr` instead of
+    /// discovering ICD manifests
+    ///
+    /// For environments where drivers are only reachable through a Vulkan
+    /// loader Kronos cannot find on its own, such as Android's libvulkan or
+    /// a custom loader. The function must resolve every Vulkan function
+    /// for a null instance (global functions) and for the instances and
+    /// devices it creates, as a loader's `vkGetInstanceProcAddr` does.
+    pub fn with_loader(get_instance_proc_addr: GetInstanceProcAddr) -> Self {
+        Self {
+            loader: Some(get_instance_proc_addr),
+        }
+    }
+}
+
 /// Initialize Kronos (loads ICD if available)
 pub fn initialize_kronos() -> Result<(), error::KronosError> {
-    log::info!("=== Kronos Implementation Initializing ===");
+    initialize_kronos_with(InitOptions::default())
+}
+
+/// Initialize Kronos as `options` describe
+///
+/// A loader given with [`InitOptions::with_loader`] replaces any driver
+/// loaded before; objects created through that driver must not be used
+/// afterwards. Without one, this is [`initialize_kronos`].
+pub fn initialize_kronos_with(options: InitOptions) -> Result<(), error::KronosError> {
+    kronos_log!(Info, "=== Kronos Implementation Initializing ===");
     let mut initialized = ICD_INITIALIZED.lock()?;
+    if let Some(loader) = options.loader {
+        let icd = icd_loader::load_icd_from_entry_point(PathBuf::from(EXTERNAL_LOADER_NAME), Some(loader))?;
+        icd_loader::install_icd(icd)?;
+        *initialized = true;
+        crate::api::refresh_devices();
+        kronos_log!(Info, "Kronos initialized with an external Vulkan loader");
+        return Ok(());
+    }
     if *initialized {
-        log::info!("Kronos already initialized");
+        kronos_log!(Info, "Kronos already initialized");
         return Ok(());
     }
     
@@ -48,11 +158,11 @@ pub fn initialize_kronos() -> Result<(), error::KronosError> {
     match icd_loader::initialize_icd_loader() {
         Ok(()) => {
             *initialized = true;
-            log::info!("Kronos initialized successfully with ICD forwarding");
+            kronos_log!(Info, "Kronos initialized successfully with ICD forwarding");
             Ok(())
         }
         Err(e) => {
-            error!("Failed to initialize Vulkan ICD loader: {}", e);
+            kronos_log!(Error, "Failed to initialize Vulkan ICD loader: {}", e);
             Err(error::KronosError::from(e))
         }
     }
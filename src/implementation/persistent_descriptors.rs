@@ -6,11 +6,12 @@
 //! - Parameters passed via push constants (≤128B)
 
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
 use super::error::IcdError;
+use super::icd_loader::{self, LoadedICD};
 
 /// Maximum push constant size (typical hardware limit)
 pub const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
@@ -52,6 +53,11 @@ lazy_static::lazy_static! {
     });
 }
 
+/// ICD owning `device`, falling back to the main ICD
+fn device_icd(device: VkDevice) -> Option<Arc<LoadedICD>> {
+    icd_loader::icd_for_device(device).or_else(icd_loader::get_icd)
+}
+
 /// Create Set0 layout for storage buffers
 ///
 /// # Safety
@@ -95,10 +101,10 @@ pub unsafe fn create_persistent_layout(
     };
     
     // Forward to ICD
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = device_icd(device) {
         if let Some(create_fn) = icd.create_descriptor_set_layout {
             let mut layout = VkDescriptorSetLayout::NULL;
-            let result = create_fn(device, &create_info, std::ptr::null(), &mut layout);
+            let result = icd_call!("vkCreateDescriptorSetLayout", create_fn(device, &create_info, std::ptr::null(), &mut layout));
             
             if result == VkResult::Success {
                 manager.set0_layout.insert((device_key, max_bindings), layout);
@@ -151,10 +157,10 @@ pub unsafe fn get_persistent_pool(
     };
     
     // Forward to ICD
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = device_icd(device) {
         if let Some(create_fn) = icd.create_descriptor_pool {
             let mut pool = VkDescriptorPool::NULL;
-            let result = create_fn(device, &create_info, std::ptr::null(), &mut pool);
+            let result = icd_call!("vkCreateDescriptorPool", create_fn(device, &create_info, std::ptr::null(), &mut pool));
             
             if result == VkResult::Success {
                 manager.pools.insert(device_key, pool);
@@ -182,7 +188,19 @@ pub unsafe fn get_persistent_descriptor_set(
     device: VkDevice,
     buffers: &[VkBuffer],
 ) -> Result<VkDescriptorSet, IcdError> {
-    let mut manager = DESCRIPTOR_MANAGER.lock()?;
+    get_or_write_persistent_descriptor_set(device, buffers).map(|(descriptor_set, _)| descriptor_set)
+}
+
+/// Like [`get_persistent_descriptor_set`], also returning how many
+/// descriptors were written: none when the set was cached
+///
+/// # Safety
+///
+/// As for [`get_persistent_descriptor_set`].
+pub unsafe fn get_or_write_persistent_descriptor_set(
+    device: VkDevice,
+    buffers: &[VkBuffer],
+) -> Result<(VkDescriptorSet, usize), IcdError> {
     let device_key = device.as_raw();
     
     // Create cache key from buffer handles
@@ -192,13 +210,13 @@ pub unsafe fn get_persistent_descriptor_set(
     let cache_key = device_key.wrapping_mul(0x9e3779b97f4a7c15) ^ binding_signature;
     
     // Check if we already have this descriptor set
-    if let Some(descriptor) = manager.descriptors.get(&cache_key) {
+    if let Some(descriptor) = DESCRIPTOR_MANAGER.lock()?.descriptors.get(&cache_key) {
         if descriptor.buffers == buffers {
-            return Ok(descriptor.descriptor_set);
+            return Ok((descriptor.descriptor_set, 0));
         }
     }
     
-    // Get or create layout and pool
+    // Get or create layout and pool; both take the manager lock themselves
     let layout = create_persistent_layout(device, buffers.len() as u32)?;
     let pool = get_persistent_pool(device, 1000, 10000)?;
     
@@ -213,9 +231,9 @@ pub unsafe fn get_persistent_descriptor_set(
     
     let mut descriptor_set = VkDescriptorSet::NULL;
     
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = device_icd(device) {
         if let Some(alloc_fn) = icd.allocate_descriptor_sets {
-            let result = alloc_fn(device, &alloc_info, &mut descriptor_set);
+            let result = icd_call!("vkAllocateDescriptorSets", alloc_fn(device, &alloc_info, &mut descriptor_set));
             if result != VkResult::Success {
                 return Err(IcdError::VulkanError(result));
             }
@@ -253,13 +271,14 @@ pub unsafe fn get_persistent_descriptor_set(
         });
     }
     
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = device_icd(device) {
         if let Some(update_fn) = icd.update_descriptor_sets {
-            update_fn(device, writes.len() as u32, writes.as_ptr(), 0, std::ptr::null());
+            icd_call!("vkUpdateDescriptorSets", update_fn(device, writes.len() as u32, writes.as_ptr(), 0, std::ptr::null()));
         }
     }
     
     // Cache the descriptor
+    let mut manager = DESCRIPTOR_MANAGER.lock()?;
     manager.generation += 1;
     let generation = manager.generation;
     let descriptors_for_device = manager
@@ -274,7 +293,7 @@ pub unsafe fn get_persistent_descriptor_set(
         generation,
     });
     
-    Ok(descriptor_set)
+    Ok((descriptor_set, writes.len()))
 }
 
 /// Create push constant range for parameters
@@ -329,9 +348,9 @@ pub unsafe fn create_compute_pipeline_layout(
     
     let mut layout = VkPipelineLayout::NULL;
     
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = device_icd(device) {
         if let Some(create_fn) = icd.create_pipeline_layout {
-            let result = create_fn(device, &create_info, std::ptr::null(), &mut layout);
+            let result = icd_call!("vkCreatePipelineLayout", create_fn(device, &create_info, std::ptr::null(), &mut layout));
             if result == VkResult::Success {
                 return Ok(layout);
             }
@@ -359,9 +378,9 @@ pub unsafe fn cleanup_persistent_descriptors(device: VkDevice) -> Result<(), Icd
     
     // Clean up pool
     if let Some(pool) = manager.pools.remove(&device_key) {
-        if let Some(icd) = super::icd_loader::get_icd() {
+        if let Some(icd) = device_icd(device) {
             if let Some(destroy_fn) = icd.destroy_descriptor_pool {
-                destroy_fn(device, pool, std::ptr::null());
+                icd_call!("vkDestroyDescriptorPool", destroy_fn(device, pool, std::ptr::null()));
             }
         }
     }
@@ -375,9 +394,9 @@ pub unsafe fn cleanup_persistent_descriptors(device: VkDevice) -> Result<(), Icd
     }
     for key in layout_keys {
         if let Some(layout) = manager.set0_layout.remove(&key) {
-            if let Some(icd) = super::icd_loader::get_icd() {
+            if let Some(icd) = device_icd(device) {
                 if let Some(destroy_fn) = icd.destroy_descriptor_set_layout {
-                    destroy_fn(device, layout, std::ptr::null());
+                    icd_call!("vkDestroyDescriptorSetLayout", destroy_fn(device, layout, std::ptr::null()));
                 }
             }
         }
@@ -3,6 +3,7 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use crate::implementation::child_objects;
 // keep single import
 use crate::implementation::icd_loader;
 
@@ -14,7 +15,7 @@ use crate::implementation::icd_loader;
 // 4. pShaderModule points to valid memory for writing the shader module handle
 // 5. The SPIR-V code in pCreateInfo is valid and contains only compute shader stages
 // 6. Code size matches the actual SPIR-V bytecode length
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateShaderModule(
     device: VkDevice,
     pCreateInfo: *const VkShaderModuleCreateInfo,
@@ -26,10 +27,10 @@ pub unsafe extern "C" fn vkCreateShaderModule(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.create_shader_module { return f(device, pCreateInfo, pAllocator, pShaderModule); }
+        if let Some(f) = icd.create_shader_module { return child_objects::created(device, icd_call!("vkCreateShaderModule", f(device, pCreateInfo, pAllocator, pShaderModule)), pShaderModule, 1); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(create_shader_module) = icd.create_shader_module { return create_shader_module(device, pCreateInfo, pAllocator, pShaderModule); }
+        if let Some(create_shader_module) = icd.create_shader_module { return child_objects::created(device, icd_call!("vkCreateShaderModule", create_shader_module(device, pCreateInfo, pAllocator, pShaderModule)), pShaderModule, 1); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -41,7 +42,7 @@ pub unsafe extern "C" fn vkCreateShaderModule(
 // 3. pAllocator matches the allocator used in vkCreateShaderModule
 // 4. No pipelines are currently using this shader module
 // 5. The shader module is not referenced by any pending operations
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyShaderModule(
     device: VkDevice,
     shaderModule: VkShaderModule,
@@ -50,13 +51,14 @@ pub unsafe extern "C" fn vkDestroyShaderModule(
     if device.is_null() || shaderModule.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, shaderModule);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_shader_module { f(device, shaderModule, pAllocator); }
+        if let Some(f) = icd.destroy_shader_module { icd_call!("vkDestroyShaderModule", f(device, shaderModule, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_shader_module) = icd.destroy_shader_module { destroy_shader_module(device, shaderModule, pAllocator); }
+        if let Some(destroy_shader_module) = icd.destroy_shader_module { icd_call!("vkDestroyShaderModule", destroy_shader_module(device, shaderModule, pAllocator)); }
     }
 }
 
@@ -69,7 +71,7 @@ pub unsafe extern "C" fn vkDestroyShaderModule(
 // 5. pAllocator is either null or points to valid allocation callbacks
 // 6. pPipelines points to an array with space for createInfoCount pipeline handles
 // 7. All shader modules, layouts, and descriptor set layouts referenced are valid
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateComputePipelines(
     device: VkDevice,
     pipelineCache: VkPipelineCache,
@@ -83,10 +85,10 @@ pub unsafe extern "C" fn vkCreateComputePipelines(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.create_compute_pipelines { return f(device, pipelineCache, createInfoCount, pCreateInfos, pAllocator, pPipelines); }
+        if let Some(f) = icd.create_compute_pipelines { return child_objects::created(device, icd_call!("vkCreateComputePipelines", f(device, pipelineCache, createInfoCount, pCreateInfos, pAllocator, pPipelines)), pPipelines, createInfoCount); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(create_compute_pipelines) = icd.create_compute_pipelines { return create_compute_pipelines(device, pipelineCache, createInfoCount, pCreateInfos, pAllocator, pPipelines); }
+        if let Some(create_compute_pipelines) = icd.create_compute_pipelines { return child_objects::created(device, icd_call!("vkCreateComputePipelines", create_compute_pipelines(device, pipelineCache, createInfoCount, pCreateInfos, pAllocator, pPipelines)), pPipelines, createInfoCount); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -98,7 +100,7 @@ pub unsafe extern "C" fn vkCreateComputePipelines(
 // 3. pAllocator matches the allocator used in vkCreateComputePipelines
 // 4. The pipeline is not currently bound to any command buffers
 // 5. No command buffers using this pipeline are executing on the GPU
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyPipeline(
     device: VkDevice,
     pipeline: VkPipeline,
@@ -107,13 +109,14 @@ pub unsafe extern "C" fn vkDestroyPipeline(
     if device.is_null() || pipeline.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, pipeline);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_pipeline { f(device, pipeline, pAllocator); }
+        if let Some(f) = icd.destroy_pipeline { icd_call!("vkDestroyPipeline", f(device, pipeline, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_pipeline) = icd.destroy_pipeline { destroy_pipeline(device, pipeline, pAllocator); }
+        if let Some(destroy_pipeline) = icd.destroy_pipeline { icd_call!("vkDestroyPipeline", destroy_pipeline(device, pipeline, pAllocator)); }
     }
 }
 
@@ -125,7 +128,7 @@ pub unsafe extern "C" fn vkDestroyPipeline(
 // 4. pPipelineLayout points to valid memory for writing the layout handle
 // 5. All descriptor set layouts referenced in pCreateInfo are valid
 // 6. Push constant ranges do not overlap and are within device limits
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreatePipelineLayout(
     device: VkDevice,
     pCreateInfo: *const VkPipelineLayoutCreateInfo,
@@ -137,10 +140,10 @@ pub unsafe extern "C" fn vkCreatePipelineLayout(
     }
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.create_pipeline_layout { return f(device, pCreateInfo, pAllocator, pPipelineLayout); }
+        if let Some(f) = icd.create_pipeline_layout { return child_objects::created(device, icd_call!("vkCreatePipelineLayout", f(device, pCreateInfo, pAllocator, pPipelineLayout)), pPipelineLayout, 1); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(create_pipeline_layout) = icd.create_pipeline_layout { return create_pipeline_layout(device, pCreateInfo, pAllocator, pPipelineLayout); }
+        if let Some(create_pipeline_layout) = icd.create_pipeline_layout { return child_objects::created(device, icd_call!("vkCreatePipelineLayout", create_pipeline_layout(device, pCreateInfo, pAllocator, pPipelineLayout)), pPipelineLayout, 1); }
     }
     VkResult::ErrorInitializationFailed
 }
@@ -152,7 +155,7 @@ pub unsafe extern "C" fn vkCreatePipelineLayout(
 // 3. pAllocator matches the allocator used in vkCreatePipelineLayout
 // 4. No pipelines are currently using this layout
 // 5. No command buffers reference this layout in their current recordings
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyPipelineLayout(
     device: VkDevice,
     pipelineLayout: VkPipelineLayout,
@@ -161,13 +164,14 @@ pub unsafe extern "C" fn vkDestroyPipelineLayout(
     if device.is_null() || pipelineLayout.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, pipelineLayout);
     
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        if let Some(f) = icd.destroy_pipeline_layout { f(device, pipelineLayout, pAllocator); }
+        if let Some(f) = icd.destroy_pipeline_layout { icd_call!("vkDestroyPipelineLayout", f(device, pipelineLayout, pAllocator)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
-        if let Some(destroy_pipeline_layout) = icd.destroy_pipeline_layout { destroy_pipeline_layout(device, pipelineLayout, pAllocator); }
+        if let Some(destroy_pipeline_layout) = icd.destroy_pipeline_layout { icd_call!("vkDestroyPipelineLayout", destroy_pipeline_layout(device, pipelineLayout, pAllocator)); }
     }
 }
 
@@ -179,7 +183,7 @@ pub unsafe extern "C" fn vkDestroyPipelineLayout(
 // 4. pCommandPool points to valid memory for writing the pool handle
 // 5. The queue family index in pCreateInfo is valid for this device
 // 6. Pool creation flags are appropriate for intended usage
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateCommandPool(
     device: VkDevice,
     pCreateInfo: *const VkCommandPoolCreateInfo,
@@ -190,31 +194,31 @@ pub unsafe extern "C" fn vkCreateCommandPool(
         return VkResult::ErrorInitializationFailed;
     }
     // Route via owning ICD if known
-    log::debug!("[vkCreateCommandPool] Checking device ICD mapping");
+    kronos_log!(Debug, "[vkCreateCommandPool] Checking device ICD mapping");
     if let Some(icd) = icd_loader::icd_for_device(device) {
-        log::debug!("[vkCreateCommandPool] Found device ICD");
+        kronos_log!(Debug, "[vkCreateCommandPool] Found device ICD");
         if let Some(f) = icd.create_command_pool {
-            log::debug!("[vkCreateCommandPool] Calling ICD's create_command_pool");
-            let res = f(device, pCreateInfo, pAllocator, pCommandPool);
+            kronos_log!(Debug, "[vkCreateCommandPool] Calling ICD's create_command_pool");
+            let res = icd_call!("vkCreateCommandPool", f(device, pCreateInfo, pAllocator, pCommandPool));
             if res == VkResult::Success {
                 icd_loader::register_command_pool_icd(*pCommandPool, &icd);
             }
-            return res;
+            return child_objects::created(device, res, pCommandPool, 1);
         } else {
-            log::warn!("[vkCreateCommandPool] Device ICD found but create_command_pool is null");
+            kronos_log!(Warn, "[vkCreateCommandPool] Device ICD found but create_command_pool is null");
         }
     }
     // Fallback
-    log::debug!("[vkCreateCommandPool] Using fallback single ICD");
+    kronos_log!(Debug, "[vkCreateCommandPool] Using fallback single ICD");
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(create_command_pool) = icd.create_command_pool {
-            log::debug!("[vkCreateCommandPool] Calling fallback ICD's create_command_pool");
-            return create_command_pool(device, pCreateInfo, pAllocator, pCommandPool);
+            kronos_log!(Debug, "[vkCreateCommandPool] Calling fallback ICD's create_command_pool");
+            return child_objects::created(device, icd_call!("vkCreateCommandPool", create_command_pool(device, pCreateInfo, pAllocator, pCommandPool)), pCommandPool, 1);
         } else {
-            log::warn!("[vkCreateCommandPool] Fallback ICD has no create_command_pool function");
+            kronos_log!(Warn, "[vkCreateCommandPool] Fallback ICD has no create_command_pool function");
         }
     } else {
-        log::warn!("[vkCreateCommandPool] No fallback ICD available");
+        kronos_log!(Warn, "[vkCreateCommandPool] No fallback ICD available");
     }
     VkResult::ErrorInitializationFailed
 }
@@ -226,7 +230,7 @@ pub unsafe extern "C" fn vkCreateCommandPool(
 // 3. pAllocator matches the allocator used in vkCreateCommandPool
 // 4. All command buffers allocated from this pool have finished execution
 // 5. No command buffers from this pool are currently being recorded
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyCommandPool(
     device: VkDevice,
     commandPool: VkCommandPool,
@@ -235,14 +239,15 @@ pub unsafe extern "C" fn vkDestroyCommandPool(
     if device.is_null() || commandPool.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, commandPool);
     if let Some(icd) = icd_loader::icd_for_command_pool(commandPool) {
-        if let Some(f) = icd.destroy_command_pool { f(device, commandPool, pAllocator); }
+        if let Some(f) = icd.destroy_command_pool { icd_call!("vkDestroyCommandPool", f(device, commandPool, pAllocator)); }
         icd_loader::unregister_command_pool(commandPool);
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(destroy_command_pool) = icd.destroy_command_pool {
-            destroy_command_pool(device, commandPool, pAllocator);
+            icd_call!("vkDestroyCommandPool", destroy_command_pool(device, commandPool, pAllocator));
         }
     }
 }
@@ -254,7 +259,7 @@ pub unsafe extern "C" fn vkDestroyCommandPool(
 // 3. pCommandBuffers points to an array with space for commandBufferCount handles
 // 4. The command pool in pAllocateInfo is valid and supports the requested level
 // 5. The command pool has sufficient space for the requested buffers
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkAllocateCommandBuffers(
     device: VkDevice,
     pAllocateInfo: *const VkCommandBufferAllocateInfo,
@@ -267,7 +272,7 @@ pub unsafe extern "C" fn vkAllocateCommandBuffers(
     let pool = (*pAllocateInfo).commandPool;
     if let Some(icd) = icd_loader::icd_for_command_pool(pool) {
         if let Some(f) = icd.allocate_command_buffers {
-            let res = f(device, pAllocateInfo, pCommandBuffers);
+            let res = icd_call!("vkAllocateCommandBuffers", f(device, pAllocateInfo, pCommandBuffers));
             if res == VkResult::Success {
                 let count = (*pAllocateInfo).commandBufferCount as isize;
                 for i in 0..count {
@@ -280,7 +285,7 @@ pub unsafe extern "C" fn vkAllocateCommandBuffers(
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(allocate_command_buffers) = icd.allocate_command_buffers {
-            return allocate_command_buffers(device, pAllocateInfo, pCommandBuffers);
+            return icd_call!("vkAllocateCommandBuffers", allocate_command_buffers(device, pAllocateInfo, pCommandBuffers));
         }
     }
     VkResult::ErrorInitializationFailed
@@ -295,7 +300,7 @@ pub unsafe extern "C" fn vkAllocateCommandBuffers(
 // 5. All command buffers were allocated from the specified pool
 // 6. None of the command buffers are currently executing on the GPU
 // 7. Command buffers are not in the recording state
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkFreeCommandBuffers(
     device: VkDevice,
     commandPool: VkCommandPool,
@@ -306,7 +311,7 @@ pub unsafe extern "C" fn vkFreeCommandBuffers(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_pool(commandPool) {
-        if let Some(f) = icd.free_command_buffers { f(device, commandPool, commandBufferCount, pCommandBuffers); }
+        if let Some(f) = icd.free_command_buffers { icd_call!("vkFreeCommandBuffers", f(device, commandPool, commandBufferCount, pCommandBuffers)); }
         for i in 0..(commandBufferCount as isize) {
             let cb = *pCommandBuffers.offset(i);
             icd_loader::unregister_command_buffer(cb);
@@ -315,7 +320,7 @@ pub unsafe extern "C" fn vkFreeCommandBuffers(
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(free_command_buffers) = icd.free_command_buffers {
-            free_command_buffers(device, commandPool, commandBufferCount, pCommandBuffers);
+            icd_call!("vkFreeCommandBuffers", free_command_buffers(device, commandPool, commandBufferCount, pCommandBuffers));
         }
     }
 }
@@ -327,7 +332,7 @@ pub unsafe extern "C" fn vkFreeCommandBuffers(
 // 3. The command buffer is not currently being recorded
 // 4. The command buffer is not currently executing on the GPU
 // 5. Usage flags in pBeginInfo match the intended recording pattern
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkBeginCommandBuffer(
     commandBuffer: VkCommandBuffer,
     pBeginInfo: *const VkCommandBufferBeginInfo,
@@ -336,11 +341,11 @@ pub unsafe extern "C" fn vkBeginCommandBuffer(
         return VkResult::ErrorInitializationFailed;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.begin_command_buffer { return f(commandBuffer, pBeginInfo); }
+        if let Some(f) = icd.begin_command_buffer { return icd_call!("vkBeginCommandBuffer", f(commandBuffer, pBeginInfo)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(begin_command_buffer) = icd.begin_command_buffer {
-            return begin_command_buffer(commandBuffer, pBeginInfo);
+            return icd_call!("vkBeginCommandBuffer", begin_command_buffer(commandBuffer, pBeginInfo));
         }
     }
     VkResult::ErrorInitializationFailed
@@ -352,7 +357,7 @@ pub unsafe extern "C" fn vkBeginCommandBuffer(
 // 2. All commands recorded since vkBeginCommandBuffer are valid
 // 3. The command buffer was successfully put into recording state
 // 4. All nested command buffer recordings have been properly ended
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkEndCommandBuffer(
     commandBuffer: VkCommandBuffer,
 ) -> VkResult {
@@ -360,11 +365,11 @@ pub unsafe extern "C" fn vkEndCommandBuffer(
         return VkResult::ErrorInitializationFailed;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.end_command_buffer { return f(commandBuffer); }
+        if let Some(f) = icd.end_command_buffer { return icd_call!("vkEndCommandBuffer", f(commandBuffer)); }
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(end_command_buffer) = icd.end_command_buffer {
-            return end_command_buffer(commandBuffer);
+            return icd_call!("vkEndCommandBuffer", end_command_buffer(commandBuffer));
         }
     }
     VkResult::ErrorInitializationFailed
@@ -377,7 +382,7 @@ pub unsafe extern "C" fn vkEndCommandBuffer(
 // 3. pipeline is a valid VkPipeline compatible with the bind point
 // 4. The pipeline's layout is compatible with subsequently bound descriptor sets
 // 5. The command buffer supports the queue family that created the pipeline
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdBindPipeline(
     commandBuffer: VkCommandBuffer,
     pipelineBindPoint: VkPipelineBindPoint,
@@ -387,12 +392,12 @@ pub unsafe extern "C" fn vkCmdBindPipeline(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_bind_pipeline { f(commandBuffer, pipelineBindPoint, pipeline); }
+        if let Some(f) = icd.cmd_bind_pipeline { icd_call!("vkCmdBindPipeline", f(commandBuffer, pipelineBindPoint, pipeline)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_bind_pipeline) = icd.cmd_bind_pipeline {
-            cmd_bind_pipeline(commandBuffer, pipelineBindPoint, pipeline);
+            icd_call!("vkCmdBindPipeline", cmd_bind_pipeline(commandBuffer, pipelineBindPoint, pipeline));
         }
     }
 }
@@ -406,7 +411,7 @@ pub unsafe extern "C" fn vkCmdBindPipeline(
 // 5. All descriptor sets are compatible with the pipeline layout
 // 6. Dynamic offsets array matches the dynamic descriptors in the sets
 // 7. firstSet + descriptorSetCount <= max sets supported by layout
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdBindDescriptorSets(
     commandBuffer: VkCommandBuffer,
     pipelineBindPoint: VkPipelineBindPoint,
@@ -421,13 +426,13 @@ pub unsafe extern "C" fn vkCmdBindDescriptorSets(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_bind_descriptor_sets { f(commandBuffer, pipelineBindPoint, layout, firstSet, descriptorSetCount, pDescriptorSets, dynamicOffsetCount, pDynamicOffsets); }
+        if let Some(f) = icd.cmd_bind_descriptor_sets { icd_call!("vkCmdBindDescriptorSets", f(commandBuffer, pipelineBindPoint, layout, firstSet, descriptorSetCount, pDescriptorSets, dynamicOffsetCount, pDynamicOffsets)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_bind_descriptor_sets) = icd.cmd_bind_descriptor_sets {
-            cmd_bind_descriptor_sets(commandBuffer, pipelineBindPoint, layout, firstSet, 
-                                   descriptorSetCount, pDescriptorSets, dynamicOffsetCount, pDynamicOffsets);
+            icd_call!("vkCmdBindDescriptorSets", cmd_bind_descriptor_sets(commandBuffer, pipelineBindPoint, layout, firstSet, 
+                                   descriptorSetCount, pDescriptorSets, dynamicOffsetCount, pDynamicOffsets));
         }
     }
 }
@@ -440,7 +445,7 @@ pub unsafe extern "C" fn vkCmdBindDescriptorSets(
 // 4. offset and size are within the push constant range defined in the layout
 // 5. pValues points to at least size bytes of valid memory
 // 6. The push constant data is properly aligned for the target architecture
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdPushConstants(
     commandBuffer: VkCommandBuffer,
     layout: VkPipelineLayout,
@@ -453,12 +458,12 @@ pub unsafe extern "C" fn vkCmdPushConstants(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_push_constants { f(commandBuffer, layout, stageFlags, offset, size, pValues); }
+        if let Some(f) = icd.cmd_push_constants { icd_call!("vkCmdPushConstants", f(commandBuffer, layout, stageFlags, offset, size, pValues)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_push_constants) = icd.cmd_push_constants {
-            cmd_push_constants(commandBuffer, layout, stageFlags, offset, size, pValues);
+            icd_call!("vkCmdPushConstants", cmd_push_constants(commandBuffer, layout, stageFlags, offset, size, pValues));
         }
     }
 }
@@ -470,7 +475,7 @@ pub unsafe extern "C" fn vkCmdPushConstants(
 // 3. groupCountX, Y, Z are within device limits and > 0
 // 4. All descriptor sets required by the pipeline are bound
 // 5. All resources referenced by descriptors are valid and accessible
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdDispatch(
     commandBuffer: VkCommandBuffer,
     groupCountX: u32,
@@ -481,12 +486,102 @@ pub unsafe extern "C" fn vkCmdDispatch(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_dispatch { f(commandBuffer, groupCountX, groupCountY, groupCountZ); }
+        if let Some(f) = icd.cmd_dispatch { icd_call!("vkCmdDispatch", f(commandBuffer, groupCountX, groupCountY, groupCountZ)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_dispatch) = icd.cmd_dispatch {
-            cmd_dispatch(commandBuffer, groupCountX, groupCountY, groupCountZ);
+            icd_call!("vkCmdDispatch", cmd_dispatch(commandBuffer, groupCountX, groupCountY, groupCountZ));
+        }
+    }
+}
+
+/// Dispatch compute work with workgroup IDs starting at a base
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. The bound compute pipeline was created with VK_PIPELINE_CREATE_DISPATCH_BASE
+//    if any base group is non-zero
+// 3. Base plus count stays within the device's workgroup count limits
+// 4. All descriptor sets required by the pipeline are bound
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdDispatchBase(
+    commandBuffer: VkCommandBuffer,
+    baseGroupX: u32,
+    baseGroupY: u32,
+    baseGroupZ: u32,
+    groupCountX: u32,
+    groupCountY: u32,
+    groupCountZ: u32,
+) {
+    if commandBuffer.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_dispatch_base {
+            icd_call!("vkCmdDispatchBase", f(commandBuffer, baseGroupX, baseGroupY, baseGroupZ, groupCountX, groupCountY, groupCountZ));
+        }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_dispatch_base) = icd.cmd_dispatch_base {
+            icd_call!(
+                "vkCmdDispatchBase",
+                cmd_dispatch_base(commandBuffer, baseGroupX, baseGroupY, baseGroupZ, groupCountX, groupCountY, groupCountZ)
+            );
+        }
+    }
+}
+
+/// Begin a block of commands discarded when a predicate in a buffer is zero
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state, outside
+//    any conditional rendering block
+// 2. pConditionalRenderingBegin points to a valid VkConditionalRenderingBeginInfoEXT
+// 3. Its buffer has CONDITIONAL_RENDERING_EXT usage and its offset is a
+//    multiple of 4 within the buffer
+// 4. VK_EXT_conditional_rendering was enabled on the device
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdBeginConditionalRenderingEXT(
+    commandBuffer: VkCommandBuffer,
+    pConditionalRenderingBegin: *const VkConditionalRenderingBeginInfoEXT,
+) {
+    if commandBuffer.is_null() || pConditionalRenderingBegin.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_begin_conditional_rendering {
+            icd_call!("vkCmdBeginConditionalRenderingEXT", f(commandBuffer, pConditionalRenderingBegin));
+        }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_begin_conditional_rendering) = icd.cmd_begin_conditional_rendering {
+            icd_call!(
+                "vkCmdBeginConditionalRenderingEXT",
+                cmd_begin_conditional_rendering(commandBuffer, pConditionalRenderingBegin)
+            );
+        }
+    }
+}
+
+/// End the conditional rendering block begun last
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. A conditional rendering block was begun in commandBuffer and not yet ended
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdEndConditionalRenderingEXT(commandBuffer: VkCommandBuffer) {
+    if commandBuffer.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_end_conditional_rendering {
+            icd_call!("vkCmdEndConditionalRenderingEXT", f(commandBuffer));
+        }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_end_conditional_rendering) = icd.cmd_end_conditional_rendering {
+            icd_call!("vkCmdEndConditionalRenderingEXT", cmd_end_conditional_rendering(commandBuffer));
         }
     }
 }
@@ -499,7 +594,7 @@ pub unsafe extern "C" fn vkCmdDispatch(
 // 4. offset is within the buffer bounds and properly aligned
 // 5. The buffer contains valid VkDispatchIndirectCommand structure at offset
 // 6. All descriptor sets required by the pipeline are bound
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdDispatchIndirect(
     commandBuffer: VkCommandBuffer,
     buffer: VkBuffer,
@@ -509,12 +604,12 @@ pub unsafe extern "C" fn vkCmdDispatchIndirect(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_dispatch_indirect { f(commandBuffer, buffer, offset); }
+        if let Some(f) = icd.cmd_dispatch_indirect { icd_call!("vkCmdDispatchIndirect", f(commandBuffer, buffer, offset)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_dispatch_indirect) = icd.cmd_dispatch_indirect {
-            cmd_dispatch_indirect(commandBuffer, buffer, offset);
+            icd_call!("vkCmdDispatchIndirect", cmd_dispatch_indirect(commandBuffer, buffer, offset));
         }
     }
 }
@@ -528,7 +623,7 @@ pub unsafe extern "C" fn vkCmdDispatchIndirect(
 // 5. All buffer memory barriers reference valid buffers and ranges
 // 6. Pipeline stages are appropriate for compute operations
 // 7. Memory barriers provide necessary synchronization guarantees
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdPipelineBarrier(
     commandBuffer: VkCommandBuffer,
     srcStageMask: VkPipelineStageFlags,
@@ -539,22 +634,22 @@ pub unsafe extern "C" fn vkCmdPipelineBarrier(
     bufferMemoryBarrierCount: u32,
     pBufferMemoryBarriers: *const VkBufferMemoryBarrier,
     imageMemoryBarrierCount: u32,
-    pImageMemoryBarriers: *const libc::c_void,
+    pImageMemoryBarriers: *const VkImageMemoryBarrier,
 ) {
     if commandBuffer.is_null() {
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_pipeline_barrier { f(commandBuffer, srcStageMask, dstStageMask, dependencyFlags,
+        if let Some(f) = icd.cmd_pipeline_barrier { icd_call!("vkCmdPipelineBarrier", f(commandBuffer, srcStageMask, dstStageMask, dependencyFlags,
                                memoryBarrierCount, pMemoryBarriers, bufferMemoryBarrierCount,
-                               pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers); }
+                               pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_pipeline_barrier) = icd.cmd_pipeline_barrier {
-            cmd_pipeline_barrier(commandBuffer, srcStageMask, dstStageMask, dependencyFlags,
+            icd_call!("vkCmdPipelineBarrier", cmd_pipeline_barrier(commandBuffer, srcStageMask, dstStageMask, dependencyFlags,
                                memoryBarrierCount, pMemoryBarriers, bufferMemoryBarrierCount,
-                               pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers);
+                               pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers));
         }
     }
 }
@@ -567,7 +662,7 @@ pub unsafe extern "C" fn vkCmdPipelineBarrier(
 // 4. All copy regions are within the bounds of their respective buffers
 // 5. Source and destination ranges do not overlap (unless same buffer with identical ranges)
 // 6. Buffers support the required usage flags for transfer operations
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdCopyBuffer(
     commandBuffer: VkCommandBuffer,
     srcBuffer: VkBuffer,
@@ -580,12 +675,12 @@ pub unsafe extern "C" fn vkCmdCopyBuffer(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_copy_buffer { f(commandBuffer, srcBuffer, dstBuffer, regionCount, pRegions); }
+        if let Some(f) = icd.cmd_copy_buffer { icd_call!("vkCmdCopyBuffer", f(commandBuffer, srcBuffer, dstBuffer, regionCount, pRegions)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_copy_buffer) = icd.cmd_copy_buffer {
-            cmd_copy_buffer(commandBuffer, srcBuffer, dstBuffer, regionCount, pRegions);
+            icd_call!("vkCmdCopyBuffer", cmd_copy_buffer(commandBuffer, srcBuffer, dstBuffer, regionCount, pRegions));
         }
     }
 }
@@ -597,7 +692,7 @@ pub unsafe extern "C" fn vkCmdCopyBuffer(
 // 3. stageMask specifies valid pipeline stages for compute operations
 // 4. The event will be signaled when the specified pipeline stages complete
 // 5. Subsequent vkCmdWaitEvents calls can safely wait on this event
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdSetEvent(
     commandBuffer: VkCommandBuffer,
     event: VkEvent,
@@ -607,12 +702,12 @@ pub unsafe extern "C" fn vkCmdSetEvent(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_set_event { f(commandBuffer, event, stageMask); }
+        if let Some(f) = icd.cmd_set_event { icd_call!("vkCmdSetEvent", f(commandBuffer, event, stageMask)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_set_event) = icd.cmd_set_event {
-            cmd_set_event(commandBuffer, event, stageMask);
+            icd_call!("vkCmdSetEvent", cmd_set_event(commandBuffer, event, stageMask));
         }
     }
 }
@@ -624,7 +719,7 @@ pub unsafe extern "C" fn vkCmdSetEvent(
 // 3. stageMask specifies valid pipeline stages for compute operations
 // 4. The event will be reset when the specified pipeline stages complete
 // 5. No other command buffers should be waiting on this event when it's reset
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdResetEvent(
     commandBuffer: VkCommandBuffer,
     event: VkEvent,
@@ -634,12 +729,12 @@ pub unsafe extern "C" fn vkCmdResetEvent(
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_reset_event { f(commandBuffer, event, stageMask); }
+        if let Some(f) = icd.cmd_reset_event { icd_call!("vkCmdResetEvent", f(commandBuffer, event, stageMask)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_reset_event) = icd.cmd_reset_event {
-            cmd_reset_event(commandBuffer, event, stageMask);
+            icd_call!("vkCmdResetEvent", cmd_reset_event(commandBuffer, event, stageMask));
         }
     }
 }
@@ -653,7 +748,7 @@ pub unsafe extern "C" fn vkCmdResetEvent(
 // 5. Memory barrier arrays match their respective counts and are valid
 // 6. All buffer memory barriers reference valid buffers and ranges
 // 7. Pipeline stages and barriers provide necessary synchronization
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCmdWaitEvents(
     commandBuffer: VkCommandBuffer,
     eventCount: u32,
@@ -665,22 +760,22 @@ pub unsafe extern "C" fn vkCmdWaitEvents(
     bufferMemoryBarrierCount: u32,
     pBufferMemoryBarriers: *const VkBufferMemoryBarrier,
     imageMemoryBarrierCount: u32,
-    pImageMemoryBarriers: *const libc::c_void,
+    pImageMemoryBarriers: *const VkImageMemoryBarrier,
 ) {
     if commandBuffer.is_null() || eventCount == 0 || pEvents.is_null() {
         return;
     }
     if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
-        if let Some(f) = icd.cmd_wait_events { f(commandBuffer, eventCount, pEvents, srcStageMask, dstStageMask,
+        if let Some(f) = icd.cmd_wait_events { icd_call!("vkCmdWaitEvents", f(commandBuffer, eventCount, pEvents, srcStageMask, dstStageMask,
                           memoryBarrierCount, pMemoryBarriers, bufferMemoryBarrierCount,
-                          pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers); }
+                          pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers)); }
         return;
     }
     if let Some(icd) = super::forward::get_icd_if_enabled() {
         if let Some(cmd_wait_events) = icd.cmd_wait_events {
-            cmd_wait_events(commandBuffer, eventCount, pEvents, srcStageMask, dstStageMask,
+            icd_call!("vkCmdWaitEvents", cmd_wait_events(commandBuffer, eventCount, pEvents, srcStageMask, dstStageMask,
                           memoryBarrierCount, pMemoryBarriers, bufferMemoryBarrierCount,
-                          pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers);
+                          pBufferMemoryBarriers, imageMemoryBarrierCount, pImageMemoryBarriers));
         }
     }
 }
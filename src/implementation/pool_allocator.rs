@@ -15,10 +15,116 @@ use super::error::IcdError;
 /// Slab size for suballocation (256 KiB default)
 const SLAB_SIZE: VkDeviceSize = 256 * 1024;
 
+/// Largest slab exponential growth produces (32 MiB default)
+const MAX_SLAB_SIZE: VkDeviceSize = 32 * 1024 * 1024;
+
 /// Minimum allocation size (64 KiB)
 #[allow(dead_code)]
 const MIN_ALLOCATION_SIZE: VkDeviceSize = 64 * 1024;
 
+/// How a slab picks the free range for an allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitStrategy {
+    /// Lowest free range that fits
+    FirstFit,
+    /// Smallest free range that fits, keeping large ranges for large requests
+    BestFit,
+    /// Power-of-two blocks at naturally aligned offsets, so freed neighbours
+    /// always merge back into larger blocks; slabs are rounded up to a
+    /// power of two
+    Buddy,
+}
+
+/// Size of each new slab of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabGrowth {
+    /// Every slab has the configured slab size
+    Fixed,
+    /// Each slab doubles the previous one, up to `max_slab_size`
+    Exponential { max_slab_size: VkDeviceSize },
+}
+
+/// Suballocation settings of one memory pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Size of the first slab
+    pub slab_size: VkDeviceSize,
+    pub growth: SlabGrowth,
+    pub fit: FitStrategy,
+}
+
+impl Default for PoolConfig {
+    /// 256 KiB slabs doubling up to 32 MiB, best-fit; see the
+    /// `pool_fragmentation` benchmark
+    fn default() -> Self {
+        Self {
+            slab_size: SLAB_SIZE,
+            growth: SlabGrowth::Exponential { max_slab_size: MAX_SLAB_SIZE },
+            fit: FitStrategy::BestFit,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Size of the slab following `slab_count` existing slabs, large enough
+    /// for `request`
+    fn slab_size(&self, slab_count: usize, request: VkDeviceSize) -> VkDeviceSize {
+        let size = match self.growth {
+            SlabGrowth::Fixed => self.slab_size,
+            SlabGrowth::Exponential { max_slab_size } => {
+                let doublings = slab_count.min(63) as u32;
+                self.slab_size
+                    .checked_mul(1 << doublings)
+                    .map_or(max_slab_size, |size| size.min(max_slab_size))
+                    .max(self.slab_size)
+            }
+        };
+        let size = size.max(request);
+        match self.fit {
+            FitStrategy::Buddy => size.next_power_of_two(),
+            FitStrategy::FirstFit | FitStrategy::BestFit => size,
+        }
+    }
+}
+
+/// Pool settings of a device, one [`PoolConfig`] per [`PoolType`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryConfig {
+    pub device_local: PoolConfig,
+    pub host_visible_coherent: PoolConfig,
+    pub host_visible_cached: PoolConfig,
+}
+
+impl MemoryConfig {
+    /// Use `config` for every pool
+    pub fn uniform(config: PoolConfig) -> Self {
+        Self {
+            device_local: config,
+            host_visible_coherent: config,
+            host_visible_cached: config,
+        }
+    }
+
+    /// Replace the settings of one pool
+    pub fn pool(mut self, pool_type: PoolType, config: PoolConfig) -> Self {
+        match pool_type {
+            PoolType::DeviceLocal => self.device_local = config,
+            PoolType::HostVisibleCoherent => self.host_visible_coherent = config,
+            PoolType::HostVisibleCached => self.host_visible_cached = config,
+        }
+        self
+    }
+
+    /// Settings of one pool
+    pub fn for_pool(&self, pool_type: PoolType) -> PoolConfig {
+        match pool_type {
+            PoolType::DeviceLocal => self.device_local,
+            PoolType::HostVisibleCoherent => self.host_visible_coherent,
+            PoolType::HostVisibleCached => self.host_visible_cached,
+        }
+    }
+}
+
 /// Memory pool types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PoolType {
@@ -50,20 +156,33 @@ impl PoolType {
     }
 }
 
-/// A single allocation within a slab
-#[derive(Debug)]
-struct SubAllocation {
+/// A range of a slab, allocated or free
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Range {
     offset: VkDeviceSize,
     size: VkDeviceSize,
-    in_use: bool,
+}
+
+impl Range {
+    fn end(&self) -> VkDeviceSize {
+        self.offset + self.size
+    }
 }
 
 /// A slab of memory that can be subdivided
+///
+/// Free space is kept as an offset-ordered list of ranges that are merged
+/// with their neighbours on free, so no two free ranges are ever adjacent.
+/// Allocating shrinks or splits a free range in place and freeing grows one,
+/// so both lists stay as long as the number of live allocations.
 struct MemorySlab {
     memory: VkDeviceMemory,
     size: VkDeviceSize,
     mapped_ptr: Option<*mut std::ffi::c_void>,
-    allocations: Vec<SubAllocation>,
+    /// Live allocations, sorted by offset
+    allocations: Vec<Range>,
+    /// Free ranges, sorted by offset and never adjacent
+    free: Vec<Range>,
     free_space: VkDeviceSize,
 }
 
@@ -72,61 +191,94 @@ unsafe impl Send for MemorySlab {}
 unsafe impl Sync for MemorySlab {}
 
 impl MemorySlab {
+    fn new(memory: VkDeviceMemory, size: VkDeviceSize, mapped_ptr: Option<*mut std::ffi::c_void>) -> Self {
+        Self {
+            memory,
+            size,
+            mapped_ptr,
+            allocations: Vec::new(),
+            free: vec![Range { offset: 0, size }],
+            free_space: size,
+        }
+    }
+
+    /// Size of the largest free range
+    fn largest_free_range(&self) -> VkDeviceSize {
+        self.free.iter().map(|range| range.size).max().unwrap_or(0)
+    }
+
     /// Try to allocate from this slab
-    fn allocate(&mut self, size: VkDeviceSize, alignment: VkDeviceSize) -> Option<VkDeviceSize> {
+    fn allocate(&mut self, size: VkDeviceSize, alignment: VkDeviceSize, fit: FitStrategy) -> Option<VkDeviceSize> {
+        let (size, alignment) = match fit {
+            FitStrategy::Buddy => {
+                let block = size.max(alignment).max(1).next_power_of_two();
+                (block, block)
+            }
+            FitStrategy::FirstFit | FitStrategy::BestFit => (size, alignment.max(1)),
+        };
         if self.free_space < size {
             return None;
         }
-        
-        // Find a free spot (first-fit algorithm)
-        let mut current_offset = 0;
-        
-        for alloc in &self.allocations {
-            if !alloc.in_use {
-                continue;
+
+        let (index, offset) = {
+            let mut candidates = self.free.iter().enumerate().filter_map(|(index, range)| {
+                let aligned_offset = (range.offset + alignment - 1) & !(alignment - 1);
+                (aligned_offset + size <= range.end()).then_some((index, aligned_offset, range.size))
+            });
+            match fit {
+                FitStrategy::FirstFit => candidates.next(),
+                // A buddy allocator splits the smallest free block that fits
+                FitStrategy::BestFit | FitStrategy::Buddy => candidates.min_by_key(|&(_, _, free)| free),
             }
-            
-            // Check if we can fit before this allocation
-            let aligned_offset = (current_offset + alignment - 1) & !(alignment - 1);
-            if aligned_offset + size <= alloc.offset {
-                // Found a spot
-                self.allocations.push(SubAllocation {
-                    offset: aligned_offset,
-                    size,
-                    in_use: true,
-                });
-                self.free_space -= size;
-                return Some(aligned_offset);
+            .map(|(index, offset, _)| (index, offset))
+        }?;
+
+        // Alignment padding stays free in front of the allocation
+        let range = self.free[index];
+        let head = Range { offset: range.offset, size: offset - range.offset };
+        let tail = Range { offset: offset + size, size: range.end() - (offset + size) };
+        match (head.size > 0, tail.size > 0) {
+            (false, false) => {
+                self.free.remove(index);
+            }
+            (true, false) => self.free[index] = head,
+            (false, true) => self.free[index] = tail,
+            (true, true) => {
+                self.free[index] = head;
+                self.free.insert(index + 1, tail);
             }
-            
-            current_offset = alloc.offset + alloc.size;
-        }
-        
-        // Check if we can fit at the end
-        let aligned_offset = (current_offset + alignment - 1) & !(alignment - 1);
-        if aligned_offset + size <= self.size {
-            self.allocations.push(SubAllocation {
-                offset: aligned_offset,
-                size,
-                in_use: true,
-            });
-            self.free_space -= size;
-            Some(aligned_offset)
-        } else {
-            None
         }
+
+        let position = self.allocations.partition_point(|a| a.offset < offset);
+        self.allocations.insert(position, Range { offset, size });
+        self.free_space -= size;
+        Some(offset)
     }
     
-    /// Free an allocation
+    /// Free an allocation, merging it with adjacent free ranges
     fn free(&mut self, offset: VkDeviceSize) -> bool {
-        if let Some(alloc) = self.allocations.iter_mut().find(|a| a.offset == offset) {
-            if alloc.in_use {
-                alloc.in_use = false;
-                self.free_space += alloc.size;
-                return true;
+        let range = match self.allocations.binary_search_by_key(&offset, |a| a.offset) {
+            Ok(index) => self.allocations.remove(index),
+            Err(_) => return false,
+        };
+        self.free_space += range.size;
+
+        let index = self.free.partition_point(|free| free.offset < range.offset);
+        let merges_prev = index > 0 && self.free[index - 1].end() == range.offset;
+        let merges_next = index < self.free.len() && self.free[index].offset == range.end();
+        match (merges_prev, merges_next) {
+            (true, true) => {
+                let next = self.free.remove(index);
+                self.free[index - 1].size += range.size + next.size;
+            }
+            (true, false) => self.free[index - 1].size += range.size,
+            (false, true) => {
+                self.free[index].offset = range.offset;
+                self.free[index].size += range.size;
             }
+            (false, false) => self.free.insert(index, range),
         }
-        false
+        true
     }
 }
 
@@ -135,16 +287,27 @@ struct MemoryPool {
     device: VkDevice,
     pool_type: PoolType,
     memory_type_index: u32,
+    /// Property flags of the memory type
+    memory_flags: VkMemoryPropertyFlags,
+    config: PoolConfig,
     slabs: Vec<MemorySlab>,
     total_allocated: VkDeviceSize,
 }
 
 impl MemoryPool {
-    fn new(device: VkDevice, pool_type: PoolType, memory_type_index: u32) -> Self {
+    fn new(
+        device: VkDevice,
+        pool_type: PoolType,
+        memory_type_index: u32,
+        memory_flags: VkMemoryPropertyFlags,
+        config: PoolConfig,
+    ) -> Self {
         Self {
             device,
             pool_type,
             memory_type_index,
+            memory_flags,
+            config,
             slabs: Vec::new(),
             total_allocated: 0,
         }
@@ -168,7 +331,7 @@ impl MemoryPool {
     ) -> Result<(VkDeviceMemory, VkDeviceSize, Option<*mut std::ffi::c_void>), IcdError> {
         // Try existing slabs first
         for slab in &mut self.slabs {
-            if let Some(offset) = slab.allocate(size, alignment) {
+            if let Some(offset) = slab.allocate(size, alignment, self.config.fit) {
                 let mapped_ptr = slab.mapped_ptr.map(|ptr| {
                     (ptr as *mut u8).add(offset as usize) as *mut std::ffi::c_void
                 });
@@ -177,7 +340,7 @@ impl MemoryPool {
         }
         
         // Need a new slab
-        let slab_size = SLAB_SIZE.max(size);
+        let slab_size = self.config.slab_size(self.slabs.len(), size);
         
         let alloc_info = VkMemoryAllocateInfo {
             sType: VkStructureType::MemoryAllocateInfo,
@@ -188,9 +351,9 @@ impl MemoryPool {
         
         let mut memory = VkDeviceMemory::NULL;
         
-        if let Some(icd) = super::icd_loader::get_icd() {
+        if let Some(icd) = super::icd_loader::icd_for_device(self.device) {
             if let Some(alloc_fn) = icd.allocate_memory {
-                let result = alloc_fn(self.device, &alloc_info, std::ptr::null(), &mut memory);
+                let result = icd_call!("vkAllocateMemory", alloc_fn(self.device, &alloc_info, std::ptr::null(), &mut memory));
                 if result != VkResult::Success {
                     return Err(IcdError::VulkanError(result));
                 }
@@ -204,9 +367,9 @@ impl MemoryPool {
         // Map if needed
         let mapped_ptr = if self.pool_type.should_map() {
             let mut ptr = std::ptr::null_mut();
-            if let Some(icd) = super::icd_loader::get_icd() {
+            if let Some(icd) = super::icd_loader::icd_for_device(self.device) {
                 if let Some(map_fn) = icd.map_memory {
-                    let result = map_fn(self.device, memory, 0, VK_WHOLE_SIZE, 0, &mut ptr);
+                    let result = icd_call!("vkMapMemory", map_fn(self.device, memory, 0, VK_WHOLE_SIZE, 0, &mut ptr));
                     if result == VkResult::Success {
                         Some(ptr)
                     } else {
@@ -223,16 +386,10 @@ impl MemoryPool {
         };
         
         // Create new slab
-        let mut slab = MemorySlab {
-            memory,
-            size: slab_size,
-            mapped_ptr,
-            allocations: Vec::new(),
-            free_space: slab_size,
-        };
+        let mut slab = MemorySlab::new(memory, slab_size, mapped_ptr);
         
         // Allocate from new slab
-        let offset = slab.allocate(size, alignment)
+        let offset = slab.allocate(size, alignment, self.config.fit)
             .expect("New slab should have space");
         
         let result_ptr = mapped_ptr.map(|ptr| {
@@ -262,6 +419,28 @@ impl MemoryPool {
         }
         false
     }
+    
+    /// Release every slab
+    ///
+    /// # Safety
+    ///
+    /// No allocation of the pool may still be in use by the GPU or host
+    unsafe fn destroy(&mut self) {
+        let icd = super::icd_loader::icd_for_device(self.device);
+        for slab in self.slabs.drain(..) {
+            if let Some(icd) = &icd {
+                if slab.mapped_ptr.is_some() {
+                    if let Some(unmap_fn) = icd.unmap_memory {
+                        icd_call!("vkUnmapMemory", unmap_fn(self.device, slab.memory));
+                    }
+                }
+                if let Some(free_fn) = icd.free_memory {
+                    icd_call!("vkFreeMemory", free_fn(self.device, slab.memory, std::ptr::null()));
+                }
+            }
+        }
+        self.total_allocated = 0;
+    }
 }
 
 /// Allocation handle
@@ -271,6 +450,7 @@ pub struct AllocationHandle {
     offset: VkDeviceSize,
     size: VkDeviceSize,
     pool_type: PoolType,
+    memory_flags: VkMemoryPropertyFlags,
     mapped_ptr: Option<*mut std::ffi::c_void>,
 }
 
@@ -294,6 +474,11 @@ impl AllocationHandle {
         self.size
     }
     
+    /// Get the property flags of the memory's type
+    pub fn memory_flags(&self) -> VkMemoryPropertyFlags {
+        self.memory_flags
+    }
+    
     /// Get mapped pointer if available
     pub fn mapped_ptr(&self) -> Option<*mut std::ffi::c_void> {
         self.mapped_ptr
@@ -304,6 +489,9 @@ impl AllocationHandle {
 pub struct PoolAllocator {
     pools: HashMap<(u64, PoolType), MemoryPool>,
     allocations: HashMap<u64, AllocationHandle>,
+    /// Tag of each pool allocation
+    allocation_tags: HashMap<u64, String>,
+    accounting: Accounting,
     next_id: u64,
 }
 
@@ -311,10 +499,163 @@ lazy_static::lazy_static! {
     static ref POOL_ALLOCATOR: Mutex<PoolAllocator> = Mutex::new(PoolAllocator {
         pools: HashMap::new(),
         allocations: HashMap::new(),
+        allocation_tags: HashMap::new(),
+        accounting: Accounting::default(),
         next_id: 1,
     });
 }
 
+/// Tag of memory allocated without one
+pub const UNTAGGED: &str = "untagged";
+
+/// Live memory under one allocation tag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TagUsage {
+    pub bytes: VkDeviceSize,
+    pub count: usize,
+}
+
+/// An allocation refused because it would take a device past its
+/// memory limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryLimitExceeded {
+    pub requested: VkDeviceSize,
+    pub limit: VkDeviceSize,
+    /// Live bytes on the device when the allocation was refused
+    pub in_use: VkDeviceSize,
+    /// Live memory per tag, largest first
+    pub tags: Vec<(String, TagUsage)>,
+}
+
+impl std::fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "allocating {} bytes would exceed the memory limit of {} bytes ({} in use",
+            self.requested, self.limit, self.in_use
+        )?;
+        for (index, (tag, usage)) in self.tags.iter().enumerate() {
+            let separator = if index == 0 { ": " } else { ", " };
+            write!(f, "{}{} {} bytes in {} allocations", separator, tag, usage.bytes, usage.count)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Live memory by device and tag, with the process-wide peak
+#[derive(Default)]
+struct Accounting {
+    tags: HashMap<(u64, String), TagUsage>,
+    /// Live bytes across all devices
+    bytes_in_use: VkDeviceSize,
+    high_water_mark: VkDeviceSize,
+    /// Soft cap on the live bytes of a device
+    limits: HashMap<u64, VkDeviceSize>,
+}
+
+impl Accounting {
+    fn device_tags(&self, device: u64) -> Vec<(String, TagUsage)> {
+        let mut tags: Vec<_> = self.tags
+            .iter()
+            .filter(|((raw, _), _)| *raw == device)
+            .map(|((_, tag), usage)| (tag.clone(), *usage))
+            .collect();
+        tags.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        tags
+    }
+
+    /// Fail if `bytes` more would take `device` past its limit
+    fn check_limit(&self, device: u64, bytes: VkDeviceSize) -> Result<(), IcdError> {
+        let Some(&limit) = self.limits.get(&device) else {
+            return Ok(());
+        };
+        let tags = self.device_tags(device);
+        let in_use: VkDeviceSize = tags.iter().map(|(_, usage)| usage.bytes).sum();
+        if in_use.saturating_add(bytes) <= limit {
+            return Ok(());
+        }
+        Err(IcdError::MemoryLimitExceeded(MemoryLimitExceeded { requested: bytes, limit, in_use, tags }))
+    }
+
+    fn add(&mut self, device: u64, tag: &str, bytes: VkDeviceSize) {
+        let usage = self.tags.entry((device, tag.to_owned())).or_default();
+        usage.bytes += bytes;
+        usage.count += 1;
+        self.bytes_in_use += bytes;
+        self.high_water_mark = self.high_water_mark.max(self.bytes_in_use);
+    }
+
+    fn remove(&mut self, device: u64, tag: &str, bytes: VkDeviceSize) {
+        let key = (device, tag.to_owned());
+        if let Some(usage) = self.tags.get_mut(&key) {
+            usage.bytes = usage.bytes.saturating_sub(bytes);
+            usage.count = usage.count.saturating_sub(1);
+            if usage.count == 0 {
+                self.tags.remove(&key);
+            }
+        }
+        self.bytes_in_use = self.bytes_in_use.saturating_sub(bytes);
+    }
+}
+
+fn lock_allocator() -> std::sync::MutexGuard<'static, PoolAllocator> {
+    POOL_ALLOCATOR.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Account for device memory allocated outside the pools
+///
+/// Every call must be balanced by a [`record_free`] with the same device,
+/// tag and size.
+pub fn record_allocation(device: VkDevice, tag: &str, bytes: VkDeviceSize) {
+    lock_allocator().accounting.add(device.as_raw(), tag, bytes);
+}
+
+/// Account for memory about to be allocated outside the pools, failing
+/// if it would exceed the device's limit
+///
+/// On success, balance the call with a [`record_free`] once the memory is
+/// released or its allocation fails.
+pub fn reserve_allocation(device: VkDevice, tag: &str, bytes: VkDeviceSize) -> Result<(), IcdError> {
+    let mut allocator = lock_allocator();
+    allocator.accounting.check_limit(device.as_raw(), bytes)?;
+    allocator.accounting.add(device.as_raw(), tag, bytes);
+    Ok(())
+}
+
+/// Account for the release of memory passed to [`record_allocation`]
+/// or [`reserve_allocation`]
+pub fn record_free(device: VkDevice, tag: &str, bytes: VkDeviceSize) {
+    lock_allocator().accounting.remove(device.as_raw(), tag, bytes);
+}
+
+/// Live memory of a device per tag, largest first
+pub fn tag_usage(device: VkDevice) -> Vec<(String, TagUsage)> {
+    lock_allocator().accounting.device_tags(device.as_raw())
+}
+
+/// Cap the live memory of a device, across all pools and allocations
+/// accounted outside them; `None` removes the cap
+///
+/// Memory already allocated is kept, but further allocations fail with
+/// [`IcdError::MemoryLimitExceeded`] while the device is over the limit.
+pub fn set_memory_limit(device: VkDevice, limit: Option<VkDeviceSize>) {
+    let mut allocator = lock_allocator();
+    match limit {
+        Some(limit) => allocator.accounting.limits.insert(device.as_raw(), limit),
+        None => allocator.accounting.limits.remove(&device.as_raw()),
+    };
+}
+
+/// Memory limit of a device, if one is set
+pub fn memory_limit(device: VkDevice) -> Option<VkDeviceSize> {
+    lock_allocator().accounting.limits.get(&device.as_raw()).copied()
+}
+
+/// Peak of live accounted memory across all devices since process start
+pub fn high_water_mark() -> VkDeviceSize {
+    lock_allocator().accounting.high_water_mark
+}
+
 /// Initialize pools for a device
 ///
 /// # Safety
@@ -328,14 +669,27 @@ lazy_static::lazy_static! {
 pub unsafe fn initialize_pools(
     device: VkDevice,
     physical_device: VkPhysicalDevice,
+) -> Result<(), IcdError> {
+    initialize_pools_with_config(device, physical_device, &MemoryConfig::default())
+}
+
+/// Initialize pools for a device with explicit slab and fit settings
+///
+/// # Safety
+///
+/// Same requirements as [`initialize_pools`]
+pub unsafe fn initialize_pools_with_config(
+    device: VkDevice,
+    physical_device: VkPhysicalDevice,
+    config: &MemoryConfig,
 ) -> Result<(), IcdError> {
     let mut allocator = POOL_ALLOCATOR.lock()?;
     
     // Get memory properties
     let mut mem_props = VkPhysicalDeviceMemoryProperties::default();
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = super::icd_loader::icd_for_physical_device(physical_device) {
         if let Some(get_props_fn) = icd.get_physical_device_memory_properties {
-            get_props_fn(physical_device, &mut mem_props);
+            icd_call!("vkGetPhysicalDeviceMemoryProperties", get_props_fn(physical_device, &mut mem_props));
         }
     }
     
@@ -347,7 +701,7 @@ pub unsafe fn initialize_pools(
             let mem_type = &mem_props.memoryTypes[i as usize];
             if mem_type.propertyFlags.contains(required_flags) {
                 let key = (device.as_raw(), *pool_type);
-                allocator.pools.insert(key, MemoryPool::new(device, *pool_type, i));
+                allocator.pools.insert(key, MemoryPool::new(device, *pool_type, i, mem_type.propertyFlags, config.for_pool(*pool_type)));
                 break;
             }
         }
@@ -356,6 +710,24 @@ pub unsafe fn initialize_pools(
     Ok(())
 }
 
+/// Give each of a device's pools its first slab, so the first allocation
+/// from it does not call vkAllocateMemory
+///
+/// # Safety
+///
+/// The device must be a valid VkDevice handle with initialized pools.
+pub unsafe fn prewarm_pools(device: VkDevice) -> Result<(), IcdError> {
+    let mut allocator = POOL_ALLOCATOR.lock()?;
+    for ((raw, _), pool) in allocator.pools.iter_mut() {
+        if *raw == device.as_raw() && pool.slabs.is_empty() {
+            // Freed slab space stays with the pool
+            let (memory, offset, _) = pool.allocate(1, 1)?;
+            pool.free(memory, offset);
+        }
+    }
+    Ok(())
+}
+
 /// Allocate memory from appropriate pool
 ///
 /// # Safety
@@ -370,10 +742,25 @@ pub unsafe fn allocate_from_pool(
     device: VkDevice,
     requirements: &VkMemoryRequirements,
     pool_type: PoolType,
+) -> Result<u64, IcdError> {
+    allocate_from_pool_tagged(device, requirements, pool_type, UNTAGGED)
+}
+
+/// Allocate memory from appropriate pool, accounted under `tag`
+///
+/// # Safety
+///
+/// Same requirements as [`allocate_from_pool`]
+pub unsafe fn allocate_from_pool_tagged(
+    device: VkDevice,
+    requirements: &VkMemoryRequirements,
+    pool_type: PoolType,
+    tag: &str,
 ) -> Result<u64, IcdError> {
     let mut allocator = POOL_ALLOCATOR.lock()?;
     
     let key = (device.as_raw(), pool_type);
+    allocator.accounting.check_limit(device.as_raw(), requirements.size)?;
     let pool = allocator.pools.get_mut(&key)
         .ok_or(IcdError::InvalidOperation("Pool not initialized"))?;
     
@@ -384,12 +771,15 @@ pub unsafe fn allocate_from_pool(
         offset,
         size: requirements.size,
         pool_type,
+        memory_flags: pool.memory_flags,
         mapped_ptr,
     };
     
     let id = allocator.next_id;
     allocator.next_id += 1;
     allocator.allocations.insert(id, handle);
+    allocator.allocation_tags.insert(id, tag.to_owned());
+    allocator.accounting.add(device.as_raw(), tag, requirements.size);
     
     Ok(id)
 }
@@ -417,6 +807,9 @@ pub unsafe fn free_allocation(device: VkDevice, id: u64) -> Result<(), IcdError>
     
     let handle = allocator.allocations.remove(&id)
         .ok_or(IcdError::InvalidOperation("Invalid allocation ID"))?;
+    if let Some(tag) = allocator.allocation_tags.remove(&id) {
+        allocator.accounting.remove(device.as_raw(), &tag, handle.size);
+    }
     
     let key = (device.as_raw(), handle.pool_type);
     if let Some(pool) = allocator.pools.get_mut(&key) {
@@ -426,12 +819,63 @@ pub unsafe fn free_allocation(device: VkDevice, id: u64) -> Result<(), IcdError>
     Ok(())
 }
 
+/// Release the pools of a device and every slab they hold
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// - The device must be a valid VkDevice handle
+/// - No resource may still be bound to memory from the device's pools
+/// - GPU must not be using the memory
+pub unsafe fn destroy_pools(device: VkDevice) -> Result<(), IcdError> {
+    let mut allocator = POOL_ALLOCATOR.lock()?;
+    allocator.accounting.limits.remove(&device.as_raw());
+    let keys: Vec<_> = allocator.pools.keys().filter(|(raw, _)| *raw == device.as_raw()).copied().collect();
+    for key in keys {
+        if let Some(mut pool) = allocator.pools.remove(&key) {
+            let memories: Vec<VkDeviceMemory> = pool.slabs.iter().map(|slab| slab.memory).collect();
+            let released: Vec<(u64, VkDeviceSize)> = allocator.allocations
+                .iter()
+                .filter(|(_, handle)| memories.contains(&handle.memory))
+                .map(|(id, handle)| (*id, handle.size))
+                .collect();
+            for (id, size) in released {
+                allocator.allocations.remove(&id);
+                if let Some(tag) = allocator.allocation_tags.remove(&id) {
+                    allocator.accounting.remove(device.as_raw(), &tag, size);
+                }
+            }
+            pool.destroy();
+        }
+    }
+    Ok(())
+}
+
 /// Get pool statistics
 #[derive(Debug, Default)]
 pub struct PoolStats {
     pub total_allocated: VkDeviceSize,
     pub total_slabs: usize,
     pub allocations_in_flight: usize,
+    /// Bytes of the slabs held by live allocations, including alignment
+    /// and buddy rounding
+    pub bytes_in_use: VkDeviceSize,
+    /// Largest single allocation the existing slabs could still satisfy
+    /// without alignment padding
+    pub largest_free_range: VkDeviceSize,
+}
+
+impl PoolStats {
+    /// Share of free slab memory unusable for one allocation of that size,
+    /// from 0 (all free memory is contiguous) to 1
+    pub fn fragmentation(&self) -> f64 {
+        let free = self.total_allocated - self.bytes_in_use;
+        if free == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free_range as f64 / free as f64
+        }
+    }
 }
 
 pub fn get_pool_stats(device: VkDevice, pool_type: PoolType) -> Result<PoolStats, IcdError> {
@@ -445,6 +889,8 @@ pub fn get_pool_stats(device: VkDevice, pool_type: PoolType) -> Result<PoolStats
             allocations_in_flight: allocator.allocations.values()
                 .filter(|a| a.pool_type == pool_type)
                 .count(),
+            bytes_in_use: pool.slabs.iter().map(|slab| slab.size - slab.free_space).sum(),
+            largest_free_range: pool.slabs.iter().map(MemorySlab::largest_free_range).max().unwrap_or(0),
         })
     } else {
         Ok(PoolStats::default())
@@ -469,9 +915,9 @@ pub unsafe fn allocate_buffer_memory(
 ) -> Result<u64, IcdError> {
     let mut requirements = VkMemoryRequirements::default();
     
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(get_reqs_fn) = icd.get_buffer_memory_requirements {
-            get_reqs_fn(device, buffer, &mut requirements);
+            icd_call!("vkGetBufferMemoryRequirements", get_reqs_fn(device, buffer, &mut requirements));
         }
     }
     
@@ -479,9 +925,9 @@ pub unsafe fn allocate_buffer_memory(
     let handle = get_allocation(allocation_id)?;
     
     // Bind buffer to memory
-    if let Some(icd) = super::icd_loader::get_icd() {
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(bind_fn) = icd.bind_buffer_memory {
-            let result = bind_fn(device, buffer, handle.memory, handle.offset);
+            let result = icd_call!("vkBindBufferMemory", bind_fn(device, buffer, handle.memory, handle.offset));
             if result != VkResult::Success {
                 free_allocation(device, allocation_id)?;
                 return Err(IcdError::VulkanError(result));
@@ -495,6 +941,7 @@ pub unsafe fn allocate_buffer_memory(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     
     #[test]
     fn test_pool_type_flags() {
@@ -509,20 +956,14 @@ mod tests {
     #[test]
     fn test_slab_allocation() {
         let memory = VkDeviceMemory::from_raw(0x1234);
-        let mut slab = MemorySlab {
-            memory,
-            size: 1024,
-            mapped_ptr: None,
-            allocations: Vec::new(),
-            free_space: 1024,
-        };
+        let mut slab = MemorySlab::new(memory, 1024, None);
         
         // Test allocation
-        let offset1 = slab.allocate(256, 16).unwrap();
+        let offset1 = slab.allocate(256, 16, FitStrategy::FirstFit).unwrap();
         assert_eq!(offset1, 0);
         assert_eq!(slab.free_space, 768);
         
-        let offset2 = slab.allocate(256, 16).unwrap();
+        let offset2 = slab.allocate(256, 16, FitStrategy::FirstFit).unwrap();
         assert_eq!(offset2, 256);
         assert_eq!(slab.free_space, 512);
         
@@ -530,4 +971,146 @@ mod tests {
         assert!(slab.free(offset1));
         assert_eq!(slab.free_space, 768);
     }
+    
+    /// A 1 KiB slab with a 256-byte hole at 0 and a 128-byte hole at 320
+    fn slab_with_holes() -> MemorySlab {
+        let mut slab = MemorySlab::new(VkDeviceMemory::from_raw(0x1234), 1024, None);
+        let offsets: Vec<_> = [256, 64, 128, 64]
+            .iter()
+            .map(|&size| slab.allocate(size, 16, FitStrategy::FirstFit).unwrap())
+            .collect();
+        assert!(slab.free(offsets[0]));
+        assert!(slab.free(offsets[2]));
+        slab
+    }
+    
+    #[test]
+    fn test_best_fit_picks_smallest_gap() {
+        assert_eq!(slab_with_holes().allocate(100, 16, FitStrategy::FirstFit), Some(0));
+        assert_eq!(slab_with_holes().allocate(100, 16, FitStrategy::BestFit), Some(320));
+    }
+    
+    #[test]
+    fn test_buddy_blocks_are_power_of_two() {
+        let mut slab = MemorySlab::new(VkDeviceMemory::from_raw(0x1234), 1024, None);
+        assert_eq!(slab.allocate(100, 4, FitStrategy::Buddy), Some(0));
+        assert_eq!(slab.allocate(200, 4, FitStrategy::Buddy), Some(256));
+        assert_eq!(slab.free_space, 1024 - 128 - 256);
+        // Freeing both merges back into one 512-byte block
+        assert!(slab.free(0));
+        assert!(slab.free(256));
+        assert_eq!(slab.allocate(512, 4, FitStrategy::Buddy), Some(0));
+    }
+    
+    #[test]
+    fn test_slab_growth() {
+        let fixed = PoolConfig { slab_size: 1024, growth: SlabGrowth::Fixed, fit: FitStrategy::FirstFit };
+        assert_eq!(fixed.slab_size(5, 100), 1024);
+        assert_eq!(fixed.slab_size(0, 4000), 4000);
+        
+        let growing = PoolConfig {
+            growth: SlabGrowth::Exponential { max_slab_size: 4096 },
+            ..fixed
+        };
+        let sizes: Vec<_> = (0..5).map(|count| growing.slab_size(count, 1)).collect();
+        assert_eq!(sizes, [1024, 2048, 4096, 4096, 4096]);
+        
+        let buddy = PoolConfig { fit: FitStrategy::Buddy, ..fixed };
+        assert_eq!(buddy.slab_size(0, 3000), 4096);
+    }
+    
+    #[test]
+    fn test_accounting_tracks_high_water_mark() {
+        let mut accounting = Accounting::default();
+        accounting.add(1, "weights", 1000);
+        accounting.add(1, "activations", 300);
+        accounting.add(2, "weights", 200);
+        assert_eq!(accounting.tags[&(1, "weights".to_owned())], TagUsage { bytes: 1000, count: 1 });
+        
+        accounting.remove(1, "weights", 1000);
+        assert!(!accounting.tags.contains_key(&(1, "weights".to_owned())));
+        assert_eq!(accounting.bytes_in_use, 500);
+        assert_eq!(accounting.high_water_mark, 1500);
+    }
+    
+    /// Check the slab's bookkeeping against its allocations
+    fn assert_consistent(slab: &MemorySlab) {
+        let mut ranges: Vec<Range> = slab.allocations.iter().chain(&slab.free).copied().collect();
+        ranges.sort_by_key(|range| range.offset);
+        // Allocations and free ranges tile the slab exactly
+        let mut end = 0;
+        for range in &ranges {
+            assert_eq!(range.offset, end, "gap or overlap at {}", end);
+            end = range.end();
+        }
+        assert_eq!(end, slab.size);
+        
+        assert!(slab.free.iter().all(|range| range.size > 0));
+        assert!(slab.free.windows(2).all(|pair| pair[0].end() < pair[1].offset), "adjacent free ranges: {:?}", slab.free);
+        assert!(slab.allocations.windows(2).all(|pair| pair[0].offset < pair[1].offset));
+        assert_eq!(slab.free_space, slab.free.iter().map(|range| range.size).sum::<VkDeviceSize>());
+    }
+    
+    #[derive(Debug, Clone)]
+    enum Op {
+        Allocate { size: VkDeviceSize, alignment_log2: u32 },
+        Free { pick: usize },
+    }
+    
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (1..2048u64, 0..8u32).prop_map(|(size, alignment_log2)| Op::Allocate { size, alignment_log2 }),
+            any::<usize>().prop_map(|pick| Op::Free { pick }),
+        ]
+    }
+    
+    fn fit() -> impl Strategy<Value = FitStrategy> {
+        prop_oneof![Just(FitStrategy::FirstFit), Just(FitStrategy::BestFit), Just(FitStrategy::Buddy)]
+    }
+    
+    proptest! {
+        #[test]
+        fn prop_allocate_free_sequences(fit in fit(), ops in proptest::collection::vec(op(), 1..200)) {
+            let mut slab = MemorySlab::new(VkDeviceMemory::from_raw(0x1234), 16384, None);
+            let mut live = Vec::new();
+            for op in ops {
+                match op {
+                    Op::Allocate { size, alignment_log2 } => {
+                        let alignment = 1 << alignment_log2;
+                        if let Some(offset) = slab.allocate(size, alignment, fit) {
+                            prop_assert_eq!(offset % alignment, 0);
+                            live.push(offset);
+                        }
+                    }
+                    Op::Free { pick } if !live.is_empty() => {
+                        let offset = live.swap_remove(pick % live.len());
+                        prop_assert!(slab.free(offset));
+                        // A second free of the same offset is rejected
+                        prop_assert!(!slab.free(offset));
+                    }
+                    Op::Free { .. } => {}
+                }
+                assert_consistent(&slab);
+                prop_assert_eq!(slab.allocations.len(), live.len());
+            }
+            
+            for offset in live {
+                prop_assert!(slab.free(offset));
+            }
+            // Everything merges back into a single range
+            prop_assert_eq!(&slab.free, &vec![Range { offset: 0, size: 16384 }]);
+        }
+        
+        #[test]
+        fn prop_free_space_is_reusable(fit in fit(), sizes in proptest::collection::vec(1..1024u64, 1..64)) {
+            let mut slab = MemorySlab::new(VkDeviceMemory::from_raw(0x1234), 65536, None);
+            let first: Vec<_> = sizes.iter().map(|&size| slab.allocate(size, 16, fit)).collect();
+            for offset in first.iter().flatten() {
+                prop_assert!(slab.free(*offset));
+            }
+            // Freed space can be handed out again at the same offsets
+            let second: Vec<_> = sizes.iter().map(|&size| slab.allocate(size, 16, fit)).collect();
+            prop_assert_eq!(first, second);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,299 @@
+//! Query pools
+//!
+//! Kronos uses timestamp queries for per-dispatch GPU timing and
+//! VK_KHR_performance_query pools for hardware counters; the entry points
+//! forward any query type to the ICD.
+
+use std::ffi::c_void;
+use crate::sys::*;
+use crate::core::*;
+use crate::ffi::*;
+use crate::implementation::child_objects;
+use crate::implementation::icd_loader;
+
+/// Create a query pool
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice created by vkCreateDevice
+// 2. pCreateInfo points to a valid VkQueryPoolCreateInfo structure
+// 3. pAllocator is either null or points to valid allocation callbacks
+// 4. pQueryPool points to valid memory for writing the query pool handle
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCreateQueryPool(
+    device: VkDevice,
+    pCreateInfo: *const VkQueryPoolCreateInfo,
+    pAllocator: *const VkAllocationCallbacks,
+    pQueryPool: *mut VkQueryPool,
+) -> VkResult {
+    if device.is_null() || pCreateInfo.is_null() || pQueryPool.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.create_query_pool { return child_objects::created(device, icd_call!("vkCreateQueryPool", f(device, pCreateInfo, pAllocator, pQueryPool)), pQueryPool, 1); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(create_query_pool) = icd.create_query_pool { return child_objects::created(device, icd_call!("vkCreateQueryPool", create_query_pool(device, pCreateInfo, pAllocator, pQueryPool)), pQueryPool, 1); }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Destroy a query pool
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice
+// 2. queryPool is a valid VkQueryPool created by vkCreateQueryPool, or VK_NULL_HANDLE
+// 3. pAllocator matches the allocator used in vkCreateQueryPool (or both are null)
+// 4. No pending command buffer references the pool
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkDestroyQueryPool(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    pAllocator: *const VkAllocationCallbacks,
+) {
+    if device.is_null() || queryPool.is_null() {
+        return;
+    }
+    let _destroyed = child_objects::destroyed(device, queryPool);
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.destroy_query_pool { icd_call!("vkDestroyQueryPool", f(device, queryPool, pAllocator)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(destroy_query_pool) = icd.destroy_query_pool { icd_call!("vkDestroyQueryPool", destroy_query_pool(device, queryPool, pAllocator)); }
+    }
+}
+
+/// Read query results back to the host
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice and queryPool a VkQueryPool created on it
+// 2. firstQuery + queryCount does not exceed the pool's query count
+// 3. pData points to at least dataSize writable bytes
+// 4. stride and flags describe a layout that fits in dataSize
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetQueryPoolResults(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+    dataSize: usize,
+    pData: *mut c_void,
+    stride: VkDeviceSize,
+    flags: VkQueryResultFlags,
+) -> VkResult {
+    if device.is_null() || queryPool.is_null() || pData.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.get_query_pool_results {
+            return icd_call!("vkGetQueryPoolResults", f(device, queryPool, firstQuery, queryCount, dataSize, pData, stride, flags));
+        }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(get_query_pool_results) = icd.get_query_pool_results {
+            return icd_call!("vkGetQueryPoolResults", get_query_pool_results(device, queryPool, firstQuery, queryCount, dataSize, pData, stride, flags));
+        }
+    }
+    VkResult::ErrorInitializationFailed
+}
+
+/// Reset a range of queries before they are written again
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. queryPool is a valid VkQueryPool
+// 3. firstQuery + queryCount does not exceed the pool's query count
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdResetQueryPool(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+) {
+    if commandBuffer.is_null() || queryPool.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_reset_query_pool { icd_call!("vkCmdResetQueryPool", f(commandBuffer, queryPool, firstQuery, queryCount)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_reset_query_pool) = icd.cmd_reset_query_pool {
+            icd_call!("vkCmdResetQueryPool", cmd_reset_query_pool(commandBuffer, queryPool, firstQuery, queryCount));
+        }
+    }
+}
+
+/// Write a GPU timestamp once prior commands reach `pipelineStage`
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. queryPool is a timestamp VkQueryPool and query is below its query count
+// 3. The query was reset since it was last written
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdWriteTimestamp(
+    commandBuffer: VkCommandBuffer,
+    pipelineStage: VkPipelineStageFlags,
+    queryPool: VkQueryPool,
+    query: u32,
+) {
+    if commandBuffer.is_null() || queryPool.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_write_timestamp { icd_call!("vkCmdWriteTimestamp", f(commandBuffer, pipelineStage, queryPool, query)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_write_timestamp) = icd.cmd_write_timestamp {
+            icd_call!("vkCmdWriteTimestamp", cmd_write_timestamp(commandBuffer, pipelineStage, queryPool, query));
+        }
+    }
+}
+
+/// Begin a query (performance queries must span the whole command buffer)
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. queryPool is a valid VkQueryPool and query is below its query count
+// 3. The query was reset and is not already active
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdBeginQuery(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+    flags: VkQueryControlFlags,
+) {
+    if commandBuffer.is_null() || queryPool.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_begin_query { icd_call!("vkCmdBeginQuery", f(commandBuffer, queryPool, query, flags)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_begin_query) = icd.cmd_begin_query {
+            icd_call!("vkCmdBeginQuery", cmd_begin_query(commandBuffer, queryPool, query, flags));
+        }
+    }
+}
+
+/// End a query started by vkCmdBeginQuery
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. commandBuffer is a valid VkCommandBuffer in the recording state
+// 2. The query was begun in the same command buffer
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkCmdEndQuery(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+) {
+    if commandBuffer.is_null() || queryPool.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_command_buffer(commandBuffer) {
+        if let Some(f) = icd.cmd_end_query { icd_call!("vkCmdEndQuery", f(commandBuffer, queryPool, query)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(cmd_end_query) = icd.cmd_end_query {
+            icd_call!("vkCmdEndQuery", cmd_end_query(commandBuffer, queryPool, query));
+        }
+    }
+}
+
+/// Enumerate the performance counters a queue family can sample
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice
+// 2. pCounterCount points to a valid u32
+// 3. pCounters and pCounterDescriptions are null or point to *pCounterCount
+//    structures with sType initialised
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR(
+    physicalDevice: VkPhysicalDevice,
+    queueFamilyIndex: u32,
+    pCounterCount: *mut u32,
+    pCounters: *mut VkPerformanceCounterKHR,
+    pCounterDescriptions: *mut VkPerformanceCounterDescriptionKHR,
+) -> VkResult {
+    if physicalDevice.is_null() || pCounterCount.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    let f = match icd_loader::icd_for_physical_device(physicalDevice) {
+        Some(icd) => icd.enumerate_queue_family_performance_query_counters,
+        None => super::forward::get_icd_if_enabled().and_then(|icd| icd.enumerate_queue_family_performance_query_counters),
+    };
+    match f {
+        Some(f) => icd_call!(
+            "vkEnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR",
+            f(physicalDevice, queueFamilyIndex, pCounterCount, pCounters, pCounterDescriptions)
+        ),
+        None => {
+            *pCounterCount = 0;
+            VkResult::Success
+        }
+    }
+}
+
+/// Number of submissions needed to collect a set of performance counters
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. physicalDevice is a valid VkPhysicalDevice
+// 2. pPerformanceQueryCreateInfo points to a valid VkQueryPoolPerformanceCreateInfoKHR
+// 3. pNumPasses points to a valid u32
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR(
+    physicalDevice: VkPhysicalDevice,
+    pPerformanceQueryCreateInfo: *const VkQueryPoolPerformanceCreateInfoKHR,
+    pNumPasses: *mut u32,
+) {
+    if physicalDevice.is_null() || pPerformanceQueryCreateInfo.is_null() || pNumPasses.is_null() {
+        return;
+    }
+    let f = match icd_loader::icd_for_physical_device(physicalDevice) {
+        Some(icd) => icd.get_queue_family_performance_query_passes,
+        None => super::forward::get_icd_if_enabled().and_then(|icd| icd.get_queue_family_performance_query_passes),
+    };
+    match f {
+        Some(f) => icd_call!(
+            "vkGetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR",
+            f(physicalDevice, pPerformanceQueryCreateInfo, pNumPasses)
+        ),
+        None => *pNumPasses = 0,
+    }
+}
+
+/// Acquire the device profiling lock required to record performance queries
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice created with VK_KHR_performance_query enabled
+// 2. pInfo points to a valid VkAcquireProfilingLockInfoKHR
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkAcquireProfilingLockKHR(
+    device: VkDevice,
+    pInfo: *const VkAcquireProfilingLockInfoKHR,
+) -> VkResult {
+    if device.is_null() || pInfo.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.acquire_profiling_lock { return icd_call!("vkAcquireProfilingLockKHR", f(device, pInfo)); }
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(acquire_profiling_lock) = icd.acquire_profiling_lock { return icd_call!("vkAcquireProfilingLockKHR", acquire_profiling_lock(device, pInfo)); }
+    }
+    VkResult::ErrorFeatureNotPresent
+}
+
+/// Release the profiling lock taken by vkAcquireProfilingLockKHR
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice that currently holds the profiling lock
+// 2. No command buffer recorded with performance queries is still executing
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkReleaseProfilingLockKHR(device: VkDevice) {
+    if device.is_null() {
+        return;
+    }
+    if let Some(icd) = icd_loader::icd_for_device(device) {
+        if let Some(f) = icd.release_profiling_lock { icd_call!("vkReleaseProfilingLockKHR", f(device)); }
+        return;
+    }
+    if let Some(icd) = super::forward::get_icd_if_enabled() {
+        if let Some(release_profiling_lock) = icd.release_profiling_lock { icd_call!("vkReleaseProfilingLockKHR", release_profiling_lock(device)); }
+    }
+}
@@ -0,0 +1,108 @@
+//! Known driver behavior per vendor and platform
+//!
+//! Kronos looks up the quirks of the selected device here instead of
+//! scattering vendor checks through the code. The only entry so far is the
+//! GPU watchdog, which resets a device whose submission runs too long:
+//!
+//! | Platform | Driver | Behavior |
+//! |----------|--------|----------|
+//! | Windows | all (WDDM) | TDR resets the GPU after 2 s (`TdrDelay`); the device is lost |
+//! | Linux | NVIDIA | With a display attached, the X watchdog kills submissions after about 5 s; headless GPUs have no limit |
+//! | Linux | AMD (amdgpu) | `lockup_timeout` resets the ring after 10 s on graphics queues, 60 s on compute-only queues |
+//! | Linux | Intel (i915/xe) | Hangcheck resets a context that cannot be preempted within a few seconds |
+//! | macOS | Apple | Command buffers blocking the display are aborted after a few seconds |
+//!
+//! Software drivers have no watchdog. They are recognized by device type
+//! and, where the device reports VK_KHR_driver_properties, by driver ID,
+//! which also tells apart drivers for the same hardware (RADV, AMDVLK).
+//!
+//! The limits can be raised by the system administrator, so the timeouts
+//! below are conservative defaults, not guarantees.
+//! `CommandBuilder::execute_split` keeps each submission under half of the
+//! device's timeout.
+
+use std::time::Duration;
+use crate::core::*;
+use super::barrier_policy::GpuVendor;
+
+/// Driver quirks of one device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    pub vendor: GpuVendor,
+    /// Driver from VK_KHR_driver_properties, if the device reports it
+    pub driver: Option<VkDriverId>,
+    /// Longest a single submission may run before the watchdog may reset
+    /// the device, if the driver has a watchdog
+    pub watchdog_timeout: Option<Duration>,
+}
+
+impl Quirks {
+    /// Quirks of a device on the platform Kronos was built for
+    pub fn for_device(properties: &VkPhysicalDeviceProperties) -> Self {
+        Self::for_driver(properties, None)
+    }
+
+    /// Quirks of a device run by `driver`
+    ///
+    /// The driver ID tells apart drivers the properties alone cannot, such
+    /// as software rasterizers reporting a non-CPU device type.
+    pub fn for_driver(properties: &VkPhysicalDeviceProperties, driver: Option<VkDriverId>) -> Self {
+        let vendor = GpuVendor::from_vendor_id(properties.vendorID);
+        let software = properties.device_type() == VkPhysicalDeviceType::Cpu
+            || matches!(driver, Some(VkDriverId::MESA_LLVMPIPE | VkDriverId::GOOGLE_SWIFTSHADER));
+        Self {
+            vendor,
+            driver,
+            watchdog_timeout: if software { None } else { watchdog_timeout(vendor) },
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn watchdog_timeout(_vendor: GpuVendor) -> Option<Duration> {
+    Some(Duration::from_secs(2))
+}
+
+#[cfg(target_os = "macos")]
+fn watchdog_timeout(_vendor: GpuVendor) -> Option<Duration> {
+    Some(Duration::from_secs(2))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn watchdog_timeout(vendor: GpuVendor) -> Option<Duration> {
+    match vendor {
+        GpuVendor::NVIDIA => Some(Duration::from_secs(5)),
+        GpuVendor::AMD => Some(Duration::from_secs(10)),
+        GpuVendor::Intel | GpuVendor::Apple | GpuVendor::Other => Some(Duration::from_secs(2)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_software_devices_have_no_watchdog() {
+        let mut properties = VkPhysicalDeviceProperties { vendorID: 0x10DE, ..Default::default() };
+        properties.deviceType = VkPhysicalDeviceType::DiscreteGpu as i32;
+        let quirks = Quirks::for_device(&properties);
+        assert_eq!(quirks.vendor, GpuVendor::NVIDIA);
+        assert!(quirks.watchdog_timeout.is_some());
+
+        properties.deviceType = VkPhysicalDeviceType::Cpu as i32;
+        assert_eq!(Quirks::for_device(&properties).watchdog_timeout, None);
+    }
+
+    #[test]
+    fn test_software_drivers_are_recognized_by_id() {
+        let properties = VkPhysicalDeviceProperties {
+            vendorID: 0x10005,
+            deviceType: VkPhysicalDeviceType::Other as i32,
+            ..Default::default()
+        };
+        assert!(Quirks::for_driver(&properties, Some(VkDriverId::MESA_RADV)).watchdog_timeout.is_some());
+        let quirks = Quirks::for_driver(&properties, Some(VkDriverId::MESA_LLVMPIPE));
+        assert_eq!(quirks.driver, Some(VkDriverId::MESA_LLVMPIPE));
+        assert_eq!(quirks.watchdog_timeout, None);
+    }
+}
@@ -5,6 +5,7 @@
 use crate::sys::*;
 use crate::core::*;
 use crate::ffi::*;
+use crate::implementation::child_objects;
 
 /// Create a fence
 // SAFETY: This function is called from C code. Caller must ensure:
@@ -13,7 +14,7 @@ use crate::ffi::*;
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pFence points to valid memory for writing the fence handle
 // 5. Fences are thread-safe and can be used across multiple threads
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateFence(
     device: VkDevice,
     pCreateInfo: *const VkFenceCreateInfo,
@@ -24,10 +25,10 @@ pub unsafe extern "C" fn vkCreateFence(
         return VkResult::ErrorInitializationFailed;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(create_fence) = icd.create_fence {
-            return create_fence(device, pCreateInfo, pAllocator, pFence);
+            return child_objects::created(device, icd_call!("vkCreateFence", create_fence(device, pCreateInfo, pAllocator, pFence)), pFence, 1);
         }
     }
     
@@ -42,7 +43,7 @@ pub unsafe extern "C" fn vkCreateFence(
 // 3. pAllocator matches the allocator used in vkCreateFence (or both are null)
 // 4. The fence is not currently being waited on by any thread
 // 5. No queue operations are pending on this fence
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyFence(
     device: VkDevice,
     fence: VkFence,
@@ -51,11 +52,12 @@ pub unsafe extern "C" fn vkDestroyFence(
     if device.is_null() || fence.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, fence);
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(destroy_fence) = icd.destroy_fence {
-            destroy_fence(device, fence, pAllocator);
+            icd_call!("vkDestroyFence", destroy_fence(device, fence, pAllocator));
         }
     }
 }
@@ -67,7 +69,7 @@ pub unsafe extern "C" fn vkDestroyFence(
 // 3. pFences points to an array of fenceCount valid VkFence handles
 // 4. All fences are in the signaled state (cannot reset unsignaled fences)
 // 5. No threads are currently waiting on these fences
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkResetFences(
     device: VkDevice,
     fenceCount: u32,
@@ -77,10 +79,10 @@ pub unsafe extern "C" fn vkResetFences(
         return VkResult::ErrorInitializationFailed;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(reset_fences) = icd.reset_fences {
-            return reset_fences(device, fenceCount, pFences);
+            return icd_call!("vkResetFences", reset_fences(device, fenceCount, pFences));
         }
     }
     
@@ -94,7 +96,7 @@ pub unsafe extern "C" fn vkResetFences(
 // 2. fence is a valid VkFence created by vkCreateFence
 // 3. This function is thread-safe and can be called concurrently
 // 4. The fence has not been destroyed
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetFenceStatus(
     device: VkDevice,
     fence: VkFence,
@@ -103,10 +105,10 @@ pub unsafe extern "C" fn vkGetFenceStatus(
         return VkResult::ErrorDeviceLost;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(get_fence_status) = icd.get_fence_status {
-            return get_fence_status(device, fence);
+            return icd_call!("vkGetFenceStatus", get_fence_status(device, fence));
         }
     }
     
@@ -122,7 +124,7 @@ pub unsafe extern "C" fn vkGetFenceStatus(
 // 4. waitAll is either VK_TRUE or VK_FALSE
 // 5. timeout value is valid (can be UINT64_MAX for infinite wait)
 // 6. This function may block the calling thread until timeout or fence signaling
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkWaitForFences(
     device: VkDevice,
     fenceCount: u32,
@@ -133,11 +135,14 @@ pub unsafe extern "C" fn vkWaitForFences(
     if device.is_null() || fenceCount == 0 || pFences.is_null() {
         return VkResult::ErrorInitializationFailed;
     }
+    if let Some(result) = crate::testing::injected_failure(crate::testing::Site::FenceWait) {
+        return result;
+    }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(wait_for_fences) = icd.wait_for_fences {
-            return wait_for_fences(device, fenceCount, pFences, waitAll, timeout);
+            return icd_call!("vkWaitForFences", wait_for_fences(device, fenceCount, pFences, waitAll, timeout));
         }
     }
     
@@ -152,7 +157,7 @@ pub unsafe extern "C" fn vkWaitForFences(
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pSemaphore points to valid memory for writing the semaphore handle
 // 5. Semaphores are used for GPU-GPU synchronization and queue ordering
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateSemaphore(
     device: VkDevice,
     pCreateInfo: *const VkSemaphoreCreateInfo,
@@ -163,10 +168,10 @@ pub unsafe extern "C" fn vkCreateSemaphore(
         return VkResult::ErrorInitializationFailed;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(create_semaphore) = icd.create_semaphore {
-            return create_semaphore(device, pCreateInfo, pAllocator, pSemaphore);
+            return child_objects::created(device, icd_call!("vkCreateSemaphore", create_semaphore(device, pCreateInfo, pAllocator, pSemaphore)), pSemaphore, 1);
         }
     }
     
@@ -181,7 +186,7 @@ pub unsafe extern "C" fn vkCreateSemaphore(
 // 3. pAllocator matches the allocator used in vkCreateSemaphore (or both are null)
 // 4. The semaphore is not pending in any queue operation
 // 5. No command buffers reference this semaphore in wait or signal operations
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroySemaphore(
     device: VkDevice,
     semaphore: VkSemaphore,
@@ -190,13 +195,61 @@ pub unsafe extern "C" fn vkDestroySemaphore(
     if device.is_null() || semaphore.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, semaphore);
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(destroy_semaphore) = icd.destroy_semaphore {
-            destroy_semaphore(device, semaphore, pAllocator);
+            icd_call!("vkDestroySemaphore", destroy_semaphore(device, semaphore, pAllocator));
+        }
+    }
+}
+
+/// Export a semaphore as a file descriptor
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice with VK_KHR_external_semaphore_fd enabled
+// 2. pGetFdInfo points to a valid VkSemaphoreGetFdInfoKHR naming a semaphore
+//    created exportable as its handleType
+// 3. pFd points to valid memory for writing the file descriptor
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetSemaphoreFdKHR(
+    device: VkDevice,
+    pGetFdInfo: *const VkSemaphoreGetFdInfoKHR,
+    pFd: *mut std::ffi::c_int,
+) -> VkResult {
+    if device.is_null() || pGetFdInfo.is_null() || pFd.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
+        if let Some(get_semaphore_fd) = icd.get_semaphore_fd {
+            return icd_call!("vkGetSemaphoreFdKHR", get_semaphore_fd(device, pGetFdInfo, pFd));
+        }
+    }
+    VkResult::ErrorExtensionNotPresent
+}
+
+/// Read the current value of a timeline semaphore
+// SAFETY: This function is called from C code. Caller must ensure:
+// 1. device is a valid VkDevice with the timelineSemaphore feature enabled
+// 2. semaphore is a timeline semaphore created on device
+// 3. pValue points to valid memory for writing the value
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
+pub unsafe extern "C" fn vkGetSemaphoreCounterValue(
+    device: VkDevice,
+    semaphore: VkSemaphore,
+    pValue: *mut u64,
+) -> VkResult {
+    if device.is_null() || semaphore.is_null() || pValue.is_null() {
+        return VkResult::ErrorInitializationFailed;
+    }
+    
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
+        if let Some(get_semaphore_counter_value) = icd.get_semaphore_counter_value {
+            return icd_call!("vkGetSemaphoreCounterValue", get_semaphore_counter_value(device, semaphore, pValue));
         }
     }
+    VkResult::ErrorFeatureNotPresent
 }
 
 /// Create an event
@@ -206,7 +259,7 @@ pub unsafe extern "C" fn vkDestroySemaphore(
 // 3. pAllocator is either null or points to valid allocation callbacks
 // 4. pEvent points to valid memory for writing the event handle
 // 5. Events provide fine-grained synchronization within command buffers
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkCreateEvent(
     device: VkDevice,
     pCreateInfo: *const VkEventCreateInfo,
@@ -217,10 +270,10 @@ pub unsafe extern "C" fn vkCreateEvent(
         return VkResult::ErrorInitializationFailed;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(create_event) = icd.create_event {
-            return create_event(device, pCreateInfo, pAllocator, pEvent);
+            return child_objects::created(device, icd_call!("vkCreateEvent", create_event(device, pCreateInfo, pAllocator, pEvent)), pEvent, 1);
         }
     }
     
@@ -235,7 +288,7 @@ pub unsafe extern "C" fn vkCreateEvent(
 // 3. pAllocator matches the allocator used in vkCreateEvent (or both are null)
 // 4. No command buffers are currently waiting on or setting this event
 // 5. All command buffers that reference this event have completed execution
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkDestroyEvent(
     device: VkDevice,
     event: VkEvent,
@@ -244,11 +297,12 @@ pub unsafe extern "C" fn vkDestroyEvent(
     if device.is_null() || event.is_null() {
         return;
     }
+    let _destroyed = child_objects::destroyed(device, event);
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(destroy_event) = icd.destroy_event {
-            destroy_event(device, event, pAllocator);
+            icd_call!("vkDestroyEvent", destroy_event(device, event, pAllocator));
         }
     }
 }
@@ -259,7 +313,7 @@ pub unsafe extern "C" fn vkDestroyEvent(
 // 2. event is a valid VkEvent created by vkCreateEvent
 // 3. This function can be called from the host to check event signaling state
 // 4. The event has not been destroyed
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkGetEventStatus(
     device: VkDevice,
     event: VkEvent,
@@ -268,10 +322,10 @@ pub unsafe extern "C" fn vkGetEventStatus(
         return VkResult::ErrorDeviceLost;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(get_event_status) = icd.get_event_status {
-            return get_event_status(device, event);
+            return icd_call!("vkGetEventStatus", get_event_status(device, event));
         }
     }
     
@@ -286,7 +340,7 @@ pub unsafe extern "C" fn vkGetEventStatus(
 // 3. Setting an event from the host signals all command buffers waiting on it
 // 4. The event has not been destroyed
 // 5. This can cause command buffers to proceed past vkCmdWaitEvents
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkSetEvent(
     device: VkDevice,
     event: VkEvent,
@@ -295,10 +349,10 @@ pub unsafe extern "C" fn vkSetEvent(
         return VkResult::ErrorDeviceLost;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(set_event) = icd.set_event {
-            return set_event(device, event);
+            return icd_call!("vkSetEvent", set_event(device, event));
         }
     }
     
@@ -313,7 +367,7 @@ pub unsafe extern "C" fn vkSetEvent(
 // 3. Resetting an event puts it back into the unsignaled state
 // 4. The event has not been destroyed
 // 5. No command buffers should be waiting on this event when reset
-#[no_mangle]
+#[cfg_attr(export_vulkan_symbols, no_mangle)]
 pub unsafe extern "C" fn vkResetEvent(
     device: VkDevice,
     event: VkEvent,
@@ -322,10 +376,10 @@ pub unsafe extern "C" fn vkResetEvent(
         return VkResult::ErrorDeviceLost;
     }
     
-    // Forward to real ICD
-    if let Some(icd) = super::forward::get_icd_if_enabled() {
+    // Forward to the ICD that owns the device
+    if let Some(icd) = super::icd_loader::icd_for_device(device) {
         if let Some(reset_event) = icd.reset_event {
-            return reset_event(device, event);
+            return icd_call!("vkResetEvent", reset_event(device, event));
         }
     }
     
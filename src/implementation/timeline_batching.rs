@@ -67,6 +67,8 @@ pub struct TimelineManager {
     batches: HashMap<u64, BatchSubmission>,
     /// Batch size threshold
     batch_size: u32,
+    /// Submissions made through [`submit_batch`]
+    stats: BatchStats,
 }
 
 lazy_static::lazy_static! {
@@ -74,6 +76,7 @@ lazy_static::lazy_static! {
         timelines: HashMap::new(),
         batches: HashMap::new(),
         batch_size: 16, // Default batch size
+        stats: BatchStats::default(),
     });
 }
 
@@ -92,24 +95,20 @@ pub unsafe fn create_timeline_semaphore(
     initial_value: u64,
 ) -> Result<VkSemaphore, IcdError> {
     // Timeline semaphore create info
-    let timeline_info = VkSemaphoreTypeCreateInfo {
-        sType: VkStructureType::SemaphoreTypeCreateInfo,
-        pNext: std::ptr::null(),
+    let mut timeline_info = VkSemaphoreTypeCreateInfo {
         semaphoreType: VkSemaphoreType::Timeline,
         initialValue: initial_value,
+        ..Default::default()
     };
     
-    let create_info = VkSemaphoreCreateInfo {
-        sType: VkStructureType::SemaphoreCreateInfo,
-        pNext: &timeline_info as *const _ as *const std::ffi::c_void,
-        flags: 0,
-    };
+    let mut create_info = VkSemaphoreCreateInfo::default();
+    let create_info = Chain::new(&mut create_info).push(&mut timeline_info);
     
     let mut semaphore = VkSemaphore::NULL;
     
     if let Some(icd) = super::icd_loader::get_icd() {
         if let Some(create_fn) = icd.create_semaphore {
-            let result = create_fn(device, &create_info, std::ptr::null(), &mut semaphore);
+            let result = icd_call!("vkCreateSemaphore", create_fn(device, create_info.as_ptr(), std::ptr::null(), &mut semaphore));
             if result == VkResult::Success {
                 return Ok(semaphore);
             }
@@ -223,7 +222,7 @@ pub unsafe fn submit_batch(
     let signal_value = timeline.current_value;
     
     // Build timeline submit info
-    let timeline_info = VkTimelineSemaphoreSubmitInfo {
+    let mut timeline_info = VkTimelineSemaphoreSubmitInfo {
         sType: VkStructureType::TimelineSemaphoreSubmitInfo,
         pNext: std::ptr::null(),
         waitSemaphoreValueCount: batch.wait_values.len() as u32,
@@ -237,9 +236,9 @@ pub unsafe fn submit_batch(
     };
     
     // Build submit info
-    let submit_info = VkSubmitInfo {
+    let mut submit_info = VkSubmitInfo {
         sType: VkStructureType::SubmitInfo,
-        pNext: &timeline_info as *const _ as *const std::ffi::c_void,
+        pNext: std::ptr::null(),
         waitSemaphoreCount: batch.wait_semaphores.len() as u32,
         pWaitSemaphores: if batch.wait_semaphores.is_empty() {
             std::ptr::null()
@@ -256,11 +255,12 @@ pub unsafe fn submit_batch(
         signalSemaphoreCount: 1,
         pSignalSemaphores: &timeline.semaphore,
     };
+    let submit_info = Chain::new(&mut submit_info).push(&mut timeline_info);
     
     // Submit to queue
     if let Some(icd) = super::icd_loader::get_icd() {
         if let Some(submit_fn) = icd.queue_submit {
-            let result = submit_fn(queue, 1, &submit_info, fence);
+            let result = icd_call!("vkQueueSubmit", submit_fn(queue, 1, submit_info.as_ptr(), fence));
             if result != VkResult::Success {
                 return Err(IcdError::VulkanError(result));
             }
@@ -273,6 +273,7 @@ pub unsafe fn submit_batch(
     
     // Reset pending count
     timeline.pending_count = 0;
+    manager.stats.record_submission(batch.command_buffers.len());
     
     Ok(signal_value)
 }
@@ -311,7 +312,7 @@ pub unsafe fn wait_timeline(
     
     if let Some(icd) = super::icd_loader::get_icd() {
         if let Some(wait_fn) = icd.wait_semaphores {
-            let result = wait_fn(device, &wait_info, timeout);
+            let result = icd_call!("vkWaitSemaphores", wait_fn(device, &wait_info, timeout));
             if result != VkResult::Success && result != VkResult::Timeout {
                 return Err(IcdError::VulkanError(result));
             }
@@ -375,7 +376,7 @@ impl BatchBuilder {
 }
 
 /// Batch statistics
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct BatchStats {
     pub total_submissions: u64,
     pub total_command_buffers: u64,
@@ -391,10 +392,9 @@ impl BatchStats {
     }
 }
 
-/// Get batch statistics
+/// Statistics of the batches submitted so far
 pub fn get_batch_stats() -> BatchStats {
-    // In a real implementation, we'd track these
-    BatchStats::default()
+    TIMELINE_MANAGER.lock().map(|manager| manager.stats.clone()).unwrap_or_default()
 }
 
 /// Set batch size threshold
@@ -16,8 +16,31 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
-pub mod core;
-pub mod sys;
+/// Log through the `log` facade if Kronos's verbosity allows `level`, and
+/// keep the message for bug reports
+#[cfg(not(feature = "minimal"))]
+macro_rules! kronos_log {
+    ($level:ident, $($arg:tt)+) => {
+        if $crate::implementation::logging::enabled(log::Level::$level) {
+            $crate::implementation::logging::remember(log::Level::$level, format_args!($($arg)+));
+            log::log!(log::Level::$level, $($arg)+);
+        }
+    };
+}
+
+/// Compiled out with the `minimal` feature; the arguments are still type
+/// checked so both builds accept the same code
+#[cfg(feature = "minimal")]
+macro_rules! kronos_log {
+    ($level:ident, $($arg:tt)+) => {
+        if false {
+            log::log!(log::Level::$level, $($arg)+);
+        }
+    };
+}
+
+// Structure, flag and handle definitions, shared with no_std users
+pub use kronos_compute_types::{core, sys};
 pub mod ffi;
 
 // Unified safe API
@@ -26,6 +49,26 @@ pub mod api;
 #[cfg(feature = "implementation")]
 pub mod implementation;
 
+// Driver call latencies
+#[cfg(feature = "icd-profiling")]
+pub mod metrics;
+
+// Steady-state allocation and driver call audit
+#[cfg(feature = "audit")]
+pub mod audit;
+
+// Failure injection and barrier hazard checks for tests
+#[cfg(feature = "implementation")]
+pub mod testing;
+
+// Environment capture for bug reports
+#[cfg(feature = "implementation")]
+pub mod report;
+
+// Flat C API over the safe layer for non-Rust embedders
+#[cfg(feature = "capi")]
+pub mod capi;
+
 // Re-export commonly used items
 pub use core::*;
 pub use sys::*;
@@ -0,0 +1,160 @@
+//! Latency of the calls Kronos forwards to the ICD
+//!
+//! With the `icd-profiling` feature every driver call made by a Kronos
+//! entry point is timed, excluding Kronos's own routing and validation, and
+//! aggregated per entry point. Comparing these times with the time spent in
+//! the safe API shows whether a slow path is in Kronos or in the driver.
+//!
+//! ```no_run
+//! for entry in kronos_compute::metrics::icd_latency() {
+//!     println!("{}: {} calls, mean {:?}, p99 {:?}", entry.entry_point, entry.count, entry.mean, entry.p99);
+//! }
+//! ```
+//!
+//! The `minimal` feature takes precedence: nothing is recorded.
+
+// Nothing records into the histograms in `minimal` builds
+#![cfg_attr(feature = "minimal", allow(dead_code))]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Sub-buckets per power of two; latencies are binned to within 1/8
+const SUB_BUCKETS: u64 = 8;
+const SUB_BUCKET_BITS: u32 = SUB_BUCKETS.trailing_zeros();
+
+/// Latency distribution of one entry point
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    count: u64,
+    total_ns: u128,
+    max_ns: u64,
+    /// Call counts by bucket, see [`bucket`]
+    buckets: Vec<u64>,
+}
+
+/// Bucket of a latency: exact below `SUB_BUCKETS` ns, then `SUB_BUCKETS`
+/// evenly spaced buckets per power of two
+fn bucket(ns: u64) -> usize {
+    if ns < SUB_BUCKETS {
+        return ns as usize;
+    }
+    let magnitude = 63 - ns.leading_zeros();
+    let sub = (ns >> (magnitude - SUB_BUCKET_BITS)) - SUB_BUCKETS;
+    ((magnitude - SUB_BUCKET_BITS + 1) as u64 * SUB_BUCKETS + sub) as usize
+}
+
+/// Largest latency that falls in `index`
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let sub = index % SUB_BUCKETS + SUB_BUCKETS;
+    ((sub + 1) << shift) - 1
+}
+
+impl Histogram {
+    fn record(&mut self, ns: u64) {
+        let index = bucket(ns);
+        if self.buckets.len() <= index {
+            self.buckets.resize(index + 1, 0);
+        }
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.total_ns += ns as u128;
+        self.max_ns = self.max_ns.max(ns);
+    }
+
+    /// Upper bound of the bucket holding the `quantile` latency
+    fn quantile(&self, quantile: f64) -> u64 {
+        let rank = ((self.count as f64 * quantile).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_upper_bound(index).min(self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+}
+
+static LATENCIES: Mutex<Option<HashMap<&'static str, Histogram>>> = Mutex::new(None);
+
+/// Record one driver call of `entry_point`
+pub(crate) fn record_icd_call(entry_point: &'static str, elapsed: Duration) {
+    let ns = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+    let mut latencies = LATENCIES.lock().unwrap();
+    latencies.get_or_insert_with(HashMap::new).entry(entry_point).or_default().record(ns);
+}
+
+/// Latency of the driver calls of one entry point
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcdLatency {
+    /// Vulkan entry point, e.g. `vkQueueSubmit`
+    pub entry_point: &'static str,
+    pub count: u64,
+    /// Time spent in the driver by all calls
+    pub total: Duration,
+    pub mean: Duration,
+    /// 99th percentile, accurate to within 1/8
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Driver call latencies since start-up or the last [`reset_icd_latency`],
+/// the entry points with the most time in the driver first
+pub fn icd_latency() -> Vec<IcdLatency> {
+    let latencies = LATENCIES.lock().unwrap();
+    let mut summary: Vec<IcdLatency> = latencies
+        .iter()
+        .flatten()
+        .map(|(&entry_point, histogram)| IcdLatency {
+            entry_point,
+            count: histogram.count,
+            total: Duration::from_nanos(histogram.total_ns.min(u64::MAX as u128) as u64),
+            mean: Duration::from_nanos((histogram.total_ns / histogram.count as u128) as u64),
+            p99: Duration::from_nanos(histogram.quantile(0.99)),
+            max: Duration::from_nanos(histogram.max_ns),
+        })
+        .collect();
+    summary.sort_by(|a, b| b.total.cmp(&a.total).then(a.entry_point.cmp(b.entry_point)));
+    summary
+}
+
+/// Discard the recorded latencies
+pub fn reset_icd_latency() {
+    *LATENCIES.lock().unwrap() = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_are_ordered_and_tight() {
+        let mut previous = 0;
+        for ns in (0..5000u64).chain([1 << 20, (1 << 20) + 1, u64::MAX / 2]) {
+            let index = bucket(ns);
+            assert!(index >= previous);
+            previous = index;
+            let upper = bucket_upper_bound(index);
+            assert!(upper >= ns && upper - ns <= ns / SUB_BUCKETS, "{} -> {}", ns, upper);
+        }
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let mut histogram = Histogram::default();
+        for ns in 1..=1000 {
+            histogram.record(ns * 1000);
+        }
+        let p99 = histogram.quantile(0.99);
+        assert!((990_000..=990_000 + 990_000 / SUB_BUCKETS).contains(&p99), "{}", p99);
+        assert_eq!(histogram.quantile(1.0), 1_000_000);
+        assert_eq!(histogram.total_ns, 500_500_000);
+    }
+}
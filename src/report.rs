@@ -0,0 +1,151 @@
+//! Environment capture for bug reports
+//!
+//! Most GPU compute bugs depend on which driver ran the code, so a useful
+//! bug report names the ICD, the devices and their drivers, how Kronos was
+//! built and configured, and what it logged on the way. [`bug_report_string`]
+//! gathers all of that into one text block to paste into an issue:
+//!
+//! ```no_run
+//! let ctx = kronos_compute::api::ComputeContext::new();
+//! eprintln!("{}", kronos_compute::report::bug_report_string());
+//! ```
+//!
+//! The user's home directory is replaced by `~` everywhere in the block,
+//! including in log messages and environment variables. Devices are listed
+//! once a context has enumerated them, and log messages only as far as the
+//! log verbosity let them through; the `minimal` feature logs nothing.
+
+use crate::api::devices::describe_cached_devices;
+use crate::api::Version;
+use crate::implementation::icd_loader::{self, IcdInfo};
+use crate::implementation::logging;
+use std::env;
+use std::fmt::Write;
+
+/// Cargo features that change Kronos's behaviour
+const FEATURES: &[(&str, bool)] = &[
+    ("validation", cfg!(feature = "validation")),
+    ("vendored", cfg!(feature = "vendored")),
+    ("implementation", cfg!(feature = "implementation")),
+    ("telemetry", cfg!(feature = "telemetry")),
+    ("mock-icd", cfg!(feature = "mock-icd")),
+    ("bundled-swiftshader", cfg!(feature = "bundled-swiftshader")),
+    ("icd-profiling", cfg!(feature = "icd-profiling")),
+    ("audit", cfg!(feature = "audit")),
+    ("opencl", cfg!(feature = "opencl")),
+    ("minimal", cfg!(feature = "minimal")),
+    ("object-registry", cfg!(feature = "object-registry")),
+];
+
+/// Environment variable prefixes that affect Kronos or the Vulkan loader
+const ENV_PREFIXES: &[&str] = &["KRONOS_", "VK_"];
+
+/// Describe the build, drivers, devices, environment and recent log
+/// messages, with the home directory redacted
+pub fn bug_report_string() -> String {
+    let mut report = String::new();
+    // Writing to a String cannot fail
+    let _ = write_report(&mut report);
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok();
+    redact_home(&report, home.as_deref())
+}
+
+fn write_report(out: &mut String) -> std::fmt::Result {
+    writeln!(out, "Kronos Compute {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(out, "Platform: {} {}", env::consts::OS, env::consts::ARCH)?;
+    let features: Vec<&str> = FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect();
+    writeln!(out, "Features: {}", features.join(", "))?;
+    writeln!(out, "Log verbosity: {:?}", logging::log_verbosity())?;
+
+    writeln!(out, "\nICDs:")?;
+    let icds = if icd_loader::aggregated_mode_enabled() {
+        icd_loader::get_all_icds()
+            .iter()
+            .map(|icd| IcdInfo {
+                library_path: icd.library_path.clone(),
+                manifest_path: None,
+                api_version: icd.api_version,
+                is_software: icd_loader::is_software_library(&icd.library_path),
+                is_portability_driver: icd.is_portability_driver,
+            })
+            .collect()
+    } else {
+        icd_loader::selected_icd_info().into_iter().collect::<Vec<_>>()
+    };
+    if icds.is_empty() {
+        writeln!(out, "  none loaded")?;
+    }
+    for icd in &icds {
+        write!(
+            out,
+            "  {} (Vulkan {}, {}",
+            icd.library_path.display(),
+            Version::from_raw(icd.api_version),
+            if icd.is_software { "software" } else { "hardware" }
+        )?;
+        if icd.is_portability_driver {
+            out.push_str(", portability");
+        }
+        out.push_str(")\n");
+    }
+
+    writeln!(out, "\nDevices:")?;
+    let devices = describe_cached_devices();
+    if devices.is_empty() {
+        writeln!(out, "  none enumerated")?;
+    }
+    for (index, device) in devices.iter().enumerate() {
+        writeln!(out, "  {}: {}", index, device)?;
+    }
+
+    writeln!(out, "\nEnvironment:")?;
+    let mut vars: Vec<(String, String)> = env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.to_string_lossy().into_owned())))
+        .filter(|(name, _)| ENV_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+        .collect();
+    vars.sort();
+    if vars.is_empty() {
+        writeln!(out, "  no overrides")?;
+    }
+    for (name, value) in &vars {
+        writeln!(out, "  {}={}", name, value)?;
+    }
+
+    let messages = logging::recent_messages();
+    writeln!(out, "\nRecent log messages ({}):", messages.len())?;
+    for message in &messages {
+        writeln!(out, "  {}", message)?;
+    }
+    Ok(())
+}
+
+/// Replace every occurrence of `home` in `text` with `~`
+fn redact_home(text: &str, home: Option<&str>) -> String {
+    match home.map(|home| home.trim_end_matches(['/', '\\'])) {
+        Some(home) if !home.is_empty() => text.replace(home, "~"),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_home_directory_is_redacted() {
+        let text = "KRONOS_ICD_SEARCH_PATHS=/home/alice/icds:/usr/share/vulkan";
+        assert_eq!(redact_home(text, Some("/home/alice/")), "KRONOS_ICD_SEARCH_PATHS=~/icds:/usr/share/vulkan");
+        assert_eq!(redact_home(text, Some("/")), text);
+        assert_eq!(redact_home(text, None), text);
+    }
+
+    #[test]
+    fn test_report_includes_version_and_log_messages() {
+        logging::remember(Level::Warn, format_args!("report test message"));
+        let report = bug_report_string();
+        assert!(report.starts_with(&format!("Kronos Compute {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("implementation"));
+        assert!(report.contains("[WARN] report test message"));
+    }
+}
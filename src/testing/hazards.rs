@@ -0,0 +1,369 @@
+//! Barrier hazard simulation
+//!
+//! A reference for what [`BarrierTracker`] must do: given a sequence of
+//! buffer accesses, the simulator finds the hazards (read-after-write,
+//! write-after-read, write-after-write, and shader access after a host
+//! write) and the fewest per-buffer barriers that separate them. Replaying
+//! the same sequence through a tracker checks that every hazard is covered
+//! by one of its barriers and measures how many barriers it spends beyond
+//! the minimum:
+//!
+//! ```
+//! use kronos_compute::implementation::barrier_policy::GpuVendor;
+//! use kronos_compute::testing::hazards::AccessSequence;
+//!
+//! let sequence = AccessSequence::generate(7, 64, 4);
+//! let report = sequence.replay(GpuVendor::AMD);
+//! assert!(report.is_safe(), "{}\n{}", sequence, report);
+//! ```
+//!
+//! Sequences are generated from a seed and print in a compact form that
+//! parses back, so a failing case can be replayed exactly.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::core::VkAccessFlags;
+use crate::implementation::barrier_policy::{BarrierTracker, GpuVendor};
+use crate::sys::VkBuffer;
+
+/// Size the tracker is told each access covers; barriers are per buffer
+const ACCESS_SIZE: u64 = 4096;
+
+/// How a buffer is accessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    /// Written by the host, e.g. an upload
+    HostWrite,
+    /// Read by a compute shader
+    ShaderRead,
+    /// Written by a compute shader
+    ShaderWrite,
+    /// Read and written by a compute shader
+    ShaderReadWrite,
+}
+
+impl AccessKind {
+    const ALL: [AccessKind; 4] = [Self::HostWrite, Self::ShaderRead, Self::ShaderWrite, Self::ShaderReadWrite];
+
+    /// Access mask handed to the tracker
+    pub fn flags(self) -> VkAccessFlags {
+        match self {
+            Self::HostWrite => VkAccessFlags::HOST_WRITE,
+            Self::ShaderRead => VkAccessFlags::SHADER_READ,
+            Self::ShaderWrite => VkAccessFlags::SHADER_WRITE,
+            Self::ShaderReadWrite => VkAccessFlags::SHADER_READ | VkAccessFlags::SHADER_WRITE,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Self::HostWrite => "h",
+            Self::ShaderRead => "r",
+            Self::ShaderWrite => "w",
+            Self::ShaderReadWrite => "rw",
+        }
+    }
+}
+
+/// One access of a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Access {
+    pub buffer: u32,
+    pub kind: AccessKind,
+}
+
+/// Accesses of one buffer since its last barrier
+#[derive(Debug, Clone, Copy, Default)]
+struct Unsynchronized {
+    host_write: bool,
+    shader_read: bool,
+    shader_write: bool,
+}
+
+impl Unsynchronized {
+    /// Whether `kind` conflicts with an access not yet behind a barrier
+    ///
+    /// Host writes are ordered with each other by the host itself.
+    fn conflicts(&self, kind: AccessKind) -> bool {
+        match kind {
+            AccessKind::HostWrite => self.shader_read || self.shader_write,
+            AccessKind::ShaderRead => self.host_write || self.shader_write,
+            AccessKind::ShaderWrite | AccessKind::ShaderReadWrite => {
+                self.host_write || self.shader_read || self.shader_write
+            }
+        }
+    }
+
+    fn add(&mut self, kind: AccessKind) {
+        match kind {
+            AccessKind::HostWrite => self.host_write = true,
+            AccessKind::ShaderRead => self.shader_read = true,
+            AccessKind::ShaderWrite => self.shader_write = true,
+            AccessKind::ShaderReadWrite => {
+                self.shader_read = true;
+                self.shader_write = true;
+            }
+        }
+    }
+}
+
+/// Per-buffer state of a replay, indexed by buffer
+#[derive(Default)]
+struct Simulator {
+    buffers: Vec<Unsynchronized>,
+}
+
+impl Simulator {
+    fn state(&mut self, buffer: u32) -> &mut Unsynchronized {
+        let index = buffer as usize;
+        if self.buffers.len() <= index {
+            self.buffers.resize(index + 1, Unsynchronized::default());
+        }
+        &mut self.buffers[index]
+    }
+
+    /// Record `access`, behind a new barrier if `barrier`; returns whether
+    /// it conflicted with an access the barriers so far do not cover
+    fn access(&mut self, access: Access, barrier: bool) -> bool {
+        let state = self.state(access.buffer);
+        let conflict = state.conflicts(access.kind);
+        if barrier {
+            *state = Unsynchronized::default();
+        }
+        state.add(access.kind);
+        conflict && !barrier
+    }
+}
+
+/// A sequence of buffer accesses, in submission order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessSequence {
+    pub accesses: Vec<Access>,
+}
+
+impl AccessSequence {
+    /// `len` accesses spread over `buffers` buffers, the same for the same seed
+    pub fn generate(seed: u64, len: usize, buffers: u32) -> Self {
+        let mut state = seed;
+        let mut next = move || {
+            // splitmix64
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        let accesses = (0..len)
+            .map(|_| Access {
+                buffer: (next() % buffers.max(1) as u64) as u32,
+                kind: AccessKind::ALL[(next() % AccessKind::ALL.len() as u64) as usize],
+            })
+            .collect();
+        Self { accesses }
+    }
+
+    /// Where the fewest barriers go: before each access that conflicts with
+    /// an earlier one on the same buffer not yet behind a barrier
+    pub fn required_barriers(&self) -> Vec<bool> {
+        let mut simulator = Simulator::default();
+        self.accesses
+            .iter()
+            .map(|&access| {
+                let barrier = simulator.state(access.buffer).conflicts(access.kind);
+                simulator.access(access, barrier);
+                barrier
+            })
+            .collect()
+    }
+
+    /// Feed the sequence to a fresh tracker for `vendor` and check its
+    /// barriers against the simulator
+    pub fn replay(&self, vendor: GpuVendor) -> ReplayReport {
+        let mut tracker = BarrierTracker::new(vendor);
+        self.replay_with(&mut tracker)
+    }
+
+    /// Feed the sequence to `tracker`, which should not have seen the
+    /// buffers before
+    pub fn replay_with(&self, tracker: &mut BarrierTracker) -> ReplayReport {
+        let required = self.required_barriers();
+        let mut simulator = Simulator::default();
+        let mut report = ReplayReport {
+            accesses: self.accesses.len() as u64,
+            required: required.iter().filter(|&&barrier| barrier).count() as u64,
+            ..ReplayReport::default()
+        };
+        for (index, &access) in self.accesses.iter().enumerate() {
+            // Offset handles by one: raw 0 is the null buffer
+            let buffer = VkBuffer::from_raw(access.buffer as u64 + 1);
+            let barrier = tracker.track_buffer_access(buffer, access.kind.flags(), 0, ACCESS_SIZE);
+            if barrier {
+                report.inserted += 1;
+            } else {
+                report.elided += 1;
+            }
+            if simulator.access(access, barrier) {
+                report.missing.push(index);
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for AccessSequence {
+    /// Space-separated `buffer:kind`, with kinds `h`, `r`, `w` and `rw`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, access) in self.accesses.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}:{}", access.buffer, access.kind.code())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AccessSequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let accesses = s
+            .split_whitespace()
+            .map(|token| {
+                let (buffer, kind) = token.split_once(':').ok_or_else(|| format!("expected buffer:kind, got {:?}", token))?;
+                let buffer = buffer.parse().map_err(|_| format!("bad buffer index in {:?}", token))?;
+                let kind = AccessKind::ALL
+                    .into_iter()
+                    .find(|candidate| candidate.code() == kind)
+                    .ok_or_else(|| format!("bad access kind in {:?}", token))?;
+                Ok(Access { buffer, kind })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { accesses })
+    }
+}
+
+/// How a tracker's barriers compare with the simulator's
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub accesses: u64,
+    /// Barriers the simulator needs
+    pub required: u64,
+    /// Barriers the tracker inserted
+    pub inserted: u64,
+    /// Accesses the tracker let through without a barrier
+    pub elided: u64,
+    /// Indices of accesses left racing with an earlier one
+    pub missing: Vec<usize>,
+}
+
+impl ReplayReport {
+    /// Whether every hazard was behind a barrier
+    pub fn is_safe(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// Share of accesses the tracker let through without a barrier
+    pub fn elision_rate(&self) -> f64 {
+        if self.accesses == 0 {
+            0.0
+        } else {
+            self.elided as f64 / self.accesses as f64
+        }
+    }
+
+    /// Barriers inserted beyond the minimum
+    pub fn excess(&self) -> u64 {
+        self.inserted.saturating_sub(self.required)
+    }
+
+    /// Add up the reports of several replays
+    pub fn merge(&mut self, other: &ReplayReport) {
+        let offset = self.accesses as usize;
+        self.accesses += other.accesses;
+        self.required += other.required;
+        self.inserted += other.inserted;
+        self.elided += other.elided;
+        self.missing.extend(other.missing.iter().map(|index| index + offset));
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} accesses: {} barriers inserted, {} required ({} excess), elision rate {:.1}%",
+            self.accesses,
+            self.inserted,
+            self.required,
+            self.excess(),
+            self.elision_rate() * 100.0
+        )?;
+        if !self.missing.is_empty() {
+            write!(f, "; missing before accesses {:?}", self.missing)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn vendor() -> impl Strategy<Value = GpuVendor> {
+        prop_oneof![
+            Just(GpuVendor::AMD),
+            Just(GpuVendor::NVIDIA),
+            Just(GpuVendor::Apple),
+            Just(GpuVendor::Intel),
+            Just(GpuVendor::Other),
+        ]
+    }
+
+    #[test]
+    fn test_sequence_round_trips_through_text() {
+        let sequence = AccessSequence::generate(42, 32, 3);
+        assert_eq!(sequence.to_string().parse::<AccessSequence>().unwrap(), sequence);
+        assert_eq!(AccessSequence::generate(42, 32, 3), sequence);
+        assert!("0:x".parse::<AccessSequence>().is_err());
+    }
+
+    #[test]
+    fn test_simulator_places_fewest_barriers() {
+        let sequence: AccessSequence = "0:h 0:r 0:r 1:w 0:w 1:w 0:h 0:h 1:rw".parse().unwrap();
+        let required = sequence.required_barriers();
+        assert_eq!(required, [false, true, false, false, true, true, true, false, true]);
+    }
+
+    #[test]
+    fn test_write_after_write_gets_a_barrier() {
+        let sequence: AccessSequence = "0:w 0:w".parse().unwrap();
+        let report = sequence.replay(GpuVendor::NVIDIA);
+        assert!(report.is_safe(), "{}", report);
+    }
+
+    #[test]
+    fn test_elision_rate_over_corpus() {
+        let mut total = ReplayReport::default();
+        for seed in 0..64 {
+            let sequence = AccessSequence::generate(seed, 128, 4);
+            let report = sequence.replay(GpuVendor::AMD);
+            assert!(report.is_safe(), "seed {}: {}\n{}", seed, sequence, report);
+            total.merge(&report);
+        }
+        // The corpus is fixed; about one access in eight needs no barrier
+        assert!(total.elision_rate() > 0.1, "{}", total);
+        assert!(total.inserted >= total.required, "{}", total);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_tracker_covers_every_hazard(seed in any::<u64>(), len in 1usize..256, buffers in 1u32..8, vendor in vendor()) {
+            let sequence = AccessSequence::generate(seed, len, buffers);
+            let report = sequence.replay(vendor);
+            prop_assert!(report.is_safe(), "{}\n{}", sequence, report);
+            prop_assert!(report.inserted >= report.required, "{}", report);
+        }
+    }
+}
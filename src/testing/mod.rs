@@ -0,0 +1,137 @@
+//! Failure injection for resilience testing
+//!
+//! Arm a [`FailurePoint`] and the matching Kronos entry point returns an
+//! error instead of reaching the driver, so applications can exercise their
+//! out-of-memory and device-lost recovery paths on healthy hardware (or on
+//! the `mock-icd` driver):
+//!
+//! ```no_run
+//! use kronos_compute::testing::{inject_failure, FailurePoint};
+//!
+//! // The 11th memory allocation from now fails with ErrorOutOfDeviceMemory
+//! inject_failure(FailurePoint::Allocate { after: 10 });
+//! ```
+//!
+//! Each injection fires once. Injections are process-wide and apply to
+//! calls from every thread.
+//!
+//! [`hazards`] checks barrier placement against a reference simulator.
+
+pub mod hazards;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use crate::ffi::VkResult;
+
+/// An entry point to fail, and how many calls succeed before it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePoint {
+    /// `vkAllocateMemory`; fails with `ErrorOutOfDeviceMemory`
+    Allocate { after: u32 },
+    /// `vkCreateBuffer`; fails with `ErrorOutOfDeviceMemory`
+    CreateBuffer { after: u32 },
+    /// `vkMapMemory`; fails with `ErrorMemoryMapFailed`
+    Map { after: u32 },
+    /// `vkQueueSubmit`; fails with `ErrorDeviceLost`
+    Submit { after: u32 },
+    /// `vkWaitForFences`; fails with `ErrorDeviceLost`
+    FenceWait { after: u32 },
+}
+
+/// Entry points with an injection site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Site {
+    Allocate,
+    CreateBuffer,
+    Map,
+    Submit,
+    FenceWait,
+}
+
+impl FailurePoint {
+    fn site(&self) -> (Site, u32) {
+        match *self {
+            Self::Allocate { after } => (Site::Allocate, after),
+            Self::CreateBuffer { after } => (Site::CreateBuffer, after),
+            Self::Map { after } => (Site::Map, after),
+            Self::Submit { after } => (Site::Submit, after),
+            Self::FenceWait { after } => (Site::FenceWait, after),
+        }
+    }
+
+    /// Error returned when the failure fires, unless overridden
+    pub fn default_result(&self) -> VkResult {
+        match self.site().0 {
+            Site::Allocate | Site::CreateBuffer => VkResult::ErrorOutOfDeviceMemory,
+            Site::Map => VkResult::ErrorMemoryMapFailed,
+            Site::Submit | Site::FenceWait => VkResult::ErrorDeviceLost,
+        }
+    }
+}
+
+struct Injection {
+    site: Site,
+    /// Calls left to succeed before this one fires
+    remaining: u32,
+    result: VkResult,
+}
+
+/// Fast path: skip the lock while nothing is armed
+static ARMED: AtomicBool = AtomicBool::new(false);
+static INJECTIONS: Mutex<Vec<Injection>> = Mutex::new(Vec::new());
+
+/// Fail `point` with its [default error](FailurePoint::default_result)
+pub fn inject_failure(point: FailurePoint) {
+    inject_failure_with(point, point.default_result());
+}
+
+/// Fail `point` with `result`
+pub fn inject_failure_with(point: FailurePoint, result: VkResult) {
+    let (site, after) = point.site();
+    let mut injections = INJECTIONS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    injections.push(Injection { site, remaining: after, result });
+    ARMED.store(true, Ordering::Release);
+}
+
+/// Disarm all pending injections
+pub fn clear_injected_failures() {
+    let mut injections = INJECTIONS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    injections.clear();
+    ARMED.store(false, Ordering::Release);
+}
+
+/// Number of injections armed and not yet fired
+pub fn pending_injected_failures() -> usize {
+    INJECTIONS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len()
+}
+
+/// Count a call at `site`, returning the error to report if an injection fires
+pub(crate) fn injected_failure(site: Site) -> Option<VkResult> {
+    if !ARMED.load(Ordering::Acquire) {
+        return None;
+    }
+    let mut injections = INJECTIONS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut fired = None;
+    injections.retain_mut(|injection| {
+        if injection.site != site {
+            return true;
+        }
+        if injection.remaining > 0 {
+            injection.remaining -= 1;
+            return true;
+        }
+        // Only the first due injection fires; later ones wait for the next call
+        if fired.is_some() {
+            return true;
+        }
+        fired = Some(injection.result);
+        false
+    });
+    if injections.is_empty() {
+        ARMED.store(false, Ordering::Release);
+    }
+    if let Some(result) = fired {
+        kronos_log!(Warn, "[testing] Injected {:?} at {:?}", result, site);
+    }
+    fired
+}
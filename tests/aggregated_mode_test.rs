@@ -65,7 +65,7 @@ fn test_aggregated_mode_enumeration() {
                         driverVersion: 0,
                         vendorID: 0,
                         deviceID: 0,
-                        deviceType: VkPhysicalDeviceType::Other,
+                        deviceType: VkPhysicalDeviceType::Other as i32,
                         deviceName: [0; 256],
                         pipelineCacheUUID: [0; 16],
                         limits: std::mem::zeroed(),
@@ -0,0 +1,91 @@
+//! The flat C API, called the way a C embedder would, against the mock ICD
+
+#![cfg(all(feature = "capi", feature = "mock-icd"))]
+
+use kronos_compute::capi::*;
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use std::ffi::{c_void, CStr};
+use std::ptr;
+
+/// Push constants of `shaders/saxpy.comp`
+#[repr(C)]
+struct SaxpyParams {
+    alpha: f32,
+    count: u32,
+}
+
+fn last_error() -> String {
+    let message = kronos_last_error();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_c_api_runs_a_dispatch_through_handles() {
+    let mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let spirv = include_bytes!("../shaders/saxpy.spv").to_vec();
+    let x: Vec<f32> = (0..1024).map(|i| i as f32).collect();
+    let bytes = std::mem::size_of_val(x.as_slice());
+
+    unsafe {
+        let mut ctx = ptr::null_mut();
+        assert_eq!(kronos_ctx_create(&mut ctx), KronosStatus::KRONOS_SUCCESS);
+
+        let mut buffers = [ptr::null_mut(); 3];
+        assert_eq!(kronos_buffer_create(ctx, x.as_ptr() as *const c_void, bytes, &mut buffers[0]), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(kronos_buffer_create(ctx, x.as_ptr() as *const c_void, bytes, &mut buffers[1]), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(kronos_buffer_create(ctx, ptr::null(), bytes, &mut buffers[2]), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(kronos_buffer_size(buffers[2]), bytes);
+
+        let mut read = vec![0.0f32; x.len()];
+        assert_eq!(kronos_buffer_read(buffers[0], read.as_mut_ptr() as *mut c_void, bytes), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(read, x);
+        assert_eq!(kronos_buffer_write(buffers[0], [7.0f32].as_ptr() as *const c_void, 4), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(kronos_buffer_read(buffers[0], read.as_mut_ptr() as *mut c_void, 8), KronosStatus::KRONOS_SUCCESS);
+        assert_eq!(read[..2], [7.0, 1.0]);
+
+        let mut pipeline = ptr::null_mut();
+        assert_eq!(
+            kronos_pipeline_create(ctx, spirv.as_ptr() as *const u32, spirv.len(), &mut pipeline),
+            KronosStatus::KRONOS_SUCCESS
+        );
+        let params = SaxpyParams { alpha: 2.0, count: 1024 };
+        let bound: Vec<*const KronosBuffer> = buffers.iter().map(|&buffer| buffer as *const _).collect();
+        let dispatches = mock.call_count("vkCmdDispatch");
+        let status = kronos_dispatch(
+            ctx,
+            pipeline,
+            bound.as_ptr(),
+            3,
+            &params as *const SaxpyParams as *const c_void,
+            std::mem::size_of::<SaxpyParams>(),
+            4,
+            1,
+            1,
+        );
+        assert_eq!(status, KronosStatus::KRONOS_SUCCESS, "{}", last_error());
+        assert_eq!(mock.call_count("vkCmdDispatch"), dispatches + 1);
+
+        // Failures come back as a status and a message on this thread
+        assert_eq!(kronos_buffer_create(ptr::null(), ptr::null(), 16, &mut buffers[0]), KronosStatus::KRONOS_ERROR_INVALID_ARGUMENT);
+        assert_eq!(last_error(), "ctx is null");
+        assert_eq!(
+            kronos_buffer_read(buffers[2], read.as_mut_ptr() as *mut c_void, bytes + 4),
+            KronosStatus::KRONOS_ERROR_INVALID_ARGUMENT
+        );
+        assert!(last_error().contains("exceeds buffer size"));
+        let mut rejected = ptr::null_mut();
+        assert_eq!(kronos_pipeline_create(ctx, spirv.as_ptr() as *const u32, 6, &mut rejected), KronosStatus::KRONOS_ERROR_SHADER);
+        assert!(rejected.is_null());
+        assert!(std::thread::spawn(|| kronos_last_error().is_null()).join().unwrap());
+
+        // Handles outlive the context they came from
+        kronos_ctx_destroy(ctx);
+        assert_eq!(kronos_buffer_read(buffers[1], read.as_mut_ptr() as *mut c_void, 4), KronosStatus::KRONOS_SUCCESS);
+        kronos_pipeline_destroy(pipeline);
+        for buffer in buffers {
+            kronos_buffer_destroy(buffer);
+        }
+        kronos_buffer_destroy(ptr::null_mut());
+    }
+}
@@ -1,6 +1,9 @@
 //! FFI safety tests - ensures bitflags are properly repr(C) or repr(transparent)
 
 use kronos_compute::core::flags::*;
+use kronos_compute::core::enums::*;
+use kronos_compute::core::structs::VkPhysicalDeviceProperties;
+use kronos_compute::ffi::VkResult;
 use std::mem;
 
 // Macro to test that a type is FFI-safe
@@ -67,4 +70,30 @@ fn test_bitflags_operations() {
     // Test from_bits
     assert_eq!(VkQueueFlags::from_bits(0x00000002), Some(VkQueueFlags::COMPUTE));
     assert_eq!(VkQueueFlags::from_bits(0xFFFFFFFF), None); // Invalid bits
+
+    // Bits from newer drivers survive a round trip
+    let newer = VkQueueFlags::from_bits_retain(0x00000102);
+    assert!(newer.contains(VkQueueFlags::COMPUTE));
+    assert_eq!(newer.bits(), 0x00000102);
+    assert_eq!(VkQueueFlags::from_bits_truncate(0x00000102), VkQueueFlags::COMPUTE);
+}
+
+#[test]
+fn test_enums_convert_from_raw_values() {
+    assert_eq!(VkDescriptorType::try_from(7), Ok(VkDescriptorType::StorageBuffer));
+    assert_eq!(VkResult::try_from(-1000069000), Ok(VkResult::ErrorOutOfPoolMemory));
+    assert_eq!(VkPipelineStageFlagBits::try_from(0x00000800), Ok(VkPipelineStageFlagBits::ComputeShader));
+
+    let unknown = VkPerformanceCounterUnitKHR::try_from(1000).unwrap_err();
+    assert_eq!(unknown, UnknownValue { type_name: "VkPerformanceCounterUnitKHR", value: 1000 });
+    assert_eq!(unknown.to_string(), "1000 is not a known VkPerformanceCounterUnitKHR");
+    assert!(VkResult::try_from(-1000174001).is_err());
+    // Codes from newer drivers are unknown errors
+    assert_eq!(VkResult::from_raw(-1000174001), VkResult::ErrorUnknown);
+    assert_eq!(VkResult::from_raw(-4), VkResult::ErrorDeviceLost);
+
+    let mut properties = VkPhysicalDeviceProperties { deviceType: 2, ..Default::default() };
+    assert_eq!(properties.device_type(), VkPhysicalDeviceType::DiscreteGpu);
+    properties.deviceType = 1000;
+    assert_eq!(properties.device_type(), VkPhysicalDeviceType::Other);
 }
\ No newline at end of file
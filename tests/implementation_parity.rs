@@ -0,0 +1,227 @@
+//! Replays a fixed set of workloads through ICD forwarding and through the
+//! pure-Rust mock ICD, and compares what each recorded and computed
+//!
+//! Both paths share the safe API, so the command streams Kronos records for
+//! a workload must match once driver handles are replaced by the order they
+//! first appear in. Outputs are compared where the mock can compute them,
+//! i.e. for transfers; it does not execute shaders. Without a Vulkan driver
+//! the forwarding pass is skipped and the mock is checked against the host.
+
+#![cfg(feature = "mock-icd")]
+
+use kronos_compute::api::{
+    BufferBinding, CommandListing, ComputeContext, PipelineConfig, PlannedCommand, PlannedResource, Result,
+};
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use std::collections::HashMap;
+
+/// One workload: runs on `ctx` and returns the output it read back, if any
+struct Workload {
+    name: &'static str,
+    run: fn(&ComputeContext) -> Result<Vec<u32>>,
+    /// Output computed on the host, for workloads the mock can execute
+    expected: Option<fn() -> Vec<u32>>,
+}
+
+/// What one path recorded and computed for a workload
+#[derive(Debug)]
+struct Replay {
+    commands: Vec<String>,
+    output: Vec<u32>,
+}
+
+const WORKLOADS: &[Workload] = &[
+    Workload { name: "staged_upload", run: staged_upload, expected: Some(ramp) },
+    Workload { name: "slice_copy", run: slice_copy, expected: Some(slice_copy_expected) },
+    Workload { name: "saxpy", run: saxpy, expected: None },
+    Workload { name: "chained_saxpy", run: chained_saxpy, expected: None },
+    Workload { name: "bind_all", run: bind_all, expected: None },
+];
+
+fn ramp() -> Vec<u32> {
+    (0..256).collect()
+}
+
+fn slice_copy_expected() -> Vec<u32> {
+    let mut values = ramp();
+    values.copy_within(..64, 128);
+    values
+}
+
+fn staged_upload(ctx: &ComputeContext) -> Result<Vec<u32>> {
+    ctx.create_buffer(&ramp())?.read()
+}
+
+fn slice_copy(ctx: &ComputeContext) -> Result<Vec<u32>> {
+    let arena = ctx.create_buffer(&ramp())?;
+    ctx.copy_slice(arena.slice(0, 256)?, arena.slice(512, 256)?)?;
+    arena.read()
+}
+
+/// Push constants of `shaders/saxpy.comp`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SaxpyParams {
+    alpha: f32,
+    count: u32,
+}
+
+fn saxpy_config() -> PipelineConfig {
+    PipelineConfig {
+        local_size: (256, 1, 1),
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        push_constant_size: std::mem::size_of::<SaxpyParams>() as u32,
+        ..Default::default()
+    }
+}
+
+fn saxpy(ctx: &ComputeContext) -> Result<Vec<u32>> {
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))?;
+    let pipeline = ctx.create_pipeline_with_config(&shader, saxpy_config())?;
+    let x = ctx.create_buffer(&[1.0f32; 1024])?;
+    let y = ctx.create_buffer(&[2.0f32; 1024])?;
+    let out = ctx.create_buffer_uninit(4096)?;
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, &x)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .push_constants(&SaxpyParams { alpha: 3.0, count: 1024 })
+        .workgroups(4, 1, 1)
+        .execute()?;
+    Ok(Vec::new())
+}
+
+fn chained_saxpy(ctx: &ComputeContext) -> Result<Vec<u32>> {
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))?;
+    let pipeline = ctx.create_pipeline_with_config(&shader, saxpy_config())?;
+    let x = ctx.create_buffer(&[1.0f32; 1024])?;
+    let out = ctx.create_buffer_uninit(4096)?;
+    // Out of order, so no persistent descriptor set
+    let mut builder = ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &out).bind_buffer(0, &x);
+    for alpha in [1.0f32, 1.0, 2.0] {
+        builder = builder.push_constants(&SaxpyParams { alpha, count: 1024 }).workgroups(4, 1, 1).then(&pipeline);
+    }
+    builder.push_constants(&SaxpyParams { alpha: 2.0, count: 512 }).workgroups(2, 1, 1).execute()?;
+    Ok(Vec::new())
+}
+
+fn bind_all(ctx: &ComputeContext) -> Result<Vec<u32>> {
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv"))?;
+    let pipeline = ctx.create_pipeline_with_config(&shader, saxpy_config())?;
+    let x = ctx.create_buffer(&[1.0f32; 256])?;
+    let y = ctx.create_buffer(&[2.0f32; 256])?;
+    let out = ctx.create_buffer_uninit(1024)?;
+    let set = pipeline.bind_all(&[&x, &y, &out])?;
+    for count in [256u32, 128] {
+        ctx.dispatch(&pipeline).descriptor_set(&set).push_constants(&SaxpyParams { alpha: 1.0, count }).execute()?;
+    }
+    Ok(Vec::new())
+}
+
+/// Replaces driver handles with the order they first appear in
+#[derive(Default)]
+struct Handles(HashMap<(&'static str, u64), usize>);
+
+impl Handles {
+    fn id(&mut self, kind: &'static str, raw: u64) -> String {
+        let next = self.0.len();
+        format!("{}#{}", kind, self.0.entry((kind, raw)).or_insert(next))
+    }
+}
+
+/// The listing with handles numbered; queue families differ between
+/// drivers and are left out
+fn normalize(listing: &CommandListing) -> Vec<String> {
+    let mut handles = Handles::default();
+    let mut lines = Vec::new();
+    for dispatch in &listing.dispatches {
+        lines.push(format!("dispatch {} blocking={}", dispatch.label, dispatch.blocking));
+        for command in &dispatch.commands {
+            lines.push(match command {
+                PlannedCommand::PipelineBarrier { src_stage, dst_stage, buffers } => {
+                    let buffers: Vec<String> = buffers
+                        .iter()
+                        .map(|(buffer, size)| format!("{} [0..{})", handles.id("buffer", buffer.as_raw()), size))
+                        .collect();
+                    format!("barrier {:?} -> {:?} {:?}", src_stage, dst_stage, buffers)
+                }
+                PlannedCommand::BindPipeline { pipeline } => format!("bind {}", handles.id("pipeline", pipeline.as_raw())),
+                PlannedCommand::BindDescriptorSet { set, persistent, bindings } => {
+                    let bindings: Vec<String> = bindings
+                        .iter()
+                        .map(|(binding, resource)| match resource {
+                            PlannedResource::Buffer { buffer, size } => {
+                                format!("{}: {} ({} bytes)", binding, handles.id("buffer", buffer.as_raw()), size)
+                            }
+                            PlannedResource::Image { view, descriptor_type } => {
+                                format!("{}: {:?} {}", binding, descriptor_type, handles.id("view", view.as_raw()))
+                            }
+                            PlannedResource::Constants { buffer, offset, size } => {
+                                format!("{}: constants {} +{} ({} bytes)", binding, handles.id("buffer", buffer.as_raw()), offset, size)
+                            }
+                        })
+                        .collect();
+                    format!("bind {} persistent={} {:?}", handles.id("set", set.as_raw()), persistent, bindings)
+                }
+                other => other.to_string(),
+            });
+        }
+    }
+    lines
+}
+
+/// Run every workload on `ctx`, first as a dry run for its command stream
+/// and then for real for its output
+fn replay(ctx: &ComputeContext) -> Vec<Replay> {
+    WORKLOADS
+        .iter()
+        .map(|workload| {
+            ctx.dry_run(true);
+            let recorded = (workload.run)(ctx);
+            ctx.dry_run(false);
+            recorded.unwrap_or_else(|e| panic!("{} failed to record: {}", workload.name, e));
+            let commands = normalize(&ctx.take_command_listing());
+            let output = (workload.run)(ctx).unwrap_or_else(|e| panic!("{} failed: {}", workload.name, e));
+            Replay { commands, output }
+        })
+        .collect()
+}
+
+#[test]
+fn test_forwarding_and_mock_record_the_same_commands() {
+    // The mock replaces any loaded driver, so the forwarding pass runs first
+    let forwarded = match ComputeContext::new() {
+        Ok(ctx) => Some(replay(&ctx)),
+        Err(e) => {
+            eprintln!("No Vulkan driver ({}); checking the mock ICD against the host only", e);
+            None
+        }
+    };
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    let ctx = ComputeContext::new().unwrap();
+    let mocked = replay(&ctx);
+    // Fresh handles, same commands
+    let again = replay(&ctx);
+
+    let chained = &mocked[3].commands;
+    assert!(chained.iter().any(|line| line.starts_with("barrier")));
+    assert_eq!(chained.iter().filter(|line| line.starts_with("bind pipeline#")).count(), 1);
+
+    for (index, workload) in WORKLOADS.iter().enumerate() {
+        let mock = &mocked[index];
+        match workload.expected {
+            Some(expected) => assert_eq!(mock.output, expected(), "{}: mock output differs from the host", workload.name),
+            None => assert!(!mock.commands.is_empty(), "{}: no commands recorded", workload.name),
+        }
+        assert_eq!(mock.commands, again[index].commands, "{}: mock replays differ", workload.name);
+        let Some(forwarded) = &forwarded else { continue };
+        assert_eq!(
+            forwarded[index].commands, mock.commands,
+            "{}: forwarding and mock recorded different commands",
+            workload.name
+        );
+        if workload.expected.is_some() {
+            assert_eq!(forwarded[index].output, mock.output, "{}: outputs differ", workload.name);
+        }
+    }
+}
@@ -0,0 +1,2568 @@
+//! Integration tests against the in-process mock ICD
+
+#![cfg(feature = "mock-icd")]
+
+use kronos_compute::api::{
+    first_divergence, hash_bytes, refresh_devices, Buffer, BufferBinding, BufferOptions, BufferUsage, ComputeContext, DescriptorPoolUsage, DeviceEvent, DispatchHashes, Features, FitStrategy, HashDivergence,
+    KronosAllocatorVtable, KronosError, KronosPlugin,
+    KronosPluginHost, KronosSchedulerVtable, MemoryConfig, MemoryPriority, PciBusAddress, PingPong, PipelineConfig, PlannedCommand, PlannedResource, PoolConfig, SlabGrowth, SplitDispatch, SubmitPlan,
+    TagUsage, Version, KRONOS_PLUGIN_ABI_VERSION,
+    ops::{self, AxpyBatch, GemvBatch},
+};
+use kronos_compute::implementation::error::IcdError;
+// Used by the tests that read the log history, which `minimal` compiles out
+#[cfg(not(feature = "minimal"))]
+use kronos_compute::implementation::child_objects::{live_children, set_destroy_policy, DestroyPolicy};
+#[cfg(not(feature = "minimal"))]
+use kronos_compute::implementation::{logging, vkDestroyDevice};
+use kronos_compute::implementation::mock_icd::{MockConfig, MockIcd};
+use kronos_compute::implementation::pool_allocator::{allocate_from_pool, free_allocation, get_pool_stats, PoolType};
+use kronos_compute::implementation::icd_loader::selected_icd_info;
+use kronos_compute::implementation::{
+    initialize_kronos_with, InitOptions, EXTERNAL_LOADER_NAME, vkAllocateMemory, vkBindBufferMemory, vkCreateBuffer, vkCreateFence, vkCreateInstance, vkDestroyFence, vkDestroyInstance, vkEnumeratePhysicalDevices, vkGetFenceStatus,
+    vkGetPhysicalDeviceFeatures, vkGetPhysicalDeviceProperties, vkQueueSubmit, vkWaitForFences,
+};
+use kronos_compute::testing::{clear_injected_failures, inject_failure, pending_injected_failures, FailurePoint};
+use kronos_compute::sys::*;
+use kronos_compute::core::*;
+use kronos_compute::ffi::*;
+use std::ffi::{c_void, CStr};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// The mock ICD is process-wide, so tests take turns installing it
+static MOCK: Mutex<()> = Mutex::new(());
+
+fn install(config: MockConfig) -> (MutexGuard<'static, ()>, MockIcd) {
+    let guard = MOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mock = MockIcd::install(config).expect("install mock ICD");
+    clear_injected_failures();
+    (guard, mock)
+}
+
+#[test]
+fn test_context_reports_mock_device() {
+    let (_guard, _mock) = install(MockConfig::default().device_name("Scripted GPU").vendor_id(0x10DE));
+    let ctx = ComputeContext::new().expect("context on mock ICD");
+
+    let properties = ctx.device_properties();
+    let name = unsafe { CStr::from_ptr(properties.deviceName.as_ptr()) };
+    assert_eq!(name.to_str().unwrap(), "Scripted GPU");
+    assert_eq!(properties.vendorID, 0x10DE);
+    assert_eq!(ctx.icd_info().unwrap().library_path.to_str(), Some("<mock-icd>"));
+}
+
+#[test]
+fn test_buffer_round_trip() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    let data: Vec<u32> = (0..256).collect();
+    let buffer = ctx.create_buffer(&data).unwrap();
+    assert_eq!(buffer.read::<u32>().unwrap(), data);
+    assert!(mock.call_count("vkCmdCopyBuffer") >= 2);
+}
+
+#[test]
+fn test_small_uploads_reuse_mapped_ring() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    let first = ctx.create_buffer(&[7u32; 16]).unwrap();
+    let maps = mock.call_count("vkMapMemory");
+    // Far more than fit in the ring at once, so regions must be reclaimed
+    let buffers: Vec<Buffer> = (0..2048u32).map(|i| ctx.create_buffer(&[i; 1024]).unwrap()).collect();
+    assert_eq!(mock.call_count("vkMapMemory"), maps);
+
+    // Too large for the ring: staged through a dedicated buffer
+    let large: Vec<u32> = (0..(2 << 20)).collect();
+    let large_buffer = ctx.create_buffer(&large).unwrap();
+    assert_eq!(mock.call_count("vkMapMemory"), maps + 1);
+
+    assert_eq!(first.read::<u32>().unwrap(), vec![7; 16]);
+    assert_eq!(buffers[2047].read::<u32>().unwrap(), vec![2047; 1024]);
+    assert_eq!(large_buffer.read::<u32>().unwrap(), large);
+}
+
+#[test]
+fn test_failed_allocation_surfaces_error() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    mock.fail_allocation(1);
+    assert!(ctx.create_buffer_uninit(1024).is_err());
+    // Only the scripted call fails
+    assert!(ctx.create_buffer_uninit(1024).is_ok());
+}
+
+#[test]
+fn test_fence_delay() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    mock.set_fence_delay(Duration::from_millis(50));
+
+    unsafe {
+        let mut fence = VkFence::NULL;
+        let info = VkFenceCreateInfo::default();
+        assert_eq!(vkCreateFence(ctx.device(), &info, ptr::null(), &mut fence), VkResult::Success);
+
+        let start = Instant::now();
+        assert_eq!(vkQueueSubmit(ctx.queue(), 0, ptr::null(), fence), VkResult::Success);
+        assert_eq!(vkGetFenceStatus(ctx.device(), fence), VkResult::NotReady);
+        assert_eq!(vkWaitForFences(ctx.device(), 1, &fence, VK_TRUE, 0), VkResult::Timeout);
+        assert_eq!(vkWaitForFences(ctx.device(), 1, &fence, VK_TRUE, u64::MAX), VkResult::Success);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        vkDestroyFence(ctx.device(), fence, ptr::null());
+    }
+}
+
+#[test]
+fn test_context_drop_releases_objects() {
+    let (_guard, mock) = install(MockConfig::default());
+    {
+        let ctx = ComputeContext::new().unwrap();
+        let _buffer = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+        assert!(!mock.live_objects().is_empty());
+    }
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+}
+
+#[test]
+fn test_context_dropped_by_a_completion_callback() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let buffers: Vec<_> = (0..3).map(|_| ctx.create_buffer(&[1.0f32; 64]).unwrap()).collect();
+    let dispatch = || {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(0, &buffers[0])
+            .bind_buffer(1, &buffers[1])
+            .bind_buffer(2, &buffers[2])
+            .push_constants(&2.0f32)
+    };
+
+    // The first callback drops the last handle while the second submission
+    // is still pending
+    let (sender, events) = mpsc::channel();
+    let (last, first_done) = (ctx.clone(), sender.clone());
+    mock.set_fence_delay(Duration::from_millis(20));
+    dispatch().on_complete(move || {
+        drop(last);
+        first_done.send("first").unwrap();
+    }).submit().unwrap();
+    mock.set_fence_delay(Duration::from_millis(200));
+    dispatch().on_complete(move || sender.send("second").unwrap()).submit().unwrap();
+    drop((buffers, pipeline, shader, ctx));
+
+    // The pending submission completes before the device goes away, and
+    // nothing is left behind
+    assert_eq!(events.recv_timeout(Duration::from_secs(5)), Ok("second"));
+    assert_eq!(events.recv_timeout(Duration::from_secs(5)), Ok("first"));
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+}
+
+#[test]
+fn test_injected_allocation_failure() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    inject_failure(FailurePoint::Allocate { after: 1 });
+    assert!(ctx.create_buffer_uninit(1024).is_ok());
+    assert!(ctx.create_buffer_uninit(1024).is_err());
+    assert_eq!(pending_injected_failures(), 0);
+    assert!(ctx.create_buffer_uninit(1024).is_ok());
+}
+
+#[test]
+fn test_injected_device_lost_on_submit() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    inject_failure(FailurePoint::Submit { after: 0 });
+    assert!(ctx.create_buffer(&[0u32; 16]).is_err());
+    // The injected error is returned before the call reaches the driver
+    assert_eq!(mock.call_count("vkQueueSubmit"), 0);
+}
+
+#[test]
+fn test_device_info_is_cached() {
+    let (_guard, mock) = install(MockConfig::default());
+    drop(ComputeContext::new().unwrap());
+    let queries = mock.call_count("vkGetPhysicalDeviceQueueFamilyProperties2");
+
+    drop(ComputeContext::new().unwrap());
+    assert_eq!(mock.call_count("vkGetPhysicalDeviceQueueFamilyProperties2"), queries);
+
+    refresh_devices();
+    drop(ComputeContext::new().unwrap());
+    assert!(mock.call_count("vkGetPhysicalDeviceQueueFamilyProperties2") > queries);
+}
+
+#[test]
+fn test_device_lost_event() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let (sender, events) = mpsc::channel();
+    ctx.on_device_event(move |event| sender.send(event.clone()).unwrap());
+
+    inject_failure(FailurePoint::Submit { after: 0 });
+    assert!(ctx.create_buffer(&[0u32; 16]).is_err());
+    assert!(ctx.is_device_lost());
+    let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(event, DeviceEvent::Lost { device_name: "Kronos Mock Device".into() });
+}
+
+#[test]
+fn test_device_removed_event() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    mock.unplug();
+    assert!(ctx.create_buffer(&[0u32; 16]).is_err());
+    // Registering after the loss still delivers the event
+    let (sender, events) = mpsc::channel();
+    ctx.on_device_event(move |event| sender.send(event.clone()).unwrap());
+    let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(event, DeviceEvent::Removed { device_name: "Kronos Mock Device".into() });
+}
+
+#[test]
+fn test_worker_transfers_span_staging_slots() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let worker = ctx.worker().unwrap();
+
+    // Larger than the whole staging ring
+    let data: Vec<u32> = (0..(5 << 20) / 4).collect();
+    let mut buffer = worker.create_buffer(&data).unwrap();
+    assert_eq!(buffer.read::<u32>().unwrap(), data);
+
+    let reversed: Vec<u32> = data.iter().rev().copied().collect();
+    worker.write(&mut buffer, &reversed).unwrap();
+    assert_eq!(worker.read::<u32>(&buffer).unwrap(), reversed);
+}
+
+#[test]
+fn test_workers_dispatch_from_threads() {
+    let (_guard, mock) = install(MockConfig::default());
+    {
+        let ctx = ComputeContext::new().unwrap();
+        let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+        let pipeline = ctx.create_pipeline(&shader).unwrap();
+        let workers: Vec<_> = (0..4).map(|_| ctx.worker().unwrap()).collect();
+
+        std::thread::scope(|scope| {
+            for (index, worker) in workers.iter().enumerate() {
+                let pipeline = &pipeline;
+                scope.spawn(move || {
+                    let x = worker.create_buffer(&[index as f32; 256]).unwrap();
+                    let y = worker.create_buffer(&[1.0f32; 256]).unwrap();
+                    let out = worker.create_buffer(&[0.0f32; 256]).unwrap();
+                    // Out-of-order bindings allocate from the worker's descriptor pool
+                    let dispatch = || worker.dispatch(pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x);
+                    let (sender, done) = mpsc::channel();
+                    dispatch().on_complete(move || sender.send(()).unwrap()).submit().unwrap();
+                    done.recv_timeout(Duration::from_secs(5)).unwrap();
+                    dispatch().execute().unwrap();
+                });
+            }
+        });
+        // Three uploads and two dispatches per worker
+        assert!(mock.call_count("vkQueueSubmit") >= 4 * 5);
+        drop(workers);
+    }
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "used with")]
+fn test_cross_context_binding_panics() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let other = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let buffer = other.create_buffer(&[0.0f32; 16]).unwrap();
+
+    let _ = ctx.dispatch(&pipeline).bind_buffer(0, &buffer);
+}
+
+#[test]
+fn test_memory_config_shapes_pool_slabs() {
+    let (_guard, mock) = install(MockConfig::default());
+    {
+        let buddy = PoolConfig { slab_size: 64 << 10, growth: SlabGrowth::Fixed, fit: FitStrategy::Buddy };
+        let ctx = ComputeContext::builder()
+            .memory_config(MemoryConfig::default().pool(PoolType::DeviceLocal, buddy))
+            .build()
+            .unwrap();
+        let requirements = VkMemoryRequirements { size: 48 << 10, alignment: 256, memoryTypeBits: 1 };
+        let ids: Vec<u64> = (0..3)
+            .map(|_| unsafe { allocate_from_pool(ctx.device(), &requirements, PoolType::DeviceLocal) }.unwrap())
+            .collect();
+
+        // Each request is rounded up to a whole 64 KiB buddy block
+        let stats = get_pool_stats(ctx.device(), PoolType::DeviceLocal).unwrap();
+        assert_eq!(stats.total_slabs, 3);
+        assert_eq!(stats.bytes_in_use, 3 * (64 << 10));
+        for id in ids {
+            unsafe { free_allocation(ctx.device(), id) }.unwrap();
+        }
+    }
+    // Slabs are released with the context
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+}
+
+#[test]
+fn test_memory_report_by_tag() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    let _activations: Vec<Buffer> = (0..2).map(|_| Buffer::new_tagged(&ctx, 4096, "activations").unwrap()).collect();
+    let weights = Buffer::new_tagged(&ctx, 1 << 20, "weights").unwrap();
+    assert_eq!(weights.tag(), "weights");
+
+    let report = ctx.memory_report();
+    assert_eq!(report.tags[0].0, "weights");
+    assert_eq!(report.tag("activations"), Some(TagUsage { bytes: 8192, count: 2 }));
+    assert_eq!(report.total(), TagUsage { bytes: (1 << 20) + 8192, count: 3 });
+    assert!(report.high_water_mark >= report.total().bytes);
+
+    drop(weights);
+    let report = ctx.memory_report();
+    assert_eq!(report.tag("weights"), None);
+    // The peak remembers the freed weights
+    assert!(report.high_water_mark >= (1 << 20) + 8192);
+}
+
+#[test]
+fn test_memory_limit_fails_fast() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::builder().memory_limit_bytes(16384).build().unwrap();
+    assert_eq!(ctx.memory_report().limit, Some(16384));
+
+    let activations = Buffer::new_tagged(&ctx, 8192, "activations").unwrap();
+    let _weights = Buffer::new_tagged(&ctx, 4096, "weights").unwrap();
+    let allocations = mock.call_count("vkAllocateMemory");
+    let err = match Buffer::new_tagged(&ctx, 8192, "activations") {
+        Err(KronosError::ImplementationError(IcdError::MemoryLimitExceeded(err))) => err,
+        other => panic!("expected the memory limit to be hit, got {:?}", other.err()),
+    };
+    assert_eq!((err.requested, err.limit, err.in_use), (8192, 16384, 12288));
+    assert_eq!(err.tags[0], ("activations".to_owned(), TagUsage { bytes: 8192, count: 1 }));
+    assert!(err.to_string().contains("weights 4096 bytes in 1 allocations"));
+    // Refused before reaching the driver, and not accounted
+    assert_eq!(mock.call_count("vkAllocateMemory"), allocations);
+    assert_eq!(ctx.memory_report().total().bytes, 12288);
+
+    // Freeing memory makes room again
+    drop(activations);
+    Buffer::new_tagged(&ctx, 8192, "activations").unwrap();
+}
+
+#[test]
+fn test_queue_family_global_priorities() {
+    use VkQueueGlobalPriorityKHR::{High, Low, Medium};
+    let (_guard, mock) = install(MockConfig::default().global_priorities(0, &[Low, Medium, High]));
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+
+    assert!(mock.call_count("vkGetPhysicalDeviceQueueFamilyProperties2") > 0);
+    let families = ctx.queue_families();
+    assert_eq!(families[0].max_global_priority, Some(High));
+    assert_eq!(families[0].queue_count, 1);
+
+    // Without the extension nothing is chained and no priority is reported
+    drop(ctx);
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+    assert_eq!(ctx.queue_families()[0].max_global_priority, None);
+}
+
+#[test]
+fn test_queue_family_selection() {
+    let family = |flags, count| VkQueueFamilyProperties {
+        queueFlags: flags,
+        queueCount: count,
+        timestampValidBits: 0,
+        minImageTransferGranularity: VkExtent3D::default(),
+    };
+    let (_guard, _mock) = install(MockConfig {
+        queue_families: vec![
+            family(VkQueueFlags::GRAPHICS | VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER, 1),
+            family(VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER, 2),
+            family(VkQueueFlags::TRANSFER, 1),
+            family(VkQueueFlags::COMPUTE, 0),
+        ],
+        ..MockConfig::default()
+    });
+    refresh_devices();
+
+    assert_eq!(ComputeContext::new().unwrap().queue_family_index(), 0);
+    let dedicated = ComputeContext::builder().prefer_dedicated_compute_family(true).build().unwrap();
+    assert_eq!(dedicated.queue_family_index(), 1);
+    assert_eq!(dedicated.queue_families()[0].flags, VkQueueFlags::GRAPHICS | VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER);
+    drop(dedicated);
+    assert_eq!(ComputeContext::builder().queue_family(1).build().unwrap().queue_family_index(), 1);
+
+    for (index, reason) in [(2, "has no compute queue"), (3, "has no compute queue"), (4, "does not exist (device has 4)")] {
+        match ComputeContext::builder().queue_family(index).build() {
+            Err(KronosError::UnsupportedHardware(message)) => assert!(message.contains(reason), "{}", message),
+            other => panic!("queue family {} accepted: {:?}", index, other.map(|ctx| ctx.queue_family_index())),
+        }
+    }
+}
+
+#[test]
+fn test_protected_queue_families_are_skipped() {
+    let family = |flags| VkQueueFamilyProperties {
+        queueFlags: flags,
+        queueCount: 1,
+        timestampValidBits: 0,
+        minImageTransferGranularity: VkExtent3D::default(),
+    };
+    let protected = VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER | VkQueueFlags::PROTECTED;
+    let (_guard, mock) = install(MockConfig {
+        queue_families: vec![family(protected), family(VkQueueFlags::COMPUTE)],
+        ..MockConfig::default()
+    });
+    refresh_devices();
+
+    // The mock is a Vulkan 1.3 device, so queues come from vkGetDeviceQueue2
+    let ctx = ComputeContext::new().unwrap();
+    assert_eq!(ctx.queue_family_index(), 1);
+    assert!(ctx.create_queue(0, 0).is_ok());
+    assert_eq!(mock.call_count("vkGetDeviceQueue2"), 2);
+    assert_eq!(mock.call_count("vkGetDeviceQueue"), 0);
+    drop(ctx);
+    // Still used when asked for, or when nothing else computes
+    assert_eq!(ComputeContext::builder().queue_family(0).build().unwrap().queue_family_index(), 0);
+    let _mock = MockIcd::install(MockConfig { queue_families: vec![family(protected)], ..MockConfig::default() }).unwrap();
+    refresh_devices();
+    assert_eq!(ComputeContext::new().unwrap().queue_family_index(), 0);
+}
+
+#[test]
+fn test_queue_command_pool_outlives_its_submissions() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let buffers: Vec<_> = (0..3).map(|_| ctx.create_buffer(&[1.0f32; 64]).unwrap()).collect();
+    let dispatch = || {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(0, &buffers[0])
+            .bind_buffer(1, &buffers[1])
+            .bind_buffer(2, &buffers[2])
+            .push_constants(&2.0f32)
+    };
+    let pools = mock.call_count("vkDestroyCommandPool");
+
+    // The queue goes away while a submission on it is in flight
+    let queue = ctx.create_queue(ctx.queue_family_index(), 0).unwrap();
+    mock.set_fence_delay(Duration::from_millis(50));
+    let (done, completed) = mpsc::channel();
+    dispatch().on_queue(&queue).on_complete(move || done.send(()).unwrap()).submit().unwrap();
+    drop(queue);
+    assert_eq!(mock.call_count("vkDestroyCommandPool"), pools);
+    completed.recv_timeout(Duration::from_secs(5)).unwrap();
+    mock.set_fence_delay(Duration::ZERO);
+
+    // The next submission frees the command buffer, then the pool goes
+    let freed = mock.call_count("vkFreeCommandBuffers");
+    dispatch().execute().unwrap();
+    assert_eq!(mock.call_count("vkFreeCommandBuffers"), freed + 2);
+    assert_eq!(mock.call_count("vkDestroyCommandPool"), pools + 1);
+
+    // A recorded command buffer keeps its queue's pool as well
+    let queue = ctx.create_queue(ctx.queue_family_index(), 0).unwrap();
+    let command_buffer = dispatch().on_queue(&queue).into_command_buffer().unwrap();
+    drop(queue);
+    assert_eq!(mock.call_count("vkDestroyCommandPool"), pools + 1);
+    drop(command_buffer);
+    assert_eq!(mock.call_count("vkDestroyCommandPool"), pools + 2);
+}
+
+#[test]
+fn test_identical_spirv_shares_a_shader_module() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let spirv = include_bytes!("../shaders/saxpy.spv");
+
+    let first = ctx.create_shader_from_spirv(spirv).unwrap();
+    let second = ctx.create_shader_from_spirv(spirv).unwrap();
+    assert_eq!(mock.call_count("vkCreateShaderModule"), 1);
+    assert_eq!(first.interface("main").unwrap(), second.interface("main").unwrap());
+
+    // The module outlives all but the last shader using it
+    drop(first);
+    assert_eq!(mock.call_count("vkDestroyShaderModule"), 0);
+    ctx.create_pipeline(&second).unwrap();
+    drop(second);
+    assert_eq!(mock.call_count("vkDestroyShaderModule"), 1);
+
+    ctx.create_shader_from_spirv(spirv).unwrap();
+    assert_eq!(mock.call_count("vkCreateShaderModule"), 2);
+}
+
+#[test]
+fn test_identical_layouts_are_shared() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let config = |push_constant_size| PipelineConfig {
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        push_constant_size,
+        ..Default::default()
+    };
+
+    let first = ctx.create_pipeline_with_config(&shader, config(4)).unwrap();
+    let second = ctx.create_pipeline_with_config(&shader, config(4)).unwrap();
+    let third = ctx.create_pipeline_with_config(&shader, config(8)).unwrap();
+    assert_eq!(mock.call_count("vkCreateDescriptorSetLayout"), 1);
+    assert_eq!(mock.call_count("vkCreatePipelineLayout"), 2);
+    assert_eq!(first.layout(), second.layout());
+    assert_ne!(first.layout(), third.layout());
+    assert_eq!(first.descriptor_set_layout(), third.descriptor_set_layout());
+
+    drop((first, second));
+    assert_eq!(mock.call_count("vkDestroyPipelineLayout"), 1);
+    assert_eq!(mock.call_count("vkDestroyDescriptorSetLayout"), 0);
+    drop(third);
+    assert_eq!(mock.call_count("vkDestroyPipelineLayout"), 2);
+    assert_eq!(mock.call_count("vkDestroyDescriptorSetLayout"), 1);
+}
+
+#[test]
+fn test_strict_conformance_reports_kronos_leniency() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::builder().strict_conformance(true).build().unwrap();
+    assert!(ctx.is_strict());
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx
+        .create_pipeline_with_config(&shader, PipelineConfig {
+            bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+            push_constant_size: 4,
+            ..Default::default()
+        })
+        .unwrap();
+    let buffers: Vec<_> = (0..3).map(|_| ctx.create_buffer_uninit(256).unwrap()).collect();
+    let dispatch = || {
+        buffers.iter().enumerate().fold(ctx.dispatch(&pipeline), |builder, (binding, buffer)| builder.bind_buffer(binding as u32, buffer))
+    };
+
+    // Conformant work is only made conservative
+    ctx.dry_run(true);
+    dispatch().push_constants(&1.0f32).execute().unwrap();
+    ctx.dry_run(false);
+    let commands = &ctx.take_command_listing().dispatches[0].commands;
+    assert!(matches!(&commands[0], PlannedCommand::PipelineBarrier { buffers, .. } if buffers.is_empty()));
+    assert!(commands.iter().any(|command| matches!(command, PlannedCommand::BindDescriptorSet { persistent: false, .. })));
+    buffers[0].read::<u32>().unwrap();
+    assert_eq!(ctx.take_conformance_issues(), Vec::new());
+
+    // create_buffer buffers cannot be copied from under stock Vulkan
+    ctx.create_buffer(&[1u32; 4]).unwrap().read::<u32>().unwrap();
+    let issues = ctx.take_conformance_issues();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].to_string(), "copy: source buffer lacks TRANSFER_SRC usage");
+
+    dispatch().bind_buffer(5, &buffers[0]).push_constants(&[0u32; 4]).workgroups(70000, 1, 1).execute().unwrap();
+    let messages: Vec<String> = ctx.take_conformance_issues().into_iter().map(|issue| issue.message).collect();
+    assert_eq!(messages.len(), 3, "{:?}", messages);
+    assert!(messages[0].contains("16 bytes of constants"));
+    assert!(messages[1].contains("70000 workgroups along x"));
+    assert!(messages[2].contains("binding 5"));
+
+    // Outside strict mode nothing is checked
+    let lenient = ComputeContext::new().unwrap();
+    assert!(!lenient.is_strict());
+    lenient.create_buffer(&[1u32; 4]).unwrap().read::<u32>().unwrap();
+    assert!(lenient.take_conformance_issues().is_empty());
+}
+
+#[test]
+fn test_raw_handles_mix_with_the_safe_api() {
+    use kronos_compute::api::{Fence, Pipeline};
+
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let data: Vec<f32> = (0..64).map(|i| i as f32).collect();
+    let size = std::mem::size_of_val(data.as_slice());
+
+    // Objects created by hand, then handed over
+    let (fence, mut raw_buffer) = unsafe {
+        let mut fence = VkFence::NULL;
+        assert_eq!(vkCreateFence(ctx.device(), &VkFenceCreateInfo::default(), ptr::null(), &mut fence), VkResult::Success);
+        let info = VkBufferCreateInfo {
+            size: size as VkDeviceSize,
+            usage: VkBufferUsageFlags::STORAGE_BUFFER,
+            ..Default::default()
+        };
+        let mut buffer = VkBuffer::NULL;
+        assert_eq!(vkCreateBuffer(ctx.device(), &info, ptr::null(), &mut buffer), VkResult::Success);
+        let alloc_info = VkMemoryAllocateInfo {
+            allocationSize: size as VkDeviceSize,
+            memoryTypeIndex: 1,
+            ..Default::default()
+        };
+        let mut memory = VkDeviceMemory::NULL;
+        assert_eq!(vkAllocateMemory(ctx.device(), &alloc_info, ptr::null(), &mut memory), VkResult::Success);
+        assert_eq!(vkBindBufferMemory(ctx.device(), buffer, memory, 0), VkResult::Success);
+        let flags = VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT;
+        (
+            Fence::from_raw_parts(&ctx, fence),
+            Buffer::from_raw_parts(&ctx, buffer, memory, size, BufferUsage::STORAGE, flags),
+        )
+    };
+    raw_buffer.write(&data).unwrap();
+    assert_eq!(raw_buffer.read::<f32>().unwrap(), data);
+
+    // A pipeline's handles, released by the safe API and taken back
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let config = || PipelineConfig {
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        push_constant_size: 4,
+        ..Default::default()
+    };
+    let created = ctx.create_pipeline_with_config(&shader, config()).unwrap();
+    let (handle, layout, set_layout) = (created.raw(), created.layout(), created.descriptor_set_layout());
+    std::mem::forget(created);
+    let pipeline = unsafe { Pipeline::from_raw_parts(&ctx, handle, layout, set_layout, &config()) }.unwrap();
+    assert_eq!(pipeline.raw(), handle);
+
+    // Kronos records the dispatch; the submission is made by hand
+    let y = ctx.create_buffer_uninit(size).unwrap();
+    let out = ctx.create_buffer_uninit(size).unwrap();
+    let command_buffer = ctx
+        .dispatch(&pipeline)
+        .bind_buffer(0, &raw_buffer)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .push_constants(&2.0f32)
+        .into_command_buffer()
+        .unwrap();
+    unsafe {
+        let submit = VkSubmitInfo {
+            commandBufferCount: 1,
+            pCommandBuffers: &command_buffer.raw(),
+            ..Default::default()
+        };
+        assert_eq!(vkQueueSubmit(ctx.queue(), 1, &submit, fence.raw()), VkResult::Success);
+    }
+    fence.wait_forever().unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatch"), 1);
+    let freed = mock.call_count("vkFreeCommandBuffers");
+    drop(command_buffer);
+    assert_eq!(mock.call_count("vkFreeCommandBuffers"), freed + 1);
+
+    // Dispatches with work on completion need Kronos to see the submission
+    let refused = ctx.dispatch(&pipeline).on_complete(|| ()).into_command_buffer();
+    assert!(matches!(refused, Err(KronosError::CommandExecutionFailed(_))));
+
+    drop((fence, raw_buffer, pipeline));
+    assert_eq!(mock.call_count("vkDestroyFence"), 1);
+    assert_eq!(mock.call_count("vkDestroyPipeline"), 1);
+    assert_eq!(mock.call_count("vkDestroyPipelineLayout"), 1);
+}
+
+/// A kernel declaring `shared float tile[TILE]`, with TILE specialization
+/// constant 0 defaulting to 64, and `shared uint counts[16]`
+fn shared_memory_kernel() -> Vec<u8> {
+    let instruction = |opcode: u32, operands: &[u32]| {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    };
+    let main = u32::from_le_bytes(*b"main");
+    let words: Vec<u32> = [
+        vec![0x0723_0203, 0x0001_0000, 0, 12, 0],
+        instruction(15, &[5, 1, main, 0]),  // OpEntryPoint GLCompute %1 "main"
+        instruction(71, &[3, 1, 0]),        // OpDecorate %3 SpecId 0
+        instruction(22, &[2, 32]),          // %2 = OpTypeFloat 32
+        instruction(21, &[4, 32, 0]),       // %4 = OpTypeInt 32 0
+        instruction(50, &[4, 3, 64]),       // %3 = OpSpecConstant %4 64
+        instruction(28, &[5, 2, 3]),        // %5 = OpTypeArray %2 %3
+        instruction(32, &[6, 4, 5]),        // %6 = OpTypePointer Workgroup %5
+        instruction(59, &[6, 10, 4]),       // %10 = OpVariable %6 Workgroup
+        instruction(43, &[4, 7, 16]),       // %7 = OpConstant %4 16
+        instruction(28, &[8, 4, 7]),        // %8 = OpTypeArray %4 %7
+        instruction(32, &[9, 4, 8]),        // %9 = OpTypePointer Workgroup %8
+        instruction(59, &[9, 11, 4]),       // %11 = OpVariable %9 Workgroup
+    ]
+    .concat();
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_shared_memory_is_sized_and_checked() {
+    use kronos_compute::api::SharedMemorySize;
+
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    // The mock leaves the limit unset, so the Vulkan minimum applies
+    assert_eq!(ctx.max_shared_memory_size(), 16384);
+    let shader = ctx.create_shader_from_spirv(&shared_memory_kernel()).unwrap();
+    let interface = shader.interface("main").unwrap();
+    assert_eq!(interface.shared_memory.len(), 2);
+    assert_eq!(interface.shared_memory_size(&[]), 64 * 4 + 16 * 4);
+    assert_eq!(interface.shared_memory_size(&[(0, 1024)]), 1024 * 4 + 16 * 4);
+
+    let default = ctx.create_pipeline(&shader).unwrap();
+    assert_eq!(default.shared_memory_size(), 320);
+
+    // The array gets what the device has left after `counts`
+    let sized = |bytes| PipelineConfig {
+        shared_memory: Some(SharedMemorySize { constant_id: 0, bytes }),
+        specialization: vec![(0, 8)],
+        ..Default::default()
+    };
+    let largest = ctx.create_pipeline_with_config(&shader, sized(None)).unwrap();
+    assert_eq!(largest.shared_memory_size(), 16384);
+    let half = ctx.create_pipeline_with_config(&shader, sized(Some(8192))).unwrap();
+    assert_eq!(half.shared_memory_size(), 8192 + 64);
+
+    // Too much is refused at creation rather than left to the driver
+    assert!(matches!(
+        ctx.create_pipeline_with_config(&shader, sized(Some(16384))),
+        Err(KronosError::ShaderCompilationFailed(_))
+    ));
+    let family = ctx.create_pipeline_family(&shader, PipelineConfig::default(), &[&[(0, 64)], &[(0, 8192)]]);
+    assert!(matches!(family, Err(KronosError::ShaderCompilationFailed(_))));
+    let unsized_constant = PipelineConfig {
+        shared_memory: Some(SharedMemorySize { constant_id: 1, bytes: None }),
+        ..Default::default()
+    };
+    assert!(ctx.create_pipeline_with_config(&shader, unsized_constant).is_err());
+}
+
+#[test]
+fn test_pipeline_creation_feedback() {
+    let (_guard, _mock) = install(MockConfig::default().pipeline_creation_feedback(Duration::from_millis(3)));
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+
+    let family = ctx.create_pipeline_family(&shader, PipelineConfig::default(), &[&[], &[(0, 1)]]).unwrap();
+    let base = family[0].creation_feedback().unwrap();
+    assert_eq!(base.duration, Duration::from_millis(3));
+    assert_eq!(base.stage_duration, Some(Duration::from_micros(1500)));
+    assert!(!base.cache_hit && !base.base_pipeline_acceleration);
+    assert!(family[1].creation_feedback().unwrap().base_pipeline_acceleration);
+
+    // Without the extension nothing is chained or reported
+    drop((family, shader, ctx));
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    assert_eq!(ctx.create_pipeline(&shader).unwrap().creation_feedback(), None);
+}
+
+#[test]
+fn test_driver_properties_identify_the_driver() {
+    let (_guard, mock) = install(MockConfig::default().driver(VkDriverId::MESA_RADV, "radv", "Mesa 24.0.5"));
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+
+    assert!(mock.call_count("vkGetPhysicalDeviceProperties2") > 0);
+    let driver = ctx.driver_info().unwrap();
+    assert_eq!(driver.id, VkDriverId::MESA_RADV);
+    assert_eq!((driver.name.as_str(), driver.info.as_str()), ("radv", "Mesa 24.0.5"));
+    assert_eq!(driver.to_string(), "RADV (radv Mesa 24.0.5), conformance 1.3.0.0");
+    assert_eq!(ctx.quirks().driver, Some(VkDriverId::MESA_RADV));
+
+    // A driver that does not fill the structure reports nothing
+    drop(ctx);
+    let _mock = MockIcd::install(MockConfig::default()).expect("install mock ICD");
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+    assert_eq!(ctx.driver_info(), None);
+    assert_eq!(ctx.quirks().driver, None);
+}
+
+#[test]
+fn test_device_uuid_identifies_the_device() {
+    let config = MockConfig::default();
+    let uuid = config.device_uuid;
+    let (_guard, _mock) = install(config);
+    refresh_devices();
+    assert_eq!(ComputeContext::new().unwrap().device_uuid(), Some(uuid));
+
+    // Vulkan 1.0 devices have no UUID to report
+    let mut config = MockConfig::default();
+    config.properties.apiVersion = VK_API_VERSION_1_0;
+    let _mock = MockIcd::install(config).expect("install mock ICD");
+    refresh_devices();
+    assert_eq!(ComputeContext::new().unwrap().device_uuid(), None);
+}
+
+#[test]
+fn test_pci_bus_info_locates_the_device() {
+    let (_guard, _mock) = install(MockConfig::default());
+    refresh_devices();
+    assert_eq!(ComputeContext::new().unwrap().pci_bus_address(), None);
+
+    let _mock = MockIcd::install(MockConfig::default().pci_bus(0, 3, 0, 0)).expect("install mock ICD");
+    refresh_devices();
+    let address = ComputeContext::new().unwrap().pci_bus_address().unwrap();
+    assert_eq!(address, PciBusAddress { domain: 0, bus: 3, device: 0, function: 0 });
+    assert_eq!(address.to_string(), "0000:03:00.0");
+}
+
+#[test]
+fn test_external_loader_skips_discovery() {
+    let (_guard, mock) = install(MockConfig::default());
+    initialize_kronos_with(InitOptions::with_loader(mock.entry_point())).unwrap();
+    assert_eq!(selected_icd_info().unwrap().library_path.to_str(), Some(EXTERNAL_LOADER_NAME));
+
+    let instances = mock.call_count("vkCreateInstance");
+    let ctx = ComputeContext::new().unwrap();
+    let buffer = ctx.create_buffer(&[1.0f32; 16]).unwrap();
+    assert_eq!(buffer.read::<f32>().unwrap(), vec![1.0; 16]);
+    assert_eq!(mock.call_count("vkCreateInstance"), instances + 1);
+}
+
+#[test]
+fn test_bug_report_names_icd_device_and_driver() {
+    let (_guard, _mock) = install(MockConfig::default().driver(VkDriverId::MESA_RADV, "radv", "Mesa 24.0.5"));
+    refresh_devices();
+    let _ctx = ComputeContext::new().unwrap();
+
+    let report = kronos_compute::report::bug_report_string();
+    assert!(report.contains("<mock-icd>"), "{}", report);
+    assert!(report.contains("RADV (radv Mesa 24.0.5)"), "{}", report);
+    assert!(report.contains("Recent log messages"), "{}", report);
+}
+
+#[test]
+#[cfg(not(feature = "minimal"))]
+fn test_bug_report_keeps_context_creation_failures() {
+    let (_guard, mock) = install(MockConfig::default());
+    mock.fail_nth("vkCreateDevice", 1, VkResult::ErrorInitializationFailed);
+    assert!(ComputeContext::new().is_err());
+
+    let report = kronos_compute::report::bug_report_string();
+    assert!(report.contains("[ERROR] [SAFE API] Failed to create device: ErrorInitializationFailed"), "{}", report);
+}
+
+#[test]
+fn test_chained_dispatches_skip_redundant_binds() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let binds = mock.call_count("vkCmdBindPipeline");
+    let sets = mock.call_count("vkCmdBindDescriptorSets");
+    let pushes = mock.call_count("vkCmdPushConstants");
+
+    // Out-of-order bindings allocate from the context's descriptor pool
+    let mut builder = ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x);
+    for _ in 0..3 {
+        builder = builder.push_constants(&2.0f32).workgroups(1, 1, 1).then(&pipeline);
+    }
+    builder.push_constants(&3.0f32).execute().unwrap();
+
+    assert_eq!(mock.call_count("vkCmdBindPipeline") - binds, 1);
+    assert_eq!(mock.call_count("vkCmdBindDescriptorSets") - sets, 1);
+    // Only the changed value is pushed again
+    assert_eq!(mock.call_count("vkCmdPushConstants") - pushes, 2);
+}
+
+#[test]
+fn test_dispatch_base_offsets_workgroups() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    assert!(ctx.supports_dispatch_base());
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let plain = ctx.create_pipeline(&shader).unwrap();
+    let config = || PipelineConfig { dispatch_base: true, allow_derivatives: true, ..Default::default() };
+    let tiled = ctx.create_pipeline_with_config(&shader, config()).unwrap();
+    assert!(tiled.allows_dispatch_base() && !plain.allows_dispatch_base());
+    assert!(tiled.derive(&shader, &[]).unwrap().allows_dispatch_base());
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+
+    // A zero base is a plain dispatch, whatever the pipeline
+    ctx.dispatch(&plain).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).base(0, 0, 0).execute().unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatchBase"), 0);
+    let result = ctx.dispatch(&plain).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).base(4, 0, 0).execute();
+    assert!(matches!(result, Err(KronosError::CommandExecutionFailed(_))));
+
+    ctx.dry_run(true);
+    ctx.dispatch(&tiled)
+        .bind_buffer(0, &x)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .workgroups(4, 1, 1)
+        .base(4, 0, 0)
+        .then(&tiled)
+        .workgroups(4, 1, 1)
+        .execute()
+        .unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let dispatches: Vec<&PlannedCommand> = listing.dispatches[0]
+        .commands
+        .iter()
+        .filter(|command| matches!(command, PlannedCommand::Dispatch { .. } | PlannedCommand::DispatchBase { .. }))
+        .collect();
+    // The chained dispatch starts again at zero
+    assert_eq!(
+        dispatches,
+        [
+            &PlannedCommand::DispatchBase { base: (4, 0, 0), x: 4, y: 1, z: 1 },
+            &PlannedCommand::Dispatch { x: 4, y: 1, z: 1 },
+        ]
+    );
+
+    let based = mock.call_count("vkCmdDispatchBase");
+    ctx.dispatch(&tiled).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).base(0, 8, 0).execute().unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatchBase") - based, 1);
+}
+
+#[test]
+fn test_batched_ops_solve_every_problem_in_one_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let a = ctx.create_buffer(&[1.0f32; 16 * 1000]).unwrap();
+    let v = ctx.create_buffer(&[1.0f32; 4 * 1000]).unwrap();
+    let gemv: Vec<GemvBatch> = (0..1000)
+        .map(|i| GemvBatch { a_offset: i * 16, x_offset: i * 4, y_offset: i * 4, rows: 4, cols: 4, alpha: 1.0, beta: 0.0 })
+        .collect();
+    let axpy: Vec<AxpyBatch> = (0..1000).map(|i| AxpyBatch { x_offset: i * 4, y_offset: 0, len: 4, alpha: 2.0 }).collect();
+
+    let dispatches = mock.call_count("vkCmdDispatch");
+    ops::gemv_batched(&ctx, &gemv, &a, &v, &v).unwrap();
+    ops::axpy_batched(&ctx, &axpy[..1], &v, &v).unwrap();
+    ops::axpy_batched(&ctx, &[], &v, &v).unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatch") - dispatches, 2);
+
+    // The last problem's matrix runs one row past the end of `a`
+    let mut past_end = gemv.clone();
+    past_end[999].rows = 5;
+    match ops::gemv_batched(&ctx, &past_end, &a, &v, &v) {
+        Err(KronosError::CommandExecutionFailed(message)) => assert!(message.contains("gemv_batched problem 999"), "{}", message),
+        other => panic!("expected a range error, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(mock.call_count("vkCmdDispatch") - dispatches, 2);
+}
+
+#[test]
+fn test_predicated_dispatches_run_on_a_device_flag() {
+    let mut config = MockConfig::default();
+    config.extensions.push(VK_EXT_CONDITIONAL_RENDERING_EXTENSION_NAME.to_string());
+    let (_guard, mock) = install(config);
+    let ctx = ComputeContext::new().unwrap();
+    assert!(ctx.supports_conditional_rendering());
+    assert!(mock.device_create_chain().contains(&VkStructureType::PhysicalDeviceConditionalRenderingFeaturesEXT));
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0u32; 64]).unwrap();
+    let flags = ctx.create_buffer(&[7u32, 0]).unwrap();
+    let predicated = |offset| ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &x).bind_buffer(2, &out).predicated_on(&flags, offset);
+
+    // The mock writes only if the dispatch ran
+    mock.write_on_next_dispatch(out.raw(), 0, &5u32.to_le_bytes());
+    predicated(4).execute().unwrap();
+    assert_eq!(out.read::<u32>().unwrap()[0], 0);
+    predicated(0).then(&pipeline).execute().unwrap();
+    assert_eq!(out.read::<u32>().unwrap()[0], 5);
+    assert_eq!(mock.call_count("vkCmdBeginConditionalRenderingEXT"), 2);
+    assert_eq!(mock.call_count("vkCmdEndConditionalRenderingEXT"), 2);
+    assert_eq!(mock.call_count("vkCmdDispatchIndirect"), 0);
+
+    for offset in [2, 8] {
+        assert!(matches!(predicated(offset).execute(), Err(KronosError::CommandExecutionFailed(_))));
+    }
+}
+
+#[test]
+fn test_predicated_dispatches_are_emulated_without_conditional_rendering() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    assert!(!ctx.supports_conditional_rendering());
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline_with_config(&shader, PipelineConfig { dispatch_base: true, ..Default::default() }).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let flag = ctx.create_buffer(&[1u32]).unwrap();
+    let predicated = || ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &x).bind_buffer(2, &out).predicated_on(&flag, 0);
+
+    // One argument-writing dispatch per predicated one, which then reads them
+    ctx.dry_run(true);
+    predicated().workgroups(4, 2, 1).then(&pipeline).workgroups(8, 1, 1).execute().unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let dispatches: Vec<&PlannedCommand> = listing.dispatches[0]
+        .commands
+        .iter()
+        .filter(|command| matches!(command, PlannedCommand::Dispatch { .. } | PlannedCommand::DispatchIndirect { .. }))
+        .collect();
+    let args = match dispatches[2] {
+        PlannedCommand::DispatchIndirect { buffer, .. } => *buffer,
+        other => panic!("expected an indirect dispatch, got {}", other),
+    };
+    assert_eq!(
+        dispatches,
+        [
+            &PlannedCommand::Dispatch { x: 1, y: 1, z: 1 },
+            &PlannedCommand::Dispatch { x: 1, y: 1, z: 1 },
+            &PlannedCommand::DispatchIndirect { buffer: args, offset: 0 },
+            &PlannedCommand::DispatchIndirect { buffer: args, offset: 12 },
+        ]
+    );
+
+    let indirect = mock.call_count("vkCmdDispatchIndirect");
+    predicated().workgroups(4, 1, 1).execute().unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatchIndirect") - indirect, 1);
+    assert_eq!(mock.call_count("vkCmdBeginConditionalRenderingEXT"), 0);
+    let result = predicated().base(4, 0, 0).execute();
+    assert!(matches!(result, Err(KronosError::CommandExecutionFailed(_))));
+}
+
+#[test]
+fn test_progress_follows_timeline_signals() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    assert!(ctx.supports_timeline_semaphores());
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &x).bind_buffer(2, &x);
+
+    let progress = ctx.create_progress(4).unwrap();
+    let (sink, reports) = mpsc::channel();
+    progress.on_progress(move |done, total| sink.send((done, total)).unwrap());
+    dispatch().progress(&progress).unwrap().execute().unwrap();
+    assert_eq!(progress.completed().unwrap(), 1);
+    assert_eq!(progress.percent().unwrap(), 25.0);
+    assert_eq!(reports.try_recv(), Ok((1, 4)));
+
+    // Dry runs submit nothing and leave the timeline alone
+    ctx.dry_run(true);
+    dispatch().progress(&progress).unwrap().execute().unwrap();
+    ctx.dry_run(false);
+    ctx.take_command_listing();
+    assert_eq!(progress.submitted(), 1);
+
+    // Chained dispatches complete, and count, together
+    mock.set_fence_delay(Duration::from_millis(50));
+    dispatch().then(&pipeline).then(&pipeline).progress(&progress).unwrap().submit().unwrap();
+    assert_eq!(progress.submitted(), 4);
+    assert_eq!(progress.completed().unwrap(), 1);
+    assert_eq!(reports.recv_timeout(Duration::from_secs(5)), Ok((4, 4)));
+    assert!(progress.is_complete().unwrap());
+    assert_eq!(mock.call_count("vkGetSemaphoreCounterValue"), 6);
+
+    let result = dispatch().progress(&progress).unwrap().into_command_buffer();
+    assert!(matches!(result, Err(KronosError::CommandExecutionFailed(_))));
+}
+
+#[test]
+fn test_timeline_semaphores_come_from_the_khr_extension_on_vulkan_1_1() {
+    let mut config = MockConfig::default();
+    config.properties.apiVersion = VK_API_VERSION_1_1;
+    let (guard, mock) = install(config.clone());
+    let ctx = ComputeContext::new().unwrap();
+    assert!(!ctx.supports_timeline_semaphores());
+    drop((ctx, mock, guard));
+
+    config.extensions.push(VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME.to_string());
+    let (_guard, mock) = install(config);
+    let ctx = ComputeContext::new().unwrap();
+    assert!(ctx.supports_timeline_semaphores());
+    assert!(mock.device_extensions().iter().any(|name| name == VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME));
+    assert!(mock.device_create_chain().contains(&VkStructureType::PhysicalDeviceTimelineSemaphoreFeatures));
+
+    // The counter is read through vkGetSemaphoreCounterValueKHR
+    let progress = ctx.create_progress(1).unwrap();
+    assert_eq!(progress.completed().unwrap(), 0);
+    assert!(mock.call_count("vkGetSemaphoreCounterValue") > 0);
+}
+
+#[test]
+fn test_command_stats_count_recorded_commands() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    assert_eq!(ctx.command_stats().command_buffers, 0);
+
+    let mut builder = ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out);
+    for _ in 0..3 {
+        builder = builder.push_constants(&2.0f32).workgroups(1, 1, 1).then(&pipeline);
+    }
+    builder.push_constants(&3.0f32).workgroups(16, 1, 1).execute().unwrap();
+
+    let report = ctx.command_stats();
+    assert_eq!(report.command_buffers, 1);
+    let stats = &report.recent[0];
+    assert_eq!(&*stats.label, pipeline.label());
+    assert_eq!(stats.dispatches, 4);
+    assert_eq!(stats.workgroups, 19);
+    assert_eq!(stats.tiny_dispatches, 3);
+    // Redundant pushes are skipped
+    assert_eq!(stats.push_constant_bytes, 8);
+    // One barrier between each pair of chained dispatches at least
+    assert!(stats.barriers >= 3);
+    // Plus one pipeline and one descriptor set bind
+    assert_eq!(stats.commands, stats.barriers + 2 + 2 + 4);
+    assert!(report.to_string().contains("3 dispatches had fewer than 8 workgroups"));
+
+    // Dry runs are not counted
+    ctx.dry_run(true);
+    ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).execute().unwrap();
+    ctx.dry_run(false);
+    assert_eq!(ctx.command_stats().command_buffers, 1);
+
+    ctx.reset_command_stats();
+    assert_eq!(ctx.command_stats(), Default::default());
+}
+
+#[test]
+fn test_stats_snapshots_diff_driver_work() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+
+    let start = ctx.stats_snapshot();
+    let x = ctx.create_buffer_uninit(256).unwrap();
+    let y = ctx.create_buffer_uninit(256).unwrap();
+    let out = ctx.create_buffer_uninit(256).unwrap();
+    let created = ctx.stats_snapshot().diff(&start);
+    assert_eq!(created.allocations, 3);
+    assert!(created.allocated_bytes >= 768);
+    assert_eq!(created.submissions, 0);
+
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).workgroups(1, 1, 1);
+    let before = ctx.stats_snapshot();
+    dispatch().execute().unwrap();
+    let first = ctx.stats_snapshot().diff(&before);
+    assert_eq!((first.allocations, first.submissions, first.dispatches), (0, 1, 1));
+    assert_eq!(first.descriptor_updates, 3);
+    assert_eq!(first.gpu_time, Duration::ZERO);
+
+    // The persistent set is written once; totals survive a stats reset
+    ctx.reset_command_stats();
+    let before = ctx.stats_snapshot();
+    dispatch().then(&pipeline).workgroups(1, 1, 1).execute().unwrap();
+    let second = ctx.stats_snapshot().diff(&before);
+    assert_eq!((second.allocations, second.submissions, second.dispatches), (0, 1, 2));
+    assert_eq!(second.descriptor_updates, 0);
+    assert!(second.barriers >= 1);
+    assert_eq!(ctx.stats_snapshot().diff(&start).dispatches, 3);
+    assert_eq!(before.diff(&ctx.stats_snapshot()), Default::default());
+    assert!(second.to_string().contains("1 submissions, 2 dispatches"));
+}
+
+#[test]
+fn test_in_order_bindings_reuse_persistent_set() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(0, &x).bind_buffer(1, &y).bind_buffer(2, &out).workgroups(1, 1, 1);
+
+    dispatch().execute().unwrap();
+    let updates = mock.call_count("vkUpdateDescriptorSets");
+    for _ in 0..3 {
+        dispatch().execute().unwrap();
+    }
+    // The set written by the first dispatch is bound as is
+    assert_eq!(mock.call_count("vkUpdateDescriptorSets"), updates);
+}
+
+#[test]
+fn test_declared_external_write_adds_one_barrier() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let barriers_per_dispatch = || {
+        let before = mock.call_count("vkCmdPipelineBarrier");
+        ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x).execute().unwrap();
+        mock.call_count("vkCmdPipelineBarrier") - before
+    };
+
+    let baseline = barriers_per_dispatch();
+    x.declare_external_write(VkPipelineStageFlags::TRANSFER, VkAccessFlags::TRANSFER_WRITE);
+    y.declare_external_read(VkPipelineStageFlags::HOST);
+    assert_eq!(barriers_per_dispatch(), baseline + 1);
+    // Only the dispatch right after the declarations waits for them
+    assert_eq!(barriers_per_dispatch(), baseline);
+}
+
+#[test]
+fn test_virtual_devices_are_enumerated() {
+    let (_guard, _mock) = install(MockConfig::default().vendor_id(0x10DE).virtual_devices(3));
+    let mut instance = VkInstance::NULL;
+    let create_info = VkInstanceCreateInfo::default();
+    unsafe {
+        assert_eq!(vkCreateInstance(&create_info, ptr::null(), &mut instance), VkResult::Success);
+        let mut count = 0;
+        vkEnumeratePhysicalDevices(instance, &mut count, ptr::null_mut());
+        assert_eq!(count, 3);
+        let mut devices = vec![VkPhysicalDevice::NULL; 2];
+        count = 2;
+        assert_eq!(vkEnumeratePhysicalDevices(instance, &mut count, devices.as_mut_ptr()), VkResult::Incomplete);
+        devices.resize(3, VkPhysicalDevice::NULL);
+        count = 3;
+        assert_eq!(vkEnumeratePhysicalDevices(instance, &mut count, devices.as_mut_ptr()), VkResult::Success);
+
+        let properties: Vec<VkPhysicalDeviceProperties> = devices
+            .iter()
+            .map(|device| {
+                let mut properties = VkPhysicalDeviceProperties::default();
+                vkGetPhysicalDeviceProperties(*device, &mut properties);
+                properties
+            })
+            .collect();
+        let vendors: Vec<u32> = properties.iter().map(|properties| properties.vendorID).collect();
+        assert_eq!(vendors, vec![0x10DE, 0x1002, 0x8086]);
+        let shared: Vec<u32> = properties.iter().map(|properties| properties.limits.maxComputeSharedMemorySize).collect();
+        assert_eq!(shared, vec![32768, 65536, 98304]);
+        vkDestroyInstance(instance, ptr::null());
+    }
+
+    // The safe API picks among them by vendor
+    let ctx = ComputeContext::builder().prefer_vendor("AMD").build().unwrap();
+    assert_eq!(ctx.device_properties().vendorID, 0x1002);
+    assert_eq!(ctx.device_properties().deviceID, MockConfig::default().properties.deviceID + 1);
+}
+
+#[test]
+fn test_portability_driver_enables_enumeration() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    assert_eq!(mock.instance_create_info(), Some((0, Vec::new())));
+    drop(ctx);
+
+    let mut config = MockConfig { portability_driver: true, ..MockConfig::default() };
+    config.instance_extensions.push(VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME.to_string());
+    config.extensions.push(VK_KHR_PORTABILITY_SUBSET_EXTENSION_NAME.to_string());
+    let mock = MockIcd::install(config).unwrap();
+    let _ctx = ComputeContext::new().unwrap();
+    assert_eq!(
+        mock.instance_create_info(),
+        Some((
+            VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR,
+            vec![VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME.to_string()]
+        ))
+    );
+    assert!(mock.device_extensions().iter().any(|name| name == VK_KHR_PORTABILITY_SUBSET_EXTENSION_NAME));
+}
+
+#[test]
+#[cfg(not(feature = "minimal"))]
+fn test_validation_routes_debug_printf_into_log() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::builder().enable_validation().build().unwrap();
+    assert!(!ctx.debug_printf_enabled());
+    drop(ctx);
+
+    let mut config = MockConfig::default().validation_layer();
+    config.extensions.push(VK_KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION_NAME.to_string());
+    let mock = MockIcd::install(config).unwrap();
+    let ctx = ComputeContext::builder().enable_validation().build().unwrap();
+    assert!(ctx.debug_printf_enabled());
+    assert_eq!(mock.instance_layers(), vec![VK_LAYER_KHRONOS_VALIDATION_NAME.to_string()]);
+    assert_eq!(mock.validation_features(), vec![VkValidationFeatureEnableEXT::DebugPrintf]);
+    assert!(mock.device_extensions().iter().any(|name| name == VK_KHR_SHADER_NON_SEMANTIC_INFO_EXTENSION_NAME));
+
+    mock.emit_debug_message(VkDebugUtilsMessageSeverityFlagsEXT::INFO, "WARNING-DEBUG-PRINTF", "x = 3\n");
+    mock.emit_debug_message(VkDebugUtilsMessageSeverityFlagsEXT::ERROR, "VUID-vkCmdDispatch-None-02697", "layout mismatch");
+    let messages = logging::recent_messages();
+    assert!(messages.iter().any(|message| message == "[INFO] [SHADER] x = 3"));
+    assert!(messages.iter().any(|message| message == "[ERROR] [VALIDATION] layout mismatch"));
+
+    // The messenger goes with the context
+    drop(ctx);
+    mock.emit_debug_message(VkDebugUtilsMessageSeverityFlagsEXT::INFO, "WARNING-DEBUG-PRINTF", "after drop");
+    assert!(!logging::recent_messages().iter().any(|message| message.contains("after drop")));
+}
+
+#[test]
+#[cfg(not(feature = "minimal"))]
+fn test_destroy_device_policy_covers_live_children() {
+    let (_guard, mock) = install(MockConfig::default());
+    let create_fence = |device| {
+        let mut fence = VkFence::NULL;
+        assert_eq!(unsafe { vkCreateFence(device, &VkFenceCreateInfo::default(), ptr::null(), &mut fence) }, VkResult::Success);
+        fence
+    };
+
+    // Safe-API objects are all gone by the time the context destroys its device
+    set_destroy_policy(DestroyPolicy::Fail);
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let _pipeline = ctx.create_pipeline(&shader).unwrap();
+    let _buffer = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let device = ctx.device();
+    assert!(live_children(device).iter().any(|(name, _)| *name == "VkPipeline"));
+    drop((ctx, shader, _pipeline, _buffer));
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+
+    // Fail leaves the device alive
+    let ctx = ComputeContext::new().unwrap();
+    let device = ctx.device();
+    let fence = create_fence(device);
+    assert!(live_children(device).contains(&("VkFence", fence.as_raw())));
+    drop(ctx);
+    assert_eq!(mock.live_objects().get("VkDevice"), Some(&1));
+    assert!(logging::recent_messages().iter().any(|message| message.starts_with("[ERROR] vkDestroyDevice") && message.contains("VkFence(")));
+    unsafe {
+        vkDestroyFence(device, fence, ptr::null());
+        assert!(live_children(device).is_empty());
+        vkDestroyDevice(device, ptr::null());
+    }
+    assert_eq!(mock.live_objects().get("VkDevice"), None);
+
+    // Defer destroys it with its last child
+    set_destroy_policy(DestroyPolicy::Defer);
+    let ctx = ComputeContext::new().unwrap();
+    let device = ctx.device();
+    let fence = create_fence(device);
+    drop(ctx);
+    assert_eq!(mock.live_objects().get("VkDevice"), Some(&1));
+    unsafe { vkDestroyFence(device, fence, ptr::null()) };
+    assert!(mock.live_objects().is_empty(), "leaked: {:?}", mock.live_objects());
+
+    // Warn destroys it at once, naming the children
+    set_destroy_policy(DestroyPolicy::Warn);
+    let ctx = ComputeContext::new().unwrap();
+    let fence = create_fence(ctx.device());
+    drop(ctx);
+    assert_eq!(mock.live_objects().get("VkDevice"), None);
+    let warning = format!("live children: VkFence({:#x})", fence.as_raw());
+    assert!(logging::recent_messages().iter().any(|message| message.starts_with("[WARN]") && message.ends_with(&warning)));
+}
+
+#[test]
+fn test_buffer_slices_bind_and_copy() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    // The mock reports no alignment limit, so the Vulkan maximum applies
+    assert_eq!(ctx.min_storage_buffer_offset_alignment(), 256);
+    let values: Vec<u32> = (0..256).collect();
+    let arena = ctx.create_buffer(&values).unwrap();
+
+    assert!(matches!(arena.slice(4, 256), Err(KronosError::BufferCreationFailed(_))));
+    assert!(matches!(arena.slice(768, 512), Err(KronosError::BufferCreationFailed(_))));
+    assert!(matches!(arena.slice(256, 0), Err(KronosError::BufferCreationFailed(_))));
+    let (x, y, out) = (arena.slice(0, 256).unwrap(), arena.slice(256, 256).unwrap(), arena.slice(512, 512).unwrap());
+    assert_eq!((out.offset(), out.len()), (512, 512));
+
+    ctx.dry_run(true);
+    ctx.dispatch(&pipeline).bind_slice(0, x).bind_slice(1, y).bind_slice(2, out).execute().unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let bound = listing.dispatches[0].commands.iter().find_map(|command| match command {
+        PlannedCommand::BindDescriptorSet { persistent, bindings, .. } => Some((*persistent, bindings.clone())),
+        _ => None,
+    });
+    let (persistent, bindings) = bound.unwrap();
+    assert!(!persistent);
+    assert_eq!(bindings[2].1, PlannedResource::Buffer { buffer: arena.raw(), size: 512 });
+
+    assert!(ctx.copy_slice(out, y).is_err());
+    assert!(ctx.copy_slice(arena.slice(0, 512).unwrap(), arena.slice(256, 512).unwrap()).is_err());
+    ctx.copy_slice(x, out).unwrap();
+    let copied = arena.read::<u32>().unwrap();
+    assert_eq!(&copied[128..192], &values[..64]);
+    assert_eq!(&copied[192..], &values[192..]);
+}
+
+#[test]
+fn test_scattered_regions_share_one_submission() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let mut buffer = ctx.create_buffer(&[0u8; 4096]).unwrap();
+    assert!(!buffer.is_host_visible());
+
+    let submits = mock.call_count("vkQueueSubmit");
+    let rows: Vec<Vec<u8>> = (1..=3u8).map(|row| vec![row; 40]).collect();
+    buffer.write_regions(&[(3000, &rows[2][..]), (100, &rows[0][..]), (140, &rows[1][..])]).unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits + 1);
+    assert!(matches!(
+        buffer.write_regions(&[(100, &rows[0][..]), (120, &rows[1][..])]),
+        Err(KronosError::BufferCreationFailed(_))
+    ));
+    assert!(buffer.write_regions(&[(4090, &rows[0][..])]).is_err());
+
+    let (mut first, mut last, mut around) = ([0u8; 80], [0u8; 40], [0u8; 4]);
+    buffer.read_regions(&mut [(100, &mut first[..]), (3000, &mut last[..]), (98, &mut around[..])]).unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits + 2);
+    assert_eq!(first[..40], rows[0][..]);
+    assert_eq!(first[40..], rows[1][..]);
+    assert_eq!(last[..], rows[2][..]);
+    assert_eq!(around, [0, 0, 1, 1]);
+    let whole = buffer.read::<u8>().unwrap();
+    assert!(whole[..100].iter().chain(&whole[180..3000]).chain(&whole[3040..]).all(|&byte| byte == 0));
+}
+
+#[test]
+fn test_write_combined_regions_go_through_a_shadow() {
+    let mut config = MockConfig::default();
+    config.memory_properties.memoryTypes[0].propertyFlags |= VkMemoryPropertyFlags::HOST_VISIBLE | VkMemoryPropertyFlags::HOST_COHERENT;
+    let (_guard, mock) = install(config);
+    let ctx = ComputeContext::new().unwrap();
+    let mut buffer = ctx.create_buffer_direct(4096).unwrap();
+    assert!(buffer.is_direct() && buffer.is_write_combined());
+
+    let submits = mock.call_count("vkQueueSubmit");
+    let rows: Vec<Vec<u8>> = (1..=3u8).map(|row| vec![row; 300]).collect();
+    buffer.write_regions(&[(3001, &rows[2][..]), (100, &rows[0][..]), (400, &rows[1][..])]).unwrap();
+    buffer.try_map_direct().unwrap().unwrap().write(1000, &rows[2][..]).unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits);
+
+    let whole = buffer.read::<u8>().unwrap();
+    assert_eq!(whole[100..400], rows[0][..]);
+    assert_eq!(whole[400..700], rows[1][..]);
+    assert_eq!(whole[1000..1300], rows[2][..]);
+    assert_eq!(whole[3001..3301], rows[2][..]);
+    assert!(whole[..100].iter().chain(&whole[700..1000]).chain(&whole[1300..3001]).chain(&whole[3301..]).all(|&byte| byte == 0));
+}
+
+#[test]
+fn test_dispatch_hashes_find_a_nondeterministic_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let bytes = |values: [u32; 4]| values.iter().flat_map(|value| value.to_ne_bytes()).collect::<Vec<u8>>();
+
+    // The first dispatch's output depends on what the "kernel" writes
+    let run = |written: u32| {
+        ctx.enable_dispatch_hashing();
+        let x = ctx.create_buffer(&[1u32, 2, 3, 4]).unwrap();
+        let y = ctx.create_buffer(&[0u32; 4]).unwrap();
+        mock.write_on_next_dispatch(y.raw(), 0, &written.to_ne_bytes());
+        ctx.dispatch(&pipeline).bind_buffer(1, &y).bind_buffer(0, &x).execute().unwrap();
+        ctx.dispatch(&pipeline).bind_buffer(0, &y).bind_buffer(1, &x).submit().unwrap();
+        assert_eq!(y.content_hash().unwrap(), hash_bytes(&bytes([written, 0, 0, 0])));
+        ctx.dispatch_hashes()
+    };
+    let reference = run(7);
+    assert_eq!(reference.len(), 2);
+    assert_eq!(reference[0].before, vec![(0, hash_bytes(&bytes([1, 2, 3, 4]))), (1, hash_bytes(&[0; 16]))]);
+    assert_eq!(reference[0].after[1], (1, hash_bytes(&bytes([7, 0, 0, 0]))));
+    assert_eq!(reference[1].before[0].1, reference[0].after[1].1);
+    assert_eq!(first_divergence(&reference, &run(7)), None);
+
+    let label = reference[0].label.clone();
+    assert_eq!(first_divergence(&reference, &run(8)), Some(HashDivergence::Output { index: 0, label, binding: 1 }));
+    let json = serde_json::to_string(&reference).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<DispatchHashes>>(&json).unwrap(), reference);
+
+    ctx.disable_dispatch_hashing();
+    let x = ctx.create_buffer(&[0u32; 4]).unwrap();
+    ctx.dispatch(&pipeline).bind_buffer(0, &x).execute().unwrap();
+    assert_eq!(ctx.dispatch_hashes().len(), 2);
+}
+
+#[test]
+fn test_icd_entry_resolves_entry_points_kronos_does_not_wrap() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+
+    let waits = mock.call_count("vkDeviceWaitIdle");
+    let wait_idle = unsafe { ctx.icd_entry::<PFN_vkDeviceWaitIdle>("vkDeviceWaitIdle") }.unwrap().unwrap();
+    assert_eq!(VkResult::from_raw(unsafe { wait_idle(ctx.device()) }), VkResult::Success);
+    let bare = unsafe { ctx.icd_entry::<unsafe extern "C" fn(VkDevice) -> VkResult>("vkDeviceWaitIdle") }.unwrap();
+    assert_eq!(unsafe { bare(ctx.device()) }, VkResult::Success);
+    assert_eq!(mock.call_count("vkDeviceWaitIdle"), waits + 2);
+
+    // Instance-level entry points resolve too, straight from the driver
+    let properties_of = unsafe { ctx.icd_entry::<PFN_vkGetPhysicalDeviceProperties>("vkGetPhysicalDeviceProperties") }.unwrap().unwrap();
+    let mut direct: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
+    let mut wrapped: VkPhysicalDeviceProperties = unsafe { std::mem::zeroed() };
+    unsafe {
+        properties_of(ctx.physical_device(), &mut direct);
+        vkGetPhysicalDeviceProperties(ctx.physical_device(), &mut wrapped);
+    }
+    assert_eq!((direct.vendorID, direct.deviceID), (wrapped.vendorID, wrapped.deviceID));
+
+    for name in ["vkCmdNotAThing", "vkDeviceWaitIdle\0"] {
+        let missing = unsafe { ctx.icd_entry::<PFN_vkDeviceWaitIdle>(name) };
+        assert!(matches!(missing, Err(KronosError::UnsupportedHardware(_))), "{:?}", name);
+    }
+}
+
+#[test]
+fn test_descriptor_pools_grow_with_the_types_pipelines_bind() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::builder().descriptor_pool_sets(2).build().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let config = PipelineConfig {
+        bindings: (0..2).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        ..Default::default()
+    };
+    let pipeline = ctx.create_pipeline_with_config(&shader, config).unwrap();
+    let buffer = ctx.create_buffer(&[0u32; 4]).unwrap();
+    assert_eq!(ctx.descriptor_pool_usage().pools, 0);
+
+    // 2 sets, then 2 more, then 4 more
+    let mut sets: Vec<_> = (0..5).map(|_| pipeline.bind_all(&[&buffer, &buffer]).unwrap()).collect();
+    let usage = ctx.descriptor_pool_usage();
+    assert_eq!((usage.pools, usage.max_sets, usage.sets_in_use), (3, 8, 5));
+    assert_eq!(mock.call_count("vkCreateDescriptorPool"), 3);
+    let storage = |usage: &DescriptorPoolUsage| usage.types.iter().map(|t| (t.descriptor_type, t.capacity, t.in_use, t.high_water)).collect::<Vec<_>>();
+    assert_eq!(storage(&usage), vec![(VkDescriptorType::StorageBuffer, 16, 10, 10)]);
+
+    // Freed sets make room again; dispatches take and return one each
+    sets.truncate(1);
+    ctx.dispatch(&pipeline).bind_buffer(0, &buffer).bind_buffer(1, &buffer).execute().unwrap();
+    let usage = ctx.descriptor_pool_usage();
+    assert_eq!((usage.pools, usage.sets_in_use, usage.sets_high_water), (3, 1, 5));
+    assert_eq!(storage(&usage), vec![(VkDescriptorType::StorageBuffer, 16, 2, 10)]);
+
+    ctx.reserve_descriptor_sets(&[(&pipeline, 100)]).unwrap();
+    let usage = ctx.descriptor_pool_usage();
+    assert_eq!((usage.pools, usage.max_sets), (4, 108));
+    assert_eq!(storage(&usage), vec![(VkDescriptorType::StorageBuffer, 216, 2, 10)]);
+    drop((sets, pipeline, shader, buffer, ctx));
+    assert_eq!(mock.call_count("vkDestroyDescriptorPool"), mock.call_count("vkCreateDescriptorPool"));
+}
+
+#[test]
+fn test_prewarm_leaves_uploads_nothing_to_allocate() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::builder().staging_capacity(64 << 10).build().unwrap();
+    let mut buffer = ctx.create_buffer(&[0u8; 32 << 10]).unwrap();
+    assert!(!buffer.is_host_visible());
+    let allocations = || mock.call_count("vkAllocateMemory") + mock.call_count("vkCreateBuffer");
+
+    let before = allocations();
+    ctx.prewarm().unwrap();
+    // The ring and the first slab of each of the three pools
+    assert_eq!(allocations(), before + 4);
+    ctx.prewarm().unwrap();
+    assert_eq!(allocations(), before + 4);
+
+    let warm = allocations();
+    buffer.write(&[7u8; 16 << 10]).unwrap();
+    assert_eq!(allocations(), warm);
+    // Over a quarter of the ring, so it gets a staging buffer of its own
+    buffer.write(&[9u8; 32 << 10]).unwrap();
+    assert_eq!(allocations(), warm + 2);
+    assert!(buffer.read::<u8>().unwrap().iter().all(|&byte| byte == 9));
+
+    let ctx = ComputeContext::builder().staging_capacity(0).build().unwrap();
+    assert!(matches!(ctx.prewarm(), Err(KronosError::BufferCreationFailed(_))));
+}
+
+#[test]
+fn test_submit_plan_moves_buffers_between_queue_families() {
+    let family = |flags, count| VkQueueFamilyProperties {
+        queueFlags: flags,
+        queueCount: count,
+        timestampValidBits: 0,
+        minImageTransferGranularity: VkExtent3D::default(),
+    };
+    let (_guard, mock) = install(MockConfig {
+        queue_families: vec![family(VkQueueFlags::COMPUTE | VkQueueFlags::TRANSFER, 1), family(VkQueueFlags::TRANSFER, 1)],
+        ..MockConfig::default()
+    });
+    refresh_devices();
+    let ctx = ComputeContext::new().unwrap();
+    let compute = ctx.create_queue(0, 0).unwrap();
+    let transfer = ctx.create_queue(1, 0).unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+
+    let mut input = ctx.create_buffer_uninit(16).unwrap();
+    input.write(&[1u32, 2, 3, 4]).unwrap();
+    let data = ctx.create_buffer(&[0u32; 4]).unwrap();
+    let output = ctx.create_buffer_uninit(16).unwrap();
+    mock.write_on_next_dispatch(data.raw(), 4, &9u32.to_ne_bytes());
+
+    let submits = mock.call_count("vkQueueSubmit");
+    let plan = SubmitPlan::new()
+        .copy(&transfer, &input, &data)
+        .dispatch(&compute, ctx.dispatch(&pipeline).bind_buffer(0, &data).bind_buffer(1, &data).bind_buffer(2, &data))
+        .copy(&transfer, &data, &output);
+    assert_eq!(plan.len(), 3);
+    ctx.submit_plan(plan).unwrap();
+    // Releases on the context's queue, upload, dispatch, readback, and acquires back
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits + 5);
+    assert_eq!(output.read::<u32>().unwrap(), vec![1, 9, 3, 4]);
+    let moves = |buffer: &Buffer| -> Vec<(u32, u32)> {
+        mock.ownership_transfers().iter().filter(|(moved, _, _)| *moved == buffer.raw()).map(|&(_, src, dst)| (src, dst)).collect()
+    };
+    assert_eq!(moves(&data), vec![(0, 1), (0, 1), (1, 0), (1, 0), (0, 1), (0, 1), (1, 0), (1, 0)]);
+    assert_eq!(moves(&output), vec![(0, 1), (0, 1), (1, 0), (1, 0)]);
+
+    let err = ctx.submit_plan(SubmitPlan::new().dispatch(&transfer, ctx.dispatch(&pipeline))).unwrap_err();
+    assert!(matches!(err, KronosError::UnsupportedHardware(_)), "{}", err);
+
+    // A batch failing to submit waits for the ones submitted before it
+    let idle = mock.call_count("vkDeviceWaitIdle");
+    mock.fail_nth("vkQueueSubmit", 2, VkResult::ErrorOutOfDeviceMemory);
+    let err = ctx.submit_plan(SubmitPlan::new().copy(&transfer, &input, &data)).unwrap_err();
+    assert!(matches!(err, KronosError::CommandExecutionFailed(_)), "{}", err);
+    assert_eq!(mock.call_count("vkDeviceWaitIdle"), idle + 1);
+    drop((compute, transfer, pipeline, shader));
+
+    // Queues of one family share a submission and need no transfers
+    let compute = ctx.create_queue(0, 0).unwrap();
+    let transfers = mock.ownership_transfers().len();
+    let submits = mock.call_count("vkQueueSubmit");
+    ctx.submit_plan(SubmitPlan::new().copy(&compute, &input, &data).copy(&compute, &data, &output)).unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits + 1);
+    assert_eq!(output.read::<u32>().unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(mock.ownership_transfers().len(), transfers);
+}
+
+#[test]
+fn test_buffer_priorities_reach_allocations() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    assert!(!ctx.supports_memory_priority());
+    ctx.create_buffer_with_options(&[1.0f32; 64], &BufferOptions::default().priority(MemoryPriority::High)).unwrap();
+    assert!(mock.memory_priorities().is_empty());
+    drop(ctx);
+
+    let mut config = MockConfig::default();
+    config.extensions.push(VK_EXT_MEMORY_PRIORITY_EXTENSION_NAME.to_string());
+    config.extensions.push(VK_EXT_PAGEABLE_DEVICE_LOCAL_MEMORY_EXTENSION_NAME.to_string());
+    let mock = MockIcd::install(config).unwrap();
+    let ctx = ComputeContext::new().unwrap();
+    assert!(ctx.supports_memory_priority());
+    let chain = mock.device_create_chain();
+    assert!(chain.contains(&VkStructureType::PhysicalDeviceMemoryPriorityFeaturesEXT));
+    assert!(chain.contains(&VkStructureType::PhysicalDevicePageableDeviceLocalMemoryFeaturesEXT));
+
+    let weights = ctx
+        .create_buffer_with_options(&[1.0f32; 64], &BufferOptions::default().priority(MemoryPriority::High).tag("weights"))
+        .unwrap();
+    assert_eq!(weights.tag(), "weights");
+    assert_eq!(weights.read::<f32>().unwrap(), vec![1.0; 64]);
+    ctx.create_buffer_uninit_with_options(256, &BufferOptions::default().priority(MemoryPriority::Low)).unwrap();
+    // Normal priority is the default, so nothing is chained
+    ctx.create_buffer_uninit(256).unwrap();
+    assert_eq!(mock.memory_priorities(), vec![1.0, 0.25]);
+}
+
+#[test]
+fn test_dispatch_after_another() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x);
+    let semaphores = mock.call_count("vkCreateSemaphore");
+
+    // A fans out to B and C, which both feed D
+    let a = dispatch();
+    let b = dispatch().after(&a).unwrap();
+    let c = dispatch().after(&a).unwrap();
+    let d = dispatch().after(&b).unwrap().after(&c).unwrap();
+    assert_eq!(mock.call_count("vkCreateSemaphore") - semaphores, 4);
+
+    // Waiting on a dispatch that was never submitted fails
+    assert!(matches!(d.execute(), Err(KronosError::SynchronizationError(_))));
+    a.submit().unwrap();
+    b.submit().unwrap();
+    c.execute().unwrap();
+
+    // The reaper releases the semaphores once their submissions complete
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.call_count("vkDestroySemaphore") < mock.call_count("vkCreateSemaphore") {
+        assert!(Instant::now() < deadline, "semaphores were not released");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    let other = ComputeContext::new().unwrap();
+    let other_pipeline = other.create_pipeline(&other.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap()).unwrap();
+    assert!(dispatch().after(&other.dispatch(&other_pipeline)).is_err());
+}
+
+#[test]
+fn test_buffers_dropped_in_flight_outlive_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let destroyed = mock.call_count("vkDestroyBuffer");
+
+    // Unsubmitted builders also keep their buffers alive
+    mock.set_fence_delay(Duration::from_millis(200));
+    let builder = ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x);
+    drop((x, y));
+    assert_eq!(mock.call_count("vkDestroyBuffer"), destroyed);
+    builder.submit().unwrap();
+    drop(out);
+    assert_eq!(mock.call_count("vkDestroyBuffer"), destroyed);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.call_count("vkDestroyBuffer") < destroyed + 3 {
+        assert!(Instant::now() < deadline, "buffers were not destroyed after the dispatch");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    // A buffer no dispatch uses is destroyed immediately
+    drop(ctx.create_buffer_uninit(1024).unwrap());
+    assert_eq!(mock.call_count("vkDestroyBuffer"), destroyed + 4);
+}
+
+#[test]
+fn test_buffers_bound_through_bind_all_outlive_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let config = PipelineConfig {
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        ..Default::default()
+    };
+    let pipeline = ctx.create_pipeline_with_config(&shader, config).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let set = pipeline.bind_all(&[&x, &y, &out]).unwrap();
+    let destroyed = mock.call_count("vkDestroyBuffer");
+
+    mock.set_fence_delay(Duration::from_millis(200));
+    ctx.dispatch(&pipeline).descriptor_set(&set).workgroups(1, 1, 1).submit().unwrap();
+    drop((x, y, out, set));
+    assert_eq!(mock.call_count("vkDestroyBuffer"), destroyed);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.call_count("vkDestroyBuffer") < destroyed + 3 {
+        assert!(Instant::now() < deadline, "buffers were not destroyed after the dispatch");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_readback_channel_keeps_previous_results() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let mut out = ctx.create_buffer_uninit(64 * 4).unwrap();
+    let channel = ctx.readback_channel::<f32>(4).unwrap();
+    assert_eq!(channel.len(), 4);
+    assert_eq!(channel.latest().unwrap(), None);
+
+    let iterate = |out: &Buffer| {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(2, out)
+            .bind_buffer(1, &y)
+            .bind_buffer(0, &x)
+            .read_back(out, &channel)
+            .unwrap()
+    };
+    out.write(&[0.5f32; 64]).unwrap();
+    iterate(&out).execute().unwrap();
+    assert_eq!(channel.latest_iteration().unwrap(), Some((1, vec![0.5; 4])));
+    assert!(mock.call_count("vkInvalidateMappedMemoryRanges") > 0);
+
+    // The previous results stay readable while the next iteration runs
+    out.write(&[0.25f32; 64]).unwrap();
+    mock.set_fence_delay(Duration::from_millis(200));
+    iterate(&out).submit().unwrap();
+    assert_eq!(channel.latest().unwrap(), Some(vec![0.5; 4]));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while channel.latest().unwrap() != Some(vec![0.25; 4]) {
+        assert!(Instant::now() < deadline, "readback did not complete");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(channel.latest_iteration().unwrap().unwrap().0, 2);
+
+    let small = ctx.create_buffer_uninit(8).unwrap();
+    assert!(ctx.dispatch(&pipeline).read_back(&small, &channel).is_err());
+}
+
+#[test]
+fn test_ping_pong_swaps_and_waits_for_previous_iteration() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let coefficients = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let mut buffers = PingPong::new(&ctx, 64 * 4).unwrap();
+    buffers.current_mut().write(&[3.0f32; 64]).unwrap();
+    let (first, second) = (buffers.current().raw(), buffers.next().raw());
+
+    ctx.dry_run(true);
+    for _ in 0..3 {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(2, &coefficients)
+            .bind_ping_pong(0, 1, &mut buffers)
+            .execute()
+            .unwrap();
+    }
+    ctx.dry_run(false);
+    assert_eq!(buffers.iterations(), 3);
+    assert_eq!(buffers.current().raw(), second);
+    assert_eq!(buffers.next().raw(), first);
+
+    let listing = ctx.take_command_listing();
+    let hazards: Vec<usize> = listing
+        .dispatches
+        .iter()
+        .map(|dispatch| {
+            dispatch
+                .commands
+                .iter()
+                .filter(|command| matches!(
+                    command,
+                    PlannedCommand::PipelineBarrier { src_stage, buffers, .. }
+                        if *src_stage == VkPipelineStageFlags::COMPUTE_SHADER && buffers.len() == 2
+                ))
+                .count()
+        })
+        .collect();
+    // The first iteration has nothing to wait for
+    assert_eq!(hazards, vec![0, 1, 1]);
+}
+
+#[test]
+fn test_device_asserts_surface_kernel_failures() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let asserts = ctx.device_asserts().unwrap();
+    asserts.register(7, "index out of range");
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(1, &y).bind_buffer(0, &x).check_asserts(2, &asserts);
+
+    dispatch().execute().unwrap();
+
+    let flags = asserts.buffer().raw();
+    mock.write_on_next_dispatch(flags, 0, &[7, 0, 0, 0, 42, 0, 0, 0]);
+    let err = dispatch().execute().unwrap_err().to_string();
+    assert!(err.contains("index out of range (code 7, value 42)"), "{}", err);
+    // Reading the flags cleared them
+    dispatch().execute().unwrap();
+
+    mock.write_on_next_dispatch(flags, 0, &[9, 0, 0, 0, 1, 0, 0, 0]);
+    dispatch().submit().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let err = loop {
+        if let Err(err) = asserts.check() {
+            break err.to_string();
+        }
+        assert!(Instant::now() < deadline, "assertion of the submitted dispatch was not reported");
+        std::thread::sleep(Duration::from_millis(1));
+    };
+    assert!(err.contains("unregistered code 9"), "{}", err);
+    asserts.check().unwrap();
+}
+
+#[test]
+fn test_split_long_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+    let dispatch = || ctx.dispatch(&pipeline).bind_buffer(2, &out).bind_buffer(1, &y).bind_buffer(0, &x).workgroups(40, 1, 1);
+
+    // The first chunk is sized from the estimate alone
+    let split = SplitDispatch {
+        max_dispatch_duration: Some(Duration::from_millis(1)),
+        workgroup_estimate: Duration::from_micros(100),
+        ..SplitDispatch::new(3)
+    };
+    let submits = mock.call_count("vkQueueSubmit");
+    let submissions = dispatch().execute_split(&split).unwrap();
+    assert!(submissions >= 2);
+    assert!(mock.call_count("vkQueueSubmit") - submits >= submissions as u64);
+
+    let unlimited = SplitDispatch { max_dispatch_duration: Some(Duration::from_secs(60)), ..split };
+    assert_eq!(dispatch().execute_split(&unlimited).unwrap(), 1);
+    assert!(dispatch().then(&pipeline).execute_split(&split).is_err());
+}
+
+#[test]
+fn test_scheduler_priorities_and_cancellation() {
+    use kronos_compute::api::{FlightLimiter, Job, JobStatus, Scheduler};
+    use std::sync::Arc;
+
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = Arc::new(ctx.create_pipeline(&shader).unwrap());
+    let x = Arc::new(ctx.create_buffer(&[1.0f32; 64]).unwrap());
+    let y = Arc::new(ctx.create_buffer(&[2.0f32; 64]).unwrap());
+    let out = Arc::new(ctx.create_buffer(&[0.0f32; 64]).unwrap());
+    let job = |priority| {
+        Job::new(pipeline.clone())
+            .bind_buffer(2, out.clone())
+            .bind_buffer(1, y.clone())
+            .bind_buffer(0, x.clone())
+            .push_constants(&2.0f32)
+            .priority(priority)
+    };
+
+    // Hold the only flight slot so every job queues up
+    let limiter = FlightLimiter::new(1);
+    let scheduler = Scheduler::new(limiter.clone());
+    scheduler.add_context(&ctx);
+    let permit = limiter.acquire();
+    let low = scheduler.submit(job(0));
+    let cancelled = scheduler.submit(job(5));
+    let high = scheduler.submit(job(10));
+    assert_eq!(scheduler.queued(), 3);
+    assert!(cancelled.cancel());
+    assert_eq!(scheduler.queued(), 2);
+
+    mock.set_fence_delay(Duration::from_millis(100));
+    drop(permit);
+    high.wait().unwrap();
+    assert_ne!(low.status(), JobStatus::Completed);
+    low.wait().unwrap();
+    assert!(!high.cancel());
+    assert!(matches!(cancelled.wait(), Err(KronosError::CommandExecutionFailed(_))));
+    assert_eq!(cancelled.status(), JobStatus::Cancelled);
+
+    // Jobs need a lane on their pipeline's device
+    let other = ComputeContext::new().unwrap();
+    let foreign = Arc::new(other.create_pipeline(&other.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap()).unwrap());
+    let orphan = scheduler.submit(Job::new(foreign));
+    assert!(orphan.wait().is_err());
+    assert!(matches!(orphan.status(), JobStatus::Failed(_)));
+}
+
+#[test]
+fn test_scheduler_timeouts_and_abandoned_jobs() {
+    use kronos_compute::api::{FlightLimiter, Job, JobStatus, Scheduler};
+    use std::sync::Arc;
+
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = Arc::new(ctx.create_pipeline(&shader).unwrap());
+    let x = Arc::new(ctx.create_buffer(&[1.0f32; 64]).unwrap());
+    let y = Arc::new(ctx.create_buffer(&[2.0f32; 64]).unwrap());
+    let out = Arc::new(ctx.create_buffer(&[0.0f32; 64]).unwrap());
+    let job = || Job::new(pipeline.clone()).bind_buffer(2, out.clone()).bind_buffer(1, y.clone()).bind_buffer(0, x.clone());
+    let limiter = FlightLimiter::new(1);
+    let scheduler = Scheduler::new(limiter.clone());
+    scheduler.add_context(&ctx);
+
+    // A job that times out in the queue is never submitted
+    let permit = limiter.acquire();
+    let expired = scheduler.submit(job().timeout(Duration::from_millis(20)));
+    assert!(matches!(expired.wait(), Err(KronosError::SynchronizationError(_))));
+    assert_eq!(expired.status(), JobStatus::TimedOut);
+    let submits = mock.call_count("vkQueueSubmit");
+    drop(permit);
+    scheduler.submit(job()).wait().unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit") - submits, 1);
+
+    // A submitted job can be abandoned; its completion is then ignored
+    mock.set_fence_delay(Duration::from_millis(100));
+    let abandoned = scheduler.submit(job());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while abandoned.status() == JobStatus::Queued {
+        assert!(Instant::now() < deadline, "job was never submitted");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(abandoned.cancel());
+    assert!(abandoned.wait().is_err());
+    let slow = scheduler.submit(job().timeout(Duration::from_millis(50)));
+    assert!(slow.wait().is_err());
+    assert_eq!(slow.status(), JobStatus::TimedOut);
+    while ctx.in_flight_submissions() > 0 || limiter.in_flight() > 0 {
+        assert!(Instant::now() < deadline, "abandoned jobs did not finish");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(abandoned.status(), JobStatus::Cancelled);
+    assert!(!abandoned.status().is_pending());
+}
+
+#[test]
+fn test_checkpointed_jobs_resume_from_disk() {
+    use kronos_compute::api::{FlightLimiter, Job, JobStatus, Scheduler};
+    use std::sync::Arc;
+
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = Arc::new(ctx.create_pipeline(&shader).unwrap());
+    let state_buffer = |values: &[u32]| {
+        let mut buffer = ctx.create_buffer_uninit(16).unwrap();
+        buffer.write(values).unwrap();
+        Arc::new(buffer)
+    };
+    let path = std::env::temp_dir().join(format!("kronos-checkpoint-{}", std::process::id())).join("job.ckpt");
+    let job = |state| Job::new(pipeline.clone()).bind_state_buffer(0, state).passes(10).checkpoint(&path, 2);
+    let scheduler = Scheduler::new(FlightLimiter::new(4));
+    scheduler.add_context(&ctx);
+
+    // Abandon a slow job once it has written a checkpoint
+    mock.set_fence_delay(Duration::from_millis(20));
+    let interrupted = scheduler.submit(job(state_buffer(&[1, 2, 3, 4])));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !path.exists() {
+        assert!(Instant::now() < deadline, "no checkpoint was written");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(interrupted.cancel());
+    while ctx.in_flight_submissions() > 0 {
+        assert!(Instant::now() < deadline, "abandoned pass did not finish");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(interrupted.status(), JobStatus::Cancelled);
+    mock.set_fence_delay(Duration::ZERO);
+
+    // A rebuilt job picks up the saved state and the remaining passes
+    let state = state_buffer(&[0; 4]);
+    let resumed = job(state.clone()).resume(&path).unwrap();
+    let done = resumed.completed_passes();
+    assert!(done >= 2 && done < 10 && done % 2 == 0, "resumed at pass {}", done);
+    assert_eq!(state.read::<u32>().unwrap(), [1, 2, 3, 4]);
+    assert!(job(state_buffer(&[0; 4])).passes(1).resume(&path).is_err());
+    assert!(Job::new(pipeline.clone()).passes(10).resume(&path).is_err());
+
+    let dispatches = mock.call_count("vkCmdDispatch");
+    scheduler.submit(resumed).wait().unwrap();
+    assert_eq!(mock.call_count("vkCmdDispatch") - dispatches, 10 - done);
+    assert!(!path.exists(), "completed job left its checkpoint");
+    let _ = std::fs::remove_dir(path.parent().unwrap());
+}
+
+#[test]
+fn test_builder_api_version() {
+    let (_guard, _mock) = install(MockConfig { api_version: VK_API_VERSION_1_1, ..MockConfig::default() });
+
+    let result = ComputeContext::builder().api_version(Version::V1_3).build();
+    assert!(matches!(result, Err(KronosError::UnsupportedHardware(_))));
+
+    let ctx = ComputeContext::builder()
+        .api_version(Version::V1_1)
+        .engine_name("Test Engine")
+        .application_version(Version::new(2, 0, 1))
+        .build()
+        .unwrap();
+    assert_eq!(ctx.api_version(), VK_API_VERSION_1_1);
+}
+
+#[test]
+#[cfg(feature = "object-registry")]
+fn test_describe_handle() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let buffer = ctx.create_buffer(&[0u32; 16]).unwrap();
+    let raw = buffer.raw().as_raw();
+
+    let description = ctx.describe_handle(raw).unwrap();
+    assert!(description.starts_with("VkBuffer(#"), "{}", description);
+    assert!(description.ends_with(&format!("{})", ctx.device_id())), "{}", description);
+    assert_eq!(format!("{:?}", buffer.raw()), description);
+
+    drop(buffer);
+    assert_eq!(ctx.describe_handle(raw), None);
+}
+
+#[test]
+#[cfg(all(feature = "icd-profiling", not(feature = "minimal")))]
+fn test_icd_latency_is_recorded() {
+    let (_guard, _mock) = install(MockConfig::default());
+    kronos_compute::metrics::reset_icd_latency();
+    let ctx = ComputeContext::new().unwrap();
+    let _buffer = ctx.create_buffer(&[0u32; 16]).unwrap();
+
+    let latency = kronos_compute::metrics::icd_latency();
+    let create_buffer = latency.iter().find(|entry| entry.entry_point == "vkCreateBuffer").unwrap();
+    assert!(create_buffer.count >= 1);
+    assert!(create_buffer.p99 <= create_buffer.max && create_buffer.mean <= create_buffer.max);
+    assert!(latency.windows(2).all(|pair| pair[0].total >= pair[1].total));
+}
+
+#[test]
+fn test_wait_on_multiple_fences() {
+    use kronos_compute::api::sync::{wait_all, wait_any};
+    use kronos_compute::api::FenceStatus;
+
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let pending = ctx.create_fence(false).unwrap();
+    let done = ctx.create_fence(true).unwrap();
+
+    assert_eq!(pending.status().unwrap(), FenceStatus::Unsignaled);
+    assert_eq!(done.status().unwrap(), FenceStatus::Signaled);
+    assert_eq!(wait_any(&[&pending, &done], 0).unwrap(), 1);
+    assert!(matches!(wait_all(&[&pending, &done], 1_000_000), Err(KronosError::SynchronizationError(_))));
+    assert!(wait_any(&[], 0).is_err());
+    wait_all(&[], 0).unwrap();
+
+    // Fences of another context cannot share a wait
+    let other = ComputeContext::new().unwrap();
+    let foreign = other.create_fence(true).unwrap();
+    assert!(wait_all(&[&done, &foreign], 0).is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_import_hip_buffer_through_external_memory() {
+    use kronos_compute::api::ExternalHandleType;
+
+    let mut config = MockConfig::default();
+    config.extensions.push(VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME.to_string());
+    config.extensions.push(VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME.to_string());
+    let (_guard, mock) = install(config);
+
+    // Without the opt-in, nothing can be imported
+    let plain = ComputeContext::new().unwrap();
+    assert!(!plain.supports_external_memory(ExternalHandleType::DmaBuf));
+    assert!(matches!(
+        unsafe { plain.import_hip_buffer(7, 4096, 4096, ExternalHandleType::DmaBuf) },
+        Err(KronosError::UnsupportedHardware(_))
+    ));
+    drop(plain);
+
+    let ctx = ComputeContext::builder().enable_external_memory().build().unwrap();
+    assert!(ctx.supports_external_memory(ExternalHandleType::OpaqueFd));
+    let buffer = unsafe { ctx.import_hip_buffer(7, 4096, 4096, ExternalHandleType::DmaBuf) }.unwrap();
+    assert_eq!(buffer.size(), 4096);
+    assert_eq!(buffer.tag(), "external");
+    assert!(!buffer.is_host_visible());
+    assert_eq!(mock.imported_fds(), vec![7]);
+    assert_eq!(mock.call_count("vkGetMemoryFdPropertiesKHR"), 1);
+
+    // Opaque fds import into whatever type they came from, without a query,
+    // and the memory is as large as the exported allocation, not the buffer
+    let before = ctx.stats_snapshot();
+    let opaque = unsafe { ctx.import_hip_buffer(8, 256, 65536, ExternalHandleType::OpaqueFd) }.unwrap();
+    assert_eq!(mock.imported_fds(), vec![7, 8]);
+    assert_eq!(mock.call_count("vkGetMemoryFdPropertiesKHR"), 1);
+    assert_eq!(ctx.stats_snapshot().diff(&before).allocated_bytes, 65536);
+    assert_eq!(opaque.size(), 256);
+    drop(opaque);
+
+    // A rejected fd stays with the caller and leaves nothing behind
+    let live = mock.live_objects();
+    assert!(unsafe { ctx.import_hip_buffer(-1, 256, 256, ExternalHandleType::DmaBuf) }.is_err());
+    assert_eq!(mock.live_objects(), live);
+
+    // So does an allocation too small for the buffer
+    assert!(matches!(
+        unsafe { ctx.import_hip_buffer(9, 4096, 256, ExternalHandleType::DmaBuf) },
+        Err(KronosError::BufferCreationFailed(_))
+    ));
+    assert_eq!(mock.live_objects(), live);
+    assert_eq!(mock.imported_fds(), vec![7, 8]);
+
+    let barriers = mock.call_count("vkCmdPipelineBarrier");
+    buffer.acquire_from_external().unwrap();
+    buffer.release_to_external().unwrap();
+    assert_eq!(mock.call_count("vkCmdPipelineBarrier"), barriers + 2);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cuda_interop_exports_memory_and_semaphores() {
+    let mut config = MockConfig::default();
+    config.extensions.push(VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME.to_string());
+    config.extensions.push(VK_KHR_EXTERNAL_SEMAPHORE_FD_EXTENSION_NAME.to_string());
+    let (_guard, mock) = install(config);
+
+    // Without the opt-in, nothing can be shared
+    let plain = ComputeContext::new().unwrap();
+    assert!(!plain.supports_external_semaphores());
+    assert!(matches!(plain.create_exportable_buffer(64), Err(KronosError::UnsupportedHardware(_))));
+    assert!(matches!(plain.create_exportable_semaphore(), Err(KronosError::UnsupportedHardware(_))));
+    drop(plain);
+
+    let ctx = ComputeContext::builder().enable_external_memory().build().unwrap();
+    assert!(ctx.supports_external_semaphores());
+
+    let shared = ctx.create_exportable_buffer(1000).unwrap();
+    let exported = shared.export_fd().unwrap();
+    // The importer needs the size of the whole allocation
+    assert!(exported.size >= 1000);
+    assert_ne!(shared.export_fd().unwrap().fd, exported.fd);
+    let plain = ctx.create_buffer(&[0u32; 16]).unwrap();
+    assert!(plain.export_fd().is_err());
+
+    let imported = unsafe { ctx.import_cuda_buffer(9, 4096) }.unwrap();
+    assert_eq!(mock.imported_fds(), vec![9]);
+    assert!(imported.export_fd().is_err());
+
+    let ready = ctx.create_exportable_semaphore().unwrap();
+    let done = ctx.create_exportable_semaphore().unwrap();
+    assert!(ready.export_fd().unwrap() >= 0);
+
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let dispatch = || {
+        ctx.dispatch(&pipeline)
+            .bind_buffer(1, &shared)
+            .bind_buffer(0, &x)
+            .acquire_external(&shared)
+            .release_external(&shared)
+    };
+
+    ctx.dry_run(true);
+    dispatch().execute().unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let transfers: Vec<VkPipelineStageFlags> = listing.dispatches[0]
+        .commands
+        .iter()
+        .filter_map(|command| match command {
+            PlannedCommand::PipelineBarrier { dst_stage, buffers, .. } if buffers == &[(shared.raw(), VK_WHOLE_SIZE)] => {
+                Some(*dst_stage)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(transfers, vec![VkPipelineStageFlags::COMPUTE_SHADER, VkPipelineStageFlags::BOTTOM_OF_PIPE]);
+
+    let submits = mock.call_count("vkQueueSubmit");
+    dispatch().wait_external(&ready).signal_external(&done).execute().unwrap();
+    assert_eq!(mock.call_count("vkQueueSubmit"), submits + 1);
+}
+
+// Counters of the test plugin below, and the driver functions it forwards to
+static PLUGIN_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static PLUGIN_FREES: AtomicUsize = AtomicUsize::new(0);
+static PLUGIN_SUBMITS: AtomicUsize = AtomicUsize::new(0);
+static PLUGIN_DESTROYS: AtomicUsize = AtomicUsize::new(0);
+static PLUGIN_HOST: Mutex<Option<(PFN_vkAllocateMemory, PFN_vkFreeMemory, PFN_vkQueueSubmit)>> = Mutex::new(None);
+
+unsafe extern "C" fn plugin_allocate(
+    _user_data: *mut c_void,
+    device: VkDevice,
+    allocate_info: *const VkMemoryAllocateInfo,
+    memory: *mut VkDeviceMemory,
+) -> VkResult {
+    PLUGIN_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+    let allocate = PLUGIN_HOST.lock().unwrap().unwrap().0.unwrap();
+    VkResult::from_raw(allocate(device, allocate_info, ptr::null(), memory))
+}
+
+unsafe extern "C" fn plugin_free(_user_data: *mut c_void, device: VkDevice, memory: VkDeviceMemory) {
+    PLUGIN_FREES.fetch_add(1, Ordering::SeqCst);
+    let free = PLUGIN_HOST.lock().unwrap().unwrap().1.unwrap();
+    free(device, memory, ptr::null())
+}
+
+unsafe extern "C" fn plugin_submit(
+    _user_data: *mut c_void,
+    queue: VkQueue,
+    submit_count: u32,
+    submits: *const VkSubmitInfo,
+    fence: VkFence,
+) -> VkResult {
+    PLUGIN_SUBMITS.fetch_add(1, Ordering::SeqCst);
+    let submit = PLUGIN_HOST.lock().unwrap().unwrap().2.unwrap();
+    VkResult::from_raw(submit(queue, submit_count, submits, fence))
+}
+
+unsafe extern "C" fn plugin_destroy(_user_data: *mut c_void) {
+    PLUGIN_DESTROYS.fetch_add(1, Ordering::SeqCst);
+}
+
+static PLUGIN_ALLOCATOR: KronosAllocatorVtable = KronosAllocatorVtable { allocate: plugin_allocate, free: plugin_free };
+static PLUGIN_SCHEDULER: KronosSchedulerVtable = KronosSchedulerVtable { submit: plugin_submit };
+
+unsafe extern "C" fn plugin_init(host: *const KronosPluginHost, plugin: *mut KronosPlugin) -> VkResult {
+    let host = &*host;
+    assert_eq!(host.abi_version, KRONOS_PLUGIN_ABI_VERSION);
+    *PLUGIN_HOST.lock().unwrap() = Some((host.allocate_memory, host.free_memory, host.queue_submit));
+    (*plugin).abi_version = KRONOS_PLUGIN_ABI_VERSION;
+    (*plugin).allocator = &PLUGIN_ALLOCATOR;
+    (*plugin).scheduler = &PLUGIN_SCHEDULER;
+    (*plugin).destroy = Some(plugin_destroy);
+    VkResult::Success
+}
+
+unsafe extern "C" fn outdated_plugin_init(_host: *const KronosPluginHost, plugin: *mut KronosPlugin) -> VkResult {
+    (*plugin).abi_version = KRONOS_PLUGIN_ABI_VERSION + 1;
+    VkResult::Success
+}
+
+#[test]
+fn test_plugin_allocates_buffers_and_schedules_submissions() {
+    let (_guard, mock) = install(MockConfig::default());
+    assert!(matches!(
+        ComputeContext::builder().plugin_init(outdated_plugin_init).build(),
+        Err(KronosError::InitializationFailed(_))
+    ));
+
+    let ctx = ComputeContext::builder().plugin_init(plugin_init).build().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let driver_allocations = mock.call_count("vkAllocateMemory");
+    let allocations = PLUGIN_ALLOCATIONS.load(Ordering::SeqCst);
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    assert_eq!(PLUGIN_ALLOCATIONS.load(Ordering::SeqCst), allocations + 2);
+    // The plugin reaches the driver through the host functions
+    assert!(mock.call_count("vkAllocateMemory") >= driver_allocations + 2);
+
+    let submits = PLUGIN_SUBMITS.load(Ordering::SeqCst);
+    ctx.dispatch(&pipeline).bind_buffer(1, &y).bind_buffer(0, &x).workgroups(1, 1, 1).execute().unwrap();
+    assert_eq!(PLUGIN_SUBMITS.load(Ordering::SeqCst), submits + 1);
+
+    drop((x, y, pipeline, shader));
+    assert_eq!(PLUGIN_FREES.load(Ordering::SeqCst), PLUGIN_ALLOCATIONS.load(Ordering::SeqCst));
+    assert_eq!(PLUGIN_DESTROYS.load(Ordering::SeqCst), 0);
+    drop(ctx);
+    assert_eq!(PLUGIN_DESTROYS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_constants_are_pushed_when_they_fit_and_ring_allocated_otherwise() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+
+    let small = ctx.create_constants::<[u32; 4]>(3).unwrap();
+    assert!(small.is_pushed() && small.slots() == 0);
+    let pipeline = ctx.create_pipeline_with_constants(&shader, PipelineConfig::default(), &small).unwrap();
+    let pushes = mock.call_count("vkCmdPushConstants");
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, &x)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .constants(&small, &[64, 0, 0, 0])
+        .unwrap()
+        .execute()
+        .unwrap();
+    assert_eq!(mock.call_count("vkCmdPushConstants") - pushes, 1);
+
+    // 256 bytes exceed the 128 every device pushes, so the block moves to
+    // a dynamic uniform buffer at binding 3
+    let large = ctx.create_constants_with_slots::<[f32; 64]>(3, 2).unwrap();
+    assert!(!large.is_pushed() && large.slots() == 2);
+    let pipeline = ctx.create_pipeline_with_constants(&shader, PipelineConfig::default(), &large).unwrap();
+    ctx.dry_run(true);
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, &x)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .constants(&large, &[1.0; 64])
+        .unwrap()
+        .then(&pipeline)
+        .constants(&large, &[2.0; 64])
+        .unwrap()
+        .execute()
+        .unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let offsets: Vec<u32> = listing.dispatches[0]
+        .commands
+        .iter()
+        .filter_map(|command| match command {
+            PlannedCommand::BindDescriptorSet { bindings, .. } => bindings.iter().find_map(|(binding, resource)| match resource {
+                PlannedResource::Constants { offset, size, .. } if *binding == 3 && *size == 256 => Some(*offset),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(offsets, [0, 256]);
+    assert_eq!(mock.call_count("vkCmdPushConstants") - pushes, 1);
+
+    // A slot stays claimed while a builder holds it
+    let held = ctx.dispatch(&pipeline).constants(&large, &[0.0; 64]).unwrap();
+    let second = ctx.dispatch(&pipeline).constants(&large, &[0.0; 64]).unwrap();
+    match ctx.dispatch(&pipeline).constants(&large, &[0.0; 64]) {
+        Err(KronosError::CommandExecutionFailed(message)) => assert!(message.contains("in flight"), "{}", message),
+        other => panic!("expected the ring to be full, got {:?}", other.map(|_| ())),
+    }
+    drop(held);
+    drop(second);
+    ctx.dispatch(&pipeline)
+        .bind_buffer(0, &x)
+        .bind_buffer(1, &y)
+        .bind_buffer(2, &out)
+        .constants(&large, &[3.0; 64])
+        .unwrap()
+        .submit()
+        .unwrap();
+    ctx.dispatch(&pipeline).constants(&large, &[0.0; 64]).unwrap();
+}
+
+#[test]
+fn test_exported_features_query_writes_the_full_structure() {
+    let mut config = MockConfig::default();
+    config.features.shaderFloat64 = VK_TRUE;
+    let (_guard, _mock) = install(config);
+    let required = Features { shader_float64: true, ..Default::default() };
+    let ctx = ComputeContext::builder().require_features(required).build().unwrap();
+    assert!(ctx.enabled_features().shader_float64);
+
+    // C callers pass all 55 members and read them at their Vulkan offsets
+    let mut full = [0xAAAA_AAAAu32; VK_PHYSICAL_DEVICE_FEATURES_FULL_COUNT];
+    unsafe { vkGetPhysicalDeviceFeatures(ctx.physical_device(), full.as_mut_ptr() as *mut VkPhysicalDeviceFeatures) };
+    assert!(full.iter().all(|&feature| feature == VK_TRUE || feature == VK_FALSE));
+    assert_eq!(VkPhysicalDeviceFeatures::from_full(&full).shaderFloat64, VK_TRUE);
+    assert_eq!(full.iter().filter(|&&feature| feature == VK_TRUE).count(), 1);
+}
+
+#[test]
+fn test_bind_all_writes_one_set_reused_by_every_dispatch() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let config = PipelineConfig {
+        bindings: (0..3).map(|binding| BufferBinding { binding, ..Default::default() }).collect(),
+        ..Default::default()
+    };
+    let pipeline = ctx.create_pipeline_with_config(&shader, config).unwrap();
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let out = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+
+    let updates = mock.call_count("vkUpdateDescriptorSets");
+    let set = pipeline.bind_all(&[&x, &y, &out]).unwrap();
+    assert_eq!((set.len(), set.is_empty()), (3, false));
+    assert_eq!(mock.call_count("vkUpdateDescriptorSets") - updates, 1);
+
+    let binds = mock.call_count("vkCmdBindDescriptorSets");
+    for _ in 0..2 {
+        ctx.dispatch(&pipeline).descriptor_set(&set).workgroups(1, 1, 1).execute().unwrap();
+    }
+    assert_eq!(mock.call_count("vkCmdBindDescriptorSets") - binds, 2);
+    assert_eq!(mock.call_count("vkUpdateDescriptorSets") - updates, 1);
+
+    assert!(matches!(pipeline.bind_all(&[]), Err(KronosError::CommandExecutionFailed(_))));
+    let mixed = ctx.dispatch(&pipeline).descriptor_set(&set).bind_buffer(0, &x).execute();
+    assert!(matches!(mixed, Err(KronosError::CommandExecutionFailed(_))));
+}
+
+#[test]
+fn test_bind_all_follows_the_declared_bindings() {
+    let (_guard, mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let binding = |binding, descriptor_type| BufferBinding { binding, descriptor_type };
+    let pipeline = |bindings| {
+        ctx.create_pipeline_with_config(&shader, PipelineConfig { bindings, ..Default::default() }).unwrap()
+    };
+    let x = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let y = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+
+    // Each buffer goes to the next declared binding, as its declared type
+    let mixed = pipeline(vec![
+        binding(4, VkDescriptorType::UniformBufferDynamic),
+        binding(1, VkDescriptorType::StorageBuffer),
+    ]);
+    let set = mixed.bind_all(&[&x, &y]).unwrap();
+    assert_eq!(
+        mock.descriptor_writes(),
+        vec![(1, VkDescriptorType::StorageBuffer), (4, VkDescriptorType::UniformBufferDynamic)]
+    );
+    ctx.dispatch(&mixed).descriptor_set(&set).execute().unwrap();
+    assert_eq!(mock.dynamic_offsets(), vec![0]);
+
+    // The buffers must match the bindings one to one
+    for buffers in [&[&x][..], &[&x, &y, &x][..]] {
+        match mixed.bind_all(buffers) {
+            Err(KronosError::CommandExecutionFailed(message)) => assert!(message.contains("2 buffer bindings"), "{}", message),
+            other => panic!("{} buffers bound to 2 bindings: {:?}", buffers.len(), other.map(|set| set.len())),
+        }
+    }
+
+    // Image bindings cannot take a buffer
+    let images = pipeline(vec![binding(0, VkDescriptorType::StorageBuffer), binding(1, VkDescriptorType::StorageImage)]);
+    match images.bind_all(&[&x, &y]) {
+        Err(KronosError::CommandExecutionFailed(message)) => assert!(message.contains("Binding 1"), "{}", message),
+        other => panic!("buffer bound to an image binding: {:?}", other.map(|set| set.len())),
+    }
+}
+
+#[test]
+fn test_named_bindings_and_push_constants_follow_reflection() {
+    let (_guard, _mock) = install(MockConfig::default());
+    let ctx = ComputeContext::new().unwrap();
+    let shader = ctx.create_shader_from_spirv(include_bytes!("../shaders/saxpy.spv")).unwrap();
+    let pipeline = ctx.create_pipeline(&shader).unwrap();
+    let a = ctx.create_buffer(&[1.0f32; 64]).unwrap();
+    let b = ctx.create_buffer(&[2.0f32; 64]).unwrap();
+    let c = ctx.create_buffer(&[0.0f32; 64]).unwrap();
+
+    // `count` follows `alpha`; setting it alone zero-fills the 8-byte block
+    ctx.dry_run(true);
+    ctx.dispatch(&pipeline)
+        .bind_named("BufferA", &a)
+        .unwrap()
+        .bind_named("BufferB", &b)
+        .unwrap()
+        .bind_named("BufferC", &c)
+        .unwrap()
+        .push_named("count", 64u32)
+        .unwrap()
+        .then(&pipeline)
+        .push_named("count", 64u32)
+        .unwrap()
+        .push_named("alpha", 2.0f32)
+        .unwrap()
+        .execute()
+        .unwrap();
+    ctx.dry_run(false);
+    let listing = ctx.take_command_listing();
+    let commands = &listing.dispatches[0].commands;
+    let pushed: Vec<&Vec<u8>> = commands
+        .iter()
+        .filter_map(|command| match command {
+            PlannedCommand::PushConstants { data } => Some(data),
+            _ => None,
+        })
+        .collect();
+    let count = 64u32.to_le_bytes();
+    let alpha = 2.0f32.to_le_bytes();
+    assert_eq!(pushed, [&[[0; 4], count].concat(), &[alpha, count].concat()]);
+    let bound = commands.iter().find_map(|command| match command {
+        PlannedCommand::BindDescriptorSet { bindings, .. } => Some(bindings.iter().map(|(binding, _)| *binding).collect::<Vec<_>>()),
+        _ => None,
+    });
+    assert_eq!(bound, Some(vec![0, 1, 2]));
+
+    let errors = [
+        ctx.dispatch(&pipeline).bind_named("BufferD", &a).err(),
+        ctx.dispatch(&pipeline).push_named("beta", 1.0f32).err(),
+        ctx.dispatch(&pipeline).push_named("count", 64u64).err(),
+    ];
+    for error in errors {
+        assert!(matches!(error, Some(KronosError::CommandExecutionFailed(_))), "{:?}", error);
+    }
+}
@@ -0,0 +1,115 @@
+//! Host struct layouts checked against kernel blocks
+
+use kronos_compute::api::reflect::reflect_spirv;
+use kronos_compute::api::layout::check_std430;
+use kronos_compute::api::{BlockLayout, BlockMember, KernelInterface, LayoutMismatch, Std430};
+
+fn saxpy_interface() -> KernelInterface {
+    let words: Vec<u32> = include_bytes!("../shaders/saxpy.spv")
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    reflect_spirv(&words, "main").unwrap()
+}
+
+fn member(name: &str, offset: u32, size: u32) -> BlockMember {
+    BlockMember { name: Some(name.to_string()), offset, size, array_stride: None, element: None }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Std430)]
+struct SaxpyParams {
+    alpha: f32,
+    count: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Std430)]
+struct SwappedParams {
+    count: u32,
+    alpha: f32,
+}
+
+#[test]
+fn test_push_constant_layout() {
+    let interface = saxpy_interface();
+    interface.check_push_constant_layout::<SaxpyParams>().unwrap();
+    let err = interface.check_push_constant_layout::<SwappedParams>().unwrap_err();
+    assert!(err.to_string().contains("field `count` is at offset 0 on the host but 4"), "{}", err);
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Std430)]
+struct PackedParticle {
+    position: [f32; 3],
+    velocity: [f32; 3],
+    mass: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Std430)]
+struct Particle {
+    position: [f32; 3],
+    _pad: f32,
+    velocity: [f32; 3],
+    mass: f32,
+}
+
+/// `struct Particle { vec3 position; vec3 velocity; float mass; }` in a
+/// `buffer Particles { Particle particles[]; }` block
+fn particles_block() -> BlockLayout {
+    let particle = BlockLayout {
+        name: Some("Particle".into()),
+        size: 32,
+        members: vec![member("position", 0, 12), member("velocity", 16, 12), member("mass", 28, 4)],
+    };
+    BlockLayout {
+        name: Some("Particles".into()),
+        size: 0,
+        members: vec![BlockMember {
+            name: Some("particles".into()),
+            offset: 0,
+            size: 0,
+            array_stride: Some(32),
+            element: Some(Box::new(particle)),
+        }],
+    }
+}
+
+#[test]
+fn test_vec3_padding_is_caught() {
+    let block = particles_block();
+    let element = block.members[0].element.as_deref().unwrap();
+    assert!(check_std430::<Particle>(element).is_empty());
+    assert_eq!(
+        check_std430::<PackedParticle>(element),
+        vec![
+            LayoutMismatch::Offset { field: "velocity", host: 12, kernel: 16 },
+            LayoutMismatch::Offset { field: "mass", host: 24, kernel: 28 },
+        ]
+    );
+
+    let mut interface = saxpy_interface();
+    interface.bindings[0].layout = Some(block);
+    let name = interface.bindings[0].name.clone().unwrap();
+    interface.check_buffer_layout::<Particle>(&name).unwrap();
+    let err = interface.check_buffer_layout::<PackedParticle>(&name).unwrap_err().to_string();
+    assert!(err.contains("add 4 bytes of padding"), "{}", err);
+    assert!(err.contains("pad it to 32"), "{}", err);
+    assert!(interface.check_buffer_layout::<Particle>("missing").is_err());
+}
+
+#[test]
+fn test_reflected_buffer_layouts() {
+    let interface = saxpy_interface();
+    let binding = interface.binding("BufferB").unwrap();
+    let block = binding.layout.as_ref().unwrap();
+    assert_eq!(block.members.len(), 1);
+    assert_eq!((block.members[0].offset, block.members[0].size), (0, 0));
+    assert_eq!(block.members[0].array_stride, Some(4));
+    assert!(block.members[0].element.is_none());
+    assert_eq!(
+        Particle::host_fields().iter().map(|field| (field.name, field.offset)).collect::<Vec<_>>(),
+        vec![("position", 0), ("_pad", 12), ("velocity", 16), ("mass", 28)]
+    );
+}
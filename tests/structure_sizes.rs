@@ -33,6 +33,14 @@ fn test_structure_sizes() {
     // Compute structures
     assert_eq!(mem::size_of::<VkComputePipelineCreateInfo>(), 96);
     assert_eq!(mem::size_of::<VkPipelineShaderStageCreateInfo>(), 48);
+    
+    // Property queries, laid out as in the Vulkan headers
+    assert_eq!(mem::size_of::<VkPhysicalDeviceProperties2>(), 840);
+    assert_eq!(mem::size_of::<VkPhysicalDeviceDriverProperties>(), 536);
+    assert_eq!(mem::size_of::<VkPhysicalDeviceIDProperties>(), 64);
+    assert_eq!(mem::size_of::<VkPhysicalDevicePCIBusInfoPropertiesEXT>(), 32);
+    assert_eq!(mem::size_of::<VkPipelineCreationFeedback>(), 16);
+    assert_eq!(mem::size_of::<VkPipelineCreationFeedbackCreateInfo>(), 40);
 }
 
 #[test]
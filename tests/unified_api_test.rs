@@ -20,6 +20,7 @@ mod tests {
                 BufferBinding::default(),
             ],
             push_constant_size: 16,
+            ..Default::default()
         };
         
         // Make sure we can at least try to create a context